@@ -0,0 +1,273 @@
+//! `pterminal-cli doctor`: checks of what pterminal needs from the system,
+//! each problem with what to do about it. The output is meant to be pasted
+//! into a support issue as is.
+
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde_json::json;
+
+use pterminal_core::config::dirs::PORTABLE_ENV;
+use pterminal_core::shell_setup::{self, Shell};
+use pterminal_core::{font, Config};
+use pterminal_ipc::IpcClient;
+use pterminal_plugin_api::discover_plugin_catalog;
+use pterminal_render::text::system_monospace_families;
+
+/// Findings so far, printed as they come
+#[derive(Default)]
+struct Report {
+    problems: usize,
+}
+
+impl Report {
+    fn ok(&mut self, check: &str, detail: impl Display) {
+        println!("ok    {check}: {detail}");
+    }
+
+    /// Worth knowing, but pterminal works
+    fn warn(&mut self, check: &str, detail: impl Display, fix: impl Display) {
+        println!("warn  {check}: {detail}");
+        println!("      → {fix}");
+    }
+
+    fn fail(&mut self, check: &str, detail: impl Display, fix: impl Display) {
+        self.problems += 1;
+        println!("FAIL  {check}: {detail}");
+        println!("      → {fix}");
+    }
+}
+
+/// Run every check. Fails when something needs fixing.
+pub async fn run(client: &IpcClient, socket: &Path) -> Result<()> {
+    let mut report = Report::default();
+    check_gpu(&mut report).await;
+    let config = check_config(&mut report);
+    check_font(&mut report, &config);
+    check_locale(&mut report);
+    check_plugins(&mut report);
+    let running = check_socket(&mut report, client, socket).await;
+    check_shell_integration(&mut report, &config, running.then_some(client)).await;
+
+    if let Some(root) = pterminal_core::config::dirs::portable_root() {
+        println!(
+            "\nPortable mode ({PORTABLE_ENV}): files are in {}",
+            root.display()
+        );
+    }
+    match report.problems {
+        0 => Ok(()),
+        1 => Err(anyhow!("1 problem found")),
+        n => Err(anyhow!("{n} problems found")),
+    }
+}
+
+async fn check_gpu(report: &mut Report) {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        ..Default::default()
+    });
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        })
+        .await;
+    match adapter {
+        Ok(adapter) => {
+            let info = adapter.get_info();
+            let detail = format!("{} ({:?}, {:?})", info.name, info.backend, info.device_type);
+            if info.device_type == wgpu::DeviceType::Cpu {
+                let fix = "install the GPU's driver (Vulkan on Linux) for smooth drawing";
+                report.warn("GPU", format!("software rendering only: {detail}"), fix);
+            } else {
+                report.ok("GPU", detail);
+            }
+        }
+        Err(e) => report.fail(
+            "GPU",
+            format!("no adapter: {e}"),
+            "pterminal draws with the GPU: install or update its driver (Vulkan on \
+             Linux, Metal on macOS, DirectX 12 or Vulkan on Windows)",
+        ),
+    }
+}
+
+fn check_config(report: &mut Report) -> Config {
+    let path = Config::config_path();
+    let (config, problems) = Config::load_checked();
+    if !path.exists() {
+        report.ok(
+            "Config",
+            format!("no {}, using the defaults", path.display()),
+        );
+    } else if problems.is_empty() {
+        report.ok("Config", path.display());
+    }
+    for problem in &problems {
+        let fix = format!("edit {}", path.display());
+        if problem.fatal {
+            let detail = format!("{problem}; the defaults are used instead");
+            report.fail("Config", detail, fix);
+        } else {
+            report.warn("Config", problem, fix);
+        }
+    }
+    config
+}
+
+fn check_font(report: &mut Report, config: &Config) {
+    let wanted = config.font.families();
+    let resolution = font::resolve(&wanted, &system_monospace_families());
+    let fix = "install it, or pick an installed monospace font with \
+               `pterminal-cli config set font.family \"<name>\" --save`";
+    match (&resolution.family, resolution.missing.is_empty()) {
+        (Some(family), true) => report.ok("Font", family),
+        (Some(family), false) => {
+            let missing = resolution.missing.join(", ");
+            report.warn("Font", format!("{missing} not found, using {family}"), fix);
+        }
+        (None, _) => report.fail("Font", "no monospace font installed", fix),
+    }
+}
+
+fn check_locale(report: &mut Report) {
+    // The first of these that is set decides the character encoding
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"].iter().find_map(|name| {
+        let value = std::env::var(name).ok()?;
+        (!value.is_empty()).then(|| format!("{name}={value}"))
+    });
+    let utf8 = |locale: &str| {
+        let locale = locale.to_ascii_lowercase();
+        locale.contains("utf-8") || locale.contains("utf8")
+    };
+    let fix = "export LANG=en_US.UTF-8 (or another UTF-8 locale) in the shell's startup file";
+    match locale {
+        Some(locale) if utf8(&locale) => report.ok("Locale", locale),
+        Some(locale) => report.warn("Locale", format!("{locale} is not UTF-8"), fix),
+        // macOS terminals often run without one and programs assume UTF-8
+        None if cfg!(target_os = "macos") => report.ok("Locale", "not set"),
+        None => report.warn("Locale", "not set, so programs may assume ASCII", fix),
+    }
+
+    // Only meaningful inside a pane, where pterminal sets both
+    if std::env::var("TERM_PROGRAM").as_deref() == Ok("pterminal") {
+        match std::env::var("TERM") {
+            Ok(term) if term == "xterm-256color" => report.ok("TERM", term),
+            term => report.warn(
+                "TERM",
+                format!("{} instead of xterm-256color", term.unwrap_or_default()),
+                "the shell's startup file changes TERM; remove that so colors and keys work",
+            ),
+        }
+    }
+}
+
+fn check_plugins(report: &mut Report) {
+    let dir = Config::plugins_dir();
+    let catalog = match discover_plugin_catalog(&dir) {
+        Ok(catalog) => catalog,
+        Err(e) => {
+            let fix = format!("check the permissions of {}", dir.display());
+            return report.fail("Plugins", format!("can't read {}: {e}", dir.display()), fix);
+        }
+    };
+    for diagnostic in &catalog.diagnostics {
+        let detail = format!(
+            "{}: {}",
+            diagnostic.plugin_dir.display(),
+            diagnostic.message
+        );
+        report.fail(
+            "Plugins",
+            detail,
+            "fix its plugin.json, or remove the plugin",
+        );
+    }
+    if catalog.diagnostics.is_empty() {
+        let enabled = catalog.plugins.iter().filter(|p| p.enabled).count();
+        let total = catalog.plugins.len();
+        report.ok("Plugins", format!("{enabled} of {total} enabled"));
+    }
+}
+
+/// Whether the app answers on its socket
+async fn check_socket(report: &mut Report, client: &IpcClient, socket: &Path) -> bool {
+    if !socket.exists() {
+        report.ok(
+            "Socket",
+            format!("pterminal is not running (no {})", socket.display()),
+        );
+        return false;
+    }
+    match client.call("ping", json!({})).await {
+        Ok(_) => {
+            report.ok("Socket", socket.display());
+            true
+        }
+        Err(e) => {
+            let fix = format!(
+                "if pterminal is not running, remove {}; otherwise restart it",
+                socket.display()
+            );
+            report.fail(
+                "Socket",
+                format!("{} doesn't answer: {e}", socket.display()),
+                fix,
+            );
+            false
+        }
+    }
+}
+
+/// The login shell's startup file, and the panes of the running app
+async fn check_shell_integration(report: &mut Report, config: &Config, client: Option<&IpcClient>) {
+    let program = config.shell();
+    let install = "run `pterminal-cli shell-integration install`";
+    let Some(shell) = Shell::from_program(&program) else {
+        let detail = format!("{program} has no snippet (bash, zsh and fish do)");
+        return report.warn(
+            "Shell integration",
+            detail,
+            "prompt marks need OSC 133 set up by hand",
+        );
+    };
+    let home = std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    let rc = shell.rc_file(&home);
+    if !shell_setup::is_installed(&rc) {
+        let detail = format!(
+            "not in {}; prompt jumps and command times are off",
+            rc.display()
+        );
+        return report.warn("Shell integration", detail, install);
+    }
+    let Some(client) = client else {
+        return report.ok("Shell integration", format!("in {}", rc.display()));
+    };
+    let panes = match client.call("pane.list", json!({})).await {
+        Ok(result) => result["panes"].as_array().cloned().unwrap_or_default(),
+        Err(_) => Vec::new(),
+    };
+    let integrated = panes
+        .iter()
+        .filter(|pane| pane["prompt_marks"].as_bool() == Some(true))
+        .count();
+    if integrated == panes.len() {
+        report.ok(
+            "Shell integration",
+            format!("in {}, all panes mark prompts", rc.display()),
+        );
+    } else {
+        let detail = format!("{} of {} panes mark prompts", integrated, panes.len());
+        let fix = format!(
+            "restart shells started before it was added (`exec {}`); \
+             `pterminal-cli shell-integration doctor` shows each pane",
+            shell.name()
+        );
+        report.warn("Shell integration", detail, fix);
+    }
+}
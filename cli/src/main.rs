@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -8,19 +8,27 @@ use serde_json::{json, Value};
 
 use pterminal_core::config::theme::RgbColor;
 use pterminal_core::config::Theme;
+use pterminal_core::shell_setup::{self, Installed, Shell};
 use pterminal_core::terminal::{GridLine, TerminalEmulator};
-use pterminal_core::PaneId;
+use pterminal_core::{color_vision, crash, update, Config, PaneId};
 use pterminal_ipc::IpcClient;
 use pterminal_render::text::{PixelRect, TextRenderer};
 use pterminal_render::BgRenderer;
+use pterminal_testkit::conformance;
+
+mod doctor;
 
 #[derive(Debug, Parser)]
 #[command(name = "pterminal-cli", about = "Control pterminal via JSON-RPC IPC")]
 struct Cli {
-    /// Override socket path (default: ~/.config/pterminal/pterminal.sock)
+    /// Override socket path (default: pterminal.sock in the config directory)
     #[arg(long)]
     socket: Option<PathBuf>,
 
+    /// Auth token (default: contents of the `.token` file next to the socket)
+    #[arg(long)]
+    token: Option<String>,
+
     #[command(subcommand)]
     command: Command,
 }
@@ -29,6 +37,8 @@ struct Cli {
 enum Command {
     Ping,
     Capabilities,
+    /// Print the JSON schema of the IPC protocol (no running app needed)
+    Schema,
     Identify,
     ListWorkspaces,
     NewWorkspace,
@@ -42,6 +52,11 @@ enum Command {
         #[arg(long)]
         index: Option<usize>,
     },
+    /// Switch to the workspace for a project directory, opening one with the
+    /// `[startup.project]` layout if there is none
+    Open {
+        path: PathBuf,
+    },
     ListPanes,
     Send {
         text: String,
@@ -56,12 +71,63 @@ enum Command {
         #[arg(long)]
         pane_id: Option<u64>,
     },
+    /// Find lines containing text in the panes of the active workspace
+    SearchPanes {
+        query: String,
+        /// Search every workspace
+        #[arg(long)]
+        all_workspaces: bool,
+        /// Most lines per pane
+        #[arg(long)]
+        limit: Option<usize>,
+    },
     Notify {
         title: String,
         body: Option<String>,
     },
     ListNotifications,
+    /// Switch to the pane a notification came from (default: the latest
+    /// alert)
+    FocusNotification {
+        #[arg(long)]
+        id: Option<u64>,
+    },
     ClearNotifications,
+    /// Show or change do not disturb, quiet hours and the mute rules
+    NotificationRules {
+        #[arg(long)]
+        do_not_disturb: Option<bool>,
+        /// "22:00-07:30", or "" for none
+        #[arg(long)]
+        quiet_hours: Option<String>,
+        /// Source to mute: bell, command, osc or ipc (repeatable)
+        #[arg(long = "mute-source")]
+        muted_sources: Vec<String>,
+        /// Regex on title or body to mute (repeatable)
+        #[arg(long = "mute-pattern")]
+        mute_patterns: Vec<String>,
+    },
+    /// Mute or unmute alerts from a workspace (default: toggle the active
+    /// one)
+    MuteWorkspace {
+        #[arg(long)]
+        workspace_id: Option<u64>,
+        #[arg(long)]
+        muted: Option<bool>,
+    },
+    /// Check the GPU, fonts, locale, config, plugins, the app's socket and
+    /// shell integration, and say how to fix what is wrong
+    Doctor,
+    /// Set up the shell to mark prompts and report its directory
+    ShellIntegration {
+        #[command(subcommand)]
+        action: ShellIntegrationAction,
+    },
+    /// Show or change config options of the running app
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
     Bench {
         #[arg(long, default_value_t = 120)]
         cols: u16,
@@ -70,6 +136,41 @@ enum Command {
         #[arg(long, default_value_t = 200)]
         iterations: usize,
     },
+    /// Run the emulator's DEC/xterm conformance cases and print a pass/fail
+    /// report (no running app needed)
+    Conformance {
+        /// Print JSON instead of a Markdown table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Bundle recent crash logs into one file, with your home directory and
+    /// user name removed, to attach to a bug report. Nothing is uploaded.
+    CrashReport {
+        /// Where to write the bundle (default: pterminal-crash-report.txt)
+        #[arg(long, default_value = "pterminal-crash-report.txt")]
+        output: PathBuf,
+    },
+    /// List a theme's colors that look alike with protanopia, deuteranopia
+    /// or tritanopia, with a lighter or darker color to use instead (no
+    /// running app needed)
+    ThemeAudit {
+        /// Built-in theme or file in the themes directory (default: the
+        /// configured theme)
+        name: Option<String>,
+    },
+    /// Print the version (no running app needed)
+    Version {
+        /// Also ask the release feed whether a newer version is out
+        #[arg(long)]
+        check: bool,
+    },
+    /// Have the running app zip its config, layout, pane sizes, recent log
+    /// lines and redacted screen text, to attach to a bug report
+    DumpState {
+        /// Where to write the zip (default: a new file in the crash directory)
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
     Rpc {
         method: String,
         #[arg(long, default_value = "{}")]
@@ -77,6 +178,44 @@ enum Command {
     },
 }
 
+#[derive(Debug, Subcommand)]
+enum ShellIntegrationAction {
+    /// Add the OSC 133 / OSC 7 snippet to the shell's startup file, or
+    /// update it (no running app needed)
+    Install {
+        /// bash, zsh or fish (default: the login shell, from $SHELL)
+        shell: Option<String>,
+    },
+    /// Check that the shells in the running app's panes send the sequences
+    Doctor {
+        /// Check one pane (default: every pane of the active workspace)
+        #[arg(long)]
+        pane_id: Option<u64>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigAction {
+    /// Options with their values, e.g. `config list font`
+    List {
+        prefix: Option<String>,
+    },
+    Get {
+        key: String,
+    },
+    /// Change an option, e.g. `config set font.size 16`. The value is read
+    /// as JSON, or taken as text if it isn't
+    Set {
+        key: String,
+        value: String,
+        /// Also write it to config.toml
+        #[arg(long)]
+        save: bool,
+    },
+    /// Print the JSON Schema of config.toml (no running app needed)
+    Schema,
+}
+
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -90,9 +229,42 @@ async fn main() -> Result<()> {
         run_bench(*cols, *rows, *iterations).await?;
         return Ok(());
     }
+    if let Command::Conformance { json } = cli.command {
+        return run_conformance(json);
+    }
+    if let Command::CrashReport { output } = &cli.command {
+        return run_crash_report(output);
+    }
+    if let Command::ThemeAudit { name } = &cli.command {
+        return run_theme_audit(name.as_deref());
+    }
+    if let Command::Version { check } = cli.command {
+        return run_version(check);
+    }
+    if let Command::ShellIntegration {
+        action: ShellIntegrationAction::Install { shell },
+    } = &cli.command
+    {
+        return run_shell_integration_install(shell.as_deref());
+    }
+    if let Command::Schema = cli.command {
+        let schema = pterminal_ipc::methods::schema();
+        println!("{}", serde_json::to_string_pretty(&schema)?);
+        return Ok(());
+    }
+    if let Command::Config {
+        action: ConfigAction::Schema,
+    } = cli.command
+    {
+        println!("{}", serde_json::to_string_pretty(&Config::json_schema())?);
+        return Ok(());
+    }
 
     let socket = cli.socket.unwrap_or_else(IpcClient::default_socket_path);
-    let client = IpcClient::new(socket);
+    let mut client = IpcClient::new(socket.clone());
+    if let Some(token) = cli.token {
+        client = client.with_token(token);
+    }
 
     let result = match cli.command {
         Command::Ping => client.call("ping", json!({})).await?,
@@ -111,6 +283,14 @@ async fn main() -> Result<()> {
                 .call("workspace.select", json!({ "id": id, "index": index }))
                 .await?
         }
+        Command::Open { path } => {
+            // Relative to where the command runs, not the app
+            let path = std::fs::canonicalize(&path)
+                .with_context(|| format!("can't open {}", path.display()))?;
+            client
+                .call("workspace.open_project", json!({ "path": path }))
+                .await?
+        }
         Command::ListPanes => client.call("pane.list", json!({})).await?,
         Command::Send { text, pane_id } => {
             client
@@ -127,6 +307,18 @@ async fn main() -> Result<()> {
                 .call("pane.capture", json!({ "pane_id": pane_id }))
                 .await?
         }
+        Command::SearchPanes {
+            query,
+            all_workspaces,
+            limit,
+        } => {
+            let params = json!({
+                "query": query,
+                "all_workspaces": all_workspaces,
+                "limit": limit,
+            });
+            client.call("pane.search", params).await?
+        }
         Command::Notify { title, body } => {
             client
                 .call(
@@ -139,8 +331,73 @@ async fn main() -> Result<()> {
                 .await?
         }
         Command::ListNotifications => client.call("notification.list", json!({})).await?,
+        Command::FocusNotification { id } => {
+            client
+                .call("notification.focus", json!({ "id": id }))
+                .await?
+        }
         Command::ClearNotifications => client.call("notification.clear", json!({})).await?,
-        Command::Bench { .. } => unreachable!("handled before IPC client init"),
+        Command::NotificationRules {
+            do_not_disturb,
+            quiet_hours,
+            muted_sources,
+            mute_patterns,
+        } => {
+            // Repeatable flags left out keep the current list
+            let params = json!({
+                "do_not_disturb": do_not_disturb,
+                "quiet_hours": quiet_hours,
+                "muted_sources": (!muted_sources.is_empty()).then_some(muted_sources),
+                "mute_patterns": (!mute_patterns.is_empty()).then_some(mute_patterns),
+            });
+            client.call("notification.rules", params).await?
+        }
+        Command::MuteWorkspace {
+            workspace_id,
+            muted,
+        } => {
+            let params = json!({ "workspace_id": workspace_id, "muted": muted });
+            client.call("notification.mute", params).await?
+        }
+        Command::Doctor => return doctor::run(&client, &socket).await,
+        Command::ShellIntegration { action } => match action {
+            ShellIntegrationAction::Doctor { pane_id } => {
+                return run_shell_integration_doctor(&client, pane_id).await;
+            }
+            ShellIntegrationAction::Install { .. } => {
+                unreachable!("handled before IPC client init")
+            }
+        },
+        Command::Config { action } => match action {
+            ConfigAction::List { prefix } => {
+                client
+                    .call("config.list", json!({ "prefix": prefix }))
+                    .await?
+            }
+            ConfigAction::Get { key } => client.call("config.get", json!({ "key": key })).await?,
+            ConfigAction::Set { key, value, save } => {
+                let value = serde_json::from_str(&value).unwrap_or(Value::String(value));
+                let params = json!({ "key": key, "value": value, "save": save });
+                client.call("config.set", params).await?
+            }
+            ConfigAction::Schema => unreachable!("handled before IPC client init"),
+        },
+        Command::DumpState { output } => {
+            // The app resolves relative paths against its own directory
+            let path = match output {
+                Some(path) => Some(std::env::current_dir()?.join(path)),
+                None => None,
+            };
+            client
+                .call("debug.dump_state", json!({ "path": path }))
+                .await?
+        }
+        Command::Bench { .. }
+        | Command::Schema
+        | Command::Conformance { .. }
+        | Command::CrashReport { .. }
+        | Command::ThemeAudit { .. }
+        | Command::Version { .. } => unreachable!("handled before IPC client init"),
         Command::Rpc { method, params } => {
             let value: Value = serde_json::from_str(&params)
                 .with_context(|| format!("failed to parse --params JSON: {params}"))?;
@@ -152,6 +409,183 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+fn run_conformance(json: bool) -> Result<()> {
+    let report = conformance::run(conformance::CASES);
+    if !json {
+        print!("{report}");
+        return Ok(());
+    }
+    let cases: Vec<Value> = report
+        .results
+        .iter()
+        .map(|(case, result)| {
+            json!({
+                "feature": case.feature,
+                "case": case.name,
+                "pass": result.is_ok(),
+                "failure": result.as_ref().err(),
+            })
+        })
+        .collect();
+    let (supported, unsupported) = report.features();
+    let report = json!({
+        "passed": report.passed(),
+        "total": report.results.len(),
+        "supported": supported,
+        "unsupported": unsupported,
+        "cases": cases,
+    });
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+fn run_crash_report(output: &Path) -> Result<()> {
+    let dir = Config::crashes_dir();
+    let count = crash::report_bundle(&dir, output)
+        .with_context(|| format!("failed to write {}", output.display()))?;
+    if count == 0 {
+        println!("No crash logs in {}", dir.display());
+        return Ok(());
+    }
+    println!(
+        "Wrote {count} crash log(s) to {}. Review it before sharing.",
+        output.display()
+    );
+    Ok(())
+}
+
+fn run_theme_audit(name: Option<&str>) -> Result<()> {
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => Config::load_checked().0.theme.name,
+    };
+    let theme = Theme::load(&name, &Config::themes_dir())
+        .ok_or_else(|| anyhow!("no theme {name} (built in or in the themes directory)"))?;
+    let hex = |color: RgbColor| format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b);
+    let confusions = color_vision::audit(&theme.colors);
+    if confusions.is_empty() {
+        println!("{name}: every color stands apart with protanopia, deuteranopia and tritanopia");
+        return Ok(());
+    }
+    for confusion in &confusions {
+        print!(
+            "{}: {} and {} look alike (ΔE {:.1})",
+            confusion.vision.name(),
+            confusion.first,
+            confusion.second,
+            confusion.delta_e
+        );
+        match confusion.suggestion {
+            Some(color) => println!("; try {} for {}", hex(color), confusion.second),
+            None => println!(),
+        }
+    }
+    println!(
+        "\n{} pair(s) in {name}. See it as they do with \
+         `pterminal-cli config set accessibility.color_vision <kind>`.",
+        confusions.len()
+    );
+    Ok(())
+}
+
+fn run_version(check: bool) -> Result<()> {
+    println!("pterminal-cli {}", update::CURRENT_VERSION);
+    if !check {
+        return Ok(());
+    }
+    let (config, _) = Config::load_checked();
+    let release = update::fetch(&config.updates.feed)?;
+    if !update::is_newer(&release.version, update::CURRENT_VERSION) {
+        println!("Up to date (latest release: {})", release.version);
+        return Ok(());
+    }
+    println!(
+        "pterminal {} is available: {}",
+        release.version, release.url
+    );
+    if !release.notes.trim().is_empty() {
+        println!("\n{}", release.notes.trim_end());
+    }
+    Ok(())
+}
+
+/// The shell named, or the login shell
+fn integration_shell(name: Option<&str>) -> Result<Shell> {
+    let program = match name {
+        Some(name) => name.to_string(),
+        None => std::env::var("SHELL").context("$SHELL is not set; name the shell")?,
+    };
+    Shell::from_program(&program)
+        .ok_or_else(|| anyhow!("no shell integration for {program}; use bash, zsh or fish"))
+}
+
+fn home_dir() -> PathBuf {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+}
+
+fn run_shell_integration_install(shell: Option<&str>) -> Result<()> {
+    let shell = integration_shell(shell)?;
+    let rc = shell.rc_file(&home_dir());
+    let message = match shell_setup::install(shell, &rc)? {
+        Installed::Added => "Added shell integration to",
+        Installed::Updated => "Updated shell integration in",
+        Installed::Unchanged => "Shell integration is already up to date in",
+    };
+    println!("{message} {}", rc.display());
+    println!(
+        "New {} shells in pterminal pick it up; `pterminal-cli shell-integration doctor` checks",
+        shell.name()
+    );
+    Ok(())
+}
+
+async fn run_shell_integration_doctor(client: &IpcClient, pane_id: Option<u64>) -> Result<()> {
+    let result = client.call("pane.list", json!({})).await?;
+    let panes: Vec<&Value> = result["panes"]
+        .as_array()
+        .map(|panes| panes.iter().collect())
+        .unwrap_or_default();
+    let panes: Vec<&Value> = panes
+        .into_iter()
+        .filter(|pane| pane_id.map_or(true, |id| pane["id"] == id))
+        .collect();
+    if panes.is_empty() {
+        return Err(anyhow!("no such pane in the active workspace"));
+    }
+
+    let mut missing = false;
+    for pane in panes {
+        let marks = pane["prompt_marks"].as_bool().unwrap_or(false);
+        let cwd = pane["reported_cwd"].as_str();
+        println!(
+            "pane {}: prompt marks (OSC 133) {}, directory (OSC 7) {}",
+            pane["id"],
+            if marks { "arrive" } else { "missing" },
+            cwd.unwrap_or("missing"),
+        );
+        missing |= !marks || cwd.is_none();
+    }
+    if !missing {
+        return Ok(());
+    }
+
+    match integration_shell(None) {
+        Ok(shell) if !shell_setup::is_installed(&shell.rc_file(&home_dir())) => println!(
+            "The {} startup file has no snippet: run `pterminal-cli shell-integration install`",
+            shell.name()
+        ),
+        Ok(shell) => println!(
+            "{} has the snippet: restart shells started before it was added (`exec {}`)",
+            shell.rc_file(&home_dir()).display(),
+            shell.name()
+        ),
+        Err(_) => println!("Only bash, zsh and fish have a snippet to install"),
+    }
+    Ok(())
+}
+
 async fn run_bench(cols: u16, rows: u16, iterations: usize) -> Result<()> {
     let theme = Arc::new(Theme::default());
 
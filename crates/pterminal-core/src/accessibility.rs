@@ -0,0 +1,138 @@
+//! Terminal content for screen readers, for `[accessibility]`.
+//!
+//! The UI exposes the visible screen as plain text along with the cursor
+//! position, and reads out new output through a live region. Working out
+//! what is new compares screen snapshots rather than tracking the byte
+//! stream, so redraws, scrolling and cursor movement are handled alike.
+
+use crate::terminal::GridLine;
+
+/// Most lines read out at once; a flood is cut to its end
+const MAX_ANNOUNCE_LINES: usize = 20;
+
+/// The screen as lines of text, trailing blanks trimmed
+pub fn screen_lines(grid: &[GridLine]) -> Vec<String> {
+    grid.iter()
+        .map(|line| {
+            let text: String = line
+                .cells
+                .iter()
+                .filter(|cell| !cell.wide_spacer)
+                .map(|cell| if cell.c == '\0' { ' ' } else { cell.c })
+                .collect();
+            text.trim_end().to_string()
+        })
+        .collect()
+}
+
+/// How the cursor position is read out
+pub fn cursor_description(row: u16, col: u16) -> String {
+    format!("Line {}, column {}", row + 1, col + 1)
+}
+
+/// Finds the output that appeared since the screen was last looked at
+#[derive(Debug, Default)]
+pub struct Announcer {
+    lines: Vec<String>,
+}
+
+impl Announcer {
+    /// Start over from `lines` without reading them out, e.g. when another
+    /// pane gets focus
+    pub fn reset(&mut self, lines: Vec<String>) {
+        self.lines = lines;
+    }
+
+    /// Take the new screen; returns the text to read out, if any
+    pub fn update(&mut self, lines: Vec<String>) -> Option<String> {
+        if lines == self.lines {
+            return None;
+        }
+        let new = new_lines(&self.lines, &lines);
+        let start = new.len().saturating_sub(MAX_ANNOUNCE_LINES);
+        let text = new[start..].join("\n");
+        self.lines = lines;
+        (!text.trim().is_empty()).then_some(text)
+    }
+}
+
+/// Lines of `new` that weren't on `old`. When `old` scrolled up into `new`
+/// everything below the overlap is new; otherwise rows are compared in
+/// place. A row that only grew contributes the part that was added.
+fn new_lines<'a>(old: &[String], new: &'a [String]) -> Vec<&'a str> {
+    let old = trim_blank_rows(old);
+    let new = trim_blank_rows(new);
+    let Some((last, kept)) = old.split_last() else {
+        return new.iter().map(String::as_str).collect();
+    };
+    // `old`'s last row may still be being written to, so it needn't match;
+    // at least one other row must, or any screen would count as scrolled
+    let scroll = (0..kept.len().max(1)).find(|&k| {
+        let kept = &kept[k..];
+        new.len() > kept.len() && new[..kept.len()] == *kept
+    });
+    let Some(scroll) = scroll else {
+        return new
+            .iter()
+            .enumerate()
+            .filter_map(|(row, line)| added(old.get(row).map(String::as_str), line))
+            .collect();
+    };
+    let row = kept.len() - scroll;
+    let mut out: Vec<&str> = added(Some(last), &new[row]).into_iter().collect();
+    out.extend(new[row + 1..].iter().map(String::as_str));
+    out
+}
+
+/// What `line` adds to `before`, if it changed
+fn added<'a>(before: Option<&str>, line: &'a str) -> Option<&'a str> {
+    let before = before.unwrap_or("");
+    if line == before {
+        return None;
+    }
+    let text = line.strip_prefix(before).unwrap_or(line).trim();
+    (!text.is_empty()).then_some(text)
+}
+
+fn trim_blank_rows(lines: &[String]) -> &[String] {
+    let len = lines
+        .iter()
+        .rposition(|line| !line.is_empty())
+        .map_or(0, |i| i + 1);
+    &lines[..len]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen(rows: &[&str]) -> Vec<String> {
+        let mut lines: Vec<String> = rows.iter().map(|row| row.to_string()).collect();
+        lines.resize(4, String::new());
+        lines
+    }
+
+    #[test]
+    fn only_new_output_is_read_out() {
+        let mut announcer = Announcer::default();
+        announcer.reset(screen(&["$"]));
+        assert_eq!(announcer.update(screen(&["$"])), None);
+
+        // Typing echoes on the prompt line, output follows below
+        assert_eq!(announcer.update(screen(&["$ ls"])), Some("ls".into()));
+        let listed = screen(&["$ ls", "a.txt  b.txt", "$"]);
+        assert_eq!(announcer.update(listed), Some("a.txt  b.txt\n$".into()));
+
+        // Scrolling off the top doesn't repeat what was already read
+        let scrolled = screen(&["a.txt  b.txt", "$ echo hi", "hi", "$"]);
+        assert_eq!(announcer.update(scrolled), Some("echo hi\nhi\n$".into()));
+
+        // A redraw reads out the rows that changed
+        let redrawn = screen(&["top - 10:00", "$ echo hi", "load 0.5"]);
+        assert_eq!(
+            announcer.update(redrawn),
+            Some("top - 10:00\nload 0.5".into())
+        );
+        assert_eq!(announcer.update(screen(&[])), None);
+    }
+}
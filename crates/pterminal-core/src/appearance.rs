@@ -0,0 +1,116 @@
+//! The desktop's light/dark preference, for `theme.follow_system`, and its
+//! reduced-motion setting, for `accessibility.reduce_motion`.
+
+use std::process::Command;
+
+/// Whether the desktop prefers dark or light windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Appearance {
+    Dark,
+    Light,
+}
+
+/// Ask the OS for its current appearance; `None` when it can't be told.
+///
+/// macOS reports the global `AppleInterfaceStyle` default (unset in light
+/// mode); elsewhere the freedesktop settings portal is read over D-Bus.
+pub fn detect() -> Option<Appearance> {
+    if cfg!(target_os = "macos") {
+        let output = Command::new("defaults")
+            .args(["read", "-g", "AppleInterfaceStyle"])
+            .output()
+            .ok()?;
+        let dark =
+            output.status.success() && String::from_utf8_lossy(&output.stdout).trim() == "Dark";
+        Some(if dark {
+            Appearance::Dark
+        } else {
+            Appearance::Light
+        })
+    } else if cfg!(unix) {
+        let output = Command::new("gdbus")
+            .args([
+                "call",
+                "--session",
+                "--dest",
+                "org.freedesktop.portal.Desktop",
+                "--object-path",
+                "/org/freedesktop/portal/desktop",
+                "--method",
+                "org.freedesktop.portal.Settings.Read",
+                "org.freedesktop.appearance",
+                "color-scheme",
+            ])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        parse_portal_color_scheme(&String::from_utf8_lossy(&output.stdout))
+    } else {
+        None
+    }
+}
+
+/// Whether the desktop asks apps to keep animation to a minimum; false when
+/// it can't be told.
+///
+/// macOS reads the universal access `reduceMotion` default; elsewhere GNOME's
+/// `enable-animations` setting.
+pub fn reduced_motion() -> bool {
+    let (program, args) = if cfg!(target_os = "macos") {
+        (
+            "defaults",
+            ["read", "com.apple.universalaccess", "reduceMotion"],
+        )
+    } else if cfg!(unix) {
+        (
+            "gsettings",
+            ["get", "org.gnome.desktop.interface", "enable-animations"],
+        )
+    } else {
+        return false;
+    };
+    Command::new(program)
+        .args(args)
+        .output()
+        .is_ok_and(|output| {
+            let value = String::from_utf8_lossy(&output.stdout);
+            output.status.success() && matches!(value.trim(), "1" | "false")
+        })
+}
+
+/// Parse the portal's `color-scheme` reply, e.g. `(<<uint32 1>>,)`:
+/// 1 prefers dark, 2 prefers light, 0 has no preference.
+fn parse_portal_color_scheme(reply: &str) -> Option<Appearance> {
+    let value = reply.split("uint32").nth(1)?;
+    let digits: String = value
+        .trim_start()
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    match digits.parse::<u32>().ok()? {
+        1 => Some(Appearance::Dark),
+        2 => Some(Appearance::Light),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn portal_replies() {
+        assert_eq!(
+            parse_portal_color_scheme("(<<uint32 1>>,)\n"),
+            Some(Appearance::Dark)
+        );
+        assert_eq!(
+            parse_portal_color_scheme("(<uint32 2>,)"),
+            Some(Appearance::Light)
+        );
+        assert_eq!(parse_portal_color_scheme("(<<uint32 0>>,)"), None);
+        assert_eq!(parse_portal_color_scheme("Error: no such key"), None);
+    }
+}
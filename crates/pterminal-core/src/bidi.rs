@@ -0,0 +1,403 @@
+//! Right-to-left text in the cell grid ("bidi mode").
+//!
+//! The grid stores cells in logical order, the order programs wrote them.
+//! With bidi on, each line is treated as a paragraph of the Unicode
+//! Bidirectional Algorithm and its cells are shown in visual order, so
+//! Hebrew and Arabic read right to left while numbers and Latin text inside
+//! them stay left to right. It is a subset of the algorithm, as in other
+//! terminals: no explicit embeddings, and every cell stays one cell wide.
+//! The cursor, selection and mouse work in logical columns and are mapped
+//! through [`Visual`].
+
+use crate::terminal::GridCell;
+
+/// Direction of a line when it has no other say
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BaseDirection {
+    /// From the first strong letter on the line, left to right without one
+    #[default]
+    Auto,
+    Ltr,
+    Rtl,
+}
+
+impl BaseDirection {
+    /// "ltr", "rtl", anything else is auto
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "ltr" => Self::Ltr,
+            "rtl" => Self::Rtl,
+            _ => Self::Auto,
+        }
+    }
+}
+
+/// Bidi classes this subset tells apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    L,
+    R,
+    /// Arabic letter
+    AL,
+    /// European number
+    EN,
+    /// Arabic number
+    AN,
+    /// Number separator (`+ -`)
+    ES,
+    /// Common separator (`, . / :`)
+    CS,
+    /// Number terminator (`# $ %`…)
+    ET,
+    WS,
+    /// Other neutral
+    ON,
+}
+
+fn class(c: char) -> Class {
+    match c {
+        '0'..='9' | '\u{06F0}'..='\u{06F9}' => Class::EN,
+        '\u{0660}'..='\u{0669}' | '\u{066B}' | '\u{066C}' => Class::AN,
+        '\u{0590}'..='\u{05FF}'
+        | '\u{07C0}'..='\u{085F}'
+        | '\u{FB1D}'..='\u{FB4F}'
+        | '\u{10800}'..='\u{10FFF}'
+        | '\u{1E800}'..='\u{1EDFF}' => Class::R,
+        '\u{0600}'..='\u{07BF}'
+        | '\u{0860}'..='\u{08FF}'
+        | '\u{FB50}'..='\u{FDFF}'
+        | '\u{FE70}'..='\u{FEFF}'
+        | '\u{1EE00}'..='\u{1EEFF}' => Class::AL,
+        '+' | '-' => Class::ES,
+        ',' | '.' | '/' | ':' | '\u{00A0}' => Class::CS,
+        '#' | '$' | '%' | '°' | '¢'..='¥' => Class::ET,
+        '\0' => Class::WS,
+        c if c.is_whitespace() => Class::WS,
+        c if c.is_alphabetic() => Class::L,
+        _ => Class::ON,
+    }
+}
+
+/// Whether `c` is written right to left
+pub fn is_rtl(c: char) -> bool {
+    matches!(class(c), Class::R | Class::AL)
+}
+
+/// The glyph shown for `c` in right-to-left text, e.g. `(` as `)`
+pub fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        '‹' => '›',
+        '›' => '‹',
+        c => c,
+    }
+}
+
+/// A line in visual order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Visual {
+    /// Logical column shown at each visual column
+    order: Vec<u16>,
+    /// Visual columns in right-to-left runs
+    rtl: Vec<bool>,
+}
+
+impl Visual {
+    /// Reorder a line. `None` when it shows as stored: no right-to-left
+    /// text on a left-to-right line.
+    pub fn of(cells: &[GridCell], base: BaseDirection) -> Option<Self> {
+        if base != BaseDirection::Rtl && !cells.iter().any(|cell| is_rtl(cell.c)) {
+            return None;
+        }
+        // A wide character and its spacer move as one
+        let mut units: Vec<(usize, usize, Class)> = Vec::with_capacity(cells.len());
+        for (col, cell) in cells.iter().enumerate() {
+            match units.last_mut() {
+                Some(unit) if cell.wide_spacer => unit.1 += 1,
+                _ => units.push((col, 1, class(cell.c))),
+            }
+        }
+        let classes: Vec<Class> = units.iter().map(|unit| unit.2).collect();
+        let levels = levels(classes, base);
+
+        let mut order: Vec<usize> = (0..units.len()).collect();
+        let max = levels.iter().copied().max().unwrap_or(0);
+        for level in (1..=max).rev() {
+            let mut i = 0;
+            while i < order.len() {
+                if levels[order[i]] < level {
+                    i += 1;
+                    continue;
+                }
+                let start = i;
+                while i < order.len() && levels[order[i]] >= level {
+                    i += 1;
+                }
+                order[start..i].reverse();
+            }
+        }
+
+        let mut visual = Self {
+            order: Vec::with_capacity(cells.len()),
+            rtl: Vec::with_capacity(cells.len()),
+        };
+        for unit in order {
+            let (start, len, _) = units[unit];
+            for col in start..start + len {
+                visual.order.push(col as u16);
+                visual.rtl.push(levels[unit] % 2 == 1);
+            }
+        }
+        Some(visual)
+    }
+
+    /// Logical column shown at visual column `col`
+    pub fn logical(&self, col: u16) -> u16 {
+        self.order.get(col as usize).copied().unwrap_or(col)
+    }
+
+    /// Visual column showing logical column `col`
+    pub fn visual(&self, col: u16) -> u16 {
+        self.order
+            .iter()
+            .position(|&logical| logical == col)
+            .map_or(col, |visual| visual as u16)
+    }
+
+    /// The line's cells in visual order, brackets in right-to-left runs
+    /// mirrored
+    pub fn apply(&self, cells: &[GridCell]) -> Vec<GridCell> {
+        self.order
+            .iter()
+            .zip(&self.rtl)
+            .filter_map(|(&col, &rtl)| {
+                let mut cell = *cells.get(col as usize)?;
+                if rtl {
+                    cell.c = mirror(cell.c);
+                }
+                Some(cell)
+            })
+            .collect()
+    }
+
+    /// Visual column ranges (start, width) showing logical columns
+    /// `start..=end`
+    pub fn runs(&self, start: u16, end: u16) -> Vec<(u16, u16)> {
+        let mut runs: Vec<(u16, u16)> = Vec::new();
+        for (visual, &logical) in self.order.iter().enumerate() {
+            if !(start..=end).contains(&logical) {
+                continue;
+            }
+            let visual = visual as u16;
+            match runs.last_mut() {
+                Some((run, width)) if *run + *width == visual => *width += 1,
+                _ => runs.push((visual, 1)),
+            }
+        }
+        runs
+    }
+}
+
+/// Embedding level of each character: resolves weak types, then neutrals,
+/// then implicit levels, then trailing whitespace (rules W1–W7, N1–N2,
+/// I1–I2 and L1 of UAX #9)
+fn levels(mut classes: Vec<Class>, base: BaseDirection) -> Vec<u8> {
+    use Class::*;
+    let rtl = match base {
+        BaseDirection::Ltr => false,
+        BaseDirection::Rtl => true,
+        BaseDirection::Auto => classes
+            .iter()
+            .find(|class| matches!(class, L | R | AL))
+            .is_some_and(|class| *class != L),
+    };
+    let sos = if rtl { R } else { L };
+    let trailing_space = classes
+        .iter()
+        .rev()
+        .take_while(|class| **class == WS)
+        .count();
+
+    // W2, W3: numbers after Arabic letters are Arabic numbers
+    let mut strong = sos;
+    for class in &mut classes {
+        match *class {
+            L | R => strong = *class,
+            AL => {
+                strong = AL;
+                *class = R;
+            }
+            EN if strong == AL => *class = AN,
+            _ => {}
+        }
+    }
+    // W4: one separator between two numbers of a kind joins them
+    for i in 1..classes.len().saturating_sub(1) {
+        let (before, after) = (classes[i - 1], classes[i + 1]);
+        classes[i] = match classes[i] {
+            ES if before == EN && after == EN => EN,
+            CS if before == after && matches!(before, EN | AN) => before,
+            class => class,
+        };
+    }
+    // W5: terminators next to European numbers are part of them
+    for i in 0..classes.len() {
+        if classes[i] != EN {
+            continue;
+        }
+        for j in (0..i).rev() {
+            if classes[j] != ET {
+                break;
+            }
+            classes[j] = EN;
+        }
+        for class in &mut classes[i + 1..] {
+            if *class != ET {
+                break;
+            }
+            *class = EN;
+        }
+    }
+    // W6: other separators and terminators are neutral
+    for class in &mut classes {
+        if matches!(class, ES | CS | ET) {
+            *class = ON;
+        }
+    }
+    // W7: European numbers in left-to-right context are left to right
+    let mut strong = sos;
+    for class in &mut classes {
+        match *class {
+            L | R => strong = *class,
+            EN if strong == L => *class = L,
+            _ => {}
+        }
+    }
+
+    // N1, N2: neutrals take the direction around them if it agrees, the
+    // line's otherwise
+    let direction = |class: Class| match class {
+        L => Some(L),
+        R | EN | AN => Some(R),
+        _ => None,
+    };
+    let mut i = 0;
+    while i < classes.len() {
+        if direction(classes[i]).is_some() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < classes.len() && direction(classes[i]).is_none() {
+            i += 1;
+        }
+        let before = start
+            .checked_sub(1)
+            .and_then(|j| direction(classes[j]))
+            .unwrap_or(sos);
+        let after = classes
+            .get(i)
+            .and_then(|&class| direction(class))
+            .unwrap_or(sos);
+        let resolved = if before == after { before } else { sos };
+        classes[start..i].fill(resolved);
+    }
+
+    // I1, I2
+    let mut levels: Vec<u8> = classes
+        .iter()
+        .map(|class| match (rtl, class) {
+            (false, R) => 1,
+            (false, AN | EN) => 2,
+            (false, _) => 0,
+            (true, R) => 1,
+            (true, _) => 2,
+        })
+        .collect();
+    // L1: blank cells ending the line belong to it, not to the run before
+    let len = levels.len();
+    levels[len - trailing_space..].fill(u8::from(rtl));
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::theme::RgbColor;
+
+    fn line(text: &str) -> Vec<GridCell> {
+        text.chars()
+            .map(|c| GridCell {
+                c,
+                fg: RgbColor::new(255, 255, 255),
+                bg: RgbColor::new(0, 0, 0),
+                bold: false,
+                italic: false,
+                underline: false,
+                wide_spacer: false,
+            })
+            .collect()
+    }
+
+    fn shown(text: &str, base: BaseDirection) -> String {
+        let cells = line(text);
+        match Visual::of(&cells, base) {
+            Some(visual) => visual.apply(&cells).iter().map(|cell| cell.c).collect(),
+            None => text.to_string(),
+        }
+    }
+
+    #[test]
+    fn right_to_left_runs_are_reversed() {
+        assert_eq!(Visual::of(&line("ls -la"), BaseDirection::Auto), None);
+        // Hebrew inside English, numbers inside Hebrew
+        assert_eq!(shown("echo שלום 42", BaseDirection::Auto), "echo 42 םולש");
+        assert_eq!(
+            shown("שלום 3.14 עולם", BaseDirection::Auto),
+            "םלוע 3.14 םולש"
+        );
+        // A right-to-left line ends on the left, brackets mirrored
+        assert_eq!(shown("(שלום) ", BaseDirection::Auto), " (םולש)");
+        assert_eq!(shown("مرحبا ١٢  ", BaseDirection::Auto), "  ١٢ ابحرم");
+        assert_eq!(shown("abc  ", BaseDirection::Rtl), "  abc");
+        assert_eq!(shown("50% הנחה", BaseDirection::Ltr), "50% החנה");
+    }
+
+    #[test]
+    fn columns_map_both_ways() {
+        let cells = line("ab שלום cd");
+        let visual = Visual::of(&cells, BaseDirection::Auto).unwrap();
+        // "ab םולש cd": logical 3..=6 are shown at 6..=3
+        assert_eq!(visual.visual(3), 6);
+        assert_eq!(visual.logical(6), 3);
+        assert_eq!(visual.visual(0), 0);
+        for col in 0..cells.len() as u16 {
+            assert_eq!(visual.logical(visual.visual(col)), col);
+        }
+        // Selecting "b של" highlights around the Hebrew word's far end
+        assert_eq!(visual.runs(1, 4), vec![(1, 2), (5, 2)]);
+
+        // A wide character keeps its spacer after it
+        let mut cells = line("שלום 字 ");
+        cells.insert(
+            6,
+            GridCell {
+                wide_spacer: true,
+                ..cells[5]
+            },
+        );
+        let visual = Visual::of(&cells, BaseDirection::Rtl).unwrap();
+        let shown = visual.apply(&cells);
+        let wide = shown.iter().position(|cell| cell.c == '字').unwrap();
+        assert!(shown[wide + 1].wide_spacer);
+    }
+}
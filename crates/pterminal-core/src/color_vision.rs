@@ -0,0 +1,273 @@
+//! Color vision deficiencies, for `accessibility.color_vision`.
+//!
+//! A simulation maps a color to how it looks with one kind of color
+//! blindness (the matrices of Machado et al. 2009 at full severity, applied
+//! to linear RGB). The renderer runs the same matrix over each frame, and
+//! [`audit`] uses it to find theme colors that can no longer be told apart.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::theme::{RgbColor, ThemeColors};
+
+/// Colors closer than this (CIE76 ΔE) are hard to tell apart at a glance
+pub const CONFUSABLE_DELTA_E: f32 = 10.0;
+
+/// A kind of color blindness
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorVision {
+    /// No red cones
+    Protanopia,
+    /// No green cones, the most common
+    Deuteranopia,
+    /// No blue cones
+    Tritanopia,
+}
+
+impl ColorVision {
+    pub const ALL: [Self; 3] = [Self::Protanopia, Self::Deuteranopia, Self::Tritanopia];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Protanopia => "protanopia",
+            Self::Deuteranopia => "deuteranopia",
+            Self::Tritanopia => "tritanopia",
+        }
+    }
+
+    /// The kind called `name`; `None` for "" and "none"
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|vision| vision.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Row-major matrix taking a linear RGB color to the simulated one
+    pub fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            Self::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            Self::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            Self::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+
+    /// How `color` looks with this kind of color blindness
+    pub fn simulate(self, color: RgbColor) -> RgbColor {
+        let linear = to_linear(color);
+        let m = self.matrix();
+        let channel = |row: [f32; 3]| {
+            let value = row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2];
+            to_srgb(value)
+        };
+        RgbColor::new(channel(m[0]), channel(m[1]), channel(m[2]))
+    }
+}
+
+/// Two theme colors that look alike with a color vision deficiency
+#[derive(Debug, Clone, PartialEq)]
+pub struct Confusion {
+    pub vision: ColorVision,
+    /// "background", or an ANSI color: "red" (1) to "bright cyan" (14)
+    pub first: &'static str,
+    pub second: &'static str,
+    /// ΔE between the two as simulated
+    pub delta_e: f32,
+    /// `second` made lighter or darker just enough to stand apart from
+    /// `first`, if that can be done
+    pub suggestion: Option<RgbColor>,
+}
+
+const NAMES: [&str; 6] = ["red", "green", "yellow", "blue", "magenta", "cyan"];
+const BRIGHT_NAMES: [&str; 6] = [
+    "bright red",
+    "bright green",
+    "bright yellow",
+    "bright blue",
+    "bright magenta",
+    "bright cyan",
+];
+
+/// Pairs of `colors` that are distinct with normal vision but not with
+/// one of the deficiencies: the six ANSI hues against each other (normal and
+/// bright apart) and against the background
+pub fn audit(colors: &ThemeColors) -> Vec<Confusion> {
+    let normal: Vec<_> = NAMES.iter().zip(&colors.ansi[1..7]).collect();
+    let bright: Vec<_> = BRIGHT_NAMES.iter().zip(&colors.ansi[9..15]).collect();
+    let mut pairs = Vec::new();
+    for hues in [&normal, &bright] {
+        for (i, &(first, a)) in hues.iter().enumerate() {
+            pairs.push(("background", colors.background, *first, *a));
+            for &(second, b) in &hues[i + 1..] {
+                pairs.push((*first, *a, *second, *b));
+            }
+        }
+    }
+
+    let mut found = Vec::new();
+    for vision in ColorVision::ALL {
+        for &(first, a, second, b) in &pairs {
+            if delta_e(a, b) < CONFUSABLE_DELTA_E {
+                continue;
+            }
+            let seen = delta_e(vision.simulate(a), vision.simulate(b));
+            if seen < CONFUSABLE_DELTA_E {
+                found.push(Confusion {
+                    vision,
+                    first,
+                    second,
+                    delta_e: seen,
+                    suggestion: separate(vision, a, b),
+                });
+            }
+        }
+    }
+    found
+}
+
+/// `b` moved towards white or black, away from `a`'s lightness, until the
+/// two are told apart with `vision`
+fn separate(vision: ColorVision, a: RgbColor, b: RgbColor) -> Option<RgbColor> {
+    let target = if lab(b)[0] >= lab(a)[0] {
+        RgbColor::new(0xff, 0xff, 0xff)
+    } else {
+        RgbColor::new(0x00, 0x00, 0x00)
+    };
+    let mix = |t: f32| {
+        let channel =
+            |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * t).round() as u8;
+        RgbColor::new(
+            channel(b.r, target.r),
+            channel(b.g, target.g),
+            channel(b.b, target.b),
+        )
+    };
+    let apart =
+        |color: RgbColor| delta_e(vision.simulate(a), vision.simulate(color)) >= CONFUSABLE_DELTA_E;
+    if !apart(mix(1.0)) {
+        return None;
+    }
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..12 {
+        let mid = (low + high) / 2.0;
+        if apart(mix(mid)) {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    Some(mix(high))
+}
+
+/// CIE76 color difference: distance in CIELAB
+pub fn delta_e(a: RgbColor, b: RgbColor) -> f32 {
+    let (a, b) = (lab(a), lab(b));
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+fn to_linear(color: RgbColor) -> [f32; 3] {
+    let channel = |c: u8| {
+        let c = c as f32 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    [channel(color.r), channel(color.g), channel(color.b)]
+}
+
+fn to_srgb(linear: f32) -> u8 {
+    let linear = linear.clamp(0.0, 1.0);
+    let c = if linear <= 0.0031308 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    };
+    (c * 255.0).round() as u8
+}
+
+/// CIELAB, D65 white
+fn lab(color: RgbColor) -> [f32; 3] {
+    let [r, g, b] = to_linear(color);
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+    let f = |t: f32| {
+        if t > 0.008856 {
+            t.cbrt()
+        } else {
+            7.787 * t + 16.0 / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simulation() {
+        let white = RgbColor::new(0xff, 0xff, 0xff);
+        let gray = RgbColor::new(0x80, 0x80, 0x80);
+        for vision in ColorVision::ALL {
+            // Grays are seen as they are
+            let seen = vision.simulate(gray);
+            assert!(delta_e(seen, gray) < 1.0, "{vision:?}: {seen:?}");
+            assert!(delta_e(vision.simulate(white), white) < 1.0);
+        }
+        let red = RgbColor::new(0xd0, 0x30, 0x30);
+        let green = RgbColor::new(0x70, 0x70, 0x00);
+        assert!(delta_e(red, green) > 30.0);
+        let seen = delta_e(
+            ColorVision::Deuteranopia.simulate(red),
+            ColorVision::Deuteranopia.simulate(green),
+        );
+        assert!(seen < CONFUSABLE_DELTA_E, "{seen}");
+        assert_eq!(
+            ColorVision::from_name("Tritanopia"),
+            Some(ColorVision::Tritanopia)
+        );
+        assert_eq!(ColorVision::from_name("none"), None);
+    }
+
+    #[test]
+    fn audit_flags_and_separates() {
+        let mut colors = ThemeColors::default();
+        colors.ansi[1] = RgbColor::new(0xd0, 0x30, 0x30);
+        colors.ansi[2] = RgbColor::new(0x70, 0x70, 0x00);
+        let found = audit(&colors);
+        let confusion = found
+            .iter()
+            .find(|c| {
+                c.vision == ColorVision::Deuteranopia && (c.first, c.second) == ("red", "green")
+            })
+            .expect("red and green flagged");
+        let suggestion = confusion.suggestion.expect("a lighter green");
+        let vision = ColorVision::Deuteranopia;
+        let seen = delta_e(vision.simulate(colors.ansi[1]), vision.simulate(suggestion));
+        assert!(seen >= CONFUSABLE_DELTA_E);
+        assert_ne!(suggestion, colors.ansi[2]);
+
+        // The high-contrast theme tells its hues apart whatever the vision
+        let high_contrast = crate::config::Theme::high_contrast();
+        let pairs: Vec<_> = audit(&high_contrast.colors)
+            .into_iter()
+            .filter(|c| c.first == "background")
+            .collect();
+        assert_eq!(pairs, Vec::new());
+    }
+}
@@ -0,0 +1,275 @@
+//! Checking `config.toml` before it is used.
+//!
+//! A syntax or type error is reported with its line and the defaults are
+//! used instead; keys pterminal doesn't know are reported too, as they are
+//! most likely typos. `${env:NAME}` in a string is replaced by the
+//! environment variable; `$${env:` escapes it. The plain `${NAME}` form is
+//! left alone so hook commands can leave it to the shell.
+
+use std::fmt;
+use std::ops::Range;
+
+use toml_edit::{ImDocument, Item, TableLike};
+
+use super::keys::FREE_FORM;
+use super::Config;
+
+/// Something wrong with the config file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigProblem {
+    /// Line of the file, from 1, when known
+    pub line: Option<usize>,
+    pub message: String,
+    /// The file could not be used; the defaults were
+    pub fatal: bool,
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {line}: {}", self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl Config {
+    /// Load the config file with what is wrong with it. Defaults are
+    /// returned when there is no file or it can't be used.
+    pub fn load_checked() -> (Self, Vec<ConfigProblem>) {
+        let path = Self::config_path();
+        if !path.exists() {
+            return (Config::default(), Vec::new());
+        }
+        match std::fs::read_to_string(&path) {
+            Ok(content) => Self::check(&content),
+            Err(e) => (
+                Config::default(),
+                vec![ConfigProblem {
+                    line: None,
+                    message: format!("can't read {}: {e}", path.display()),
+                    fatal: true,
+                }],
+            ),
+        }
+    }
+
+    /// Parse config file text, substituting `${env:NAME}` from the
+    /// environment
+    pub fn check(text: &str) -> (Self, Vec<ConfigProblem>) {
+        check_with(text, |name| std::env::var(name).ok())
+    }
+}
+
+fn check_with(text: &str, env: impl Fn(&str) -> Option<String>) -> (Config, Vec<ConfigProblem>) {
+    let fatal = |span: Option<Range<usize>>, message: &str| {
+        let message = message.trim_end().replace('\n', ", ");
+        let line = span.map(|span| line_of(text, span.start));
+        let message = match line.and_then(|line| text.lines().nth(line - 1)) {
+            Some(source) => format!("{message} (`{}`)", source.trim()),
+            None => message,
+        };
+        let problem = ConfigProblem {
+            line,
+            message,
+            fatal: true,
+        };
+        (Config::default(), vec![problem])
+    };
+    let doc = match ImDocument::parse(text) {
+        Ok(doc) => doc,
+        Err(e) => return fatal(e.span(), e.message()),
+    };
+    if let Err(e) = toml::from_str::<Config>(text) {
+        return fatal(e.span(), e.message());
+    }
+
+    let mut problems = Vec::new();
+    let known = toml::Value::try_from(Config::default()).unwrap_or(toml::Value::Boolean(false));
+    let mut unknown = Vec::new();
+    if let Some(known) = known.as_table() {
+        unknown_keys(doc.as_table(), known, "", &mut unknown);
+    }
+    for (path, span) in unknown {
+        problems.push(ConfigProblem {
+            line: span.map(|span| line_of(text, span.start)),
+            message: format!("unknown key `{path}`, ignored"),
+            fatal: false,
+        });
+    }
+
+    let mut value = match text.parse::<toml::Table>() {
+        Ok(table) => toml::Value::Table(table),
+        Err(e) => return fatal(e.span(), e.message()),
+    };
+    let mut unset = Vec::new();
+    substitute(&mut value, "", &env, &mut unset);
+    for (path, name) in unset {
+        problems.push(ConfigProblem {
+            line: None,
+            message: format!("`{path}` uses ${{env:{name}}}, which is not set"),
+            fatal: false,
+        });
+    }
+    match value.try_into::<Config>() {
+        Ok(config) => (config, problems),
+        Err(e) => fatal(None, e.message()),
+    }
+}
+
+/// Keys of `table` that `known` (the defaults) doesn't have, with where
+/// they are
+fn unknown_keys(
+    table: &dyn TableLike,
+    known: &toml::Table,
+    path: &str,
+    out: &mut Vec<(String, Option<Range<usize>>)>,
+) {
+    for (key, item) in table.iter() {
+        let full = if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{path}.{key}")
+        };
+        // Some options also accept a dashed spelling
+        let Some(known) = known.get(key).or_else(|| known.get(&key.replace('-', "_"))) else {
+            let span = table.get_key_value(key).and_then(|(key, _)| key.span());
+            out.push((full, span));
+            continue;
+        };
+        if FREE_FORM.contains(&full.as_str()) {
+            continue;
+        }
+        match (item, known) {
+            (Item::ArrayOfTables(tables), toml::Value::Array(known)) => {
+                // Only lists with a default entry show what an entry holds
+                if let Some(toml::Value::Table(known)) = known.first() {
+                    for table in tables.iter() {
+                        unknown_keys(table, known, &full, out);
+                    }
+                }
+            }
+            (item, toml::Value::Table(known)) => {
+                if let Some(table) = item.as_table_like() {
+                    unknown_keys(table, known, &full, out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Replace `${env:NAME}` in every string, collecting the unset names
+fn substitute(
+    value: &mut toml::Value,
+    path: &str,
+    env: &impl Fn(&str) -> Option<String>,
+    unset: &mut Vec<(String, String)>,
+) {
+    match value {
+        toml::Value::String(text) if text.contains("${env:") => {
+            *text = expand(text, |name| {
+                env(name).or_else(|| {
+                    unset.push((path.to_string(), name.to_string()));
+                    None
+                })
+            });
+        }
+        toml::Value::Array(values) => {
+            for value in values {
+                substitute(value, path, env, unset);
+            }
+        }
+        toml::Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                substitute(value, &path, env, unset);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `text` with `${env:NAME}` replaced; unset names become empty
+fn expand(text: &str, mut lookup: impl FnMut(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${env:") {
+        if rest[..start].ends_with('$') {
+            // `$${env:` is a literal `${env:`
+            out.push_str(&rest[..start - 1]);
+            out.push_str("${env:");
+            rest = &rest[start + 6..];
+            continue;
+        }
+        let Some(len) = rest[start + 6..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let name = &rest[start + 6..start + 6 + len];
+        out.push_str(&lookup(name).unwrap_or_default());
+        rest = &rest[start + 6 + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Line, from 1, of a byte offset
+fn line_of(text: &str, offset: usize) -> usize {
+    let offset = offset.min(text.len());
+    // Spans are on char boundaries
+    text[..offset].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(text: &str) -> (Config, Vec<ConfigProblem>) {
+        let env = |name: &str| (name == "HOME").then(|| "/home/me".to_string());
+        check_with(text, env)
+    }
+
+    #[test]
+    fn problems_have_lines() {
+        let (config, problems) = check("[font]\nsize = 15\n\n[window]\nopacity = \"lots\"\n");
+        assert_eq!(config.font.size, Config::default().font.size);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].fatal);
+        assert_eq!(problems[0].line, Some(5));
+        assert!(problems[0].message.contains("opacity = \"lots\""));
+
+        let (_, problems) = check("[font\nsize = 15\n");
+        assert_eq!((problems[0].line, problems[0].fatal), (Some(1), true));
+
+        let text = "[font]\nsise = 15\n[fnot]\nsize = 1\n[keybindings]\n\"ctrl+q\" = \"quit\"\n\
+                    [hooks]\npane-exited = \"true\"\n";
+        let (_, problems) = check(text);
+        let found: Vec<_> = problems.iter().map(|p| (p.line, p.fatal)).collect();
+        assert_eq!(found, vec![(Some(2), false), (Some(3), false)]);
+        assert!(problems[0].message.contains("`font.sise`"));
+    }
+
+    #[test]
+    fn env_substitution() {
+        let text = "[general]\nworking_directory = \"${env:HOME}/src\"\n\
+                    shell = \"${env:NO_SUCH_VAR}zsh\"\n\
+                    [hooks]\nbell = \"echo ${BELL} $${env:HOME}\"\n";
+        let (config, problems) = check(text);
+        assert_eq!(config.general.working_directory, "/home/me/src");
+        assert_eq!(config.general.shell, "zsh");
+        assert_eq!(config.hooks.bell, "echo ${BELL} ${env:HOME}");
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].message.contains("NO_SUCH_VAR"));
+    }
+
+    #[test]
+    fn starter_file_is_clean() {
+        let (_, problems) = check(super::super::STARTER_CONFIG);
+        assert_eq!(problems, Vec::new());
+    }
+}
@@ -0,0 +1,267 @@
+//! Where pterminal keeps its files.
+//!
+//! Following the XDG base directory spec, what the user edits (config.toml,
+//! themes, layouts) is in `$XDG_CONFIG_HOME/pterminal`, what is installed
+//! into pterminal (plugins) in `$XDG_DATA_HOME/pterminal`, and what it
+//! records as it runs (pane logs, crash logs, sessions) in
+//! `$XDG_STATE_HOME/pterminal`. Without the variables, the platform's usual
+//! directories are used.
+//!
+//! In portable mode the three are `config`, `data` and `state` in one
+//! directory instead: the binary's, or the one `PTERMINAL_PORTABLE` names,
+//! for running from a USB stick or keeping a setup per project.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use directories::ProjectDirs;
+
+/// Turns portable mode on: "1" keeps everything next to the binary, a path
+/// keeps it there
+pub const PORTABLE_ENV: &str = "PTERMINAL_PORTABLE";
+
+/// What a directory holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirKind {
+    /// Files the user edits
+    Config,
+    /// Files installed into pterminal
+    Data,
+    /// Files pterminal writes as it runs
+    State,
+}
+
+impl DirKind {
+    fn xdg_var(self) -> &'static str {
+        match self {
+            Self::Config => "XDG_CONFIG_HOME",
+            Self::Data => "XDG_DATA_HOME",
+            Self::State => "XDG_STATE_HOME",
+        }
+    }
+
+    /// Name of the directory in portable mode
+    fn portable_name(self) -> &'static str {
+        match self {
+            Self::Config => "config",
+            Self::Data => "data",
+            Self::State => "state",
+        }
+    }
+}
+
+/// The directory for `kind` files
+pub fn dir(kind: DirKind) -> PathBuf {
+    resolve(kind, |name| std::env::var_os(name), portable_root())
+}
+
+/// Where portable mode keeps everything, or `None` when it is off
+pub fn portable_root() -> Option<PathBuf> {
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+    let cwd = std::env::current_dir().unwrap_or_default();
+    portable_root_from(std::env::var_os(PORTABLE_ENV), exe_dir, &cwd)
+}
+
+fn portable_root_from(
+    value: Option<OsString>,
+    exe_dir: Option<PathBuf>,
+    cwd: &Path,
+) -> Option<PathBuf> {
+    let value = value.filter(|value| !value.is_empty())?;
+    match value.to_str() {
+        Some("0" | "false" | "no" | "off") => None,
+        Some("1" | "true" | "yes" | "on") => exe_dir,
+        _ => Some(cwd.join(value)),
+    }
+}
+
+fn resolve(
+    kind: DirKind,
+    env: impl Fn(&str) -> Option<OsString>,
+    portable: Option<PathBuf>,
+) -> PathBuf {
+    if let Some(root) = portable {
+        return root.join(kind.portable_name());
+    }
+    // The spec has relative paths ignored
+    let xdg = env(kind.xdg_var())
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute());
+    if let Some(base) = xdg {
+        return base.join("pterminal");
+    }
+    let dirs = ProjectDirs::from("", "", "pterminal");
+    let platform = dirs.as_ref().map(|dirs| match kind {
+        DirKind::Config => dirs.config_dir().to_path_buf(),
+        DirKind::Data => dirs.data_dir().to_path_buf(),
+        // Only Linux has a state directory; elsewhere it is the local data
+        // directory, as with XDG's default of ~/.local/state
+        DirKind::State => dirs
+            .state_dir()
+            .unwrap_or_else(|| dirs.data_local_dir())
+            .to_path_buf(),
+    });
+    platform.unwrap_or_else(|| {
+        let home = env("HOME")
+            .or_else(|| env("USERPROFILE"))
+            .map_or_else(|| PathBuf::from("."), PathBuf::from);
+        let base = match kind {
+            DirKind::Config => home.join(".config"),
+            DirKind::Data => home.join(".local/share"),
+            DirKind::State => home.join(".local/state"),
+        };
+        base.join("pterminal")
+    })
+}
+
+/// Move crash logs, pane logs and scratch files out of the config and data
+/// directories, where versions before the state directory kept them. Called
+/// once at startup; a file already in the state directory is never replaced.
+pub fn migrate_state() {
+    if portable_root().is_some() {
+        return;
+    }
+    let Some(dirs) = ProjectDirs::from("", "", "pterminal") else {
+        return;
+    };
+    let state = dir(DirKind::State);
+    let old = [
+        (dirs.config_dir().join("crashes"), "crashes"),
+        (dirs.data_dir().join("logs"), "logs"),
+        (dirs.data_dir().join("scratch"), "scratch"),
+    ];
+    for (from, name) in old {
+        if let Err(e) = move_entries(&from, &state.join(name)) {
+            tracing::warn!("Moving {} to the state directory: {e}", from.display());
+        }
+    }
+}
+
+fn move_entries(from: &Path, to: &Path) -> std::io::Result<()> {
+    if from == to || !from.is_dir() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let dest = to.join(entry.file_name());
+        if dest.symlink_metadata().is_err() {
+            std::fs::rename(entry.path(), dest)?;
+        }
+    }
+    // Kept while anything is left in it
+    let _ = std::fs::remove_dir(from);
+    Ok(())
+}
+
+/// Write `contents` to a new file `name` in `dir`, both only the current
+/// user can open. A file already there is replaced, never written through,
+/// so a link planted in its place is harmless.
+pub fn write_private(dir: &Path, name: &str, contents: &[u8]) -> std::io::Result<PathBuf> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        builder.mode(0o700).create(dir)?;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    #[cfg(not(unix))]
+    builder.create(dir)?;
+    let path = dir.join(name);
+    match std::fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    std::io::Write::write_all(&mut options.open(&path)?, contents)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xdg_variables_split_the_files() {
+        let env = |name: &str| match name {
+            "XDG_CONFIG_HOME" => Some(OsString::from("/xdg/config")),
+            "XDG_STATE_HOME" => Some(OsString::from("/xdg/state")),
+            // Relative, so ignored
+            "XDG_DATA_HOME" => Some(OsString::from("xdg/data")),
+            _ => None,
+        };
+        let config = resolve(DirKind::Config, env, None);
+        assert_eq!(config, Path::new("/xdg/config/pterminal"));
+        let state = resolve(DirKind::State, env, None);
+        assert_eq!(state, Path::new("/xdg/state/pterminal"));
+        assert!(!resolve(DirKind::Data, env, None).starts_with("xdg"));
+
+        let root = Some(PathBuf::from("/usb/pterminal"));
+        let data = resolve(DirKind::Data, env, root);
+        assert_eq!(data, Path::new("/usb/pterminal/data"));
+    }
+
+    #[test]
+    fn portable_mode() {
+        let exe = Some(PathBuf::from("/usb/bin"));
+        let cwd = Path::new("/home/me/project");
+        let root = |value: &str| portable_root_from(Some(value.into()), exe.clone(), cwd);
+        assert_eq!(root("1"), exe);
+        assert_eq!(root("off"), None);
+        assert_eq!(root(""), None);
+        assert_eq!(root(".pterminal"), Some(cwd.join(".pterminal")));
+        assert_eq!(root("/opt/pt"), Some(PathBuf::from("/opt/pt")));
+        assert_eq!(portable_root_from(None, exe.clone(), cwd), None);
+    }
+
+    #[test]
+    fn old_state_moves_without_replacing() {
+        let root = std::env::temp_dir().join(format!("pterminal-migrate-{}", std::process::id()));
+        let (from, to) = (root.join("data/logs"), root.join("state/logs"));
+        std::fs::create_dir_all(&from).unwrap();
+        std::fs::create_dir_all(&to).unwrap();
+        std::fs::write(from.join("old.log"), "old").unwrap();
+        std::fs::write(from.join("both.log"), "older").unwrap();
+        std::fs::write(to.join("both.log"), "newer").unwrap();
+
+        move_entries(&from, &to).unwrap();
+        assert_eq!(std::fs::read(to.join("old.log")).unwrap(), b"old");
+        assert_eq!(std::fs::read(to.join("both.log")).unwrap(), b"newer");
+        // What could not move stays where it was
+        assert_eq!(std::fs::read(from.join("both.log")).unwrap(), b"older");
+        assert!(!from.join("old.log").exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn private_files_replace_links() {
+        let dir = std::env::temp_dir().join(format!("pterminal-private-{}", std::process::id()));
+        let target = dir.with_extension("target");
+        std::fs::write(&target, "kept").unwrap();
+        std::fs::create_dir_all(&dir).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&target, dir.join("notes.md")).unwrap();
+
+        let path = write_private(&dir, "notes.md", b"# notes").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"# notes");
+        assert_eq!(std::fs::read(&target).unwrap(), b"kept");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(&dir), 0o700);
+            assert_eq!(mode(&path), 0o600);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+        let _ = std::fs::remove_file(&target);
+    }
+}
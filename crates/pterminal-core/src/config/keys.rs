@@ -0,0 +1,536 @@
+//! Config options by dotted key (`font.size`), for `config.get/set/list`
+//! over IPC and tools that edit the config.
+//!
+//! Keys and their types come from the defaults, so every option is covered
+//! without a list to keep up to date. A new value goes back through serde,
+//! so it is checked the same way the file is.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use toml_edit::{DocumentMut, Item, Table};
+
+use super::Config;
+
+/// Tables whose keys are the user's own, not config options
+pub(super) const FREE_FORM: &[&str] = &["keybindings"];
+
+/// Options the running app applies when set over IPC, as keys or whole
+/// tables; the rest take effect on the next launch
+pub const LIVE_KEYS: &[&str] = &[
+    "font.family",
+    "font.bold_family",
+    "font.italic_family",
+    "font.bold_italic_family",
+    "font.synthetic_styles",
+    "font.size",
+    "font.line_height",
+    "font.letter_spacing",
+    "font.zoom",
+    "theme",
+    "cursor.animate",
+    "cursor.animation_ms",
+    "cursor.trail",
+    "scrollback.smooth",
+    "scrollback.smooth_duration_ms",
+    "scrollback.smooth_easing",
+    "scrollback.trackpad_multiplier",
+    "selection",
+    "notification",
+    "bidi",
+    "presentation.font_size",
+    "history",
+    "hooks",
+    "capture",
+    "accessibility.high_contrast",
+    "accessibility.min_contrast",
+    "accessibility.cursor_scale",
+    "accessibility.color_vision",
+    "window.opacity",
+    "window.unfocused_opacity",
+    "window.inactive_pane_opacity",
+    "status_bar",
+    "keybindings",
+];
+
+/// Whether setting `key` takes effect without a restart
+pub fn is_live(key: &str) -> bool {
+    LIVE_KEYS.iter().any(|live| {
+        key == *live
+            || key
+                .strip_prefix(live)
+                .is_some_and(|rest| rest.starts_with('.'))
+    })
+}
+
+/// Actions key bindings run, with what each does
+pub const ACTIONS: &[(&str, &str)] = &[
+    ("copy", "Copy the selection, or send Ctrl+C without one"),
+    ("copy-styled", "Copy the selection with its colors"),
+    ("copy-html", "Copy the selection as HTML"),
+    ("paste", "Paste the clipboard"),
+    ("new-workspace", "Open a tab"),
+    ("close-workspace", "Close the tab"),
+    ("workspace-1", "Go to tab 1"),
+    ("workspace-2", "Go to tab 2"),
+    ("workspace-3", "Go to tab 3"),
+    ("workspace-4", "Go to tab 4"),
+    ("workspace-5", "Go to tab 5"),
+    ("workspace-6", "Go to tab 6"),
+    ("workspace-7", "Go to tab 7"),
+    ("workspace-8", "Go to tab 8"),
+    ("workspace-9", "Go to tab 9"),
+    ("split-right", "Split the pane to the right"),
+    ("split-down", "Split the pane downwards"),
+    ("split-with-env", "Split with the pane's environment"),
+    ("duplicate-pane", "Split with the pane's command"),
+    ("respawn-pane", "Restart the pane's shell"),
+    ("focus-next", "Focus the next pane"),
+    ("focus-prev", "Focus the previous pane"),
+    ("search", "Search every pane's scrollback"),
+    ("pane-search", "Filter the pane's scrollback"),
+    ("snippets", "Pick a snippet to run"),
+    ("history", "Pick a command from the shell history"),
+    ("processes", "Show the pane's processes"),
+    ("theme-picker", "Pick a theme"),
+    ("overview", "Show every tab's panes"),
+    ("log-viewer", "Show the app log"),
+    ("send-to-pane", "Type into another pane"),
+    ("export-layout", "Save the tab's layout"),
+    ("import-layout", "Open a saved layout"),
+    ("rerun-last-input", "Send the previous line again"),
+    ("full-line", "Show the full line under the cursor"),
+    ("marks", "Pick a prompt mark"),
+    ("prev-mark", "Scroll to the previous prompt"),
+    ("next-mark", "Scroll to the next prompt"),
+    ("latest-alert", "Go to the latest alert"),
+    ("pin-screen", "Pin the screen to compare with later output"),
+    ("toggle-presentation", "Turn presentation mode on or off"),
+    ("toggle-eco", "Turn power saving on or off"),
+    ("toggle-high-contrast", "Turn high contrast on or off"),
+    ("toggle-prompt-gutter", "Show or hide the prompt gutter"),
+    ("toggle-wrap", "Turn line wrapping off or on for the pane"),
+    ("pause-output", "Pause or resume the pane's output"),
+    ("log-output", "Start or stop logging the pane's output"),
+    ("toggle-do-not-disturb", "Turn do not disturb on or off"),
+    ("font-bigger", "Make the pane's text bigger"),
+    ("font-smaller", "Make the pane's text smaller"),
+    ("font-reset", "Reset the pane's text size"),
+    ("cheat-sheet", "Show or hide the key bindings"),
+    ("settings", "Open the settings"),
+];
+
+/// Whether key bindings can run `action`
+pub fn is_action(action: &str) -> bool {
+    ACTIONS.iter().any(|(name, _)| *name == action)
+}
+
+/// The action bound to `keys`, which may be spelled differently in
+/// `bindings`
+pub fn bound_action<'a>(bindings: &'a HashMap<String, String>, keys: &str) -> Option<&'a str> {
+    let normalized = normalize_binding(keys);
+    bindings
+        .iter()
+        .find(|(other, _)| normalize_binding(other) == normalized)
+        .map(|(_, action)| action.as_str())
+}
+
+/// A key binding's keys in one spelling: lowercase, modifiers sorted, and
+/// "cmd", "command", "meta" and "win" as "super"; for spotting two bindings
+/// of the same keys
+pub fn normalize_binding(keys: &str) -> String {
+    // The last part is the key, which may be "+" itself
+    let (modifiers, key) = match keys.trim().strip_suffix("++") {
+        Some(modifiers) => (modifiers, "+"),
+        None => keys.trim().rsplit_once('+').unwrap_or(("", keys.trim())),
+    };
+    let mut parts: Vec<String> = modifiers
+        .split('+')
+        .map(|part| part.trim().to_lowercase())
+        .filter(|part| !part.is_empty())
+        .map(|part| match part.as_str() {
+            "cmd" | "command" | "meta" | "win" => "super".to_string(),
+            "control" => "ctrl".to_string(),
+            "option" | "opt" => "alt".to_string(),
+            _ => part,
+        })
+        .collect();
+    parts.sort_unstable();
+    parts.dedup();
+    parts.push(key.trim().to_lowercase());
+    parts.join("+")
+}
+
+/// Key bindings as aligned lines of what they do and their keys, in the
+/// order of `ACTIONS`, for the cheat sheet. Keys another action is bound
+/// to as well are marked, as are actions there are none of.
+pub fn cheat_sheet(bindings: &HashMap<String, String>) -> String {
+    let mut by_action: BTreeMap<(usize, &str), Vec<&str>> = BTreeMap::new();
+    for (keys, action) in bindings {
+        let order = ACTIONS
+            .iter()
+            .position(|(name, _)| name == action)
+            .unwrap_or(ACTIONS.len());
+        by_action.entry((order, action)).or_default().push(keys);
+    }
+    let labels: Vec<String> = by_action
+        .keys()
+        .map(|(order, action)| match ACTIONS.get(*order) {
+            Some((_, description)) => description.to_string(),
+            None => format!("{action} (no such action)"),
+        })
+        .collect();
+    let width = labels
+        .iter()
+        .map(|label| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut lines = Vec::with_capacity(by_action.len());
+    for (((_, action), mut keys), label) in by_action.into_iter().zip(labels) {
+        keys.sort_unstable();
+        let keys: Vec<String> = keys
+            .into_iter()
+            .map(|keys| {
+                let normalized = normalize_binding(keys);
+                let others: Vec<&str> = bindings
+                    .iter()
+                    .filter(|(other, other_action)| {
+                        *other_action != action && normalize_binding(other) == normalized
+                    })
+                    .map(|(_, other_action)| other_action.as_str())
+                    .collect();
+                if others.is_empty() {
+                    keys.to_string()
+                } else {
+                    format!("{keys} (also {})", others.join(", "))
+                }
+            })
+            .collect();
+        lines.push(format!("{label:width$}  {}", keys.join(", ")));
+    }
+    lines.join("\n")
+}
+
+impl Config {
+    /// Every option with its value, sorted by key. Lists and the
+    /// free-form tables are one option each.
+    pub fn values(&self) -> Vec<(String, Value)> {
+        let mut values = Vec::new();
+        if let toml::Value::Table(table) = self.to_toml() {
+            collect_leaves(&table, "", &mut values);
+        }
+        values.sort_by(|a, b| a.0.cmp(&b.0));
+        values
+            .into_iter()
+            .map(|(key, value)| (key, to_json(&value)))
+            .collect()
+    }
+
+    /// Value of an option, or of a table of them
+    pub fn value(&self, key: &str) -> Option<Value> {
+        let root = self.to_toml();
+        let mut value = &root;
+        for part in key.split('.') {
+            value = value.as_table()?.get(part)?;
+        }
+        Some(to_json(value))
+    }
+
+    /// Change an option. Numbers and booleans given for a text option are
+    /// taken as text, and whole numbers for a fractional one as a fraction.
+    pub fn set_value(&mut self, key: &str, value: Value) -> Result<(), String> {
+        let new = toml::Value::try_from(&value)
+            .map_err(|_| format!("`{key}` can't be set to {value}"))?;
+        let mut root = self.to_toml();
+        let (parents, leaf) = key.rsplit_once('.').unwrap_or(("", key));
+        let mut table = root
+            .as_table_mut()
+            .ok_or_else(|| "config is not a table".to_string())?;
+        if !parents.is_empty() {
+            for part in parents.split('.') {
+                table = table
+                    .get_mut(part)
+                    .and_then(toml::Value::as_table_mut)
+                    .ok_or_else(|| format!("unknown config key `{key}`"))?;
+            }
+        }
+        let new = match table.get(leaf) {
+            Some(old) => coerce(key, old, new)?,
+            // Keys of a free-form table, e.g. a new key binding
+            None if FREE_FORM.contains(&parents) => new,
+            None => return Err(format!("unknown config key `{key}`")),
+        };
+        table.insert(leaf.to_string(), new);
+        *self = root
+            .try_into()
+            .map_err(|e: toml::de::Error| format!("`{key}`: {}", e.message().trim_end()))?;
+        Ok(())
+    }
+
+    /// JSON Schema of `config.toml`, with the defaults
+    pub fn json_schema() -> Value {
+        let mut schema = schema_of(&Config::default().to_toml(), "");
+        schema["$schema"] = json!("https://json-schema.org/draft/2020-12/schema");
+        schema["title"] = json!("pterminal config.toml");
+        schema
+    }
+
+    /// Write one option into the config file, keeping the rest of it,
+    /// comments included, as it is
+    pub fn save_value(key: &str, value: &Value) -> Result<()> {
+        let path = Self::config_path();
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let text = save_value_in(&text, key, value)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, text)?;
+        Ok(())
+    }
+
+    fn to_toml(&self) -> toml::Value {
+        toml::Value::try_from(self).unwrap_or(toml::Value::Table(toml::Table::new()))
+    }
+}
+
+fn collect_leaves(table: &toml::Table, path: &str, out: &mut Vec<(String, toml::Value)>) {
+    for (key, value) in table {
+        let key = if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{path}.{key}")
+        };
+        match value {
+            toml::Value::Table(table) if !FREE_FORM.contains(&key.as_str()) => {
+                collect_leaves(table, &key, out);
+            }
+            value => out.push((key, value.clone())),
+        }
+    }
+}
+
+/// `new` as the type of `old`, where that is what was meant
+fn coerce(key: &str, old: &toml::Value, new: toml::Value) -> Result<toml::Value, String> {
+    let new = match (old, new) {
+        (toml::Value::Float(_), toml::Value::Integer(n)) => toml::Value::Float(n as f64),
+        (toml::Value::String(_), toml::Value::Integer(n)) => toml::Value::String(n.to_string()),
+        (toml::Value::String(_), toml::Value::Float(n)) => toml::Value::String(n.to_string()),
+        (toml::Value::String(_), toml::Value::Boolean(b)) => toml::Value::String(b.to_string()),
+        (_, new) => new,
+    };
+    if std::mem::discriminant(old) != std::mem::discriminant(&new) {
+        return Err(format!("`{key}` takes {}, not {}", kind(old), kind(&new)));
+    }
+    Ok(new)
+}
+
+fn kind(value: &toml::Value) -> &'static str {
+    match value {
+        toml::Value::String(_) => "a string",
+        toml::Value::Integer(_) => "a whole number",
+        toml::Value::Float(_) => "a number",
+        toml::Value::Boolean(_) => "true or false",
+        toml::Value::Datetime(_) => "a date",
+        toml::Value::Array(_) => "a list",
+        toml::Value::Table(_) => "a table",
+    }
+}
+
+fn to_json(value: &toml::Value) -> Value {
+    match value {
+        // Options are all f32: 0.85, not 0.8500000238418579
+        toml::Value::Float(n) => {
+            let short = (*n as f32).to_string().parse().unwrap_or(*n);
+            serde_json::Number::from_f64(short).map_or(Value::Null, Value::Number)
+        }
+        toml::Value::Array(values) => values.iter().map(to_json).collect(),
+        toml::Value::Table(table) => table
+            .iter()
+            .map(|(key, value)| (key.clone(), to_json(value)))
+            .collect(),
+        value => serde_json::to_value(value).unwrap_or(Value::Null),
+    }
+}
+
+fn schema_of(value: &toml::Value, path: &str) -> Value {
+    match value {
+        toml::Value::Table(table) if FREE_FORM.contains(&path) => {
+            let items = table
+                .values()
+                .next()
+                .map_or(json!({}), |v| schema_of(v, ""));
+            json!({ "type": "object", "additionalProperties": items, "default": to_json(value) })
+        }
+        toml::Value::Table(table) => {
+            let properties: serde_json::Map<_, _> = table
+                .iter()
+                .map(|(key, value)| {
+                    let path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{path}.{key}")
+                    };
+                    (key.clone(), schema_of(value, &path))
+                })
+                .collect();
+            json!({ "type": "object", "properties": properties, "additionalProperties": false })
+        }
+        toml::Value::Array(values) => {
+            let items = values.first().map_or(json!({}), |v| schema_of(v, ""));
+            json!({ "type": "array", "items": items, "default": to_json(value) })
+        }
+        value => {
+            let kind = match value {
+                toml::Value::String(_) => "string",
+                toml::Value::Integer(_) => "integer",
+                toml::Value::Float(_) => "number",
+                toml::Value::Boolean(_) => "boolean",
+                _ => "string",
+            };
+            json!({ "type": kind, "default": to_json(value) })
+        }
+    }
+}
+
+/// `text` with `key` set to `value`, creating its table if needed
+fn save_value_in(text: &str, key: &str, value: &Value) -> Result<String> {
+    let mut doc: DocumentMut = text.parse()?;
+    let new: toml_edit::Value = toml::Value::try_from(value)?.to_string().parse()?;
+    let (parents, leaf) = key.rsplit_once('.').unwrap_or(("", key));
+    let mut table = doc.as_table_mut() as &mut dyn toml_edit::TableLike;
+    if !parents.is_empty() {
+        for part in parents.split('.') {
+            table = table
+                .entry(part)
+                .or_insert_with(|| Item::Table(Table::new()))
+                .as_table_like_mut()
+                .ok_or_else(|| anyhow!("`{part}` in the config file is not a table"))?;
+        }
+    }
+    // Tables are written as `[key]` sections, where the old one was
+    if let toml_edit::Value::InlineTable(new) = new {
+        let mut new = new.into_table();
+        new.fmt();
+        if let Some(Item::Table(old)) = table.get(leaf) {
+            if let Some(position) = old.position() {
+                new.set_position(position);
+            }
+            *new.decor_mut() = old.decor().clone();
+        }
+        table.insert(leaf, Item::Table(new));
+        return Ok(doc.to_string());
+    }
+    match table.get_mut(leaf) {
+        // Keep the comment after the old value
+        Some(Item::Value(old)) => {
+            let decor = old.decor().clone();
+            *old = new;
+            *old.decor_mut() = decor;
+        }
+        _ => {
+            table.insert(leaf, Item::Value(new));
+        }
+    }
+    Ok(doc.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_by_key() {
+        let mut config = Config::default();
+        assert_eq!(config.value("font.size"), Some(json!(14.0)));
+        config.set_value("font.size", json!(16)).unwrap();
+        assert_eq!(config.font.size, 16.0);
+        config.set_value("general.shell", json!("fish")).unwrap();
+        config
+            .set_value("keybindings.ctrl+q", json!("quit"))
+            .unwrap();
+        assert_eq!(config.keybindings["ctrl+q"], "quit");
+        assert!(config.set_value("font.sise", json!(16)).is_err());
+        assert!(config.set_value("font.size", json!("big")).is_err());
+        assert!(config.set_value("logging.format", json!("xml")).is_err());
+        assert_eq!(config.font.size, 16.0);
+        config.set_value("window.opacity", json!(0.85)).unwrap();
+        assert_eq!(config.value("window.opacity"), Some(json!(0.85)));
+
+        let values = config.values();
+        assert!(values.iter().any(|(key, _)| key == "keybindings"));
+        assert!(values.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(is_live("theme.name") && is_live("font.size") && !is_live("font.sizes"));
+        let schema = Config::json_schema();
+        assert_eq!(
+            schema["properties"]["font"]["properties"]["size"]["type"],
+            "number"
+        );
+    }
+
+    #[test]
+    fn saving_keeps_the_rest_of_the_file() {
+        let text = "# mine\n[font]\nsize = 14 # small\nfamily = \"Iosevka\"\n";
+        let saved = save_value_in(text, "font.size", &json!(16.0)).unwrap();
+        assert_eq!(
+            saved,
+            "# mine\n[font]\nsize = 16.0 # small\nfamily = \"Iosevka\"\n"
+        );
+        let saved = save_value_in("", "window.opacity", &json!(0.9)).unwrap();
+        assert_eq!(saved, "[window]\nopacity = 0.9\n");
+        let text = "[keybindings]\n\"ctrl+a\" = \"x\"\n\n[font]\nsize = 14\n";
+        let saved = save_value_in(text, "keybindings", &json!({ "ctrl+b": "y" })).unwrap();
+        assert_eq!(
+            saved,
+            "[keybindings]\n\"ctrl+b\" = \"y\"\n\n[font]\nsize = 14\n"
+        );
+    }
+
+    #[test]
+    fn binding_spellings() {
+        assert_eq!(normalize_binding("Shift+Ctrl+T"), "ctrl+shift+t");
+        assert_eq!(
+            normalize_binding("cmd+shift+p"),
+            normalize_binding("shift+super+P")
+        );
+        assert_eq!(normalize_binding("Ctrl++"), "ctrl++");
+    }
+
+    #[test]
+    fn bindings_found_in_any_spelling() {
+        let bindings: HashMap<String, String> =
+            [("cmd+shift+v".to_string(), "pin-screen".to_string())].into();
+        assert_eq!(bound_action(&bindings, "shift+super+V"), Some("pin-screen"));
+        assert_eq!(bound_action(&bindings, "super+v"), None);
+    }
+
+    #[test]
+    fn default_bindings_run_actions() {
+        for (keys, action) in &Config::default().keybindings {
+            assert!(is_action(action), "{keys} is bound to {action}");
+        }
+    }
+
+    #[test]
+    fn cheat_sheet_by_action() {
+        let bindings: HashMap<String, String> = [
+            ("ctrl+shift+t", "new-workspace"),
+            ("cmd+t", "new-workspace"),
+            ("ctrl+shift+f", "search"),
+            ("Shift+Ctrl+F", "find"),
+        ]
+        .into_iter()
+        .map(|(keys, action)| (keys.to_string(), action.to_string()))
+        .collect();
+        assert_eq!(
+            cheat_sheet(&bindings),
+            "Open a tab                      cmd+t, ctrl+shift+t\n\
+             Search every pane's scrollback  ctrl+shift+f (also find)\n\
+             find (no such action)           Shift+Ctrl+F (also search)"
+        );
+    }
+}
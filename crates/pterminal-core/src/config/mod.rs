@@ -1,13 +1,26 @@
+mod check;
+pub mod dirs;
+pub mod keys;
 pub mod theme;
 
 use std::path::PathBuf;
 
-use anyhow::Result;
-use directories::ProjectDirs;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 
+pub use check::ConfigProblem;
+pub use dirs::DirKind;
 pub use theme::Theme;
 
+use crate::appearance::Appearance;
+use crate::bidi::BaseDirection;
+use crate::color_vision::ColorVision;
+use crate::hooks::HookEvent;
+use crate::pane_log::LogFormat;
+use crate::snippet::Snippet;
+use crate::status_bar::StatusSegment;
+use crate::workspace::layout::StartupWorkspace;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Config {
@@ -15,11 +28,38 @@ pub struct Config {
     pub font: FontConfig,
     pub theme: ThemeRef,
     pub window: WindowConfig,
+    pub borders: BorderConfig,
     pub scrollback: ScrollbackConfig,
     pub cursor: CursorConfig,
+    pub selection: SelectionConfig,
     pub notification: NotificationConfig,
     pub tmux: TmuxConfig,
+    pub nowrap: NoWrapConfig,
+    pub long_lines: LongLineConfig,
+    pub flow: FlowConfig,
+    pub bidi: BidiConfig,
+    pub progress: ProgressConfig,
+    pub presentation: PresentationConfig,
+    pub keystrokes: KeystrokeConfig,
+    pub history: HistoryConfig,
+    pub prompt_times: PromptTimesConfig,
+    pub status_bar: StatusBarConfig,
+    pub global_hotkeys: GlobalHotkeysConfig,
+    pub drop: DropConfig,
+    pub reconnect: ReconnectConfig,
+    pub ipc: IpcConfig,
+    pub startup: StartupConfig,
+    pub hooks: HooksConfig,
+    pub logging: LoggingConfig,
+    pub capture: CaptureConfig,
+    pub power: PowerConfig,
+    pub accessibility: AccessibilityConfig,
+    pub updates: UpdatesConfig,
+    /// Keys to the action they run (`keys::ACTIONS`); a table of the
+    /// user's own replaces the defaults
     pub keybindings: std::collections::HashMap<String, String>,
+    /// Named command snippets (`[[snippets]]` tables)
+    pub snippets: Vec<Snippet>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,31 +74,121 @@ pub struct GeneralConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct FontConfig {
+    /// Family name, or a comma-separated list tried in order
     pub family: String,
+    /// Families for bold, italic and bold-italic text; empty uses `family`
+    pub bold_family: String,
+    pub italic_family: String,
+    pub bold_italic_family: String,
+    /// Stand in the regular face (slanted for italic) when a family has no
+    /// bold or italic face; off renders those styles upright and regular
+    pub synthetic_styles: bool,
     pub size: f32,
     pub bold_is_bright: bool,
+    /// Line height as a multiple of the font size
+    pub line_height: f32,
+    /// Extra horizontal space between cells (logical pixels)
+    pub letter_spacing: f32,
+    /// What a pane does when its font size changes while running: "reflow"
+    /// fits the columns and rows to the pane again, "scale" keeps them and
+    /// draws the text bigger or smaller
+    pub zoom: String,
+}
+
+/// How panes respond to their font size changing while running
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ZoomMode {
+    /// Fit the grid to the pane again, resizing the shell's terminal
+    #[default]
+    Reflow,
+    /// Keep the grid, so full-screen programs aren't redrawn at a new size
+    Scale,
+}
+
+impl ZoomMode {
+    /// "scale", anything else is reflow
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "scale" => Self::Scale,
+            _ => Self::Reflow,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct ThemeRef {
     pub name: String,
+    /// Switch between `dark` and `light` with the OS appearance instead of
+    /// using `name`
+    pub follow_system: bool,
+    pub dark: String,
+    pub light: String,
+    /// Post-processing shader run over every frame: a preset ("scanlines",
+    /// "curvature", "glow") or a WGSL file, relative to the config
+    /// directory; empty for none. Themes may set their own.
+    pub shader: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct WindowConfig {
+    /// Opacity of pane content while the window has focus
     pub opacity: f32,
+    /// Opacity of pane content while another window has focus
+    pub unfocused_opacity: f32,
+    /// Extra factor for every pane but the active one
+    pub inactive_pane_opacity: f32,
     pub blur: bool,
+    /// "full", or "none" for a window without title bar and borders. On
+    /// Wayland, full decorations are drawn by the compositor when it
+    /// supports that, else by pterminal
     pub decorations: String,
     pub startup_mode: String,
+    /// Inner padding around each pane's text area (logical pixels; unset =
+    /// none, or 6 with `--raw`)
+    pub padding: Option<f32>,
+    /// Tab strip placement: "auto" (top, with two or more tabs), "top",
+    /// "bottom" or "hidden"
+    pub tab_bar: String,
+}
+
+/// Pane dividers; their colors come from the theme
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BorderConfig {
+    /// Divider thickness (logical pixels)
+    pub width: f32,
+    /// Thicken and tint the divider under the pointer
+    pub hover_highlight: bool,
+    /// Thickness of the divider under the pointer (logical pixels)
+    pub hover_width: f32,
+    /// Tint the active pane's edges that face other panes
+    pub active_accent: bool,
+    /// Thickness of the active pane's edge accent (logical pixels)
+    pub accent_width: f32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct ScrollbackConfig {
     pub lines: usize,
+    /// Lines scrolled per mouse-wheel notch
     pub multiplier: u32,
+    /// Extra factor applied to mouse-wheel (line) scrolling
+    pub wheel_multiplier: f32,
+    /// Factor applied to trackpad (pixel) scrolling. The Slint backend only
+    /// receives pixel deltas, so there it applies to all scrolling.
+    pub trackpad_multiplier: f32,
+    /// On the alternate screen, send wheel scrolls as arrow keys (when the
+    /// application enabled DECSET 1007 and isn't capturing the mouse)
+    pub alternate_scroll: bool,
+    /// Slide the content between line positions instead of jumping
+    pub smooth: bool,
+    /// How long a smooth scroll takes
+    pub smooth_duration_ms: u64,
+    /// Timing curve of a smooth scroll: "linear", "ease-out" or "ease-in-out"
+    pub smooth_easing: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,6 +197,12 @@ pub struct CursorConfig {
     pub style: String,
     pub blink: bool,
     pub blink_interval_ms: u64,
+    /// Slide the cursor between cells instead of jumping
+    pub animate: bool,
+    /// How long a slide takes
+    pub animation_ms: u64,
+    /// Leave a short fading trail behind a sliding cursor
+    pub trail: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -74,7 +210,21 @@ pub struct CursorConfig {
 pub struct NotificationConfig {
     pub enabled: bool,
     pub detect_bell: bool,
+    /// Notify when a program asks with OSC 9 or OSC 777, with a desktop
+    /// notification too while the window is in the background
     pub detect_osc: bool,
+    /// Silence notifications from panes and IPC: they are listed, already
+    /// read, without asking for attention
+    pub do_not_disturb: bool,
+    /// Do not disturb every day between these local times, e.g.
+    /// "22:00-07:30"; empty for never
+    pub quiet_hours: String,
+    /// Sources whose notifications are silenced: "bell", "command", "osc",
+    /// "ipc"
+    pub muted_sources: Vec<String>,
+    /// Notifications whose title or body matches one of these regexes are
+    /// silenced
+    pub mute_patterns: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -85,29 +235,466 @@ pub struct TmuxConfig {
     pub prefer_socket_notify: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoWrapConfig {
+    /// Start new panes in no-wrap mode
+    pub enabled: bool,
+    /// Minimum grid width (columns) while a pane is in no-wrap mode
+    pub columns: u16,
+}
+
+/// What counts as a word, for double-click selection, and how copies
+/// treat wrapped lines
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SelectionConfig {
+    /// Characters that are part of a word besides letters and digits, as
+    /// in iTerm2 (default: `/-+\~_.`, so paths select whole)
+    pub word_characters: String,
+    /// Copy a line the terminal wrapped as one line, e.g. to run a long
+    /// command again, rather than as the rows it is shown on, e.g. to quote
+    /// a log. Each pane can switch from its context menu.
+    pub join_wrapped: bool,
+}
+
+impl SelectionConfig {
+    pub fn is_word_char(&self, ch: char) -> bool {
+        ch.is_alphanumeric() || self.word_characters.contains(ch)
+    }
+}
+
+/// Very long single lines, e.g. minified JSON catted to the terminal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LongLineConfig {
+    /// Longest line shown in full; the rest is hidden behind a marker and
+    /// can be opened in the pager (0 = no limit)
+    pub max_bytes: usize,
+    /// Pager for a hidden line (default: `$PAGER`, else `less`)
+    pub pager: String,
+}
+
+/// Output flow between programs and the screen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FlowConfig {
+    /// Output rate (MB/s) past which a pane fast-forwards, showing only
+    /// the latest screenful until the output slows down (0 = no limit)
+    pub max_output_mb_per_sec: u64,
+    /// Stop sending input when a program outputs XOFF (Ctrl+S), until it
+    /// outputs XON or Ctrl+Q is typed; off ignores both
+    pub honor_xoff: bool,
+}
+
+/// Right-to-left text (Hebrew, Arabic) shown in visual order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BidiConfig {
+    pub enabled: bool,
+    /// Direction of each line: "auto" (from its first letter), "ltr" or
+    /// "rtl"
+    pub direction: String,
+}
+
+impl BidiConfig {
+    /// Base direction lines are laid out in, `None` when bidi is off
+    pub fn base_direction(&self) -> Option<BaseDirection> {
+        self.enabled
+            .then(|| BaseDirection::from_name(&self.direction))
+    }
+}
+
+impl LongLineConfig {
+    /// The configured pager, `$PAGER` or `less`
+    pub fn pager(&self) -> String {
+        if !self.pager.is_empty() {
+            return self.pager.clone();
+        }
+        std::env::var("PAGER")
+            .ok()
+            .filter(|pager| !pager.trim().is_empty())
+            .unwrap_or_else(|| "less".to_string())
+    }
+}
+
+/// Progress reported by programs (OSC 9;4)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProgressConfig {
+    pub enabled: bool,
+    /// Also read percentages from progress bars redrawn in place
+    pub detect_text: bool,
+    /// Show progress on the Dock icon (macOS)
+    pub dock: bool,
+}
+
+/// Settings applied while presentation mode is toggled on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PresentationConfig {
+    /// Font size used for every pane while presenting
+    pub font_size: f32,
+    /// Switch to the built-in high-contrast theme while presenting
+    pub high_contrast: bool,
+    /// Show the keystroke overlay while presenting
+    pub show_keystrokes: bool,
+}
+
+/// On-screen overlay of recently pressed keys, for screencasts and demos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeystrokeConfig {
+    /// Always show the overlay (presentation mode can also turn it on)
+    pub enabled: bool,
+    /// Overlay font size (logical points)
+    pub font_size: f32,
+    /// Window corner: "bottom-right", "bottom-left", "top-right" or "top-left"
+    pub position: String,
+    /// How long a key stays on screen (milliseconds)
+    pub timeout_ms: u64,
+}
+
+/// Command history popup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    pub enabled: bool,
+    /// Shell history file to read (empty = `$HISTFILE` or the shell's default)
+    pub file: String,
+    /// Most history entries offered
+    pub max_entries: usize,
+}
+
+/// Gutter with the time each command ran, from shell integration marks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PromptTimesConfig {
+    /// Show the gutter in new panes; Cmd+Shift+Z toggles it per pane
+    pub gutter: bool,
+    /// "relative" ("3m ago") or "absolute" (local time of day)
+    pub format: String,
+}
+
+/// Bar along the bottom of the window showing `segments` left to right
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatusBarConfig {
+    pub enabled: bool,
+    /// "cwd", "git-branch", "time", "pane-size", "scroll" and
+    /// "notifications", or the id of a segment a plugin contributes;
+    /// contributed segments not listed come last
+    pub segments: Vec<String>,
+    /// Logical points
+    pub font_size: f32,
+}
+
+/// System-wide shortcuts that work while another app has focus, written
+/// like key bindings ("ctrl+shift+space"); empty turns one off. Read at
+/// startup only.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GlobalHotkeysConfig {
+    /// Show and focus the window, or hide it when it's focused
+    pub toggle_window: String,
+    /// Paste the clipboard into the active pane
+    pub paste_clipboard: String,
+}
+
+/// Files dragged onto a pane
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DropConfig {
+    /// `cd` into a single dropped directory instead of pasting its path
+    pub cd_into_directory: bool,
+}
+
+/// Re-running ssh/mosh sessions that dropped with a network error
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReconnectConfig {
+    pub enabled: bool,
+    /// Programs whose sessions are reconnected
+    pub commands: Vec<String>,
+    /// Delay before the first attempt (milliseconds); doubled for each retry
+    pub initial_delay_ms: u64,
+    /// Longest delay between attempts (milliseconds)
+    pub max_delay_ms: u64,
+    /// Attempts before giving up
+    pub max_attempts: u32,
+}
+
+/// Access to the IPC socket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IpcConfig {
+    /// Require a token before panes can be read or controlled. The tokens are
+    /// written next to the socket, readable only by the user.
+    pub require_auth: bool,
+    /// Allow methods that return screen text, typed input or command lines
+    pub allow_content_read: bool,
+    /// Longest request line a client may send; longer ones close the connection
+    pub max_message_bytes: usize,
+    /// Longest text a single `terminal.send` may type
+    pub max_send_bytes: usize,
+    /// Requests each connection may make per second, in bursts of up to a
+    /// second's worth (0 = unlimited)
+    pub requests_per_second: u32,
+    /// Also serve the methods on the D-Bus session bus (Linux). Callers get
+    /// control access without a token, like any process of the user that can
+    /// read the token file.
+    pub dbus: bool,
+    /// Also answer AppleScript's `call` command (macOS), which Shortcuts
+    /// can run too. Scripts get control access without a token; macOS asks
+    /// the user before another app may send pterminal Apple Events.
+    pub applescript: bool,
+}
+
+/// Workspaces to open at launch instead of a single shell. pterminal runs
+/// one window per process, so these become the launch window's tabs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StartupConfig {
+    /// `[[startup.workspaces]]` tables, opened in order
+    pub workspaces: Vec<StartupWorkspace>,
+    /// How `pterminal-cli open` lays out a new project workspace. Pane
+    /// directories are relative to the project; `name` and `cwd` are unused.
+    pub project: StartupWorkspace,
+}
+
+/// Pane output logs (`pane.log.start`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Where logs started without a path go (empty = `logs` in the state
+    /// directory)
+    pub directory: String,
+    /// "plain" (escape sequences stripped) or "raw"
+    pub format: LogFormat,
+    /// Prefix each line with the UTC time it was logged
+    pub timestamps: bool,
+    /// Log only the final state of progress bars redrawn with CR (plain
+    /// logs only)
+    pub collapse_progress: bool,
+    /// Rotate a log once it reaches this size (MiB; 0 = never)
+    pub max_size_mb: u64,
+    /// Rotated files kept next to each log
+    pub keep: usize,
+    /// Start logging a pane while its foreground command line contains one
+    /// of these, e.g. "ssh prod"
+    pub auto: Vec<String>,
+}
+
+/// Defaults for `pane.read_screen` and `pane.capture`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureConfig {
+    /// Plain text; off keeps colors and bold/italic as SGR escapes
+    pub strip_ansi: bool,
+    /// Prefix each line with the capture time
+    pub timestamps: bool,
+}
+
+/// Power saving: in eco mode frames are capped at `eco_max_fps`, cursor
+/// and scroll animations and the post effect are off, tab previews stop
+/// refreshing and idle worker threads wake less often
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerConfig {
+    /// "auto" (while on battery), "on" or "off"; the View menu toggle and
+    /// Cmd+Shift+B override it until the next launch
+    pub eco: String,
+    pub eco_max_fps: u32,
+}
+
+impl PowerConfig {
+    /// Whether eco mode applies; `on_battery` is only asked for "auto"
+    pub fn eco_enabled(&self, on_battery: impl FnOnce() -> bool) -> bool {
+        match self.eco.as_str() {
+            "on" => true,
+            "off" => false,
+            _ => on_battery(),
+        }
+    }
+}
+
+/// Screen reader support, contrast, motion and cursor size
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessibilityConfig {
+    /// Expose the active pane's screen and cursor position through the
+    /// platform accessibility API
+    pub screen_reader: bool,
+    /// Have screen readers read out new output as it arrives
+    pub announce_output: bool,
+    /// Use the high-contrast theme; the View menu toggle and Cmd+Shift+H
+    /// flip it until the next launch
+    pub high_contrast: bool,
+    /// Lowest contrast ratio (1-21) text keeps against its background in
+    /// any theme; 0 leaves it to the theme
+    pub min_contrast: f32,
+    /// Turn off cursor and scroll animations: "auto" (when the OS asks for
+    /// reduced motion), "on" or "off"
+    pub reduce_motion: String,
+    /// Width of the bar cursor as a multiple of the usual
+    pub cursor_scale: f32,
+    /// Show everything as seen with a kind of color blindness:
+    /// "protanopia", "deuteranopia" or "tritanopia"; "" for as it is
+    pub color_vision: String,
+}
+
+impl AccessibilityConfig {
+    /// Whether animations are off; `os_prefers` is only asked for "auto"
+    pub fn reduce_motion_enabled(&self, os_prefers: impl FnOnce() -> bool) -> bool {
+        match self.reduce_motion.as_str() {
+            "on" => true,
+            "off" => false,
+            _ => os_prefers(),
+        }
+    }
+
+    /// The color blindness to simulate, if any
+    pub fn color_vision(&self) -> Option<ColorVision> {
+        ColorVision::from_name(&self.color_vision)
+    }
+}
+
+/// Looking for a newer release (off by default)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdatesConfig {
+    /// Fetch the release feed at launch, at most once per interval, and say
+    /// when a newer version is out. Nothing else is sent.
+    pub check: bool,
+    /// URL of the latest release, in the GitHub API's form
+    pub feed: String,
+    pub interval_hours: u64,
+}
+
+/// Shell commands run on lifecycle events, with `PANE_ID`, `CWD`,
+/// `EXIT_CODE` and the like in their environment; empty runs nothing
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    pub startup: String,
+    #[serde(alias = "pane-spawned")]
+    pub pane_spawned: String,
+    #[serde(alias = "pane-exited")]
+    pub pane_exited: String,
+    #[serde(alias = "workspace-created")]
+    pub workspace_created: String,
+    pub bell: String,
+    #[serde(alias = "command-finished")]
+    pub command_finished: String,
+}
+
+impl HooksConfig {
+    /// Command configured for `event`
+    pub fn command(&self, event: HookEvent) -> Option<&str> {
+        let command = match event {
+            HookEvent::Startup => &self.startup,
+            HookEvent::PaneSpawned => &self.pane_spawned,
+            HookEvent::PaneExited => &self.pane_exited,
+            HookEvent::WorkspaceCreated => &self.workspace_created,
+            HookEvent::Bell => &self.bell,
+            HookEvent::CommandFinished => &self.command_finished,
+        };
+        Some(command.as_str()).filter(|c| !c.trim().is_empty())
+    }
+}
+
 impl Config {
-    /// Load config from default path (~/.config/pterminal/config.toml)
+    /// Load config from default path (`config.toml` in the config directory),
+    /// failing if it can't be used. See [`load_checked`](Self::load_checked)
+    /// for the smaller problems too.
     pub fn load() -> Result<Self> {
-        let path = Self::config_path();
-        if path.exists() {
-            let content = std::fs::read_to_string(&path)?;
-            let config: Config = toml::from_str(&content)?;
-            Ok(config)
-        } else {
-            Ok(Config::default())
+        let (config, problems) = Self::load_checked();
+        match problems.into_iter().find(|problem| problem.fatal) {
+            Some(problem) => Err(anyhow!("{}: {problem}", Self::config_path().display())),
+            None => Ok(config),
         }
     }
 
+    /// Directory of the files the user edits: config.toml, themes, layouts.
+    /// See [`dirs`] for where the directories are.
     pub fn config_dir() -> PathBuf {
-        ProjectDirs::from("", "", "pterminal")
-            .map(|d| d.config_dir().to_path_buf())
-            .unwrap_or_else(|| dirs_fallback().join("pterminal"))
+        dirs::dir(DirKind::Config)
+    }
+
+    /// Directory of files installed into pterminal, such as plugins
+    pub fn data_dir() -> PathBuf {
+        dirs::dir(DirKind::Data)
+    }
+
+    /// Directory of what pterminal records as it runs: logs, crash logs
+    /// and sessions
+    pub fn state_dir() -> PathBuf {
+        dirs::dir(DirKind::State)
     }
 
     pub fn config_path() -> PathBuf {
         Self::config_dir().join("config.toml")
     }
 
+    /// Write a config file of commented-out examples, unless there is one.
+    /// Done on the first launch, so the welcome is only shown once.
+    pub fn create_starter_file() -> Result<()> {
+        let path = Self::config_path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        std::io::Write::write_all(&mut file, STARTER_CONFIG.as_bytes())?;
+        Ok(())
+    }
+
+    /// Directory holding user themes (`<name>.toml`)
+    pub fn themes_dir() -> PathBuf {
+        Self::config_dir().join("themes")
+    }
+
+    /// Directory holding exported workspace layouts
+    pub fn layouts_dir() -> PathBuf {
+        Self::config_dir().join("layouts")
+    }
+
+    /// Directory holding installed plugins
+    pub fn plugins_dir() -> PathBuf {
+        Self::data_dir().join("plugins")
+    }
+
+    /// When updates were last checked for, by its modification time
+    pub fn update_stamp_path() -> PathBuf {
+        Self::state_dir().join("update-check")
+    }
+
+    /// Write `contents` to a file `name` only the current user can open,
+    /// for handing to a pager; one of the same name is replaced
+    pub fn write_scratch(name: &str, contents: &[u8]) -> std::io::Result<PathBuf> {
+        dirs::write_private(&Self::state_dir().join("scratch"), name, contents)
+    }
+
+    /// Directory holding crash logs of panicked threads
+    pub fn crashes_dir() -> PathBuf {
+        Self::state_dir().join("crashes")
+    }
+
+    /// Directory for pane logs started without a path
+    pub fn logs_dir(&self) -> PathBuf {
+        if !self.logging.directory.is_empty() {
+            return PathBuf::from(&self.logging.directory);
+        }
+        Self::state_dir().join("logs")
+    }
+
     /// Resolve the shell to use
     pub fn shell(&self) -> String {
         if !self.general.shell.is_empty() {
@@ -145,11 +732,35 @@ impl Default for Config {
             font: FontConfig::default(),
             theme: ThemeRef::default(),
             window: WindowConfig::default(),
+            borders: BorderConfig::default(),
             scrollback: ScrollbackConfig::default(),
             cursor: CursorConfig::default(),
+            selection: SelectionConfig::default(),
             notification: NotificationConfig::default(),
             tmux: TmuxConfig::default(),
+            nowrap: NoWrapConfig::default(),
+            long_lines: LongLineConfig::default(),
+            flow: FlowConfig::default(),
+            bidi: BidiConfig::default(),
+            progress: ProgressConfig::default(),
+            presentation: PresentationConfig::default(),
+            keystrokes: KeystrokeConfig::default(),
+            history: HistoryConfig::default(),
+            prompt_times: PromptTimesConfig::default(),
+            status_bar: StatusBarConfig::default(),
+            global_hotkeys: GlobalHotkeysConfig::default(),
+            drop: DropConfig::default(),
+            reconnect: ReconnectConfig::default(),
+            ipc: IpcConfig::default(),
+            startup: StartupConfig::default(),
+            hooks: HooksConfig::default(),
+            logging: LoggingConfig::default(),
+            capture: CaptureConfig::default(),
+            power: PowerConfig::default(),
+            accessibility: AccessibilityConfig::default(),
+            updates: UpdatesConfig::default(),
             keybindings: default_keybindings(),
+            snippets: Vec::new(),
         }
     }
 }
@@ -169,16 +780,53 @@ impl Default for FontConfig {
     fn default() -> Self {
         Self {
             family: "Monaco".to_string(),
+            bold_family: String::new(),
+            italic_family: String::new(),
+            bold_italic_family: String::new(),
+            synthetic_styles: true,
             size: 14.0,
             bold_is_bright: false,
+            line_height: 1.22,
+            letter_spacing: 0.0,
+            zoom: "reflow".to_string(),
         }
     }
 }
 
+impl FontConfig {
+    /// Configured families, in the order they are tried
+    pub fn families(&self) -> Vec<&str> {
+        self.family
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .collect()
+    }
+
+    pub fn zoom_mode(&self) -> ZoomMode {
+        ZoomMode::from_name(&self.zoom)
+    }
+}
+
 impl Default for ThemeRef {
     fn default() -> Self {
         Self {
             name: "default-dark".to_string(),
+            follow_system: false,
+            dark: "default-dark".to_string(),
+            light: "default-light".to_string(),
+            shader: String::new(),
+        }
+    }
+}
+
+impl ThemeRef {
+    /// Theme to use given the OS appearance, when known
+    pub fn active_name(&self, appearance: Option<Appearance>) -> &str {
+        match appearance {
+            Some(Appearance::Dark) if self.follow_system => &self.dark,
+            Some(Appearance::Light) if self.follow_system => &self.light,
+            _ => &self.name,
         }
     }
 }
@@ -187,9 +835,42 @@ impl Default for WindowConfig {
     fn default() -> Self {
         Self {
             opacity: 1.0,
+            unfocused_opacity: 1.0,
+            inactive_pane_opacity: 1.0,
             blur: false,
             decorations: "full".to_string(),
             startup_mode: "windowed".to_string(),
+            padding: None,
+            tab_bar: "auto".to_string(),
+        }
+    }
+}
+
+impl WindowConfig {
+    /// Opacity of a pane's content given window and pane focus
+    pub fn pane_opacity(&self, window_focused: bool, active_pane: bool) -> f32 {
+        let window = if window_focused {
+            self.opacity
+        } else {
+            self.unfocused_opacity
+        };
+        let pane = if active_pane {
+            1.0
+        } else {
+            self.inactive_pane_opacity
+        };
+        (window * pane).clamp(0.0, 1.0)
+    }
+}
+
+impl Default for BorderConfig {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            hover_highlight: true,
+            hover_width: 3.0,
+            active_accent: false,
+            accent_width: 2.0,
         }
     }
 }
@@ -199,6 +880,12 @@ impl Default for ScrollbackConfig {
         Self {
             lines: 10_000,
             multiplier: 3,
+            wheel_multiplier: 1.0,
+            trackpad_multiplier: 1.0,
+            alternate_scroll: true,
+            smooth: false,
+            smooth_duration_ms: 120,
+            smooth_easing: "ease-out".to_string(),
         }
     }
 }
@@ -209,6 +896,9 @@ impl Default for CursorConfig {
             style: "block".to_string(),
             blink: true,
             blink_interval_ms: 530,
+            animate: true,
+            animation_ms: 60,
+            trail: false,
         }
     }
 }
@@ -219,6 +909,10 @@ impl Default for NotificationConfig {
             enabled: true,
             detect_bell: true,
             detect_osc: true,
+            do_not_disturb: false,
+            quiet_hours: String::new(),
+            muted_sources: Vec::new(),
+            mute_patterns: Vec::new(),
         }
     }
 }
@@ -233,20 +927,270 @@ impl Default for TmuxConfig {
     }
 }
 
+impl Default for SelectionConfig {
+    fn default() -> Self {
+        Self {
+            word_characters: "/-+\\~_.".to_string(),
+            join_wrapped: false,
+        }
+    }
+}
+
+impl Default for NoWrapConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            columns: 512,
+        }
+    }
+}
+
+impl Default for LongLineConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: 128 * 1024,
+            pager: String::new(),
+        }
+    }
+}
+
+impl Default for FlowConfig {
+    fn default() -> Self {
+        Self {
+            max_output_mb_per_sec: 64,
+            honor_xoff: false,
+        }
+    }
+}
+
+impl Default for BidiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            direction: "auto".into(),
+        }
+    }
+}
+
+impl Default for ProgressConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            detect_text: false,
+            dock: true,
+        }
+    }
+}
+
+impl Default for PresentationConfig {
+    fn default() -> Self {
+        Self {
+            font_size: 22.0,
+            high_contrast: true,
+            show_keystrokes: true,
+        }
+    }
+}
+
+impl Default for KeystrokeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            font_size: 20.0,
+            position: "bottom-right".into(),
+            timeout_ms: 2000,
+        }
+    }
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            file: String::new(),
+            max_entries: 10_000,
+        }
+    }
+}
+
+impl Default for PromptTimesConfig {
+    fn default() -> Self {
+        Self {
+            gutter: false,
+            format: "relative".into(),
+        }
+    }
+}
+
+impl Default for StatusBarConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            segments: StatusSegment::DEFAULTS
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            font_size: 12.0,
+        }
+    }
+}
+
+impl Default for IpcConfig {
+    fn default() -> Self {
+        Self {
+            require_auth: true,
+            allow_content_read: true,
+            max_message_bytes: 1024 * 1024,
+            max_send_bytes: 64 * 1024,
+            requests_per_second: 100,
+            dbus: false,
+            applescript: false,
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            directory: String::new(),
+            format: LogFormat::Plain,
+            timestamps: false,
+            collapse_progress: false,
+            max_size_mb: 50,
+            keep: 3,
+            auto: Vec::new(),
+        }
+    }
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            strip_ansi: true,
+            timestamps: false,
+        }
+    }
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        Self {
+            eco: "auto".into(),
+            eco_max_fps: 30,
+        }
+    }
+}
+
+impl Default for AccessibilityConfig {
+    fn default() -> Self {
+        Self {
+            screen_reader: true,
+            announce_output: true,
+            high_contrast: false,
+            min_contrast: 0.0,
+            reduce_motion: "auto".into(),
+            cursor_scale: 1.0,
+            color_vision: String::new(),
+        }
+    }
+}
+
+impl Default for UpdatesConfig {
+    fn default() -> Self {
+        Self {
+            check: false,
+            feed: crate::update::DEFAULT_FEED.to_string(),
+            interval_hours: 24,
+        }
+    }
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            commands: vec!["ssh".into(), "mosh".into()],
+            initial_delay_ms: 2_000,
+            max_delay_ms: 60_000,
+            max_attempts: 5,
+        }
+    }
+}
+
+/// config.toml as first written: every option is optional
+const STARTER_CONFIG: &str = r#"# pterminal config. Every option is optional.
+# `pterminal --check-config` checks this file, and
+# `pterminal-cli config schema` lists every option.
+
+# [font]
+# family = "Monaco"
+# size = 14
+
+# [theme]
+# name = "default-dark"
+
+# [window]
+# opacity = 1.0
+
+# Say when a new version is out (fetches the release feed once a day)
+# [updates]
+# check = true
+
+# Replaces the default bindings, so list every one to keep
+# [keybindings]
+# "super+t" = "new-workspace"
+"#;
+
 fn default_keybindings() -> std::collections::HashMap<String, String> {
     let mut m = std::collections::HashMap::new();
-    m.insert("ctrl+shift+t".into(), "new-workspace".into());
-    m.insert("ctrl+shift+w".into(), "close-workspace".into());
-    m.insert("ctrl+shift+d".into(), "split-right".into());
-    m.insert("ctrl+shift+e".into(), "split-down".into());
-    m.insert("ctrl+shift+h".into(), "focus-left".into());
-    m.insert("ctrl+shift+l".into(), "focus-right".into());
-    m.insert("ctrl+shift+j".into(), "focus-down".into());
-    m.insert("ctrl+shift+k".into(), "focus-up".into());
-    m.insert("ctrl+shift+p".into(), "command-palette".into());
-    m.insert("ctrl+shift+f".into(), "search".into());
-    m.insert("ctrl+shift+n".into(), "notifications".into());
-    m.insert("ctrl+tab".into(), "next-workspace".into());
-    m.insert("ctrl+shift+tab".into(), "prev-workspace".into());
+    m.insert("super+c".into(), "copy".into());
+    m.insert("ctrl+c".into(), "copy".into());
+    m.insert("super+shift+c".into(), "copy-styled".into());
+    m.insert("super+alt+c".into(), "copy-html".into());
+    m.insert("super+v".into(), "paste".into());
+    m.insert("ctrl+v".into(), "paste".into());
+    m.insert("super+t".into(), "new-workspace".into());
+    m.insert("super+w".into(), "close-workspace".into());
+    for n in 1..=9 {
+        m.insert(format!("super+{n}"), format!("workspace-{n}"));
+    }
+    m.insert("super+d".into(), "split-right".into());
+    m.insert("super+shift+d".into(), "split-down".into());
+    m.insert("super+shift+o".into(), "split-with-env".into());
+    m.insert("super+shift+enter".into(), "duplicate-pane".into());
+    m.insert("super+shift+backspace".into(), "respawn-pane".into());
+    m.insert("super+]".into(), "focus-next".into());
+    m.insert("super+[".into(), "focus-prev".into());
+    m.insert("super+f".into(), "search".into());
+    m.insert("super+shift+f".into(), "pane-search".into());
+    m.insert("super+shift+s".into(), "snippets".into());
+    m.insert("super+shift+y".into(), "history".into());
+    m.insert("super+shift+x".into(), "processes".into());
+    m.insert("super+shift+k".into(), "theme-picker".into());
+    m.insert("super+shift+g".into(), "overview".into());
+    m.insert("super+shift+a".into(), "log-viewer".into());
+    m.insert("super+shift+i".into(), "send-to-pane".into());
+    m.insert("super+shift+e".into(), "export-layout".into());
+    m.insert("super+shift+l".into(), "import-layout".into());
+    m.insert("super+shift+u".into(), "rerun-last-input".into());
+    m.insert("super+shift+j".into(), "full-line".into());
+    m.insert("super+shift+n".into(), "marks".into());
+    m.insert("super+shift+up".into(), "prev-mark".into());
+    m.insert("super+shift+down".into(), "next-mark".into());
+    m.insert("super+shift+\\".into(), "latest-alert".into());
+    m.insert("super+shift+v".into(), "pin-screen".into());
+    m.insert("super+shift+m".into(), "toggle-presentation".into());
+    m.insert("super+shift+b".into(), "toggle-eco".into());
+    m.insert("super+shift+h".into(), "toggle-high-contrast".into());
+    m.insert("super+shift+z".into(), "toggle-prompt-gutter".into());
+    m.insert("super+shift+r".into(), "toggle-wrap".into());
+    m.insert("super+shift+p".into(), "pause-output".into());
+    m.insert("super+alt+o".into(), "log-output".into());
+    m.insert("super+alt+n".into(), "toggle-do-not-disturb".into());
+    m.insert("super+shift+=".into(), "font-bigger".into());
+    m.insert("super+shift+-".into(), "font-smaller".into());
+    m.insert("super+shift+0".into(), "font-reset".into());
+    m.insert("super+/".into(), "cheat-sheet".into());
+    m.insert("super+,".into(), "settings".into());
     m
 }
@@ -1,10 +1,25 @@
+use std::path::Path;
+
 use serde::{Deserialize, Serialize};
 
+/// Themes that ship with pterminal
+pub const BUILTIN_THEMES: [&str; 3] = ["default-dark", "default-light", "high-contrast"];
+
 /// Terminal color theme
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Theme {
+    /// Defaults to the file name for themes loaded from a file
+    #[serde(default)]
     pub name: String,
     pub colors: ThemeColors,
+    /// Post-processing shader for this theme, overriding `theme.shader`
+    #[serde(default)]
+    pub shader: Option<String>,
+    /// Lowest contrast ratio (1-21, as in WCAG) text keeps against its
+    /// background; text colors are lightened or darkened to reach it.
+    /// 0 shows colors as they are.
+    #[serde(default)]
+    pub min_contrast: f32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +31,13 @@ pub struct ThemeColors {
     pub selection_fg: RgbColor,
     /// ANSI colors 0-15
     pub ansi: [RgbColor; 16],
+    /// Lines between split panes; a neutral gray when unset
+    #[serde(default)]
+    pub divider: Option<RgbColor>,
+    /// Divider under the pointer and the active pane's edge accent; the
+    /// cursor color when unset
+    #[serde(default)]
+    pub border_accent: Option<RgbColor>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -49,6 +71,59 @@ impl RgbColor {
         let b = u8::from_str_radix(&hex[4..6], 16).ok()?;
         Some(Self { r, g, b })
     }
+
+    /// Relative luminance, as WCAG defines it
+    pub fn luminance(self) -> f32 {
+        let linear = |c: u8| {
+            let c = c as f32 / 255.0;
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+
+    /// WCAG contrast ratio against `other`, from 1 (none) to 21
+    pub fn contrast(self, other: RgbColor) -> f32 {
+        let (a, b) = (self.luminance(), other.luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// This color, moved towards black or white (whichever stands out more
+    /// on `bg`) just far enough to reach a contrast of `ratio`
+    pub fn with_contrast(self, bg: RgbColor, ratio: f32) -> Self {
+        if self.contrast(bg) >= ratio {
+            return self;
+        }
+        let white = RgbColor::new(0xff, 0xff, 0xff);
+        let black = RgbColor::new(0x00, 0x00, 0x00);
+        let target = if white.contrast(bg) >= black.contrast(bg) {
+            white
+        } else {
+            black
+        };
+        let mix = |t: f32| {
+            let channel =
+                |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * t).round() as u8;
+            RgbColor::new(
+                channel(self.r, target.r),
+                channel(self.g, target.g),
+                channel(self.b, target.b),
+            )
+        };
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..12 {
+            let mid = (low + high) / 2.0;
+            if mix(mid).contrast(bg) >= ratio {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        mix(high)
+    }
 }
 
 impl Default for Theme {
@@ -56,10 +131,147 @@ impl Default for Theme {
         Self {
             name: "iterm2-default".to_string(),
             colors: ThemeColors::default(),
+            shader: None,
+            min_contrast: 0.0,
+        }
+    }
+}
+
+impl Theme {
+    /// Built-in theme called `name`
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "default-dark" | "iterm2-default" => Some(Self::default()),
+            "default-light" => Some(Self::default_light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            _ => None,
+        }
+    }
+
+    /// Theme called `name`: a built-in one, or `<name>.toml` in `dir`.
+    pub fn load(name: &str, dir: &Path) -> Option<Self> {
+        if let Some(theme) = Self::named(name) {
+            return Some(theme);
+        }
+        let content = std::fs::read_to_string(dir.join(format!("{name}.toml"))).ok()?;
+        let mut theme: Theme = match toml::from_str(&content) {
+            Ok(theme) => theme,
+            Err(e) => {
+                tracing::warn!("Theme {name}: {e}");
+                return None;
+            }
+        };
+        if theme.name.is_empty() {
+            theme.name = name.to_string();
+        }
+        Some(theme)
+    }
+
+    /// Names of the built-in themes followed by the `*.toml` files in `dir`
+    pub fn installed(dir: &Path) -> Vec<String> {
+        let mut files: Vec<String> = std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+            .filter(|name| !BUILTIN_THEMES.contains(&name.as_str()))
+            .collect();
+        files.sort();
+        BUILTIN_THEMES
+            .iter()
+            .map(|name| name.to_string())
+            .chain(files)
+            .collect()
+    }
+
+    /// Light counterpart of the default theme (Tomorrow)
+    pub fn default_light() -> Self {
+        Self {
+            name: "default-light".to_string(),
+            colors: ThemeColors {
+                background: RgbColor::new(0xff, 0xff, 0xff),
+                foreground: RgbColor::new(0x4d, 0x4d, 0x4c),
+                cursor: RgbColor::new(0x4d, 0x4d, 0x4c),
+                selection_bg: RgbColor::new(0xd6, 0xd6, 0xd6),
+                selection_fg: RgbColor::new(0x4d, 0x4d, 0x4c),
+                ansi: [
+                    RgbColor::new(0x00, 0x00, 0x00), // 0 black
+                    RgbColor::new(0xc8, 0x28, 0x29), // 1 red
+                    RgbColor::new(0x71, 0x8c, 0x00), // 2 green
+                    RgbColor::new(0xa0, 0x78, 0x00), // 3 yellow
+                    RgbColor::new(0x42, 0x71, 0xae), // 4 blue
+                    RgbColor::new(0x89, 0x59, 0xa8), // 5 magenta
+                    RgbColor::new(0x3e, 0x99, 0x9f), // 6 cyan
+                    RgbColor::new(0xbf, 0xbf, 0xbf), // 7 white
+                    RgbColor::new(0x8e, 0x90, 0x8c), // 8  bright black
+                    RgbColor::new(0xe0, 0x4a, 0x4a), // 9  bright red
+                    RgbColor::new(0x8a, 0xa4, 0x10), // 10 bright green
+                    RgbColor::new(0xc0, 0x90, 0x10), // 11 bright yellow
+                    RgbColor::new(0x5a, 0x8a, 0xc8), // 12 bright blue
+                    RgbColor::new(0xa0, 0x70, 0xc0), // 13 bright magenta
+                    RgbColor::new(0x50, 0xb0, 0xb8), // 14 bright cyan
+                    RgbColor::new(0xff, 0xff, 0xff), // 15 bright white
+                ],
+                divider: Some(RgbColor::new(0xc8, 0xc8, 0xc8)),
+                border_accent: Some(RgbColor::new(0x42, 0x71, 0xae)),
+            },
+            shader: None,
+            min_contrast: 0.0,
+        }
+    }
+
+    /// Pure black/white theme with saturated ANSI colors, for projectors and
+    /// screen sharing.
+    pub fn high_contrast() -> Self {
+        Self {
+            name: "high-contrast".to_string(),
+            colors: ThemeColors {
+                background: RgbColor::new(0x00, 0x00, 0x00),
+                foreground: RgbColor::new(0xff, 0xff, 0xff),
+                cursor: RgbColor::new(0xff, 0xff, 0x00),
+                selection_bg: RgbColor::new(0xff, 0xff, 0xff),
+                selection_fg: RgbColor::new(0x00, 0x00, 0x00),
+                ansi: [
+                    RgbColor::new(0x00, 0x00, 0x00), // 0 black
+                    RgbColor::new(0xff, 0x40, 0x40), // 1 red
+                    RgbColor::new(0x40, 0xff, 0x40), // 2 green
+                    RgbColor::new(0xff, 0xff, 0x40), // 3 yellow
+                    RgbColor::new(0x60, 0xa0, 0xff), // 4 blue
+                    RgbColor::new(0xff, 0x60, 0xff), // 5 magenta
+                    RgbColor::new(0x40, 0xff, 0xff), // 6 cyan
+                    RgbColor::new(0xff, 0xff, 0xff), // 7 white
+                    RgbColor::new(0x90, 0x90, 0x90), // 8  bright black
+                    RgbColor::new(0xff, 0x70, 0x70), // 9  bright red
+                    RgbColor::new(0x70, 0xff, 0x70), // 10 bright green
+                    RgbColor::new(0xff, 0xff, 0x80), // 11 bright yellow
+                    RgbColor::new(0x90, 0xc0, 0xff), // 12 bright blue
+                    RgbColor::new(0xff, 0x90, 0xff), // 13 bright magenta
+                    RgbColor::new(0x80, 0xff, 0xff), // 14 bright cyan
+                    RgbColor::new(0xff, 0xff, 0xff), // 15 bright white
+                ],
+                divider: Some(RgbColor::new(0xff, 0xff, 0xff)),
+                border_accent: Some(RgbColor::new(0xff, 0xff, 0x00)),
+            },
+            shader: None,
+            min_contrast: 7.0,
         }
     }
 }
 
+impl ThemeColors {
+    /// Color of the lines between split panes
+    pub fn divider_color(&self) -> RgbColor {
+        self.divider.unwrap_or(RgbColor::new(0x73, 0x73, 0x80))
+    }
+
+    /// Color of the hovered divider and the active pane's edge accent
+    pub fn border_accent_color(&self) -> RgbColor {
+        self.border_accent.unwrap_or(self.cursor)
+    }
+}
+
 impl Default for ThemeColors {
     fn default() -> Self {
         // iTerm2 default dark theme (Snazzy variant)
@@ -89,6 +301,53 @@ impl Default for ThemeColors {
                 RgbColor::new(0x9a, 0xec, 0xfe), // 14 bright cyan    #9aecfe
                 RgbColor::new(0xf1, 0xf1, 0xf0), // 15 bright white   #f1f1f0
             ],
+            divider: None,
+            border_accent: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn installed_and_loaded_from_dir() {
+        let dir = std::env::temp_dir().join(format!("pterminal-themes-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut file = Theme::default_light();
+        file.name = String::new();
+        file.colors.background = RgbColor::new(1, 2, 3);
+        std::fs::write(dir.join("paper.toml"), toml::to_string(&file).unwrap()).unwrap();
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+
+        let names = Theme::installed(&dir);
+        assert_eq!(names[..3], BUILTIN_THEMES.map(String::from));
+        assert_eq!(names[3..], ["paper".to_string()]);
+        let paper = Theme::load("paper", &dir).unwrap();
+        assert_eq!(paper.name, "paper");
+        assert_eq!(paper.colors.background, RgbColor::new(1, 2, 3));
+        let builtin = Theme::load("high-contrast", &dir).unwrap();
+        assert_eq!(builtin.name, "high-contrast");
+        assert!(Theme::load("missing", &dir).is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn text_is_raised_to_the_minimum_contrast() {
+        let bg = RgbColor::new(0x27, 0x29, 0x35);
+        let dim = RgbColor::new(0x40, 0x40, 0x48);
+        let raised = dim.with_contrast(bg, 4.5);
+        assert!(raised.contrast(bg) >= 4.5);
+        assert!(raised.contrast(bg) < 4.8, "moved further than needed");
+        assert!(raised.r > dim.r);
+
+        // Dark text on a light background gets darker
+        let light = RgbColor::new(0xf0, 0xf0, 0xf0);
+        let gray = RgbColor::new(0xb0, 0xb0, 0xb0);
+        assert!(gray.with_contrast(light, 7.0).r < gray.r);
+
+        let white = RgbColor::new(0xff, 0xff, 0xff);
+        assert_eq!(white.with_contrast(bg, 4.5), white);
+    }
+}
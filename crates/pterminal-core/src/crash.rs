@@ -0,0 +1,226 @@
+//! Panics in worker threads.
+//!
+//! [`install_hook`] writes every panic, with a backtrace, to a crash log in
+//! the crash directory. A pane's parser and PTY threads run their bodies
+//! under [`guard`], so one that panics leaves a [`Failure`] the UI shows on
+//! the pane, rather than a pane that silently stops updating.
+//!
+//! Nothing is sent anywhere: [`report_bundle`] gathers recent logs into one
+//! file, with the user's home and name scrubbed, for them to attach to a bug
+//! report if they choose.
+
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::fmt::Write as _;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::pane_log::timestamp;
+
+/// Crash logs a report bundle includes, newest first
+const MAX_BUNDLED_LOGS: usize = 10;
+
+/// Write a crash log to `dir` for every panic, then run the previous hook.
+pub fn install_hook(dir: PathBuf) {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let thread = std::thread::current();
+        let log = CrashLog {
+            thread: thread.name().unwrap_or("unnamed").to_string(),
+            message: panic_message(info.payload()),
+            location: info.location().map(ToString::to_string),
+            backtrace: Backtrace::force_capture().to_string(),
+        };
+        match log.write(&dir) {
+            Ok(path) => eprintln!("pterminal: crash log written to {}", path.display()),
+            Err(e) => eprintln!("pterminal: failed to write crash log: {e}"),
+        }
+        previous(info);
+    }));
+}
+
+/// One panic, as written to `crash-<unix time>-<thread>.log`
+struct CrashLog {
+    thread: String,
+    message: String,
+    location: Option<String>,
+    backtrace: String,
+}
+
+impl CrashLog {
+    fn write(&self, dir: &Path) -> io::Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let now = SystemTime::now();
+        let secs = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let path = dir.join(format!("crash-{secs}-{}.log", self.thread));
+        let mut text = String::new();
+        let _ = writeln!(text, "{}", environment());
+        let _ = writeln!(text, "time: {}", timestamp(now));
+        let _ = writeln!(text, "thread: {}", self.thread);
+        let _ = writeln!(text, "panic: {}", self.message);
+        if let Some(location) = &self.location {
+            let _ = writeln!(text, "at: {location}");
+        }
+        let _ = write!(text, "\nbacktrace:\n{}", self.backtrace);
+        std::fs::write(&path, text)?;
+        Ok(path)
+    }
+}
+
+/// "pterminal 0.1.0 (linux x86_64)"
+fn environment() -> String {
+    format!(
+        "pterminal {} ({} {})",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// Why a pane's worker thread stopped, once one has panicked
+#[derive(Debug, Clone, Default)]
+pub struct Failure(Arc<Mutex<Option<String>>>);
+
+impl Failure {
+    pub fn get(&self) -> Option<String> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn set(&self, reason: String) {
+        let mut slot = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        // The first panic is the cause; later ones follow from it
+        slot.get_or_insert(reason);
+    }
+}
+
+/// Run a worker thread's body, recording a panic in `failure` instead of
+/// unwinding out of the thread. The hook has logged it by then.
+pub fn guard(failure: &Failure, body: impl FnOnce()) {
+    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(body)) {
+        let thread = std::thread::current();
+        let name = thread.name().unwrap_or("worker");
+        failure.set(format!(
+            "{name} thread panicked: {}",
+            panic_message(&*payload)
+        ));
+    }
+}
+
+/// Gather the newest crash logs in `dir` into one file at `out`, with the
+/// home directory and user name scrubbed. Returns how many were included;
+/// with none, nothing is written.
+pub fn report_bundle(dir: &Path, out: &Path) -> io::Result<usize> {
+    let mut logs: Vec<PathBuf> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+            .collect(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e),
+    };
+    // Named by unix time, so this is newest first
+    logs.sort_by(|a, b| b.cmp(a));
+    logs.truncate(MAX_BUNDLED_LOGS);
+    if logs.is_empty() {
+        return Ok(0);
+    }
+
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok();
+    let mut bundle = format!(
+        "pterminal crash report\n{}\ncrash logs: {}\n",
+        environment(),
+        logs.len()
+    );
+    for path in &logs {
+        let text = std::fs::read_to_string(path)?;
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let _ = write!(bundle, "\n===== {name}\n{text}");
+    }
+    std::fs::write(out, anonymize(&bundle, home.as_deref(), user.as_deref()))?;
+    Ok(logs.len())
+}
+
+/// `text` with the home directory shown as `~` and the user name as
+/// `<user>`. Names under three characters are left, as they would match
+/// too much.
+pub fn anonymize(text: &str, home: Option<&Path>, user: Option<&str>) -> String {
+    let mut text = text.to_string();
+    if let Some(home) = home.and_then(Path::to_str).filter(|h| h.len() > 1) {
+        text = text.replace(home, "~");
+    }
+    if let Some(user) = user.filter(|u| u.len() >= 3) {
+        text = text.replace(user, "<user>");
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guard_records_the_first_panic() {
+        let failure = Failure::default();
+        guard(&failure, || {});
+        assert_eq!(failure.get(), None);
+
+        let worker = failure.clone();
+        std::thread::Builder::new()
+            .name("pty-reader".into())
+            .spawn(move || {
+                guard(&worker, || panic!("bad {}", 1));
+                guard(&worker, || panic!("later"));
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(
+            failure.get().as_deref(),
+            Some("pty-reader thread panicked: bad 1")
+        );
+    }
+
+    #[test]
+    fn bundle_scrubs_home_and_user() {
+        assert_eq!(
+            anonymize(
+                "at /home/alice/src/main.rs by alice",
+                Some(Path::new("/home/alice")),
+                Some("alice")
+            ),
+            "at ~/src/main.rs by <user>"
+        );
+        assert_eq!(anonymize("a b", None, Some("a")), "a b");
+
+        let dir = std::env::temp_dir().join(format!("pterminal-crash-{}", std::process::id()));
+        let log = CrashLog {
+            thread: "term-parser".into(),
+            message: "index out of bounds".into(),
+            location: Some("src/grid.rs:1:2".into()),
+            backtrace: "0: main".into(),
+        };
+        log.write(&dir).unwrap();
+        let out = dir.join("report.txt");
+        assert_eq!(report_bundle(&dir, &out).unwrap(), 1);
+        let bundle = std::fs::read_to_string(&out).unwrap();
+        assert!(bundle.contains("thread: term-parser"));
+        assert!(bundle.contains("panic: index out of bounds"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
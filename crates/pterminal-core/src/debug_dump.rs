@@ -0,0 +1,279 @@
+//! Snapshots of internal state for bug reports.
+//!
+//! The app gathers a [`StateDump`] of named text files (config, layout,
+//! pane sizes, recent log lines) and writes it as a zip the user can attach
+//! to an issue. The user's home and name are scrubbed from every file, and
+//! screen text goes through [`redact`] first so none of what was on screen
+//! leaves the machine, only its shape. So do the commands and text in the
+//! config, see [`redacted_config`].
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+
+use crate::config::keys;
+use crate::crash::anonymize;
+use crate::Config;
+
+/// Files for a state dump, in the order they're added
+#[derive(Debug, Default)]
+pub struct StateDump {
+    files: Vec<(String, String)>,
+    home: Option<PathBuf>,
+    user: Option<String>,
+}
+
+impl StateDump {
+    /// Scrubbing the current user's home and name
+    pub fn new() -> Self {
+        Self {
+            files: Vec::new(),
+            home: std::env::var_os("HOME").map(PathBuf::from),
+            user: std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .ok(),
+        }
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, text: &str) {
+        let text = anonymize(text, self.home.as_deref(), self.user.as_deref());
+        self.files.push((name.into(), text));
+    }
+
+    pub fn add_json(&mut self, name: impl Into<String>, value: &serde_json::Value) {
+        let text = serde_json::to_string_pretty(value).unwrap_or_default();
+        self.add(name, &text);
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.files.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// Write the files to a zip at `path`, stored uncompressed
+    pub fn write_zip(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut zip = ZipWriter::default();
+        for (name, text) in &self.files {
+            zip.add(name, text.as_bytes())?;
+        }
+        std::fs::write(path, zip.finish()?)
+    }
+}
+
+/// `state-<unix time>.zip` in the crash directory
+pub fn default_path() -> PathBuf {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    Config::crashes_dir().join(format!("state-{secs}.zip"))
+}
+
+/// Screen text with every letter shown as `x` and digit as `0`. Spacing,
+/// punctuation and line lengths are kept, which is usually what a layout
+/// or wrapping bug needs.
+pub fn redact(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_numeric() {
+                '0'
+            } else if c.is_alphabetic() {
+                'x'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Config options holding commands, typed text or environment values
+const PRIVATE_OPTIONS: &[&str] = &[
+    "snippets",
+    "hooks",
+    "startup",
+    "reconnect.commands",
+    "logging.auto",
+    "notification.mute_patterns",
+];
+
+/// The config as a dump shows it: every string in the options holding
+/// commands, snippets or environment values [`redact`]ed, and key bindings
+/// to anything but a built-in action too
+pub fn redacted_config(config: &Config) -> Value {
+    let mut value = serde_json::to_value(config).unwrap_or_default();
+    for option in PRIVATE_OPTIONS {
+        if let Some(private) = value.pointer_mut(&format!("/{}", option.replace('.', "/"))) {
+            redact_strings(private);
+        }
+    }
+    if let Some(Value::Object(bindings)) = value.get_mut("keybindings") {
+        for action in bindings.values_mut() {
+            if !action.as_str().is_some_and(keys::is_action) {
+                redact_strings(action);
+            }
+        }
+    }
+    value
+}
+
+fn redact_strings(value: &mut Value) {
+    match value {
+        Value::String(text) => *text = redact(text),
+        Value::Array(items) => items.iter_mut().for_each(redact_strings),
+        Value::Object(fields) => fields.values_mut().for_each(redact_strings),
+        _ => {}
+    }
+}
+
+/// Just enough of the zip format for a handful of small text files
+#[derive(Default)]
+struct ZipWriter {
+    out: Vec<u8>,
+    central: Vec<u8>,
+    entries: u16,
+}
+
+/// Version 2.0, as needed for plain stored files
+const ZIP_VERSION: u16 = 20;
+/// Names are UTF-8
+const ZIP_FLAGS: u16 = 0x0800;
+/// 1980-01-01 00:00 in DOS format; entries carry no real times
+const ZIP_DATE: u16 = 0x21;
+
+impl ZipWriter {
+    fn add(&mut self, name: &str, data: &[u8]) -> io::Result<()> {
+        let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "state dump too large");
+        let offset = u32::try_from(self.out.len()).map_err(|_| too_large())?;
+        let size = u32::try_from(data.len()).map_err(|_| too_large())?;
+        let name_len = u16::try_from(name.len()).map_err(|_| too_large())?;
+        let crc = crc32(data);
+
+        self.out.write_all(&0x0403_4b50u32.to_le_bytes())?;
+        for field in [ZIP_VERSION, ZIP_FLAGS, 0, 0, ZIP_DATE] {
+            self.out.write_all(&field.to_le_bytes())?;
+        }
+        for field in [crc, size, size] {
+            self.out.write_all(&field.to_le_bytes())?;
+        }
+        self.out.write_all(&name_len.to_le_bytes())?;
+        self.out.write_all(&0u16.to_le_bytes())?;
+        self.out.write_all(name.as_bytes())?;
+        self.out.write_all(data)?;
+
+        self.central.write_all(&0x0201_4b50u32.to_le_bytes())?;
+        for field in [ZIP_VERSION, ZIP_VERSION, ZIP_FLAGS, 0, 0, ZIP_DATE] {
+            self.central.write_all(&field.to_le_bytes())?;
+        }
+        for field in [crc, size, size] {
+            self.central.write_all(&field.to_le_bytes())?;
+        }
+        // Name length, then no extra field, comment, disk or attributes
+        for field in [name_len, 0, 0, 0, 0] {
+            self.central.write_all(&field.to_le_bytes())?;
+        }
+        self.central.write_all(&0u32.to_le_bytes())?;
+        self.central.write_all(&offset.to_le_bytes())?;
+        self.central.write_all(name.as_bytes())?;
+        self.entries = self.entries.checked_add(1).ok_or_else(too_large)?;
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<Vec<u8>> {
+        let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "state dump too large");
+        let offset = u32::try_from(self.out.len()).map_err(|_| too_large())?;
+        let size = u32::try_from(self.central.len()).map_err(|_| too_large())?;
+        self.out.append(&mut self.central);
+        self.out.write_all(&0x0605_4b50u32.to_le_bytes())?;
+        for field in [0, 0, self.entries, self.entries] {
+            self.out.write_all(&field.to_le_bytes())?;
+        }
+        self.out.write_all(&size.to_le_bytes())?;
+        self.out.write_all(&offset.to_le_bytes())?;
+        self.out.write_all(&0u16.to_le_bytes())?;
+        Ok(self.out)
+    }
+}
+
+/// CRC-32 as zip uses it (IEEE, reflected)
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_screen_text() {
+        assert_eq!(redact("$ ls -la ~/Ünï 42"), "$ xx -xx ~/xxx 00");
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn redacts_config_commands() {
+        let mut config = Config::default();
+        config.hooks.bell = "notify-send --token s3cret".into();
+        config.snippets.push(crate::snippet::Snippet {
+            name: "deploy".into(),
+            command: "deploy --password hunter2".into(),
+            description: String::new(),
+        });
+        let workspace = crate::workspace::layout::StartupWorkspace {
+            panes: vec![crate::workspace::layout::PaneLayout {
+                command: Some("API_KEY=abc123 serve".into()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        config.startup.workspaces.push(workspace);
+        config
+            .keybindings
+            .insert("super+k".into(), "kubectl".into());
+
+        let text = redacted_config(&config).to_string();
+        for secret in ["s3cret", "hunter2", "abc123", "kubectl"] {
+            assert!(!text.contains(secret), "{secret} in {text}");
+        }
+        let value = redacted_config(&config);
+        assert_eq!(value["keybindings"]["super+t"], "new-workspace");
+        assert_eq!(value["font"]["size"], config.font.size);
+    }
+
+    #[test]
+    fn writes_a_readable_zip() {
+        let mut dump = StateDump {
+            home: Some(PathBuf::from("/home/alice")),
+            user: Some("alice".into()),
+            ..StateDump::default()
+        };
+        dump.add("config.toml", "shell = \"/home/alice/bin/fish\"\n");
+        dump.add_json("panes.json", &serde_json::json!({ "cols": 80 }));
+        assert_eq!(dump.names(), ["config.toml", "panes.json"]);
+
+        let path = std::env::temp_dir().join(format!("pterminal-dump-{}.zip", std::process::id()));
+        dump.write_zip(&path).unwrap();
+        let zip = std::fs::read(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(&zip[..4], b"PK\x03\x04");
+        let eocd = &zip[zip.len() - 22..];
+        assert_eq!(&eocd[..4], b"PK\x05\x06");
+        assert_eq!(u16::from_le_bytes([eocd[10], eocd[11]]), 2);
+        let first = &zip[30..30 + "config.toml".len()];
+        assert_eq!(first, b"config.toml");
+        let text = String::from_utf8_lossy(&zip);
+        assert!(text.contains("shell = \"~/bin/fish\""));
+        assert!(!text.contains("alice"));
+    }
+}
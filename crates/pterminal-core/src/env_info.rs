@@ -0,0 +1,186 @@
+//! Detects the Python virtualenv / conda environment and direnv state of a pane.
+//!
+//! The Python environment comes from the `(name)` prefix that activation
+//! scripts add to the prompt (captured through OSC 133 marks), falling back to
+//! the shell's `VIRTUAL_ENV` / `CONDA_DEFAULT_ENV` variables.
+
+use std::path::{Path, PathBuf};
+
+use crate::shell_words::quote_path;
+
+/// An activated Python environment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PythonEnv {
+    pub name: String,
+    /// Root of the virtualenv; `None` means a conda env activated by name
+    pub venv: Option<PathBuf>,
+}
+
+impl PythonEnv {
+    /// Shell command that activates this environment in a new shell.
+    pub fn activation_command(&self) -> String {
+        match &self.venv {
+            Some(root) => format!("source {}", quote_path(&root.join("bin/activate"))),
+            None => format!("conda activate {}", self.name),
+        }
+    }
+}
+
+/// Environment state shown next to a pane
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PaneEnv {
+    pub python: Option<PythonEnv>,
+    /// `.envrc` direnv loads for the pane's cwd
+    pub direnv: Option<PathBuf>,
+}
+
+impl PaneEnv {
+    /// Resolve a pane's environment.
+    ///
+    /// `prompt` is the last prompt drawn (when shell integration reports one)
+    /// and `var` looks up the shell's environment variables.
+    pub fn detect(
+        prompt: Option<&str>,
+        cwd: Option<&Path>,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Self {
+        let python = match prompt {
+            Some(prompt) => prompt_env_name(prompt).map(|name| {
+                let venv = var("VIRTUAL_ENV")
+                    .map(PathBuf::from)
+                    .filter(|root| venv_name(root).as_deref() == Some(name.as_str()))
+                    .or_else(|| cwd.and_then(|cwd| find_venv(&name, cwd)));
+                PythonEnv { name, venv }
+            }),
+            None => env_from_vars(&var),
+        };
+        Self {
+            python,
+            direnv: cwd.and_then(find_envrc),
+        }
+    }
+
+    /// "venv:name", "conda:name" and/or "direnv", or empty
+    pub fn label(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(python) = &self.python {
+            let kind = if python.venv.is_some() {
+                "venv"
+            } else {
+                "conda"
+            };
+            parts.push(format!("{kind}:{}", python.name));
+        }
+        if self.direnv.is_some() {
+            parts.push("direnv".to_string());
+        }
+        parts.join(" · ")
+    }
+}
+
+fn env_from_vars(var: &impl Fn(&str) -> Option<String>) -> Option<PythonEnv> {
+    if let Some(root) = var("VIRTUAL_ENV")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+    {
+        let name = venv_name(&root)?;
+        return Some(PythonEnv {
+            name,
+            venv: Some(root),
+        });
+    }
+    let name = var("CONDA_DEFAULT_ENV").filter(|v| !v.is_empty())?;
+    Some(PythonEnv { name, venv: None })
+}
+
+/// Name in a leading `(name) ` prompt prefix, as added by venv and conda.
+pub fn prompt_env_name(prompt: &str) -> Option<String> {
+    let rest = prompt.trim_start().strip_prefix('(')?;
+    let (name, after) = rest.split_once(')')?;
+    let valid = !name.is_empty() && !name.contains(char::is_whitespace) && !name.contains('(');
+    (valid && after.starts_with(char::is_whitespace)).then(|| name.to_string())
+}
+
+/// Prompt name of a virtualenv: the `prompt` in `pyvenv.cfg`, else its directory name.
+fn venv_name(root: &Path) -> Option<String> {
+    let configured = std::fs::read_to_string(root.join("pyvenv.cfg"))
+        .ok()
+        .and_then(|cfg| {
+            cfg.lines().find_map(|line| {
+                let (key, value) = line.split_once('=')?;
+                (key.trim() == "prompt").then(|| value.trim().trim_matches('\'').to_string())
+            })
+        });
+    configured.or_else(|| Some(root.file_name()?.to_string_lossy().into_owned()))
+}
+
+/// Look for the virtualenv named `name` in `cwd` and its ancestors.
+pub fn find_venv(name: &str, cwd: &Path) -> Option<PathBuf> {
+    cwd.ancestors().find_map(|dir| {
+        [name, ".venv", "venv", "env"]
+            .into_iter()
+            .map(|candidate| dir.join(candidate))
+            .find(|root| {
+                root.join("bin/activate").is_file() && venv_name(root).as_deref() == Some(name)
+            })
+    })
+}
+
+/// Nearest `.envrc` at or above `cwd`.
+pub fn find_envrc(cwd: &Path) -> Option<PathBuf> {
+    cwd.ancestors()
+        .map(|dir| dir.join(".envrc"))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_prefix() {
+        assert_eq!(
+            prompt_env_name("(.venv) user@host:~$").as_deref(),
+            Some(".venv")
+        );
+        assert_eq!(prompt_env_name("(base) ➜  src").as_deref(), Some("base"));
+        assert_eq!(prompt_env_name("user@host:~$"), None);
+        assert_eq!(prompt_env_name("(main)$"), None);
+        assert_eq!(prompt_env_name("(a b) $"), None);
+    }
+
+    #[test]
+    fn detect_from_vars_and_files() {
+        let dir = std::env::temp_dir().join(format!("pterminal-env-{}", std::process::id()));
+        let venv = dir.join(".venv");
+        std::fs::create_dir_all(venv.join("bin")).unwrap();
+        std::fs::write(venv.join("bin/activate"), "").unwrap();
+        std::fs::write(
+            venv.join("pyvenv.cfg"),
+            "home = /usr/bin\nprompt = 'proj'\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join(".envrc"), "layout python\n").unwrap();
+        let sub = dir.join("src");
+        std::fs::create_dir_all(&sub).unwrap();
+
+        let env = PaneEnv::detect(Some("(proj) $"), Some(&sub), |_| None);
+        assert_eq!(
+            env.python.as_ref().and_then(|p| p.venv.clone()),
+            Some(venv.clone())
+        );
+        assert_eq!(env.direnv, Some(dir.join(".envrc")));
+        assert_eq!(env.label(), "venv:proj · direnv");
+
+        let conda = PaneEnv::detect(None, None, |key| {
+            (key == "CONDA_DEFAULT_ENV").then(|| "ml".to_string())
+        });
+        assert_eq!(conda.label(), "conda:ml");
+        assert_eq!(
+            conda.python.unwrap().activation_command(),
+            "conda activate ml"
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::terminal::Progress;
+
 /// Internal events for cross-module communication
 #[derive(Debug, Clone)]
 pub enum TermEvent {
@@ -5,6 +7,23 @@ pub enum TermEvent {
     TitleChanged(String),
     /// Bell received
     Bell,
+    /// A command run at the prompt finished (shell integration, OSC 133;D)
+    CommandFinished {
+        /// Command line, when it could be read from the screen
+        command: Option<String>,
+        exit_code: Option<i32>,
+    },
+    /// A program asked for a notification (OSC 9 or OSC 777)
+    Notify {
+        /// Only OSC 777 has one
+        title: Option<String>,
+        body: String,
+    },
+    /// Progress reported by the program (OSC 9;4), `None` once cleared
+    Progress(Option<Progress>),
+    /// Output is coming too fast to show and is being skipped: bytes
+    /// skipped so far, `None` once it has slowed down
+    FastForward(Option<u64>),
     /// Terminal exited
     Exited,
     /// Request redraw
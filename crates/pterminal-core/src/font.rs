@@ -0,0 +1,107 @@
+//! Resolving `font.family` against the installed monospace families.
+
+use crate::fuzzy::fuzzy_score;
+
+/// Tried in order when nothing configured resolves
+pub const FALLBACK_FAMILIES: &[&str] = &[
+    "Menlo",
+    "SF Mono",
+    "Monaco",
+    "DejaVu Sans Mono",
+    "Liberation Mono",
+    "Consolas",
+    "Courier New",
+];
+
+/// The family picked for `font.family` and the configured ones that failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontResolution {
+    /// Installed family to render with; `None` leaves the font database's
+    /// own default
+    pub family: Option<String>,
+    /// Configured families that are not installed (or not monospace)
+    pub missing: Vec<String>,
+}
+
+/// Pick a family from `installed` for the `wanted` list, in order:
+///
+/// 1. the first wanted family that is installed (ignoring case, spaces and
+///    dashes, so "JetBrainsMono" finds "JetBrains Mono"),
+/// 2. the nearest fuzzy match of each wanted family ("Fira Code" finds
+///    "FiraCode Nerd Font Mono"),
+/// 3. the first installed of [`FALLBACK_FAMILIES`].
+pub fn resolve(wanted: &[&str], installed: &[String]) -> FontResolution {
+    let keys: Vec<String> = installed.iter().map(|name| normalize(name)).collect();
+    let exact = |name: &str| {
+        let key = normalize(name);
+        keys.iter().position(|k| *k == key)
+    };
+
+    let mut missing = Vec::new();
+    for name in wanted {
+        match exact(name) {
+            Some(idx) => {
+                return FontResolution {
+                    family: Some(installed[idx].clone()),
+                    missing,
+                }
+            }
+            None => missing.push(name.to_string()),
+        }
+    }
+
+    let nearest = wanted.iter().find_map(|name| {
+        let key = normalize(name);
+        keys.iter()
+            .enumerate()
+            .filter_map(|(idx, k)| fuzzy_score(&key, k).map(|score| (idx, score)))
+            .max_by_key(|&(idx, score)| (score, std::cmp::Reverse(idx)))
+            .map(|(idx, _)| idx)
+    });
+    let family = nearest
+        .or_else(|| FALLBACK_FAMILIES.iter().find_map(|name| exact(name)))
+        .map(|idx| installed[idx].clone());
+    FontResolution { family, missing }
+}
+
+/// Lowercase with spaces, dashes and underscores dropped
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn installed() -> Vec<String> {
+        [
+            "DejaVu Sans Mono",
+            "FiraCode Nerd Font Mono",
+            "JetBrains Mono",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+
+    #[test]
+    fn resolution_order() {
+        let fonts = installed();
+        let exact = resolve(&["Iosevka", "jetbrainsmono"], &fonts);
+        assert_eq!(exact.family.as_deref(), Some("JetBrains Mono"));
+        assert_eq!(exact.missing, ["Iosevka"]);
+
+        let fuzzy = resolve(&["Fira Code"], &fonts);
+        assert_eq!(fuzzy.family.as_deref(), Some("FiraCode Nerd Font Mono"));
+        assert_eq!(fuzzy.missing, ["Fira Code"]);
+
+        let fallback = resolve(&["Iosevka"], &fonts);
+        assert_eq!(fallback.family.as_deref(), Some("DejaVu Sans Mono"));
+
+        let none = resolve(&["Iosevka"], &[]);
+        assert_eq!(none.family, None);
+        assert_eq!(none.missing, ["Iosevka"]);
+    }
+}
@@ -0,0 +1,74 @@
+//! Small fuzzy matcher for pickers (snippets, history).
+
+/// Score `candidate` against `query` as a case-insensitive subsequence match.
+///
+/// Returns `None` when the query characters do not all appear in order.
+/// Consecutive matches and matches at word starts score higher; an empty
+/// query matches everything with a score of 0.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    let mut score = 0;
+    let mut query_chars = query.chars().flat_map(char::to_lowercase).peekable();
+    let mut prev_matched = false;
+    let mut prev: Option<char> = None;
+
+    for c in candidate.chars() {
+        let Some(&want) = query_chars.peek() else {
+            break;
+        };
+        let matched = c.to_lowercase().eq(std::iter::once(want));
+        if matched {
+            query_chars.next();
+            score += 1;
+            if prev_matched {
+                score += 4;
+            }
+            if prev.map_or(true, |p| !p.is_alphanumeric()) {
+                score += 3;
+            }
+        }
+        prev_matched = matched;
+        prev = Some(c);
+    }
+
+    if query_chars.peek().is_some() {
+        return None;
+    }
+    // Prefer shorter candidates among equal matches
+    Some(score * 100 - candidate.chars().count().min(99) as i32)
+}
+
+/// Indices of `candidates` matching `query`, best match first. An empty query
+/// keeps every candidate in its original order.
+pub fn fuzzy_filter<'a>(query: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<usize> {
+    if query.is_empty() {
+        return (0..candidates.into_iter().count()).collect();
+    }
+    let mut scored: Vec<(usize, i32)> = candidates
+        .into_iter()
+        .enumerate()
+        .filter_map(|(idx, candidate)| fuzzy_score(query, candidate).map(|score| (idx, score)))
+        .collect();
+    // Stable sort keeps the original order among equal scores
+    scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    scored.into_iter().map(|(idx, _)| idx).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subsequence_match() {
+        assert!(fuzzy_score("kgp", "kubectl get pods").is_some());
+        assert!(fuzzy_score("KGP", "kubectl get pods").is_some());
+        assert!(fuzzy_score("pgk", "kubectl get pods").is_none());
+        assert_eq!(fuzzy_score("", "anything"), Some(-8));
+    }
+
+    #[test]
+    fn filter_ranks_word_starts_first() {
+        let candidates = ["git stash pop", "ssh prod", "display"];
+        assert_eq!(fuzzy_filter("sp", candidates), vec![1, 0, 2]);
+        assert_eq!(fuzzy_filter("", candidates), vec![0, 1, 2]);
+    }
+}
@@ -0,0 +1,263 @@
+//! User commands run on lifecycle events (`[hooks]`), for automation that
+//! does not need a plugin.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use tracing::{debug, warn};
+
+use crate::config::HooksConfig;
+use crate::split::PaneId;
+use crate::workspace::WorkspaceId;
+
+/// A pane's bells, or its finished commands, closer together than this
+/// run their hook once
+const REPEAT_INTERVAL: Duration = Duration::from_secs(1);
+/// Hooks running at once; events past it are dropped
+const MAX_RUNNING: usize = 8;
+/// Events waiting for the worker; events past it are dropped
+const QUEUE_LEN: usize = 64;
+/// How often the worker reaps hooks that finished
+const REAP_INTERVAL: Duration = Duration::from_millis(200);
+
+/// When a hook runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookEvent {
+    /// The app finished opening its first workspaces
+    Startup,
+    PaneSpawned,
+    /// A pane's shell ended
+    PaneExited,
+    WorkspaceCreated,
+    Bell,
+    /// A command run at the prompt finished (needs shell integration)
+    CommandFinished,
+}
+
+impl HookEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Startup => "startup",
+            Self::PaneSpawned => "pane-spawned",
+            Self::PaneExited => "pane-exited",
+            Self::WorkspaceCreated => "workspace-created",
+            Self::Bell => "bell",
+            Self::CommandFinished => "command-finished",
+        }
+    }
+}
+
+/// What a hook is told about its event, as environment variables
+#[derive(Debug, Clone, Default)]
+pub struct HookContext {
+    pub pane_id: Option<PaneId>,
+    pub workspace_id: Option<WorkspaceId>,
+    pub cwd: Option<PathBuf>,
+    pub exit_code: Option<i32>,
+    /// Command line that finished (`command-finished`)
+    pub command: Option<String>,
+}
+
+impl HookContext {
+    /// `PTERMINAL_EVENT`, plus `PANE_ID`, `WORKSPACE_ID`, `CWD`, `EXIT_CODE`
+    /// and `COMMAND` when known
+    pub fn env(&self, event: HookEvent) -> Vec<(&'static str, String)> {
+        let mut env = vec![("PTERMINAL_EVENT", event.name().to_string())];
+        if let Some(id) = self.pane_id {
+            env.push(("PANE_ID", id.to_string()));
+        }
+        if let Some(id) = self.workspace_id {
+            env.push(("WORKSPACE_ID", id.to_string()));
+        }
+        if let Some(cwd) = &self.cwd {
+            env.push(("CWD", cwd.display().to_string()));
+        }
+        if let Some(code) = self.exit_code {
+            env.push(("EXIT_CODE", code.to_string()));
+        }
+        if let Some(command) = &self.command {
+            env.push(("COMMAND", command.clone()));
+        }
+        env
+    }
+}
+
+/// Run the hook configured for `event`, if any, in the background. Hooks
+/// start on a worker thread, which drops repeats and events past its limits;
+/// failures to start them are logged.
+pub fn run(hooks: &HooksConfig, event: HookEvent, context: HookContext) {
+    static WORKER: OnceLock<Option<SyncSender<Job>>> = OnceLock::new();
+    let Some(command) = hooks.command(event) else {
+        return;
+    };
+    let Some(worker) = WORKER.get_or_init(start_worker) else {
+        return;
+    };
+    let job = Job {
+        command: command.to_string(),
+        event,
+        context,
+    };
+    match worker.try_send(job) {
+        Ok(()) => {}
+        Err(TrySendError::Full(_)) => debug!(event = event.name(), "Hook queue full, dropped"),
+        Err(TrySendError::Disconnected(_)) => warn!(event = event.name(), "Hook worker is gone"),
+    }
+}
+
+struct Job {
+    command: String,
+    event: HookEvent,
+    context: HookContext,
+}
+
+fn start_worker() -> Option<SyncSender<Job>> {
+    let (sender, jobs) = mpsc::sync_channel(QUEUE_LEN);
+    match std::thread::Builder::new()
+        .name("hooks".into())
+        .spawn(move || work(jobs))
+    {
+        Ok(_) => Some(sender),
+        Err(e) => {
+            warn!("Hook worker failed to start: {e}");
+            None
+        }
+    }
+}
+
+/// Start hooks as they come in and reap those that finished
+fn work(jobs: Receiver<Job>) {
+    let mut repeats = RepeatFilter::default();
+    let mut running: Vec<Child> = Vec::new();
+    loop {
+        let job = if running.is_empty() {
+            jobs.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            jobs.recv_timeout(REAP_INTERVAL)
+        };
+        running.retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+        let job = match job {
+            Ok(job) => job,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        let Job {
+            command,
+            event,
+            context,
+        } = job;
+        if !repeats.allow(event, &context, Instant::now()) {
+            continue;
+        }
+        if running.len() >= MAX_RUNNING {
+            debug!(event = event.name(), "Too many hooks running, dropped");
+            continue;
+        }
+        match spawn(&command, event, &context) {
+            Ok(child) => running.push(child),
+            Err(e) => warn!(event = event.name(), "Hook failed to start: {e}"),
+        }
+    }
+    for mut child in running {
+        let _ = child.wait();
+    }
+}
+
+/// Drops bells and finished commands that repeat within
+/// [`REPEAT_INTERVAL`] of the last run for the same pane
+#[derive(Default)]
+struct RepeatFilter {
+    last_run: HashMap<(HookEvent, Option<PaneId>), Instant>,
+}
+
+impl RepeatFilter {
+    fn allow(&mut self, event: HookEvent, context: &HookContext, now: Instant) -> bool {
+        if !matches!(event, HookEvent::Bell | HookEvent::CommandFinished) {
+            return true;
+        }
+        let key = (event, context.pane_id);
+        if let Some(last) = self.last_run.get(&key) {
+            if now.duration_since(*last) < REPEAT_INTERVAL {
+                return false;
+            }
+        }
+        self.last_run.insert(key, now);
+        true
+    }
+}
+
+/// Start `command` through the system shell with the event's environment,
+/// in the event's cwd. Output is discarded.
+fn spawn(command: &str, event: HookEvent, context: &HookContext) -> Result<Child> {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    } else {
+        let mut cmd = Command::new("/bin/sh");
+        cmd.args(["-c", command]);
+        cmd
+    };
+    cmd.envs(context.env(event))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if let Some(cwd) = context.cwd.as_ref().filter(|cwd| cwd.is_dir()) {
+        cmd.current_dir(cwd);
+    }
+    Ok(cmd.spawn()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_env() {
+        let context = HookContext {
+            pane_id: Some(3),
+            cwd: Some(PathBuf::from("/tmp")),
+            exit_code: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            context.env(HookEvent::PaneExited),
+            [
+                ("PTERMINAL_EVENT", "pane-exited".to_string()),
+                ("PANE_ID", "3".to_string()),
+                ("CWD", "/tmp".to_string()),
+                ("EXIT_CODE", "1".to_string()),
+            ]
+        );
+
+        let hooks = HooksConfig {
+            bell: "true".into(),
+            ..Default::default()
+        };
+        assert_eq!(hooks.command(HookEvent::Bell), Some("true"));
+        assert_eq!(hooks.command(HookEvent::Startup), None);
+    }
+
+    #[test]
+    fn repeats_are_dropped_per_pane() {
+        let mut repeats = RepeatFilter::default();
+        let pane = |id| HookContext {
+            pane_id: Some(id),
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let soon = start + Duration::from_millis(100);
+        assert!(repeats.allow(HookEvent::Bell, &pane(1), start));
+        assert!(!repeats.allow(HookEvent::Bell, &pane(1), soon));
+        assert!(repeats.allow(HookEvent::Bell, &pane(2), soon));
+        assert!(repeats.allow(HookEvent::CommandFinished, &pane(1), soon));
+        assert!(repeats.allow(HookEvent::PaneExited, &pane(1), soon));
+        assert!(repeats.allow(HookEvent::PaneExited, &pane(1), soon));
+        assert!(repeats.allow(HookEvent::Bell, &pane(1), start + REPEAT_INTERVAL));
+    }
+}
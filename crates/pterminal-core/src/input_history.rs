@@ -0,0 +1,118 @@
+//! Lines typed or sent into a pane, reconstructed from the input bytes.
+//!
+//! Unlike shell history this also covers REPLs and programs that keep no
+//! history of their own. Editing beyond backspace (cursor keys, completion) is
+//! not tracked, so a line is only as accurate as the keys that produced it.
+
+use std::collections::VecDeque;
+
+/// Lines kept per pane
+const DEFAULT_CAPACITY: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    None,
+    /// After ESC
+    Start,
+    /// Inside a CSI (`ESC [`) or SS3 (`ESC O`) sequence
+    Sequence,
+}
+
+/// Bounded history of submitted input lines, oldest first
+#[derive(Debug, Clone)]
+pub struct InputHistory {
+    lines: VecDeque<String>,
+    capacity: usize,
+    /// Line being typed
+    pending: String,
+    escape: Escape,
+}
+
+impl Default for InputHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl InputHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            capacity: capacity.max(1),
+            pending: String::new(),
+            escape: Escape::None,
+        }
+    }
+
+    /// Feed bytes written to the pane.
+    pub fn record(&mut self, bytes: &[u8]) {
+        for ch in String::from_utf8_lossy(bytes).chars() {
+            match self.escape {
+                Escape::Start => {
+                    self.escape = match ch {
+                        '[' | 'O' => Escape::Sequence,
+                        _ => Escape::None,
+                    };
+                    continue;
+                }
+                Escape::Sequence => {
+                    if ('\x40'..='\x7e').contains(&ch) {
+                        self.escape = Escape::None;
+                    }
+                    continue;
+                }
+                Escape::None => {}
+            }
+            match ch {
+                '\r' | '\n' => self.submit(),
+                '\x1b' => self.escape = Escape::Start,
+                '\x7f' | '\x08' => {
+                    self.pending.pop();
+                }
+                // Ctrl+C, Ctrl+U: the line is discarded
+                '\x03' | '\x15' => self.pending.clear(),
+                c if c.is_control() && c != '\t' => {}
+                c => self.pending.push(c),
+            }
+        }
+    }
+
+    fn submit(&mut self) {
+        let line = std::mem::take(&mut self.pending);
+        let line = line.trim();
+        if line.is_empty() || self.lines.back().is_some_and(|last| last == line) {
+            return;
+        }
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line.to_string());
+    }
+
+    /// Submitted lines, oldest first
+    pub fn lines(&self) -> impl Iterator<Item = &str> + '_ {
+        self.lines.iter().map(String::as_str)
+    }
+
+    /// Most recently submitted line
+    pub fn last(&self) -> Option<&str> {
+        self.lines.back().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconstructs_lines() {
+        let mut history = InputHistory::new(2);
+        history.record(b"cargo tset\x7f\x7f\x7fest\r");
+        history.record(b"rm -rf /\x15ls\x1b[A\x1bOB\r");
+        history.record(b"ls\r\r");
+        assert_eq!(history.lines().collect::<Vec<_>>(), ["cargo test", "ls"]);
+        history.record("print('é')\n".as_bytes());
+        assert_eq!(history.lines().collect::<Vec<_>>(), ["ls", "print('é')"]);
+        assert_eq!(history.last(), Some("print('é')"));
+    }
+}
@@ -0,0 +1,242 @@
+//! Shortcuts matched by physical key, whatever the keyboard layout.
+//!
+//! Keys are named by their W3C `code`, which is where the key sits on a US
+//! keyboard ("BracketLeft" is the key right of P). Shortcuts written with
+//! punctuation or digits ("super+[", "super+1") stay on the key that types
+//! it in the US layout, so they still work on German and French keyboards
+//! where "[" takes AltGr and digits take Shift. Letters follow the letter
+//! the layout types, as in other apps (undo is on the Z key of a QWERTZ
+//! keyboard), except on layouts without Latin letters, such as Russian,
+//! where they fall back to the key's position too.
+//!
+//! The known layouts' tables name keys the way the keyboard labels them,
+//! for showing shortcuts.
+
+/// Keys of the main block, in the order layouts list their characters
+const CODES: [&str; 48] = [
+    "Backquote",
+    "Digit1",
+    "Digit2",
+    "Digit3",
+    "Digit4",
+    "Digit5",
+    "Digit6",
+    "Digit7",
+    "Digit8",
+    "Digit9",
+    "Digit0",
+    "Minus",
+    "Equal",
+    "KeyQ",
+    "KeyW",
+    "KeyE",
+    "KeyR",
+    "KeyT",
+    "KeyY",
+    "KeyU",
+    "KeyI",
+    "KeyO",
+    "KeyP",
+    "BracketLeft",
+    "BracketRight",
+    "Backslash",
+    "KeyA",
+    "KeyS",
+    "KeyD",
+    "KeyF",
+    "KeyG",
+    "KeyH",
+    "KeyJ",
+    "KeyK",
+    "KeyL",
+    "Semicolon",
+    "Quote",
+    "IntlBackslash",
+    "KeyZ",
+    "KeyX",
+    "KeyC",
+    "KeyV",
+    "KeyB",
+    "KeyN",
+    "KeyM",
+    "Comma",
+    "Period",
+    "Slash",
+];
+
+/// A keyboard layout: what each key of the main block types without
+/// modifiers
+#[derive(Debug, PartialEq, Eq)]
+pub struct KeyboardLayout {
+    pub name: &'static str,
+    /// One character per key of `CODES`
+    chars: &'static str,
+}
+
+impl KeyboardLayout {
+    pub const US: Self = Self {
+        name: "us",
+        chars: "`1234567890-=qwertyuiop[]\\asdfghjkl;'\\zxcvbnm,./",
+    };
+    /// German QWERTZ
+    pub const DE: Self = Self {
+        name: "de",
+        chars: "^1234567890ß´qwertzuiopü+#asdfghjklöä<yxcvbnm,.-",
+    };
+    /// French AZERTY
+    pub const FR: Self = Self {
+        name: "fr",
+        chars: "²&é\"'(-è_çà)=azertyuiop^$*qsdfghjklmù<wxcvbn,;:!",
+    };
+    /// Russian ЙЦУКЕН
+    pub const RU: Self = Self {
+        name: "ru",
+        chars: "ё1234567890-=йцукенгшщзхъ\\фывапролджэ\\ячсмитьбю.",
+    };
+
+    /// Known layouts, US first
+    pub const ALL: &'static [Self] = &[Self::US, Self::DE, Self::FR, Self::RU];
+
+    /// What the key `code` types without modifiers
+    pub fn char_for(&self, code: &str) -> Option<char> {
+        let index = CODES.iter().position(|c| *c == code)?;
+        self.chars.chars().nth(index)
+    }
+
+    /// The key that types `ch` without modifiers
+    fn code_for(&self, ch: char) -> Option<&'static str> {
+        let index = self.chars.chars().position(|c| c == ch)?;
+        Some(CODES[index])
+    }
+
+    /// The known layout typing most of the characters seen on their keys,
+    /// as `(code, character)` pairs from key presses; US on a tie
+    pub fn detect<'a>(seen: impl IntoIterator<Item = (&'a str, char)> + Clone) -> &'static Self {
+        let mut best = &Self::ALL[0];
+        let mut best_score = 0;
+        for layout in Self::ALL {
+            let score = seen
+                .clone()
+                .into_iter()
+                .filter(|(code, ch)| layout.char_for(code) == ch.to_lowercase().next())
+                .count();
+            if score > best_score {
+                best = layout;
+                best_score = score;
+            }
+        }
+        best
+    }
+
+    /// A key binding with its key named as this layout labels it, e.g.
+    /// "super+ü" for "super+[" on a German keyboard. Letters, digits and
+    /// named keys are shown as written.
+    pub fn display_binding(&self, keys: &str) -> String {
+        let (modifiers, key) = match keys.strip_suffix("++") {
+            Some(modifiers) => (modifiers, "+"),
+            None => keys.rsplit_once('+').unwrap_or(("", keys)),
+        };
+        let mut chars = key.chars();
+        let (Some(ch), None) = (chars.next(), chars.next()) else {
+            return keys.to_string();
+        };
+        if ch.is_ascii_alphanumeric() {
+            return keys.to_string();
+        }
+        let shown = Self::US
+            .code_for(ch)
+            .and_then(|code| self.char_for(code))
+            .unwrap_or(ch);
+        if modifiers.is_empty() {
+            shown.to_string()
+        } else {
+            format!("{modifiers}+{shown}")
+        }
+    }
+}
+
+/// The character a key press matches shortcuts by. `typed` is what the
+/// layout typed (a control character with Ctrl held on some platforms) and
+/// `code` the physical key, when known. Latin letters are matched as
+/// typed, other keys of the main block by the US character on that key;
+/// anything else is `typed` unchanged.
+pub fn shortcut_char(code: Option<&str>, typed: char) -> char {
+    let Some(us) = code.and_then(|code| KeyboardLayout::US.char_for(code)) else {
+        return typed;
+    };
+    let letter = match typed as u32 {
+        // Ctrl+letter
+        1..=26 => (b'a' + typed as u8 - 1) as char,
+        _ => typed.to_ascii_lowercase(),
+    };
+    if letter.is_ascii_alphabetic() {
+        letter
+    } else {
+        us
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layouts_cover_every_key() {
+        for layout in KeyboardLayout::ALL {
+            assert_eq!(layout.chars.chars().count(), CODES.len(), "{}", layout.name);
+        }
+    }
+
+    #[test]
+    fn punctuation_and_digits_match_by_position() {
+        // German: the Ü key is "[" on a US keyboard
+        assert_eq!(shortcut_char(Some("BracketLeft"), 'ü'), '[');
+        assert_eq!(shortcut_char(Some("BracketRight"), '+'), ']');
+        // French: digits take Shift, the unshifted 1 key types "&"
+        assert_eq!(shortcut_char(Some("Digit1"), '&'), '1');
+        assert_eq!(shortcut_char(Some("BracketLeft"), '^'), '[');
+        // Russian letters fall back to the US letter on the key
+        assert_eq!(shortcut_char(Some("KeyC"), 'с'), 'c');
+        assert_eq!(shortcut_char(Some("Comma"), 'б'), ',');
+    }
+
+    #[test]
+    fn latin_letters_match_as_typed() {
+        // QWERTZ and AZERTY move letters; shortcuts follow the letter
+        assert_eq!(shortcut_char(Some("KeyY"), 'z'), 'z');
+        assert_eq!(shortcut_char(Some("KeyQ"), 'A'), 'a');
+        assert_eq!(shortcut_char(Some("KeyA"), '\u{11}'), 'q');
+        // Keys outside the main block, or unknown ones, are left alone
+        assert_eq!(shortcut_char(Some("Enter"), '\n'), '\n');
+        assert_eq!(shortcut_char(None, 'ü'), 'ü');
+    }
+
+    #[test]
+    fn bindings_shown_in_the_layout() {
+        assert_eq!(KeyboardLayout::DE.display_binding("super+["), "super+ü");
+        assert_eq!(
+            KeyboardLayout::FR.display_binding("ctrl+shift+/"),
+            "ctrl+shift+!"
+        );
+        assert_eq!(KeyboardLayout::RU.display_binding("super+."), "super+ю");
+        assert_eq!(KeyboardLayout::RU.display_binding("super+t"), "super+t");
+        assert_eq!(KeyboardLayout::DE.display_binding("ctrl+-"), "ctrl+ß");
+        assert_eq!(KeyboardLayout::DE.display_binding("ctrl++"), "ctrl++");
+        assert_eq!(KeyboardLayout::US.display_binding("super+["), "super+[");
+    }
+
+    #[test]
+    fn layout_detected_from_key_presses() {
+        let german = [("KeyY", 'z'), ("BracketLeft", 'ü'), ("KeyA", 'a')];
+        assert_eq!(KeyboardLayout::detect(german), &KeyboardLayout::DE);
+        let french = [("KeyQ", 'a'), ("Digit2", 'é'), ("KeyM", ',')];
+        assert_eq!(KeyboardLayout::detect(french), &KeyboardLayout::FR);
+        let russian = [("KeyQ", 'Й'), ("KeyC", 'с')];
+        assert_eq!(KeyboardLayout::detect(russian), &KeyboardLayout::RU);
+        assert_eq!(
+            KeyboardLayout::detect(std::iter::empty()),
+            &KeyboardLayout::US
+        );
+        assert_eq!(KeyboardLayout::detect([("KeyA", 'a')]), &KeyboardLayout::US);
+    }
+}
@@ -1,13 +1,41 @@
+pub mod accessibility;
+pub mod appearance;
+pub mod bidi;
+pub mod color_vision;
 pub mod config;
+pub mod crash;
+pub mod debug_dump;
+pub mod env_info;
 pub mod event;
+pub mod font;
+pub mod fuzzy;
 pub mod git_info;
+pub mod hooks;
+pub mod input_history;
+pub mod keyboard_layout;
+pub mod link;
+pub mod log_ring;
 pub mod notification;
+pub mod pane_log;
 pub mod port_scanner;
+pub mod power;
+pub mod prompt_time;
+pub mod process_tree;
+pub mod reconnect;
+pub mod rich_text;
+pub mod scale;
+pub mod shell_history;
+pub mod shell_setup;
+pub mod shell_words;
+pub mod snippet;
 pub mod split;
+pub mod ssh;
+pub mod status_bar;
 pub mod terminal;
+pub mod update;
 pub mod workspace;
 
 pub use config::Config;
-pub use notification::{Notification, NotificationStore};
-pub use split::{PaneId, PaneRect, SplitDirection, SplitTree};
+pub use notification::{Notification, NotificationRules, NotificationSource, NotificationStore};
+pub use split::{DropSide, PaneId, PaneRect, SplitDirection, SplitTree};
 pub use workspace::{Workspace, WorkspaceId, WorkspaceManager};
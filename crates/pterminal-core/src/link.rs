@@ -0,0 +1,138 @@
+//! URL detection in terminal lines, for hover underlines.
+
+use crate::terminal::GridLine;
+
+/// Schemes a link starts with
+const SCHEMES: [&str; 6] = [
+    "https://", "http://", "file://", "ftp://", "ssh://", "mailto:",
+];
+
+/// A URL on one grid line, by cell column (`end` exclusive)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub start: u16,
+    pub end: u16,
+    pub url: String,
+}
+
+/// Links on a grid line, left to right. Links wrapped onto the next line
+/// end at the line's edge.
+pub fn find_links(line: &GridLine) -> Vec<Link> {
+    // One char per cell, with the column it starts at
+    let cells: Vec<(u16, char)> = line
+        .cells
+        .iter()
+        .enumerate()
+        .filter(|(_, cell)| !cell.wide_spacer)
+        .map(|(col, cell)| (col as u16, cell.c))
+        .collect();
+    let text: Vec<char> = cells.iter().map(|&(_, c)| c).collect();
+
+    let mut links = Vec::new();
+    let mut i = 0;
+    while i < text.len() {
+        let at_boundary = i == 0 || !text[i - 1].is_alphanumeric();
+        let Some(scheme) = SCHEMES
+            .iter()
+            .find(|scheme| at_boundary && starts_with(&text[i..], scheme))
+        else {
+            i += 1;
+            continue;
+        };
+        let mut end = i + scheme.len();
+        while end < text.len() && is_url_char(text[end]) {
+            end += 1;
+        }
+        end = trim_url_end(&text[i..end]) + i;
+        if end > i + scheme.len() {
+            let last = cells[end - 1].0;
+            let wide = line
+                .cells
+                .get(last as usize + 1)
+                .is_some_and(|c| c.wide_spacer);
+            links.push(Link {
+                start: cells[i].0,
+                end: last + 1 + u16::from(wide),
+                url: text[i..end].iter().collect(),
+            });
+        }
+        i = end.max(i + 1);
+    }
+    links
+}
+
+/// The link covering column `col`, if any
+pub fn link_at(line: &GridLine, col: u16) -> Option<Link> {
+    find_links(line)
+        .into_iter()
+        .find(|link| (link.start..link.end).contains(&col))
+}
+
+fn starts_with(text: &[char], prefix: &str) -> bool {
+    let mut chars = text.iter();
+    prefix.chars().all(|p| chars.next() == Some(&p))
+}
+
+fn is_url_char(c: char) -> bool {
+    !c.is_whitespace() && !c.is_control() && !matches!(c, '<' | '>' | '"' | '\'' | '`')
+}
+
+/// Length of `url` without trailing punctuation and unbalanced closing
+/// brackets, so "(see https://x.org/a)." links just "https://x.org/a".
+fn trim_url_end(url: &[char]) -> usize {
+    let mut end = url.len();
+    while end > 0 {
+        let c = url[end - 1];
+        let unbalanced = |open: char, close: char| {
+            c == close && {
+                let opened = url[..end].iter().filter(|&&c| c == open).count();
+                let closed = url[..end].iter().filter(|&&c| c == close).count();
+                closed > opened
+            }
+        };
+        let trailing = matches!(c, '.' | ',' | ';' | ':' | '!' | '?');
+        if trailing || unbalanced('(', ')') || unbalanced('[', ']') || unbalanced('{', '}') {
+            end -= 1;
+        } else {
+            break;
+        }
+    }
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::theme::RgbColor;
+    use crate::terminal::GridCell;
+
+    fn line(text: &str) -> GridLine {
+        let cell = |c| GridCell {
+            c,
+            fg: RgbColor::new(0, 0, 0),
+            bg: RgbColor::new(0, 0, 0),
+            bold: false,
+            italic: false,
+            underline: false,
+            wide_spacer: false,
+        };
+        GridLine {
+            cells: text.chars().map(cell).collect(),
+        }
+    }
+
+    #[test]
+    fn finds_urls() {
+        let links = find_links(&line("see (https://example.org/a_(b)), or http://x.io."));
+        let urls: Vec<&str> = links.iter().map(|l| l.url.as_str()).collect();
+        assert_eq!(urls, ["https://example.org/a_(b)", "http://x.io"]);
+        assert_eq!((links[0].start, links[0].end), (5, 30));
+
+        assert_eq!(
+            link_at(&line("go https://a.b now"), 3).unwrap().url,
+            "https://a.b"
+        );
+        assert_eq!(link_at(&line("go https://a.b now"), 14), None);
+        assert!(find_links(&line("xhttps://a.b https://")).is_empty());
+    }
+}
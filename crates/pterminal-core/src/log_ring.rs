@@ -0,0 +1,159 @@
+//! The most recent log lines, kept in memory alongside stderr.
+//!
+//! GUI users never see stderr, so [`RingLayer`] copies every event the
+//! tracing subscriber lets through into a bounded ring that the log viewer
+//! and state dumps read back.
+
+use std::collections::VecDeque;
+use std::fmt::{self, Write as _};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+use crate::pane_log::timestamp;
+
+/// Lines kept before the oldest are dropped
+pub const CAPACITY: usize = 2000;
+
+/// One logged event
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub time: SystemTime,
+    pub level: Level,
+    /// Module that logged it, e.g. "pterminal_ipc::server"
+    pub target: String,
+    /// The message followed by any other fields as `key=value`
+    pub message: String,
+}
+
+/// "2026-10-16T09:30:05.120Z  WARN pterminal_ipc::server: message"
+impl fmt::Display for LogLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:>5} {}: {}",
+            timestamp(self.time),
+            self.level,
+            self.target,
+            self.message
+        )
+    }
+}
+
+/// Bounded buffer of recent [`LogLine`]s
+#[derive(Debug, Default)]
+pub struct LogRing {
+    lines: Mutex<VecDeque<LogLine>>,
+    pushed: AtomicU64,
+}
+
+impl LogRing {
+    pub fn push(&self, line: LogLine) {
+        let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        if lines.len() == CAPACITY {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+        self.pushed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Lines pushed so far, dropped ones included; changes with every line
+    pub fn pushed(&self) -> u64 {
+        self.pushed.load(Ordering::Relaxed)
+    }
+
+    /// Lines kept, oldest first
+    pub fn lines(&self) -> Vec<LogLine> {
+        let lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        lines.iter().cloned().collect()
+    }
+}
+
+/// The ring [`RingLayer`] fills
+pub fn global() -> &'static LogRing {
+    static RING: OnceLock<LogRing> = OnceLock::new();
+    RING.get_or_init(LogRing::default)
+}
+
+/// Subscriber layer copying events into [`global`]
+pub struct RingLayer;
+
+impl<S: Subscriber> Layer<S> for RingLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let metadata = event.metadata();
+        global().push(LogLine {
+            time: SystemTime::now(),
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            message: fields.text,
+        });
+    }
+}
+
+/// An event's fields as one line, message first
+#[derive(Default)]
+struct Fields {
+    text: String,
+}
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let fields = std::mem::take(&mut self.text);
+            let _ = write!(self.text, "{value:?}{fields}");
+        } else {
+            let _ = write!(self.text, " {}={value:?}", field.name());
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.record_debug(field, &format_args!("{value}"));
+        } else {
+            let _ = write!(self.text, " {}={value}", field.name());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn keeps_recent_events() {
+        let subscriber = tracing_subscriber::registry().with(RingLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(pane_id = 3, path = "/tmp/x", "log {} failed", "rotation");
+        });
+        let line = global()
+            .lines()
+            .into_iter()
+            .rfind(|line| line.target == module_path!())
+            .unwrap();
+        assert_eq!(line.level, Level::WARN);
+        assert_eq!(line.message, "log rotation failed pane_id=3 path=/tmp/x");
+        assert!(line.to_string().ends_with(
+            " WARN pterminal_core::log_ring::tests: log rotation failed pane_id=3 path=/tmp/x"
+        ));
+
+        let ring = LogRing::default();
+        for i in 0..CAPACITY + 5 {
+            ring.push(LogLine {
+                message: i.to_string(),
+                ..line.clone()
+            });
+        }
+        let lines = ring.lines();
+        assert_eq!(lines.len(), CAPACITY);
+        assert_eq!(lines[0].message, "5");
+        assert_eq!(ring.pushed(), CAPACITY as u64 + 5);
+    }
+}
@@ -1,3 +1,5 @@
+mod rules;
 mod store;
 
+pub use rules::{local_minute, parse_quiet_hours, NotificationRules, NotificationSource};
 pub use store::{Notification, NotificationStore};
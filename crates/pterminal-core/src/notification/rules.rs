@@ -0,0 +1,218 @@
+//! Which notifications ask for attention, for the `[notification]` rules.
+//!
+//! Do not disturb, switched on or during quiet hours, silences every
+//! notification from panes and IPC; muted sources and patterns silence the
+//! ones they match. A silenced notification is still listed, already read,
+//! so nothing is lost. pterminal's own messages about what was just done
+//! always get through.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use regex_automata::meta::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::config::NotificationConfig;
+use crate::prompt_time;
+
+/// Where a notification came from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationSource {
+    /// pterminal itself, e.g. "Timestamp copied"
+    #[default]
+    App,
+    Bell,
+    /// A command finished (needs shell integration)
+    Command,
+    /// A program asked with OSC 9 or OSC 777
+    Osc,
+    /// `notification.send` over IPC
+    Ipc,
+}
+
+impl NotificationSource {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::App => "app",
+            Self::Bell => "bell",
+            Self::Command => "command",
+            Self::Osc => "osc",
+            Self::Ipc => "ipc",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [Self::App, Self::Bell, Self::Command, Self::Osc, Self::Ipc]
+            .into_iter()
+            .find(|source| source.name() == name)
+    }
+}
+
+/// The rules of a [`NotificationConfig`], ready to apply
+#[derive(Debug, Default)]
+pub struct NotificationRules {
+    do_not_disturb: bool,
+    /// Minutes into the local day when quiet hours start and end
+    quiet_hours: Option<(u32, u32)>,
+    muted_sources: Vec<NotificationSource>,
+    patterns: Vec<Regex>,
+}
+
+impl NotificationRules {
+    /// Quiet hours, sources and patterns that don't parse are left out,
+    /// with a warning
+    pub fn new(config: &NotificationConfig) -> Self {
+        let (rules, problems) = Self::parse(config);
+        for problem in problems {
+            warn!("notification rule not used: {problem}");
+        }
+        rules
+    }
+
+    /// Like [`new`](Self::new), but fails on the first rule that doesn't
+    /// parse
+    pub fn strict(config: &NotificationConfig) -> Result<Self, String> {
+        match Self::parse(config) {
+            (rules, problems) if problems.is_empty() => Ok(rules),
+            (_, problems) => Err(problems.join("; ")),
+        }
+    }
+
+    fn parse(config: &NotificationConfig) -> (Self, Vec<String>) {
+        let mut problems = Vec::new();
+        let quiet_hours = match config.quiet_hours.trim() {
+            "" => None,
+            text => {
+                let hours = parse_quiet_hours(text);
+                if hours.is_none() {
+                    problems.push(format!("quiet hours {text:?}, expected HH:MM-HH:MM"));
+                }
+                hours
+            }
+        };
+        let mut muted_sources = Vec::new();
+        for name in &config.muted_sources {
+            match NotificationSource::from_name(name) {
+                Some(source) => muted_sources.push(source),
+                None => problems.push(format!("unknown source {name:?}")),
+            }
+        }
+        let mut patterns = Vec::new();
+        for pattern in &config.mute_patterns {
+            match Regex::new(pattern) {
+                Ok(regex) => patterns.push(regex),
+                Err(e) => problems.push(format!("pattern {pattern:?}: {e}")),
+            }
+        }
+        let rules = Self {
+            do_not_disturb: config.do_not_disturb,
+            quiet_hours,
+            muted_sources,
+            patterns,
+        };
+        (rules, problems)
+    }
+
+    /// Do not disturb is on at `minute` of the local day
+    pub fn quiet(&self, minute: u32) -> bool {
+        self.do_not_disturb
+            || self.quiet_hours.is_some_and(|(start, end)| {
+                if start <= end {
+                    (start..end).contains(&minute)
+                } else {
+                    // Across midnight
+                    minute >= start || minute < end
+                }
+            })
+    }
+
+    /// Whether a notification from `source` is silenced at `minute` of the
+    /// local day
+    pub fn silences(
+        &self,
+        source: NotificationSource,
+        title: &str,
+        body: &str,
+        minute: u32,
+    ) -> bool {
+        if source == NotificationSource::App {
+            return false;
+        }
+        self.quiet(minute)
+            || self.muted_sources.contains(&source)
+            || self
+                .patterns
+                .iter()
+                .any(|pattern| pattern.is_match(title) || pattern.is_match(body))
+    }
+
+    /// [`silences`](Self::silences) at the current local time
+    pub fn silences_now(&self, source: NotificationSource, title: &str, body: &str) -> bool {
+        let now = SystemTime::now();
+        let minute = local_minute(now, prompt_time::local_offset(now));
+        self.silences(source, title, body, minute)
+    }
+
+    /// [`quiet`](Self::quiet) at the current local time
+    pub fn quiet_now(&self) -> bool {
+        let now = SystemTime::now();
+        self.quiet(local_minute(now, prompt_time::local_offset(now)))
+    }
+}
+
+/// "22:00-07:30" as minutes into the day
+pub fn parse_quiet_hours(text: &str) -> Option<(u32, u32)> {
+    let (start, end) = text.split_once('-')?;
+    Some((parse_clock(start)?, parse_clock(end)?))
+}
+
+fn parse_clock(text: &str) -> Option<u32> {
+    let (hours, minutes) = text.trim().split_once(':')?;
+    let (hours, minutes): (u32, u32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+/// Minutes into the local day at `time`, `offset` seconds from UTC
+pub fn local_minute(time: SystemTime, offset: i64) -> u32 {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+        + offset;
+    (secs.rem_euclid(86_400) / 60) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn rules_silence_what_they_match() {
+        let config = NotificationConfig {
+            quiet_hours: "22:00-07:30".into(),
+            muted_sources: vec!["bell".into(), "chime".into()],
+            mute_patterns: vec!["^npm (WARN|notice)".into(), "(".into()],
+            ..Default::default()
+        };
+        assert!(NotificationRules::strict(&config).is_err());
+        let rules = NotificationRules::new(&config);
+        let noon = 12 * 60;
+        assert!(rules.silences(NotificationSource::Bell, "Bell", "vim", noon));
+        assert!(rules.silences(NotificationSource::Osc, "npm notice", "", noon));
+        assert!(!rules.silences(NotificationSource::Osc, "Build done", "", noon));
+        // Quiet hours run across midnight
+        assert!(rules.silences(NotificationSource::Ipc, "Deploy", "", 23 * 60));
+        assert!(rules.silences(NotificationSource::Ipc, "Deploy", "", 7 * 60));
+        assert!(!rules.silences(NotificationSource::Ipc, "Deploy", "", 7 * 60 + 30));
+        // pterminal's own messages always get through
+        assert!(!rules.silences(NotificationSource::App, "Bell", "", 23 * 60));
+
+        assert_eq!(parse_quiet_hours("9:00-17:00"), Some((540, 1020)));
+        assert_eq!(parse_quiet_hours("25:00-07:00"), None);
+        let time = UNIX_EPOCH + Duration::from_secs(86_400 + 3600);
+        assert_eq!(local_minute(time, -2 * 3600), 23 * 60);
+    }
+}
@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use super::NotificationSource;
+use crate::split::PaneId;
+use crate::workspace::WorkspaceId;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Notification {
     pub id: u64,
@@ -8,6 +12,14 @@ pub struct Notification {
     pub body: String,
     pub created_at_ms: u128,
     pub read: bool,
+    #[serde(default)]
+    pub source: NotificationSource,
+    /// Workspace and pane it came from (a bell, a finished command), which
+    /// jumping to it focuses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_id: Option<WorkspaceId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pane_id: Option<PaneId>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -25,18 +37,52 @@ impl NotificationStore {
     }
 
     pub fn push(&mut self, title: impl Into<String>, body: impl Into<String>) -> Notification {
+        self.push_from(title, body, NotificationSource::App, None, false)
+    }
+
+    /// Push a notification from `source`, from a pane in a workspace when
+    /// `origin` is given. A silenced one is stored already read.
+    pub fn push_from(
+        &mut self,
+        title: impl Into<String>,
+        body: impl Into<String>,
+        source: NotificationSource,
+        origin: Option<(WorkspaceId, PaneId)>,
+        silenced: bool,
+    ) -> Notification {
         let notification = Notification {
             id: self.next_id,
             title: title.into(),
             body: body.into(),
             created_at_ms: now_ms(),
-            read: false,
+            read: silenced,
+            source,
+            workspace_id: origin.map(|(workspace_id, _)| workspace_id),
+            pane_id: origin.map(|(_, pane_id)| pane_id),
         };
         self.next_id += 1;
         self.items.push(notification.clone());
         notification
     }
 
+    pub fn get(&self, id: u64) -> Option<&Notification> {
+        self.items.iter().find(|n| n.id == id)
+    }
+
+    /// The newest unread notification that came from a pane
+    pub fn latest_alert(&self) -> Option<&Notification> {
+        self.items
+            .iter()
+            .rev()
+            .find(|n| !n.read && n.pane_id.is_some())
+    }
+
+    pub fn mark_read(&mut self, id: u64) {
+        if let Some(item) = self.items.iter_mut().find(|n| n.id == id) {
+            item.read = true;
+        }
+    }
+
     pub fn list(&self) -> &[Notification] {
         &self.items
     }
@@ -62,3 +108,47 @@ fn now_ms() -> u128 {
         .map(|d| d.as_millis())
         .unwrap_or(0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latest_alert_is_the_newest_unread_from_a_pane() {
+        let mut store = NotificationStore::new();
+        assert!(store.latest_alert().is_none());
+        let bell = store.push_from(
+            "Bell",
+            "Pane 2",
+            NotificationSource::Bell,
+            Some((1, 2)),
+            false,
+        );
+        let finished = store.push_from(
+            "Command finished",
+            "make",
+            NotificationSource::Command,
+            Some((3, 5)),
+            false,
+        );
+        store.push("Layout exported", "~/layout.json");
+        assert_eq!(store.latest_alert().map(|n| n.id), Some(finished.id));
+        assert_eq!(finished.workspace_id, Some(3));
+
+        // Silenced: listed, but not an alert
+        store.push_from(
+            "Bell",
+            "Pane 4",
+            NotificationSource::Bell,
+            Some((1, 4)),
+            true,
+        );
+        assert_eq!(store.latest_alert().map(|n| n.id), Some(finished.id));
+        assert_eq!(store.unread_count(), 3);
+
+        store.mark_read(finished.id);
+        assert_eq!(store.latest_alert().map(|n| n.id), Some(bell.id));
+        store.mark_all_read();
+        assert!(store.latest_alert().is_none());
+    }
+}
@@ -0,0 +1,440 @@
+//! Teeing a pane's PTY output to a file, raw or with escape sequences
+//! stripped, rotated by size. Lines can be timestamped, and progress bars
+//! redrawn with CR collapsed to what they finally showed.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::split::PaneId;
+
+/// What a log file holds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Text only: escape sequences and control characters removed
+    #[default]
+    #[serde(alias = "stripped")]
+    Plain,
+    /// Bytes exactly as the PTY sent them, replayable with `cat`
+    Raw,
+}
+
+impl LogFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Plain => "plain",
+            Self::Raw => "raw",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "plain" | "stripped" => Some(Self::Plain),
+            "raw" => Some(Self::Raw),
+            _ => None,
+        }
+    }
+}
+
+/// How output is rewritten on its way into a log
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LogOptions {
+    pub format: LogFormat,
+    /// Prefix each line with the UTC time it was logged
+    pub timestamps: bool,
+    /// Keep only the final state of lines redrawn with CR (plain logs only)
+    pub collapse_progress: bool,
+}
+
+/// `<dir>/pane-<id>-<unix time>.log`, for logs started without a path
+pub fn default_path(dir: &Path, pane_id: PaneId) -> PathBuf {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    dir.join(format!("pane-{pane_id}-{secs}.log"))
+}
+
+/// "2026-10-16T09:30:05.120Z", for line timestamps
+pub fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// `text` with each line prefixed by `time`, as in timestamped logs
+pub fn timestamp_lines(text: &str, time: SystemTime) -> String {
+    let prefix = format!("[{}] ", timestamp(time));
+    let lines: Vec<String> = text
+        .split('\n')
+        .map(|line| format!("{prefix}{line}"))
+        .collect();
+    lines.join("\n")
+}
+
+/// Gregorian date of a day count since 1970-01-01 (Howard Hinnant's
+/// `civil_from_days`)
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// An open pane log
+pub struct PaneLog {
+    path: PathBuf,
+    options: LogOptions,
+    /// Rotate once the file reaches this size (0 = never)
+    max_bytes: u64,
+    /// Rotated files kept as `<path>.1` (newest) to `<path>.<keep>`
+    keep: usize,
+    file: File,
+    written: u64,
+    stripper: AnsiStripper,
+    lines: LineBuffer,
+}
+
+impl PaneLog {
+    /// Open `path` for appending, creating its directory.
+    pub fn open(path: &Path, options: LogOptions, max_bytes: u64, keep: usize) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("creating {}", parent.display()))?;
+        }
+        let file = open_append(path)?;
+        let written = file.metadata().map(|m| m.len()).unwrap_or(0);
+        // Collapsing works on cells, which escape sequences would shift
+        let collapse = options.collapse_progress && options.format == LogFormat::Plain;
+        Ok(Self {
+            path: path.to_path_buf(),
+            options,
+            max_bytes,
+            keep,
+            file,
+            written,
+            stripper: AnsiStripper {
+                keep_cr: collapse,
+                ..Default::default()
+            },
+            lines: LineBuffer {
+                timestamps: options.timestamps,
+                collapse,
+                ..Default::default()
+            },
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn options(&self) -> LogOptions {
+        self.options
+    }
+
+    /// Append a chunk of PTY output, rotating first if the file is full.
+    pub fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        if self.max_bytes > 0 && self.written >= self.max_bytes {
+            self.rotate()?;
+        }
+        let stripped;
+        let bytes = match self.options.format {
+            LogFormat::Raw => bytes,
+            LogFormat::Plain => {
+                stripped = self.stripper.strip(bytes);
+                &stripped
+            }
+        };
+        let out = self.lines.feed(bytes, SystemTime::now());
+        self.write_out(&out)
+    }
+
+    fn write_out(&mut self, bytes: &[u8]) -> Result<()> {
+        self.file.write_all(bytes)?;
+        self.written += bytes.len() as u64;
+        Ok(())
+    }
+
+    /// Shift `<path>.N` to `<path>.N+1`, dropping the oldest, and start a
+    /// fresh file.
+    fn rotate(&mut self) -> Result<()> {
+        self.file.flush()?;
+        let numbered = |n: usize| {
+            let mut name = self.path.clone().into_os_string();
+            name.push(format!(".{n}"));
+            PathBuf::from(name)
+        };
+        if self.keep == 0 {
+            let _ = std::fs::remove_file(&self.path);
+        } else {
+            let _ = std::fs::remove_file(numbered(self.keep));
+            for n in (1..self.keep).rev() {
+                let _ = std::fs::rename(numbered(n), numbered(n + 1));
+            }
+            std::fs::rename(&self.path, numbered(1))?;
+        }
+        self.file = open_append(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Drop for PaneLog {
+    /// Write out a line still being collapsed
+    fn drop(&mut self) {
+        let rest = self.lines.finish(SystemTime::now());
+        if !rest.is_empty() {
+            let _ = self.write_out(&rest);
+        }
+    }
+}
+
+/// Open `path` for appending, creating it readable only by the current user:
+/// a pane's output can hold anything typed or shown in it
+fn open_append(path: &Path) -> Result<File> {
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .with_context(|| format!("opening {}", path.display()))
+}
+
+/// Removes escape sequences from a byte stream. Sequences may be split
+/// across chunks, so the parser state carries over between calls.
+#[derive(Debug, Default)]
+struct AnsiStripper {
+    state: StripState,
+    /// Pass CR through, for collapsing redrawn lines
+    keep_cr: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum StripState {
+    #[default]
+    Ground,
+    /// After ESC
+    Escape,
+    /// After ESC followed by an intermediate byte (`ESC ( B`)
+    EscapeIntermediate,
+    /// In a CSI sequence, until its final byte
+    Csi,
+    /// In an OSC, DCS, SOS, PM or APC string, until BEL or ST
+    String,
+    /// ESC inside a string: ST if followed by `\`
+    StringEscape,
+}
+
+impl AnsiStripper {
+    fn strip(&mut self, bytes: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(bytes.len());
+        for &b in bytes {
+            self.state = match (self.state, b) {
+                (StripState::Ground, 0x1b) => StripState::Escape,
+                (StripState::Ground, b'\n' | b'\t') => {
+                    out.push(b);
+                    StripState::Ground
+                }
+                (StripState::Ground, b'\r') if self.keep_cr => {
+                    out.push(b);
+                    StripState::Ground
+                }
+                // Other controls (CR, BS, BEL, ...) only move the cursor
+                (StripState::Ground, 0x00..=0x1f | 0x7f) => StripState::Ground,
+                (StripState::Ground, _) => {
+                    out.push(b);
+                    StripState::Ground
+                }
+                (StripState::Escape, b'[') => StripState::Csi,
+                (StripState::Escape, b']' | b'P' | b'X' | b'^' | b'_') => StripState::String,
+                (StripState::Escape, 0x20..=0x2f) => StripState::EscapeIntermediate,
+                (StripState::Escape | StripState::EscapeIntermediate, _) => StripState::Ground,
+                (StripState::Csi, 0x40..=0x7e) => StripState::Ground,
+                (StripState::Csi, _) => StripState::Csi,
+                (StripState::String, 0x07) => StripState::Ground,
+                (StripState::String, 0x1b) => StripState::StringEscape,
+                (StripState::String, _) => StripState::String,
+                (StripState::StringEscape, b'\\') => StripState::Ground,
+                (StripState::StringEscape, _) => StripState::String,
+            };
+        }
+        out
+    }
+}
+
+/// Splits output into lines to timestamp them and collapse redraws
+#[derive(Debug, Default)]
+struct LineBuffer {
+    timestamps: bool,
+    collapse: bool,
+    /// Something was written since the last newline
+    mid_line: bool,
+    /// The line being redrawn, and where the next char lands (collapse only)
+    line: Vec<char>,
+    col: usize,
+    /// Start of a UTF-8 sequence split across chunks (collapse only)
+    partial: Vec<u8>,
+}
+
+impl LineBuffer {
+    fn feed(&mut self, bytes: &[u8], now: SystemTime) -> Vec<u8> {
+        if !self.timestamps && !self.collapse {
+            return bytes.to_vec();
+        }
+        let mut out = Vec::with_capacity(bytes.len());
+        if !self.collapse {
+            for &b in bytes {
+                if !self.mid_line && self.timestamps {
+                    out.extend(format!("[{}] ", timestamp(now)).bytes());
+                }
+                out.push(b);
+                self.mid_line = b != b'\n';
+            }
+            return out;
+        }
+
+        self.partial.extend_from_slice(bytes);
+        // Hold back a UTF-8 sequence cut off at the end of the chunk
+        let complete = match std::str::from_utf8(&self.partial) {
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            _ => self.partial.len(),
+        };
+        let text = String::from_utf8_lossy(&self.partial[..complete]).into_owned();
+        self.partial.drain(..complete);
+        self.collapse_text(&text, now, out)
+    }
+
+    fn collapse_text(&mut self, text: &str, now: SystemTime, mut out: Vec<u8>) -> Vec<u8> {
+        for c in text.chars() {
+            match c {
+                '\r' => self.col = 0,
+                '\n' => {
+                    self.end_line(now, &mut out);
+                    out.push(b'\n');
+                }
+                _ if self.col < self.line.len() => {
+                    self.line[self.col] = c;
+                    self.col += 1;
+                }
+                _ => {
+                    self.line.push(c);
+                    self.col += 1;
+                }
+            }
+        }
+        out
+    }
+
+    fn end_line(&mut self, now: SystemTime, out: &mut Vec<u8>) {
+        if self.timestamps {
+            out.extend(format!("[{}] ", timestamp(now)).bytes());
+        }
+        let line: String = self.line.drain(..).collect();
+        out.extend(line.bytes());
+        self.col = 0;
+    }
+
+    /// The unfinished line, if one is held back
+    fn finish(&mut self, now: SystemTime) -> Vec<u8> {
+        let mut out = Vec::new();
+        if self.collapse && !self.line.is_empty() {
+            self.end_line(now, &mut out);
+            out.push(b'\n');
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_sequences_across_chunks() {
+        let mut stripper = AnsiStripper::default();
+        let mut out = stripper.strip(b"\x1b[1;3");
+        out.extend(stripper.strip(b"1mred\x1b[0m\r\n\x1b]0;ti"));
+        out.extend(stripper.strip(b"tle\x07\x1b(Bok\x1b]8;;x\x1b\\!\n"));
+        assert_eq!(String::from_utf8(out).unwrap(), "red\nok!\n");
+    }
+
+    #[test]
+    fn timestamps_and_collapsed_progress() {
+        let time = UNIX_EPOCH + std::time::Duration::from_millis(1_760_607_005_120);
+        assert_eq!(timestamp(time), "2025-10-16T09:30:05.120Z");
+        assert_eq!(timestamp(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+
+        let mut lines = LineBuffer {
+            timestamps: true,
+            ..Default::default()
+        };
+        let out = [lines.feed(b"a\nb", time), lines.feed(b"c\n", time)].concat();
+        let stamp = "[2025-10-16T09:30:05.120Z] ";
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!("{stamp}a\n{stamp}bc\n")
+        );
+
+        let mut lines = LineBuffer {
+            collapse: true,
+            ..Default::default()
+        };
+        let bar = "10% ██\r100% ████ done\r50".as_bytes();
+        // Split inside a multi-byte char
+        let mut out = lines.feed(&bar[..8], time);
+        out.extend(lines.feed(&bar[8..], time));
+        out.extend(lines.feed(b"\nnext", time));
+        assert_eq!(String::from_utf8(out).unwrap(), "500% ████ done\n");
+        assert_eq!(lines.finish(time), b"next\n");
+    }
+
+    #[test]
+    fn rotates_by_size() {
+        let dir = std::env::temp_dir().join(format!("pterminal-log-{}", std::process::id()));
+        let path = dir.join("pane.log");
+        let options = LogOptions {
+            format: LogFormat::Raw,
+            ..Default::default()
+        };
+        let mut log = PaneLog::open(&path, options, 4, 2).unwrap();
+        for chunk in ["aaaa", "bbbb", "cccc", "dd"] {
+            log.write(chunk.as_bytes()).unwrap();
+        }
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("pane.log"), "dd");
+        assert_eq!(read("pane.log.1"), "cccc");
+        assert_eq!(read("pane.log.2"), "bbbb");
+        assert!(!dir.join("pane.log.3").exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
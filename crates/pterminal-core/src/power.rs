@@ -0,0 +1,121 @@
+//! Power saving ("eco") mode, for `[power]`.
+//!
+//! Eco mode is process-wide: the UI caps the frame rate and turns off
+//! animations and effects, and worker threads poll [`idle_park`] times
+//! longer when they have nothing to do. New input and output still wake them
+//! at once, so typing latency is unaffected.
+
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// How much longer idle worker threads sleep in eco mode
+const ECO_PARK_FACTOR: u32 = 20;
+
+static ECO: AtomicBool = AtomicBool::new(false);
+
+pub fn set_eco(on: bool) {
+    ECO.store(on, Ordering::Relaxed);
+}
+
+pub fn eco() -> bool {
+    ECO.load(Ordering::Relaxed)
+}
+
+/// How long an idle worker thread parks: `base`, stretched in eco mode
+pub fn idle_park(base: Duration) -> Duration {
+    if eco() {
+        base * ECO_PARK_FACTOR
+    } else {
+        base
+    }
+}
+
+/// Time between frames at `max_fps`, at least one per second
+pub fn frame_interval(max_fps: u32) -> Duration {
+    Duration::from_secs(1) / max_fps.max(1)
+}
+
+/// Whether the machine runs on battery; false when it can't be told.
+///
+/// macOS asks `pmset`; Linux reads the power supplies in sysfs.
+pub fn on_battery() -> bool {
+    if cfg!(target_os = "macos") {
+        Command::new("pmset")
+            .args(["-g", "batt"])
+            .output()
+            .is_ok_and(|output| pmset_on_battery(&String::from_utf8_lossy(&output.stdout)))
+    } else if cfg!(target_os = "linux") {
+        sysfs_on_battery(Path::new("/sys/class/power_supply"))
+    } else {
+        false
+    }
+}
+
+/// "Now drawing from 'Battery Power'" on the first line of `pmset -g batt`
+fn pmset_on_battery(output: &str) -> bool {
+    output
+        .lines()
+        .next()
+        .is_some_and(|line| line.contains("'Battery Power'"))
+}
+
+/// On battery when there's a system battery and no mains or USB supply is
+/// online. Batteries of peripherals (mice, headsets) have `scope = Device`.
+fn sysfs_on_battery(dir: &Path) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return false;
+    };
+    let read = |supply: &Path, name: &str| {
+        std::fs::read_to_string(supply.join(name))
+            .map(|text| text.trim().to_string())
+            .unwrap_or_default()
+    };
+    let mut battery = false;
+    for supply in entries.filter_map(|entry| entry.ok().map(|e| e.path())) {
+        match read(&supply, "type").as_str() {
+            "Mains" | "USB" if read(&supply, "online") == "1" => return false,
+            "Battery" if read(&supply, "scope") != "Device" => battery = true,
+            _ => {}
+        }
+    }
+    battery
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_power_sources() {
+        assert!(pmset_on_battery(
+            "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=1)\t81%; discharging"
+        ));
+        assert!(!pmset_on_battery("Now drawing from 'AC Power'\n"));
+
+        let dir = std::env::temp_dir().join(format!("pterminal-power-{}", std::process::id()));
+        let supply = |name: &str, files: &[(&str, &str)]| {
+            let path = dir.join(name);
+            std::fs::create_dir_all(&path).unwrap();
+            for (file, text) in files {
+                std::fs::write(path.join(file), format!("{text}\n")).unwrap();
+            }
+        };
+        assert!(!sysfs_on_battery(&dir));
+        supply(
+            "hidpp_battery_0",
+            &[("type", "Battery"), ("scope", "Device")],
+        );
+        assert!(!sysfs_on_battery(&dir));
+        supply("BAT0", &[("type", "Battery"), ("status", "Discharging")]);
+        supply("AC", &[("type", "Mains"), ("online", "0")]);
+        assert!(sysfs_on_battery(&dir));
+        supply("AC", &[("type", "Mains"), ("online", "1")]);
+        assert!(!sysfs_on_battery(&dir));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(frame_interval(30), Duration::from_secs(1) / 30);
+        assert_eq!(frame_interval(0), Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,139 @@
+//! Process tree of a pane's shell, with CPU/memory usage and signalling.
+//!
+//! Uses `ps` and `kill`, which behave the same on Linux and macOS.
+
+use std::process::Command;
+
+use anyhow::{bail, Result};
+
+/// One process from a `ps` snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub ppid: u32,
+    /// CPU usage in percent of one core
+    pub cpu: f32,
+    /// Resident memory in KiB
+    pub rss_kb: u64,
+    pub command: String,
+}
+
+/// Signals offered for stuck processes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    Int,
+    Term,
+    Kill,
+}
+
+impl Signal {
+    pub const ALL: [Signal; 3] = [Signal::Int, Signal::Term, Signal::Kill];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Int => "INT",
+            Self::Term => "TERM",
+            Self::Kill => "KILL",
+        }
+    }
+
+    /// Parse "INT", "SIGINT" or "sigint".
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_uppercase();
+        let name = name.strip_prefix("SIG").unwrap_or(&name);
+        Self::ALL.into_iter().find(|s| s.name() == name)
+    }
+}
+
+/// All processes on the system; empty where `ps` is unavailable.
+pub fn snapshot() -> Vec<ProcessInfo> {
+    let output = Command::new("ps")
+        .args(["-A", "-o", "pid=,ppid=,pcpu=,rss=,args="])
+        .output();
+    match output {
+        Ok(output) if output.status.success() => parse_ps(&String::from_utf8_lossy(&output.stdout)),
+        _ => Vec::new(),
+    }
+}
+
+fn parse_ps(output: &str) -> Vec<ProcessInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid = fields.next()?.parse().ok()?;
+            let ppid = fields.next()?.parse().ok()?;
+            let cpu = fields.next()?.parse().ok()?;
+            let rss_kb = fields.next()?.parse().ok()?;
+            let command = fields.collect::<Vec<_>>().join(" ");
+            Some(ProcessInfo {
+                pid,
+                ppid,
+                cpu,
+                rss_kb,
+                command,
+            })
+        })
+        .collect()
+}
+
+/// `root` and its descendants in tree order, each with its depth below `root`.
+pub fn tree(processes: &[ProcessInfo], root: u32) -> Vec<(usize, ProcessInfo)> {
+    let mut out = Vec::new();
+    let Some(root) = processes.iter().find(|p| p.pid == root) else {
+        return out;
+    };
+    let mut stack = vec![(0, root)];
+    while let Some((depth, process)) = stack.pop() {
+        out.push((depth, process.clone()));
+        // Reversed so children come off the stack in pid order
+        let mut children: Vec<_> = processes
+            .iter()
+            .filter(|p| p.ppid == process.pid && p.pid != process.pid)
+            .collect();
+        children.sort_by_key(|p| std::cmp::Reverse(p.pid));
+        stack.extend(children.into_iter().map(|child| (depth + 1, child)));
+    }
+    out
+}
+
+/// Send `signal` to `pid`.
+pub fn send_signal(pid: u32, signal: Signal) -> Result<()> {
+    let status = Command::new("kill")
+        .args(["-s", signal.name(), &pid.to_string()])
+        .status()?;
+    if !status.success() {
+        bail!("kill -s {} {pid} failed", signal.name());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_build_tree() {
+        let output = "    1     0  0.0  1024 /sbin/init\n\
+                      100     1  0.5  4096 -zsh\n\
+                      250   100 97.3 81920 cargo test --workspace\n\
+                      120   100  0.0  2048 tail -f log\n\
+                      251   250  1.0   512 rustc\n";
+        let processes = parse_ps(output);
+        assert_eq!(processes.len(), 5);
+        assert_eq!(processes[2].command, "cargo test --workspace");
+        let tree: Vec<_> = tree(&processes, 100)
+            .into_iter()
+            .map(|(depth, p)| (depth, p.pid))
+            .collect();
+        assert_eq!(tree, vec![(0, 100), (1, 120), (1, 250), (2, 251)]);
+        assert!(super::tree(&processes, 999).is_empty());
+    }
+
+    #[test]
+    fn signal_names() {
+        assert_eq!(Signal::from_name("sigterm"), Some(Signal::Term));
+        assert_eq!(Signal::from_name("KILL"), Some(Signal::Kill));
+        assert_eq!(Signal::from_name("HUP"), None);
+    }
+}
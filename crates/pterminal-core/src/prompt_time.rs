@@ -0,0 +1,112 @@
+//! When commands ran, for the prompt timestamps gutter (`[prompt_times]`).
+//!
+//! Shell integration (OSC 133) tells when a command line is accepted and
+//! when it finishes; the emulator keeps a [`PromptTime`] for each, and the
+//! UI shows it beside the command's line either as an age ("3m ago") or as
+//! local wall-clock time.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Local};
+
+use crate::pane_log::civil_from_days;
+
+/// When a command ran
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PromptTime {
+    /// When the command line was accepted (OSC 133;C)
+    pub started: SystemTime,
+    /// How long it ran, once it finished (OSC 133;D)
+    pub duration: Option<Duration>,
+    pub exit_code: Option<i32>,
+}
+
+impl PromptTime {
+    pub fn new(started: SystemTime) -> Self {
+        Self {
+            started,
+            duration: None,
+            exit_code: None,
+        }
+    }
+
+    /// Gutter label, "relative" (an age) or "absolute" (time of day)
+    pub fn label(&self, format: &str, now: SystemTime) -> String {
+        match format {
+            "absolute" => clock_time(self.started, local_offset(self.started)),
+            _ => age(self.started, now),
+        }
+    }
+}
+
+/// How long ago `time` was, e.g. "now", "42s ago", "3m ago", "2d ago"
+pub fn age(time: SystemTime, now: SystemTime) -> String {
+    let secs = now.duration_since(time).unwrap_or_default().as_secs();
+    match secs {
+        0..=4 => "now".into(),
+        5..=59 => format!("{secs}s ago"),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86_399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86_400),
+    }
+}
+
+/// Time of day, "HH:MM:SS", at `offset` seconds from UTC
+pub fn clock_time(time: SystemTime, offset: i64) -> String {
+    let (_, secs) = local_days(time, offset);
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Date and time with the UTC offset, e.g. "2026-10-16 14:03:12 +0200", as
+/// copied from the gutter
+pub fn full_time(time: SystemTime, offset: i64) -> String {
+    let (days, _) = local_days(time, offset);
+    let (year, month, day) = civil_from_days(days);
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset_minutes = offset.abs() / 60;
+    format!(
+        "{year:04}-{month:02}-{day:02} {} {sign}{:02}{:02}",
+        clock_time(time, offset),
+        offset_minutes / 60,
+        offset_minutes % 60
+    )
+}
+
+/// Day count since 1970-01-01 and seconds into that day
+fn local_days(time: SystemTime, offset: i64) -> (i64, i64) {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+        + offset;
+    (secs.div_euclid(86_400), secs.rem_euclid(86_400))
+}
+
+/// The local time zone's offset from UTC in seconds at `time`, so times on
+/// either side of a daylight saving change each get their own
+pub fn local_offset(time: SystemTime) -> i64 {
+    let local: DateTime<Local> = time.into();
+    i64::from(local.offset().local_minus_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_and_copied_times() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_760_607_005);
+        let later = |secs| time + Duration::from_secs(secs);
+        assert_eq!(age(time, later(2)), "now");
+        assert_eq!(age(time, later(42)), "42s ago");
+        assert_eq!(age(time, later(185)), "3m ago");
+        assert_eq!(age(time, later(3 * 86_400)), "3d ago");
+        // A clock that went backwards isn't in the future
+        assert_eq!(age(later(10), time), "now");
+
+        assert_eq!(clock_time(time, 0), "09:30:05");
+        assert_eq!(clock_time(time, -10 * 3600), "23:30:05");
+        assert_eq!(full_time(time, 7200), "2025-10-16 11:30:05 +0200");
+        assert_eq!(full_time(time, -10 * 3600), "2025-10-15 23:30:05 -1000");
+    }
+}
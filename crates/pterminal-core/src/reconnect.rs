@@ -0,0 +1,209 @@
+//! Automatic reconnection of ssh/mosh sessions dropped by network errors.
+//!
+//! The UI samples each pane's foreground command. While a connection command
+//! runs it is remembered; when it ends with one of ssh's network error
+//! messages on screen, the command is typed again after an exponential backoff.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::config::ReconnectConfig;
+use crate::shell_words::quote;
+
+/// A session that stayed up this long resets the attempt counter
+const STABLE_AFTER: Duration = Duration::from_secs(30);
+
+/// Time a retried command gets to show up in the foreground
+const LAUNCH_GRACE: Duration = Duration::from_secs(5);
+
+/// What ssh and mosh print when the network drops or refuses the session
+const DISCONNECT_MESSAGES: &[&str] = &[
+    "closed by remote host",
+    "broken pipe",
+    "connection reset",
+    "connection timed out",
+    "operation timed out",
+    "network is unreachable",
+    "no route to host",
+    "connection refused",
+    "timeout, server",
+    "mosh did not make a successful connection",
+];
+
+/// Whether the end of the screen shows a network disconnect message.
+pub fn is_network_disconnect(screen: &str) -> bool {
+    screen
+        .lines()
+        .rev()
+        .filter(|line| !line.trim().is_empty())
+        .take(4)
+        .any(|line| {
+            let line = line.to_lowercase();
+            DISCONNECT_MESSAGES.iter().any(|msg| line.contains(msg))
+        })
+}
+
+/// Change noticed by [`ReconnectWatch::observe`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// The watched connection is no longer in the foreground
+    Ended,
+    /// The backoff elapsed: type this command line to reconnect
+    Retry(String),
+}
+
+/// Per-pane reconnection state
+#[derive(Debug, Default)]
+pub struct ReconnectWatch {
+    /// Command line of the last connection seen in the foreground
+    connection: Option<Vec<String>>,
+    /// Set while the connection is (believed to be) running
+    running_since: Option<Instant>,
+    attempts: u32,
+    retry_at: Option<Instant>,
+    /// When the last attempt was typed into the pane
+    retried_at: Option<Instant>,
+}
+
+impl ReconnectWatch {
+    /// Feed the pane's current foreground command line.
+    pub fn observe(
+        &mut self,
+        foreground: Option<&[String]>,
+        config: &ReconnectConfig,
+        now: Instant,
+    ) -> Option<WatchEvent> {
+        if let Some(argv) = foreground.filter(|argv| is_connection(argv, config)) {
+            if self.connection.as_deref() != Some(argv) {
+                self.connection = Some(argv.to_vec());
+                self.attempts = 0;
+            }
+            let since = *self.running_since.get_or_insert(now);
+            if now.duration_since(since) >= STABLE_AFTER {
+                self.attempts = 0;
+            }
+            self.retry_at = None;
+            self.retried_at = None;
+            return None;
+        }
+        if let Some(at) = self.retry_at {
+            if now < at {
+                return None;
+            }
+            self.retry_at = None;
+            // Counted as running until the grace period shows otherwise, so a
+            // connection that fails quickly still reports `Ended`
+            self.running_since = Some(now);
+            self.retried_at = Some(now);
+            let argv = self.connection.as_ref()?;
+            let line: Vec<String> = argv.iter().map(|arg| quote(arg)).collect();
+            return Some(WatchEvent::Retry(line.join(" ")));
+        }
+        if self
+            .retried_at
+            .is_some_and(|at| now.duration_since(at) < LAUNCH_GRACE)
+        {
+            return None;
+        }
+        self.retried_at = None;
+        self.running_since.take()?;
+        self.connection.as_ref().map(|_| WatchEvent::Ended)
+    }
+
+    /// Schedule the next attempt after a dropped session. Returns the delay and
+    /// the attempt number, or `None` once `max_attempts` is used up.
+    pub fn schedule(&mut self, config: &ReconnectConfig, now: Instant) -> Option<(Duration, u32)> {
+        if self.connection.is_none() || self.attempts >= config.max_attempts {
+            self.cancel();
+            return None;
+        }
+        let factor = 1u64 << self.attempts.min(16);
+        let delay_ms = config
+            .initial_delay_ms
+            .saturating_mul(factor)
+            .min(config.max_delay_ms.max(config.initial_delay_ms));
+        let delay = Duration::from_millis(delay_ms);
+        self.attempts += 1;
+        self.retry_at = Some(now + delay);
+        Some((delay, self.attempts))
+    }
+
+    /// Whether an attempt is waiting for its backoff
+    pub fn is_pending(&self) -> bool {
+        self.retry_at.is_some()
+    }
+
+    /// Drop any pending attempt and forget the connection.
+    pub fn cancel(&mut self) {
+        *self = Self::default();
+    }
+}
+
+fn is_connection(argv: &[String], config: &ReconnectConfig) -> bool {
+    let Some(program) = argv.first().and_then(|p| Path::new(p).file_name()) else {
+        return false;
+    };
+    // A bare `ssh` prints usage; only commands with arguments connect somewhere
+    argv.len() > 1 && config.commands.iter().any(|c| program == c.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn detects_disconnect_messages() {
+        let screen = "me@web1:~$ \nClient_loop: send disconnect: Broken pipe\n\n";
+        assert!(is_network_disconnect(screen));
+        assert!(!is_network_disconnect("Connection to web1 closed.\n$ "));
+    }
+
+    #[test]
+    fn retries_with_backoff_until_attempts_run_out() {
+        let config = ReconnectConfig {
+            enabled: true,
+            max_attempts: 2,
+            ..ReconnectConfig::default()
+        };
+        let ssh = argv("ssh -p 2222 web1");
+        let shell = argv("-zsh");
+        let mut watch = ReconnectWatch::default();
+        let t0 = Instant::now();
+
+        assert_eq!(watch.observe(Some(&ssh), &config, t0), None);
+        assert_eq!(
+            watch.observe(Some(&shell), &config, t0),
+            Some(WatchEvent::Ended)
+        );
+        let (delay, attempt) = watch.schedule(&config, t0).unwrap();
+        assert_eq!(
+            (delay, attempt),
+            (Duration::from_millis(config.initial_delay_ms), 1)
+        );
+        assert_eq!(watch.observe(Some(&shell), &config, t0), None);
+        assert_eq!(
+            watch.observe(Some(&shell), &config, t0 + delay),
+            Some(WatchEvent::Retry("ssh -p 2222 web1".into()))
+        );
+
+        // The retry failed straight away
+        let t1 = t0 + delay + Duration::from_secs(1);
+        assert_eq!(watch.observe(Some(&shell), &config, t1), None);
+        let t1 = t1 + LAUNCH_GRACE;
+        assert_eq!(
+            watch.observe(Some(&shell), &config, t1),
+            Some(WatchEvent::Ended)
+        );
+        assert_eq!(watch.schedule(&config, t1).unwrap().1, 2);
+        assert!(watch.is_pending());
+        let t2 = t1 + Duration::from_secs(600);
+        assert!(watch.observe(None, &config, t2).is_some());
+        let t3 = t2 + LAUNCH_GRACE;
+        assert_eq!(watch.observe(None, &config, t3), Some(WatchEvent::Ended));
+        assert_eq!(watch.schedule(&config, t3), None);
+    }
+}
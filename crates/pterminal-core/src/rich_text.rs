@@ -0,0 +1,271 @@
+//! Selected cells as plain text, or as HTML or RTF keeping their colors and
+//! bold/italic, for "Copy as HTML" and "Copy with Styles", and screen
+//! captures as text with SGR escapes.
+
+use std::fmt::Write;
+
+use crate::config::theme::RgbColor;
+use crate::terminal::GridCell;
+
+/// Attributes shared by a run of cells
+#[derive(Clone, Copy, PartialEq)]
+struct RunStyle {
+    fg: RgbColor,
+    bg: RgbColor,
+    bold: bool,
+    italic: bool,
+}
+
+/// Runs of equally styled text in a row, without trailing blanks
+fn runs(cells: &[GridCell]) -> Vec<(RunStyle, String)> {
+    let mut runs: Vec<(RunStyle, String)> = Vec::new();
+    for cell in cells.iter().filter(|cell| !cell.wide_spacer) {
+        let style = RunStyle {
+            fg: cell.fg,
+            bg: cell.bg,
+            bold: cell.bold,
+            italic: cell.italic,
+        };
+        let c = if cell.c == '\0' { ' ' } else { cell.c };
+        match runs.last_mut() {
+            Some((last, text)) if *last == style => text.push(c),
+            _ => runs.push((style, c.to_string())),
+        }
+    }
+    // Match plain-text copy, which drops trailing spaces
+    while let Some((_, text)) = runs.last_mut() {
+        let trimmed = text.trim_end_matches(' ').len();
+        text.truncate(trimmed);
+        if !text.is_empty() {
+            break;
+        }
+        runs.pop();
+    }
+    runs
+}
+
+fn hex(color: RgbColor) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+/// A line per row, without trailing spaces. A row marked in `joined` runs
+/// on into the next without a line break, as the rest of a line the
+/// terminal wrapped.
+pub fn to_text(rows: &[Vec<GridCell>], joined: &[bool]) -> String {
+    let mut text = String::new();
+    for (i, row) in rows.iter().enumerate() {
+        let line: String = row
+            .iter()
+            .map(|cell| if cell.c == '\0' { ' ' } else { cell.c })
+            .collect();
+        let last = i + 1 == rows.len();
+        if !last && joined.get(i).copied().unwrap_or(false) {
+            text.push_str(&line);
+            continue;
+        }
+        text.push_str(line.trim_end_matches(' '));
+        if !last {
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// A `<pre>` block with one `<span>` per styled run. Backgrounds equal to
+/// `background` (the terminal's) are left to the block.
+pub fn to_html(rows: &[Vec<GridCell>], font_family: &str, background: RgbColor) -> String {
+    let mut html = format!(
+        "<pre style=\"font-family: '{}', monospace; background-color: {};\">",
+        escape_html(font_family),
+        hex(background)
+    );
+    for (i, row) in rows.iter().enumerate() {
+        if i > 0 {
+            html.push('\n');
+        }
+        for (style, text) in runs(row) {
+            let mut css = format!("color: {};", hex(style.fg));
+            if style.bg != background {
+                let _ = write!(css, " background-color: {};", hex(style.bg));
+            }
+            if style.bold {
+                css.push_str(" font-weight: bold;");
+            }
+            if style.italic {
+                css.push_str(" font-style: italic;");
+            }
+            let _ = write!(html, "<span style=\"{css}\">{}</span>", escape_html(&text));
+        }
+    }
+    html.push_str("</pre>");
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// An RTF document with a color table, `\cf`/`\cb` per run and `\b`/`\i`.
+pub fn to_rtf(rows: &[Vec<GridCell>], font_family: &str, background: RgbColor) -> String {
+    let rows: Vec<Vec<(RunStyle, String)>> = rows.iter().map(|row| runs(row)).collect();
+    let mut colors = vec![background];
+    for (style, _) in rows.iter().flatten() {
+        for color in [style.fg, style.bg] {
+            if !colors.contains(&color) {
+                colors.push(color);
+            }
+        }
+    }
+    // Color table entries are 1-based; entry 0 is "auto"
+    let index = |color: RgbColor| colors.iter().position(|c| *c == color).unwrap_or(0) + 1;
+
+    let mut rtf = String::from("{\\rtf1\\ansi\\deff0");
+    let font = escape_rtf(font_family);
+    let _ = write!(rtf, "{{\\fonttbl{{\\f0\\fmodern {font};}}}}");
+    rtf.push_str("{\\colortbl;");
+    for color in &colors {
+        let _ = write!(rtf, "\\red{}\\green{}\\blue{};", color.r, color.g, color.b);
+    }
+    rtf.push('}');
+    let _ = write!(rtf, "\\f0\\cb{} ", index(background));
+    for (i, row) in rows.iter().enumerate() {
+        if i > 0 {
+            rtf.push_str("\\line ");
+        }
+        for (style, text) in row {
+            let _ = write!(rtf, "{{\\cf{}\\cb{}", index(style.fg), index(style.bg));
+            if style.bold {
+                rtf.push_str("\\b");
+            }
+            if style.italic {
+                rtf.push_str("\\i");
+            }
+            let _ = write!(rtf, " {}}}", escape_rtf(text));
+        }
+    }
+    rtf.push('}');
+    rtf
+}
+
+/// Text with a truecolor SGR sequence per styled run, reset at the end of
+/// each line. Backgrounds equal to `background` are left unset.
+pub fn to_ansi(rows: &[Vec<GridCell>], background: RgbColor) -> String {
+    let mut out = String::new();
+    for (i, row) in rows.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let runs = runs(row);
+        for (style, text) in &runs {
+            let (fg, bg) = (style.fg, style.bg);
+            let _ = write!(out, "\x1b[0;38;2;{};{};{}", fg.r, fg.g, fg.b);
+            if bg != background {
+                let _ = write!(out, ";48;2;{};{};{}", bg.r, bg.g, bg.b);
+            }
+            if style.bold {
+                out.push_str(";1");
+            }
+            if style.italic {
+                out.push_str(";3");
+            }
+            out.push('m');
+            out.push_str(text);
+        }
+        if !runs.is_empty() {
+            out.push_str("\x1b[0m");
+        }
+    }
+    out
+}
+
+/// Escape RTF control characters; non-ASCII becomes `\uN?` (UTF-16 units)
+fn escape_rtf(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            _ => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    let _ = write!(out, "\\u{}?", *unit as i16);
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(text: &str, fg: RgbColor, bold: bool) -> Vec<GridCell> {
+        text.chars()
+            .map(|c| GridCell {
+                c,
+                fg,
+                bg: RgbColor::new(0, 0, 0),
+                bold,
+                italic: false,
+                underline: false,
+                wide_spacer: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn html_and_rtf() {
+        let red = RgbColor::new(255, 0, 0);
+        let white = RgbColor::new(255, 255, 255);
+        let mut row = cells("a<b ", red, true);
+        row.extend(cells("é   ", white, false));
+        let rows = vec![row, cells("  ", white, false)];
+        let black = RgbColor::new(0, 0, 0);
+
+        assert_eq!(
+            to_html(&rows, "Menlo", black),
+            "<pre style=\"font-family: 'Menlo', monospace; background-color: #000000;\">\
+             <span style=\"color: #ff0000; font-weight: bold;\">a&lt;b </span>\
+             <span style=\"color: #ffffff;\">é</span>\n</pre>"
+        );
+        assert_eq!(
+            to_rtf(&rows, "Menlo", black),
+            "{\\rtf1\\ansi\\deff0{\\fonttbl{\\f0\\fmodern Menlo;}}\
+             {\\colortbl;\\red0\\green0\\blue0;\\red255\\green0\\blue0;\
+             \\red255\\green255\\blue255;}\
+             \\f0\\cb1 {\\cf2\\cb1\\b a<b }{\\cf3\\cb1 \\u233?}\\line }"
+        );
+        assert_eq!(
+            to_ansi(&rows, black),
+            "\x1b[0;38;2;255;0;0;1ma<b \x1b[0;38;2;255;255;255mé\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn plain_text_joins_wrapped_rows() {
+        let white = RgbColor::new(255, 255, 255);
+        let rows = vec![
+            cells("echo a ", white, false),
+            cells("b  ", white, false),
+            cells("ok ", white, false),
+        ];
+        assert_eq!(to_text(&rows, &[]), "echo a\nb\nok");
+        // Spaces at the wrap are part of the line
+        assert_eq!(to_text(&rows, &[true, false, true]), "echo a b\nok");
+    }
+}
@@ -0,0 +1,158 @@
+//! Display scale and the coordinate pipeline built on it.
+//!
+//! Slint gives lengths and pointer positions in logical pixels. The terminal
+//! texture and everything drawn into it is in physical pixels, and the grid
+//! is counted in cells of a physical size that follows the scale. Keeping
+//! those conversions here, in one place, means a window moved between a 1x
+//! and a 2x display (or onto a 1.25x or 1.5x one) sizes its grid and
+//! hit-tests the pointer the same way at every scale.
+
+use std::time::{Duration, Instant};
+
+/// Changes this small are rounding noise from the window system
+pub const SCALE_EPSILON: f64 = 0.01;
+
+/// After a change, how long the next one waits
+const SETTLE: Duration = Duration::from_millis(150);
+
+/// Slack (physical pixels) for float error when a length is an exact
+/// multiple of a cell
+const FIT_TOLERANCE: f32 = 1e-3;
+
+/// Hysteresis for a window's scale. A window straddling two displays can
+/// report each display's scale in turn as it moves; every change re-derives
+/// the cell size and resizes every PTY, so after one is applied, the next
+/// waits until [`SETTLE`] has passed.
+#[derive(Debug, Default)]
+pub struct ScaleTracker {
+    changed_at: Option<Instant>,
+    waiting: bool,
+}
+
+impl ScaleTracker {
+    /// Feed the scale the window reports while `current` is applied. Returns
+    /// the scale to apply, if it should change now.
+    pub fn observe(&mut self, current: f64, seen: f64, now: Instant) -> Option<f64> {
+        self.waiting = false;
+        if !(seen.is_finite() && seen > 0.0) || (seen - current).abs() <= SCALE_EPSILON {
+            return None;
+        }
+        if self
+            .changed_at
+            .is_some_and(|at| now.duration_since(at) < SETTLE)
+        {
+            self.waiting = true;
+            return None;
+        }
+        self.changed_at = Some(now);
+        Some(seen)
+    }
+
+    /// Whether the last scale seen differs from the applied one but is held
+    /// back; the caller should observe again shortly.
+    pub fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+}
+
+/// Physical size of an area `width` × `height` logical pixels in size,
+/// rounded to whole pixels as the window system does
+pub fn drawable_size(width: f32, height: f32, scale: f64) -> (u32, u32) {
+    let scale = scale as f32;
+    (
+        (width * scale).round().max(0.0) as u32,
+        (height * scale).round().max(0.0) as u32,
+    )
+}
+
+/// A logical pointer position in physical pixels
+pub fn to_physical(x: f32, y: f32, scale: f64) -> (f32, f32) {
+    let scale = scale as f32;
+    (x * scale, y * scale)
+}
+
+/// Columns and rows of `cell`-sized cells that fit in `width` × `height`
+/// physical pixels, at least one of each
+pub fn grid_size(width: f32, height: f32, (cell_w, cell_h): (f32, f32)) -> (u16, u16) {
+    let fit = |len: f32, cell: f32| ((len + FIT_TOLERANCE) / cell).floor().max(1.0) as u16;
+    (fit(width, cell_w), fit(height, cell_h))
+}
+
+/// Cell at physical offset (`x`, `y`) from a grid's top-left corner
+pub fn cell_at(x: f32, y: f32, (cell_w, cell_h): (f32, f32)) -> (u16, u16) {
+    let index = |offset: f32, cell: f32| ((offset + FIT_TOLERANCE) / cell).floor().max(0.0) as u16;
+    (index(x, cell_w), index(y, cell_h))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCALES: [f64; 4] = [1.0, 1.25, 1.5, 2.0];
+
+    /// A 10pt font's cell at `scale`, as the renderer derives it
+    fn cell(scale: f64) -> (f32, f32) {
+        let font_size = 10.0 * scale as f32;
+        (font_size * 0.6, font_size * 1.2)
+    }
+
+    #[test]
+    fn grid_is_the_same_at_every_scale() {
+        for scale in SCALES {
+            let padding = 8.0 * scale as f32;
+            let grid =
+                |(w, h): (u32, u32)| grid_size(w as f32 - padding, h as f32 - padding, cell(scale));
+            // 80×24 cells of 6×12 logical pixels plus 4 of padding each side
+            let size = grid(drawable_size(488.0, 296.0, scale));
+            assert_eq!(size, (80, 24), "at {scale}");
+            let size = grid(drawable_size(487.0, 295.0, scale));
+            assert_eq!(size, (79, 23), "at {scale}");
+        }
+        assert_eq!(grid_size(3.0, 0.0, cell(1.0)), (1, 1));
+    }
+
+    #[test]
+    fn pointer_maps_to_the_cell_under_it() {
+        for scale in SCALES {
+            let padding = 4.0;
+            for (col, row) in [(0u16, 0u16), (1, 0), (39, 11), (79, 23)] {
+                // Logical corners of the cell, just inside it
+                let left = padding + f32::from(col) * 6.0;
+                let top = padding + f32::from(row) * 12.0;
+                for (x, y) in [(left, top), (left + 5.99, top + 11.99)] {
+                    let (px, py) = to_physical(x, y, scale);
+                    let offset = padding * scale as f32;
+                    let hit = cell_at(px - offset, py - offset, cell(scale));
+                    assert_eq!(hit, (col, row), "({x}, {y}) at {scale}");
+                }
+            }
+            // Above and left of the grid, in the padding
+            let (px, py) = to_physical(1.0, 1.0, scale);
+            let offset = 4.0 * scale as f32;
+            assert_eq!(cell_at(px - offset, py - offset, cell(scale)), (0, 0));
+        }
+        assert_eq!(drawable_size(801.0, 600.0, 1.25), (1001, 750));
+    }
+
+    #[test]
+    fn scale_changes_settle_before_flipping_back() {
+        let mut tracker = ScaleTracker::default();
+        let t0 = Instant::now();
+        assert_eq!(tracker.observe(2.0, 2.004, t0), None);
+        assert_eq!(tracker.observe(2.0, f64::NAN, t0), None);
+        assert_eq!(tracker.observe(1.0, 2.0, t0), Some(2.0));
+        assert!(!tracker.is_waiting());
+
+        // Straddling the two displays: back to 1x is held until it settles
+        let t1 = t0 + Duration::from_millis(40);
+        assert_eq!(tracker.observe(2.0, 1.0, t1), None);
+        assert!(tracker.is_waiting());
+        assert_eq!(tracker.observe(2.0, 2.0, t1), None);
+        assert!(!tracker.is_waiting());
+        assert_eq!(tracker.observe(2.0, 1.0, t0 + SETTLE), Some(1.0));
+        assert_eq!(
+            tracker.observe(1.0, 1.5, t0 + SETTLE * 2 + Duration::from_millis(1)),
+            Some(1.5)
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! Reads the user's shell history file for history suggestions.
+//!
+//! Understands bash (optionally with `#<timestamp>` lines), zsh extended
+//! history (`: <time>:<duration>;command`) and fish's YAML-like format.
+
+use std::path::{Path, PathBuf};
+
+/// History file of the given shell: `$HISTFILE` if set, otherwise the shell's
+/// default location.
+pub fn history_path(shell: &str) -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("HISTFILE").filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    let home = directories::BaseDirs::new()?.home_dir().to_path_buf();
+    let name = Path::new(shell).file_name()?.to_str()?;
+    let path = match name {
+        "zsh" => home.join(".zsh_history"),
+        // fish uses XDG paths on every platform
+        "fish" => std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(".local/share"))
+            .join("fish/fish_history"),
+        _ => home.join(".bash_history"),
+    };
+    Some(path)
+}
+
+/// Load up to `limit` distinct commands from a history file, most recent first.
+pub fn load(path: &Path, limit: usize) -> Vec<String> {
+    let Ok(bytes) = std::fs::read(path) else {
+        return Vec::new();
+    };
+    let content = String::from_utf8_lossy(&bytes);
+    let commands = if path.ends_with("fish_history") {
+        parse_fish(&content)
+    } else {
+        parse_sh(&content)
+    };
+    most_recent_distinct(commands, limit)
+}
+
+/// bash and zsh history, oldest first
+fn parse_sh(content: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+    let mut pending: Option<String> = None;
+    for line in content.lines() {
+        if let Some(command) = pending.as_mut() {
+            // zsh continues multi-line commands with a trailing backslash
+            command.push('\n');
+            command.push_str(line);
+        } else if line.len() > 1
+            && line.starts_with('#')
+            && line[1..].bytes().all(|b| b.is_ascii_digit())
+        {
+            // bash HISTTIMEFORMAT timestamp
+            continue;
+        } else {
+            let command = match line.strip_prefix(": ") {
+                Some(extended) => extended.split_once(';').map_or(line, |(_, cmd)| cmd),
+                None => line,
+            };
+            pending = Some(command.to_string());
+        }
+        if let Some(command) = pending.as_mut() {
+            if command.ends_with('\\') {
+                command.pop();
+            } else if let Some(command) = pending.take() {
+                commands.push(command);
+            }
+        }
+    }
+    commands.extend(pending);
+    commands
+}
+
+/// fish history, oldest first
+fn parse_fish(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| line.strip_prefix("- cmd: "))
+        .map(unescape_fish)
+        .collect()
+}
+
+/// fish escapes newlines as `\n` and backslashes as `\\`
+fn unescape_fish(cmd: &str) -> String {
+    let mut out = String::with_capacity(cmd.len());
+    let mut chars = cmd.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('\\') => out.push('\\'),
+            other => {
+                out.push('\\');
+                out.extend(other);
+            }
+        }
+    }
+    out
+}
+
+fn most_recent_distinct(commands: Vec<String>, limit: usize) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    commands
+        .into_iter()
+        .rev()
+        .map(|cmd| cmd.trim().to_string())
+        .filter(|cmd| !cmd.is_empty() && seen.insert(cmd.clone()))
+        .take(limit)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zsh_extended_and_bash_timestamps() {
+        let content = ": 1700000000:0;git status\n#1700000001\nls -la\n\
+                       : 1700000002:3;echo one \\\ntwo\ngit status\n";
+        let commands = most_recent_distinct(parse_sh(content), 10);
+        assert_eq!(commands, vec!["git status", "echo one \ntwo", "ls -la"]);
+    }
+
+    #[test]
+    fn fish_history() {
+        let content = "- cmd: cargo build\n  when: 1700000000\n- cmd: echo a\\\\nb\n";
+        assert_eq!(parse_fish(content), vec!["cargo build", "echo a\\nb"]);
+    }
+}
@@ -0,0 +1,209 @@
+//! Shell integration snippets for the user's shell startup file.
+//!
+//! The snippets make bash, zsh and fish mark their prompts and commands
+//! (OSC 133) and report their directory (OSC 7), and only when running in
+//! pterminal (`TERM_PROGRAM=pterminal`). They are written between marker
+//! lines, so installing again replaces the block instead of adding another.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+const BEGIN: &str = "# >>> pterminal shell integration >>>";
+const END: &str = "# <<< pterminal shell integration <<<";
+
+const BASH: &str = r#"if [ "$TERM_PROGRAM" = pterminal ] && [ -z "$__pterminal_integrated" ]; then
+    __pterminal_integrated=1
+    __pterminal_prompt() {
+        local code=$?
+        printf '\e]133;D;%s\a\e]7;file://%s%s\a\e]133;A\a' "$code" "$HOSTNAME" "$PWD"
+    }
+    PROMPT_COMMAND="__pterminal_prompt${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+    PS1="$PS1\[\e]133;B\a\]"
+    PS0="${PS0:-}\e]133;C\a"
+fi"#;
+
+const ZSH: &str = r#"if [[ "$TERM_PROGRAM" == pterminal && -z "$__pterminal_integrated" ]]; then
+    __pterminal_integrated=1
+    __pterminal_precmd() {
+        local code=$?
+        printf '\e]133;D;%s\a\e]7;file://%s%s\a\e]133;A\a' "$code" "$HOST" "$PWD"
+    }
+    __pterminal_preexec() {
+        printf '\e]133;C\a'
+    }
+    # First, so it sees the command's exit status
+    precmd_functions=(__pterminal_precmd $precmd_functions)
+    preexec_functions+=(__pterminal_preexec)
+    PS1="$PS1%{"$'\e]133;B\a'"%}"
+fi"#;
+
+const FISH: &str = r#"if test "$TERM_PROGRAM" = pterminal; and not set -q __pterminal_integrated
+    set -g __pterminal_integrated 1
+    function __pterminal_postexec --on-event fish_postexec
+        printf '\e]133;D;%s\a' $status
+    end
+    function __pterminal_preexec --on-event fish_preexec
+        printf '\e]133;C\a'
+    end
+    function __pterminal_prompt --on-event fish_prompt
+        printf '\e]7;file://%s%s\a\e]133;A\a' $hostname "$PWD"
+    end
+    functions -c fish_prompt __pterminal_fish_prompt
+    function fish_prompt
+        __pterminal_fish_prompt
+        printf '\e]133;B\a'
+    end
+end"#;
+
+/// A shell pterminal has an integration snippet for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    /// The shell a program path or name runs, e.g. `/bin/zsh` or `bash`
+    pub fn from_program(program: &str) -> Option<Self> {
+        let name = Path::new(program).file_name()?.to_str()?;
+        match name.trim_start_matches('-') {
+            "bash" => Some(Self::Bash),
+            "zsh" => Some(Self::Zsh),
+            "fish" => Some(Self::Fish),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Bash => "bash",
+            Self::Zsh => "zsh",
+            Self::Fish => "fish",
+        }
+    }
+
+    /// The lines written to the startup file, markers included
+    pub fn snippet(self) -> String {
+        let body = match self {
+            Self::Bash => BASH,
+            Self::Zsh => ZSH,
+            Self::Fish => FISH,
+        };
+        format!("{BEGIN}\n{body}\n{END}\n")
+    }
+
+    /// The startup file interactive shells read: `~/.bashrc`,
+    /// `$ZDOTDIR/.zshrc` or fish's `config.fish`
+    pub fn rc_file(self, home: &Path) -> PathBuf {
+        let var = |name: &str| {
+            std::env::var_os(name)
+                .map(PathBuf::from)
+                .filter(|path| path.is_absolute())
+        };
+        match self {
+            Self::Bash => home.join(".bashrc"),
+            Self::Zsh => var("ZDOTDIR")
+                .unwrap_or_else(|| home.to_path_buf())
+                .join(".zshrc"),
+            // fish uses XDG paths on every platform
+            Self::Fish => var("XDG_CONFIG_HOME")
+                .unwrap_or_else(|| home.join(".config"))
+                .join("fish/config.fish"),
+        }
+    }
+}
+
+/// What installing did to the startup file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Installed {
+    /// The snippet was appended
+    Added,
+    /// An older snippet was replaced
+    Updated,
+    /// The current snippet was already there
+    Unchanged,
+}
+
+/// Add `shell`'s snippet to the startup file at `path`, creating it if
+/// needed, or bring one already there up to date
+pub fn install(shell: Shell, path: &Path) -> Result<Installed> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("can't read {}", path.display())),
+    };
+    let (text, installed) = with_snippet(&text, &shell.snippet());
+    if installed != Installed::Unchanged {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, text).with_context(|| format!("can't write {}", path.display()))?;
+    }
+    Ok(installed)
+}
+
+/// Whether the startup file at `path` has a snippet
+pub fn is_installed(path: &Path) -> bool {
+    std::fs::read_to_string(path).is_ok_and(|text| text.lines().any(|line| line == BEGIN))
+}
+
+/// `text` with `snippet` in place of the marked block, or appended
+fn with_snippet(text: &str, snippet: &str) -> (String, Installed) {
+    let begin = text.match_indices(BEGIN).map(|(at, _)| at).find(|&at| {
+        // Only a marker on a line of its own
+        at == 0 || text[..at].ends_with('\n')
+    });
+    if let Some(begin) = begin {
+        let end = text[begin..]
+            .find(END)
+            .map(|at| begin + at + END.len())
+            .map(|at| at + usize::from(text[at..].starts_with('\n')));
+        // A block missing its end marker is replaced to the end of the file
+        let end = end.unwrap_or(text.len());
+        if text[begin..end] == *snippet {
+            return (text.to_string(), Installed::Unchanged);
+        }
+        let replaced = format!("{}{snippet}{}", &text[..begin], &text[end..]);
+        return (replaced, Installed::Updated);
+    }
+    let mut appended = text.to_string();
+    if !appended.is_empty() {
+        // Set apart by a blank line
+        if !appended.ends_with('\n') {
+            appended.push('\n');
+        }
+        appended.push('\n');
+    }
+    appended.push_str(snippet);
+    (appended, Installed::Added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn installing_is_idempotent() {
+        let snippet = Shell::Zsh.snippet();
+        let (text, installed) = with_snippet("export EDITOR=vim", &snippet);
+        assert_eq!(installed, Installed::Added);
+        assert_eq!(text, format!("export EDITOR=vim\n\n{snippet}"));
+        assert_eq!(
+            with_snippet(&text, &snippet),
+            (text.clone(), Installed::Unchanged)
+        );
+
+        // An older version is replaced where it is
+        let old = format!("{BEGIN}\necho old\n{END}\nalias ll='ls -l'\n");
+        let (text, installed) = with_snippet(&old, &snippet);
+        assert_eq!(installed, Installed::Updated);
+        assert_eq!(text, format!("{snippet}alias ll='ls -l'\n"));
+
+        assert_eq!(with_snippet("", &snippet).0, snippet);
+        assert_eq!(Shell::from_program("/usr/bin/fish"), Some(Shell::Fish));
+        assert_eq!(Shell::from_program("-bash"), Some(Shell::Bash));
+        assert_eq!(Shell::from_program("pwsh"), None);
+    }
+}
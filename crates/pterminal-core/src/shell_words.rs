@@ -0,0 +1,138 @@
+//! Quoting text so a shell reads it back as a single word.
+
+use std::path::Path;
+
+/// Quoting rules to follow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellKind {
+    Posix,
+    PowerShell,
+    Cmd,
+}
+
+impl ShellKind {
+    /// Guess the quoting rules from the shell program, e.g. `/bin/zsh` or `pwsh.exe`.
+    pub fn from_shell(shell: &str) -> Self {
+        // Split on both separators so Windows paths are recognised anywhere
+        let name = shell.rsplit(['/', '\\']).next().unwrap_or(shell);
+        let name = name.to_ascii_lowercase();
+        match name.strip_suffix(".exe").unwrap_or(&name) {
+            "pwsh" | "powershell" => Self::PowerShell,
+            "cmd" => Self::Cmd,
+            _ => Self::Posix,
+        }
+    }
+
+    /// Quote `word` for this shell.
+    pub fn quote(self, word: &str) -> String {
+        match self {
+            Self::Posix => quote(word),
+            Self::PowerShell if is_plain(word, "_-./:\\") => word.to_string(),
+            Self::PowerShell => format!("'{}'", quote_powershell(word)),
+            Self::Cmd if is_plain(word, "_-./:\\") => word.to_string(),
+            Self::Cmd => format!("\"{}\"", quote_cmd(word)),
+        }
+    }
+
+    /// Command that changes to `dir`.
+    pub fn cd_command(self, dir: &Path) -> String {
+        let dir = self.quote(&dir.to_string_lossy());
+        match self {
+            Self::Cmd => format!("cd /d {dir}"),
+            _ => format!("cd {dir}"),
+        }
+    }
+}
+
+/// Escape `word` for a PowerShell single-quoted string, which any of the
+/// single quotation marks PowerShell accepts would end
+fn quote_powershell(word: &str) -> String {
+    let mut out = String::with_capacity(word.len());
+    for c in word.chars() {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+            out.push(c);
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Escape `word` for a cmd.exe double-quoted string. A `"` is doubled so
+/// the quoting stays open, and a `%` is put outside the quotes behind a
+/// `^`, where cmd cannot read it as the start of a `%VAR%`.
+fn quote_cmd(word: &str) -> String {
+    word.replace('"', "\"\"").replace('%', "\"^%\"")
+}
+
+fn is_plain(word: &str, punctuation: &str) -> bool {
+    !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_alphanumeric() || punctuation.contains(c))
+}
+
+/// Quote `word` for a POSIX shell. Words made only of characters the shell
+/// treats literally are returned as is, anything else is single-quoted.
+pub fn quote(word: &str) -> String {
+    if is_plain(word, "_-./:@%+=,") {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+/// Quote a path for a POSIX shell.
+pub fn quote_path(path: &Path) -> String {
+    quote(&path.to_string_lossy())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quote("/usr/local/bin"), "/usr/local/bin");
+        assert_eq!(quote("My Files/a.txt"), "'My Files/a.txt'");
+        assert_eq!(quote("it's"), r"'it'\''s'");
+        assert_eq!(quote("$HOME"), "'$HOME'");
+        assert_eq!(quote(""), "''");
+    }
+
+    #[test]
+    fn windows_shells() {
+        assert_eq!(
+            ShellKind::from_shell("C:\\Windows\\System32\\cmd.exe"),
+            ShellKind::Cmd
+        );
+        assert_eq!(ShellKind::from_shell("pwsh"), ShellKind::PowerShell);
+        assert_eq!(ShellKind::from_shell("/bin/zsh"), ShellKind::Posix);
+        let ps = ShellKind::PowerShell;
+        assert_eq!(
+            ps.quote("C:\\Users\\me\\it's here"),
+            "'C:\\Users\\me\\it''s here'"
+        );
+        assert_eq!(
+            ShellKind::Cmd.cd_command(Path::new("C:\\My Files")),
+            "cd /d \"C:\\My Files\""
+        );
+    }
+
+    #[test]
+    fn powershell_typographic_quotes() {
+        let ps = ShellKind::PowerShell;
+        assert_eq!(ps.quote("it\u{2019}s"), "'it\u{2019}\u{2019}s'");
+        assert_eq!(
+            ps.quote("\u{2018}a\u{201A}b\u{201B}"),
+            "'\u{2018}\u{2018}a\u{201A}\u{201A}b\u{201B}\u{201B}'"
+        );
+    }
+
+    #[test]
+    fn cmd_variables_and_quotes() {
+        let cmd = ShellKind::Cmd;
+        assert_eq!(cmd.quote("%PATH%"), "\"\"^%\"PATH\"^%\"\"");
+        assert_eq!(cmd.quote("100% done"), "\"100\"^%\" done\"");
+        assert_eq!(cmd.quote("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -0,0 +1,115 @@
+//! User-defined command snippets with `${placeholder}` prompts.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// A named snippet from a `[[snippets]]` config table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet {
+    pub name: String,
+    /// Text written to the pane. `${name}` and `${name:default}` are asked for
+    /// before inserting.
+    pub command: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// A value the user is prompted for before a snippet is inserted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placeholder {
+    pub name: String,
+    pub default: Option<String>,
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Field(Placeholder),
+}
+
+/// Split a command into literal text and `${...}` fields. An unterminated
+/// `${` is kept as text.
+fn segments(command: &str) -> Vec<Segment<'_>> {
+    let mut out = Vec::new();
+    let mut rest = command;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+        let inner = &rest[start + 2..start + 2 + len];
+        let (name, default) = match inner.split_once(':') {
+            Some((name, default)) => (name, Some(default.to_string())),
+            None => (inner, None),
+        };
+        out.push(Segment::Text(&rest[..start]));
+        out.push(Segment::Field(Placeholder {
+            name: name.trim().to_string(),
+            default,
+        }));
+        rest = &rest[start + 2 + len + 1..];
+    }
+    out.push(Segment::Text(rest));
+    out
+}
+
+impl Snippet {
+    /// Placeholders in order of first appearance, without duplicates.
+    pub fn placeholders(&self) -> Vec<Placeholder> {
+        let mut found: Vec<Placeholder> = Vec::new();
+        for segment in segments(&self.command) {
+            if let Segment::Field(field) = segment {
+                if !found.iter().any(|p| p.name == field.name) {
+                    found.push(field);
+                }
+            }
+        }
+        found
+    }
+
+    /// Substitute placeholder values. A missing or empty value falls back to
+    /// the placeholder's default, or nothing.
+    pub fn expand(&self, values: &HashMap<String, String>) -> String {
+        let mut out = String::with_capacity(self.command.len());
+        for segment in segments(&self.command) {
+            match segment {
+                Segment::Text(text) => out.push_str(text),
+                Segment::Field(field) => {
+                    let value = values
+                        .get(&field.name)
+                        .filter(|v| !v.is_empty())
+                        .or(field.default.as_ref());
+                    out.push_str(value.map_or("", String::as_str));
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(command: &str) -> Snippet {
+        Snippet {
+            name: "test".into(),
+            command: command.into(),
+            description: String::new(),
+        }
+    }
+
+    #[test]
+    fn placeholders_deduplicated_in_order() {
+        let s = snippet("kubectl -n ${ns:default} logs ${pod} -c ${pod}");
+        let names: Vec<_> = s.placeholders().into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["ns", "pod"]);
+        assert_eq!(s.placeholders()[0].default.as_deref(), Some("default"));
+    }
+
+    #[test]
+    fn expand_with_defaults() {
+        let s = snippet("ssh ${user:root}@${host} ${unterminated");
+        let values = HashMap::from([("host".to_string(), "db1".to_string())]);
+        assert_eq!(s.expand(&values), "ssh root@db1 ${unterminated");
+    }
+}
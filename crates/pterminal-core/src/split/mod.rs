@@ -1,6 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+use crate::workspace::layout::{LayoutNode, PaneLayout};
+
 pub type PaneId = u64;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum SplitDirection {
     Horizontal, // left | right
     Vertical,   // top / bottom
@@ -14,6 +19,66 @@ pub struct PaneRect {
     pub height: f32,
 }
 
+impl PaneRect {
+    /// Half of this rect on the given side (where a dropped pane would land)
+    pub fn drop_zone(&self, side: DropSide) -> PaneRect {
+        let (half_w, half_h) = (self.width / 2.0, self.height / 2.0);
+        match side {
+            DropSide::Left => PaneRect {
+                width: half_w,
+                ..self.clone()
+            },
+            DropSide::Right => PaneRect {
+                x: self.x + half_w,
+                width: half_w,
+                ..self.clone()
+            },
+            DropSide::Top => PaneRect {
+                height: half_h,
+                ..self.clone()
+            },
+            DropSide::Bottom => PaneRect {
+                y: self.y + half_h,
+                height: half_h,
+                ..self.clone()
+            },
+        }
+    }
+}
+
+/// Edge of a pane that another pane is dropped onto
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropSide {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl DropSide {
+    /// Side of `rect` nearest to the point (same coordinate space as `rect`)
+    pub fn nearest(rect: &PaneRect, x: f32, y: f32) -> Self {
+        let fx = ((x - rect.x) / rect.width.max(f32::EPSILON)).clamp(0.0, 1.0);
+        let fy = ((y - rect.y) / rect.height.max(f32::EPSILON)).clamp(0.0, 1.0);
+        [
+            (fx, DropSide::Left),
+            (1.0 - fx, DropSide::Right),
+            (fy, DropSide::Top),
+            (1.0 - fy, DropSide::Bottom),
+        ]
+        .into_iter()
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map_or(DropSide::Right, |(_, side)| side)
+    }
+
+    fn direction(self) -> SplitDirection {
+        match self {
+            DropSide::Left | DropSide::Right => SplitDirection::Horizontal,
+            DropSide::Top | DropSide::Bottom => SplitDirection::Vertical,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct SplitTree {
     root: SplitNode,
@@ -38,7 +103,7 @@ impl SplitTree {
     }
 
     pub fn split(&mut self, target: PaneId, direction: SplitDirection, new_pane: PaneId) {
-        Self::split_node(&mut self.root, target, direction, new_pane);
+        Self::split_node(&mut self.root, target, direction, new_pane, false);
     }
 
     fn split_node(
@@ -46,26 +111,50 @@ impl SplitTree {
         target: PaneId,
         direction: SplitDirection,
         new_pane: PaneId,
+        new_first: bool,
     ) -> bool {
         match node {
             SplitNode::Leaf(id) if *id == target => {
-                let old = std::mem::replace(node, SplitNode::Leaf(0));
+                let old = Box::new(std::mem::replace(node, SplitNode::Leaf(0)));
+                let new = Box::new(SplitNode::Leaf(new_pane));
+                let (first, second) = if new_first { (new, old) } else { (old, new) };
                 *node = SplitNode::Split {
                     direction,
                     ratio: 0.5,
-                    first: Box::new(old),
-                    second: Box::new(SplitNode::Leaf(new_pane)),
+                    first,
+                    second,
                 };
                 true
             }
             SplitNode::Leaf(_) => false,
             SplitNode::Split { first, second, .. } => {
-                Self::split_node(first, target, direction, new_pane)
-                    || Self::split_node(second, target, direction, new_pane)
+                Self::split_node(first, target, direction, new_pane, new_first)
+                    || Self::split_node(second, target, direction, new_pane, new_first)
             }
         }
     }
 
+    /// Detach a pane from the tree, promoting its sibling. Unlike closing,
+    /// the pane is expected to be re-attached elsewhere. Returns false if
+    /// it's the only pane or not in this tree.
+    pub fn detach(&mut self, pane_id: PaneId) -> bool {
+        self.remove(pane_id)
+    }
+
+    /// Attach a (detached) pane on the given side of `target`, taking half its space.
+    pub fn attach(&mut self, target: PaneId, side: DropSide, pane_id: PaneId) -> bool {
+        let new_first = matches!(side, DropSide::Left | DropSide::Top);
+        Self::split_node(&mut self.root, target, side.direction(), pane_id, new_first)
+    }
+
+    /// Move a pane next to another pane in the same tree.
+    pub fn move_pane(&mut self, pane_id: PaneId, target: PaneId, side: DropSide) -> bool {
+        if pane_id == target || !self.contains(target) || !self.detach(pane_id) {
+            return false;
+        }
+        self.attach(target, side, pane_id)
+    }
+
     /// Removes a pane, promoting its sibling. Returns false if it's the only pane.
     pub fn remove(&mut self, pane_id: PaneId) -> bool {
         if let SplitNode::Leaf(id) = &self.root {
@@ -207,6 +296,64 @@ impl SplitTree {
         Some(ids[(pos + ids.len() - 1) % ids.len()])
     }
 
+    /// The tree's shape with `pane` describing each pane, for export.
+    pub fn to_layout(&self, mut pane: impl FnMut(PaneId) -> PaneLayout) -> LayoutNode {
+        Self::node_layout(&self.root, &mut pane)
+    }
+
+    fn node_layout(node: &SplitNode, pane: &mut impl FnMut(PaneId) -> PaneLayout) -> LayoutNode {
+        match node {
+            SplitNode::Leaf(id) => LayoutNode::Pane(pane(*id)),
+            SplitNode::Split {
+                direction,
+                ratio,
+                first,
+                second,
+            } => LayoutNode::Split {
+                direction: *direction,
+                ratio: *ratio,
+                first: Box::new(Self::node_layout(first, pane)),
+                second: Box::new(Self::node_layout(second, pane)),
+            },
+        }
+    }
+
+    /// Build a tree shaped like `layout`, taking pane IDs from `next_id`.
+    /// Returns each new pane with its description, in layout order.
+    pub fn from_layout(
+        layout: &LayoutNode,
+        mut next_id: impl FnMut() -> PaneId,
+    ) -> (Self, Vec<(PaneId, PaneLayout)>) {
+        let mut panes = Vec::new();
+        let root = Self::layout_node_tree(layout, &mut next_id, &mut panes);
+        (Self { root }, panes)
+    }
+
+    fn layout_node_tree(
+        layout: &LayoutNode,
+        next_id: &mut impl FnMut() -> PaneId,
+        panes: &mut Vec<(PaneId, PaneLayout)>,
+    ) -> SplitNode {
+        match layout {
+            LayoutNode::Pane(pane) => {
+                let id = next_id();
+                panes.push((id, pane.clone()));
+                SplitNode::Leaf(id)
+            }
+            LayoutNode::Split {
+                direction,
+                ratio,
+                first,
+                second,
+            } => SplitNode::Split {
+                direction: *direction,
+                ratio: ratio.clamp(0.1, 0.9),
+                first: Box::new(Self::layout_node_tree(first, next_id, panes)),
+                second: Box::new(Self::layout_node_tree(second, next_id, panes)),
+            },
+        }
+    }
+
     /// Adjust the ratio of the parent split containing `pane_id` by `delta`.
     pub fn adjust_ratio(&mut self, pane_id: PaneId, delta: f32) {
         Self::adjust_ratio_node(&mut self.root, pane_id, delta);
@@ -294,6 +441,38 @@ mod tests {
         assert_eq!(tree.prev_pane(1), Some(3)); // wraps
     }
 
+    #[test]
+    fn move_pane_to_side() {
+        let mut tree = SplitTree::new(1);
+        tree.split(1, SplitDirection::Horizontal, 2);
+        tree.split(2, SplitDirection::Horizontal, 3);
+
+        assert!(tree.move_pane(3, 1, DropSide::Left));
+        assert_eq!(tree.pane_ids(), vec![3, 1, 2]);
+
+        assert!(tree.move_pane(2, 3, DropSide::Bottom));
+        let layout = tree.layout();
+        assert_eq!(tree.pane_ids(), vec![3, 2, 1]);
+        assert!((layout[1].1.y - 0.5).abs() < f32::EPSILON);
+
+        assert!(!tree.move_pane(1, 1, DropSide::Top));
+        assert!(!tree.move_pane(1, 9, DropSide::Top));
+    }
+
+    #[test]
+    fn nearest_drop_side() {
+        let rect = PaneRect {
+            x: 0.5,
+            y: 0.0,
+            width: 0.5,
+            height: 1.0,
+        };
+        assert_eq!(DropSide::nearest(&rect, 0.55, 0.5), DropSide::Left);
+        assert_eq!(DropSide::nearest(&rect, 0.75, 0.95), DropSide::Bottom);
+        let zone = rect.drop_zone(DropSide::Right);
+        assert!((zone.x - 0.75).abs() < f32::EPSILON);
+    }
+
     #[test]
     fn adjust_ratio() {
         let mut tree = SplitTree::new(1);
@@ -0,0 +1,187 @@
+//! Recognises ssh sessions running in a pane and builds file transfers that
+//! reuse their destination.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::shell_words::quote;
+
+/// ssh options that take an argument (see ssh(1))
+const OPTIONS_WITH_ARG: &str = "BbcDEeFIiJLlmOoPpQRSWw";
+
+/// Where an ssh command connects to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshTarget {
+    /// `[user@]host` as given to ssh
+    pub destination: String,
+    pub port: Option<String>,
+    /// Options the transfer passes on to ssh: identity, config, jump host, `-o`
+    pub options: Vec<String>,
+}
+
+/// Program used to upload files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferTool {
+    Scp,
+    Rsync,
+}
+
+impl TransferTool {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Scp => "scp",
+            Self::Rsync => "rsync",
+        }
+    }
+}
+
+impl SshTarget {
+    /// Parse an ssh command line. Returns `None` if `argv` is not an ssh
+    /// invocation with a destination.
+    pub fn from_argv(argv: &[String]) -> Option<Self> {
+        let program = Path::new(argv.first()?).file_name()?.to_str()?;
+        if program != "ssh" {
+            return None;
+        }
+        let mut login = None;
+        let mut port = None;
+        let mut options = Vec::new();
+        let mut args = argv[1..].iter();
+        let destination = loop {
+            let arg = args.next()?;
+            let Some(flags) = arg.strip_prefix('-').filter(|f| !f.is_empty()) else {
+                break arg.clone();
+            };
+            if flags == "-" {
+                break args.next()?.clone();
+            }
+            // Flags may be grouped (`-tt`) and values attached (`-p2222`)
+            for (i, flag) in flags.char_indices() {
+                if !OPTIONS_WITH_ARG.contains(flag) {
+                    continue;
+                }
+                let attached = &flags[i + flag.len_utf8()..];
+                let value = if attached.is_empty() {
+                    args.next()?.clone()
+                } else {
+                    attached.to_string()
+                };
+                match flag {
+                    'l' => login = Some(value),
+                    'p' => port = Some(value),
+                    'i' | 'F' | 'J' | 'o' => options.extend([format!("-{flag}"), value]),
+                    _ => {}
+                }
+                break;
+            }
+        };
+
+        let mut destination = destination;
+        if let Some(uri) = destination.strip_prefix("ssh://") {
+            let uri = uri.trim_end_matches('/');
+            let (host, uri_port) = match uri.rsplit_once(':') {
+                Some((host, p)) if p.bytes().all(|b| b.is_ascii_digit()) => (host, Some(p)),
+                _ => (uri, None),
+            };
+            port = port.or(uri_port.map(str::to_string));
+            destination = host.to_string();
+        }
+        if let Some(login) = login.filter(|_| !destination.contains('@')) {
+            destination = format!("{login}@{destination}");
+        }
+        Some(Self {
+            destination,
+            port,
+            options,
+        })
+    }
+
+    /// Command that copies `files` into the remote home directory.
+    pub fn upload_command(&self, tool: TransferTool, files: &[PathBuf]) -> Command {
+        let mut cmd = Command::new(tool.name());
+        match tool {
+            TransferTool::Scp => {
+                cmd.args(["-r", "-q"]);
+                if let Some(port) = &self.port {
+                    cmd.args(["-P", port]);
+                }
+                cmd.args(&self.options);
+            }
+            TransferTool::Rsync => {
+                let mut ssh = vec!["ssh".to_string()];
+                if let Some(port) = &self.port {
+                    ssh.extend(["-p".to_string(), port.clone()]);
+                }
+                ssh.extend(self.options.iter().map(|o| quote(o)));
+                cmd.args(["-a", "-e", &ssh.join(" ")]);
+            }
+        }
+        cmd.args(files);
+        cmd.arg(format!("{}:", self.destination));
+        cmd
+    }
+
+    /// Shell-escaped path of an uploaded file on the remote side.
+    pub fn remote_path(file: &Path) -> String {
+        let name = file
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        format!("~/{}", quote(&name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parse_ssh_command_lines() {
+        let target =
+            SshTarget::from_argv(&argv("ssh -t -p2222 -i ~/.ssh/id -l deploy web1 uptime"));
+        assert_eq!(
+            target,
+            Some(SshTarget {
+                destination: "deploy@web1".into(),
+                port: Some("2222".into()),
+                options: vec!["-i".into(), "~/.ssh/id".into()],
+            })
+        );
+        let uri = SshTarget::from_argv(&argv("/usr/bin/ssh -A ssh://me@db:22")).unwrap();
+        assert_eq!(
+            (uri.destination.as_str(), uri.port.as_deref()),
+            ("me@db", Some("22"))
+        );
+        assert_eq!(SshTarget::from_argv(&argv("ssh -v")), None);
+        assert_eq!(SshTarget::from_argv(&argv("scp a b:")), None);
+    }
+
+    #[test]
+    fn upload_commands() {
+        let target = SshTarget::from_argv(&argv("ssh -p 2222 -J bastion host")).unwrap();
+        let files = [PathBuf::from("/tmp/a b.txt")];
+        let scp = target.upload_command(TransferTool::Scp, &files);
+        let args: Vec<_> = scp.get_args().map(|a| a.to_string_lossy()).collect();
+        assert_eq!(
+            args,
+            [
+                "-r",
+                "-q",
+                "-P",
+                "2222",
+                "-J",
+                "bastion",
+                "/tmp/a b.txt",
+                "host:"
+            ]
+        );
+        let rsync = target.upload_command(TransferTool::Rsync, &files);
+        let args: Vec<_> = rsync.get_args().map(|a| a.to_string_lossy()).collect();
+        assert_eq!(args[..3], ["-a", "-e", "ssh -p 2222 -J bastion"]);
+        assert_eq!(SshTarget::remote_path(&files[0]), "~/'a b.txt'");
+    }
+}
@@ -0,0 +1,129 @@
+//! Segments of the status bar along the bottom of the window
+//! (`[status_bar]`).
+//!
+//! The config lists segments by name; names that aren't built in are the
+//! ids of segments plugins contribute, whose text the plugin sets.
+
+/// What a status bar segment shows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatusSegment {
+    /// Working directory of the active pane
+    Cwd,
+    /// Git branch of the active pane's directory
+    GitBranch,
+    /// Local time of day
+    Time,
+    /// Columns and rows of the active pane
+    PaneSize,
+    /// How far the active pane is scrolled back
+    Scroll,
+    /// Unread notifications
+    Notifications,
+    /// Contributed by a plugin, by its id
+    Plugin(String),
+}
+
+impl StatusSegment {
+    /// Segments shown when the config doesn't list any
+    pub const DEFAULTS: &'static [&'static str] = &[
+        "cwd",
+        "git-branch",
+        "pane-size",
+        "scroll",
+        "notifications",
+        "time",
+    ];
+
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "cwd" => Self::Cwd,
+            "git-branch" => Self::GitBranch,
+            "time" => Self::Time,
+            "pane-size" => Self::PaneSize,
+            "scroll" => Self::Scroll,
+            "notifications" => Self::Notifications,
+            id => Self::Plugin(id.to_string()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Cwd => "cwd",
+            Self::GitBranch => "git-branch",
+            Self::Time => "time",
+            Self::PaneSize => "pane-size",
+            Self::Scroll => "scroll",
+            Self::Notifications => "notifications",
+            Self::Plugin(id) => id,
+        }
+    }
+}
+
+/// The configured segments in order, then contributed segments the config
+/// doesn't name
+pub fn segments(names: &[String], contributed: &[&str]) -> Vec<StatusSegment> {
+    let mut segments: Vec<StatusSegment> = names
+        .iter()
+        .map(|name| StatusSegment::from_name(name.trim()))
+        .collect();
+    for id in contributed {
+        if !names.iter().any(|name| name.trim() == *id) {
+            segments.push(StatusSegment::Plugin(id.to_string()));
+        }
+    }
+    segments
+}
+
+/// Scroll position text, lines scrolled back as in "↑ 120"; empty at the
+/// bottom
+pub fn scroll_text(offset: usize) -> String {
+    if offset == 0 {
+        return String::new();
+    }
+    format!("↑ {offset}")
+}
+
+/// Unread notification count text, e.g. "● 3"; empty when all are read
+pub fn notifications_text(unread: usize) -> String {
+    if unread == 0 {
+        return String::new();
+    }
+    format!("● {unread}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for name in StatusSegment::DEFAULTS {
+            assert_eq!(StatusSegment::from_name(name).name(), *name);
+        }
+        let plugin = StatusSegment::from_name("acme.battery");
+        assert_eq!(plugin, StatusSegment::Plugin("acme.battery".into()));
+        assert_eq!(plugin.name(), "acme.battery");
+    }
+
+    #[test]
+    fn contributed_segments_follow_configured_ones() {
+        let names = vec!["time".to_string(), "acme.battery".to_string()];
+        let shown = segments(&names, &["acme.battery", "acme.vpn"]);
+        assert_eq!(
+            shown,
+            [
+                StatusSegment::Time,
+                StatusSegment::Plugin("acme.battery".into()),
+                StatusSegment::Plugin("acme.vpn".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_at_rest() {
+        assert_eq!(scroll_text(0), "");
+        assert_eq!(scroll_text(12), "↑ 12");
+        assert_eq!(notifications_text(0), "");
+        assert_eq!(notifications_text(3), "● 3");
+    }
+}
@@ -1,26 +1,79 @@
-use std::sync::mpsc::{self, Receiver, Sender};
-use std::sync::Arc;
-use std::time::Duration;
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 
 use alacritty_terminal::event::{Event as AlacrittyEvent, EventListener};
 use alacritty_terminal::grid::Dimensions;
+use alacritty_terminal::index::{Column, Line, Point};
+use alacritty_terminal::term::color::Colors;
 use alacritty_terminal::term::test::TermSize;
-use alacritty_terminal::term::{self, Term, TermDamage};
-use alacritty_terminal::vte::ansi::{self, StdSyncHandler};
+use alacritty_terminal::term::{self, Term, TermDamage, TermMode};
+use alacritty_terminal::vte::ansi::{self, NamedColor, Rgb, StdSyncHandler};
+use tracing::warn;
 
 use crate::config::theme::{RgbColor, Theme};
+use crate::crash::{self, Failure};
 use crate::event::TermEvent;
+use crate::power;
+use crate::prompt_time::PromptTime;
+use crate::terminal::history::LineCounter;
+use crate::terminal::metadata::{PublishedMetadata, TermMetadata};
+use crate::terminal::osc_notify::NotifyScanner;
+use crate::terminal::progress::ProgressScanner;
+use crate::terminal::query::QueryScanner;
+use crate::terminal::search::{self, Needle, SearchMatch};
+use crate::terminal::shell_integration::{PromptMark, PromptScanner, ShellIntegrationStatus};
 use crate::terminal::spsc;
+use crate::terminal::throughput::{Flow, ThroughputGuard};
 
 const PARSER_CONTROL_QUEUE_DEPTH: usize = 512;
 const PARSER_INPUT_QUEUE_DEPTH: usize = 2048;
 // Reduced from 5ms to 1ms for lower latency during high-throughput scenarios
-const PARSER_IDLE_PARK_MS: u64 = 1;
+const PARSER_IDLE_PARK: Duration = Duration::from_millis(1);
+/// Commands remembered from shell integration marks
+const MAX_CAPTURED_COMMANDS: usize = 500;
+/// Output held while paused before the parser stops taking more, which
+/// leaves the rest in the PTY and blocks the program writing it
+const MAX_HELD_BYTES: usize = 64 * 1024 * 1024;
+/// Shortest and longest wait of the PTY reader for room in a full parser
+/// queue; it backs off between the two rather than spinning
+const STALL_BACKOFF_MIN: Duration = Duration::from_micros(100);
+const STALL_BACKOFF_MAX: Duration = Duration::from_millis(10);
+/// A wait this long is logged: the parser can't keep up with the program
+const STALL_WARN: Duration = Duration::from_secs(1);
+/// A query the parser thread leaves unanswered this long means it hung
+const QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long queries wait once it has, so the UI keeps going without it
+const HUNG_QUERY_TIMEOUT: Duration = Duration::from_millis(5);
+/// Bytes of output per screen cell kept while fast-forwarding, leaving room
+/// for escape sequences
+const FAST_FORWARD_BYTES_PER_CELL: usize = 4;
+
+/// Modes a program may have left set, put back for a new shell: the main
+/// screen, no scroll region (the cursor stays put), plain text, normal
+/// cursor and keypad keys, no bracketed paste, mouse or focus reports, a
+/// visible cursor and autowrap
+const REATTACH_RESET: &str = "\x1b[?1049l\x1b7\x1b[r\x1b8\x1b[0m\x1b[?1l\x1b>\x1b[?2004l\
+    \x1b[?1000l\x1b[?1002l\x1b[?1003l\x1b[?1006l\x1b[?1004l\x1b[?25h\x1b[?7h";
+
+/// Formats the reply to an OSC 4/10/11/12 color query
+type ColorFormatter = Arc<dyn Fn(Rgb) -> String + Sync + Send>;
+
+/// A reply to a terminal query, queued in the order the queries arrived
+enum Reply {
+    Bytes(Vec<u8>),
+    /// A color query, answered by the parser thread which owns the palette
+    Color(usize, ColorFormatter),
+}
 
 /// Event listener that collects events
 #[derive(Clone)]
 struct Listener {
     sender: std::sync::mpsc::Sender<TermEvent>,
+    /// Replies to terminal queries, written back to the PTY once the
+    /// parser stops
+    replies: Sender<Reply>,
 }
 
 impl EventListener for Listener {
@@ -29,9 +82,19 @@ impl EventListener for Listener {
             AlacrittyEvent::Title(title) => {
                 let _ = self.sender.send(TermEvent::TitleChanged(title));
             }
+            // Popping the title stack (CSI 23 t) back to before any title
+            AlacrittyEvent::ResetTitle => {
+                let _ = self.sender.send(TermEvent::TitleChanged(String::new()));
+            }
             AlacrittyEvent::Bell => {
                 let _ = self.sender.send(TermEvent::Bell);
             }
+            AlacrittyEvent::PtyWrite(text) => {
+                let _ = self.replies.send(Reply::Bytes(text.into_bytes()));
+            }
+            AlacrittyEvent::ColorRequest(index, format) => {
+                let _ = self.replies.send(Reply::Color(index, format));
+            }
             _ => {}
         }
     }
@@ -41,6 +104,399 @@ impl EventListener for Listener {
 struct TermInner {
     term: Term<Listener>,
     processor: ansi::Processor<StdSyncHandler>,
+    queries: QueryScanner,
+    /// Where replies to terminal queries go: the PTY attached, which a
+    /// restart replaces
+    pty_reply: Sender<Vec<u8>>,
+    replies: Receiver<Reply>,
+    /// Theme of the most recent render extraction, used to answer color
+    /// queries; the configured one until the first
+    theme: Arc<Theme>,
+    prompts: PromptScanner,
+    /// Shell integration sequences received so far
+    integration: ShellIntegrationStatus,
+    progress: ProgressScanner,
+    notify: NotifyScanner,
+    /// Where the prompt being drawn starts (OSC 133;A), as an absolute position
+    prompt_start: Option<(usize, usize)>,
+    /// Text of the last complete prompt; empty if it could not be read
+    prompt: Option<String>,
+    /// Where the command line being typed starts (OSC 133;B), as an absolute
+    /// line counted from the top of the history and a column
+    command_start: Option<(usize, usize)>,
+    /// Commands run in this terminal, oldest first
+    commands: VecDeque<String>,
+    /// Command executing since OSC 133;C (empty if unreadable)
+    running: Option<String>,
+    lines: LineCounter,
+    /// When each command ran, by the id of the line it was typed on
+    prompt_times: VecDeque<(u64, PromptTime)>,
+    /// Where each command and its output are, oldest first
+    blocks: VecDeque<BlockSpan>,
+    /// Named marks by line id, in line order
+    marks: Vec<(u64, String)>,
+    /// Output held back while paused, applied on resume
+    held: Option<VecDeque<Vec<u8>>>,
+    held_bytes: usize,
+    throughput: ThroughputGuard,
+    events: Sender<TermEvent>,
+}
+
+impl TermInner {
+    /// Apply output, or hold it while paused
+    fn input(&mut self, data: Vec<u8>) {
+        match &mut self.held {
+            Some(held) => {
+                self.held_bytes += data.len();
+                held.push_back(data);
+            }
+            None => {
+                let flow = self.throughput.feed(data, Instant::now(), self.screenful());
+                self.apply_flow(flow);
+            }
+        }
+    }
+
+    /// Let the throughput guard notice time passing; true if it had output
+    /// to apply
+    fn poll_throughput(&mut self) -> bool {
+        if !self.throughput.is_skipping() {
+            return false;
+        }
+        let flow = self.throughput.poll(Instant::now(), self.screenful());
+        self.apply_flow(flow)
+    }
+
+    fn apply_flow(&mut self, flow: Flow) -> bool {
+        match flow {
+            Flow::Parse(data) => self.advance(&data),
+            Flow::Skipping { tail, skipped } => {
+                self.advance(&tail);
+                let _ = self.events.send(TermEvent::FastForward(Some(skipped)));
+            }
+            Flow::CaughtUp(tail) => {
+                self.advance(&tail);
+                let _ = self.events.send(TermEvent::FastForward(None));
+            }
+            Flow::Wait => return false,
+        }
+        true
+    }
+
+    /// Output bytes that roughly fill the screen
+    fn screenful(&self) -> usize {
+        self.term.columns() * self.term.screen_lines() * FAST_FORWARD_BYTES_PER_CELL
+    }
+
+    /// Paused with as much held as allowed
+    fn held_full(&self) -> bool {
+        self.held.is_some() && self.held_bytes >= MAX_HELD_BYTES
+    }
+
+    fn metadata(&self) -> TermMetadata {
+        let grid = self.term.grid();
+        let cursor = grid.cursor.point;
+        TermMetadata {
+            cols: self.term.columns() as u16,
+            rows: self.term.screen_lines() as u16,
+            cursor: (cursor.column.0 as u16, cursor.line.0 as u16),
+            display_offset: grid.display_offset(),
+            history_size: grid.history_size(),
+            alt_screen: self.term.mode().contains(TermMode::ALT_SCREEN),
+        }
+    }
+
+    /// Pause or resume; resuming catches up on everything held
+    fn set_paused(&mut self, paused: bool) {
+        if paused {
+            self.held.get_or_insert_with(VecDeque::new);
+        } else if let Some(held) = self.held.take() {
+            self.held_bytes = 0;
+            for data in held {
+                self.advance(&data);
+            }
+        }
+    }
+
+    fn advance(&mut self, data: &[u8]) {
+        if let Some(progress) = self.progress.scan(data) {
+            let _ = self.events.send(TermEvent::Progress(progress));
+        }
+        for notification in self.notify.scan(data) {
+            let _ = self.events.send(TermEvent::Notify {
+                title: notification.title,
+                body: notification.body,
+            });
+        }
+        // Feed the parser up to each prompt mark, so the cursor is where the
+        // shell emitted it, and each query we answer, so replies go out in
+        // the order of the queries
+        let marks = self.prompts.scan(data).into_iter();
+        let mut found: Vec<_> = marks.map(|(end, mark)| (end, Ok(mark))).collect();
+        let queries = self.queries.scan(data).into_iter();
+        found.extend(queries.map(|(end, reply)| (end, Err(reply))));
+        found.sort_by_key(|(end, _)| *end);
+        let mut replies = Vec::new();
+        let mut start = 0;
+        for (end, found) in found {
+            self.processor.advance(&mut self.term, &data[start..end]);
+            self.observe_lines();
+            match found {
+                Ok(mark) => self.on_prompt_mark(mark),
+                Err(reply) => {
+                    self.take_replies(&mut replies);
+                    replies.extend_from_slice(&reply);
+                }
+            }
+            start = end;
+        }
+        self.processor.advance(&mut self.term, &data[start..]);
+        self.observe_lines();
+        self.take_replies(&mut replies);
+        if !replies.is_empty() {
+            let _ = self.pty_reply.send(replies);
+        }
+    }
+
+    /// Add the replies alacritty queued to `out`, in order
+    fn take_replies(&mut self, out: &mut Vec<u8>) {
+        while let Ok(reply) = self.replies.try_recv() {
+            match reply {
+                Reply::Bytes(bytes) => out.extend_from_slice(&bytes),
+                Reply::Color(index, format) => {
+                    let color = palette_color(index, &self.theme, self.term.colors());
+                    let reply = format(Rgb {
+                        r: color.r,
+                        g: color.g,
+                        b: color.b,
+                    });
+                    out.extend_from_slice(reply.as_bytes());
+                }
+            }
+        }
+    }
+
+    /// Take a new PTY's output from here on, after putting back the modes
+    /// the old program left set and writing `separator` below its output
+    fn reattach(&mut self, reply: Sender<Vec<u8>>, separator: &str) {
+        self.pty_reply = reply;
+        // Nothing half-read of the old output carries over
+        self.processor = ansi::Processor::new();
+        self.queries = QueryScanner::default();
+        self.prompts = PromptScanner::default();
+        self.progress = ProgressScanner::default();
+        self.notify = NotifyScanner::default();
+        self.integration = ShellIntegrationStatus::default();
+        self.close_block();
+        self.prompt_start = None;
+        self.command_start = None;
+        self.running = None;
+        let text = format!("{REATTACH_RESET}\r\n\x1b[2m{separator}\x1b[0m\r\n");
+        self.advance(text.as_bytes());
+    }
+
+    fn on_prompt_mark(&mut self, mark: PromptMark) {
+        if let PromptMark::WorkingDirectory(path) = mark {
+            self.integration.reported_cwd = Some(path);
+            return;
+        }
+        self.integration.prompt_marks = true;
+        match mark {
+            PromptMark::PromptStart => {
+                self.prompt_start = Some(self.cursor_absolute());
+                self.command_start = None;
+            }
+            PromptMark::CommandStart => {
+                let prompt = self.prompt_start.take().and_then(|at| self.text_since(at));
+                self.prompt = Some(prompt.unwrap_or_default());
+                self.command_start = Some(self.cursor_absolute());
+            }
+            PromptMark::CommandExecuted => {
+                if let Some((line, column)) = self.command_start {
+                    if self.prompt_times.len() == MAX_CAPTURED_COMMANDS {
+                        self.prompt_times.pop_front();
+                    }
+                    let time = PromptTime::new(SystemTime::now());
+                    self.prompt_times.push_back((self.lines.id(line), time));
+                    if self.blocks.len() == MAX_CAPTURED_COMMANDS {
+                        self.blocks.pop_front();
+                    }
+                    let (output, output_column) = self.cursor_absolute();
+                    self.blocks.push_back(BlockSpan {
+                        command: (self.lines.id(line), column),
+                        output: (self.lines.id(output), output_column),
+                        end: None,
+                    });
+                }
+                let command = self.command_start.take().and_then(|at| self.text_since(at));
+                self.running = Some(command.clone().unwrap_or_default());
+                if let Some(command) = command {
+                    if self.commands.back() != Some(&command) {
+                        if self.commands.len() == MAX_CAPTURED_COMMANDS {
+                            self.commands.pop_front();
+                        }
+                        self.commands.push_back(command);
+                    }
+                }
+            }
+            PromptMark::CommandFinished(exit_code) => {
+                self.command_start = None;
+                self.close_block();
+                if let Some(command) = self.running.take() {
+                    let last = self.prompt_times.back_mut();
+                    if let Some((_, time)) = last.filter(|(_, time)| time.duration.is_none()) {
+                        time.duration = time.started.elapsed().ok();
+                        time.exit_code = exit_code;
+                    }
+                    let command = (!command.is_empty()).then_some(command);
+                    let _ = self
+                        .events
+                        .send(TermEvent::CommandFinished { command, exit_code });
+                }
+            }
+            PromptMark::WorkingDirectory(_) => {}
+        }
+    }
+
+    /// End the output of a command still running at the cursor
+    fn close_block(&mut self) {
+        let (line, column) = self.cursor_absolute();
+        let end = (self.lines.id(line), column);
+        if let Some(block) = self.blocks.back_mut().filter(|block| block.end.is_none()) {
+            block.end = Some(end);
+        }
+    }
+
+    /// The command whose line or output is at `row` of the view, with its
+    /// output; `None` on the alternate screen
+    fn block_at(&self, row: usize) -> Option<CommandBlock> {
+        if self.term.mode().contains(TermMode::ALT_SCREEN) {
+            return None;
+        }
+        let grid = self.term.grid();
+        let line = grid.history_size() - grid.display_offset() + row;
+        let position = |(id, column): (u64, usize)| Some((self.lines.line(id)?, column));
+        // A command still running has output up to the cursor
+        let cursor = self.cursor_absolute();
+        let end_of = |block: &BlockSpan| block.end.and_then(position).unwrap_or(cursor);
+        let block = self.blocks.iter().rev().find(|block| {
+            let Some(command) = position(block.command) else {
+                return false;
+            };
+            let end = end_of(block);
+            // Output ending at a line's start leaves that line to the prompt
+            command.0 <= line && (line < end.0 || (line == end.0 && end.1 > 0))
+        })?;
+        let output = position(block.output)?;
+        let end = end_of(block);
+        let command = self.text_between(position(block.command)?, output)?;
+        let command = command.trim();
+        let output = self.text_between(output, end).unwrap_or_default();
+        (!command.is_empty()).then(|| CommandBlock {
+            command: command.to_string(),
+            output: output.trim_end().to_string(),
+        })
+    }
+
+    /// Keep line ids in step with the primary screen's history
+    fn observe_lines(&mut self) {
+        if !self.term.mode().contains(TermMode::ALT_SCREEN) {
+            self.lines.observe(self.term.grid());
+        }
+    }
+
+    /// Prompt times of commands typed on lines in view, by screen row
+    fn prompt_times_in_view(&self) -> Vec<(u16, PromptTime)> {
+        if self.term.mode().contains(TermMode::ALT_SCREEN) {
+            return Vec::new();
+        }
+        let grid = self.term.grid();
+        let top = grid.history_size() - grid.display_offset();
+        self.prompt_times
+            .iter()
+            .filter_map(|&(id, time)| {
+                let row = self.lines.line(id)?.checked_sub(top)?;
+                (row < grid.screen_lines()).then_some((row as u16, time))
+            })
+            .collect()
+    }
+
+    /// Mark the line at the top of the view; a mark already there is
+    /// renamed. Returns the line, or `None` on the alternate screen.
+    fn add_mark(&mut self, name: String) -> Option<usize> {
+        if self.term.mode().contains(TermMode::ALT_SCREEN) {
+            return None;
+        }
+        let grid = self.term.grid();
+        let line = grid.history_size() - grid.display_offset();
+        let id = self.lines.id(line);
+        match self.marks.binary_search_by_key(&id, |(id, _)| *id) {
+            Ok(i) => self.marks[i].1 = name,
+            Err(i) => self.marks.insert(i, (id, name)),
+        }
+        Some(line)
+    }
+
+    /// Marks on lines still in the history, top first
+    fn marks(&mut self) -> Vec<Mark> {
+        if self.term.mode().contains(TermMode::ALT_SCREEN) {
+            return Vec::new();
+        }
+        let lines = &self.lines;
+        self.marks.retain(|(id, _)| lines.line(*id).is_some());
+        self.marks
+            .iter()
+            .filter_map(|(id, name)| {
+                let line = lines.line(*id)?;
+                Some(Mark {
+                    name: name.clone(),
+                    line,
+                })
+            })
+            .collect()
+    }
+
+    fn cursor_absolute(&self) -> (usize, usize) {
+        let grid = self.term.grid();
+        let point = grid.cursor.point;
+        let line = grid.history_size() as i32 + point.line.0;
+        (line.max(0) as usize, point.column.0)
+    }
+
+    /// Text from an absolute position up to the cursor, trimmed; `None` when
+    /// empty, on the alternate screen, or no longer on the grid.
+    fn text_since(&self, start: (usize, usize)) -> Option<String> {
+        let text = self.text_between(start, self.cursor_absolute())?;
+        let text = text.trim();
+        (!text.is_empty()).then(|| text.to_string())
+    }
+
+    /// Text from one absolute position up to another, which it leaves out;
+    /// `None` when there is none, on the alternate screen, or no longer on
+    /// the grid.
+    fn text_between(&self, (line, column): (usize, usize), end: (usize, usize)) -> Option<String> {
+        if self.term.mode().contains(TermMode::ALT_SCREEN) {
+            return None;
+        }
+        let grid = self.term.grid();
+        let start_line = line as i32 - grid.history_size() as i32;
+        if start_line < -(grid.history_size() as i32) || start_line >= grid.screen_lines() as i32 {
+            return None;
+        }
+        let start = Point::new(Line(start_line), Column(column));
+        // End just before `end`; at column 0 that is the previous line's end
+        let end_line = Line(end.0 as i32 - grid.history_size() as i32);
+        let last = self.term.last_column();
+        let end = if end.1 > 0 {
+            Point::new(end_line, Column(end.1 - 1).min(last))
+        } else {
+            Point::new(end_line - 1, last)
+        };
+        if end < start {
+            return None;
+        }
+        Some(self.term.bounds_to_string(start, end))
+    }
 }
 
 /// Terminal emulator wrapping alacritty_terminal
@@ -48,8 +504,83 @@ pub struct TerminalEmulator {
     control_tx: spsc::Producer<ControlCommand>,
     input_tx: Option<spsc::Producer<Vec<u8>>>,
     event_rx: Receiver<TermEvent>,
+    reply_rx: Option<Receiver<Vec<u8>>>,
     parser_waker: std::thread::Thread,
     parser_thread: Option<std::thread::JoinHandle<()>>,
+    /// Set if the parser thread panicked; the screen no longer updates
+    failure: Failure,
+    stalls: Arc<Mutex<InputStalls>>,
+    metadata: Arc<PublishedMetadata>,
+    /// When a query first went unanswered; the next answer clears it
+    hung: Mutex<Option<Instant>>,
+    screen_tx: Sender<ScreenRequest>,
+}
+
+/// A theme to extract the screen with and where to send it
+type ScreenRequest = (Arc<Theme>, Sender<Vec<GridLine>>);
+
+/// Reads a terminal's screen from any thread, without going through the
+/// emulator's owner
+#[derive(Clone)]
+pub struct ScreenReader {
+    screen_tx: Sender<ScreenRequest>,
+    parser_waker: std::thread::Thread,
+}
+
+impl ScreenReader {
+    /// The visible grid, as [`TerminalEmulator::extract_grid`] has it.
+    /// `None` when the parser stopped or didn't answer in time.
+    pub fn extract_grid(&self, theme: &Arc<Theme>) -> Option<Vec<GridLine>> {
+        let (tx, rx) = mpsc::channel();
+        self.screen_tx.send((Arc::clone(theme), tx)).ok()?;
+        self.parser_waker.unpark();
+        rx.recv_timeout(QUERY_TIMEOUT).ok()
+    }
+}
+
+/// Time the PTY reader spent waiting for room in the parser's input queue.
+/// While it waits, output stays in the PTY and the program writing it
+/// blocks, so the parser sets the pace.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InputStalls {
+    pub count: u64,
+    pub total: Duration,
+    pub longest: Duration,
+}
+
+impl InputStalls {
+    fn record(&mut self, waited: Duration) {
+        self.count += 1;
+        self.total += waited;
+        self.longest = self.longest.max(waited);
+    }
+}
+
+/// A named bookmark in a pane's output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mark {
+    pub name: String,
+    /// Absolute buffer line, counted from the oldest line of history; it
+    /// moves up as the history is trimmed
+    pub line: usize,
+}
+
+/// A command run at the prompt and its output, found through shell
+/// integration marks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandBlock {
+    pub command: String,
+    /// Output so far while the command runs, without trailing whitespace
+    pub output: String,
+}
+
+/// Where a command was typed, where its output starts and, once it
+/// finished, ends, as line ids and columns
+#[derive(Debug, Clone, Copy)]
+struct BlockSpan {
+    command: (u64, usize),
+    output: (u64, usize),
+    end: Option<(u64, usize)>,
 }
 
 /// Result of incrementally extracting the viewport grid.
@@ -65,6 +596,81 @@ impl GridDelta {
     }
 }
 
+/// Terminal modes that affect how UI input is translated for the PTY
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InputModes {
+    /// Alternate screen is active (full-screen apps like vim, less)
+    pub alt_screen: bool,
+    /// DECCKM: arrow keys send SS3 (`ESC O A`) instead of CSI
+    pub app_cursor: bool,
+    /// DECKPAM: keypad keys send SS3 sequences instead of what they type
+    pub app_keypad: bool,
+    /// DECSET 1007: wheel scrolls become arrow keys on the alternate screen
+    pub alternate_scroll: bool,
+    /// Application has requested mouse reporting
+    pub mouse_reporting: bool,
+    /// DECSET 1006: mouse reports use the SGR encoding
+    pub sgr_mouse: bool,
+    /// DECSET 1005: mouse report coordinates are UTF-8 encoded
+    pub utf8_mouse: bool,
+    /// DECSET 1004: application wants focus-in/out events
+    pub focus_reporting: bool,
+}
+
+impl InputModes {
+    /// Whether wheel scrolling should be sent as arrow keys rather than
+    /// scrolling the (empty) alternate-screen history.
+    pub fn scroll_as_arrows(&self) -> bool {
+        self.alt_screen && self.alternate_scroll && !self.mouse_reporting
+    }
+
+    /// Arrow key sequence for one line of scrolling (positive = up)
+    pub fn arrow_key(&self, up: bool) -> &'static [u8] {
+        match (self.app_cursor, up) {
+            (true, true) => b"\x1bOA",
+            (true, false) => b"\x1bOB",
+            (false, true) => b"\x1b[A",
+            (false, false) => b"\x1b[B",
+        }
+    }
+
+    /// Focus event to send when the pane gains or loses focus, if requested
+    pub fn focus_report(&self, focused: bool) -> Option<&'static [u8]> {
+        match (self.focus_reporting, focused) {
+            (false, _) => None,
+            (true, true) => Some(b"\x1b[I"),
+            (true, false) => Some(b"\x1b[O"),
+        }
+    }
+
+    /// Mouse report of one wheel click at the 0-based cell `col`, `row`, if
+    /// the application asked for mouse reports. `button` is the X11 wheel
+    /// button: 4 and 5 scroll up and down, 6 and 7 left and right. `None`
+    /// also when the legacy encoding can't reach the cell.
+    pub fn wheel_report(&self, button: u8, col: u16, row: u16) -> Option<Vec<u8>> {
+        if !self.mouse_reporting || !(4..=7).contains(&button) {
+            return None;
+        }
+        // Wheel buttons are 64 and up, after the three pressable ones
+        let code = 64 + u32::from(button - 4);
+        let (x, y) = (u32::from(col) + 1, u32::from(row) + 1);
+        if self.sgr_mouse {
+            return Some(format!("\x1b[<{code};{x};{y}M").into_bytes());
+        }
+        let mut report = b"\x1b[M".to_vec();
+        for value in [code, x, y] {
+            let value = value + 32;
+            if self.utf8_mouse {
+                let ch = char::from_u32(value).filter(|_| value <= 2047)?;
+                report.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+            } else {
+                report.push(u8::try_from(value).ok()?);
+            }
+        }
+        Some(report)
+    }
+}
+
 enum ControlCommand {
     Input(Vec<u8>),
     Resize(u16, u16),
@@ -72,6 +678,32 @@ enum ControlCommand {
     QuerySize(Sender<(u16, u16)>),
     QueryCursor(Sender<(u16, u16)>),
     QueryDisplayOffset(Sender<usize>),
+    QueryHistorySize(Sender<usize>),
+    QueryWrapped(Sender<Vec<bool>>),
+    /// Answered straight away, to tell a hung parser thread from a slow one
+    Ping(Sender<()>),
+    SetTextProgress(bool),
+    SetOutputLimit(u64),
+    /// Replies once applied, so output sent afterwards is held or not
+    SetPaused(bool, Sender<()>),
+    QueryHeldBytes(Sender<Option<usize>>),
+    QueryInputModes(Sender<InputModes>),
+    QueryCommands(Sender<Vec<String>>),
+    QueryCommandLine(Sender<Option<String>>),
+    QueryPrompt(Sender<Option<String>>),
+    QueryShellIntegration(Sender<ShellIntegrationStatus>),
+    QueryPromptTimes(Sender<Vec<(u16, PromptTime)>>),
+    QueryBlock(usize, Sender<Option<CommandBlock>>),
+    AddMark(String, Sender<Option<usize>>),
+    QueryMarks(Sender<Vec<Mark>>),
+    Search(Needle, usize, Sender<Vec<SearchMatch>>),
+    ScrollToLine(usize),
+    /// Read output from `input` and send replies to `reply` instead
+    Reattach {
+        input: spsc::Consumer<Vec<u8>>,
+        reply: Sender<Vec<u8>>,
+        separator: String,
+    },
     ExtractFull {
         theme: Arc<Theme>,
         reply: Sender<Vec<GridLine>>,
@@ -91,46 +723,115 @@ struct DeltaExtractReply {
 
 impl TerminalEmulator {
     pub fn new(cols: u16, rows: u16) -> Self {
+        Self::with_theme(cols, rows, Arc::new(Theme::default()))
+    }
+
+    /// A terminal answering color queries from `theme` until the first
+    /// render extraction brings its own
+    pub fn with_theme(cols: u16, rows: u16, theme: Arc<Theme>) -> Self {
         let (event_tx, event_rx) = mpsc::channel();
+        let (reply_tx, reply_rx) = mpsc::channel();
         let (control_tx, control_rx) = spsc::channel(PARSER_CONTROL_QUEUE_DEPTH);
+        let (screen_tx, screen_rx) = mpsc::channel::<ScreenRequest>();
         let (input_tx, input_rx) = spsc::channel::<Vec<u8>>(PARSER_INPUT_QUEUE_DEPTH);
+        let failure = Failure::default();
+        let parser_failure = failure.clone();
+        let metadata = Arc::new(PublishedMetadata::default());
+        let published = Arc::clone(&metadata);
 
         let parser_thread = std::thread::Builder::new()
             .name("term-parser".into())
             .spawn(move || {
-                let listener = Listener { sender: event_tx };
-                let size = TermSize::new(cols as usize, rows as usize);
-                let term = Term::new(term::Config::default(), &size, listener);
-                let processor = ansi::Processor::new();
-                let mut inner = TermInner { term, processor };
-                let mut render_cache: Vec<GridLine> = Vec::new();
-
-                loop {
-                    let mut did_work = false;
-
-                    while let Some(data) = input_rx.try_pop() {
-                        let TermInner {
-                            ref mut term,
-                            ref mut processor,
-                        } = inner;
-                        processor.advance(term, &data);
-                        did_work = true;
-                    }
-
-                    while let Some(cmd) = control_rx.try_pop() {
-                        did_work = true;
-                        if handle_control_command(cmd, &mut inner, &mut render_cache) {
-                            return;
+                crash::guard(&parser_failure, move || {
+                    let (replies_tx, replies) = mpsc::channel();
+                    let listener = Listener {
+                        sender: event_tx.clone(),
+                        replies: replies_tx,
+                    };
+                    // Term::resize clamps to these, but Term::new does not
+                    let size = TermSize::new(
+                        (cols as usize).max(term::MIN_COLUMNS),
+                        (rows as usize).max(term::MIN_SCREEN_LINES),
+                    );
+                    let term = Term::new(term::Config::default(), &size, listener);
+                    let processor = ansi::Processor::new();
+                    let mut inner = TermInner {
+                        term,
+                        processor,
+                        queries: QueryScanner::default(),
+                        pty_reply: reply_tx,
+                        replies,
+                        theme,
+                        prompts: PromptScanner::default(),
+                        integration: ShellIntegrationStatus::default(),
+                        progress: ProgressScanner::default(),
+                        notify: NotifyScanner::default(),
+                        prompt_start: None,
+                        prompt: None,
+                        command_start: None,
+                        commands: VecDeque::new(),
+                        running: None,
+                        lines: LineCounter::default(),
+                        prompt_times: VecDeque::new(),
+                        blocks: VecDeque::new(),
+                        marks: Vec::new(),
+                        held: None,
+                        held_bytes: 0,
+                        throughput: ThroughputGuard::default(),
+                        events: event_tx,
+                    };
+                    let mut render_cache: Vec<GridLine> = Vec::new();
+                    let mut input_rx = input_rx;
+                    let take_input = |inner: &mut TermInner, input_rx: &spsc::Consumer<_>| {
+                        let mut took = false;
+                        while !inner.held_full() {
+                            let Some(data) = input_rx.try_pop() else {
+                                break;
+                            };
+                            inner.input(data);
+                            took = true;
+                        }
+                        took
+                    };
+
+                    published.publish(inner.metadata());
+                    loop {
+                        let mut did_work = take_input(&mut inner, &input_rx);
+                        did_work |= inner.poll_throughput();
+
+                        while let Some(cmd) = control_rx.try_pop() {
+                            did_work = true;
+                            // Output queued before the command applies first
+                            take_input(&mut inner, &input_rx);
+                            if let ControlCommand::Reattach {
+                                input,
+                                reply,
+                                separator,
+                            } = cmd
+                            {
+                                input_rx = input;
+                                inner.reattach(reply, &separator);
+                                continue;
+                            }
+                            if handle_control_command(cmd, &mut inner, &mut render_cache) {
+                                return;
+                            }
+                        }
+                        while let Ok((theme, reply)) = screen_rx.try_recv() {
+                            take_input(&mut inner, &input_rx);
+                            let _ = reply.send(extract_grid_full_from_term(&inner.term, &theme));
                         }
-                    }
 
-                    if !did_work {
-                        if input_rx.is_producer_closed() && control_rx.is_producer_closed() {
-                            return;
+                        if did_work {
+                            published.publish(inner.metadata());
+                        } else {
+                            if input_rx.is_producer_closed() && control_rx.is_producer_closed() {
+                                return;
+                            }
+                            std::thread::park_timeout(power::idle_park(PARSER_IDLE_PARK));
                         }
-                        std::thread::park_timeout(Duration::from_millis(PARSER_IDLE_PARK_MS));
                     }
-                }
+                })
             })
             .expect("spawn terminal parser thread");
         let parser_waker = parser_thread.thread().clone();
@@ -139,8 +840,22 @@ impl TerminalEmulator {
             control_tx,
             input_tx: Some(input_tx),
             event_rx,
+            reply_rx: Some(reply_rx),
             parser_waker,
             parser_thread: Some(parser_thread),
+            failure,
+            stalls: Arc::default(),
+            metadata,
+            hung: Mutex::default(),
+            screen_tx,
+        }
+    }
+
+    /// A handle other threads can read the screen through
+    pub fn screen_reader(&self) -> ScreenReader {
+        ScreenReader {
+            screen_tx: self.screen_tx.clone(),
+            parser_waker: self.parser_waker.clone(),
         }
     }
 
@@ -150,11 +865,7 @@ impl TerminalEmulator {
             let _ = enqueue_input_bytes(input_tx, &self.parser_waker, data);
             return;
         }
-        let _ = send_control(
-            &self.control_tx,
-            &self.parser_waker,
-            ControlCommand::Input(data.to_vec()),
-        );
+        self.send(ControlCommand::Input(data.to_vec()));
     }
 
     /// Drain pending events
@@ -166,24 +877,46 @@ impl TerminalEmulator {
         events
     }
 
+    /// Why the parser thread stopped, if it panicked. The emulator answers
+    /// nothing useful after that and the pane needs a restart.
+    pub fn failure(&self) -> Option<String> {
+        self.failure.get()
+    }
+
+    /// How long output has waited on the parser so far
+    pub fn input_stalls(&self) -> InputStalls {
+        *self.stalls.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// How long the parser thread has gone without answering, once a query
+    /// to it timed out; `None` while it answers. Asks it again, briefly, so
+    /// this clears once it catches up.
+    pub fn unresponsive(&self) -> Option<Duration> {
+        self.hung_since()?;
+        self.query(ControlCommand::Ping);
+        self.hung_since().map(|since| since.elapsed())
+    }
+
+    /// Commands waiting for the parser thread, for diagnostics
+    pub fn queued_commands(&self) -> usize {
+        self.control_tx.queued()
+    }
+
+    /// Size, cursor and view as of the parser's last batch of work, read
+    /// without waiting on it. It may trail commands just sent, which the
+    /// blocking queries such as [`Self::size`] apply first.
+    pub fn metadata(&self) -> TermMetadata {
+        self.metadata.load()
+    }
+
     /// Get current dimensions
     pub fn size(&self) -> (u16, u16) {
-        let (tx, rx) = mpsc::channel();
-        let _ = send_control_blocking(
-            &self.control_tx,
-            &self.parser_waker,
-            ControlCommand::QuerySize(tx),
-        );
-        rx.recv().unwrap_or((0, 0))
+        self.query(ControlCommand::QuerySize).unwrap_or((0, 0))
     }
 
     /// Resize the terminal
     pub fn resize(&self, cols: u16, rows: u16) {
-        let _ = send_control_blocking(
-            &self.control_tx,
-            &self.parser_waker,
-            ControlCommand::Resize(cols, rows),
-        );
+        self.send(ControlCommand::Resize(cols, rows));
     }
 
     /// Take the dedicated parser-input handle for the PTY reader thread.
@@ -193,56 +926,164 @@ impl TerminalEmulator {
         self.input_tx.take().map(|input_tx| TerminalEmulatorHandle {
             input_tx,
             parser_waker: self.parser_waker.clone(),
+            reply_rx: self.reply_rx.take(),
+            stalls: Arc::clone(&self.stalls),
         })
     }
 
+    /// A parser-input handle for a new PTY, in place of the one taken
+    /// before: the screen and history stay, and output from the old handle
+    /// is no longer read. `separator` is written dimmed on a line of its
+    /// own below the old output.
+    pub fn reattach_parser_handle(&mut self, separator: &str) -> TerminalEmulatorHandle {
+        let (input_tx, input_rx) = spsc::channel(PARSER_INPUT_QUEUE_DEPTH);
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.input_tx = None;
+        self.send(ControlCommand::Reattach {
+            input: input_rx,
+            reply: reply_tx,
+            separator: separator.to_string(),
+        });
+        TerminalEmulatorHandle {
+            input_tx,
+            parser_waker: self.parser_waker.clone(),
+            reply_rx: Some(reply_rx),
+            stalls: Arc::clone(&self.stalls),
+        }
+    }
+
+    /// Whether each visible row runs on into the next: a line the terminal
+    /// wrapped, rather than one the program broke
+    pub fn wrapped_rows(&self) -> Vec<bool> {
+        self.query(ControlCommand::QueryWrapped).unwrap_or_default()
+    }
+
     /// Get cursor position as (col, row)
     pub fn cursor_position(&self) -> (u16, u16) {
-        let (tx, rx) = mpsc::channel();
-        let _ = send_control_blocking(
-            &self.control_tx,
-            &self.parser_waker,
-            ControlCommand::QueryCursor(tx),
-        );
-        rx.recv().unwrap_or((0, 0))
+        self.query(ControlCommand::QueryCursor).unwrap_or((0, 0))
     }
 
     /// Scroll the display by delta lines (positive = scroll up into history)
     pub fn scroll(&self, delta: i32) {
-        let _ = send_control_blocking(
-            &self.control_tx,
-            &self.parser_waker,
-            ControlCommand::Scroll(delta),
-        );
+        self.send(ControlCommand::Scroll(delta));
+    }
+
+    /// Scroll so an absolute buffer line (counted from the oldest line of
+    /// history) is at the top of the view, or as near as it can get
+    pub fn scroll_to_line(&self, line: usize) {
+        self.send(ControlCommand::ScrollToLine(line));
     }
 
     /// Get current display offset (0 = bottom, >0 = scrolled into history)
     pub fn display_offset(&self) -> usize {
-        let (tx, rx) = mpsc::channel();
-        let _ = send_control_blocking(
-            &self.control_tx,
-            &self.parser_waker,
-            ControlCommand::QueryDisplayOffset(tx),
-        );
-        rx.recv().unwrap_or(0)
+        self.query(ControlCommand::QueryDisplayOffset).unwrap_or(0)
+    }
+
+    /// Lines of scrollback above the screen. With [`Self::display_offset`]
+    /// this places the view in absolute buffer lines, counted from the
+    /// oldest line of history.
+    pub fn history_size(&self) -> usize {
+        self.query(ControlCommand::QueryHistorySize).unwrap_or(0)
+    }
+
+    /// Also read progress from percentages in lines redrawn with CR, not
+    /// just from OSC 9;4
+    pub fn set_text_progress(&self, enabled: bool) {
+        self.send(ControlCommand::SetTextProgress(enabled));
+    }
+
+    /// Output faster than `bytes_per_sec` (0 = no limit) fast-forwards:
+    /// only the latest screenful is parsed until it slows down, and
+    /// [`TermEvent::FastForward`] reports the bytes skipped.
+    pub fn set_output_limit(&self, bytes_per_sec: u64) {
+        self.send(ControlCommand::SetOutputLimit(bytes_per_sec));
+    }
+
+    /// Pause or resume applying output to the grid. While paused, output is
+    /// held back (up to 64 MiB, then the program is blocked) and the screen
+    /// stays as it was; resuming catches up on everything held.
+    pub fn set_paused(&self, paused: bool) {
+        self.query(|tx| ControlCommand::SetPaused(paused, tx));
+    }
+
+    /// Bytes of output held back while paused, `None` when not paused
+    pub fn held_bytes(&self) -> Option<usize> {
+        self.query(ControlCommand::QueryHeldBytes).flatten()
+    }
+
+    /// Get the modes that affect input translation
+    pub fn input_modes(&self) -> InputModes {
+        self.query(ControlCommand::QueryInputModes)
+            .unwrap_or_default()
+    }
+
+    /// Commands captured through shell integration (OSC 133), oldest first
+    pub fn recent_commands(&self) -> Vec<String> {
+        self.query(ControlCommand::QueryCommands)
+            .unwrap_or_default()
+    }
+
+    /// Text typed so far at the prompt, when shell integration marks where it starts
+    pub fn command_line(&self) -> Option<String> {
+        self.query(ControlCommand::QueryCommandLine).flatten()
+    }
+
+    /// Text of the last prompt the shell drew, when shell integration marks it
+    pub fn prompt_text(&self) -> Option<String> {
+        self.query(ControlCommand::QueryPrompt).flatten()
+    }
+
+    /// Which shell integration sequences the shell has sent, to tell whether
+    /// it is set up
+    pub fn shell_integration(&self) -> ShellIntegrationStatus {
+        self.query(ControlCommand::QueryShellIntegration)
+            .unwrap_or_default()
+    }
+
+    /// Mark the line at the top of the view with `name`, renaming a mark
+    /// already there. Returns the line marked; `None` on the alternate
+    /// screen, which has no history to mark.
+    pub fn add_mark(&self, name: impl Into<String>) -> Option<usize> {
+        self.query(|tx| ControlCommand::AddMark(name.into(), tx))
+            .flatten()
+    }
+
+    /// Marks on lines still in the history, top first
+    pub fn marks(&self) -> Vec<Mark> {
+        self.query(ControlCommand::QueryMarks).unwrap_or_default()
+    }
+
+    /// The last `limit` lines of history and screen containing `query`, top
+    /// first; case-insensitive unless the query has an uppercase letter
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchMatch> {
+        let Some(needle) = Needle::new(query) else {
+            return Vec::new();
+        };
+        self.query(|tx| ControlCommand::Search(needle, limit, tx))
+            .unwrap_or_default()
+    }
+
+    /// When the commands typed on lines in view ran, by screen row. Needs
+    /// shell integration (OSC 133); empty on the alternate screen.
+    pub fn prompt_times(&self) -> Vec<(u16, PromptTime)> {
+        self.query(ControlCommand::QueryPromptTimes)
+            .unwrap_or_default()
+    }
+
+    /// The command typed or printing output at `row` of the view, with its
+    /// output. Needs shell integration (OSC 133).
+    pub fn command_block_at(&self, row: u16) -> Option<CommandBlock> {
+        self.query(|tx| ControlCommand::QueryBlock(row as usize, tx))
+            .flatten()
     }
 
     /// Extract terminal grid content for rendering (respects display_offset for scrollback)
     pub fn extract_grid(&self, theme: &Arc<Theme>) -> Vec<GridLine> {
-        let (tx, rx) = mpsc::channel();
-        if send_control_blocking(
-            &self.control_tx,
-            &self.parser_waker,
-            ControlCommand::ExtractFull {
-                theme: Arc::clone(theme),
-                reply: tx,
-            },
-        )
-        .is_err()
-        {
-            return Vec::new();
-        }
-        rx.recv().unwrap_or_default()
+        self.query(|reply| ControlCommand::ExtractFull {
+            theme: Arc::clone(theme),
+            reply,
+        })
+        .unwrap_or_default()
     }
 
     /// Incrementally update a cached grid snapshot using alacritty's damage tracking.
@@ -270,27 +1111,15 @@ impl TerminalEmulator {
         out: &mut Vec<GridLine>,
         timeout: Option<Duration>,
     ) -> (GridDelta, (u16, u16)) {
-        let (tx, rx) = mpsc::channel();
-        if send_control_blocking(
-            &self.control_tx,
-            &self.parser_waker,
-            ControlCommand::ExtractDelta {
+        // Use timeout to avoid blocking main thread during high-throughput
+        let reply = self.query_within(
+            |reply| ControlCommand::ExtractDelta {
                 theme: Arc::clone(theme),
-                reply: tx,
+                reply,
             },
-        )
-        .is_err()
-        {
-            return (GridDelta::default(), (0, 0));
-        }
-
-        // Use timeout to avoid blocking main thread during high-throughput
-        let reply_result = match timeout {
-            Some(t) => rx.recv_timeout(t),
-            None => rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
-        };
-
-        let Ok(reply) = reply_result else {
+            timeout,
+        );
+        let Some(reply) = reply else {
             // Parser busy - return empty delta, keep existing cached grid
             return (GridDelta::default(), (0, 0));
         };
@@ -316,27 +1145,129 @@ impl TerminalEmulator {
 
         (reply.delta, reply.cursor)
     }
+
+    /// Queue a command for the parser thread. A queue that stays full marks
+    /// it unresponsive.
+    fn send(&self, cmd: ControlCommand) {
+        let sent = Instant::now();
+        let timeout = self.query_timeout();
+        if send_control(&self.control_tx, &self.parser_waker, cmd, timeout).is_err()
+            && !self.control_tx.is_consumer_closed()
+        {
+            self.set_hung(sent);
+        }
+    }
+
+    fn query<T>(&self, cmd: impl FnOnce(Sender<T>) -> ControlCommand) -> Option<T> {
+        self.query_within(cmd, None)
+    }
+
+    /// Ask the parser thread something, waiting up to `timeout`. `None` when
+    /// it stopped or didn't answer in time; a query that waited as long as
+    /// a hang takes marks it unresponsive, and any answer clears that.
+    fn query_within<T>(
+        &self,
+        cmd: impl FnOnce(Sender<T>) -> ControlCommand,
+        timeout: Option<Duration>,
+    ) -> Option<T> {
+        let hang = self.query_timeout();
+        let wait = timeout.map_or(hang, |timeout| timeout.min(hang));
+        let sent = Instant::now();
+        let (tx, rx) = mpsc::channel();
+        let reply = match send_control(&self.control_tx, &self.parser_waker, cmd(tx), wait) {
+            Ok(()) => rx.recv_timeout(wait.saturating_sub(sent.elapsed())),
+            Err(_) if self.control_tx.is_consumer_closed() => return None,
+            Err(_) => Err(RecvTimeoutError::Timeout),
+        };
+        match reply {
+            Ok(value) => {
+                *self.hung.lock().unwrap_or_else(|e| e.into_inner()) = None;
+                Some(value)
+            }
+            Err(RecvTimeoutError::Timeout) if wait == hang => {
+                self.set_hung(sent);
+                None
+            }
+            Err(_) => None,
+        }
+    }
+
+    fn hung_since(&self) -> Option<Instant> {
+        *self.hung.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set_hung(&self, since: Instant) {
+        let mut hung = self.hung.lock().unwrap_or_else(|e| e.into_inner());
+        if hung.is_none() {
+            warn!("terminal parser thread stopped answering");
+            *hung = Some(since);
+        }
+    }
+
+    fn query_timeout(&self) -> Duration {
+        if self.hung_since().is_some() {
+            HUNG_QUERY_TIMEOUT
+        } else {
+            QUERY_TIMEOUT
+        }
+    }
 }
 
 /// Handle for sharing emulator with PTY reader thread
 pub struct TerminalEmulatorHandle {
     input_tx: spsc::Producer<Vec<u8>>,
     parser_waker: std::thread::Thread,
+    reply_rx: Option<Receiver<Vec<u8>>>,
+    stalls: Arc<Mutex<InputStalls>>,
 }
 
 impl TerminalEmulatorHandle {
+    /// Queue output for the parser. While its queue is full this waits,
+    /// backing off, and records how long.
     pub fn process(&self, data: &[u8]) {
-        let _ = enqueue_input_bytes(&self.input_tx, &self.parser_waker, data);
+        if data.is_empty() {
+            return;
+        }
+        let mut chunk = data.to_vec();
+        let mut stalled = None;
+        let mut backoff = STALL_BACKOFF_MIN;
+        while let Err(rejected) = self.input_tx.try_push(chunk) {
+            if self.input_tx.is_consumer_closed() {
+                return;
+            }
+            chunk = rejected;
+            stalled.get_or_insert_with(Instant::now);
+            self.parser_waker.unpark();
+            std::thread::sleep(backoff);
+            backoff = (backoff * 2).min(STALL_BACKOFF_MAX);
+        }
+        self.parser_waker.unpark();
+        if let Some(since) = stalled {
+            let waited = since.elapsed();
+            if waited >= STALL_WARN {
+                warn!(?waited, "terminal parser fell behind the program's output");
+            }
+            self.stalls
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .record(waited);
+        }
+    }
+
+    /// Take the receiver for query replies (DA, XTVERSION, XTGETTCAP, ...)
+    /// that must be written back to the PTY.
+    pub fn take_reply_receiver(&mut self) -> Option<Receiver<Vec<u8>>> {
+        self.reply_rx.take()
     }
 }
 
 impl Drop for TerminalEmulator {
     fn drop(&mut self) {
-        let _ = send_control_blocking(
-            &self.control_tx,
-            &self.parser_waker,
-            ControlCommand::Shutdown,
-        );
+        self.send(ControlCommand::Shutdown);
+        // A hung parser thread is left behind rather than waited on forever
+        if self.hung_since().is_some() {
+            return;
+        }
         if let Some(handle) = self.parser_thread.take() {
             let _ = handle.join();
         }
@@ -361,37 +1292,34 @@ fn send_control(
     control_tx: &spsc::Producer<ControlCommand>,
     parser_waker: &std::thread::Thread,
     cmd: ControlCommand,
+    timeout: Duration,
 ) -> Result<(), ControlCommand> {
-    control_tx.push_blocking(cmd)?;
+    control_tx.push_timeout(cmd, timeout)?;
     parser_waker.unpark();
     Ok(())
 }
 
-fn send_control_blocking(
-    control_tx: &spsc::Producer<ControlCommand>,
-    parser_waker: &std::thread::Thread,
-    cmd: ControlCommand,
-) -> Result<(), ControlCommand> {
-    send_control(control_tx, parser_waker, cmd)
-}
-
 fn handle_control_command(
     cmd: ControlCommand,
     inner: &mut TermInner,
     render_cache: &mut Vec<GridLine>,
 ) -> bool {
     match cmd {
-        ControlCommand::Input(data) => {
-            let TermInner {
-                ref mut term,
-                ref mut processor,
-            } = inner;
-            processor.advance(term, &data);
-        }
+        ControlCommand::Input(data) => inner.input(data),
         ControlCommand::Resize(cols, rows) => {
+            use alacritty_terminal::grid::Scroll;
+            let grid = inner.term.grid();
+            let (offset, history) = (grid.display_offset(), grid.history_size());
             inner
                 .term
                 .resize(TermSize::new(cols as usize, rows as usize));
+            // Keep a view scrolled into history at the same place in it
+            if offset > 0 {
+                let grid = inner.term.grid_mut();
+                let target = scaled_offset(offset, history, grid.history_size());
+                let delta = target as i32 - grid.display_offset() as i32;
+                grid.scroll_display(Scroll::Delta(delta));
+            }
         }
         ControlCommand::Scroll(delta) => {
             use alacritty_terminal::grid::Scroll;
@@ -410,12 +1338,90 @@ fn handle_control_command(
         ControlCommand::QueryDisplayOffset(reply) => {
             let _ = reply.send(inner.term.grid().display_offset());
         }
+        ControlCommand::QueryHistorySize(reply) => {
+            let _ = reply.send(inner.term.grid().history_size());
+        }
+        ControlCommand::QueryWrapped(reply) => {
+            use alacritty_terminal::term::cell::Flags;
+            let grid = inner.term.grid();
+            let offset = grid.display_offset() as i32;
+            let last = Column(grid.columns() - 1);
+            let wrapped = (0..grid.screen_lines() as i32).map(|row| {
+                let cell = &grid[Line(row - offset)][last];
+                cell.flags.contains(Flags::WRAPLINE)
+            });
+            let _ = reply.send(wrapped.collect());
+        }
+        ControlCommand::SetTextProgress(enabled) => inner.progress.text = enabled,
+        ControlCommand::SetOutputLimit(limit) => inner.throughput.set_limit(limit),
+        ControlCommand::SetPaused(paused, done) => {
+            inner.set_paused(paused);
+            let _ = done.send(());
+        }
+        ControlCommand::Ping(reply) => {
+            let _ = reply.send(());
+        }
+        ControlCommand::QueryHeldBytes(reply) => {
+            let _ = reply.send(inner.held.is_some().then_some(inner.held_bytes));
+        }
+        ControlCommand::QueryInputModes(reply) => {
+            let mode = inner.term.mode();
+            let _ = reply.send(InputModes {
+                alt_screen: mode.contains(TermMode::ALT_SCREEN),
+                app_cursor: mode.contains(TermMode::APP_CURSOR),
+                app_keypad: mode.contains(TermMode::APP_KEYPAD),
+                alternate_scroll: mode.contains(TermMode::ALTERNATE_SCROLL),
+                mouse_reporting: mode.intersects(TermMode::MOUSE_MODE),
+                sgr_mouse: mode.contains(TermMode::SGR_MOUSE),
+                utf8_mouse: mode.contains(TermMode::UTF8_MOUSE),
+                focus_reporting: mode.contains(TermMode::FOCUS_IN_OUT),
+            });
+        }
+        ControlCommand::QueryCommands(reply) => {
+            let _ = reply.send(inner.commands.iter().cloned().collect());
+        }
+        ControlCommand::QueryCommandLine(reply) => {
+            let line = inner.command_start.and_then(|at| inner.text_since(at));
+            let _ = reply.send(line);
+        }
+        ControlCommand::QueryPrompt(reply) => {
+            let _ = reply.send(inner.prompt.clone());
+        }
+        ControlCommand::QueryShellIntegration(reply) => {
+            let _ = reply.send(inner.integration.clone());
+        }
+        ControlCommand::QueryPromptTimes(reply) => {
+            let _ = reply.send(inner.prompt_times_in_view());
+        }
+        ControlCommand::QueryBlock(row, reply) => {
+            let _ = reply.send(inner.block_at(row));
+        }
+        ControlCommand::AddMark(name, reply) => {
+            let _ = reply.send(inner.add_mark(name));
+        }
+        ControlCommand::QueryMarks(reply) => {
+            let _ = reply.send(inner.marks());
+        }
+        ControlCommand::Search(needle, limit, reply) => {
+            let _ = reply.send(search::search(inner.term.grid(), &needle, limit));
+        }
+        ControlCommand::ScrollToLine(line) => {
+            use alacritty_terminal::grid::Scroll;
+            let grid = inner.term.grid_mut();
+            let offset = grid.history_size().saturating_sub(line);
+            let delta = offset as i32 - grid.display_offset() as i32;
+            grid.scroll_display(Scroll::Delta(delta));
+        }
         ControlCommand::ExtractFull { theme, reply } => {
             let lines = extract_grid_full_from_term(&inner.term, &theme);
             let _ = reply.send(lines);
         }
         ControlCommand::ExtractDelta { theme, reply } => {
-            let delta = extract_grid_delta_from_term(&mut inner.term, &theme, render_cache);
+            // Colors are resolved at extraction, so a new theme repaints everything
+            let theme_changed = !Arc::ptr_eq(&theme, &inner.theme);
+            let delta =
+                extract_grid_delta_from_term(&mut inner.term, &theme, render_cache, theme_changed);
+            inner.theme = Arc::clone(&theme);
             let rows = if delta.full {
                 render_cache.iter().cloned().enumerate().collect()
             } else {
@@ -432,16 +1438,30 @@ fn handle_control_command(
                 cursor: (cursor.column.0 as u16, cursor.line.0 as u16),
             });
         }
+        // Applied by the parser loop, which owns the input queue
+        ControlCommand::Reattach { .. } => {}
         ControlCommand::Shutdown => return true,
     }
     false
 }
 
+/// `offset` into `old_history` lines of scrollback, moved to the same
+/// fraction of `new_history`
+fn scaled_offset(offset: usize, old_history: usize, new_history: usize) -> usize {
+    if old_history == 0 {
+        return 0;
+    }
+    // Still scrolled, unless the history is gone
+    let scaled = (offset * new_history + old_history / 2) / old_history;
+    scaled.max(1).min(new_history)
+}
+
 fn extract_grid_full_from_term(term: &Term<Listener>, theme: &Theme) -> Vec<GridLine> {
     use alacritty_terminal::index::{Column, Line};
     use alacritty_terminal::term::cell::Flags;
 
     let grid = term.grid();
+    let palette = term.colors();
     let num_lines = grid.screen_lines();
     let num_cols = grid.columns();
     let display_offset = grid.display_offset();
@@ -453,8 +1473,7 @@ fn extract_grid_full_from_term(term: &Term<Listener>, theme: &Theme) -> Vec<Grid
         for col_idx in 0..num_cols {
             let point = alacritty_terminal::index::Point::new(Line(actual_line), Column(col_idx));
             let cell = &grid[point];
-            let fg = alacritty_color_to_rgb(&cell.fg, theme);
-            let bg = alacritty_color_to_rgb(&cell.bg, theme);
+            let (fg, bg) = cell_colors(cell, theme, palette);
             let flags = cell.flags;
 
             cells.push(GridCell {
@@ -477,6 +1496,7 @@ fn extract_grid_delta_from_term(
     term: &mut Term<Listener>,
     theme: &Theme,
     out: &mut Vec<GridLine>,
+    force_full: bool,
 ) -> GridDelta {
     use alacritty_terminal::index::{Column, Line};
     use alacritty_terminal::term::cell::Flags;
@@ -500,27 +1520,27 @@ fn extract_grid_delta_from_term(
         }
     }
 
-    if shape_changed {
+    if shape_changed || force_full {
         delta.full = true;
         delta.dirty_rows.clear();
     }
 
     let grid = term.grid();
+    let palette = term.colors();
 
     if delta.full {
         // Resize line count but reuse existing cell Vec capacity.
         out.resize_with(num_lines, || GridLine { cells: Vec::with_capacity(num_cols) });
         out.truncate(num_lines);
-        for line_idx in 0..num_lines {
-            let cells = &mut out[line_idx].cells;
+        for (line_idx, line) in out.iter_mut().enumerate() {
+            let cells = &mut line.cells;
             cells.clear();
             let actual_line = line_idx as i32 - display_offset as i32;
             for col_idx in 0..num_cols {
                 let point =
                     alacritty_terminal::index::Point::new(Line(actual_line), Column(col_idx));
                 let cell = &grid[point];
-                let fg = alacritty_color_to_rgb(&cell.fg, theme);
-                let bg = alacritty_color_to_rgb(&cell.bg, theme);
+                let (fg, bg) = cell_colors(cell, theme, palette);
                 let flags = cell.flags;
 
                 cells.push(GridCell {
@@ -549,8 +1569,7 @@ fn extract_grid_delta_from_term(
                 let point =
                     alacritty_terminal::index::Point::new(Line(actual_line), Column(col_idx));
                 let cell = &grid[point];
-                let fg = alacritty_color_to_rgb(&cell.fg, theme);
-                let bg = alacritty_color_to_rgb(&cell.bg, theme);
+                let (fg, bg) = cell_colors(cell, theme, palette);
                 let flags = cell.flags;
 
                 cells.push(GridCell {
@@ -589,42 +1608,48 @@ pub struct GridCell {
     pub wide_spacer: bool,
 }
 
+/// A cell's text and background colors, the text raised to the theme's
+/// minimum contrast
+fn cell_colors(
+    cell: &alacritty_terminal::term::cell::Cell,
+    theme: &Theme,
+    palette: &Colors,
+) -> (RgbColor, RgbColor) {
+    let fg = alacritty_color_to_rgb(&cell.fg, theme, palette);
+    let bg = alacritty_color_to_rgb(&cell.bg, theme, palette);
+    if theme.min_contrast > 1.0 {
+        (fg.with_contrast(bg, theme.min_contrast), bg)
+    } else {
+        (fg, bg)
+    }
+}
+
 /// Convert alacritty_terminal color to our RgbColor
-pub fn alacritty_color_to_rgb(color: &ansi::Color, theme: &Theme) -> RgbColor {
+pub fn alacritty_color_to_rgb(color: &ansi::Color, theme: &Theme, palette: &Colors) -> RgbColor {
     match color {
-        ansi::Color::Named(named) => {
-            use ansi::NamedColor;
-            match named {
-                NamedColor::Foreground | NamedColor::BrightForeground => theme.colors.foreground,
-                NamedColor::Background => theme.colors.background,
-                NamedColor::Cursor => theme.colors.cursor,
-                NamedColor::DimBlack => dim_color(theme.colors.ansi[0]),
-                NamedColor::DimRed => dim_color(theme.colors.ansi[1]),
-                NamedColor::DimGreen => dim_color(theme.colors.ansi[2]),
-                NamedColor::DimYellow => dim_color(theme.colors.ansi[3]),
-                NamedColor::DimBlue => dim_color(theme.colors.ansi[4]),
-                NamedColor::DimMagenta => dim_color(theme.colors.ansi[5]),
-                NamedColor::DimCyan => dim_color(theme.colors.ansi[6]),
-                NamedColor::DimWhite => dim_color(theme.colors.ansi[7]),
-                _ => {
-                    let idx = *named as usize;
-                    if idx < 16 {
-                        theme.colors.ansi[idx]
-                    } else {
-                        theme.colors.foreground
-                    }
-                }
-            }
-        }
+        ansi::Color::Named(named) => palette_color(*named as usize, theme, palette),
         ansi::Color::Spec(rgb) => RgbColor::new(rgb.r, rgb.g, rgb.b),
-        ansi::Color::Indexed(idx) => {
-            if (*idx as usize) < 16 {
-                theme.colors.ansi[*idx as usize]
-            } else {
-                // 256-color palette: compute from index
-                index_256_to_rgb(*idx)
-            }
-        }
+        ansi::Color::Indexed(idx) => palette_color(*idx as usize, theme, palette),
+    }
+}
+
+/// Resolve a palette slot (0-255 or a `NamedColor`), preferring colors the
+/// application set with OSC 4/10/11/12 over the theme.
+fn palette_color(index: usize, theme: &Theme, palette: &Colors) -> RgbColor {
+    if let Some(rgb) = palette[index] {
+        return RgbColor::new(rgb.r, rgb.g, rgb.b);
+    }
+    const DIM_BLACK: usize = NamedColor::DimBlack as usize;
+    const DIM_WHITE: usize = NamedColor::DimWhite as usize;
+    match index {
+        0..=15 => theme.colors.ansi[index],
+        // 256-color palette: compute from index
+        16..=255 => index_256_to_rgb(index as u8),
+        _ if index == NamedColor::Background as usize => theme.colors.background,
+        _ if index == NamedColor::Cursor as usize => theme.colors.cursor,
+        DIM_BLACK..=DIM_WHITE => dim_color(palette_color(index - DIM_BLACK, theme, palette)),
+        // Foreground, BrightForeground, DimForeground
+        _ => theme.colors.foreground,
     }
 }
 
@@ -655,3 +1680,314 @@ fn index_256_to_rgb(idx: u8) -> RgbColor {
         RgbColor::new(v, v, v)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_query_answered_before_da1() {
+        let mut theme = Theme::default();
+        theme.colors.background = RgbColor {
+            r: 0x12,
+            g: 0x34,
+            b: 0x56,
+        };
+        let mut emulator = TerminalEmulator::with_theme(20, 5, Arc::new(theme));
+        let mut handle = emulator.take_parser_handle().unwrap();
+        let replies = handle.take_reply_receiver().unwrap();
+        // What vim and fzf send: the query, then DA1 to tell an answer from none
+        handle.process(b"\x1b]11;?\x1b\\\x1b[c");
+        let mut reply = Vec::new();
+        while !reply.ends_with(b"c") {
+            reply.extend(replies.recv_timeout(Duration::from_secs(2)).unwrap());
+        }
+        let reply = String::from_utf8(reply).unwrap();
+        assert!(
+            reply.starts_with("\x1b]11;rgb:1212/3434/5656\x1b\\\x1b[?"),
+            "{reply:?}"
+        );
+    }
+
+    #[test]
+    fn resize_keeps_scroll_position() {
+        assert_eq!(scaled_offset(50, 100, 200), 100);
+        assert_eq!(scaled_offset(1, 100, 40), 1);
+        assert_eq!(scaled_offset(30, 100, 0), 0);
+
+        let emulator = TerminalEmulator::new(20, 10);
+        for i in 0..60 {
+            emulator.process(format!("line {i}\r\n").as_bytes());
+        }
+        emulator.scroll(20);
+        let history = emulator.history_size();
+        assert_eq!(emulator.display_offset(), 20);
+
+        emulator.resize(20, 5);
+        let scaled = scaled_offset(20, history, emulator.history_size());
+        assert_eq!(emulator.display_offset(), scaled);
+
+        // Following the output stays that way
+        emulator.scroll(-100);
+        emulator.resize(20, 8);
+        assert_eq!(emulator.display_offset(), 0);
+    }
+
+    #[test]
+    fn metadata_follows_the_parser() {
+        let emulator = TerminalEmulator::new(20, 5);
+        // Published once the parser gets to it, which readers don't wait for
+        let settled = |expected: TermMetadata| {
+            let deadline = Instant::now() + Duration::from_secs(2);
+            while emulator.metadata() != expected && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            emulator.metadata()
+        };
+        emulator.process(b"ab\r\ncd");
+        emulator.resize(30, 8);
+        let mut expected = TermMetadata {
+            cols: 30,
+            rows: 8,
+            cursor: (2, 1),
+            display_offset: 0,
+            history_size: 0,
+            alt_screen: false,
+        };
+        assert_eq!(settled(expected), expected);
+
+        // Six newlines reach the bottom row, four more scroll into history
+        emulator.process(&b"\r\n".repeat(10));
+        expected.cursor = (0, 7);
+        expected.history_size = 4;
+        assert_eq!(settled(expected), expected);
+
+        // The alternate screen keeps no history
+        emulator.process(b"\x1b[?1049h");
+        expected.alt_screen = true;
+        expected.history_size = 0;
+        assert_eq!(settled(expected), expected);
+    }
+
+    #[test]
+    fn paused_output_is_held_until_resumed() {
+        let emulator = TerminalEmulator::new(20, 5);
+        assert_eq!(emulator.held_bytes(), None);
+        emulator.set_paused(true);
+        emulator.process(b"abc");
+        emulator.process(b"de");
+        assert_eq!(emulator.cursor_position(), (0, 0));
+        assert_eq!(emulator.held_bytes(), Some(5));
+
+        emulator.set_paused(false);
+        assert_eq!(emulator.cursor_position(), (5, 0));
+        assert_eq!(emulator.held_bytes(), None);
+    }
+
+    #[test]
+    fn prompt_times_follow_their_line() {
+        let emulator = TerminalEmulator::new(20, 5);
+        emulator.process(b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07");
+        emulator.process(b"a.txt\r\n\x1b]133;D;1\x07");
+        let times = emulator.prompt_times();
+        assert_eq!(times.len(), 1);
+        let (row, time) = times[0];
+        assert_eq!(row, 0);
+        assert_eq!(time.exit_code, Some(1));
+        assert!(time.duration.is_some());
+
+        // Scrolled out of view and back
+        emulator.process("x\r\n".repeat(10).as_bytes());
+        assert!(emulator.prompt_times().is_empty());
+        emulator.scroll(100);
+        assert_eq!(emulator.prompt_times(), vec![(0, time)]);
+    }
+
+    #[test]
+    fn command_blocks_found_by_row() {
+        let emulator = TerminalEmulator::new(20, 8);
+        emulator.process(b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07");
+        emulator.process(b"a.txt\r\nb.txt\r\n\x1b]133;D;0\x07");
+        emulator.process(b"\x1b]133;A\x07$ \x1b]133;B\x07sleep 9\r\n\x1b]133;C\x07zz");
+        let listed = CommandBlock {
+            command: "ls".into(),
+            output: "a.txt\nb.txt".into(),
+        };
+        assert_eq!(emulator.command_block_at(0), Some(listed.clone()));
+        assert_eq!(emulator.command_block_at(2), Some(listed));
+        // Still running: its output so far
+        let running = emulator.command_block_at(4).unwrap();
+        assert_eq!(running.command, "sleep 9");
+        assert_eq!(running.output, "zz");
+        assert_eq!(emulator.command_block_at(6), None);
+    }
+
+    #[test]
+    fn wrapped_rows_seen() {
+        let emulator = TerminalEmulator::new(10, 4);
+        emulator.process(b"0123456789abc\r\nshort\r\n");
+        assert_eq!(emulator.wrapped_rows(), [true, false, false, false]);
+    }
+
+    #[test]
+    fn reattached_pty_keeps_history() {
+        let mut emulator = TerminalEmulator::new(20, 5);
+        let old = emulator.take_parser_handle().unwrap();
+        old.process(b"\x1b[?1004hold output\r\n$ ");
+        drop(old);
+        let mut new = emulator.reattach_parser_handle("restarted");
+        let replies = new.take_reply_receiver().unwrap();
+        new.process(b"$ \x1b[c");
+        // Replies go to the new PTY
+        let reply = replies.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert!(reply.starts_with(b"\x1b[?"));
+
+        let theme = Arc::new(Theme::default());
+        let lines: Vec<String> = emulator
+            .extract_grid(&theme)
+            .iter()
+            .map(|line| line.cells.iter().map(|cell| cell.c).collect())
+            .map(|line: String| line.trim_end().to_string())
+            .collect();
+        assert_eq!(lines[..4], ["old output", "$", "restarted", "$"]);
+        assert!(!emulator.input_modes().focus_reporting);
+    }
+
+    #[test]
+    fn screen_reader_reads_from_another_thread() {
+        let emulator = TerminalEmulator::new(20, 5);
+        emulator.process(b"hello\r\nworld");
+        let reader = emulator.screen_reader();
+        let theme = Arc::new(Theme::default());
+        let grid = std::thread::spawn(move || reader.extract_grid(&theme))
+            .join()
+            .unwrap()
+            .unwrap();
+        let lines: Vec<String> = grid
+            .iter()
+            .map(|line| line.cells.iter().map(|cell| cell.c).collect())
+            .map(|line: String| line.trim_end().to_string())
+            .collect();
+        assert_eq!(lines[..2], ["hello", "world"]);
+
+        let reader = emulator.screen_reader();
+        drop(emulator);
+        assert!(reader.extract_grid(&Arc::new(Theme::default())).is_none());
+    }
+
+    #[test]
+    fn wheel_reports_follow_mouse_encoding() {
+        let emulator = TerminalEmulator::new(20, 5);
+        assert_eq!(emulator.input_modes().wheel_report(6, 0, 0), None);
+
+        emulator.process(b"\x1b[?1000h");
+        let legacy = emulator.input_modes();
+        assert_eq!(legacy.wheel_report(6, 2, 1).unwrap(), b"\x1b[M\x62\x23\x22");
+        assert_eq!(legacy.wheel_report(7, 0, 0).unwrap(), b"\x1b[M\x63\x21\x21");
+        assert_eq!(legacy.wheel_report(6, 300, 0), None);
+        assert_eq!(legacy.wheel_report(1, 0, 0), None);
+
+        emulator.process(b"\x1b[?1006h");
+        let sgr = emulator.input_modes();
+        assert_eq!(sgr.wheel_report(6, 2, 1).unwrap(), b"\x1b[<66;3;2M");
+        assert_eq!(sgr.wheel_report(7, 300, 0).unwrap(), b"\x1b[<67;301;1M");
+
+        emulator.process(b"\x1b[?1006l\x1b[?1005h");
+        let utf8 = emulator.input_modes().wheel_report(7, 99, 0).unwrap();
+        assert_eq!(utf8, "\x1b[M\x63\u{84}\x21".as_bytes());
+    }
+
+    #[test]
+    fn title_stack_restores_titles() {
+        let emulator = TerminalEmulator::new(20, 5);
+        let titles = |bytes: &[u8]| {
+            emulator.process(bytes);
+            let deadline = Instant::now() + Duration::from_secs(2);
+            let mut titles = Vec::new();
+            while titles.is_empty() && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(1));
+                for event in emulator.poll_events() {
+                    if let TermEvent::TitleChanged(title) = event {
+                        titles.push(title);
+                    }
+                }
+            }
+            titles
+        };
+        // Pushed before any title: popping clears the program's title
+        assert_eq!(titles(b"\x1b[22;0t\x1b]2;vim\x07"), ["vim"]);
+        assert_eq!(titles(b"\x1b[23;0t"), [""]);
+
+        assert_eq!(titles(b"\x1b]2;shell\x07"), ["shell"]);
+        assert_eq!(titles(b"\x1b[22t\x1b]2;ssh box\x07"), ["ssh box"]);
+        assert_eq!(titles(b"\x1b[22t\x1b]2;vim\x07"), ["vim"]);
+        assert_eq!(titles(b"\x1b[23t"), ["ssh box"]);
+        assert_eq!(titles(b"\x1b[23t"), ["shell"]);
+    }
+
+    #[test]
+    fn shell_integration_seen() {
+        let emulator = TerminalEmulator::new(20, 5);
+        assert_eq!(emulator.shell_integration(), Default::default());
+        emulator.process(b"\x1b]7;file://box/srv\x07\x1b]133;A\x07$ ");
+        let status = emulator.shell_integration();
+        assert!(status.prompt_marks);
+        assert_eq!(
+            status.reported_cwd.as_deref(),
+            Some(std::path::Path::new("/srv"))
+        );
+    }
+
+    #[test]
+    fn marks_stay_on_their_line() {
+        let emulator = TerminalEmulator::new(20, 5);
+        emulator.process("x\r\n".repeat(20).as_bytes());
+        emulator.scroll(6);
+        assert_eq!(emulator.add_mark("build"), Some(10));
+        emulator.scroll(-3);
+        assert_eq!(emulator.add_mark("test"), Some(13));
+        assert_eq!(emulator.add_mark("tests"), Some(13));
+        let names: Vec<_> = emulator.marks().into_iter().map(|m| m.name).collect();
+        assert_eq!(names, ["build", "tests"]);
+
+        emulator.scroll(-100);
+        emulator.scroll_to_line(10);
+        assert_eq!(emulator.display_offset(), 6);
+
+        // Not on the alternate screen, which has no history
+        emulator.process(b"\x1b[?1049h");
+        assert_eq!(emulator.add_mark("vim"), None);
+        assert!(emulator.marks().is_empty());
+    }
+
+    #[test]
+    fn search_finds_lines_in_history() {
+        let emulator = TerminalEmulator::new(20, 5);
+        for i in 0..20 {
+            emulator.process(format!("line {i}\r\n").as_bytes());
+        }
+        emulator.process("warn: 漢字 Error\r\nerror again".as_bytes());
+        let found = emulator.search("error", 10);
+        let lines: Vec<_> = found.iter().map(|m| (m.line, m.columns, m.count)).collect();
+        // The wide chars take two cells each
+        assert_eq!(lines, [(20, (11, 15), 1), (21, (0, 4), 1)]);
+        assert_eq!(found[0].text, "warn: 漢字 Error");
+        // The newest matches are kept
+        let lines: Vec<_> = emulator
+            .search("line 1", 3)
+            .iter()
+            .map(|m| m.line)
+            .collect();
+        assert_eq!(lines, [17, 18, 19]);
+        assert!(emulator.search("Line", 10).is_empty());
+    }
+
+    #[test]
+    fn narrowest_terminal_takes_output() {
+        // Found by the emulator_process fuzz target
+        let emulator = TerminalEmulator::new(1, 0);
+        emulator.process(b"abc\r\n\x1b[2@x");
+        assert_eq!(emulator.size(), (2, 1));
+        assert_eq!(emulator.cursor_position(), (1, 0));
+    }
+}
@@ -0,0 +1,102 @@
+//! Line numbers that stay put while the scrollback is trimmed.
+//!
+//! alacritty counts lines from the oldest line of history, so once the
+//! history is full every new line renumbers all the others. [`LineCounter`]
+//! keeps count of the lines that fell off the top; adding that count gives
+//! each line an id that names it until it is gone.
+
+use alacritty_terminal::grid::{Dimensions, Grid};
+use alacritty_terminal::index::{Column, Line};
+use alacritty_terminal::term::cell::Cell;
+
+#[derive(Debug, Default)]
+pub(crate) struct LineCounter {
+    /// Lines dropped from the top of the history so far
+    dropped: u64,
+    /// The newest line of history: the address of its first cell, and the
+    /// line. Lines of history only move up, as lines above them are
+    /// dropped, and their cells stay put, so where the cell is found next
+    /// tells how many were dropped.
+    anchor: Option<(usize, usize)>,
+}
+
+impl LineCounter {
+    /// Catch up with output applied to `grid`, the primary screen's
+    pub(crate) fn observe(&mut self, grid: &Grid<Cell>) {
+        if let Some((cell, line)) = self.anchor {
+            let last = grid.total_lines() - 1;
+            if line > last || row_cell(grid, line) != cell {
+                let moved = (0..=line.min(last))
+                    .rev()
+                    .find(|&i| row_cell(grid, i) == cell);
+                // Not found: it was dropped too, or the history cleared
+                self.dropped += moved.map_or(line + 1, |i| line - i) as u64;
+            }
+        }
+        let history = grid.history_size();
+        self.anchor = (history > 0).then(|| (row_cell(grid, history - 1), history - 1));
+    }
+
+    /// Id of a line counted from the oldest line of history
+    pub(crate) fn id(&self, line: usize) -> u64 {
+        self.dropped + line as u64
+    }
+
+    /// Where the line with `id` is now, counted from the oldest line of
+    /// history; `None` once it was dropped
+    pub(crate) fn line(&self, id: u64) -> Option<usize> {
+        id.checked_sub(self.dropped).map(|line| line as usize)
+    }
+}
+
+/// Address of the first cell of a line counted from the oldest line
+fn row_cell(grid: &Grid<Cell>, line: usize) -> usize {
+    let line = Line(line as i32 - grid.history_size() as i32);
+    &grid[line][Column(0)] as *const Cell as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::event::VoidListener;
+    use alacritty_terminal::term::test::TermSize;
+    use alacritty_terminal::term::{self, Term};
+    use alacritty_terminal::vte::ansi::Processor;
+
+    use super::*;
+
+    fn first_char(term: &Term<VoidListener>, line: usize) -> char {
+        let grid = term.grid();
+        grid[Line(line as i32 - grid.history_size() as i32)][Column(0)].c
+    }
+
+    #[test]
+    fn ids_survive_a_full_history() {
+        let config = term::Config {
+            scrolling_history: 5,
+            ..Default::default()
+        };
+        let mut term = Term::new(config, &TermSize::new(10, 3), VoidListener);
+        let mut processor: Processor = Processor::new();
+        let mut counter = LineCounter::default();
+        counter.observe(term.grid());
+
+        processor.advance(&mut term, b"0\r\n1\r\n2\r\n3\r\n");
+        counter.observe(term.grid());
+        let one = counter.id(1);
+        // Fill the history, then drop "0" off the top
+        for output in [&b"a\r\nb\r\n"[..], b"c\r\n", b"d\r\n"] {
+            processor.advance(&mut term, output);
+            counter.observe(term.grid());
+            assert_eq!(first_char(&term, counter.line(one).unwrap()), '1');
+        }
+        assert_eq!(counter.line(one), Some(0));
+
+        // Several lines dropped at once
+        let three = counter.id(2);
+        processor.advance(&mut term, b"e\r\nf\r\n");
+        counter.observe(term.grid());
+        assert_eq!(counter.line(one), None);
+        assert_eq!(counter.line(three), Some(0));
+        assert_eq!(first_char(&term, 0), '3');
+    }
+}
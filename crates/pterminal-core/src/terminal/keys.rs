@@ -0,0 +1,259 @@
+//! Escape sequences for keys that don't type text, as xterm sends them.
+//!
+//! Modifiers are added xterm's way, as a parameter of 1 plus 1 for Shift,
+//! 2 for Alt, 4 for Ctrl and 8 for Super: Ctrl+F5 is `CSI 15;5~`. Cursor
+//! keys follow DECCKM and the keypad follows DECKPAM (application keypad),
+//! which full-screen programs such as mc and htop turn on. F13 to F24 are
+//! Shift+F1 to Shift+F12, as xterm's PC keyboard and its terminfo have them.
+
+use super::InputModes;
+
+/// Modifiers held with a key
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KeyMods {
+    pub shift: bool,
+    pub alt: bool,
+    pub ctrl: bool,
+    /// Command on macOS, the Windows key elsewhere
+    pub meta: bool,
+}
+
+impl KeyMods {
+    /// xterm's modifier parameter; `None` without modifiers
+    fn param(self) -> Option<u8> {
+        let bits = u8::from(self.shift)
+            | u8::from(self.alt) << 1
+            | u8::from(self.ctrl) << 2
+            | u8::from(self.meta) << 3;
+        (bits != 0).then_some(bits + 1)
+    }
+}
+
+/// A key that sends an escape sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecialKey {
+    /// F1 to F24
+    F(u8),
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+    /// The context menu key
+    Menu,
+    PrintScreen,
+    Keypad(KeypadKey),
+}
+
+/// A key of the numeric keypad, with Num Lock on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeypadKey {
+    Digit(u8),
+    Decimal,
+    Comma,
+    Divide,
+    Multiply,
+    Subtract,
+    Add,
+    Equal,
+    Enter,
+}
+
+impl KeypadKey {
+    /// The keypad key with this W3C `code`, e.g. "Numpad7"
+    pub fn from_code(code: &str) -> Option<Self> {
+        let name = code.strip_prefix("Numpad")?;
+        Some(match name {
+            "Decimal" => Self::Decimal,
+            "Comma" => Self::Comma,
+            "Divide" => Self::Divide,
+            "Multiply" => Self::Multiply,
+            "Subtract" => Self::Subtract,
+            "Add" => Self::Add,
+            "Equal" => Self::Equal,
+            "Enter" => Self::Enter,
+            digit => match digit.as_bytes() {
+                [d @ b'0'..=b'9'] => Self::Digit(d - b'0'),
+                _ => return None,
+            },
+        })
+    }
+
+    /// What the key types outside application keypad mode
+    fn text(self) -> &'static [u8] {
+        match self {
+            Self::Digit(d) => {
+                let d = usize::from(d.min(9));
+                &b"0123456789"[d..=d]
+            }
+            Self::Decimal => b".",
+            Self::Comma => b",",
+            Self::Divide => b"/",
+            Self::Multiply => b"*",
+            Self::Subtract => b"-",
+            Self::Add => b"+",
+            Self::Equal => b"=",
+            Self::Enter => b"\r",
+        }
+    }
+
+    /// Final character of the SS3 sequence it sends in application keypad
+    /// mode
+    fn app_final(self) -> u8 {
+        match self {
+            Self::Digit(d) => b'p' + d.min(9),
+            Self::Decimal => b'n',
+            Self::Comma => b'l',
+            Self::Divide => b'o',
+            Self::Multiply => b'j',
+            Self::Subtract => b'm',
+            Self::Add => b'k',
+            Self::Equal => b'X',
+            Self::Enter => b'M',
+        }
+    }
+}
+
+/// Bytes to send for `key` pressed with `mods`, in the terminal's `modes`
+pub fn encode_key(key: SpecialKey, mods: KeyMods, modes: &InputModes) -> Vec<u8> {
+    if let SpecialKey::F(n @ 13..=24) = key {
+        let mods = KeyMods {
+            shift: true,
+            ..mods
+        };
+        return encode_key(SpecialKey::F(n - 12), mods, modes);
+    }
+    let param = mods.param();
+    match key {
+        // SS3 P..S unmodified, CSI 1;m P..S with modifiers
+        SpecialKey::F(n @ 1..=4) => letter_key(b'P' + n - 1, param, true),
+        SpecialKey::F(n) => match function_key_number(n) {
+            Some(number) => tilde_key(number, param),
+            None => Vec::new(),
+        },
+        SpecialKey::Up => letter_key(b'A', param, modes.app_cursor),
+        SpecialKey::Down => letter_key(b'B', param, modes.app_cursor),
+        SpecialKey::Right => letter_key(b'C', param, modes.app_cursor),
+        SpecialKey::Left => letter_key(b'D', param, modes.app_cursor),
+        SpecialKey::Home => letter_key(b'H', param, modes.app_cursor),
+        SpecialKey::End => letter_key(b'F', param, modes.app_cursor),
+        SpecialKey::Insert => tilde_key(2, param),
+        SpecialKey::Delete => tilde_key(3, param),
+        SpecialKey::PageUp => tilde_key(5, param),
+        SpecialKey::PageDown => tilde_key(6, param),
+        // The VT220's Do key, where xterm puts Menu
+        SpecialKey::Menu => tilde_key(29, param),
+        // xterm has no sequence for it; kitty's is unambiguous
+        SpecialKey::PrintScreen => match param {
+            Some(m) => format!("\x1b[57361;{m}u").into_bytes(),
+            None => b"\x1b[57361u".to_vec(),
+        },
+        SpecialKey::Keypad(key) if modes.app_keypad => vec![0x1b, b'O', key.app_final()],
+        SpecialKey::Keypad(key) => key.text().to_vec(),
+    }
+}
+
+/// The number in `CSI n~` for F5 to F12
+fn function_key_number(n: u8) -> Option<u8> {
+    const NUMBERS: [u8; 8] = [15, 17, 18, 19, 20, 21, 23, 24];
+    NUMBERS.get(usize::from(n.checked_sub(5)?)).copied()
+}
+
+/// `CSI n~`, or `CSI n;m~` with modifiers
+fn tilde_key(number: u8, param: Option<u8>) -> Vec<u8> {
+    match param {
+        Some(m) => format!("\x1b[{number};{m}~").into_bytes(),
+        None => format!("\x1b[{number}~").into_bytes(),
+    }
+}
+
+/// `CSI x` (or `SS3 x` when `ss3`), and `CSI 1;m x` with modifiers
+fn letter_key(last: u8, param: Option<u8>, ss3: bool) -> Vec<u8> {
+    match param {
+        Some(m) => format!("\x1b[1;{m}{}", last as char).into_bytes(),
+        None if ss3 => vec![0x1b, b'O', last],
+        None => vec![0x1b, b'[', last],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(key: SpecialKey, mods: KeyMods) -> String {
+        String::from_utf8(encode_key(key, mods, &InputModes::default())).unwrap()
+    }
+
+    #[test]
+    fn function_keys() {
+        let none = KeyMods::default();
+        assert_eq!(encode(SpecialKey::F(1), none), "\x1bOP");
+        assert_eq!(encode(SpecialKey::F(4), none), "\x1bOS");
+        assert_eq!(encode(SpecialKey::F(5), none), "\x1b[15~");
+        assert_eq!(encode(SpecialKey::F(10), none), "\x1b[21~");
+        assert_eq!(encode(SpecialKey::F(12), none), "\x1b[24~");
+        assert_eq!(encode(SpecialKey::F(13), none), "\x1b[1;2P");
+        assert_eq!(encode(SpecialKey::F(16), none), "\x1b[1;2S");
+        assert_eq!(encode(SpecialKey::F(17), none), "\x1b[15;2~");
+        assert_eq!(encode(SpecialKey::F(24), none), "\x1b[24;2~");
+        let ctrl = KeyMods {
+            ctrl: true,
+            ..KeyMods::default()
+        };
+        assert_eq!(encode(SpecialKey::F(20), ctrl), "\x1b[19;6~");
+        assert_eq!(encode(SpecialKey::F(25), none), "");
+    }
+
+    #[test]
+    fn modifiers_are_a_parameter() {
+        let shift = KeyMods {
+            shift: true,
+            ..KeyMods::default()
+        };
+        let ctrl_alt = KeyMods {
+            ctrl: true,
+            alt: true,
+            ..KeyMods::default()
+        };
+        assert_eq!(encode(SpecialKey::F(1), shift), "\x1b[1;2P");
+        assert_eq!(encode(SpecialKey::F(5), ctrl_alt), "\x1b[15;7~");
+        assert_eq!(encode(SpecialKey::Up, ctrl_alt), "\x1b[1;7A");
+        assert_eq!(encode(SpecialKey::Delete, shift), "\x1b[3;2~");
+        assert_eq!(encode(SpecialKey::Menu, KeyMods::default()), "\x1b[29~");
+        assert_eq!(encode(SpecialKey::PrintScreen, shift), "\x1b[57361;2u");
+    }
+
+    #[test]
+    fn cursor_and_keypad_modes() {
+        let none = KeyMods::default();
+        let app = InputModes {
+            app_cursor: true,
+            app_keypad: true,
+            ..InputModes::default()
+        };
+        let enter = SpecialKey::Keypad(KeypadKey::Enter);
+        let seven = SpecialKey::Keypad(KeypadKey::from_code("Numpad7").unwrap());
+        assert_eq!(encode(SpecialKey::Up, none), "\x1b[A");
+        assert_eq!(encode(enter, none), "\r");
+        assert_eq!(encode(seven, none), "7");
+        assert_eq!(encode_key(SpecialKey::Up, none, &app), b"\x1bOA");
+        assert_eq!(encode_key(SpecialKey::Home, none, &app), b"\x1bOH");
+        assert_eq!(encode_key(enter, none, &app), b"\x1bOM");
+        assert_eq!(encode_key(seven, none, &app), b"\x1bOw");
+        let add = SpecialKey::Keypad(KeypadKey::Add);
+        assert_eq!(encode_key(add, none, &app), b"\x1bOk");
+    }
+
+    #[test]
+    fn keypad_codes() {
+        assert_eq!(KeypadKey::from_code("Numpad0"), Some(KeypadKey::Digit(0)));
+        assert_eq!(KeypadKey::from_code("NumpadEnter"), Some(KeypadKey::Enter));
+        assert_eq!(KeypadKey::from_code("NumpadBackspace"), None);
+        assert_eq!(KeypadKey::from_code("Digit1"), None);
+    }
+}
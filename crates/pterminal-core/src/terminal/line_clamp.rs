@@ -0,0 +1,204 @@
+//! Cutting short single lines too long to display, such as minified JSON
+//! catted to the terminal: shaping and background spans for megabytes of
+//! wrapped text would stall rendering. A run of printable output longer
+//! than the limit is cut there and ends with a marker saying how much was
+//! hidden; the whole line is kept so it can be opened in a pager.
+
+use std::borrow::Cow;
+
+/// Most of a clamped line kept for the pager
+const MAX_KEPT_BYTES: usize = 64 * 1024 * 1024;
+
+/// A line cut short on screen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClampedLine {
+    /// The whole line as output (up to 64 MiB)
+    pub bytes: Vec<u8>,
+    /// Bytes left off the screen
+    pub hidden: usize,
+}
+
+/// Where the clamp is in an escape sequence
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    #[default]
+    Ground,
+    Esc,
+    Csi,
+    /// OSC, DCS and the like, up to BEL or ST
+    Str,
+    StrEsc,
+}
+
+/// Clamps runs of printable output. A run ends at a control character or
+/// at any escape sequence but SGR, so colored text still counts as one line
+/// while full-screen apps moving the cursor around never build up a run.
+#[derive(Debug, Default)]
+pub struct LineClamp {
+    /// Longest run shown; 0 shows everything
+    max_bytes: usize,
+    escape: Escape,
+    /// Printable bytes in the current run
+    run: usize,
+    /// Text of the current run, kept in case it is cut
+    line: Vec<u8>,
+    hidden: usize,
+    clamped: Option<ClampedLine>,
+}
+
+impl LineClamp {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            ..Default::default()
+        }
+    }
+
+    /// Change the limit; a line already being cut finishes as before
+    pub fn set_max_bytes(&mut self, max_bytes: usize) {
+        self.max_bytes = max_bytes;
+    }
+
+    /// `data` with printable bytes past the limit removed and a marker where
+    /// a cut line ends. Borrowed unchanged when nothing was cut.
+    pub fn filter<'a>(&mut self, data: &'a [u8]) -> Cow<'a, [u8]> {
+        if self.max_bytes == 0 && self.hidden == 0 {
+            return Cow::Borrowed(data);
+        }
+        let mut out: Option<Vec<u8>> = None;
+        // data[..copied] is accounted for in `out`
+        let mut copied = 0;
+        for (i, &b) in data.iter().enumerate() {
+            match self.escape {
+                Escape::Ground => {}
+                Escape::Esc => {
+                    self.escape = match b {
+                        b'[' => Escape::Csi,
+                        b']' | b'P' | b'_' | b'^' => Escape::Str,
+                        _ => {
+                            self.end_run();
+                            Escape::Ground
+                        }
+                    };
+                    continue;
+                }
+                Escape::Csi => {
+                    if (0x40..=0x7e).contains(&b) {
+                        self.escape = Escape::Ground;
+                        if b != b'm' {
+                            self.end_run();
+                        }
+                    }
+                    continue;
+                }
+                Escape::Str => {
+                    self.escape = match b {
+                        0x07 => Escape::Ground,
+                        0x1b => Escape::StrEsc,
+                        _ => Escape::Str,
+                    };
+                    continue;
+                }
+                Escape::StrEsc => {
+                    self.escape = Escape::Ground;
+                    continue;
+                }
+            }
+            if b == 0x1b {
+                self.escape = Escape::Esc;
+                continue;
+            }
+            if b < 0x20 || b == 0x7f {
+                if self.hidden > 0 {
+                    let out = out.get_or_insert_with(Vec::new);
+                    out.extend_from_slice(&data[copied..i]);
+                    out.extend_from_slice(self.finish().as_bytes());
+                    copied = i;
+                }
+                self.end_run();
+                continue;
+            }
+            self.run += 1;
+            if self.line.len() < MAX_KEPT_BYTES {
+                self.line.push(b);
+            }
+            // Cut on a character boundary, then drop the rest of the run
+            let continuation = b & 0xc0 == 0x80;
+            let over = self.max_bytes > 0 && self.run > self.max_bytes;
+            if self.hidden > 0 || (over && !continuation) {
+                let out = out.get_or_insert_with(Vec::new);
+                out.extend_from_slice(&data[copied..i]);
+                copied = i + 1;
+                self.hidden += 1;
+            }
+        }
+        match out {
+            Some(mut out) => {
+                out.extend_from_slice(&data[copied..]);
+                Cow::Owned(out)
+            }
+            None => Cow::Borrowed(data),
+        }
+    }
+
+    /// Start counting afresh, unless a line is being cut: that only ends at
+    /// a control character, so its marker lands at the end of the line
+    fn end_run(&mut self) {
+        if self.hidden == 0 {
+            self.run = 0;
+            self.line.clear();
+        }
+    }
+
+    /// The most recently cut line, once it has ended
+    pub fn take_clamped(&mut self) -> Option<ClampedLine> {
+        self.clamped.take()
+    }
+
+    /// End the line being cut, returning its marker
+    fn finish(&mut self) -> String {
+        let hidden = std::mem::take(&mut self.hidden);
+        self.clamped = Some(ClampedLine {
+            bytes: std::mem::take(&mut self.line),
+            hidden,
+        });
+        // Inverse on and off again, leaving the line's other attributes
+        format!(" \x1b[7m[line truncated, {hidden} bytes hidden]\x1b[27m")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cuts_long_runs_across_chunks() {
+        let mut clamp = LineClamp::new(4);
+        assert!(matches!(clamp.filter(b"ab\r\nabcd\n"), Cow::Borrowed(_)));
+        // Colors don't end a run; other escape sequences do
+        assert!(matches!(clamp.filter(b"abc\x1b[Hcde\n"), Cow::Borrowed(_)));
+
+        let mut out = clamp.filter(b"abc").into_owned();
+        out.extend_from_slice(&clamp.filter("d\x1b[1mé\x1b]0;title\x07fgh".as_bytes()));
+        assert!(clamp.take_clamped().is_none());
+        out.extend_from_slice(&clamp.filter(b"ij\r\nok"));
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "abcd\x1b[1m\x1b]0;title\x07 \x1b[7m[line truncated, 7 bytes hidden]\x1b[27m\r\nok"
+        );
+        assert_eq!(
+            clamp.take_clamped(),
+            Some(ClampedLine {
+                bytes: "abcdéfghij".as_bytes().to_vec(),
+                hidden: 7,
+            })
+        );
+
+        // A character is not split at the limit
+        let mut clamp = LineClamp::new(4);
+        let out = clamp.filter("abcé!\n".as_bytes()).into_owned();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .starts_with("abcé \x1b[7m[line truncated, 1 bytes"));
+    }
+}
@@ -0,0 +1,119 @@
+//! What the UI asks about a terminal every frame, without a round trip to
+//! the parser thread.
+//!
+//! The parser thread publishes a [`TermMetadata`] after each batch of work
+//! and the UI reads the latest one lock-free. A sequence number that is odd
+//! while a write is under way lets a reader retry instead of seeing half of
+//! one; there is only ever one writer.
+
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+
+/// Size, cursor and view of a terminal as of the parser's last batch of work
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TermMetadata {
+    pub cols: u16,
+    pub rows: u16,
+    /// (col, row)
+    pub cursor: (u16, u16),
+    /// Lines scrolled into history, 0 at the bottom
+    pub display_offset: usize,
+    /// Lines of history above the screen
+    pub history_size: usize,
+    pub alt_screen: bool,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct PublishedMetadata {
+    seq: AtomicU64,
+    /// Columns, rows, cursor column and cursor row, 16 bits each
+    size: AtomicU64,
+    /// The display offset above the alt screen flag, in the low bit
+    view: AtomicU64,
+    history: AtomicU64,
+}
+
+impl PublishedMetadata {
+    /// Only the parser thread publishes
+    pub(crate) fn publish(&self, metadata: TermMetadata) {
+        let size = u64::from(metadata.cols) << 48
+            | u64::from(metadata.rows) << 32
+            | u64::from(metadata.cursor.0) << 16
+            | u64::from(metadata.cursor.1);
+        let view = (metadata.display_offset as u64) << 1 | u64::from(metadata.alt_screen);
+        let history = metadata.history_size as u64;
+        if size == self.size.load(Ordering::Relaxed)
+            && view == self.view.load(Ordering::Relaxed)
+            && history == self.history.load(Ordering::Relaxed)
+        {
+            return;
+        }
+        let seq = self.seq.load(Ordering::Relaxed);
+        self.seq.store(seq + 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        self.size.store(size, Ordering::Relaxed);
+        self.view.store(view, Ordering::Relaxed);
+        self.history.store(history, Ordering::Relaxed);
+        self.seq.store(seq + 2, Ordering::Release);
+    }
+
+    pub(crate) fn load(&self) -> TermMetadata {
+        loop {
+            let seq = self.seq.load(Ordering::Acquire);
+            if seq % 2 == 1 {
+                std::hint::spin_loop();
+                continue;
+            }
+            let size = self.size.load(Ordering::Relaxed);
+            let view = self.view.load(Ordering::Relaxed);
+            let history = self.history.load(Ordering::Relaxed);
+            fence(Ordering::Acquire);
+            if self.seq.load(Ordering::Relaxed) == seq {
+                return TermMetadata {
+                    cols: (size >> 48) as u16,
+                    rows: (size >> 32) as u16,
+                    cursor: ((size >> 16) as u16, size as u16),
+                    display_offset: (view >> 1) as usize,
+                    history_size: history as usize,
+                    alt_screen: view & 1 == 1,
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn readers_see_whole_snapshots() {
+        let metadata = |n: u16| TermMetadata {
+            cols: n,
+            rows: n,
+            cursor: (n, n),
+            display_offset: 100_000 + usize::from(n),
+            history_size: 200_000 + usize::from(n),
+            alt_screen: n % 2 == 1,
+        };
+        let published = Arc::new(PublishedMetadata::default());
+        assert_eq!(published.load(), TermMetadata::default());
+        published.publish(metadata(0));
+
+        let writer = {
+            let published = Arc::clone(&published);
+            std::thread::spawn(move || {
+                for n in 1..20_000 {
+                    published.publish(metadata(n));
+                }
+            })
+        };
+        while !writer.is_finished() {
+            let seen = published.load();
+            assert_eq!(seen, metadata(seen.cols));
+        }
+        writer.join().unwrap();
+        assert_eq!(published.load().cols, 19_999);
+    }
+}
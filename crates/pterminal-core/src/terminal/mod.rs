@@ -1,6 +1,27 @@
 pub mod emulator;
+mod history;
+mod keys;
+mod line_clamp;
+mod metadata;
+mod osc_notify;
+mod pinned;
+mod progress;
 mod pty;
+mod query;
+mod search;
+mod shell_integration;
 mod spsc;
+mod throughput;
 
-pub use emulator::{GridCell, GridDelta, GridLine, TerminalEmulator, TerminalEmulatorHandle};
+pub use emulator::{
+    CommandBlock, GridCell, GridDelta, GridLine, InputModes, InputStalls, Mark, ScreenReader,
+    TerminalEmulator, TerminalEmulatorHandle,
+};
+pub use keys::{encode_key, KeyMods, KeypadKey, SpecialKey};
+pub use line_clamp::{ClampedLine, LineClamp};
+pub use metadata::TermMetadata;
+pub use pinned::PinnedScreen;
+pub use progress::Progress;
 pub use pty::PtyHandle;
+pub use search::SearchMatch;
+pub use shell_integration::ShellIntegrationStatus;
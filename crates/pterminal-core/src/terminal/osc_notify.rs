@@ -0,0 +1,139 @@
+//! Notifications programs ask for with an escape sequence.
+//!
+//! iTerm2's `OSC 9 ; message` and urxvt's `OSC 777 ; notify ; title ; body`
+//! ask the terminal to notify the user. alacritty ignores both, so they are
+//! picked out here. ConEmu's numbered `OSC 9 ; n ; ...` commands (progress
+//! is `9;4`) share the prefix and are not notifications.
+
+/// Longest OSC 9/777 payload we collect; longer ones are dropped
+const MAX_OSC_LEN: usize = 1024;
+
+/// A notification asked for by a program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct OscNotification {
+    /// Only OSC 777 has a title
+    pub(crate) title: Option<String>,
+    pub(crate) body: String,
+}
+
+#[derive(Debug, Default)]
+enum State {
+    #[default]
+    Ground,
+    Escape,
+    Osc(Vec<u8>),
+    OscEscape(Vec<u8>),
+}
+
+/// Incremental scanner for OSC 9 and OSC 777 notifications; keeps state
+/// across PTY reads.
+#[derive(Debug, Default)]
+pub(crate) struct NotifyScanner {
+    state: State,
+}
+
+impl NotifyScanner {
+    /// Scan a chunk of PTY output for notifications, in order
+    pub(crate) fn scan(&mut self, data: &[u8]) -> Vec<OscNotification> {
+        let mut notifications = Vec::new();
+        for &byte in data {
+            if let Some(notification) = self.advance(byte) {
+                notifications.push(notification);
+            }
+        }
+        notifications
+    }
+
+    fn advance(&mut self, byte: u8) -> Option<OscNotification> {
+        let mut notification = None;
+        self.state = match std::mem::take(&mut self.state) {
+            State::Ground | State::Escape if byte == 0x1b => State::Escape,
+            State::Ground => State::Ground,
+            State::Escape if byte == b']' => State::Osc(Vec::new()),
+            State::Escape => State::Ground,
+            State::Osc(body) if byte == 0x07 => {
+                notification = parse_notification(&body);
+                State::Ground
+            }
+            State::Osc(body) if byte == 0x1b => State::OscEscape(body),
+            State::Osc(mut body) if body.len() < MAX_OSC_LEN => {
+                body.push(byte);
+                // Only OSC 9 and OSC 777 are collected; other OSCs are skipped
+                let prefix = if body.starts_with(b"7") {
+                    &b"777;"[..]
+                } else {
+                    b"9;"
+                };
+                if body.len() <= prefix.len() && !prefix.starts_with(&body) {
+                    State::Ground
+                } else {
+                    State::Osc(body)
+                }
+            }
+            State::Osc(_) => State::Ground,
+            State::OscEscape(body) => {
+                if byte == b'\\' {
+                    notification = parse_notification(&body);
+                    State::Ground
+                } else {
+                    // Not ST: the OSC was cancelled, reinterpret as a new escape
+                    self.state = State::Escape;
+                    return self.advance(byte);
+                }
+            }
+        };
+        notification
+    }
+}
+
+/// `9;message` or `777;notify;title;body`
+fn parse_notification(body: &[u8]) -> Option<OscNotification> {
+    let body = String::from_utf8_lossy(body);
+    if let Some(message) = body.strip_prefix("9;") {
+        // ConEmu's commands: a number, alone or followed by `;`
+        let number = message.split(';').next().unwrap_or_default();
+        if message.is_empty() || number.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        return Some(OscNotification {
+            title: None,
+            body: message.to_string(),
+        });
+    }
+    let (title, message) = body.strip_prefix("777;notify;")?.split_once(';')?;
+    Some(OscNotification {
+        title: Some(title.to_string()).filter(|title| !title.is_empty()),
+        body: message.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osc_9_and_777_notifications() {
+        let mut scanner = NotifyScanner::default();
+        let notification = |title: Option<&str>, body: &str| OscNotification {
+            title: title.map(str::to_string),
+            body: body.to_string(),
+        };
+        assert_eq!(
+            scanner.scan(b"\x1b]9;Build done\x07\x1b]777;notify;CI;tests passed\x1b\\"),
+            vec![
+                notification(None, "Build done"),
+                notification(Some("CI"), "tests passed"),
+            ]
+        );
+        // Split across reads
+        assert!(scanner.scan(b"\x1b]777;notify;;dep").is_empty());
+        assert_eq!(
+            scanner.scan(b"loyed\x07"),
+            vec![notification(None, "deployed")]
+        );
+        // ConEmu's progress and other commands, and other OSCs
+        assert!(scanner
+            .scan(b"\x1b]9;4;1;50\x07\x1b]9;12\x07\x1b]0;9;title\x07\x1b]7;file://h/\x07")
+            .is_empty());
+    }
+}
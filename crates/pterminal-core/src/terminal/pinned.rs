@@ -0,0 +1,146 @@
+//! A pane's screen pinned as a reference: a freeze-frame shown faintly
+//! through the blank parts of the live screen, so output from before a
+//! change can be compared with output after it. Cells whose text differs
+//! from the pinned screen are tinted.
+
+use crate::config::theme::RgbColor;
+
+use super::emulator::{GridCell, GridLine};
+
+/// How strongly pinned text shows through, from 0 (not at all) to 1
+const GHOST_OPACITY: f32 = 0.35;
+
+/// How strongly a changed cell's background takes the tint
+const CHANGED_TINT: f32 = 0.3;
+
+/// A snapshot of a pane's visible lines
+#[derive(Clone)]
+pub struct PinnedScreen {
+    lines: Vec<GridLine>,
+}
+
+impl PinnedScreen {
+    pub fn new(lines: Vec<GridLine>) -> Self {
+        Self { lines }
+    }
+
+    /// Write `live` to `out` with the pinned text showing through where the
+    /// live screen is blank, and cells whose text changed tinted with
+    /// `tint`. Returns how many cells changed.
+    pub fn overlay_into(
+        &self,
+        live: &[GridLine],
+        tint: RgbColor,
+        out: &mut Vec<GridLine>,
+    ) -> usize {
+        out.clear();
+        let mut changed = 0;
+        for (row, line) in live.iter().enumerate() {
+            let mut cells = line.cells.clone();
+            let pinned = self.lines.get(row).map_or(&[][..], |line| &line.cells[..]);
+            let mut col = 0;
+            while col < cells.len() {
+                let Some(&old) = pinned.get(col) else {
+                    break;
+                };
+                // A wide character with the spacer after it
+                let width = if pinned.get(col + 1).is_some_and(|next| next.wide_spacer) {
+                    2
+                } else {
+                    1
+                };
+                let span = col..(col + width).min(cells.len());
+                let shown = &mut cells[span.clone()];
+                if shown.iter().all(is_blank) {
+                    if !is_blank(&old) && !old.wide_spacer {
+                        for (cell, old) in shown.iter_mut().zip(&pinned[span.clone()]) {
+                            *cell = ghost(old, cell.bg);
+                        }
+                    }
+                } else if !is_blank(&old) && shown[0].c != old.c {
+                    changed += 1;
+                    for cell in shown {
+                        cell.bg = mix(cell.bg, tint, CHANGED_TINT);
+                    }
+                }
+                col = span.end.max(col + 1);
+            }
+            out.push(GridLine { cells });
+        }
+        changed
+    }
+}
+
+fn is_blank(cell: &GridCell) -> bool {
+    !cell.wide_spacer && matches!(cell.c, ' ' | '\0')
+}
+
+/// `old` drawn faintly on a background of `bg`
+fn ghost(old: &GridCell, bg: RgbColor) -> GridCell {
+    GridCell {
+        fg: mix(bg, old.fg, GHOST_OPACITY),
+        bg,
+        underline: false,
+        ..*old
+    }
+}
+
+fn mix(from: RgbColor, to: RgbColor, t: f32) -> RgbColor {
+    let channel = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * t).round() as u8;
+    RgbColor::new(
+        channel(from.r, to.r),
+        channel(from.g, to.g),
+        channel(from.b, to.b),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(text: &str) -> GridLine {
+        let cell = |c| GridCell {
+            c,
+            fg: RgbColor::new(0xff, 0xff, 0xff),
+            bg: RgbColor::new(0, 0, 0),
+            bold: false,
+            italic: false,
+            underline: false,
+            wide_spacer: false,
+        };
+        GridLine {
+            cells: text.chars().map(cell).collect(),
+        }
+    }
+
+    fn text(line: &GridLine) -> String {
+        line.cells.iter().map(|cell| cell.c).collect()
+    }
+
+    #[test]
+    fn pinned_text_shows_through() {
+        let pinned = PinnedScreen::new(vec![line("ok 12 tests  "), line("done         ")]);
+        let live = [line("ok 13 tests  "), line("             ")];
+        let tint = RgbColor::new(0xff, 0xff, 0);
+        let mut out = Vec::new();
+        assert_eq!(pinned.overlay_into(&live, tint, &mut out), 1);
+
+        // Live text wins; the changed digit is tinted
+        assert_eq!(text(&out[0]), "ok 13 tests  ");
+        assert_eq!(
+            out[0].cells[4].bg,
+            mix(RgbColor::new(0, 0, 0), tint, CHANGED_TINT)
+        );
+        assert_eq!(out[0].cells[3].bg, RgbColor::new(0, 0, 0));
+        // The blank line shows the pinned one, faintly
+        assert_eq!(text(&out[1]), "done         ");
+        let faint = out[1].cells[0].fg;
+        assert!(faint.r < 0xff && faint.r > 0);
+
+        // More live lines than pinned ones are left alone
+        let live = [line("a"), line("b"), line("c")];
+        assert_eq!(pinned.overlay_into(&live, tint, &mut out), 2);
+        assert_eq!(out.len(), 3);
+        assert_eq!(text(&out[2]), "c");
+    }
+}
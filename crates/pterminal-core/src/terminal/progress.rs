@@ -0,0 +1,241 @@
+//! Progress reported by programs, shown on the Dock/taskbar and as a strip
+//! on the pane.
+//!
+//! Programs that support it emit ConEmu's `OSC 9 ; 4 ; state ; percent`
+//! (state 0 clears, 1 is normal, 2 error, 3 indeterminate, 4 paused).
+//! Optionally, percentages in lines redrawn in place with CR, as plain-text
+//! progress bars do, count too; the line ending clears them.
+
+/// Longest OSC 9;4 payload we collect
+const MAX_OSC_LEN: usize = 32;
+/// Tail of a redrawn line searched for a percentage
+const MAX_LINE_LEN: usize = 256;
+
+/// A program's progress
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Progress {
+    Normal(u8),
+    Error(u8),
+    Indeterminate,
+    Paused(u8),
+}
+
+impl Progress {
+    /// Percent done, `None` when indeterminate
+    pub fn percent(self) -> Option<u8> {
+        match self {
+            Self::Normal(percent) | Self::Error(percent) | Self::Paused(percent) => Some(percent),
+            Self::Indeterminate => None,
+        }
+    }
+
+    /// One progress for several panes: an error if any, else the furthest
+    /// behind, else indeterminate
+    pub fn overall(progress: impl IntoIterator<Item = Progress>) -> Option<Progress> {
+        let mut overall: Option<Progress> = None;
+        for progress in progress {
+            overall = Some(match (overall, progress) {
+                (Some(error @ Self::Error(_)), _) | (_, error @ Self::Error(_)) => error,
+                (Some(current), next) => match (current.percent(), next.percent()) {
+                    (Some(a), Some(b)) if b < a => next,
+                    (None, Some(_)) => next,
+                    _ => current,
+                },
+                (None, next) => next,
+            });
+        }
+        overall
+    }
+}
+
+#[derive(Debug, Default)]
+enum State {
+    #[default]
+    Ground,
+    Escape,
+    /// Any other escape sequence, skipped up to its final byte
+    Sequence,
+    Osc(Vec<u8>),
+    OscEscape(Vec<u8>),
+}
+
+/// Incremental scanner for progress; keeps state across PTY reads.
+#[derive(Debug, Default)]
+pub(crate) struct ProgressScanner {
+    state: State,
+    /// Also read percentages from lines redrawn with CR
+    pub(crate) text: bool,
+    /// Printable text of the current line (the tail, when long)
+    line: Vec<u8>,
+    /// A CR was seen; the next byte tells a redraw from a line ending
+    after_cr: bool,
+    /// Progress last read from text, cleared when its line ends
+    text_active: bool,
+}
+
+impl ProgressScanner {
+    /// Scan a chunk of PTY output. Returns the last change in it: a new
+    /// progress, or `Some(None)` once it is cleared.
+    pub(crate) fn scan(&mut self, data: &[u8]) -> Option<Option<Progress>> {
+        let mut update = None;
+        for &byte in data {
+            if let Some(progress) = self.advance(byte) {
+                update = Some(progress);
+            }
+        }
+        update
+    }
+
+    fn advance(&mut self, byte: u8) -> Option<Option<Progress>> {
+        let mut update = None;
+        if self.text && self.after_cr && byte != b'\n' {
+            // Redrawn in place
+            self.after_cr = false;
+            if let Some(percent) = text_percent(&self.line) {
+                self.text_active = true;
+                update = Some(Some(Progress::Normal(percent)));
+            }
+            self.line.clear();
+        }
+        self.state = match std::mem::take(&mut self.state) {
+            State::Ground | State::Escape if byte == 0x1b => State::Escape,
+            State::Ground => {
+                if self.text {
+                    update = self.text_byte(byte).or(update);
+                }
+                State::Ground
+            }
+            State::Escape if byte == b']' => State::Osc(Vec::new()),
+            State::Escape if byte == b'[' => State::Sequence,
+            State::Escape => State::Ground,
+            State::Sequence if (0x40..=0x7e).contains(&byte) => State::Ground,
+            State::Sequence => State::Sequence,
+            State::Osc(body) if byte == 0x07 => {
+                update = parse_progress(&body).or(update);
+                State::Ground
+            }
+            State::Osc(body) if byte == 0x1b => State::OscEscape(body),
+            State::Osc(mut body) if body.len() < MAX_OSC_LEN => {
+                body.push(byte);
+                // Only OSC 9;4 is collected; other OSCs are skipped
+                if body.len() <= 4 && !b"9;4;".starts_with(&body) {
+                    State::Ground
+                } else {
+                    State::Osc(body)
+                }
+            }
+            State::Osc(_) => State::Ground,
+            State::OscEscape(body) => {
+                if byte == b'\\' {
+                    update = parse_progress(&body).or(update);
+                    State::Ground
+                } else {
+                    // Not ST: the OSC was cancelled, reinterpret as a new escape
+                    self.state = State::Escape;
+                    return self.advance(byte).or(update);
+                }
+            }
+        };
+        update
+    }
+
+    /// Track the line's text; a line ending clears progress read from it
+    fn text_byte(&mut self, byte: u8) -> Option<Option<Progress>> {
+        match byte {
+            b'\r' => self.after_cr = true,
+            b'\n' => {
+                self.after_cr = false;
+                self.line.clear();
+                if std::mem::take(&mut self.text_active) {
+                    return Some(None);
+                }
+            }
+            0x20.. => {
+                if self.line.len() == MAX_LINE_LEN {
+                    self.line.remove(0);
+                }
+                self.line.push(byte);
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+/// `9;4;state[;percent]`
+fn parse_progress(body: &[u8]) -> Option<Option<Progress>> {
+    let params = std::str::from_utf8(body.strip_prefix(b"9;4;")?).ok()?;
+    let mut fields = params.split(';');
+    let state = fields.next()?;
+    let percent = fields
+        .next()
+        .and_then(|percent| percent.trim().parse::<u32>().ok())
+        .unwrap_or(0)
+        .min(100) as u8;
+    match state {
+        "0" => Some(None),
+        "1" => Some(Some(Progress::Normal(percent))),
+        "2" => Some(Some(Progress::Error(percent))),
+        "3" => Some(Some(Progress::Indeterminate)),
+        "4" => Some(Some(Progress::Paused(percent))),
+        _ => None,
+    }
+}
+
+/// The last `NN%` (or `NN.N%`) in a line
+fn text_percent(line: &[u8]) -> Option<u8> {
+    let text = String::from_utf8_lossy(line);
+    text.match_indices('%').rev().find_map(|(at, _)| {
+        let number = text[..at]
+            .rsplit(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .next()?;
+        let percent: f32 = number.parse().ok()?;
+        (0.0..=100.0).contains(&percent).then_some(percent as u8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osc_progress() {
+        let mut scanner = ProgressScanner::default();
+        assert_eq!(
+            scanner.scan(b"\x1b]9;4;1;42\x07build"),
+            Some(Some(Progress::Normal(42)))
+        );
+        assert_eq!(scanner.scan(b"\x1b]9;4;3\x1b"), None);
+        assert_eq!(scanner.scan(b"\\"), Some(Some(Progress::Indeterminate)));
+        assert_eq!(scanner.scan(b"\x1b]9;4;0;0\x07"), Some(None));
+        // OSC 9 notifications and other OSCs are not progress
+        assert_eq!(scanner.scan(b"\x1b]9;done 50%\x07\x1b]0;9;4;1\x07"), None);
+    }
+
+    #[test]
+    fn text_progress() {
+        let mut scanner = ProgressScanner {
+            text: true,
+            ..Default::default()
+        };
+        // Lines that are not redrawn don't count
+        assert_eq!(scanner.scan(b"coverage 87%\r\n"), None);
+        assert_eq!(
+            scanner.scan(b"[###   ] \x1b[1m12.5%\x1b[0m\r[####  ] 50%\r"),
+            Some(Some(Progress::Normal(12)))
+        );
+        assert_eq!(scanner.scan(b"["), Some(Some(Progress::Normal(50))));
+        assert_eq!(scanner.scan(b"######] 100%\r\n"), Some(None));
+    }
+
+    #[test]
+    fn overall_progress() {
+        use Progress::*;
+        assert_eq!(
+            Progress::overall([Indeterminate, Normal(60), Paused(20)]),
+            Some(Paused(20))
+        );
+        assert_eq!(Progress::overall([Normal(10), Error(90)]), Some(Error(90)));
+        assert_eq!(Progress::overall([]), None);
+    }
+}
@@ -1,19 +1,27 @@
 use std::io::{Read, Write};
+use std::path::PathBuf;
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc, Mutex,
 };
 use std::time::Duration;
 
 use anyhow::Result;
 use portable_pty::{CommandBuilder, NativePtySystem, PtyPair, PtySize, PtySystem};
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
 
+use crate::crash::{self, Failure};
+use crate::pane_log::PaneLog;
+use crate::power;
 use crate::terminal::emulator::TerminalEmulatorHandle;
+use crate::terminal::line_clamp::{ClampedLine, LineClamp};
 use crate::terminal::spsc;
 
 const INPUT_QUEUE_DEPTH: usize = 1024;
-const WRITER_IDLE_PARK_MS: u64 = 5;
+const WRITER_IDLE_PARK: Duration = Duration::from_millis(5);
+/// XOFF (DC3, Ctrl+S) and XON (DC1, Ctrl+Q)
+const XOFF: u8 = 0x13;
+const XON: u8 = 0x11;
 
 /// Handle to a running PTY process
 pub struct PtyHandle {
@@ -22,9 +30,21 @@ pub struct PtyHandle {
     master: Box<dyn portable_pty::MasterPty + Send>,
     reader_thread: Option<std::thread::JoinHandle<()>>,
     writer_thread: Option<std::thread::JoinHandle<()>>,
-    _child: Box<dyn portable_pty::Child + Send + Sync>,
+    child: Box<dyn portable_pty::Child + Send + Sync>,
     /// Set to true when the reader thread exits (shell process ended)
     exited: Arc<AtomicBool>,
+    /// Log the reader thread tees output into
+    log: Arc<Mutex<Option<PaneLog>>>,
+    /// Longest line shown before it is cut short (0 = no limit)
+    max_line_bytes: Arc<AtomicUsize>,
+    /// Last line cut short, until taken
+    clamped: Arc<Mutex<Option<ClampedLine>>>,
+    /// Whether XOFF in the output stops input being sent
+    honor_xoff: Arc<AtomicBool>,
+    /// Input held back by XOFF, until XON
+    input_stopped: Arc<AtomicBool>,
+    /// Set if the reader or writer thread panicked
+    failure: Failure,
 }
 
 impl PtyHandle {
@@ -34,7 +54,7 @@ impl PtyHandle {
         working_dir: &std::path::Path,
         cols: u16,
         rows: u16,
-        emulator: TerminalEmulatorHandle,
+        mut emulator: TerminalEmulatorHandle,
         on_output_ready: impl Fn() + Send + 'static,
         on_exit: impl Fn() + Send + 'static,
     ) -> Result<Self> {
@@ -55,6 +75,8 @@ impl PtyHandle {
         }
         cmd.env("TERM", "xterm-256color");
         cmd.env("COLORTERM", "truecolor");
+        // What shell integration snippets check before emitting anything
+        cmd.env("TERM_PROGRAM", "pterminal");
 
         let child = pair.slave.spawn_command(cmd)?;
         debug!(shell = shell, "PTY process spawned");
@@ -64,55 +86,99 @@ impl PtyHandle {
 
         let mut writer = pair.master.take_writer()?;
         let (input_tx, input_rx) = spsc::channel::<Vec<u8>>(INPUT_QUEUE_DEPTH);
+        let reply_rx = emulator.take_reply_receiver();
         let exited = Arc::new(AtomicBool::new(false));
         let exited_clone = exited.clone();
+        let log: Arc<Mutex<Option<PaneLog>>> = Arc::default();
+        let log_clone = log.clone();
+        let max_line_bytes = Arc::new(AtomicUsize::new(0));
+        let max_line_bytes_clone = max_line_bytes.clone();
+        let clamped: Arc<Mutex<Option<ClampedLine>>> = Arc::default();
+        let clamped_clone = clamped.clone();
+        let honor_xoff = Arc::new(AtomicBool::new(false));
+        let honor_xoff_clone = honor_xoff.clone();
+        let input_stopped = Arc::new(AtomicBool::new(false));
+        let (stopped_reader, stopped_writer) = (input_stopped.clone(), input_stopped.clone());
+        let failure = Failure::default();
+        let (reader_failure, writer_failure) = (failure.clone(), failure.clone());
 
         // Spawn dedicated writer thread so UI/input handling never blocks on PTY writes.
         let writer_thread = std::thread::Builder::new()
             .name("pty-writer".into())
-            .spawn(move || loop {
-                let mut did_work = false;
-                while let Some(chunk) = input_rx.try_pop() {
-                    if chunk.is_empty() {
-                        continue;
+            .spawn(move || {
+                crash::guard(&writer_failure, move || loop {
+                    let mut did_work = false;
+                    // Answers to terminal queries go out alongside user input
+                    for reply in reply_rx.iter().flat_map(|rx| rx.try_iter()) {
+                        if let Err(e) = writer.write_all(&reply) {
+                            error!("PTY write error: {}", e);
+                            return;
+                        }
+                        did_work = true;
                     }
-                    if let Err(e) = writer.write_all(&chunk) {
-                        error!("PTY write error: {}", e);
-                        return;
+                    // Held in the queue while stopped; replies still go out
+                    while !stopped_writer.load(Ordering::Acquire) {
+                        let Some(chunk) = input_rx.try_pop() else {
+                            break;
+                        };
+                        if chunk.is_empty() {
+                            continue;
+                        }
+                        if let Err(e) = writer.write_all(&chunk) {
+                            error!("PTY write error: {}", e);
+                            return;
+                        }
+                        did_work = true;
                     }
-                    did_work = true;
-                }
 
-                if !did_work {
-                    if input_rx.is_producer_closed() {
-                        return;
+                    if !did_work {
+                        if input_rx.is_producer_closed() {
+                            return;
+                        }
+                        std::thread::park_timeout(power::idle_park(WRITER_IDLE_PARK));
                     }
-                    std::thread::park_timeout(Duration::from_millis(WRITER_IDLE_PARK_MS));
-                }
+                })
             })?;
         let writer_waker = writer_thread.thread().clone();
+        let writer_waker_reader = writer_waker.clone();
 
         // Spawn reader thread with 1MB buffer for high throughput
         let mut reader = pair.master.try_clone_reader()?;
         let reader_thread = std::thread::Builder::new()
             .name("pty-reader".into())
             .spawn(move || {
-                // 1MB heap-allocated buffer for better I/O throughput (vs 8KB stack)
-                let mut buf = vec![0u8; 1024 * 1024];
-                loop {
-                    match reader.read(&mut buf) {
-                        Ok(0) => break,
-                        Ok(n) => {
-                            emulator.process(&buf[..n]);
-                            on_output_ready();
-                        }
-                        Err(e) => {
-                            error!("PTY read error: {}", e);
-                            break;
+                crash::guard(&reader_failure, || {
+                    // 1MB heap-allocated buffer for better I/O throughput (vs 8KB stack)
+                    let mut buf = vec![0u8; 1024 * 1024];
+                    let mut clamp = LineClamp::default();
+                    loop {
+                        match reader.read(&mut buf) {
+                            Ok(0) => break,
+                            Ok(n) => {
+                                clamp.set_max_bytes(max_line_bytes_clone.load(Ordering::Relaxed));
+                                if honor_xoff_clone.load(Ordering::Relaxed) {
+                                    if let Some(stop) = flow_control(&buf[..n]) {
+                                        stopped_reader.store(stop, Ordering::Release);
+                                        writer_waker_reader.unpark();
+                                    }
+                                }
+                                emulator.process(&clamp.filter(&buf[..n]));
+                                if let Some(line) = clamp.take_clamped() {
+                                    *lock(&clamped_clone) = Some(line);
+                                }
+                                on_output_ready();
+                                // Logs get the output in full
+                                tee_output(&log_clone, &buf[..n]);
+                            }
+                            Err(e) => {
+                                error!("PTY read error: {}", e);
+                                break;
+                            }
                         }
                     }
-                }
-                exited_clone.store(true, Ordering::Release);
+                    exited_clone.store(true, Ordering::Release);
+                });
+                // Wakes the UI to close the pane, or to show that it failed
                 on_exit();
             })?;
 
@@ -122,8 +188,14 @@ impl PtyHandle {
             master: pair.master,
             reader_thread: Some(reader_thread),
             writer_thread: Some(writer_thread),
-            _child: child,
+            child,
             exited,
+            log,
+            max_line_bytes,
+            clamped,
+            honor_xoff,
+            input_stopped,
+            failure,
         })
     }
 
@@ -138,6 +210,10 @@ impl PtyHandle {
             .ok_or_else(|| anyhow::anyhow!("PTY input queue disconnected"))?
             .push_blocking(data.to_vec())
             .map_err(|_| anyhow::anyhow!("PTY input queue disconnected"))?;
+        // Typing Ctrl+Q resumes input stopped by XOFF
+        if data.contains(&XON) {
+            self.input_stopped.store(false, Ordering::Release);
+        }
         self.writer_waker.unpark();
         Ok(())
     }
@@ -153,10 +229,224 @@ impl PtyHandle {
         Ok(())
     }
 
+    /// Why the reader or writer thread stopped, if it panicked. The shell
+    /// may still run, but output or input no longer gets through.
+    pub fn failure(&self) -> Option<String> {
+        self.failure.get()
+    }
+
     /// Check if the shell process has exited
     pub fn is_alive(&self) -> bool {
         !self.exited.load(Ordering::Acquire)
     }
+
+    /// Exit status of the shell once it has ended and been reaped
+    pub fn exit_code(&mut self) -> Option<i32> {
+        let status = self.child.try_wait().ok()??;
+        Some(status.exit_code() as i32)
+    }
+
+    /// Tee output into `log` from now on, replacing any log already open
+    pub fn start_log(&self, log: PaneLog) {
+        *lock(&self.log) = Some(log);
+    }
+
+    /// Stop logging, returning the path that was being written
+    pub fn stop_log(&self) -> Option<PathBuf> {
+        let log = lock(&self.log).take()?;
+        Some(log.path().to_path_buf())
+    }
+
+    /// Path of the log being written, if any
+    pub fn log_path(&self) -> Option<PathBuf> {
+        let log = lock(&self.log);
+        log.as_ref().map(|log| log.path().to_path_buf())
+    }
+
+    /// Cut lines longer than `max_bytes` short on screen (0 = no limit)
+    pub fn set_max_line_bytes(&self, max_bytes: usize) {
+        self.max_line_bytes.store(max_bytes, Ordering::Relaxed);
+    }
+
+    /// Stop sending input when the program outputs XOFF, until it outputs
+    /// XON or Ctrl+Q is typed; otherwise both are ignored
+    pub fn set_honor_xoff(&self, honor: bool) {
+        self.honor_xoff.store(honor, Ordering::Relaxed);
+        if !honor {
+            self.input_stopped.store(false, Ordering::Release);
+            self.writer_waker.unpark();
+        }
+    }
+
+    /// Whether input is held back by XOFF
+    pub fn input_stopped(&self) -> bool {
+        self.input_stopped.load(Ordering::Acquire)
+    }
+
+    /// The last line cut short since the previous call, in full
+    pub fn take_clamped_line(&self) -> Option<ClampedLine> {
+        lock(&self.clamped).take()
+    }
+
+    /// Process ID of the shell
+    pub fn child_pid(&self) -> Option<u32> {
+        self.child.process_id()
+    }
+
+    /// Current working directory of the shell, if the platform exposes it
+    pub fn cwd(&self) -> Option<std::path::PathBuf> {
+        process_cwd(self.child_pid()?)
+    }
+
+    /// Value of an environment variable in the shell, if the platform exposes it
+    pub fn env_var(&self, key: &str) -> Option<String> {
+        process_env_var(self.child_pid()?, key)
+    }
+
+    /// Command line of the terminal's foreground process: the running
+    /// program, or the shell itself at the prompt
+    pub fn foreground_command(&self) -> Option<Vec<String>> {
+        #[cfg(unix)]
+        let pid = self.master.process_group_leader()? as u32;
+        #[cfg(not(unix))]
+        let pid = self.child_pid()?;
+        process_argv(pid)
+    }
+}
+
+/// Whether output leaves input stopped (last XOFF) or resumed (last XON),
+/// if it has either
+fn flow_control(output: &[u8]) -> Option<bool> {
+    output.iter().rev().find_map(|&b| match b {
+        XOFF => Some(true),
+        XON => Some(false),
+        _ => None,
+    })
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Append output to the pane's log; a failing log is closed.
+fn tee_output(log: &Mutex<Option<PaneLog>>, bytes: &[u8]) {
+    let mut log = lock(log);
+    let Some(open) = log.as_mut() else {
+        return;
+    };
+    if let Err(e) = open.write(bytes) {
+        warn!(path = %open.path().display(), "Pane log stopped: {e:#}");
+        *log = None;
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn process_cwd(pid: u32) -> Option<std::path::PathBuf> {
+    std::fs::read_link(format!("/proc/{pid}/cwd")).ok()
+}
+
+#[cfg(target_os = "macos")]
+fn process_cwd(pid: u32) -> Option<std::path::PathBuf> {
+    use std::os::unix::ffi::OsStrExt;
+    // No procfs on macOS; the kernel reports the cwd's vnode path
+    let mut info: libc::proc_vnodepathinfo = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of_val(&info) as libc::c_int;
+    let written = unsafe {
+        libc::proc_pidinfo(
+            pid as libc::c_int,
+            libc::PROC_PIDVNODEPATHINFO,
+            0,
+            (&mut info as *mut libc::proc_vnodepathinfo).cast(),
+            size,
+        )
+    };
+    if written != size {
+        return None;
+    }
+    // A NUL-terminated path of MAXPATHLEN chars, split in rows by libc
+    let bytes: Vec<u8> = info
+        .pvi_cdir
+        .vip_path
+        .iter()
+        .flatten()
+        .take_while(|&&c| c != 0)
+        .map(|&c| c as u8)
+        .collect();
+    (!bytes.is_empty()).then(|| std::ffi::OsStr::from_bytes(&bytes).into())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn process_cwd(_pid: u32) -> Option<std::path::PathBuf> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn process_env_var(pid: u32, key: &str) -> Option<String> {
+    // Only the environment the shell started with; later exports are not visible
+    let environ = std::fs::read(format!("/proc/{pid}/environ")).ok()?;
+    environ.split(|&b| b == 0).find_map(|entry| {
+        let value = entry.strip_prefix(key.as_bytes())?.strip_prefix(b"=")?;
+        Some(String::from_utf8_lossy(value).into_owned())
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_env_var(_pid: u32, _key: &str) -> Option<String> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn process_argv(pid: u32) -> Option<Vec<String>> {
+    let cmdline = std::fs::read(format!("/proc/{pid}/cmdline")).ok()?;
+    let argv: Vec<String> = cmdline
+        .split(|&b| b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect();
+    (!argv.is_empty()).then_some(argv)
+}
+
+#[cfg(target_os = "macos")]
+fn process_argv(pid: u32) -> Option<Vec<String>> {
+    let null = std::ptr::null_mut();
+    let mut arg_max: libc::c_int = 0;
+    let mut size = std::mem::size_of_val(&arg_max);
+    let mut mib = [libc::CTL_KERN, libc::KERN_ARGMAX];
+    let ok = unsafe {
+        let buffer = (&mut arg_max as *mut libc::c_int).cast();
+        libc::sysctl(mib.as_mut_ptr(), 2, buffer, &mut size, null, 0) == 0
+    };
+    if !ok {
+        return None;
+    }
+    // argc, the executable path padded with NULs, then argv and the
+    // environment, NUL-separated
+    let mut args = vec![0u8; usize::try_from(arg_max).ok()?];
+    let mut size = args.len();
+    let mut mib = [libc::CTL_KERN, libc::KERN_PROCARGS2, pid as libc::c_int];
+    let ok = unsafe {
+        let buffer = args.as_mut_ptr().cast();
+        libc::sysctl(mib.as_mut_ptr(), 3, buffer, &mut size, null, 0) == 0
+    };
+    if !ok {
+        return None;
+    }
+    let args = args.get(..size)?;
+    let argc = i32::from_ne_bytes(args.get(..4)?.try_into().ok()?);
+    let rest = &args[4..];
+    let exe_end = rest.iter().position(|&b| b == 0)?;
+    let argv_start = exe_end + rest[exe_end..].iter().position(|&b| b != 0)?;
+    let argv: Vec<String> = rest[argv_start..]
+        .split(|&b| b == 0)
+        .take(usize::try_from(argc).ok()?)
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect();
+    (!argv.is_empty()).then_some(argv)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn process_argv(_pid: u32) -> Option<Vec<String>> {
+    None
 }
 
 impl Drop for PtyHandle {
@@ -171,3 +461,16 @@ impl Drop for PtyHandle {
         let _ = self.writer_thread.take();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_xon_or_xoff_wins() {
+        assert_eq!(flow_control(b"plain output\r\n"), None);
+        assert_eq!(flow_control(b"a\x13b"), Some(true));
+        assert_eq!(flow_control(b"\x13wait\x11go"), Some(false));
+        assert_eq!(flow_control(b"\x11\x1b[1m\x13"), Some(true));
+    }
+}
@@ -0,0 +1,194 @@
+//! Answers terminal queries that alacritty_terminal does not handle itself.
+//!
+//! DA1/DA2 are answered by alacritty and forwarded as `PtyWrite` events;
+//! XTVERSION (`CSI > q`) and XTGETTCAP (`DCS + q <hex> ST`) are recognized
+//! here by a small scanner that runs alongside the VTE parser.
+
+/// Name reported by XTVERSION
+const TERMINAL_NAME: &str = "pterminal";
+
+/// Longest CSI parameter string we bother to collect
+const MAX_CSI_LEN: usize = 16;
+/// Longest XTGETTCAP request we accept before giving up on the sequence
+const MAX_DCS_LEN: usize = 1024;
+
+/// Capabilities advertised through XTGETTCAP.
+///
+/// `None` marks a boolean capability (reported without a value).
+const CAPABILITIES: &[(&str, Option<&str>)] = &[
+    ("TN", Some("xterm-256color")),
+    ("name", Some("xterm-256color")),
+    ("Co", Some("256")),
+    ("colors", Some("256")),
+    ("RGB", None),
+    ("Tc", None),
+    ("setrgbf", Some("\x1b[38;2;%p1%d;%p2%d;%p3%dm")),
+    ("setrgbb", Some("\x1b[48;2;%p1%d;%p2%d;%p3%dm")),
+    ("Smulx", Some("\x1b[4:%p1%dm")),
+    ("Ss", Some("\x1b[%p1%d q")),
+    ("Se", Some("\x1b[2 q")),
+    ("Ms", Some("\x1b]52;%p1%s;%p2%s\x07")),
+    ("Sync", Some("\x1b[?2026%?%p1%{1}%-%tl%eh%;")),
+    ("kbs", Some("\x7f")),
+];
+
+#[derive(Debug, Default)]
+enum State {
+    #[default]
+    Ground,
+    Escape,
+    Csi(Vec<u8>),
+    Dcs(Vec<u8>),
+    DcsEscape(Vec<u8>),
+}
+
+/// Incremental scanner for query sequences; keeps state across PTY reads.
+#[derive(Debug, Default)]
+pub(crate) struct QueryScanner {
+    state: State,
+}
+
+impl QueryScanner {
+    /// Scan a chunk of PTY output. Each reply to write back is returned
+    /// with the offset just past its query, so it can go out after the
+    /// parser's replies to earlier queries.
+    pub(crate) fn scan(&mut self, data: &[u8]) -> Vec<(usize, Vec<u8>)> {
+        let mut replies = Vec::new();
+        let mut reply = Vec::new();
+        for (i, &byte) in data.iter().enumerate() {
+            self.advance(byte, &mut reply);
+            if !reply.is_empty() {
+                replies.push((i + 1, std::mem::take(&mut reply)));
+            }
+        }
+        replies
+    }
+
+    fn advance(&mut self, byte: u8, replies: &mut Vec<u8>) {
+        self.state = match std::mem::take(&mut self.state) {
+            State::Ground | State::Escape if byte == 0x1b => State::Escape,
+            State::Ground => State::Ground,
+            State::Escape => match byte {
+                b'[' => State::Csi(Vec::new()),
+                b'P' => State::Dcs(Vec::new()),
+                _ => State::Ground,
+            },
+            State::Csi(mut params) => match byte {
+                0x1b => State::Escape,
+                0x20..=0x3f if params.len() < MAX_CSI_LEN => {
+                    params.push(byte);
+                    State::Csi(params)
+                }
+                0x40..=0x7e => {
+                    if byte == b'q' && matches!(params.as_slice(), b">" | b">0") {
+                        xtversion(replies);
+                    }
+                    State::Ground
+                }
+                // C0 controls are executed mid-sequence without ending it
+                0x00..=0x1f => State::Csi(params),
+                _ => State::Ground,
+            },
+            State::Dcs(mut body) => match byte {
+                0x1b => State::DcsEscape(body),
+                _ if body.len() < MAX_DCS_LEN => {
+                    body.push(byte);
+                    // Only XTGETTCAP is collected; other DCS payloads (sixel,
+                    // tmux passthrough) are skipped until the next ESC.
+                    if body.len() == 2 && body != b"+q" {
+                        State::Ground
+                    } else {
+                        State::Dcs(body)
+                    }
+                }
+                _ => State::Ground,
+            },
+            State::DcsEscape(body) => {
+                if byte == b'\\' {
+                    if let Some(names) = body.strip_prefix(b"+q") {
+                        xtgettcap(names, replies);
+                    }
+                    State::Ground
+                } else {
+                    // Not ST: the DCS was cancelled, reinterpret as a new escape
+                    self.state = State::Escape;
+                    return self.advance(byte, replies);
+                }
+            }
+        };
+    }
+}
+
+/// `DCS > | name(version) ST`
+fn xtversion(replies: &mut Vec<u8>) {
+    replies.extend_from_slice(b"\x1bP>|");
+    replies.extend_from_slice(TERMINAL_NAME.as_bytes());
+    replies.push(b'(');
+    replies.extend_from_slice(env!("CARGO_PKG_VERSION").as_bytes());
+    replies.extend_from_slice(b")\x1b\\");
+}
+
+/// One `DCS 1 + r name=value ST` per known capability, `DCS 0 + r ST` otherwise.
+fn xtgettcap(names: &[u8], replies: &mut Vec<u8>) {
+    for hex_name in names.split(|&b| b == b';') {
+        let known = hex_decode(hex_name).and_then(|name| {
+            CAPABILITIES
+                .iter()
+                .find(|(cap, _)| cap.as_bytes() == name.as_slice())
+        });
+        match known {
+            Some((_, value)) => {
+                replies.extend_from_slice(b"\x1bP1+r");
+                replies.extend_from_slice(hex_name);
+                if let Some(value) = value {
+                    replies.push(b'=');
+                    replies.extend_from_slice(hex_encode(value.as_bytes()).as_bytes());
+                }
+            }
+            None => replies.extend_from_slice(b"\x1bP0+r"),
+        }
+        replies.extend_from_slice(b"\x1b\\");
+    }
+}
+
+fn hex_decode(hex: &[u8]) -> Option<Vec<u8>> {
+    if hex.is_empty() || hex.len() % 2 != 0 {
+        return None;
+    }
+    hex.chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02X}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xtversion_reply() {
+        let mut scanner = QueryScanner::default();
+        let reply = scanner.scan(b"ls\x1b[>0q");
+        let expected = format!("\x1bP>|pterminal({})\x1b\\", env!("CARGO_PKG_VERSION"));
+        assert_eq!(reply, [(7, expected.into_bytes())]);
+    }
+
+    #[test]
+    fn xtgettcap_split_across_reads() {
+        let mut scanner = QueryScanner::default();
+        // "Co" and an unknown "zz"
+        assert!(scanner.scan(b"\x1bP+q436F;").is_empty());
+        let reply = scanner.scan(b"7A7A\x1b\\");
+        let expected = b"\x1bP1+r436F=323536\x1b\\\x1bP0+r\x1b\\";
+        assert_eq!(reply, [(6, expected.to_vec())]);
+    }
+
+    #[test]
+    fn other_dcs_ignored() {
+        let mut scanner = QueryScanner::default();
+        assert!(scanner.scan(b"\x1bPq#0;2;0;0;0\x1b\\\x1b[?25h").is_empty());
+    }
+}
@@ -0,0 +1,120 @@
+//! Finding text in a terminal's scrollback and screen.
+//!
+//! Lines are searched one grid row at a time, so a match broken by a soft
+//! wrap is not found. The query is plain text, case-insensitive unless it
+//! has an uppercase letter.
+
+use alacritty_terminal::grid::{Dimensions, Grid};
+use alacritty_terminal::index::{Column, Line};
+use alacritty_terminal::term::cell::{Cell, Flags};
+
+/// A line with at least one match
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchMatch {
+    /// Absolute buffer line, counted from the oldest line of history
+    pub line: usize,
+    /// First and last cell of the first match on the line
+    pub columns: (usize, usize),
+    /// Matches on the line
+    pub count: usize,
+    /// Text of the line, trailing blanks trimmed
+    pub text: String,
+}
+
+/// A query, folded for matching
+pub(crate) struct Needle {
+    chars: Vec<char>,
+    ignore_case: bool,
+}
+
+impl Needle {
+    /// `None` for a blank query
+    pub(crate) fn new(query: &str) -> Option<Self> {
+        if query.trim().is_empty() {
+            return None;
+        }
+        let ignore_case = !query.chars().any(char::is_uppercase);
+        let chars = query.chars().map(|c| fold(c, ignore_case)).collect();
+        Some(Self { chars, ignore_case })
+    }
+
+    /// Start of each match in `hay`, by char index; matches don't overlap
+    fn find_all(&self, hay: &[char]) -> Vec<usize> {
+        let mut found = Vec::new();
+        let mut start = 0;
+        while start + self.chars.len() <= hay.len() {
+            let candidate = &hay[start..start + self.chars.len()];
+            if candidate
+                .iter()
+                .zip(&self.chars)
+                .all(|(&c, &n)| fold(c, self.ignore_case) == n)
+            {
+                found.push(start);
+                start += self.chars.len();
+            } else {
+                start += 1;
+            }
+        }
+        found
+    }
+}
+
+fn fold(c: char, ignore_case: bool) -> char {
+    if ignore_case {
+        c.to_lowercase().next().unwrap_or(c)
+    } else {
+        c
+    }
+}
+
+/// The last `limit` lines of `grid` matching `needle`, top first
+pub(crate) fn search(grid: &Grid<Cell>, needle: &Needle, limit: usize) -> Vec<SearchMatch> {
+    let history = grid.history_size();
+    let mut found = Vec::new();
+    for line in (0..grid.total_lines()).rev() {
+        if found.len() == limit {
+            break;
+        }
+        let row = &grid[Line(line as i32 - history as i32)];
+        // Each char with the cell it starts in; spacers after wide chars
+        // have none
+        let (chars, columns): (Vec<char>, Vec<usize>) = (0..grid.columns())
+            .map(|col| (&row[Column(col)], col))
+            .filter(|(cell, _)| {
+                !cell
+                    .flags
+                    .intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+            })
+            .map(|(cell, col)| (cell.c, col))
+            .unzip();
+        let starts = needle.find_all(&chars);
+        let Some(&first) = starts.first() else {
+            continue;
+        };
+        let last = first + needle.chars.len() - 1;
+        let text: String = chars.iter().collect();
+        found.push(SearchMatch {
+            line,
+            columns: (columns[first], columns[last]),
+            count: starts.len(),
+            text: text.trim_end().to_string(),
+        });
+    }
+    found.reverse();
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smart_case_matches() {
+        let hay: Vec<char> = "Error: error ERROR".chars().collect();
+        let starts = |query: &str| Needle::new(query).unwrap().find_all(&hay);
+        assert_eq!(starts("error"), vec![0, 7, 13]);
+        assert_eq!(starts("Error"), vec![0]);
+        assert_eq!(starts("rr"), vec![1, 8, 14]);
+        assert!(Needle::new("  ").is_none());
+    }
+}
@@ -0,0 +1,196 @@
+//! Shell integration marks (OSC 133, "FinalTerm" semantic prompts) and the
+//! working directory the shell reports (OSC 7).
+//!
+//! Shells configured for it emit `OSC 133 ; A` at the start of the prompt,
+//! `; B` where the command line starts, `; C` when the command is executed
+//! and `; D [; exit]` when it finishes, and `OSC 7 ; file://host/path` when
+//! the directory changes. alacritty ignores these, so they are picked out
+//! here by a scanner that runs alongside the VTE parser.
+
+use std::path::PathBuf;
+
+/// Longest OSC 133 or OSC 7 payload we collect (a long path fits)
+const MAX_OSC_LEN: usize = 4096;
+
+/// Which shell integration sequences a terminal has received
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShellIntegrationStatus {
+    /// The shell has marked a prompt (OSC 133)
+    pub prompt_marks: bool,
+    /// Directory the shell last reported (OSC 7)
+    pub reported_cwd: Option<PathBuf>,
+}
+
+/// A semantic prompt mark
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PromptMark {
+    /// `OSC 133 ; A` — prompt is about to be drawn
+    PromptStart,
+    /// `OSC 133 ; B` — prompt done, the user types the command from here
+    CommandStart,
+    /// `OSC 133 ; C` — command line accepted, output follows
+    CommandExecuted,
+    /// `OSC 133 ; D [; exit]` — command finished
+    CommandFinished(Option<i32>),
+    /// `OSC 7 ; file://host/path` — the shell's working directory
+    WorkingDirectory(PathBuf),
+}
+
+#[derive(Debug, Default)]
+enum State {
+    #[default]
+    Ground,
+    Escape,
+    Osc(Vec<u8>),
+    OscEscape(Vec<u8>),
+}
+
+/// Incremental scanner for OSC 133 and OSC 7; keeps state across PTY reads.
+#[derive(Debug, Default)]
+pub(crate) struct PromptScanner {
+    state: State,
+}
+
+impl PromptScanner {
+    /// Scan a chunk of PTY output. Each mark is returned with the offset just
+    /// past its terminator, so the caller can feed the parser up to that point
+    /// before acting on it.
+    pub(crate) fn scan(&mut self, data: &[u8]) -> Vec<(usize, PromptMark)> {
+        let mut marks = Vec::new();
+        for (i, &byte) in data.iter().enumerate() {
+            if let Some(mark) = self.advance(byte) {
+                marks.push((i + 1, mark));
+            }
+        }
+        marks
+    }
+
+    fn advance(&mut self, byte: u8) -> Option<PromptMark> {
+        let mut mark = None;
+        self.state = match std::mem::take(&mut self.state) {
+            State::Ground | State::Escape if byte == 0x1b => State::Escape,
+            State::Ground => State::Ground,
+            State::Escape if byte == b']' => State::Osc(Vec::new()),
+            State::Escape => State::Ground,
+            State::Osc(body) if byte == 0x07 => {
+                mark = parse_mark(&body);
+                State::Ground
+            }
+            State::Osc(body) if byte == 0x1b => State::OscEscape(body),
+            State::Osc(mut body) if body.len() < MAX_OSC_LEN => {
+                body.push(byte);
+                // Only OSC 133 and OSC 7 are collected; other OSCs are skipped
+                let prefix = if body.starts_with(b"7") {
+                    &b"7;"[..]
+                } else {
+                    b"133;"
+                };
+                if body.len() <= prefix.len() && !prefix.starts_with(&body) {
+                    State::Ground
+                } else {
+                    State::Osc(body)
+                }
+            }
+            State::Osc(_) => State::Ground,
+            State::OscEscape(body) => {
+                if byte == b'\\' {
+                    mark = parse_mark(&body);
+                    State::Ground
+                } else {
+                    // Not ST: the OSC was cancelled, reinterpret as a new escape
+                    self.state = State::Escape;
+                    return self.advance(byte);
+                }
+            }
+        };
+        mark
+    }
+}
+
+fn parse_mark(body: &[u8]) -> Option<PromptMark> {
+    if let Some(url) = body.strip_prefix(b"7;") {
+        return file_url_path(url).map(PromptMark::WorkingDirectory);
+    }
+    let params = std::str::from_utf8(body.strip_prefix(b"133;")?).ok()?;
+    let mut fields = params.split(';');
+    match fields.next()? {
+        "A" => Some(PromptMark::PromptStart),
+        "B" => Some(PromptMark::CommandStart),
+        "C" => Some(PromptMark::CommandExecuted),
+        "D" => Some(PromptMark::CommandFinished(
+            fields.next().and_then(|code| code.parse().ok()),
+        )),
+        _ => None,
+    }
+}
+
+/// The path of a `file://host/path` URL, percent-decoded. The host is not
+/// checked: a shell on another machine over ssh names its own.
+fn file_url_path(url: &[u8]) -> Option<PathBuf> {
+    let rest = url.strip_prefix(b"file://")?;
+    let path = &rest[rest.iter().position(|&b| b == b'/')?..];
+    let mut decoded = Vec::with_capacity(path.len());
+    let mut i = 0;
+    while i < path.len() {
+        let hex = path
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok());
+        match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(byte) if path[i] == b'%' => {
+                decoded.push(byte);
+                i += 3;
+            }
+            _ => {
+                decoded.push(path[i]);
+                i += 1;
+            }
+        }
+    }
+    Some(PathBuf::from(String::from_utf8(decoded).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_with_offsets() {
+        let mut scanner = PromptScanner::default();
+        let data = b"\x1b]133;A\x07$ \x1b]133;B\x1b\\ls\r\n";
+        assert_eq!(
+            scanner.scan(data),
+            vec![(8, PromptMark::PromptStart), (19, PromptMark::CommandStart)]
+        );
+    }
+
+    #[test]
+    fn finished_split_across_reads() {
+        let mut scanner = PromptScanner::default();
+        assert!(scanner.scan(b"out\x1b]133;D;").is_empty());
+        assert_eq!(
+            scanner.scan(b"127\x07"),
+            vec![(4, PromptMark::CommandFinished(Some(127)))]
+        );
+    }
+
+    #[test]
+    fn other_osc_ignored() {
+        let mut scanner = PromptScanner::default();
+        assert!(scanner
+            .scan(b"\x1b]0;133;A\x07\x1b]1337;B\x07\x1b]777;x\x07")
+            .is_empty());
+    }
+
+    #[test]
+    fn reported_directory() {
+        let mut scanner = PromptScanner::default();
+        let data = b"\x1b]7;file://box/home/me/My%20Code\x1b\\\x1b]7;/relative\x07";
+        assert_eq!(
+            scanner.scan(data),
+            vec![(
+                34,
+                PromptMark::WorkingDirectory(PathBuf::from("/home/me/My Code"))
+            )]
+        );
+    }
+}
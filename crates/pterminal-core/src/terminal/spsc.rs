@@ -2,6 +2,7 @@ use std::cell::UnsafeCell;
 use std::mem::MaybeUninit;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /// Bounded lock-free single-producer/single-consumer ring buffer.
 ///
@@ -69,6 +70,43 @@ impl<T> Producer<T> {
             }
         }
     }
+
+    /// Like [`Self::push_blocking`], but gives up when the queue stays full
+    /// for `timeout`
+    pub(crate) fn push_timeout(&self, mut value: T, timeout: Duration) -> Result<(), T> {
+        let deadline = Instant::now() + timeout;
+        let mut spins = 0u32;
+        loop {
+            match self.try_push(value) {
+                Ok(()) => return Ok(()),
+                Err(v) => {
+                    value = v;
+                    if self.inner.consumer_closed.load(Ordering::Acquire)
+                        || Instant::now() >= deadline
+                    {
+                        return Err(value);
+                    }
+                    if spins < 64 {
+                        std::hint::spin_loop();
+                    } else {
+                        std::thread::yield_now();
+                    }
+                    spins = spins.wrapping_add(1);
+                }
+            }
+        }
+    }
+
+    /// Values pushed and not yet popped
+    pub(crate) fn queued(&self) -> usize {
+        let head = self.inner.head.load(Ordering::Relaxed);
+        let tail = self.inner.tail.load(Ordering::Acquire);
+        head.wrapping_sub(tail)
+    }
+
+    pub(crate) fn is_consumer_closed(&self) -> bool {
+        self.inner.consumer_closed.load(Ordering::Acquire)
+    }
 }
 
 impl<T> Drop for Producer<T> {
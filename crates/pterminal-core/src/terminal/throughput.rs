@@ -0,0 +1,172 @@
+//! Guard against runaway output. A program writing hundreds of MB/s keeps
+//! the parser busy with text nobody can read, and every frame redraws a
+//! screen already scrolled away. Past a ceiling the pane fast-forwards:
+//! output is counted but not parsed, except for the latest screenful, which
+//! is shown a few times a second and once more when the flood stops.
+
+use std::time::{Duration, Instant};
+
+/// Period the rate is measured over, and how often the screen updates
+/// while fast-forwarding
+const WINDOW: Duration = Duration::from_millis(250);
+
+/// What the guard did with output, or with the passing of time
+#[derive(Debug, PartialEq, Eq)]
+pub enum Flow {
+    /// Parse these bytes
+    Parse(Vec<u8>),
+    /// Fast-forwarding: parse the latest screenful, `skipped` bytes so far
+    /// have been dropped
+    Skipping { tail: Vec<u8>, skipped: u64 },
+    /// Caught up: parse the latest screenful and go back to normal
+    CaughtUp(Vec<u8>),
+    /// Nothing to do yet
+    Wait,
+}
+
+/// Per-pane throughput accounting
+#[derive(Debug)]
+pub struct ThroughputGuard {
+    /// Bytes per second before fast-forwarding; 0 = no limit
+    limit: u64,
+    window_start: Instant,
+    window_bytes: u64,
+    /// Bytes dropped, while fast-forwarding
+    skipped: Option<u64>,
+    /// Latest output while fast-forwarding
+    tail: Vec<u8>,
+    /// The tail starts mid-line
+    tail_cut: bool,
+}
+
+impl Default for ThroughputGuard {
+    fn default() -> Self {
+        Self {
+            limit: 0,
+            window_start: Instant::now(),
+            window_bytes: 0,
+            skipped: None,
+            tail: Vec::new(),
+            tail_cut: false,
+        }
+    }
+}
+
+impl ThroughputGuard {
+    /// Change the ceiling (bytes per second, 0 = none); takes effect from
+    /// the next window
+    pub fn set_limit(&mut self, bytes_per_sec: u64) {
+        self.limit = bytes_per_sec;
+    }
+
+    /// Whether output is being skipped
+    pub fn is_skipping(&self) -> bool {
+        self.skipped.is_some()
+    }
+
+    /// Account for output; `keep` bytes are about a screenful, escape
+    /// sequences included
+    pub fn feed(&mut self, data: Vec<u8>, now: Instant, keep: usize) -> Flow {
+        self.window_bytes += data.len() as u64;
+        if self.skipped.is_none() {
+            let over = self.limit > 0 && self.window_bytes > self.window_limit();
+            if !over {
+                if now.duration_since(self.window_start) >= WINDOW {
+                    self.start_window(now);
+                }
+                return Flow::Parse(data);
+            }
+            self.skipped = Some(0);
+        }
+        self.tail.extend_from_slice(&data);
+        // Trim in batches so each chunk isn't a memmove
+        if self.tail.len() > keep.saturating_mul(2) {
+            let drop = self.tail.len() - keep;
+            self.tail.drain(..drop);
+            self.tail_cut = true;
+            *self.skipped.get_or_insert(0) += drop as u64;
+        }
+        self.poll(now, keep)
+    }
+
+    /// Check the clock; call when idle so a flood that stopped is noticed
+    pub fn poll(&mut self, now: Instant, keep: usize) -> Flow {
+        let Some(skipped) = self.skipped else {
+            return Flow::Wait;
+        };
+        if now.duration_since(self.window_start) < WINDOW {
+            return Flow::Wait;
+        }
+        let calm = self.window_bytes <= self.window_limit();
+        self.start_window(now);
+        let skipped = skipped + self.trim_tail(keep);
+        let tail = std::mem::take(&mut self.tail);
+        if calm {
+            self.skipped = None;
+            Flow::CaughtUp(tail)
+        } else {
+            self.skipped = Some(skipped);
+            Flow::Skipping { tail, skipped }
+        }
+    }
+
+    fn window_limit(&self) -> u64 {
+        self.limit * WINDOW.as_millis() as u64 / 1000
+    }
+
+    fn start_window(&mut self, now: Instant) {
+        self.window_start = now;
+        self.window_bytes = 0;
+    }
+
+    /// Cut the tail to `keep` bytes, starting on a line if it was cut.
+    /// Returns how many bytes were dropped.
+    fn trim_tail(&mut self, keep: usize) -> u64 {
+        let mut drop = self.tail.len().saturating_sub(keep);
+        if drop > 0 || self.tail_cut {
+            if let Some(newline) = self.tail[drop..].iter().position(|&b| b == b'\n') {
+                drop += newline + 1;
+            }
+        }
+        self.tail.drain(..drop);
+        self.tail_cut = false;
+        drop as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn floods_fast_forward_until_they_stop() {
+        let mut guard = ThroughputGuard::default();
+        let t0 = Instant::now();
+        let chunk = || b"0123456789abcdef\n".repeat(32);
+        assert_eq!(guard.feed(chunk(), t0, 40), Flow::Parse(chunk()));
+
+        // 4 KB/s allows 1 KB per window: the second chunk tips it over
+        guard.set_limit(4096);
+        guard.start_window(t0);
+        assert_eq!(guard.feed(chunk(), t0, 40), Flow::Parse(chunk()));
+        assert_eq!(guard.feed(chunk(), t0, 40), Flow::Wait);
+        assert!(guard.is_skipping());
+
+        // Still flooding: the screen gets the last full lines
+        let t1 = t0 + WINDOW;
+        let Flow::Skipping { tail, skipped } = guard.feed(chunk(), t1, 40) else {
+            panic!("not skipping");
+        };
+        assert_eq!(tail, b"0123456789abcdef\n0123456789abcdef\n");
+        assert_eq!(skipped, 2 * 544 - tail.len() as u64);
+
+        // Quiet for a window: caught up
+        assert_eq!(guard.poll(t1 + WINDOW / 2, 40), Flow::Wait);
+        assert_eq!(guard.poll(t1 + WINDOW, 40), Flow::CaughtUp(Vec::new()));
+        assert!(!guard.is_skipping());
+        assert_eq!(
+            guard.feed(b"ok".to_vec(), t1 + WINDOW, 40),
+            Flow::Parse(b"ok".to_vec())
+        );
+    }
+}
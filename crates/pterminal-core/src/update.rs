@@ -0,0 +1,144 @@
+//! Checking for a newer release.
+//!
+//! Nothing is checked unless `updates.check` is on. A check fetches the
+//! release feed (the latest GitHub release, by default) with curl, at most
+//! once per `updates.interval_hours`; no identifier or usage data is sent,
+//! and nothing is downloaded or installed.
+
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// The latest release, from the GitHub API
+pub const DEFAULT_FEED: &str = "https://api.github.com/repos/DuinoDu/pterminal/releases/latest";
+
+/// The version running
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A release as the feed describes it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Release {
+    /// Without a leading `v`
+    pub version: String,
+    /// Markdown
+    pub notes: String,
+    /// Page of the release
+    pub url: String,
+}
+
+/// The release in a feed response: a GitHub release object
+pub fn parse_release(json: &str) -> Result<Release> {
+    #[derive(Deserialize)]
+    struct GithubRelease {
+        tag_name: String,
+        #[serde(default)]
+        body: Option<String>,
+        #[serde(default)]
+        html_url: String,
+    }
+    let release: GithubRelease = serde_json::from_str(json).context("unexpected feed")?;
+    // Names a file and a tab, so nothing but a version
+    let version = release.tag_name.trim_start_matches('v');
+    let allowed = |c: char| c.is_ascii_alphanumeric() || c == '.' || c == '-';
+    if version.is_empty() || !version.chars().all(allowed) {
+        bail!("unexpected release version {version:?}");
+    }
+    Ok(Release {
+        version: version.to_string(),
+        notes: release.body.unwrap_or_default(),
+        url: release.html_url,
+    })
+}
+
+/// Fetch the latest release from `feed`. Blocks for up to 15 seconds.
+pub fn fetch(feed: &str) -> Result<Release> {
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location"])
+        .args(["--max-time", "15", "--proto", "=https"])
+        .args(["--header", "Accept: application/vnd.github+json"])
+        .args(["--user-agent", "pterminal"])
+        .arg(feed)
+        .output()
+        .context("can't run curl")?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        bail!("{feed}: {}", error.trim());
+    }
+    parse_release(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Whether `version` is later than `than`. Versions are dotted numbers; a
+/// pre-release (`1.2.0-beta.1`) comes before its release.
+pub fn is_newer(version: &str, than: &str) -> bool {
+    fn key(version: &str) -> (Vec<u64>, bool) {
+        let version = version.trim_start_matches('v');
+        let (numbers, pre) = match version.split_once('-') {
+            Some((numbers, _)) => (numbers, true),
+            None => (version, false),
+        };
+        let mut numbers: Vec<u64> = numbers
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect();
+        while numbers.last() == Some(&0) {
+            numbers.pop();
+        }
+        (numbers, !pre)
+    }
+    key(version) > key(than)
+}
+
+/// Whether a check is due: the last one, recorded by [`record_check`] in
+/// `stamp`, was longer than `interval` ago
+pub fn is_due(stamp: &Path, interval: Duration) -> bool {
+    let checked = std::fs::metadata(stamp).and_then(|meta| meta.modified());
+    match checked.map(|time| SystemTime::now().duration_since(time)) {
+        Ok(Ok(since)) => since >= interval,
+        // Never checked, or the clock went back
+        _ => true,
+    }
+}
+
+/// Remember that a check was made now, with the version found
+pub fn record_check(stamp: &Path, version: &str) -> std::io::Result<()> {
+    if let Some(dir) = stamp.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(stamp, version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions() {
+        assert!(is_newer("0.2.0", "0.1.9"));
+        assert!(is_newer("v1.10.0", "1.9.3"));
+        assert!(is_newer("1.2.0", "1.2.0-rc.1"));
+        assert!(!is_newer("1.2", "1.2.0"));
+        assert!(!is_newer("1.2.0-beta", "1.2.0"));
+        assert!(!is_newer("0.1.0", "0.1.0"));
+    }
+
+    #[test]
+    fn github_release() {
+        let json = r#"{"tag_name": "v0.3.0", "body": "- Faster", "html_url": "https://x/r"}"#;
+        let release = parse_release(json).unwrap();
+        assert_eq!(release.version, "0.3.0");
+        assert_eq!(release.notes, "- Faster");
+        assert!(parse_release(r#"{"message": "Not Found"}"#).is_err());
+        assert!(parse_release(r#"{"tag_name": "v1.0/../../x"}"#).is_err());
+
+        let dir = std::env::temp_dir().join(format!("pterminal-update-{}", std::process::id()));
+        let stamp = dir.join("update-check");
+        assert!(is_due(&stamp, Duration::from_secs(60)));
+        record_check(&stamp, "0.3.0").unwrap();
+        assert!(!is_due(&stamp, Duration::from_secs(60)));
+        assert!(is_due(&stamp, Duration::ZERO));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
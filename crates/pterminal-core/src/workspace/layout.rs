@@ -0,0 +1,443 @@
+//! Shareable workspace layouts: the split structure with each pane's
+//! directory, Python environment and startup command, saved as TOML or JSON
+//! so another machine can reproduce the same set of shells.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::split::SplitDirection;
+
+/// A workspace as written to a layout file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceLayout {
+    #[serde(default)]
+    pub name: String,
+    pub root: LayoutNode,
+}
+
+/// A pane, or a split of two sub-layouts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutNode {
+    Pane(PaneLayout),
+    Split {
+        direction: SplitDirection,
+        /// Share of the first child, 0.1 to 0.9
+        #[serde(default = "half")]
+        ratio: f32,
+        first: Box<LayoutNode>,
+        second: Box<LayoutNode>,
+    },
+}
+
+fn half() -> f32 {
+    0.5
+}
+
+/// What a pane's shell starts with
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaneLayout {
+    /// Working directory; a leading `~` is the importing user's home
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Shell command run first to set up the environment, e.g. activating
+    /// a virtualenv
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Command typed into the shell once it starts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+impl PaneLayout {
+    /// Lines to type into the new shell, in order
+    pub fn startup_lines(&self) -> impl Iterator<Item = &str> {
+        [self.profile.as_deref(), self.command.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter(|line| !line.trim().is_empty())
+    }
+}
+
+/// `path` as written to a layout: under `home` it becomes `~/...`, so the
+/// file works for whoever imports it
+pub fn portable_path(path: &Path, home: &Path) -> String {
+    match path.strip_prefix(home) {
+        Ok(rel) if rel.as_os_str().is_empty() => "~".to_string(),
+        Ok(rel) => format!("~/{}", rel.display()),
+        Err(_) => path.display().to_string(),
+    }
+}
+
+/// Inverse of [`portable_path`]: a leading `~` becomes `home`
+pub fn expand_path(path: &str, home: &Path) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some("") => home.to_path_buf(),
+        Some(rel) if rel.starts_with('/') => home.join(&rel[1..]),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Common shapes for a list of panes, used by `[[startup.workspaces]]`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LayoutTemplate {
+    /// Side by side, equal widths
+    #[default]
+    Columns,
+    /// Stacked, equal heights
+    Rows,
+    /// First pane on the left half, the others stacked on the right
+    MainLeft,
+    /// First pane on the top half, the others side by side below
+    MainTop,
+    /// Rows of equal columns, as close to square as fits
+    Grid,
+}
+
+impl LayoutTemplate {
+    /// Arrange `panes` (at least one is used) in this shape
+    pub fn build(self, panes: Vec<PaneLayout>) -> LayoutNode {
+        let mut nodes: Vec<LayoutNode> = panes.into_iter().map(LayoutNode::Pane).collect();
+        if nodes.is_empty() {
+            nodes.push(LayoutNode::Pane(PaneLayout::default()));
+        }
+        match self {
+            Self::Columns => even(SplitDirection::Horizontal, nodes),
+            Self::Rows => even(SplitDirection::Vertical, nodes),
+            Self::MainLeft | Self::MainTop if nodes.len() > 1 => {
+                let (direction, others) = match self {
+                    Self::MainLeft => (SplitDirection::Horizontal, SplitDirection::Vertical),
+                    _ => (SplitDirection::Vertical, SplitDirection::Horizontal),
+                };
+                let main = nodes.remove(0);
+                LayoutNode::Split {
+                    direction,
+                    ratio: 0.5,
+                    first: Box::new(main),
+                    second: Box::new(even(others, nodes)),
+                }
+            }
+            Self::MainLeft | Self::MainTop => nodes.remove(0),
+            Self::Grid => {
+                let cols = (nodes.len() as f32).sqrt().ceil() as usize;
+                let mut rows = Vec::new();
+                while !nodes.is_empty() {
+                    let rest = nodes.split_off(cols.min(nodes.len()));
+                    rows.push(even(SplitDirection::Horizontal, nodes));
+                    nodes = rest;
+                }
+                even(SplitDirection::Vertical, rows)
+            }
+        }
+    }
+}
+
+/// `nodes` (not empty) in equal shares along `direction`
+fn even(direction: SplitDirection, mut nodes: Vec<LayoutNode>) -> LayoutNode {
+    let count = nodes.len();
+    let first = nodes.remove(0);
+    if nodes.is_empty() {
+        return first;
+    }
+    LayoutNode::Split {
+        direction,
+        ratio: 1.0 / count as f32,
+        first: Box::new(first),
+        second: Box::new(even(direction, nodes)),
+    }
+}
+
+/// A workspace opened at launch (`[[startup.workspaces]]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StartupWorkspace {
+    /// Tab name; empty keeps the default "Workspace N"
+    pub name: String,
+    /// Layout file to open, by name in the layouts directory or by path.
+    /// When set, `template`, `cwd` and `panes` are ignored.
+    pub layout: String,
+    pub template: LayoutTemplate,
+    /// Working directory of panes that set none
+    pub cwd: String,
+    /// Panes in template order, each with its own `cwd`, `profile` and `command`
+    pub panes: Vec<PaneLayout>,
+}
+
+impl StartupWorkspace {
+    /// The layout to open, loading `layout` from `layouts_dir` if named
+    pub fn to_layout(&self, layouts_dir: &Path, home: &Path) -> Result<WorkspaceLayout> {
+        if !self.layout.is_empty() {
+            let path = if self.layout.contains('/') || self.layout.contains('.') {
+                expand_path(&self.layout, home)
+            } else {
+                let toml = layouts_dir.join(format!("{}.toml", self.layout));
+                let json = layouts_dir.join(format!("{}.json", self.layout));
+                if !toml.exists() && json.exists() {
+                    json
+                } else {
+                    toml
+                }
+            };
+            let mut layout = WorkspaceLayout::load(&path)?;
+            if !self.name.is_empty() {
+                layout.name = self.name.clone();
+            }
+            return Ok(layout);
+        }
+        let panes = self
+            .panes
+            .iter()
+            .map(|pane| PaneLayout {
+                cwd: pane
+                    .cwd
+                    .clone()
+                    .or_else(|| Some(self.cwd.clone()).filter(|cwd| !cwd.is_empty())),
+                ..pane.clone()
+            })
+            .collect();
+        Ok(WorkspaceLayout {
+            name: self.name.clone(),
+            root: self.template.build(panes),
+        })
+    }
+
+    /// The layout of a new workspace for the project at `root`, named after
+    /// its directory. Panes open in `root` unless they set a directory of
+    /// their own, which when relative is taken from `root` too.
+    pub fn to_project_layout(
+        &self,
+        root: &Path,
+        layouts_dir: &Path,
+        home: &Path,
+    ) -> Result<WorkspaceLayout> {
+        let template = StartupWorkspace {
+            name: String::new(),
+            cwd: String::new(),
+            ..self.clone()
+        };
+        let mut layout = template.to_layout(layouts_dir, home)?;
+        layout.name = root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| root.display().to_string());
+        in_project(&mut layout.root, root);
+        Ok(layout)
+    }
+}
+
+/// Point each pane of `node` at `root`, or at its own directory within it
+fn in_project(node: &mut LayoutNode, root: &Path) {
+    match node {
+        LayoutNode::Pane(pane) => {
+            let cwd = match pane.cwd.as_deref() {
+                None | Some("") => root.to_path_buf(),
+                Some(cwd) if cwd.starts_with('~') || Path::new(cwd).is_absolute() => return,
+                Some(cwd) => root.join(cwd),
+            };
+            pane.cwd = Some(cwd.to_string_lossy().into_owned());
+        }
+        LayoutNode::Split { first, second, .. } => {
+            in_project(first, root);
+            in_project(second, root);
+        }
+    }
+}
+
+/// File format, picked from the extension: `.json` is JSON, anything else TOML
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutFormat {
+    Toml,
+    Json,
+}
+
+impl LayoutFormat {
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Toml,
+        }
+    }
+}
+
+impl WorkspaceLayout {
+    pub fn to_string(&self, format: LayoutFormat) -> Result<String> {
+        Ok(match format {
+            LayoutFormat::Toml => toml::to_string_pretty(self)?,
+            LayoutFormat::Json => serde_json::to_string_pretty(self)? + "\n",
+        })
+    }
+
+    pub fn parse(text: &str, format: LayoutFormat) -> Result<Self> {
+        Ok(match format {
+            LayoutFormat::Toml => toml::from_str(text)?,
+            LayoutFormat::Json => serde_json::from_str(text)?,
+        })
+    }
+
+    /// Write to `path`, creating its directory
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let text = self.to_string(LayoutFormat::of(path))?;
+        std::fs::write(path, text).with_context(|| format!("writing {}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::parse(&text, LayoutFormat::of(path))
+            .with_context(|| format!("parsing {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::split::SplitTree;
+
+    #[test]
+    fn round_trip() {
+        let mut tree = SplitTree::new(1);
+        tree.split(1, SplitDirection::Horizontal, 2);
+        tree.split(2, SplitDirection::Vertical, 3);
+        let home = Path::new("/home/me");
+        let layout = WorkspaceLayout {
+            name: "api".into(),
+            root: tree.to_layout(|id| PaneLayout {
+                cwd: Some(portable_path(&home.join(format!("src/{id}")), home)),
+                profile: (id == 2).then(|| "source .venv/bin/activate".into()),
+                command: (id == 3).then(|| "cargo watch".into()),
+            }),
+        };
+
+        for format in [LayoutFormat::Toml, LayoutFormat::Json] {
+            let text = layout.to_string(format).unwrap();
+            assert_eq!(WorkspaceLayout::parse(&text, format).unwrap(), layout);
+        }
+
+        let mut next = 10;
+        let (rebuilt, panes) = SplitTree::from_layout(&layout.root, || {
+            next += 1;
+            next
+        });
+        assert_eq!(rebuilt.pane_ids(), vec![11, 12, 13]);
+        assert!((rebuilt.layout()[2].1.y - 0.5).abs() < f32::EPSILON);
+        let (_, pane) = &panes[2];
+        assert_eq!(pane.cwd.as_deref(), Some("~/src/3"));
+        assert_eq!(
+            expand_path("~/src/3", Path::new("/u")),
+            Path::new("/u/src/3")
+        );
+        assert_eq!(expand_path("/srv", Path::new("/u")), Path::new("/srv"));
+        assert_eq!(pane.startup_lines().collect::<Vec<_>>(), ["cargo watch"]);
+    }
+
+    #[test]
+    fn templates() {
+        let panes = |n: usize| vec![PaneLayout::default(); n];
+        let shape = |template: LayoutTemplate, n| {
+            let (tree, _) = SplitTree::from_layout(&template.build(panes(n)), {
+                let mut id = 0;
+                move || {
+                    id += 1;
+                    id
+                }
+            });
+            tree.layout()
+                .into_iter()
+                .map(|(_, r)| [r.x, r.y, r.width, r.height].map(|v| (v * 12.0).round() as u8))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            shape(LayoutTemplate::Columns, 3),
+            [[0, 0, 4, 12], [4, 0, 4, 12], [8, 0, 4, 12]]
+        );
+        assert_eq!(
+            shape(LayoutTemplate::MainLeft, 3),
+            [[0, 0, 6, 12], [6, 0, 6, 6], [6, 6, 6, 6]]
+        );
+        assert_eq!(
+            shape(LayoutTemplate::Grid, 3),
+            [[0, 0, 6, 6], [6, 0, 6, 6], [0, 6, 12, 6]]
+        );
+        assert_eq!(shape(LayoutTemplate::MainTop, 0), [[0, 0, 12, 12]]);
+
+        let startup: StartupWorkspace = toml::from_str(
+            r#"
+            template = "rows"
+            cwd = "~/app"
+            panes = [{ command = "nvim" }, { cwd = "/tmp" }]
+            "#,
+        )
+        .unwrap();
+        let layout = startup
+            .to_layout(Path::new("/none"), Path::new("/u"))
+            .unwrap();
+        let LayoutNode::Split { first, second, .. } = layout.root else {
+            panic!("expected a split");
+        };
+        let cwd = |node: &LayoutNode| match node {
+            LayoutNode::Pane(pane) => pane.cwd.clone(),
+            LayoutNode::Split { .. } => None,
+        };
+        assert_eq!(cwd(&first).as_deref(), Some("~/app"));
+        assert_eq!(cwd(&second).as_deref(), Some("/tmp"));
+    }
+
+    #[test]
+    fn project_layout() {
+        let project: StartupWorkspace = toml::from_str(
+            r#"
+            name = "ignored"
+            template = "main-left"
+            panes = [{ command = "nvim" }, { cwd = "web" }, { cwd = "~/notes" }]
+            "#,
+        )
+        .unwrap();
+        let layout = project
+            .to_project_layout(
+                Path::new("/u/code/foo"),
+                Path::new("/none"),
+                Path::new("/u"),
+            )
+            .unwrap();
+        assert_eq!(layout.name, "foo");
+        let mut next = 0;
+        let (_, panes) = SplitTree::from_layout(&layout.root, || {
+            next += 1;
+            next
+        });
+        let cwds: Vec<_> = panes.iter().map(|(_, pane)| pane.cwd.as_deref()).collect();
+        assert_eq!(
+            cwds,
+            [
+                Some("/u/code/foo"),
+                Some("/u/code/foo/web"),
+                Some("~/notes")
+            ]
+        );
+        assert_eq!(panes[0].1.command.as_deref(), Some("nvim"));
+    }
+
+    #[test]
+    fn minimal_toml() {
+        let text = r#"
+            [root.split]
+            direction = "horizontal"
+            [root.split.first.pane]
+            cwd = "/srv"
+            [root.split.second.pane]
+        "#;
+        let layout = WorkspaceLayout::parse(text, LayoutFormat::Toml).unwrap();
+        let LayoutNode::Split { ratio, second, .. } = &layout.root else {
+            panic!("expected a split");
+        };
+        assert!((ratio - 0.5).abs() < f32::EPSILON);
+        assert_eq!(**second, LayoutNode::Pane(PaneLayout::default()));
+    }
+}
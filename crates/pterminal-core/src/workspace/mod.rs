@@ -1,4 +1,9 @@
-use crate::split::{PaneId, SplitTree};
+pub mod layout;
+
+use std::path::{Path, PathBuf};
+
+use crate::split::{PaneId, SplitDirection, SplitTree};
+use layout::{PaneLayout, WorkspaceLayout};
 
 pub type WorkspaceId = u64;
 
@@ -8,6 +13,13 @@ pub struct Workspace {
     pub name: String,
     pub split_tree: SplitTree,
     active_pane: PaneId,
+    /// Panes whose scrolling is linked: scrolling one scrolls the others
+    scroll_linked: Vec<PaneId>,
+    /// Alerts from this workspace's panes are silenced
+    pub muted: bool,
+    /// Root directory of the project this workspace is for; new panes
+    /// open there
+    pub project: Option<PathBuf>,
 }
 
 impl Workspace {
@@ -17,6 +29,9 @@ impl Workspace {
             name: format!("Workspace {}", id),
             split_tree: SplitTree::new(pane_id),
             active_pane: pane_id,
+            scroll_linked: Vec::new(),
+            muted: false,
+            project: None,
         }
     }
 
@@ -33,6 +48,31 @@ impl Workspace {
     pub fn pane_ids(&self) -> Vec<PaneId> {
         self.split_tree.pane_ids()
     }
+
+    /// Link or unlink a pane's scrolling with the other linked panes.
+    pub fn set_scroll_linked(&mut self, id: PaneId, linked: bool) {
+        self.scroll_linked
+            .retain(|&p| p != id && self.split_tree.contains(p));
+        if linked && self.split_tree.contains(id) {
+            self.scroll_linked.push(id);
+        }
+    }
+
+    pub fn is_scroll_linked(&self, id: PaneId) -> bool {
+        self.scroll_linked.contains(&id) && self.split_tree.contains(id)
+    }
+
+    /// Other panes that scroll along with `id` (empty when `id` is not linked).
+    pub fn scroll_peers(&self, id: PaneId) -> Vec<PaneId> {
+        if !self.is_scroll_linked(id) {
+            return Vec::new();
+        }
+        self.scroll_linked
+            .iter()
+            .copied()
+            .filter(|&p| p != id && self.split_tree.contains(p))
+            .collect()
+    }
 }
 
 #[derive(Debug)]
@@ -65,6 +105,25 @@ impl WorkspaceManager {
         (ws_id, pane_id)
     }
 
+    /// Open a workspace shaped like `layout` and switch to it. Returns its
+    /// ID and each new pane with its description; the first is focused.
+    pub fn add_workspace_from_layout(
+        &mut self,
+        layout: &WorkspaceLayout,
+    ) -> (WorkspaceId, Vec<(PaneId, PaneLayout)>) {
+        let ws_id = self.next_workspace_id;
+        self.next_workspace_id += 1;
+        let (split_tree, panes) = SplitTree::from_layout(&layout.root, || self.next_pane_id());
+        let mut ws = Workspace::new(ws_id, panes[0].0);
+        if !layout.name.is_empty() {
+            ws.name = layout.name.clone();
+        }
+        ws.split_tree = split_tree;
+        self.workspaces.push(ws);
+        self.active_index = self.workspaces.len() - 1;
+        (ws_id, panes)
+    }
+
     pub fn close_workspace(&mut self, id: WorkspaceId) {
         if self.workspaces.len() <= 1 {
             return; // don't close the last workspace
@@ -103,6 +162,68 @@ impl WorkspaceManager {
         &self.workspaces
     }
 
+    pub fn workspace_mut(&mut self, idx: usize) -> Option<&mut Workspace> {
+        self.workspaces.get_mut(idx)
+    }
+
+    /// Index of the workspace containing `pane_id`.
+    pub fn workspace_index_of(&self, pane_id: PaneId) -> Option<usize> {
+        self.workspaces
+            .iter()
+            .position(|ws| ws.split_tree.contains(pane_id))
+    }
+
+    /// Index of the workspace for the project at `root`.
+    pub fn project_index(&self, root: &Path) -> Option<usize> {
+        self.workspaces
+            .iter()
+            .position(|ws| ws.project.as_deref() == Some(root))
+    }
+
+    /// Move a pane into another workspace next to that workspace's active pane.
+    /// The source workspace is closed if the pane was its only one. The target
+    /// becomes the active workspace with the moved pane focused.
+    /// Returns false if the pane is unknown or already in the target.
+    pub fn move_pane(&mut self, pane_id: PaneId, target_idx: usize) -> bool {
+        let Some(src_idx) = self.workspace_index_of(pane_id) else {
+            return false;
+        };
+        if src_idx == target_idx || target_idx >= self.workspaces.len() {
+            return false;
+        }
+        let target_id = self.workspaces[target_idx].id;
+
+        let src = &mut self.workspaces[src_idx];
+        src.set_scroll_linked(pane_id, false);
+        if !src.split_tree.remove(pane_id) {
+            // Only pane left: the whole workspace goes away
+            self.workspaces.remove(src_idx);
+        } else if src.active_pane == pane_id {
+            src.active_pane = src.split_tree.pane_ids()[0];
+        }
+
+        let Some(target_idx) = self.workspaces.iter().position(|ws| ws.id == target_id) else {
+            return false;
+        };
+        let target = &mut self.workspaces[target_idx];
+        target
+            .split_tree
+            .split(target.active_pane, SplitDirection::Horizontal, pane_id);
+        target.active_pane = pane_id;
+        self.active_index = target_idx;
+        true
+    }
+
+    /// Link or unlink scrolling for a pane in any workspace.
+    /// Returns false if the pane is unknown.
+    pub fn set_scroll_linked(&mut self, pane_id: PaneId, linked: bool) -> bool {
+        let Some(idx) = self.workspace_index_of(pane_id) else {
+            return false;
+        };
+        self.workspaces[idx].set_scroll_linked(pane_id, linked);
+        true
+    }
+
     /// Allocate a new pane ID (used when splitting panes).
     pub fn next_pane_id(&mut self) -> PaneId {
         let id = self.next_pane_id;
@@ -142,6 +263,16 @@ mod tests {
         assert_eq!(mgr.active_index(), 0);
     }
 
+    #[test]
+    fn project_workspaces() {
+        let mut mgr = WorkspaceManager::new();
+        mgr.add_workspace();
+        assert_eq!(mgr.project_index(Path::new("/code/foo")), None);
+        mgr.workspace_mut(1).unwrap().project = Some(PathBuf::from("/code/foo"));
+        assert_eq!(mgr.project_index(Path::new("/code/foo")), Some(1));
+        assert_eq!(mgr.project_index(Path::new("/code")), None);
+    }
+
     #[test]
     fn close_workspace() {
         let mut mgr = WorkspaceManager::new();
@@ -151,6 +282,76 @@ mod tests {
         assert_eq!(mgr.workspace_count(), 1);
     }
 
+    #[test]
+    fn move_pane_between_workspaces() {
+        let mut mgr = WorkspaceManager::new();
+        let (_, second) = mgr.add_workspace();
+        let third = mgr.next_pane_id();
+        mgr.active_workspace_mut()
+            .split_tree
+            .split(second, SplitDirection::Horizontal, third);
+
+        assert!(mgr.move_pane(third, 0));
+        assert_eq!(mgr.active_index(), 0);
+        assert_eq!(mgr.active_workspace().pane_ids(), vec![0, third]);
+        assert_eq!(mgr.active_workspace().active_pane(), third);
+        assert_eq!(mgr.workspaces()[1].pane_ids(), vec![second]);
+    }
+
+    #[test]
+    fn move_last_pane_closes_source_workspace() {
+        let mut mgr = WorkspaceManager::new();
+        let (_, pane) = mgr.add_workspace();
+        assert!(mgr.move_pane(pane, 0));
+        assert_eq!(mgr.workspace_count(), 1);
+        assert_eq!(mgr.active_workspace().pane_ids(), vec![0, pane]);
+        assert!(!mgr.move_pane(pane, 0));
+    }
+
+    #[test]
+    fn scroll_linked_panes() {
+        let mut mgr = WorkspaceManager::new();
+        let second = mgr.next_pane_id();
+        let third = mgr.next_pane_id();
+        let ws = mgr.active_workspace_mut();
+        ws.split_tree.split(0, SplitDirection::Horizontal, second);
+        ws.split_tree.split(second, SplitDirection::Vertical, third);
+
+        assert!(mgr.set_scroll_linked(0, true));
+        assert!(mgr.set_scroll_linked(third, true));
+        assert!(!mgr.set_scroll_linked(99, true));
+        let ws = mgr.active_workspace();
+        assert_eq!(ws.scroll_peers(0), vec![third]);
+        assert_eq!(ws.scroll_peers(third), vec![0]);
+        assert!(ws.scroll_peers(second).is_empty());
+
+        // A pane moved elsewhere leaves the group
+        mgr.add_workspace();
+        assert!(mgr.move_pane(third, 1));
+        assert!(mgr.workspaces()[0].scroll_peers(0).is_empty());
+        assert!(!mgr.active_workspace().is_scroll_linked(third));
+    }
+
+    #[test]
+    fn add_workspace_from_layout() {
+        let mut mgr = WorkspaceManager::new();
+        let mut tree = SplitTree::new(0);
+        tree.split(0, SplitDirection::Horizontal, 1);
+        let layout = WorkspaceLayout {
+            name: "dev".into(),
+            root: tree.to_layout(|_| PaneLayout::default()),
+        };
+        let (ws_id, panes) = mgr.add_workspace_from_layout(&layout);
+        assert_eq!(ws_id, 1);
+        let ids: Vec<PaneId> = panes.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        let ws = mgr.active_workspace();
+        assert_eq!(ws.name, "dev");
+        assert_eq!(ws.pane_ids(), ids);
+        assert_eq!(ws.active_pane(), 1);
+        assert_eq!(mgr.next_pane_id(), 3);
+    }
+
     #[test]
     fn cannot_close_last_workspace() {
         let mut mgr = WorkspaceManager::new();
@@ -0,0 +1,198 @@
+//! Who may call what over the IPC socket.
+//!
+//! The server writes two tokens next to the socket, readable only by the
+//! user and removed when it stops: one grants control, the other read-only
+//! access. A connection starts with public access (ping, identify, ...) and
+//! is raised by an `auth` call.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use pterminal_core::config::IpcConfig;
+use serde::{Deserialize, Serialize};
+
+/// Access granted to a connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Tier {
+    Public,
+    Read,
+    Control,
+}
+
+/// Access a method needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Public,
+    /// Lists and state, no terminal content
+    Read,
+    /// Screen text, typed input, command lines
+    ReadContent,
+    Control,
+}
+
+impl Access {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Public => "public",
+            Self::Read => "read",
+            Self::ReadContent => "read_content",
+            Self::Control => "control",
+        }
+    }
+
+    fn tier(self) -> Tier {
+        match self {
+            Self::Public => Tier::Public,
+            Self::Read | Self::ReadContent => Tier::Read,
+            Self::Control => Tier::Control,
+        }
+    }
+}
+
+/// File holding the control token for the socket at `socket`
+pub fn token_path(socket: &Path) -> PathBuf {
+    socket.with_extension("token")
+}
+
+/// File holding the read-only token for the socket at `socket`
+pub fn read_token_path(socket: &Path) -> PathBuf {
+    socket.with_extension("read-token")
+}
+
+/// Tokens and switches applied to every connection
+#[derive(Debug, Clone)]
+pub struct AccessPolicy {
+    /// `None` when authentication is disabled
+    tokens: Option<(String, String)>,
+    allow_content_read: bool,
+}
+
+impl AccessPolicy {
+    /// Build the policy from config, writing fresh token files when
+    /// authentication is required.
+    pub fn create(socket: &Path, config: &IpcConfig) -> Result<Self> {
+        let tokens = if config.require_auth {
+            let control = random_token()?;
+            let read = random_token()?;
+            write_private(&token_path(socket), &control)?;
+            write_private(&read_token_path(socket), &read)?;
+            Some((control, read))
+        } else {
+            None
+        };
+        Ok(Self {
+            tokens,
+            allow_content_read: config.allow_content_read,
+        })
+    }
+
+    /// Tier of a connection before it authenticates
+    pub fn initial_tier(&self) -> Tier {
+        match self.tokens {
+            Some(_) => Tier::Public,
+            None => Tier::Control,
+        }
+    }
+
+    /// Tier granted by `token`, if it matches either token.
+    pub fn authenticate(&self, token: &str) -> Option<Tier> {
+        let Some((control, read)) = &self.tokens else {
+            return Some(Tier::Control);
+        };
+        if constant_time_eq(token, control) {
+            Some(Tier::Control)
+        } else if constant_time_eq(token, read) {
+            Some(Tier::Read)
+        } else {
+            None
+        }
+    }
+
+    /// Why a connection at `tier` may not call a method needing `access`.
+    pub fn check(&self, tier: Tier, access: Access) -> Result<(), &'static str> {
+        if access == Access::ReadContent && !self.allow_content_read {
+            return Err("reading terminal content over IPC is disabled");
+        }
+        if tier < access.tier() {
+            return Err(match access.tier() {
+                Tier::Control => "control token required (call auth first)",
+                _ => "token required (call auth first)",
+            });
+        }
+        Ok(())
+    }
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+fn random_token() -> Result<String> {
+    let mut bytes = [0u8; 24];
+    #[cfg(unix)]
+    {
+        use std::io::Read;
+        std::fs::File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    }
+    #[cfg(not(unix))]
+    {
+        use std::hash::{BuildHasher, Hasher};
+        for chunk in bytes.chunks_mut(8) {
+            let value = std::collections::hash_map::RandomState::new()
+                .build_hasher()
+                .finish();
+            chunk.copy_from_slice(&value.to_le_bytes()[..chunk.len()]);
+        }
+    }
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Write `contents` to a file only the current user can read.
+fn write_private(path: &Path, contents: &str) -> Result<()> {
+    let _ = std::fs::remove_file(path);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiers_and_tokens() {
+        let socket =
+            std::env::temp_dir().join(format!("pterminal-auth-{}.sock", std::process::id()));
+        let config = IpcConfig {
+            allow_content_read: false,
+            ..IpcConfig::default()
+        };
+        let policy = AccessPolicy::create(&socket, &config).unwrap();
+        let control = std::fs::read_to_string(token_path(&socket)).unwrap();
+        let read = std::fs::read_to_string(read_token_path(&socket)).unwrap();
+        assert_eq!(policy.authenticate(&control), Some(Tier::Control));
+        assert_eq!(policy.authenticate(&read), Some(Tier::Read));
+        assert_eq!(policy.authenticate("guess"), None);
+
+        let tier = policy.initial_tier();
+        assert!(policy.check(tier, Access::Public).is_ok());
+        assert!(policy.check(tier, Access::Read).is_err());
+        assert!(policy.check(Tier::Read, Access::Read).is_ok());
+        assert!(policy.check(Tier::Read, Access::Control).is_err());
+        assert!(policy.check(Tier::Control, Access::ReadContent).is_err());
+        let _ = std::fs::remove_file(token_path(&socket));
+        let _ = std::fs::remove_file(read_token_path(&socket));
+    }
+}
@@ -1,9 +1,10 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 
 use anyhow::{anyhow, Context, Result};
-use serde_json::Value;
+use serde_json::{json, Value};
 #[cfg(unix)]
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 #[cfg(unix)]
@@ -11,6 +12,8 @@ use tokio::net::UnixStream;
 #[cfg(unix)]
 use tokio::time::timeout;
 
+use crate::auth;
+use crate::methods::RpcMethod;
 use crate::protocol::{JsonRpcRequest, JsonRpcResponse};
 
 static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
@@ -19,13 +22,23 @@ static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
 pub struct IpcClient {
     socket_path: PathBuf,
     timeout: Duration,
+    /// Sent with an `auth` call before each request
+    token: Option<String>,
 }
 
 impl IpcClient {
+    /// Client for the socket at `socket_path`, using the control token
+    /// written next to it when readable.
     pub fn new(socket_path: impl AsRef<Path>) -> Self {
+        let socket_path = socket_path.as_ref().to_path_buf();
+        let token = std::fs::read_to_string(auth::token_path(&socket_path))
+            .ok()
+            .map(|token| token.trim().to_string())
+            .filter(|token| !token.is_empty());
         Self {
-            socket_path: socket_path.as_ref().to_path_buf(),
+            socket_path,
             timeout: Duration::from_secs(3),
+            token,
         }
     }
 
@@ -38,11 +51,59 @@ impl IpcClient {
         self
     }
 
+    /// Authenticate with `token` instead of the control token file.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Call a typed method.
+    pub async fn request<M: RpcMethod>(&self, params: M::Params) -> Result<M::Result> {
+        let result = self.call(M::NAME, serde_json::to_value(params)?).await?;
+        serde_json::from_value(result).with_context(|| format!("unexpected {} result", M::NAME))
+    }
+
     pub async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+        let request = JsonRpcRequest::new(id, method.to_string(), params);
+        let line = self.exchange(&serde_json::to_vec(&request)?).await?;
+        let response: JsonRpcResponse =
+            serde_json::from_str(line.trim()).context("failed to parse IPC response")?;
+        into_result(response)
+    }
+
+    /// Send several calls as one JSON-RPC batch. The app may run them
+    /// concurrently; results are returned in call order.
+    pub async fn batch(&self, calls: &[(&str, Value)]) -> Result<Vec<Result<Value>>> {
+        let first_id = NEXT_REQUEST_ID.fetch_add(calls.len() as u64, Ordering::Relaxed);
+        let requests: Vec<JsonRpcRequest> = calls
+            .iter()
+            .zip(first_id..)
+            .map(|((method, params), id)| JsonRpcRequest::new(id, *method, params.clone()))
+            .collect();
+        let line = self.exchange(&serde_json::to_vec(&requests)?).await?;
+        let responses: Vec<JsonRpcResponse> =
+            serde_json::from_str(line.trim()).context("failed to parse IPC batch response")?;
+        let mut by_id: HashMap<String, JsonRpcResponse> = responses
+            .into_iter()
+            .map(|response| (response.id.to_string(), response))
+            .collect();
+        Ok(requests
+            .iter()
+            .map(|request| {
+                by_id
+                    .remove(&request.id.to_string())
+                    .ok_or_else(|| anyhow!("no response for {}", request.method))
+                    .and_then(into_result)
+            })
+            .collect())
+    }
+
+    /// Write one request line and read the response line.
+    async fn exchange(&self, payload: &[u8]) -> Result<String> {
         #[cfg(not(unix))]
         {
-            let _ = method;
-            let _ = params;
+            let _ = payload;
             return Err(anyhow!(
                 "IPC client is only implemented for unix in this build"
             ));
@@ -50,39 +111,57 @@ impl IpcClient {
 
         #[cfg(unix)]
         {
-            let id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
-            let request = JsonRpcRequest::new(id, method.to_string(), params);
-
-            let mut stream = timeout(self.timeout, UnixStream::connect(&self.socket_path))
+            let stream = timeout(self.timeout, UnixStream::connect(&self.socket_path))
                 .await
                 .context("IPC connect timeout")?
                 .with_context(|| {
                     format!("failed to connect to socket {}", self.socket_path.display())
                 })?;
+            let mut stream = BufReader::new(stream);
 
-            let payload = serde_json::to_vec(&request)?;
-            timeout(self.timeout, stream.write_all(&payload))
-                .await
-                .context("IPC write timeout")??;
-            timeout(self.timeout, stream.write_all(b"\n"))
-                .await
-                .context("IPC write timeout")??;
-
-            let mut reader = BufReader::new(stream);
-            let mut line = String::new();
-            let n = timeout(self.timeout, reader.read_line(&mut line))
-                .await
-                .context("IPC read timeout")??;
-            if n == 0 {
-                return Err(anyhow!("IPC connection closed by server"));
+            if let Some(token) = &self.token {
+                let id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+                let request = JsonRpcRequest::new(id, "auth", json!({ "token": token }));
+                let line = self
+                    .round_trip(&mut stream, &serde_json::to_vec(&request)?)
+                    .await?;
+                let response: JsonRpcResponse =
+                    serde_json::from_str(line.trim()).context("failed to parse IPC response")?;
+                into_result(response).context("IPC authentication failed")?;
             }
 
-            let response: JsonRpcResponse =
-                serde_json::from_str(line.trim()).context("failed to parse IPC response")?;
-            if let Some(err) = response.error {
-                return Err(anyhow!("RPC error {}: {}", err.code, err.message));
-            }
-            Ok(response.result.unwrap_or(Value::Null))
+            let line = self.round_trip(&mut stream, payload).await?;
+            Ok(line)
         }
     }
+
+    #[cfg(unix)]
+    async fn round_trip(
+        &self,
+        stream: &mut BufReader<UnixStream>,
+        payload: &[u8],
+    ) -> Result<String> {
+        timeout(self.timeout, stream.write_all(payload))
+            .await
+            .context("IPC write timeout")??;
+        timeout(self.timeout, stream.write_all(b"\n"))
+            .await
+            .context("IPC write timeout")??;
+
+        let mut line = String::new();
+        let n = timeout(self.timeout, stream.read_line(&mut line))
+            .await
+            .context("IPC read timeout")??;
+        if n == 0 {
+            return Err(anyhow!("IPC connection closed by server"));
+        }
+        Ok(line)
+    }
+}
+
+fn into_result(response: JsonRpcResponse) -> Result<Value> {
+    if let Some(err) = response.error {
+        return Err(anyhow!("RPC error {}: {}", err.code, err.message));
+    }
+    Ok(response.result.unwrap_or(Value::Null))
 }
@@ -0,0 +1,244 @@
+//! The IPC methods on the D-Bus session bus, for desktop automation tools
+//! that speak D-Bus rather than the socket protocol.
+//!
+//! One interface with a generic `Call(method, params)` taking and returning
+//! JSON, so every socket method is reachable without a D-Bus binding each:
+//!
+//! ```sh
+//! gdbus call --session --dest io.github.DuinoDu.Pterminal \
+//!   --object-path /io/github/DuinoDu/Pterminal \
+//!   --method io.github.DuinoDu.Pterminal1.Call workspace.list '{}'
+//! ```
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use pterminal_core::config::IpcConfig;
+use serde_json::Value;
+use tokio::sync::oneshot;
+use tracing::{error, warn};
+use zbus::fdo;
+
+use crate::auth::{Access, AccessPolicy, Tier};
+use crate::limits::RequestGate;
+use crate::methods::{self, RpcMethod};
+use crate::protocol::{JsonRpcRequest, JsonRpcResponse};
+use crate::server::RpcHandler;
+
+pub const BUS_NAME: &str = "io.github.DuinoDu.Pterminal";
+pub const OBJECT_PATH: &str = "/io/github/DuinoDu/Pterminal";
+
+/// The D-Bus connection, served on its own thread until dropped
+pub struct DbusService {
+    shutdown_tx: Option<oneshot::Sender<()>>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl DbusService {
+    /// Claim [`BUS_NAME`] and serve `handler` at [`OBJECT_PATH`], within the
+    /// socket's size and rate limits. Failing to reach the bus or claim the
+    /// name is logged on the service thread.
+    pub fn start(
+        handler: RpcHandler,
+        policy: Arc<AccessPolicy>,
+        config: &IpcConfig,
+    ) -> Result<Self> {
+        let gate = RequestGate::new(config);
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        let thread = std::thread::Builder::new()
+            .name("pterminal-dbus".to_string())
+            .spawn(move || {
+                let rt = match tokio::runtime::Builder::new_current_thread()
+                    .enable_io()
+                    .enable_time()
+                    .build()
+                {
+                    Ok(rt) => rt,
+                    Err(e) => {
+                        error!("failed to build tokio runtime for D-Bus: {e}");
+                        return;
+                    }
+                };
+                rt.block_on(async move {
+                    let control = Control {
+                        handler,
+                        policy,
+                        gate,
+                    };
+                    let connection = zbus::connection::Builder::session()
+                        .and_then(|builder| builder.name(BUS_NAME))
+                        .and_then(|builder| builder.serve_at(OBJECT_PATH, control));
+                    let connection = match connection {
+                        Ok(builder) => builder.build().await,
+                        Err(e) => Err(e),
+                    };
+                    match connection {
+                        // Served until shutdown; the connection runs its own tasks
+                        Ok(_connection) => {
+                            let _ = shutdown_rx.await;
+                        }
+                        Err(e) => warn!("failed to serve {BUS_NAME} on D-Bus: {e}"),
+                    }
+                });
+            })?;
+        Ok(Self {
+            shutdown_tx: Some(shutdown_tx),
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for DbusService {
+    fn drop(&mut self) {
+        if let Some(tx) = self.shutdown_tx.take() {
+            let _ = tx.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// The session bus only reaches the user's own processes, so callers are
+/// treated as holding the control token. All of them share one set of
+/// limits.
+struct Control {
+    handler: RpcHandler,
+    policy: Arc<AccessPolicy>,
+    gate: RequestGate,
+}
+
+impl Control {
+    /// The request a `Call` makes, once it is within the limits and
+    /// allowed
+    fn request(&self, method: String, params: &str) -> fdo::Result<JsonRpcRequest> {
+        if method == methods::Auth::NAME {
+            return Err(fdo::Error::InvalidArgs(
+                "auth is not needed over D-Bus".into(),
+            ));
+        }
+        if let Err(response) = self.gate.admit(Value::Null, method.len() + params.len()) {
+            return Err(fdo::Error::LimitsExceeded(error_message(response)));
+        }
+        let params = match params.trim() {
+            "" => Value::Null,
+            params => serde_json::from_str(params)
+                .map_err(|e| fdo::Error::InvalidArgs(format!("params: {e}")))?,
+        };
+        let access = methods::find(&method).map_or(Access::Control, |m| m.access);
+        self.policy
+            .check(Tier::Control, access)
+            .map_err(|message| fdo::Error::AccessDenied(message.into()))?;
+        Ok(JsonRpcRequest::new(0, method, params))
+    }
+}
+
+/// A handler's response as `Call` returns it: the JSON result, or its
+/// error message
+fn call_result(response: JsonRpcResponse) -> fdo::Result<String> {
+    if response.error.is_some() {
+        return Err(fdo::Error::Failed(error_message(response)));
+    }
+    let result = response.result.unwrap_or(Value::Null);
+    Ok(result.to_string())
+}
+
+fn error_message(response: JsonRpcResponse) -> String {
+    response
+        .error
+        .map(|error| error.message)
+        .unwrap_or_default()
+}
+
+#[zbus::interface(name = "io.github.DuinoDu.Pterminal1")]
+impl Control {
+    /// Call `method` with JSON `params` (empty for none) and return its
+    /// JSON result
+    async fn call(&self, method: String, params: String) -> fdo::Result<String> {
+        let request = self.request(method, &params)?;
+        let handler = self.handler.clone();
+        let response = tokio::task::spawn_blocking(move || handler(request))
+            .await
+            .map_err(|_| fdo::Error::Failed("request handler failed".into()))?;
+        call_result(response)
+    }
+
+    /// Names of the methods `Call` accepts
+    fn methods(&self) -> Vec<String> {
+        methods::METHODS
+            .iter()
+            .filter(|m| m.name != methods::Auth::NAME)
+            .map(|m| m.name.to_string())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn control(config: IpcConfig) -> Control {
+        // Without auth no token files are written
+        let config = IpcConfig {
+            require_auth: false,
+            ..config
+        };
+        let socket = std::env::temp_dir().join("pterminal-dbus-test.sock");
+        Control {
+            handler: Arc::new(|request| JsonRpcResponse::success(request.id, Value::Null)),
+            policy: Arc::new(AccessPolicy::create(&socket, &config).unwrap()),
+            gate: RequestGate::new(&config),
+        }
+    }
+
+    #[test]
+    fn calls_map_to_requests() {
+        let control = control(IpcConfig::default());
+        let request = control
+            .request("terminal.send".into(), r#"{"text": "ls"}"#)
+            .unwrap();
+        assert_eq!(request.method, "terminal.send");
+        assert_eq!(request.params, json!({ "text": "ls" }));
+        let request = control.request("workspace.list".into(), " ").unwrap();
+        assert_eq!(request.params, Value::Null);
+
+        let denied =
+            |method: &str, params: &str| control.request(method.into(), params).unwrap_err();
+        assert!(matches!(denied("auth", ""), fdo::Error::InvalidArgs(_)));
+        assert!(matches!(
+            denied("pane.list", "{"),
+            fdo::Error::InvalidArgs(_)
+        ));
+    }
+
+    #[test]
+    fn calls_held_to_limits_and_policy() {
+        let control = control(IpcConfig {
+            allow_content_read: false,
+            max_message_bytes: 64,
+            requests_per_second: 2,
+            ..IpcConfig::default()
+        });
+        let long = format!(r#"{{"text": "{}"}}"#, "x".repeat(64));
+        let error = control.request("terminal.send".into(), &long).unwrap_err();
+        assert!(matches!(error, fdo::Error::LimitsExceeded(_)));
+        let error = control.request("pane.read_screen".into(), "").unwrap_err();
+        assert!(matches!(error, fdo::Error::AccessDenied(_)));
+        assert!(control.request("pane.list".into(), "").is_ok());
+        let error = control.request("pane.list".into(), "").unwrap_err();
+        assert!(matches!(error, fdo::Error::LimitsExceeded(_)));
+    }
+
+    #[test]
+    fn responses_map_to_results() {
+        let ok = JsonRpcResponse::success(json!(0), json!({ "pane_id": 3 }));
+        assert_eq!(call_result(ok).unwrap(), r#"{"pane_id":3}"#);
+        let failed = JsonRpcResponse::invalid_params(json!(0), "no such pane");
+        match call_result(failed) {
+            Err(fdo::Error::Failed(message)) => assert_eq!(message, "no such pane"),
+            other => panic!("{other:?}"),
+        }
+    }
+}
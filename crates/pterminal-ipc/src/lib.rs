@@ -1,7 +1,15 @@
+pub mod auth;
 pub mod client;
+#[cfg(target_os = "linux")]
+pub mod dbus;
+pub mod limits;
+pub mod methods;
 pub mod protocol;
+pub mod schema;
 pub mod server;
 
 pub use client::IpcClient;
+pub use limits::RequestGate;
+pub use methods::RpcMethod;
 pub use protocol::{JsonRpcError, JsonRpcRequest, JsonRpcResponse};
 pub use server::{IpcServer, RpcHandler};
@@ -0,0 +1,123 @@
+//! Size and rate limits every caller is held to, whether it comes over the
+//! socket, D-Bus or AppleScript.
+
+use std::sync::Mutex;
+use std::time::Instant;
+
+use pterminal_core::config::IpcConfig;
+use serde_json::Value;
+
+use crate::protocol::JsonRpcResponse;
+
+/// Limits for one caller: a socket connection, the D-Bus service or
+/// AppleScript
+#[derive(Debug)]
+pub struct RequestGate {
+    max_message_bytes: usize,
+    limiter: Mutex<RateLimiter>,
+}
+
+impl RequestGate {
+    pub fn new(config: &IpcConfig) -> Self {
+        Self {
+            max_message_bytes: config.max_message_bytes,
+            limiter: Mutex::new(RateLimiter::new(config.requests_per_second, Instant::now())),
+        }
+    }
+
+    /// Longest request a caller may send
+    pub fn max_message_bytes(&self) -> usize {
+        self.max_message_bytes
+    }
+
+    /// Take one request from the rate limit, or `false` when over it
+    pub fn try_acquire(&self) -> bool {
+        let mut limiter = self.limiter.lock().unwrap_or_else(|e| e.into_inner());
+        limiter.try_acquire(Instant::now())
+    }
+
+    /// Let through a request `len` bytes long, or the error to answer it
+    /// with: too large, or over the rate limit
+    pub fn admit(&self, id: Value, len: usize) -> Result<(), JsonRpcResponse> {
+        if len > self.max_message_bytes {
+            return Err(JsonRpcResponse::too_large(id, self.max_message_bytes));
+        }
+        if !self.try_acquire() {
+            return Err(JsonRpcResponse::rate_limited(id));
+        }
+        Ok(())
+    }
+}
+
+/// Token bucket refilled at `rate` requests per second and holding at most a
+/// second's worth, so short bursts pass but tight loops are throttled.
+#[derive(Debug)]
+struct RateLimiter {
+    /// Requests per second; 0 disables the limit
+    rate: f64,
+    available: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    fn new(rate: u32, now: Instant) -> Self {
+        Self {
+            rate: f64::from(rate),
+            available: f64::from(rate),
+            updated: now,
+        }
+    }
+
+    /// Take one request from the bucket, or `false` if it is empty.
+    fn try_acquire(&mut self, now: Instant) -> bool {
+        if self.rate == 0.0 {
+            return true;
+        }
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.updated = now;
+        self.available = (self.available + elapsed * self.rate).min(self.rate);
+        if self.available < 1.0 {
+            return false;
+        }
+        self.available -= 1.0;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limiter_refills() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(2, start);
+        assert!(limiter.try_acquire(start));
+        assert!(limiter.try_acquire(start));
+        assert!(!limiter.try_acquire(start));
+        let later = start + std::time::Duration::from_millis(500);
+        assert!(limiter.try_acquire(later));
+        assert!(!limiter.try_acquire(later));
+        // Idle time does not bank more than a second's worth
+        let idle = later + std::time::Duration::from_secs(60);
+        assert!(limiter.try_acquire(idle));
+        assert!(limiter.try_acquire(idle));
+        assert!(!limiter.try_acquire(idle));
+
+        let mut unlimited = RateLimiter::new(0, start);
+        assert!((0..1000).all(|_| unlimited.try_acquire(start)));
+    }
+
+    #[test]
+    fn gate_checks_size_then_rate() {
+        let gate = RequestGate::new(&IpcConfig {
+            max_message_bytes: 10,
+            requests_per_second: 1,
+            ..IpcConfig::default()
+        });
+        let error = |result: Result<(), JsonRpcResponse>| result.unwrap_err().error.unwrap().code;
+        assert_eq!(error(gate.admit(Value::Null, 11)), -32003);
+        assert!(gate.admit(Value::Null, 10).is_ok());
+        assert_eq!(error(gate.admit(Value::Null, 1)), -32002);
+    }
+}
@@ -0,0 +1,911 @@
+//! Typed IPC methods: parameter and result types for every method the app
+//! serves, and a registry that describes them as a machine-readable schema.
+
+use std::collections::HashMap;
+
+use pterminal_core::Notification;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::auth::{Access, Tier};
+use crate::protocol::{JsonRpcRequest, JsonRpcResponse, RpcResult};
+use crate::schema::{rpc_struct, SchemaType};
+
+/// A JSON-RPC method with typed parameters and result
+pub trait RpcMethod {
+    const NAME: &'static str;
+    /// Older or shorter names accepted for the method
+    const ALIASES: &'static [&'static str];
+    const SUMMARY: &'static str;
+    const ACCESS: Access;
+    type Params: Serialize + DeserializeOwned + SchemaType;
+    type Result: Serialize + DeserializeOwned + SchemaType;
+}
+
+/// Registry entry describing one method
+pub struct MethodInfo {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub summary: &'static str,
+    pub access: Access,
+    params: fn() -> Value,
+    result: fn() -> Value,
+}
+
+impl MethodInfo {
+    const fn of<M: RpcMethod>() -> Self {
+        Self {
+            name: M::NAME,
+            aliases: M::ALIASES,
+            summary: M::SUMMARY,
+            access: M::ACCESS,
+            params: <M::Params as SchemaType>::schema,
+            result: <M::Result as SchemaType>::schema,
+        }
+    }
+
+    pub fn schema(&self) -> Value {
+        json!({
+            "name": self.name,
+            "aliases": self.aliases,
+            "summary": self.summary.trim(),
+            "access": self.access.name(),
+            "params": (self.params)(),
+            "result": (self.result)(),
+        })
+    }
+}
+
+macro_rules! rpc_methods {
+    ($(
+        #[doc = $doc:literal]
+        $method:ident = $name:literal $(| $alias:literal)* [$access:ident]:
+            $params:ty => $result:ty;
+    )*) => {
+        $(
+            #[doc = $doc]
+            pub struct $method;
+
+            impl RpcMethod for $method {
+                const NAME: &'static str = $name;
+                const ALIASES: &'static [&'static str] = &[$($alias),*];
+                const SUMMARY: &'static str = $doc;
+                const ACCESS: Access = Access::$access;
+                type Params = $params;
+                type Result = $result;
+            }
+        )*
+
+        /// Every method served by the app
+        pub const METHODS: &[MethodInfo] = &[$(MethodInfo::of::<$method>()),*];
+    };
+}
+
+rpc_methods! {
+    /// Raise this connection's access with a token from the token files
+    Auth = "auth" [Public]:
+        AuthParams => AuthResult;
+    /// Check that the app is responding
+    Ping = "ping" | "system.ping" [Public]:
+        NoParams => PingResult;
+    /// List methods with their parameter and result schemas
+    Capabilities = "capabilities" | "system.capabilities" [Public]:
+        NoParams => CapabilitiesResult;
+    /// Identify the running app
+    Identify = "identify" | "system.identify" [Public]:
+        NoParams => IdentifyResult;
+    /// List workspaces
+    WorkspaceList = "workspace.list" | "list-workspaces" [Read]:
+        NoParams => WorkspaceListResult;
+    /// Open a workspace with one pane
+    WorkspaceNew = "workspace.new" | "new-workspace" [Control]:
+        NoParams => WorkspaceNewResult;
+    /// Close a workspace (default: the active one)
+    WorkspaceClose = "workspace.close" | "close-workspace" [Control]:
+        WorkspaceCloseParams => WorkspaceCloseResult;
+    /// Switch to a workspace by id or index
+    WorkspaceSelect = "workspace.select" | "select-workspace" [Control]:
+        WorkspaceSelectParams => WorkspaceSelectResult;
+    /// List panes of the active workspace
+    PaneList = "pane.list" | "list-panes" [Read]:
+        NoParams => PaneListResult;
+    /// Type text into a pane
+    TerminalSend = "terminal.send" | "send" [Control]:
+        SendParams => SendResult;
+    /// Lines recently sent to a pane, oldest first
+    PaneInputHistory = "pane.input_history" [ReadContent]:
+        InputHistoryParams => InputHistoryResult;
+    /// Visible text of a pane
+    PaneReadScreen = "pane.read_screen" | "read-screen" [ReadContent]:
+        CaptureParams => ScreenResult;
+    /// Visible text of a pane (same as pane.read_screen)
+    PaneCapture = "pane.capture" | "capture-pane" [ReadContent]:
+        CaptureParams => ScreenResult;
+    /// Turn line wrapping of a pane on or off
+    PaneSetWrap = "pane.set_wrap" | "set-wrap" [Control]:
+        SetWrapParams => SetWrapResult;
+    /// Link a pane's scrolling to the other linked panes of its workspace
+    PaneLinkScroll = "pane.link_scroll" | "link-scroll" [Control]:
+        LinkScrollParams => LinkScrollResult;
+    /// Pin a pane's screen to compare later output with, or unpin it
+    PanePin = "pane.pin" | "pin" [Control]:
+        PinParams => PinResult;
+    /// Start a new shell in a pane, keeping its history above a separator
+    PaneRespawn = "pane.respawn" | "respawn" [Control]:
+        PaneParams => RespawnResult;
+    /// Process tree of a pane's shell
+    PaneProcesses = "pane.processes" | "processes" [ReadContent]:
+        PaneParams => ProcessesResult;
+    /// Send INT, TERM or KILL to a process of a pane
+    PaneSignal = "pane.signal" | "signal" [Control]:
+        SignalParams => SignalResult;
+    /// Named marks in a pane's output, top first
+    PaneMarks = "pane.marks" | "list-marks" [Read]:
+        PaneParams => PaneMarksResult;
+    /// Lines of history and screen containing text, across panes
+    PaneSearch = "pane.search" | "search-panes" [ReadContent]:
+        PaneSearchParams => PaneSearchResult;
+    /// Split the active pane with its Python env activated
+    PaneSplitWithEnv = "pane.split_with_env" | "split-with-env" [Control]:
+        SplitWithEnvParams => SplitWithEnvResult;
+    /// Split a pane with a new shell in its directory, optionally rerunning its command
+    PaneDuplicate = "pane.duplicate" | "duplicate" [Control]:
+        DuplicateParams => DuplicateResult;
+    /// Grow, shrink or reset a pane's font size, reflowing or scaling its grid
+    PaneZoom = "pane.zoom" | "zoom" [Control]:
+        ZoomParams => ZoomResult;
+    /// Insert a configured snippet into a pane
+    SnippetsRun = "snippets.run" | "run-snippet" [Control]:
+        RunSnippetParams => RunSnippetResult;
+    /// Turn presentation mode on or off (default: toggle)
+    AppPresentation = "app.presentation" | "presentation" [Control]:
+        PresentationParams => PresentationResult;
+    /// Add a notification
+    NotificationSend = "notification.send" | "notify" [Control]:
+        NotifyParams => NotifyResult;
+    /// List notifications
+    NotificationList = "notification.list" | "list-notifications" [Read]:
+        NoParams => NotificationListResult;
+    /// Focus the pane a notification came from (default: the latest alert)
+    NotificationFocus = "notification.focus" | "focus-notification" [Control]:
+        NotificationFocusParams => NotifyResult;
+    /// Get or change do not disturb, quiet hours and the mute rules
+    NotificationRules = "notification.rules" | "notification-rules" [Control]:
+        NotificationRulesParams => NotificationRulesResult;
+    /// Mute or unmute alerts from a workspace (default: toggle the active one)
+    NotificationMute = "notification.mute" | "mute-workspace" [Control]:
+        NotificationMuteParams => NotificationMuteResult;
+    /// Remove all notifications
+    NotificationClear = "notification.clear" | "clear-notifications" [Control]:
+        NoParams => NotificationClearResult;
+    /// Set the text of a status bar segment a plugin contributes
+    StatusSet = "status.set" | "set-status" [Control]:
+        StatusSetParams => StatusSetResult;
+    /// Monospace font families usable for font.family
+    FontsList = "fonts.list" | "list-fonts" [Read]:
+        NoParams => FontsListResult;
+    /// Config options with their values, optionally under a prefix
+    ConfigList = "config.list" | "config-list" [Read]:
+        ConfigListParams => ConfigListResult;
+    /// Value of a config option, or of a table of them
+    ConfigGet = "config.get" | "config-get" [Read]:
+        ConfigGetParams => ConfigValue;
+    /// Change a config option, applied at once where the app supports it
+    ConfigSet = "config.set" | "config-set" [Control]:
+        ConfigSetParams => ConfigSetResult;
+    /// JSON Schema of config.toml and the options applied without a restart
+    ConfigSchema = "config.schema" | "config-schema" [Public]:
+        NoParams => ConfigSchemaResult;
+    /// Save a workspace's splits, cwds and startup commands to a layout file
+    WorkspaceExport = "workspace.export" | "export-workspace" [Control]:
+        WorkspaceExportParams => WorkspaceExportResult;
+    /// Open a workspace from a layout file
+    WorkspaceImport = "workspace.import" | "import-workspace" [Control]:
+        WorkspaceImportParams => WorkspaceImportResult;
+    /// Switch to the workspace for a project directory, opening one if needed
+    WorkspaceOpenProject = "workspace.open_project" | "open-project" [Control]:
+        OpenProjectParams => OpenProjectResult;
+    /// Tee a pane's output to a file, rotated by size
+    PaneLogStart = "pane.log.start" | "start-log" [Control]:
+        PaneLogStartParams => PaneLogResult;
+    /// Stop logging a pane's output
+    PaneLogStop = "pane.log.stop" | "stop-log" [Control]:
+        PaneParams => PaneLogResult;
+    /// Zip app state, recent log lines and redacted screens for a bug report
+    DebugDumpState = "debug.dump_state" | "dump-state" [Control]:
+        DumpStateParams => DumpStateResult;
+}
+
+/// Registry entry for a method name or alias.
+pub fn find(method: &str) -> Option<&'static MethodInfo> {
+    METHODS
+        .iter()
+        .find(|m| m.name == method || m.aliases.contains(&method))
+}
+
+/// Machine-readable description of the whole protocol.
+pub fn schema() -> Value {
+    let methods: Vec<Value> = METHODS.iter().map(MethodInfo::schema).collect();
+    json!({ "jsonrpc": "2.0", "methods": methods })
+}
+
+/// Decode the request's params for `M`, run `handler` and encode its result.
+pub fn dispatch<M: RpcMethod>(
+    request: &JsonRpcRequest,
+    handler: impl FnOnce(M::Params) -> RpcResult<M::Result>,
+) -> JsonRpcResponse {
+    let id = request.id.clone();
+    // Methods without parameters are usually called with none at all
+    let params = match &request.params {
+        Value::Null => json!({}),
+        params => params.clone(),
+    };
+    let params = match serde_json::from_value(params) {
+        Ok(params) => params,
+        Err(e) => return JsonRpcResponse::invalid_params(id, format!("{}: {e}", M::NAME)),
+    };
+    match handler(params).map(serde_json::to_value) {
+        Ok(Ok(result)) => JsonRpcResponse::success(id, result),
+        Ok(Err(e)) => JsonRpcResponse::internal_error(id, e.to_string()),
+        Err(e) => JsonRpcResponse::error(id, e.code, e.message),
+    }
+}
+
+rpc_struct! {
+    pub struct NoParams {}
+}
+
+rpc_struct! {
+    /// Targets a pane (default: the active pane)
+    pub struct PaneParams {
+        pub pane_id: Option<u64>,
+    }
+}
+
+rpc_struct! {
+    pub struct CaptureParams {
+        pub pane_id: Option<u64>,
+        /// Plain text; false keeps colors as SGR escapes. Default from
+        /// `capture.strip_ansi`
+        pub strip_ansi: Option<bool>,
+        /// Prefix lines with the capture time; default from
+        /// `capture.timestamps`
+        pub timestamps: Option<bool>,
+    }
+}
+
+rpc_struct! {
+    pub struct AuthParams {
+        pub token: String,
+    }
+}
+
+rpc_struct! {
+    pub struct AuthResult {
+        pub tier: Tier,
+    }
+}
+
+rpc_struct! {
+    pub struct PingResult {
+        pub pong: bool,
+    }
+}
+
+rpc_struct! {
+    pub struct CapabilitiesResult {
+        pub methods: Vec<String>,
+        /// See [`schema`]
+        pub schema: Value,
+    }
+}
+
+rpc_struct! {
+    pub struct IdentifyResult {
+        pub app: String,
+        pub version: String,
+        pub pid: u32,
+        pub platform: String,
+        pub socket: String,
+    }
+}
+
+rpc_struct! {
+    pub struct WorkspaceInfo {
+        pub id: u64,
+        pub index: usize,
+        pub name: String,
+        pub active: bool,
+        pub pane_count: usize,
+        /// Project directory the workspace is for
+        pub project: Option<String>,
+    }
+}
+
+rpc_struct! {
+    pub struct WorkspaceListResult {
+        pub workspaces: Vec<WorkspaceInfo>,
+    }
+}
+
+rpc_struct! {
+    pub struct WorkspaceNewResult {
+        pub workspace_id: u64,
+        pub pane_id: u64,
+    }
+}
+
+rpc_struct! {
+    pub struct WorkspaceCloseParams {
+        pub id: Option<u64>,
+    }
+}
+
+rpc_struct! {
+    pub struct WorkspaceCloseResult {
+        pub closed_workspace_id: u64,
+    }
+}
+
+rpc_struct! {
+    /// `id` takes precedence over `index`
+    pub struct WorkspaceSelectParams {
+        pub id: Option<u64>,
+        pub index: Option<usize>,
+    }
+}
+
+rpc_struct! {
+    pub struct WorkspaceSelectResult {
+        pub selected_index: usize,
+        pub workspace_id: u64,
+    }
+}
+
+rpc_struct! {
+    /// `path` ending in `.json` writes JSON, anything else TOML
+    pub struct WorkspaceExportParams {
+        /// Default: the active workspace
+        pub id: Option<u64>,
+        /// Default: `layouts/<workspace name>.toml` in the config directory
+        pub path: Option<String>,
+    }
+}
+
+rpc_struct! {
+    pub struct WorkspaceExportResult {
+        pub path: String,
+        pub pane_count: usize,
+    }
+}
+
+rpc_struct! {
+    pub struct WorkspaceImportParams {
+        pub path: String,
+    }
+}
+
+rpc_struct! {
+    pub struct WorkspaceImportResult {
+        pub workspace_id: u64,
+        pub pane_ids: Vec<u64>,
+    }
+}
+
+rpc_struct! {
+    pub struct OpenProjectParams {
+        /// Absolute path of the project's root directory
+        pub path: String,
+    }
+}
+
+rpc_struct! {
+    pub struct OpenProjectResult {
+        pub workspace_id: u64,
+        /// Panes opened for a new workspace; empty when it already existed
+        pub pane_ids: Vec<u64>,
+        pub created: bool,
+    }
+}
+
+rpc_struct! {
+    pub struct PaneLogStartParams {
+        pub pane_id: Option<u64>,
+        /// Log file (default: a new file in the logs directory)
+        pub path: Option<String>,
+        /// "plain" (escape sequences stripped) or "raw"; default from
+        /// `logging.format`
+        pub format: Option<String>,
+        /// Prefix lines with the time; default from `logging.timestamps`
+        pub timestamps: Option<bool>,
+        /// Log only the final state of CR-redrawn progress bars; default
+        /// from `logging.collapse_progress`
+        pub collapse_progress: Option<bool>,
+    }
+}
+
+rpc_struct! {
+    pub struct PaneLogResult {
+        pub pane_id: u64,
+        /// Log file started, or stopped; none if the pane was not logging
+        pub path: Option<String>,
+    }
+}
+
+rpc_struct! {
+    pub struct DumpStateParams {
+        /// Zip to write (default: a new file in the crash directory)
+        pub path: Option<String>,
+    }
+}
+
+rpc_struct! {
+    pub struct DumpStateResult {
+        pub path: String,
+        /// Files in the zip
+        pub files: Vec<String>,
+    }
+}
+
+rpc_struct! {
+    pub struct PaneInfo {
+        pub id: u64,
+        pub active: bool,
+        pub alive: bool,
+        pub wrap: bool,
+        pub scroll_linked: bool,
+        /// Python env and direnv label, e.g. "venv:app · direnv"
+        pub env: Option<String>,
+        /// File the pane's output is logged to
+        pub log: Option<String>,
+        /// Time output waited for the parser to catch up (milliseconds)
+        pub stall_ms: u64,
+        /// The shell marks its prompts (OSC 133 shell integration)
+        pub prompt_marks: bool,
+        /// Directory the shell last reported (OSC 7)
+        pub reported_cwd: Option<String>,
+    }
+}
+
+rpc_struct! {
+    pub struct PaneListResult {
+        pub panes: Vec<PaneInfo>,
+    }
+}
+
+rpc_struct! {
+    pub struct SendParams {
+        pub text: String,
+        pub pane_id: Option<u64>,
+    }
+}
+
+rpc_struct! {
+    pub struct SendResult {
+        pub pane_id: u64,
+        pub bytes: usize,
+    }
+}
+
+rpc_struct! {
+    pub struct InputHistoryParams {
+        pub pane_id: Option<u64>,
+        /// Most recent lines to return (default: all)
+        pub limit: Option<usize>,
+    }
+}
+
+rpc_struct! {
+    pub struct InputHistoryResult {
+        pub pane_id: u64,
+        pub lines: Vec<String>,
+    }
+}
+
+rpc_struct! {
+    pub struct MarkInfo {
+        pub name: String,
+        /// Buffer line counted from the oldest line of history
+        pub line: usize,
+    }
+}
+
+rpc_struct! {
+    pub struct PaneSearchParams {
+        /// Plain text, case-insensitive unless it has an uppercase letter
+        pub query: String,
+        /// Search every workspace, not just the active one
+        pub all_workspaces: Option<bool>,
+        /// Most lines per pane, the newest (default: 100)
+        pub limit: Option<usize>,
+    }
+}
+
+rpc_struct! {
+    pub struct PaneSearchMatch {
+        pub pane_id: u64,
+        pub workspace_id: u64,
+        /// Buffer line counted from the oldest line of history
+        pub line: usize,
+        /// Cell where the first match on the line starts
+        pub column: usize,
+        /// Matches on the line
+        pub count: usize,
+        pub text: String,
+    }
+}
+
+rpc_struct! {
+    pub struct PaneSearchResult {
+        /// Grouped by pane, the active pane first; top first within a pane
+        pub matches: Vec<PaneSearchMatch>,
+    }
+}
+
+rpc_struct! {
+    pub struct PaneMarksResult {
+        pub pane_id: u64,
+        pub marks: Vec<MarkInfo>,
+    }
+}
+
+rpc_struct! {
+    pub struct ScreenResult {
+        pub pane_id: u64,
+        pub text: String,
+    }
+}
+
+rpc_struct! {
+    pub struct SetWrapParams {
+        pub wrap: bool,
+        pub pane_id: Option<u64>,
+    }
+}
+
+rpc_struct! {
+    pub struct SetWrapResult {
+        pub pane_id: u64,
+        pub wrap: bool,
+    }
+}
+
+rpc_struct! {
+    pub struct PinParams {
+        pub pane_id: Option<u64>,
+        /// Default: toggle
+        pub pinned: Option<bool>,
+    }
+}
+
+rpc_struct! {
+    pub struct PinResult {
+        pub pane_id: u64,
+        pub pinned: bool,
+    }
+}
+
+rpc_struct! {
+    pub struct RespawnResult {
+        pub pane_id: u64,
+        /// False when the pane's screen had stopped working and was replaced
+        pub kept_history: bool,
+    }
+}
+
+rpc_struct! {
+    pub struct LinkScrollParams {
+        pub pane_id: Option<u64>,
+        /// Default: toggle
+        pub linked: Option<bool>,
+    }
+}
+
+rpc_struct! {
+    pub struct LinkScrollResult {
+        pub pane_id: u64,
+        pub linked: bool,
+        pub linked_with: Vec<u64>,
+    }
+}
+
+rpc_struct! {
+    pub struct ProcessEntry {
+        pub pid: u32,
+        pub ppid: u32,
+        /// Depth below the pane's shell
+        pub depth: usize,
+        /// CPU usage in percent of one core
+        pub cpu: f32,
+        pub rss_kb: u64,
+        pub command: String,
+    }
+}
+
+rpc_struct! {
+    pub struct ProcessesResult {
+        pub pane_id: u64,
+        pub processes: Vec<ProcessEntry>,
+    }
+}
+
+rpc_struct! {
+    pub struct SignalParams {
+        pub pane_id: Option<u64>,
+        pub pid: u32,
+        /// "INT" (default), "TERM" or "KILL"
+        pub signal: Option<String>,
+    }
+}
+
+rpc_struct! {
+    pub struct SignalResult {
+        pub pane_id: u64,
+        pub pid: u32,
+        pub signal: String,
+    }
+}
+
+rpc_struct! {
+    pub struct SplitWithEnvParams {
+        /// "right" (default) or "down"
+        pub direction: Option<String>,
+    }
+}
+
+rpc_struct! {
+    pub struct SplitWithEnvResult {
+        pub pane_id: u64,
+    }
+}
+
+rpc_struct! {
+    pub struct DuplicateParams {
+        pub pane_id: Option<u64>,
+        /// "right" (default) or "down"
+        pub direction: Option<String>,
+        /// Run the pane's foreground command in the new one too
+        pub rerun: Option<bool>,
+    }
+}
+
+rpc_struct! {
+    pub struct DuplicateResult {
+        pub pane_id: u64,
+        /// The command run again, if any
+        pub command: Option<String>,
+    }
+}
+
+rpc_struct! {
+    pub struct RunSnippetParams {
+        pub name: String,
+        /// Placeholder values by name
+        pub values: Option<HashMap<String, String>>,
+        pub pane_id: Option<u64>,
+    }
+}
+
+rpc_struct! {
+    pub struct RunSnippetResult {
+        pub pane_id: u64,
+        pub text: String,
+    }
+}
+
+rpc_struct! {
+    pub struct ZoomParams {
+        pub pane_id: Option<u64>,
+        /// Points to add, negative to shrink; 0 restores the configured size
+        pub step: f32,
+        /// "reflow" fits the grid to the pane again, "scale" keeps its
+        /// columns and rows. Default from font.zoom
+        pub mode: Option<String>,
+    }
+}
+
+rpc_struct! {
+    pub struct ZoomResult {
+        pub pane_id: u64,
+        /// Logical points
+        pub font_size: f32,
+        pub cols: u32,
+        pub rows: u32,
+    }
+}
+
+rpc_struct! {
+    pub struct PresentationParams {
+        pub enabled: Option<bool>,
+    }
+}
+
+rpc_struct! {
+    pub struct PresentationResult {
+        pub enabled: bool,
+    }
+}
+
+rpc_struct! {
+    pub struct NotifyParams {
+        pub title: Option<String>,
+        pub body: Option<String>,
+        /// Alias for `body`
+        pub message: Option<String>,
+    }
+}
+
+rpc_struct! {
+    pub struct NotifyResult {
+        pub notification: Notification,
+    }
+}
+
+rpc_struct! {
+    pub struct NotificationFocusParams {
+        pub id: Option<u64>,
+    }
+}
+
+rpc_struct! {
+    /// Fields left out stay as they are
+    pub struct NotificationRulesParams {
+        pub do_not_disturb: Option<bool>,
+        /// "HH:MM-HH:MM", or "" for none
+        pub quiet_hours: Option<String>,
+        /// "bell", "command", "osc" or "ipc"
+        pub muted_sources: Option<Vec<String>>,
+        /// Regexes matched against title and body
+        pub mute_patterns: Option<Vec<String>>,
+    }
+}
+
+rpc_struct! {
+    pub struct NotificationRulesResult {
+        pub do_not_disturb: bool,
+        pub quiet_hours: String,
+        /// Whether it is quiet now, switched on or in quiet hours
+        pub quiet: bool,
+        pub muted_sources: Vec<String>,
+        pub mute_patterns: Vec<String>,
+    }
+}
+
+rpc_struct! {
+    pub struct NotificationMuteParams {
+        pub workspace_id: Option<u64>,
+        pub muted: Option<bool>,
+    }
+}
+
+rpc_struct! {
+    pub struct NotificationMuteResult {
+        pub workspace_id: u64,
+        pub muted: bool,
+    }
+}
+
+rpc_struct! {
+    pub struct NotificationListResult {
+        pub notifications: Vec<Notification>,
+    }
+}
+
+rpc_struct! {
+    pub struct NotificationClearResult {
+        pub cleared: bool,
+    }
+}
+
+rpc_struct! {
+    pub struct StatusSetParams {
+        /// Id the plugin contributes the segment under
+        pub segment: String,
+        /// Empty hides the segment
+        pub text: String,
+    }
+}
+
+rpc_struct! {
+    pub struct StatusSetResult {
+        pub segment: String,
+    }
+}
+
+rpc_struct! {
+    pub struct FontsListResult {
+        pub families: Vec<String>,
+        /// Family `font.family` resolved to
+        pub active: Option<String>,
+    }
+}
+
+rpc_struct! {
+    pub struct ConfigListParams {
+        /// Only options under this key, e.g. "font"
+        pub prefix: Option<String>,
+    }
+}
+
+rpc_struct! {
+    pub struct ConfigValue {
+        /// Dotted key, e.g. "font.size"
+        pub key: String,
+        pub value: Value,
+        /// Setting it takes effect without a restart
+        pub live: bool,
+    }
+}
+
+rpc_struct! {
+    pub struct ConfigListResult {
+        pub values: Vec<ConfigValue>,
+    }
+}
+
+rpc_struct! {
+    pub struct ConfigGetParams {
+        pub key: String,
+    }
+}
+
+rpc_struct! {
+    pub struct ConfigSetParams {
+        pub key: String,
+        pub value: Value,
+        /// Also write it to config.toml, keeping the rest of the file
+        pub save: Option<bool>,
+    }
+}
+
+rpc_struct! {
+    pub struct ConfigSetResult {
+        pub key: String,
+        /// The value as stored, e.g. 16.0 for 16
+        pub value: Value,
+        /// Applied now; otherwise on the next launch
+        pub live: bool,
+        pub saved: bool,
+    }
+}
+
+rpc_struct! {
+    pub struct ConfigSchemaResult {
+        pub schema: Value,
+        /// Keys and tables applied without a restart
+        pub live: Vec<String>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_names_are_unique() {
+        let mut names: Vec<&str> = METHODS
+            .iter()
+            .flat_map(|m| std::iter::once(m.name).chain(m.aliases.iter().copied()))
+            .collect();
+        let count = names.len();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), count);
+        assert_eq!(find("capture-pane").map(|m| m.name), Some("pane.capture"));
+        let send = &schema()["methods"][9];
+        assert_eq!(send["name"], "terminal.send");
+        assert_eq!(send["params"]["required"], json!(["text"]));
+    }
+
+    #[test]
+    fn dispatch_decodes_params() {
+        let request = JsonRpcRequest::new(1, "send", json!({ "pane_id": 3 }));
+        let response = dispatch::<TerminalSend>(&request, |_| unreachable!());
+        assert_eq!(response.error.map(|e| e.code), Some(-32602));
+
+        let request = JsonRpcRequest::new(2, "ping", Value::Null);
+        let response = dispatch::<Ping>(&request, |NoParams {}| Ok(PingResult { pong: true }));
+        assert_eq!(response.result, Some(json!({ "pong": true })));
+    }
+}
@@ -3,6 +3,9 @@ use serde_json::{json, Value};
 
 pub const JSONRPC_VERSION: &str = "2.0";
 
+/// Outcome of a typed method handler
+pub type RpcResult<T> = Result<T, JsonRpcError>;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcRequest {
     #[serde(default = "default_jsonrpc")]
@@ -30,6 +33,22 @@ pub struct JsonRpcError {
     pub message: String,
 }
 
+impl JsonRpcError {
+    pub fn invalid_params(message: impl Into<String>) -> Self {
+        Self {
+            code: -32602,
+            message: message.into(),
+        }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self {
+            code: -32603,
+            message: message.into(),
+        }
+    }
+}
+
 impl JsonRpcRequest {
     pub fn new(id: u64, method: impl Into<String>, params: Value) -> Self {
         Self {
@@ -82,6 +101,18 @@ impl JsonRpcResponse {
     pub fn internal_error(id: Value, message: impl Into<String>) -> Self {
         Self::error(id, -32603, message)
     }
+
+    pub fn unauthorized(id: Value, message: impl Into<String>) -> Self {
+        Self::error(id, -32001, message)
+    }
+
+    pub fn rate_limited(id: Value) -> Self {
+        Self::error(id, -32002, "rate limit exceeded, slow down")
+    }
+
+    pub fn too_large(id: Value, limit: usize) -> Self {
+        Self::error(id, -32003, format!("message exceeds {limit} bytes"))
+    }
 }
 
 fn default_jsonrpc() -> String {
@@ -0,0 +1,142 @@
+//! JSON Schema fragments for the types carried over IPC, so clients in other
+//! languages can generate bindings from `capabilities`.
+
+use std::collections::HashMap;
+
+use pterminal_core::Notification;
+use serde_json::{json, Value};
+
+use crate::auth::Tier;
+
+/// A type with a JSON Schema description
+pub trait SchemaType {
+    fn schema() -> Value;
+
+    /// Whether an object field of this type must be present
+    const REQUIRED: bool = true;
+}
+
+impl SchemaType for bool {
+    fn schema() -> Value {
+        json!({ "type": "boolean" })
+    }
+}
+
+macro_rules! unsigned_schema {
+    ($($ty:ty),*) => {
+        $(impl SchemaType for $ty {
+            fn schema() -> Value {
+                json!({ "type": "integer", "minimum": 0 })
+            }
+        })*
+    };
+}
+
+unsigned_schema!(u32, u64, usize);
+
+impl SchemaType for f32 {
+    fn schema() -> Value {
+        json!({ "type": "number" })
+    }
+}
+
+impl SchemaType for String {
+    fn schema() -> Value {
+        json!({ "type": "string" })
+    }
+}
+
+/// Any JSON value
+impl SchemaType for Value {
+    fn schema() -> Value {
+        json!({})
+    }
+}
+
+impl<T: SchemaType> SchemaType for Option<T> {
+    fn schema() -> Value {
+        T::schema()
+    }
+
+    const REQUIRED: bool = false;
+}
+
+impl<T: SchemaType> SchemaType for Vec<T> {
+    fn schema() -> Value {
+        json!({ "type": "array", "items": T::schema() })
+    }
+}
+
+impl<T: SchemaType> SchemaType for HashMap<String, T> {
+    fn schema() -> Value {
+        json!({ "type": "object", "additionalProperties": T::schema() })
+    }
+}
+
+impl SchemaType for Tier {
+    fn schema() -> Value {
+        json!({ "type": "string", "enum": ["public", "read", "control"] })
+    }
+}
+
+impl SchemaType for Notification {
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "id": u64::schema(),
+                "title": String::schema(),
+                "body": String::schema(),
+                "created_at_ms": u64::schema(),
+                "read": bool::schema(),
+                "source": {
+                    "type": "string",
+                    "enum": ["app", "bell", "command", "osc", "ipc"],
+                },
+                "workspace_id": Option::<u64>::schema(),
+                "pane_id": Option::<u64>::schema(),
+            },
+            "required": ["id", "title", "body", "created_at_ms", "read"],
+        })
+    }
+}
+
+/// Declare a serde struct together with its object schema.
+macro_rules! rpc_struct {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $($(#[$field_meta:meta])* pub $field:ident: $ty:ty,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+        pub struct $name {
+            $($(#[$field_meta])* pub $field: $ty,)*
+        }
+
+        impl $crate::schema::SchemaType for $name {
+            #[allow(unused_mut)]
+            fn schema() -> serde_json::Value {
+                let mut properties = serde_json::Map::new();
+                let mut required: Vec<&str> = Vec::new();
+                $(
+                    properties.insert(
+                        stringify!($field).to_string(),
+                        <$ty as $crate::schema::SchemaType>::schema(),
+                    );
+                    if <$ty as $crate::schema::SchemaType>::REQUIRED {
+                        required.push(stringify!($field));
+                    }
+                )*
+                serde_json::json!({
+                    "type": "object",
+                    "properties": properties,
+                    "required": required,
+                })
+            }
+        }
+    };
+}
+
+pub(crate) use rpc_struct;
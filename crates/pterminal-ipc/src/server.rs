@@ -3,12 +3,27 @@ use std::sync::Arc;
 
 use anyhow::Result;
 #[cfg(unix)]
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use serde_json::Value;
+#[cfg(unix)]
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 #[cfg(unix)]
 use tokio::net::{UnixListener, UnixStream};
 use tokio::sync::oneshot;
+#[cfg(unix)]
+use tokio::sync::{mpsc, RwLock, Semaphore};
 use tracing::{error, warn};
 
+use pterminal_core::config::IpcConfig;
+
+use crate::auth::{read_token_path, token_path, AccessPolicy};
+#[cfg(unix)]
+use crate::auth::{Access, Tier};
+#[cfg(target_os = "linux")]
+use crate::dbus::DbusService;
+#[cfg(unix)]
+use crate::limits::RequestGate;
+#[cfg(unix)]
+use crate::methods::{self, AuthParams, AuthResult, RpcMethod};
 use crate::protocol::{JsonRpcRequest, JsonRpcResponse};
 
 pub type RpcHandler = Arc<dyn Fn(JsonRpcRequest) -> JsonRpcResponse + Send + Sync>;
@@ -17,14 +32,26 @@ pub struct IpcServer {
     socket_path: PathBuf,
     shutdown_tx: Option<oneshot::Sender<()>>,
     thread: Option<std::thread::JoinHandle<()>>,
+    /// Token files were written next to the socket, to be removed with it
+    wrote_tokens: bool,
+    /// The same methods on the session bus, with `ipc.dbus`
+    #[cfg(target_os = "linux")]
+    _dbus: Option<DbusService>,
 }
 
 impl IpcServer {
-    pub fn start(socket_path: impl AsRef<Path>, handler: RpcHandler) -> Result<Self> {
+    pub fn start(
+        socket_path: impl AsRef<Path>,
+        handler: RpcHandler,
+        config: &IpcConfig,
+    ) -> Result<Self> {
         let socket_path = socket_path.as_ref().to_path_buf();
         if let Some(parent) = socket_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
+        let policy = Arc::new(AccessPolicy::create(&socket_path, config)?);
+        // Each connection gets its own limits
+        let limits = config.clone();
 
         #[cfg(unix)]
         if socket_path.exists() {
@@ -33,10 +60,17 @@ impl IpcServer {
 
         #[cfg(not(unix))]
         {
-            let _ = handler;
+            let _ = (handler, policy, limits);
             anyhow::bail!("IPC server is only implemented for unix in this build");
         }
 
+        #[cfg(target_os = "linux")]
+        let dbus = if config.dbus {
+            Some(DbusService::start(handler.clone(), policy.clone(), config)?)
+        } else {
+            None
+        };
+
         #[cfg(unix)]
         {
             let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
@@ -56,7 +90,7 @@ impl IpcServer {
                         }
                     };
                     rt.block_on(async move {
-                        let listener = match UnixListener::bind(&path_for_thread) {
+                        let listener = match bind_private(&path_for_thread) {
                             Ok(listener) => listener,
                             Err(e) => {
                                 error!(
@@ -66,7 +100,7 @@ impl IpcServer {
                                 return;
                             }
                         };
-                        run_accept_loop(listener, handler, shutdown_rx).await;
+                        run_accept_loop(listener, handler, policy, limits, shutdown_rx).await;
                     });
                 })?;
 
@@ -74,6 +108,9 @@ impl IpcServer {
                 socket_path,
                 shutdown_tx: Some(shutdown_tx),
                 thread: Some(thread),
+                wrote_tokens: config.require_auth,
+                #[cfg(target_os = "linux")]
+                _dbus: dbus,
             })
         }
     }
@@ -83,10 +120,33 @@ impl IpcServer {
     }
 }
 
+/// Bind a socket at `path` only the user may connect to. It is bound in a
+/// fresh directory of mode 0700, restricted to 0600 and then moved into
+/// place, so it is never reachable by others, whatever the umask.
+#[cfg(unix)]
+fn bind_private(path: &Path) -> std::io::Result<UnixListener> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let staging = path.with_file_name(format!(".{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&staging);
+    std::fs::DirBuilder::new().mode(0o700).create(&staging)?;
+    let staged = staging.join("socket");
+    let bound = UnixListener::bind(&staged).and_then(|listener| {
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+        std::fs::rename(&staged, path)?;
+        Ok(listener)
+    });
+    let _ = std::fs::remove_dir_all(&staging);
+    bound
+}
+
 #[cfg(unix)]
 async fn run_accept_loop(
     listener: UnixListener,
     handler: RpcHandler,
+    policy: Arc<AccessPolicy>,
+    limits: IpcConfig,
     mut shutdown_rx: oneshot::Receiver<()>,
 ) {
     loop {
@@ -98,8 +158,10 @@ async fn run_accept_loop(
                 match accepted {
                     Ok((stream, _)) => {
                         let handler = handler.clone();
+                        let policy = policy.clone();
+                        let gate = RequestGate::new(&limits);
                         tokio::spawn(async move {
-                            handle_client(stream, handler).await;
+                            handle_client(stream, handler, policy, gate).await;
                         });
                     }
                     Err(e) => {
@@ -111,15 +173,59 @@ async fn run_accept_loop(
     }
 }
 
+/// Handler calls running at once per connection
+#[cfg(unix)]
+const MAX_IN_FLIGHT: usize = 32;
+
+/// Each line is handled on its own task, so a client may pipeline requests
+/// without waiting for responses. Requests that only read run alongside each
+/// other; any other request waits for the ones before it and holds back the
+/// ones after, so pipelined sends reach the pane in the order they were
+/// written. Responses are written as they complete; clients match them by
+/// id.
 #[cfg(unix)]
-async fn handle_client(stream: UnixStream, handler: RpcHandler) {
+async fn handle_client(
+    stream: UnixStream,
+    handler: RpcHandler,
+    policy: Arc<AccessPolicy>,
+    gate: RequestGate,
+) {
     let (reader_half, mut writer_half) = stream.into_split();
     let mut reader = BufReader::new(reader_half);
-    let mut line = String::new();
+    let (response_tx, mut response_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+    let writer = tokio::spawn(async move {
+        while let Some(payload) = response_rx.recv().await {
+            if writer_half.write_all(&payload).await.is_err()
+                || writer_half.write_all(b"\n").await.is_err()
+            {
+                break;
+            }
+        }
+    });
+    let send = |response: &JsonRpcResponse| match serde_json::to_vec(response) {
+        Ok(payload) => {
+            let _ = response_tx.send(payload);
+        }
+        Err(e) => warn!("ipc serialize response failed: {e}"),
+    };
+    let mut connection = Connection {
+        handler,
+        tier: policy.initial_tier(),
+        policy,
+        in_flight: Arc::new(Semaphore::new(MAX_IN_FLIGHT)),
+        gate: Arc::new(gate),
+    };
+    let max_message_bytes = connection.gate.max_message_bytes();
+    // Taken in arrival order (the lock queues fairly): shared by reads,
+    // exclusive for everything else
+    let order = Arc::new(RwLock::new(()));
+    let mut line = Vec::new();
 
     loop {
         line.clear();
-        let n = match reader.read_line(&mut line).await {
+        // One byte over the limit tells a full-size line from a longer one
+        let limit = max_message_bytes as u64 + 1;
+        let n = match (&mut reader).take(limit).read_until(b'\n', &mut line).await {
             Ok(n) => n,
             Err(e) => {
                 warn!("ipc read failed: {e}");
@@ -129,38 +235,215 @@ async fn handle_client(stream: UnixStream, handler: RpcHandler) {
         if n == 0 {
             break;
         }
+        if line.len() > max_message_bytes && !line.ends_with(b"\n") {
+            // The rest of the line cannot be skipped reliably; hang up
+            warn!("ipc message over {max_message_bytes} bytes, closing");
+            send(&JsonRpcResponse::too_large(Value::Null, max_message_bytes));
+            break;
+        }
 
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
+        let Ok(message) = std::str::from_utf8(&line) else {
+            send(&JsonRpcResponse::parse_error());
+            continue;
+        };
+        let message = message.trim();
+        if message.is_empty() {
             continue;
         }
 
-        let response = match serde_json::from_str::<JsonRpcRequest>(trimmed) {
-            Ok(req) => {
-                if req.jsonrpc != "2.0" {
-                    JsonRpcResponse::invalid_request(req.id)
-                } else {
-                    (handler)(req)
-                }
-            }
-            Err(_) => JsonRpcResponse::parse_error(),
+        // Handled in line so requests after it see the new tier
+        if let Some(response) = authenticate(message, &mut connection) {
+            send(&response);
+            continue;
+        }
+
+        let Ok(value) = serde_json::from_str::<Value>(message) else {
+            send(&JsonRpcResponse::parse_error());
+            continue;
         };
+        if only_reads(&value) {
+            let turn = order.clone().read_owned().await;
+            spawn_message(value, connection.clone(), response_tx.clone(), turn);
+        } else {
+            let turn = order.clone().write_owned().await;
+            spawn_message(value, connection.clone(), response_tx.clone(), turn);
+        }
+    }
 
-        let payload = match serde_json::to_vec(&response) {
-            Ok(data) => data,
-            Err(e) => {
-                warn!("ipc serialize response failed: {e}");
-                break;
+    // Let requests still in flight finish and flush their responses
+    drop(response_tx);
+    let _ = writer.await;
+}
+
+/// Handle one line on its own task, holding its `turn` until it is answered
+#[cfg(unix)]
+fn spawn_message<T: Send + 'static>(
+    value: Value,
+    connection: Connection,
+    response_tx: mpsc::UnboundedSender<Vec<u8>>,
+    turn: T,
+) {
+    tokio::spawn(async move {
+        let response = handle_message(value, &connection).await;
+        drop(turn);
+        match response {
+            Ok(Some(payload)) => {
+                let _ = response_tx.send(payload);
             }
-        };
-        if writer_half.write_all(&payload).await.is_err()
-            || writer_half.write_all(b"\n").await.is_err()
-        {
-            break;
+            Ok(None) => {}
+            Err(e) => warn!("ipc serialize response failed: {e}"),
+        }
+    });
+}
+
+/// Whether every request in a line only reads, so it may run alongside
+/// other requests
+#[cfg(unix)]
+fn only_reads(value: &Value) -> bool {
+    let reads = |item: &Value| {
+        item.get("method")
+            .and_then(Value::as_str)
+            .and_then(methods::find)
+            .is_some_and(|method| method.access == Access::Read)
+    };
+    match value {
+        Value::Array(items) => items.iter().all(reads),
+        item => reads(item),
+    }
+}
+
+/// What a request handled for a connection needs
+#[cfg(unix)]
+#[derive(Clone)]
+struct Connection {
+    handler: RpcHandler,
+    policy: Arc<AccessPolicy>,
+    tier: Tier,
+    in_flight: Arc<Semaphore>,
+    gate: Arc<RequestGate>,
+}
+
+#[cfg(unix)]
+impl Connection {
+    fn over_rate_limit(&self) -> bool {
+        !self.gate.try_acquire()
+    }
+}
+
+/// Answer an `auth` request, updating the connection's tier. Returns `None`
+/// for any other message.
+#[cfg(unix)]
+fn authenticate(message: &str, connection: &mut Connection) -> Option<JsonRpcResponse> {
+    let request = serde_json::from_str::<JsonRpcRequest>(message).ok()?;
+    if request.method != methods::Auth::NAME {
+        return None;
+    }
+    let id = request.id;
+    // Also keeps token guessing slow
+    if connection.over_rate_limit() {
+        return Some(JsonRpcResponse::rate_limited(id));
+    }
+    let Ok(params) = serde_json::from_value::<AuthParams>(request.params) else {
+        return Some(JsonRpcResponse::invalid_params(
+            id,
+            "auth: missing params.token",
+        ));
+    };
+    let Some(granted) = connection.policy.authenticate(&params.token) else {
+        return Some(JsonRpcResponse::unauthorized(id, "invalid token"));
+    };
+    connection.tier = granted;
+    let result = serde_json::to_value(AuthResult { tier: granted }).unwrap_or_default();
+    Some(JsonRpcResponse::success(id, result))
+}
+
+/// Handle one line: a single request or a batch array of requests. Batch
+/// elements that only read run concurrently, as JSON-RPC allows; any other
+/// batch runs in order. Responses keep the request order and leave out
+/// notifications, and `None` means nothing to answer.
+#[cfg(unix)]
+async fn handle_message(
+    value: Value,
+    connection: &Connection,
+) -> serde_json::Result<Option<Vec<u8>>> {
+    match value {
+        Value::Array(items) if items.is_empty() => {
+            serde_json::to_vec(&JsonRpcResponse::invalid_request(Value::Null)).map(Some)
         }
+        Value::Array(items) => {
+            let mut responses = Vec::with_capacity(items.len());
+            if items.iter().all(only_reads) {
+                let calls: Vec<_> = items
+                    .into_iter()
+                    .map(|item| {
+                        let id = item.get("id").cloned().unwrap_or_default();
+                        (id, tokio::spawn(call_handler(item, connection.clone())))
+                    })
+                    .collect();
+                for (id, call) in calls {
+                    responses.push(call.await.unwrap_or_else(|_| Some(handler_failed(id))));
+                }
+            } else {
+                for item in items {
+                    responses.push(call_handler(item, connection.clone()).await);
+                }
+            }
+            let responses: Vec<_> = responses.into_iter().flatten().collect();
+            if responses.is_empty() {
+                return Ok(None);
+            }
+            serde_json::to_vec(&responses).map(Some)
+        }
+        item => match call_handler(item, connection.clone()).await {
+            Some(response) => serde_json::to_vec(&response).map(Some),
+            None => Ok(None),
+        },
     }
 }
 
+/// Run the (blocking) handler for one request off the async runtime. `None`
+/// for a notification (a request without an id), which gets no response.
+#[cfg(unix)]
+async fn call_handler(item: Value, connection: Connection) -> Option<JsonRpcResponse> {
+    let notification = item.is_object() && item.get("id").is_none();
+    let request = match serde_json::from_value::<JsonRpcRequest>(item) {
+        Ok(request) if request.jsonrpc == "2.0" => request,
+        Ok(request) => return Some(JsonRpcResponse::invalid_request(request.id)),
+        Err(_) => return Some(JsonRpcResponse::invalid_request(Value::Null)),
+    };
+    let response = respond(request, connection).await;
+    (!notification).then_some(response)
+}
+
+/// Check a request against the connection's limits and access, then run it
+#[cfg(unix)]
+async fn respond(request: JsonRpcRequest, connection: Connection) -> JsonRpcResponse {
+    if request.method == methods::Auth::NAME {
+        return JsonRpcResponse::invalid_params(request.id, "auth must be sent on its own");
+    }
+    if connection.over_rate_limit() {
+        return JsonRpcResponse::rate_limited(request.id);
+    }
+    // Unknown methods are left to the handler, but only for full access
+    let access = methods::find(&request.method).map_or(Access::Control, |m| m.access);
+    if let Err(message) = connection.policy.check(connection.tier, access) {
+        return JsonRpcResponse::unauthorized(request.id, message);
+    }
+    let id = request.id.clone();
+    let Ok(_permit) = connection.in_flight.acquire_owned().await else {
+        return handler_failed(id);
+    };
+    let handler = connection.handler;
+    tokio::task::spawn_blocking(move || handler(request))
+        .await
+        .unwrap_or_else(|_| handler_failed(id))
+}
+
+#[cfg(unix)]
+fn handler_failed(id: Value) -> JsonRpcResponse {
+    JsonRpcResponse::internal_error(id, "request handler failed")
+}
+
 impl Drop for IpcServer {
     fn drop(&mut self) {
         if let Some(tx) = self.shutdown_tx.take() {
@@ -173,5 +456,138 @@ impl Drop for IpcServer {
         if self.socket_path.exists() {
             let _ = std::fs::remove_file(&self.socket_path);
         }
+        if self.wrote_tokens {
+            let _ = std::fs::remove_file(token_path(&self.socket_path));
+            let _ = std::fs::remove_file(read_token_path(&self.socket_path));
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use serde_json::json;
+
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::IpcClient;
+
+    /// Serve `handler` on a fresh socket without authentication
+    async fn serve(name: &str, handler: RpcHandler) -> (IpcServer, PathBuf) {
+        let socket =
+            std::env::temp_dir().join(format!("pterminal-ipc-{name}-{}.sock", std::process::id()));
+        let config = IpcConfig {
+            require_auth: false,
+            ..IpcConfig::default()
+        };
+        let server = IpcServer::start(&socket, handler, &config).unwrap();
+        for _ in 0..50 {
+            if socket.exists() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        (server, socket)
+    }
+
+    #[tokio::test]
+    async fn socket_and_tokens_are_private_to_the_server() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let socket =
+            std::env::temp_dir().join(format!("pterminal-ipc-private-{}.sock", std::process::id()));
+        let handler: RpcHandler =
+            Arc::new(|request| JsonRpcResponse::success(request.id, json!(true)));
+        let server = IpcServer::start(&socket, handler, &IpcConfig::default()).unwrap();
+        for _ in 0..50 {
+            if socket.exists() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let mode = std::fs::metadata(&socket).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(token_path(&socket).exists());
+
+        drop(server);
+        assert!(!socket.exists());
+        assert!(!token_path(&socket).exists());
+        assert!(!read_token_path(&socket).exists());
+    }
+
+    #[tokio::test]
+    async fn batch_keeps_request_order() {
+        let handler: RpcHandler = Arc::new(|request: JsonRpcRequest| {
+            if request.method == "fail" {
+                return JsonRpcResponse::method_not_found(request.id, &request.method);
+            }
+            // Finish later requests first
+            let delay = request.params["delay_ms"].as_u64().unwrap_or(0);
+            std::thread::sleep(std::time::Duration::from_millis(delay));
+            JsonRpcResponse::success(request.id, json!(request.method))
+        });
+        let (_server, socket) = serve("batch", handler).await;
+        let client = IpcClient::new(&socket);
+
+        let results = client
+            .batch(&[
+                ("a", json!({ "delay_ms": 50 })),
+                ("fail", json!({})),
+                ("c", Value::Null),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(results[0].as_ref().unwrap(), "a");
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap(), "c");
+    }
+
+    #[tokio::test]
+    async fn pipelined_sends_keep_order() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let log = sent.clone();
+        let handler: RpcHandler = Arc::new(move |request: JsonRpcRequest| {
+            if request.params["panic"].as_bool() == Some(true) {
+                panic!("handler failed");
+            }
+            // The first send is the slowest; it must still land first
+            let delay = request.params["delay_ms"].as_u64().unwrap_or(0);
+            std::thread::sleep(std::time::Duration::from_millis(delay));
+            let text = request.params["text"].as_str().unwrap_or_default();
+            log.lock().unwrap().push(text.to_string());
+            JsonRpcResponse::success(request.id, json!(true))
+        });
+        let (_server, socket) = serve("pipeline", handler).await;
+
+        let stream = UnixStream::connect(&socket).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let requests = [
+            json!({"jsonrpc": "2.0", "id": 1, "method": "terminal.send",
+                "params": {"text": "ls", "delay_ms": 50}}),
+            json!({"jsonrpc": "2.0", "id": 2, "method": "terminal.send",
+                "params": {"text": "\r"}}),
+            // A notification: run, but never answered
+            json!({"jsonrpc": "2.0", "method": "terminal.send",
+                "params": {"text": "!"}}),
+            json!({"jsonrpc": "2.0", "id": 3, "method": "terminal.send",
+                "params": {"panic": true}}),
+        ];
+        for request in &requests {
+            let mut line = serde_json::to_vec(request).unwrap();
+            line.push(b'\n');
+            writer.write_all(&line).await.unwrap();
+        }
+        writer.shutdown().await.unwrap();
+
+        let mut lines = BufReader::new(reader).lines();
+        let mut responses = Vec::new();
+        while let Some(line) = lines.next_line().await.unwrap() {
+            responses.push(serde_json::from_str::<JsonRpcResponse>(&line).unwrap());
+        }
+        let ids: Vec<_> = responses.iter().map(|r| r.id.clone()).collect();
+        assert_eq!(ids, [json!(1), json!(2), json!(3)]);
+        // A failed handler still answers with the request's id
+        assert!(responses[2].error.is_some());
+        assert_eq!(*sent.lock().unwrap(), ["ls", "\r", "!"]);
     }
 }
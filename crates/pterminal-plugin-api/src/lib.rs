@@ -99,6 +99,8 @@ pub struct Contributions {
     pub sidebar_views: Vec<SidebarViewContribution>,
     #[serde(default)]
     pub tab_types: Vec<TabTypeContribution>,
+    #[serde(default)]
+    pub status_segments: Vec<StatusSegmentContribution>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -124,6 +126,15 @@ pub struct TabTypeContribution {
     pub title: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusSegmentContribution {
+    pub id: String,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub run: Option<String>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum PluginLifecycleState {
@@ -32,7 +32,8 @@ fn manifest_deserializes_contributions() {
         "contributes": {
             "commands": [{ "id": "acme.workspace.focus", "title": "Focus Workspace" }],
             "sidebarViews": [{ "id": "acme.workspace.tree", "title": "Workspaces", "order": 100 }],
-            "tabTypes": [{ "id": "acme.browser", "title": "Browser" }]
+            "tabTypes": [{ "id": "acme.browser", "title": "Browser" }],
+            "statusSegments": [{ "id": "acme.workspace.count", "text": "1 ws" }]
         },
         "permissions": ["terminal.topology.read"]
     });
@@ -41,6 +42,8 @@ fn manifest_deserializes_contributions() {
     assert_eq!(manifest.contributes.commands.len(), 1);
     assert_eq!(manifest.contributes.sidebar_views.len(), 1);
     assert_eq!(manifest.contributes.tab_types.len(), 1);
+    assert_eq!(manifest.contributes.status_segments[0].text, "1 ws");
+    assert_eq!(manifest.contributes.status_segments[0].run, None);
     assert_eq!(manifest.permissions, vec!["terminal.topology.read"]);
 }
 
@@ -0,0 +1,151 @@
+//! Pane borders: divider lines between split panes, a highlight on the
+//! divider under the pointer and an accent along the active pane's edges.
+
+use pterminal_core::split::{PaneId, PaneRect};
+
+use crate::bg::BgRect;
+use crate::text::PixelRect;
+
+/// How borders are drawn (physical pixels, RGBA colors)
+#[derive(Debug, Clone, Copy)]
+pub struct BorderStyle {
+    pub width: f32,
+    pub color: [f32; 4],
+    /// Width and color of the divider under the pointer; `None` disables it
+    pub hover: Option<(f32, [f32; 4])>,
+    /// Width and color of the active pane's edge accent; `None` disables it
+    pub accent: Option<(f32, [f32; 4])>,
+}
+
+/// A divider line centered on a shared pane edge (physical pixels)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Divider {
+    pub vertical: bool,
+    /// x of a vertical divider, y of a horizontal one
+    pub pos: f32,
+    /// Where the divider starts along its length
+    pub start: f32,
+    pub len: f32,
+}
+
+impl Divider {
+    /// Whether a point lies within `slop` pixels of the divider
+    pub fn hit(&self, x: f32, y: f32, slop: f32) -> bool {
+        let (across, along) = if self.vertical { (x, y) } else { (y, x) };
+        (across - self.pos).abs() <= slop && along >= self.start && along < self.start + self.len
+    }
+
+    fn rect(&self, width: f32, color: [f32; 4]) -> BgRect {
+        let offset = self.pos - width * 0.5;
+        if self.vertical {
+            BgRect {
+                x: offset,
+                y: self.start,
+                w: width,
+                h: self.len,
+                color,
+            }
+        } else {
+            BgRect {
+                x: self.start,
+                y: offset,
+                w: self.len,
+                h: width,
+                color,
+            }
+        }
+    }
+}
+
+/// Divider lines at the split boundaries of `layout`, one per shared edge.
+pub fn dividers(
+    layout: &[(PaneId, PaneRect)],
+    window_w: u32,
+    window_h: u32,
+    tab_bar_h: f32,
+) -> Vec<Divider> {
+    if layout.len() <= 1 {
+        return Vec::new();
+    }
+    let cw = (window_w as f32).max(1.0);
+    let ch = window_h as f32 - tab_bar_h;
+    let mut dividers: Vec<Divider> = Vec::new();
+    let mut push = |divider: Divider| {
+        let key = |d: &Divider| (d.vertical, d.pos as i32, d.start as i32, d.len as i32);
+        if !dividers.iter().any(|d| key(d) == key(&divider)) {
+            dividers.push(divider);
+        }
+    };
+
+    for (_, pr) in layout {
+        // Right edge → vertical divider
+        let right = pr.x + pr.width;
+        if right > 0.001 && right < 0.999 {
+            push(Divider {
+                vertical: true,
+                pos: right * cw,
+                start: pr.y * ch + tab_bar_h,
+                len: pr.height * ch,
+            });
+        }
+        // Bottom edge → horizontal divider
+        let bottom = pr.y + pr.height;
+        if bottom > 0.001 && bottom < 0.999 {
+            push(Divider {
+                vertical: false,
+                pos: bottom * ch + tab_bar_h,
+                start: pr.x * cw,
+                len: pr.width * cw,
+            });
+        }
+    }
+    dividers
+}
+
+/// Background rects for `dividers`, the `hovered` one and an accent along
+/// the edges of `active` (the active pane's full area) that face other panes.
+pub fn border_rects(
+    dividers: &[Divider],
+    hovered: Option<Divider>,
+    active: Option<&PixelRect>,
+    style: &BorderStyle,
+) -> Vec<BgRect> {
+    let mut rects: Vec<BgRect> = dividers
+        .iter()
+        .map(|divider| match style.hover {
+            Some((width, color)) if hovered == Some(*divider) => divider.rect(width, color),
+            _ => divider.rect(style.width, style.color),
+        })
+        .collect();
+
+    if let (Some((width, color)), Some(pane)) = (style.accent, active) {
+        // Only edges shared with a neighbour; window edges stay bare
+        let near = |a: f32, b: f32| (a - b).abs() <= style.width.max(width) + 2.0;
+        for divider in dividers {
+            let (lo, hi) = if divider.vertical {
+                (pane.y, pane.y + pane.h)
+            } else {
+                (pane.x, pane.x + pane.w)
+            };
+            let start = divider.start.max(lo);
+            let end = (divider.start + divider.len).min(hi);
+            if end <= start {
+                continue;
+            }
+            let touches = if divider.vertical {
+                near(divider.pos, pane.x) || near(divider.pos, pane.x + pane.w)
+            } else {
+                near(divider.pos, pane.y) || near(divider.pos, pane.y + pane.h)
+            };
+            if touches {
+                let edge = Divider {
+                    start,
+                    len: end - start,
+                    ..*divider
+                };
+                rects.push(edge.rect(width, color));
+            }
+        }
+    }
+    rects
+}
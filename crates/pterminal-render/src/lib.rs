@@ -1,7 +1,14 @@
 pub mod bg;
+pub mod border;
 pub mod grid;
+pub mod post;
+pub mod preview;
 pub mod renderer;
+pub mod tab_strip;
 pub mod text;
 
 pub use bg::{BgRect, BgRenderer};
-pub use renderer::{OffscreenRenderer, Renderer};
+pub use post::PostProcessor;
+pub use preview::PreviewRenderer;
+pub use renderer::{DeviceLost, OffscreenRenderer, Renderer};
+pub use tab_strip::{TabBarPosition, TabHit, TabStripLayout};
@@ -0,0 +1,232 @@
+use anyhow::{anyhow, bail, Result};
+use wgpu::naga;
+
+/// Prelude the user effect is appended to: bindings, `frame(uv)` and the
+/// full-screen pass that calls `effect(uv)`.
+const PRELUDE: &str = include_str!("post.wgsl");
+
+/// Effects shipped with pterminal, by name
+pub const PRESETS: [(&str, &str); 3] = [
+    ("scanlines", include_str!("shaders/scanlines.wgsl")),
+    ("curvature", include_str!("shaders/curvature.wgsl")),
+    ("glow", include_str!("shaders/glow.wgsl")),
+];
+
+/// Source of the preset effect called `name`
+pub fn preset(name: &str) -> Option<&'static str> {
+    PRESETS
+        .iter()
+        .find(|(preset, _)| *preset == name)
+        .map(|(_, source)| *source)
+}
+
+/// Effect simulating a kind of color blindness, from the row-major matrix
+/// taking linear RGB to what is seen
+pub fn color_vision_effect(matrix: [[f32; 3]; 3]) -> String {
+    // WGSL matrices are built column by column
+    let columns: Vec<String> = (0..3)
+        .map(|col| {
+            let column = matrix.map(|row| format!("{:?}", row[col]));
+            format!("vec3<f32>({})", column.join(", "))
+        })
+        .collect();
+    format!(
+        "const VISION = mat3x3<f32>({});\n{}",
+        columns.join(", "),
+        include_str!("shaders/color_vision.wgsl")
+    )
+}
+
+/// A user-supplied WGSL pass over the composed terminal frame (scanlines,
+/// curvature, glow, ...).
+///
+/// The effect defines `fn effect(uv: vec2<f32>) -> vec4<f32>`, reading the
+/// frame through `frame(uv)` and its size through `post.resolution`.
+pub struct PostProcessor {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    uniform: wgpu::Buffer,
+}
+
+impl PostProcessor {
+    /// Compile `effect`. Errors carry the WGSL diagnostics, with line
+    /// numbers counted from the start of the effect.
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, effect: &str) -> Result<Self> {
+        if !effect.contains("fn effect") {
+            bail!("shader must define fn effect(uv: vec2<f32>) -> vec4<f32>");
+        }
+        // Effect first, so reported line numbers match its file
+        let source = format!("{effect}\n{PRELUDE}");
+        validate(&source)?;
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("post_shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("post_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("post_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            immediate_size: 0,
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("post_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview_mask: None,
+            cache: None,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("post_sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let uniform = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("post_uniform"),
+            size: 16, // 4x f32
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Ok(Self {
+            pipeline,
+            bind_group_layout,
+            sampler,
+            uniform,
+        })
+    }
+
+    /// Run the effect over `src`, writing the result to `dst` (same size).
+    pub fn apply(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        src: &wgpu::Texture,
+        dst: &wgpu::Texture,
+    ) {
+        queue.write_buffer(
+            &self.uniform,
+            0,
+            bytemuck::cast_slice(&[src.width() as f32, src.height() as f32, 0.0, 0.0]),
+        );
+
+        let src_view = src.create_view(&wgpu::TextureViewDescriptor::default());
+        let dst_view = dst.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("post_bind_group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&src_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.uniform.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("post_encoder"),
+        });
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("post_pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &dst_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                    depth_slice: None,
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+                multiview_mask: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+    }
+}
+
+/// Parse and validate the full shader up front: wgpu treats an invalid
+/// module as a device error, which is fatal by default.
+fn validate(source: &str) -> Result<()> {
+    let module = naga::front::wgsl::parse_str(source)
+        .map_err(|e| anyhow!("{}", e.emit_to_string(source)))?;
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::empty(),
+    )
+    .validate(&module)
+    .map_err(|e| anyhow!("{}", e.emit_to_string(source)))?;
+    Ok(())
+}
@@ -0,0 +1,41 @@
+// Post-processing prelude — a user effect samples the composed frame.
+// The effect source is appended and must define
+//     fn effect(uv: vec2<f32>) -> vec4<f32>
+struct PostUniforms {
+    // Frame size in physical pixels
+    resolution: vec2<f32>,
+    _pad: vec2<f32>,
+};
+
+@group(0) @binding(0) var frame_tex: texture_2d<f32>;
+@group(0) @binding(1) var frame_sampler: sampler;
+@group(0) @binding(2) var<uniform> post: PostUniforms;
+
+// The composed terminal frame at `uv` (0..1, y down)
+fn frame(uv: vec2<f32>) -> vec4<f32> {
+    return textureSampleLevel(frame_tex, frame_sampler, uv, 0.0);
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_idx: u32) -> VertexOutput {
+    // Single triangle covering the whole target
+    let corners = array<vec2<f32>, 3>(
+        vec2<f32>(0.0, 0.0), vec2<f32>(2.0, 0.0), vec2<f32>(0.0, 2.0),
+    );
+    let corner = corners[vertex_idx];
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(corner.x * 2.0 - 1.0, 1.0 - corner.y * 2.0, 0.0, 1.0);
+    out.uv = corner;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return effect(in.uv);
+}
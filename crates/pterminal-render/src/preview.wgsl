@@ -0,0 +1,34 @@
+// Downsampling blit for tab previews — samples a sub-rect of the terminal texture
+struct Region {
+    // uv origin and size of the source rect
+    origin: vec2<f32>,
+    size: vec2<f32>,
+};
+
+@group(0) @binding(0) var src_tex: texture_2d<f32>;
+@group(0) @binding(1) var src_sampler: sampler;
+@group(0) @binding(2) var<uniform> region: Region;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_idx: u32) -> VertexOutput {
+    // Single triangle covering the whole target
+    let corners = array<vec2<f32>, 3>(
+        vec2<f32>(0.0, 0.0), vec2<f32>(2.0, 0.0), vec2<f32>(0.0, 2.0),
+    );
+    let corner = corners[vertex_idx];
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(corner.x * 2.0 - 1.0, 1.0 - corner.y * 2.0, 0.0, 1.0);
+    out.uv = region.origin + corner * region.size;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(src_tex, src_sampler, in.uv);
+}
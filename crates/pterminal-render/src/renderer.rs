@@ -1,10 +1,41 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
 use anyhow::Result;
 use wgpu::SurfaceTarget;
 
 use crate::bg::BgRenderer;
-use crate::text::TextRenderer;
+use crate::post::{self, PostProcessor};
+use crate::preview::PreviewRenderer;
+use crate::text::{PixelRect, TextRenderer};
 use pterminal_core::config::theme::RgbColor;
 
+/// Set once wgpu reports a device lost to a driver reset, GPU hang or
+/// unplugged eGPU. Anything submitted to a lost device fails, so renderers
+/// check this before each frame and rebuild on a new device instead.
+#[derive(Debug, Clone, Default)]
+pub struct DeviceLost(Arc<AtomicBool>);
+
+impl DeviceLost {
+    /// Watch `device`, replacing any device-lost callback set on it
+    pub fn watch(device: &wgpu::Device) -> Self {
+        let lost = Self::default();
+        let flag = Arc::clone(&lost.0);
+        device.set_device_lost_callback(move |reason, message| {
+            // `Destroyed` is the device being dropped on purpose
+            if reason == wgpu::DeviceLostReason::Unknown {
+                tracing::error!("GPU device lost: {message}");
+                flag.store(true, Ordering::Release);
+            }
+        });
+        lost
+    }
+
+    pub fn is_lost(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
 /// Main GPU renderer managing wgpu state
 pub struct Renderer {
     pub device: wgpu::Device,
@@ -15,15 +46,22 @@ pub struct Renderer {
     pub bg_renderer: BgRenderer,
     /// Overlay bg renderer — draws AFTER text (for context menu)
     pub overlay_bg_renderer: BgRenderer,
+    device_lost: DeviceLost,
 }
 
-impl Renderer {
-    pub async fn new(
+/// Device, queue and configured surface for a window
+struct Gpu {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    surface: wgpu::Surface<'static>,
+    surface_config: wgpu::SurfaceConfiguration,
+}
+
+impl Gpu {
+    async fn new(
         window: impl Into<SurfaceTarget<'static>>,
         width: u32,
         height: u32,
-        scale_factor: f64,
-        font_size: f32,
     ) -> Result<Self> {
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
@@ -85,6 +123,31 @@ impl Renderer {
         };
         surface.configure(&device, &surface_config);
 
+        Ok(Self {
+            device,
+            queue,
+            surface,
+            surface_config,
+        })
+    }
+}
+
+impl Renderer {
+    pub async fn new(
+        window: impl Into<SurfaceTarget<'static>>,
+        width: u32,
+        height: u32,
+        scale_factor: f64,
+        font_size: f32,
+    ) -> Result<Self> {
+        let Gpu {
+            device,
+            queue,
+            surface,
+            surface_config,
+        } = Gpu::new(window, width, height).await?;
+        let surface_format = surface_config.format;
+
         let text_renderer = TextRenderer::new(
             &device,
             &queue,
@@ -97,6 +160,7 @@ impl Renderer {
 
         let bg_renderer = BgRenderer::new(&device, &queue, surface_format, width, height);
         let overlay_bg_renderer = BgRenderer::new(&device, &queue, surface_format, width, height);
+        let device_lost = DeviceLost::watch(&device);
 
         Ok(Self {
             device,
@@ -106,9 +170,34 @@ impl Renderer {
             text_renderer,
             bg_renderer,
             overlay_bg_renderer,
+            device_lost,
         })
     }
 
+    /// Whether the device was lost; see [`Renderer::recover`]
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.is_lost()
+    }
+
+    /// Start over on a new device after the old one was lost: a new surface
+    /// for `window`, and every renderer's GPU state rebuilt. Shaped text and
+    /// all other CPU state is kept, so callers only need to redraw.
+    pub async fn recover(&mut self, window: impl Into<SurfaceTarget<'static>>) -> Result<()> {
+        let (width, height) = (self.width(), self.height());
+        let gpu = Gpu::new(window, width, height).await?;
+        let format = gpu.surface_config.format;
+        self.text_renderer
+            .recreate_gpu_resources(&gpu.device, &gpu.queue, format);
+        self.bg_renderer = BgRenderer::new(&gpu.device, &gpu.queue, format, width, height);
+        self.overlay_bg_renderer = BgRenderer::new(&gpu.device, &gpu.queue, format, width, height);
+        self.device_lost = DeviceLost::watch(&gpu.device);
+        self.device = gpu.device;
+        self.queue = gpu.queue;
+        self.surface = gpu.surface;
+        self.surface_config = gpu.surface_config;
+        Ok(())
+    }
+
     pub fn resize(&mut self, width: u32, height: u32) {
         if width > 0 && height > 0 {
             self.surface_config.width = width;
@@ -124,6 +213,9 @@ impl Renderer {
         bg_color: RgbColor,
         draw: impl FnOnce(&mut TextRenderer),
     ) -> Result<bool> {
+        if self.is_device_lost() {
+            return Ok(false);
+        }
         let output = match self.surface.get_current_texture() {
             Ok(output) => output,
             Err(wgpu::SurfaceError::Lost) => {
@@ -204,9 +296,17 @@ pub struct OffscreenRenderer {
     pub text_renderer: TextRenderer,
     pub bg_renderer: BgRenderer,
     pub overlay_bg_renderer: BgRenderer,
+    pub preview_renderer: PreviewRenderer,
+    /// Effect run over each composed frame, with its source for rebuilding
+    post: Option<(PostProcessor, String)>,
+    /// Color blindness simulation, run after the effect
+    color_vision: Option<(PostProcessor, String)>,
+    /// Frame that previews of workspaces off screen are drawn in
+    preview_frame: Option<wgpu::Texture>,
     width: u32,
     height: u32,
     format: wgpu::TextureFormat,
+    device_lost: DeviceLost,
 }
 
 impl OffscreenRenderer {
@@ -226,6 +326,8 @@ impl OffscreenRenderer {
             TextRenderer::new(&device, &queue, format, width, height, scale_factor, font_size);
         let bg_renderer = BgRenderer::new(&device, &queue, format, width, height);
         let overlay_bg_renderer = BgRenderer::new(&device, &queue, format, width, height);
+        let preview_renderer = PreviewRenderer::new(&device, format);
+        let device_lost = DeviceLost::watch(&device);
 
         Self {
             device,
@@ -233,10 +335,45 @@ impl OffscreenRenderer {
             text_renderer,
             bg_renderer,
             overlay_bg_renderer,
+            preview_renderer,
+            post: None,
+            color_vision: None,
+            preview_frame: None,
             width,
             height,
             format,
+            device_lost,
+        }
+    }
+
+    /// Whether the device was lost; see [`OffscreenRenderer::recover`]
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.is_lost()
+    }
+
+    /// Move to a new device/queue after the old one was lost, rebuilding
+    /// every renderer's GPU state. Shaped text, the post effect and all
+    /// other CPU state are kept, so callers only need to redraw.
+    pub fn recover(&mut self, device: wgpu::Device, queue: wgpu::Queue) {
+        let (width, height, format) = (self.width, self.height, self.format);
+        self.text_renderer
+            .recreate_gpu_resources(&device, &queue, format);
+        self.bg_renderer = BgRenderer::new(&device, &queue, format, width, height);
+        self.overlay_bg_renderer = BgRenderer::new(&device, &queue, format, width, height);
+        self.preview_renderer = PreviewRenderer::new(&device, format);
+        self.preview_frame = None;
+        for pass in [&mut self.post, &mut self.color_vision] {
+            if let Some((_, effect)) = pass.take() {
+                match PostProcessor::new(&device, format, &effect) {
+                    Ok(post) => *pass = Some((post, effect)),
+                    // It compiled before, so this is the new device refusing it
+                    Err(e) => tracing::warn!("failed to rebuild the post effect: {e:#}"),
+                }
+            }
         }
+        self.device_lost = DeviceLost::watch(&device);
+        self.device = device;
+        self.queue = queue;
     }
 
     pub fn resize(&mut self, width: u32, height: u32) {
@@ -247,24 +384,63 @@ impl OffscreenRenderer {
         }
     }
 
+    /// Run the WGSL `effect` over every frame from now on (see
+    /// [`PostProcessor`]), or no effect for `None`. A shader that fails to
+    /// compile leaves frames unprocessed.
+    pub fn set_post_effect(&mut self, effect: Option<&str>) -> Result<()> {
+        self.post = None;
+        if let Some(effect) = effect {
+            let post = PostProcessor::new(&self.device, self.format, effect)?;
+            self.post = Some((post, effect.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Show frames as seen through `matrix` (see
+    /// [`post::color_vision_effect`]), after any effect; `None` shows them
+    /// as they are
+    pub fn set_color_vision(&mut self, matrix: Option<[[f32; 3]; 3]>) -> Result<()> {
+        self.color_vision = None;
+        if let Some(matrix) = matrix {
+            let effect = post::color_vision_effect(matrix);
+            let pass = PostProcessor::new(&self.device, self.format, &effect)?;
+            self.color_vision = Some((pass, effect));
+        }
+        Ok(())
+    }
+
     /// Render the terminal scene to a new wgpu::Texture and return it.
     /// The texture has RENDER_ATTACHMENT | TEXTURE_BINDING usage (required by Slint).
     pub fn render_to_texture(&mut self, bg_color: RgbColor) -> wgpu::Texture {
-        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("offscreen_terminal"),
-            size: wgpu::Extent3d {
-                width: self.width.max(1),
-                height: self.height.max(1),
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: self.format,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
-        });
+        let mut texture = self.frame_texture();
+        self.draw_scene(&texture, bg_color);
+        self.text_renderer.post_render();
+
+        for (pass, _) in [&self.post, &self.color_vision].into_iter().flatten() {
+            let output = self.frame_texture();
+            pass.apply(&self.device, &self.queue, &texture, &output);
+            texture = output;
+        }
+        texture
+    }
 
+    /// Render the terminal scene into a frame kept for previewing
+    /// workspaces off screen, drawn over while the window size holds.
+    /// Thumbnails skip the post effect and color vision passes.
+    pub fn render_preview_frame(&mut self, bg_color: RgbColor) -> wgpu::Texture {
+        let size = (self.width.max(1), self.height.max(1));
+        let texture = match self.preview_frame.take() {
+            Some(frame) if (frame.width(), frame.height()) == size => frame,
+            _ => self.frame_texture(),
+        };
+        self.draw_scene(&texture, bg_color);
+        self.text_renderer.post_render();
+        self.preview_frame = Some(texture.clone());
+        texture
+    }
+
+    /// Draw what the renderers have prepared into `texture`
+    fn draw_scene(&self, texture: &wgpu::Texture, bg_color: RgbColor) {
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         let mut encoder = self
@@ -304,9 +480,36 @@ impl OffscreenRenderer {
         }
 
         self.queue.submit(std::iter::once(encoder.finish()));
-        self.text_renderer.post_render();
+    }
 
-        texture
+    fn frame_texture(&self) -> wgpu::Texture {
+        self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("offscreen_terminal"),
+            size: wgpu::Extent3d {
+                width: self.width.max(1),
+                height: self.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+    }
+
+    /// Downsample `region` of a rendered frame into a thumbnail `width` pixels
+    /// wide, drawn over `reuse` when it has the thumbnail's size.
+    pub fn render_preview(
+        &self,
+        frame: &wgpu::Texture,
+        region: &PixelRect,
+        width: u32,
+        reuse: Option<&wgpu::Texture>,
+    ) -> wgpu::Texture {
+        self.preview_renderer
+            .render(&self.device, &self.queue, frame, region, width, reuse)
     }
 
     pub fn width(&self) -> u32 {
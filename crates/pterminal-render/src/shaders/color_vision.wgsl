@@ -0,0 +1,17 @@
+// How the frame looks with a kind of color blindness. VISION, declared
+// before this, maps linear RGB to what is seen; the frame is sRGB.
+fn to_linear(c: vec3<f32>) -> vec3<f32> {
+    let curve = pow((c + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(curve, c / 12.92, c <= vec3<f32>(0.04045));
+}
+
+fn to_srgb(c: vec3<f32>) -> vec3<f32> {
+    let curve = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(curve, c * 12.92, c <= vec3<f32>(0.0031308));
+}
+
+fn effect(uv: vec2<f32>) -> vec4<f32> {
+    let color = frame(uv);
+    let seen = clamp(VISION * to_linear(color.rgb), vec3<f32>(0.0), vec3<f32>(1.0));
+    return vec4<f32>(to_srgb(seen), color.a);
+}
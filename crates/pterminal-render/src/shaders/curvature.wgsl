@@ -0,0 +1,12 @@
+// Bulge the frame like a curved CRT screen, with darkened corners
+fn effect(uv: vec2<f32>) -> vec4<f32> {
+    let centered = uv * 2.0 - 1.0;
+    let bent = centered * (1.0 + 0.04 * dot(centered.yx, centered.yx));
+    let curved = bent * 0.5 + 0.5;
+    if (any(curved < vec2<f32>(0.0)) || any(curved > vec2<f32>(1.0))) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+    let color = frame(curved);
+    let vignette = 1.0 - 0.25 * dot(centered, centered) * 0.5;
+    return vec4<f32>(color.rgb * vignette, color.a);
+}
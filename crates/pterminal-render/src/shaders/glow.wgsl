@@ -0,0 +1,14 @@
+// Bleed bright text into its surroundings with a small cross-shaped blur
+fn effect(uv: vec2<f32>) -> vec4<f32> {
+    let color = frame(uv);
+    let px = 1.5 / post.resolution;
+    var blur = vec3<f32>(0.0);
+    for (var i = 1; i <= 3; i++) {
+        let d = px * f32(i);
+        blur += frame(uv + vec2<f32>(d.x, 0.0)).rgb + frame(uv - vec2<f32>(d.x, 0.0)).rgb;
+        blur += frame(uv + vec2<f32>(0.0, d.y)).rgb + frame(uv - vec2<f32>(0.0, d.y)).rgb;
+    }
+    blur /= 12.0;
+    let bright = max(blur - vec3<f32>(0.4), vec3<f32>(0.0));
+    return vec4<f32>(color.rgb + bright * 0.6, color.a);
+}
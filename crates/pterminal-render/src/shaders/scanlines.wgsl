@@ -0,0 +1,7 @@
+// Darken every other row of physical pixels, like a CRT's scanlines
+fn effect(uv: vec2<f32>) -> vec4<f32> {
+    let color = frame(uv);
+    let row = floor(uv.y * post.resolution.y);
+    let shade = select(1.0, 0.75, row % 2.0 >= 1.0);
+    return vec4<f32>(color.rgb * shade, color.a);
+}
@@ -0,0 +1,197 @@
+/// Horizontal layout of the tab strip, shared by the tab bar renderer and
+/// hit-testing so both agree once tabs overflow the window width.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TabStripLayout {
+    pub tab_count: usize,
+    /// Width of every tab (physical pixels)
+    pub tab_width: f32,
+    /// Width available to tabs: window width minus the overflow button
+    pub strip_width: f32,
+    /// Horizontal scroll offset of the strip (physical pixels)
+    pub scroll_x: f32,
+    /// Width of the overflow-menu button at the right edge (0 when tabs fit)
+    pub overflow_button_w: f32,
+    /// Width of the close button at the right edge of each tab
+    pub close_button_w: f32,
+}
+
+/// Result of hit-testing a click inside the tab bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabHit {
+    Tab(usize),
+    Close(usize),
+    Overflow,
+}
+
+/// Where the tab strip goes (`window.tab_bar`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabBarPosition {
+    /// Along the top, once a second tab is open
+    Auto,
+    Top,
+    Bottom,
+    /// Never shown; tabs are switched with keybindings
+    Hidden,
+}
+
+impl TabBarPosition {
+    /// Parse a config value such as `"bottom"`; unknown names mean `"auto"`.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "top" => Self::Top,
+            "bottom" => Self::Bottom,
+            "hidden" => Self::Hidden,
+            _ => Self::Auto,
+        }
+    }
+
+    /// Whether the strip is shown while `tab_count` tabs are open
+    pub fn shown(self, tab_count: usize) -> bool {
+        match self {
+            Self::Auto => tab_count > 1,
+            Self::Top | Self::Bottom => tab_count > 0,
+            Self::Hidden => false,
+        }
+    }
+
+    pub fn is_bottom(self) -> bool {
+        self == Self::Bottom
+    }
+}
+
+impl TabStripLayout {
+    /// Tabs share the window width evenly until they would shrink below
+    /// `min_tab_w`; past that they keep the minimum width, the strip scrolls,
+    /// and an overflow button is reserved at the right edge.
+    pub fn new(
+        total_w: f32,
+        tab_count: usize,
+        min_tab_w: f32,
+        overflow_button_w: f32,
+        close_button_w: f32,
+        scroll_x: f32,
+    ) -> Self {
+        let count = tab_count.max(1) as f32;
+        let overflows = total_w / count < min_tab_w;
+        let (tab_width, strip_width, overflow_button_w) = if overflows {
+            (
+                min_tab_w,
+                (total_w - overflow_button_w).max(0.0),
+                overflow_button_w,
+            )
+        } else {
+            (total_w / count, total_w, 0.0)
+        };
+        let mut layout = Self {
+            tab_count,
+            tab_width,
+            strip_width,
+            scroll_x: 0.0,
+            overflow_button_w,
+            close_button_w,
+        };
+        layout.scroll_x = scroll_x.clamp(0.0, layout.max_scroll());
+        layout
+    }
+
+    pub fn overflows(&self) -> bool {
+        self.overflow_button_w > 0.0
+    }
+
+    pub fn max_scroll(&self) -> f32 {
+        (self.tab_count as f32 * self.tab_width - self.strip_width).max(0.0)
+    }
+
+    /// Left edge of a tab in window coordinates (may be negative or past the strip).
+    pub fn tab_x(&self, idx: usize) -> f32 {
+        idx as f32 * self.tab_width - self.scroll_x
+    }
+
+    /// Adjust the scroll offset so the given tab is fully visible.
+    pub fn scroll_to_tab(&mut self, idx: usize) {
+        let left = idx as f32 * self.tab_width;
+        let right = left + self.tab_width;
+        if left < self.scroll_x {
+            self.scroll_x = left;
+        } else if right > self.scroll_x + self.strip_width {
+            self.scroll_x = right - self.strip_width;
+        }
+        self.scroll_x = self.scroll_x.clamp(0.0, self.max_scroll());
+    }
+
+    pub fn hit_test(&self, x: f32) -> Option<TabHit> {
+        if x < 0.0 {
+            return None;
+        }
+        if x >= self.strip_width {
+            return self.overflows().then_some(TabHit::Overflow);
+        }
+        let idx = ((x + self.scroll_x) / self.tab_width) as usize;
+        if idx >= self.tab_count {
+            return None;
+        }
+        let tab_right = self.tab_x(idx) + self.tab_width;
+        if x > tab_right - self.close_button_w {
+            Some(TabHit::Close(idx))
+        } else {
+            Some(TabHit::Tab(idx))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tabs_share_the_width_until_they_overflow() {
+        let fits = TabStripLayout::new(800.0, 4, 100.0, 30.0, 20.0, 50.0);
+        assert_eq!(fits.tab_width, 200.0);
+        assert_eq!(fits.strip_width, 800.0);
+        assert!(!fits.overflows());
+        assert_eq!(fits.scroll_x, 0.0);
+
+        let overflows = TabStripLayout::new(800.0, 10, 100.0, 30.0, 20.0, 1000.0);
+        assert_eq!(overflows.tab_width, 100.0);
+        assert_eq!(overflows.strip_width, 770.0);
+        assert!(overflows.overflows());
+        assert_eq!(overflows.max_scroll(), 230.0);
+        assert_eq!(overflows.scroll_x, 230.0);
+    }
+
+    #[test]
+    fn hit_test_at_the_scroll_edges() {
+        let mut layout = TabStripLayout::new(800.0, 10, 100.0, 30.0, 20.0, 0.0);
+        assert_eq!(layout.hit_test(-1.0), None);
+        assert_eq!(layout.hit_test(0.0), Some(TabHit::Tab(0)));
+        assert_eq!(layout.hit_test(85.0), Some(TabHit::Close(0)));
+        assert_eq!(layout.hit_test(769.0), Some(TabHit::Tab(7)));
+        assert_eq!(layout.hit_test(770.0), Some(TabHit::Overflow));
+
+        layout.scroll_x = layout.max_scroll();
+        assert_eq!(layout.hit_test(0.0), Some(TabHit::Tab(2)));
+        assert_eq!(layout.hit_test(769.0), Some(TabHit::Close(9)));
+        assert_eq!(layout.hit_test(799.0), Some(TabHit::Overflow));
+
+        let fits = TabStripLayout::new(800.0, 2, 100.0, 30.0, 20.0, 0.0);
+        assert_eq!(fits.hit_test(800.0), None);
+    }
+
+    #[test]
+    fn scroll_to_tab_brings_it_into_view() {
+        let mut layout = TabStripLayout::new(800.0, 10, 100.0, 30.0, 20.0, 0.0);
+        layout.scroll_to_tab(3);
+        assert_eq!(layout.scroll_x, 0.0);
+
+        layout.scroll_to_tab(8);
+        assert_eq!(layout.scroll_x, 130.0);
+        assert_eq!(layout.tab_x(8) + layout.tab_width, layout.strip_width);
+
+        layout.scroll_to_tab(9);
+        assert_eq!(layout.scroll_x, layout.max_scroll());
+
+        layout.scroll_to_tab(1);
+        assert_eq!(layout.scroll_x, 100.0);
+        assert_eq!(layout.tab_x(1), 0.0);
+    }
+}
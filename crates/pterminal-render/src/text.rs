@@ -1,22 +1,96 @@
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
+use glyphon::cosmic_text::CacheKeyFlags;
 use glyphon::{
     fontdb, Attrs, Buffer, Cache, Color, Family, FontSystem, Metrics, Resolution, Shaping, Style,
     SwashCache, TextArea, TextAtlas, TextBounds, TextRenderer as GlyphonTextRenderer, Viewport,
     Weight,
 };
 
+use pterminal_core::bidi::{BaseDirection, Visual};
 use pterminal_core::config::theme::RgbColor;
+use pterminal_core::font::{self, FontResolution};
 use pterminal_core::split::PaneId;
 use pterminal_core::terminal::GridLine;
 
+use crate::tab_strip::TabStripLayout;
+
+/// LRM then LRO: shape text left to right in the order given
+const LTR_OVERRIDE: &str = "\u{200E}\u{202D}";
+
+/// Bounds for per-pane font size overrides (logical points)
+const PANE_FONT_SIZE_MIN: f32 = 6.0;
+const PANE_FONT_SIZE_MAX: f32 = 72.0;
+
+/// Smooth cursor movement settings
+#[derive(Debug, Clone, Copy)]
+pub struct CursorAnimation {
+    pub duration: Duration,
+    /// Draw fading copies of the cursor behind it while it slides
+    pub trail: bool,
+}
+
+/// How far behind the cursor (in animation progress) each trail copy lags,
+/// with its opacity
+const CURSOR_TRAIL: [(f32, f32); 3] = [(0.45, 0.12), (0.3, 0.22), (0.15, 0.35)];
+
+/// The cursor sliding from `from` (cell coordinates) to its current cell
+struct CursorMotion {
+    from: (f32, f32),
+    started: Instant,
+}
+
+/// Smooth scrolling settings
+#[derive(Debug, Clone, Copy)]
+pub struct ScrollAnimation {
+    pub duration: Duration,
+    pub easing: Easing,
+}
+
+/// Animation timing curve
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    /// Parse a config value such as `"ease-out"` (the default).
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "linear" => Self::Linear,
+            "ease-in-out" => Self::EaseInOut,
+            _ => Self::EaseOut,
+        }
+    }
+
+    /// Map linear progress `t` (0..=1) onto the curve
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Self::Linear => t,
+            Self::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Self::EaseInOut if t < 0.5 => 4.0 * t * t * t,
+            Self::EaseInOut => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+        }
+    }
+}
+
+/// Pane content sliding from `from` rows away back to its resting position
+struct ScrollMotion {
+    from: f32,
+    started: Instant,
+}
+
 /// A colored span referencing byte ranges in a shared String
-struct RichSpan {
-    start: usize,
-    end: usize,
-    fg: RgbColor,
-    bold: bool,
-    italic: bool,
+#[derive(Debug, Clone, PartialEq)]
+pub struct RichSpan {
+    pub start: usize,
+    pub end: usize,
+    pub fg: RgbColor,
+    pub bold: bool,
+    pub italic: bool,
 }
 
 /// Pixel rectangle for pane positioning (physical pixels)
@@ -44,10 +118,22 @@ struct PaneBuffer {
     selection_bg_spans: Vec<BgSpan>,
     /// Cursor position and color for vertical bar rendering
     cursor: Option<(u16, u16, [f32; 4])>, // (col, row, color)
+    /// Set while the cursor animates towards `cursor`
+    cursor_motion: Option<CursorMotion>,
+    /// Set while the content slides after a scroll
+    scroll_motion: Option<ScrollMotion>,
     last_selection: Option<((u16, u16), (u16, u16))>,
     last_selection_bg: RgbColor,
     last_default_bg: RgbColor,
     last_line_layout_key: Option<(u32, u32)>,
+    /// Horizontal scroll offset in columns; `Some` means no-wrap mode
+    h_scroll: Option<u16>,
+    /// Column count of the last grid snapshot
+    grid_cols: usize,
+    /// Opacity the line buffers' text colors were built with
+    text_opacity: f32,
+    /// Visual order of each row shown reordered in bidi mode
+    visual: Vec<Option<Visual>>,
     /// Reusable scratch buffers to avoid per-line allocation
     scratch_text: String,
     scratch_spans: Vec<RichSpan>,
@@ -56,11 +142,27 @@ struct PaneBuffer {
 }
 
 /// A horizontal run of cells sharing the same background color
-struct BgSpan {
-    col: u16,
-    row: u16,
-    width: u16,
-    color: [f32; 4],
+#[derive(Debug, Clone, PartialEq)]
+pub struct BgSpan {
+    pub col: u16,
+    pub row: u16,
+    pub width: u16,
+    pub color: [f32; 4],
+}
+
+/// Background spans drawn behind `grid`'s text, without a GPU; for
+/// headless tests of span generation
+pub fn bg_spans(grid: &[GridLine], default_bg: RgbColor) -> Vec<BgSpan> {
+    let mut out = Vec::new();
+    rebuild_content_bg_spans(&mut out, grid, default_bg);
+    out
+}
+
+/// A line's text as handed to the shaper, and its style runs
+pub fn text_spans(line: &GridLine) -> (String, Vec<RichSpan>) {
+    let (mut text, mut spans) = (String::new(), Vec::new());
+    build_line_rich_text_into(line, &mut text, &mut spans);
+    (text, spans)
 }
 
 /// Text rendering using glyphon (cosmic-text + wgpu), supporting multiple panes.
@@ -74,15 +176,46 @@ pub struct TextRenderer {
     overlay_renderer: GlyphonTextRenderer,
     viewport: Viewport,
     pane_buffers: HashMap<PaneId, PaneBuffer>,
+    /// Per-pane font size overrides (logical points)
+    pane_font_sizes: HashMap<PaneId, f32>,
+    /// Per-pane content opacity, 1.0 when absent
+    pane_opacity: HashMap<PaneId, f32>,
     width: u32,
     height: u32,
     scale_factor: f32,
     font_size: f32,
     line_height: f32,
+    /// Line height as a multiple of the (unscaled) font size
+    line_height_factor: f32,
+    /// Extra space between cells (logical pixels)
+    letter_spacing: f32,
     /// Tab bar label buffer (None = no tab bar)
     tab_bar: Option<TabBar>,
+    /// Horizontal scroll offset of the tab strip (physical pixels)
+    tab_scroll_x: f32,
+    /// Draw the tab bar along the bottom edge instead of the top
+    tab_bar_bottom: bool,
     /// Context menu overlay (None = hidden)
-    context_menu: Option<ContextMenuOverlay>,
+    context_menu: Option<OverlayPanel>,
+    /// Recently pressed keys, with the inputs the panel was built from
+    keystrokes: Option<(OverlayPanel, KeystrokeOverlayKey)>,
+    /// Key binding cheat sheet, with the inputs the panel was built from
+    cheat_sheet: Option<(OverlayPanel, CheatSheetKey)>,
+    /// Bar along the bottom edge (None = hidden)
+    status_bar: Option<StatusBar>,
+    /// `None` moves the cursor without animation
+    cursor_animation: Option<CursorAnimation>,
+    /// `None` scrolls by whole lines
+    scroll_animation: Option<ScrollAnimation>,
+    /// Width of the bar cursor as a multiple of the usual 2px
+    cursor_scale: f32,
+    /// Lines' base direction in bidi mode; `None` shows cells as stored
+    bidi: Option<BaseDirection>,
+    /// Underline under the link the pointer is over
+    link_underline: Option<LinkUnderline>,
+    /// Configured bold/italic faces, and how each style resolved
+    font_faces: FontFaces,
+    face_styles: FaceStyles,
     atlas_trim_frames: u32,
 }
 
@@ -90,13 +223,20 @@ pub struct TextRenderer {
 struct TabBar {
     /// Per-tab text buffers with their x-offset
     tab_buffers: Vec<(Buffer, f32)>, // (buffer, x_offset)
+    /// Overflow-menu button label, drawn outside the scrolled strip
+    overflow_buffer: Option<(Buffer, f32)>,
     height: f32, // physical pixels
+    /// Top edge of the bar (physical pixels)
+    y: f32,
     bg_rects: Vec<crate::bg::BgRect>,
+    layout: TabStripLayout,
+    active: Option<usize>,
     content_hash: u64,
 }
 
-/// Context menu overlay
-struct ContextMenuOverlay {
+/// Boxed text drawn in the overlay pass (context menu, keystrokes, cheat
+/// sheet)
+struct OverlayPanel {
     buffer: Buffer,
     x: f32,
     y: f32,
@@ -105,6 +245,228 @@ struct ContextMenuOverlay {
     bg_rects: Vec<crate::bg::BgRect>,
 }
 
+/// Inputs of the keystroke overlay; the panel is only rebuilt when they change
+#[derive(PartialEq)]
+struct KeystrokeOverlayKey {
+    text: String,
+    /// Physical font size
+    font_size: f32,
+    corner: OverlayCorner,
+    /// The window less the status bar
+    window: (u32, u32),
+}
+
+/// Status bar segments laid out left to right, drawn in the overlay pass
+struct StatusBar {
+    /// Each segment's text, left edge and width
+    segments: Vec<(Buffer, f32, f32)>,
+    y: f32,
+    h: f32,
+    bg_rects: Vec<crate::bg::BgRect>,
+    key: StatusBarKey,
+}
+
+/// Inputs of the status bar; it is only rebuilt when they change
+#[derive(PartialEq)]
+struct StatusBarKey {
+    segments: Vec<String>,
+    /// Physical font size
+    font_size: f32,
+    fg: RgbColor,
+    bg: RgbColor,
+    window: (u32, u32),
+}
+
+/// Inputs of the cheat sheet overlay; the panel is only rebuilt when they change
+#[derive(PartialEq)]
+struct CheatSheetKey {
+    text: String,
+    /// Physical font size
+    font_size: f32,
+    window: (u32, u32),
+}
+
+/// Families for bold and italic cell text; `None` uses the regular family
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontFaces {
+    pub bold: Option<String>,
+    pub italic: Option<String>,
+    pub bold_italic: Option<String>,
+    /// Request bold/italic from families that lack such a face, letting
+    /// the regular face stand in (slanted for italic). When off, those
+    /// styles render with the regular face as is.
+    pub synthetic: bool,
+}
+
+impl Default for FontFaces {
+    fn default() -> Self {
+        Self {
+            bold: None,
+            italic: None,
+            bold_italic: None,
+            synthetic: true,
+        }
+    }
+}
+
+/// How one of the bold, italic and bold-italic styles is drawn
+#[derive(Debug, Clone)]
+struct FaceStyle {
+    /// Explicit family; `None` uses the regular (monospace) family
+    family: Option<String>,
+    weight: Weight,
+    style: Style,
+    flags: CacheKeyFlags,
+}
+
+impl FaceStyle {
+    /// Resolve the style (`bold`, `italic`) against the installed faces.
+    /// Families that aren't installed are dropped and added to `missing`.
+    fn resolve(
+        db: &fontdb::Database,
+        family: Option<&str>,
+        bold: bool,
+        italic: bool,
+        synthetic: bool,
+        missing: &mut Vec<String>,
+    ) -> Self {
+        let family = family.filter(|name| {
+            let installed = db.faces().any(|face| {
+                face.families
+                    .iter()
+                    .any(|(family, _)| family.eq_ignore_ascii_case(name))
+            });
+            if !installed {
+                missing.push(name.to_string());
+            }
+            installed
+        });
+        let weight = if bold { Weight::BOLD } else { Weight::NORMAL };
+        let style = if italic { Style::Italic } else { Style::Normal };
+        let face = db
+            .query(&fontdb::Query {
+                families: &[family.map_or(Family::Monospace, Family::Name)],
+                weight,
+                style,
+                ..Default::default()
+            })
+            .and_then(|id| db.face(id));
+        let real_bold = !bold || face.is_some_and(|face| face.weight.0 >= 600);
+        let real_italic = !italic || face.is_some_and(|face| face.style != Style::Normal);
+        let flags = if italic && !real_italic && synthetic {
+            CacheKeyFlags::FAKE_ITALIC
+        } else {
+            CacheKeyFlags::empty()
+        };
+        Self {
+            family: family.map(str::to_string),
+            weight: if real_bold || synthetic {
+                weight
+            } else {
+                Weight::NORMAL
+            },
+            style: if real_italic || synthetic {
+                style
+            } else {
+                Style::Normal
+            },
+            flags,
+        }
+    }
+
+    fn apply<'a>(&'a self, attrs: Attrs<'a>) -> Attrs<'a> {
+        let attrs = attrs
+            .weight(self.weight)
+            .style(self.style)
+            .cache_key_flags(self.flags);
+        match &self.family {
+            Some(family) => attrs.family(Family::Name(family)),
+            None => attrs,
+        }
+    }
+}
+
+/// Bold, italic and bold-italic styles, in that order
+type FaceStyles = [FaceStyle; 3];
+
+fn resolve_face_styles(db: &fontdb::Database, faces: &FontFaces) -> (FaceStyles, Vec<String>) {
+    let mut missing = Vec::new();
+    let styles = [
+        (faces.bold.as_deref(), true, false),
+        (faces.italic.as_deref(), false, true),
+        (faces.bold_italic.as_deref(), true, true),
+    ]
+    .map(|(family, bold, italic)| {
+        FaceStyle::resolve(db, family, bold, italic, faces.synthetic, &mut missing)
+    });
+    (styles, missing)
+}
+
+/// Attributes of a span: `attrs` with the span's bold/italic style applied
+fn styled_attrs<'a>(
+    styles: &'a FaceStyles,
+    attrs: Attrs<'a>,
+    bold: bool,
+    italic: bool,
+) -> Attrs<'a> {
+    match (bold, italic) {
+        (false, false) => attrs,
+        (true, false) => styles[0].apply(attrs),
+        (false, true) => styles[1].apply(attrs),
+        (true, true) => styles[2].apply(attrs),
+    }
+}
+
+/// An underline across cells `start..end` of a pane row (hovered link)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinkUnderline {
+    pub pane_id: PaneId,
+    pub row: u16,
+    pub start: u16,
+    pub end: u16,
+    pub color: RgbColor,
+}
+
+/// Window corner an overlay is anchored to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl OverlayCorner {
+    /// Parse a config value such as `"bottom-right"` (the default).
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "top-left" => Self::TopLeft,
+            "top-right" => Self::TopRight,
+            "bottom-left" => Self::BottomLeft,
+            _ => Self::BottomRight,
+        }
+    }
+}
+
+/// Monospace families installed on the system, without a renderer (for
+/// diagnostics; loading the fonts takes a moment)
+pub fn system_monospace_families() -> Vec<String> {
+    let mut db = fontdb::Database::new();
+    db.load_system_fonts();
+    monospace_families_in(&db)
+}
+
+fn monospace_families_in(db: &fontdb::Database) -> Vec<String> {
+    let mut families: Vec<String> = db
+        .faces()
+        .filter(|face| face.monospaced)
+        .filter_map(|face| face.families.first().map(|(name, _)| name.clone()))
+        .collect();
+    families.sort_unstable();
+    families.dedup();
+    families
+}
+
 impl TextRenderer {
     pub fn new(
         device: &wgpu::Device,
@@ -147,6 +509,9 @@ impl TextRenderer {
         );
 
         Self {
+            link_underline: None,
+            face_styles: resolve_face_styles(font_system.db(), &FontFaces::default()).0,
+            font_faces: FontFaces::default(),
             font_system,
             swash_cache,
             atlas,
@@ -154,17 +519,66 @@ impl TextRenderer {
             overlay_renderer,
             viewport,
             pane_buffers: HashMap::new(),
+            pane_font_sizes: HashMap::new(),
+            pane_opacity: HashMap::new(),
             width,
             height,
             scale_factor: scale,
             font_size: scaled_font_size,
             line_height: scaled_line_height,
+            line_height_factor: 1.22,
+            letter_spacing: 0.0,
             tab_bar: None,
+            tab_bar_bottom: false,
+            tab_scroll_x: 0.0,
             context_menu: None,
+            keystrokes: None,
+            cheat_sheet: None,
+            status_bar: None,
+            cursor_animation: None,
+            scroll_animation: None,
+            cursor_scale: 1.0,
+            bidi: None,
             atlas_trim_frames: 0,
         }
     }
 
+    /// Rebuild the glyph atlas and glyphon renderers on a new device after
+    /// the old one was lost. Shaped pane, tab and overlay buffers are kept;
+    /// their glyphs are rasterized into the new atlas on the next prepare.
+    pub fn recreate_gpu_resources(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+    ) {
+        let cache = Cache::new(device);
+        self.atlas = TextAtlas::new(device, queue, &cache, format);
+        self.glyphon_renderer = GlyphonTextRenderer::new(
+            &mut self.atlas,
+            device,
+            wgpu::MultisampleState::default(),
+            None,
+        );
+        self.overlay_renderer = GlyphonTextRenderer::new(
+            &mut self.atlas,
+            device,
+            wgpu::MultisampleState::default(),
+            None,
+        );
+        self.viewport = Viewport::new(device, &cache);
+        Self::preload_ascii_glyphs(
+            &mut self.font_system,
+            &mut self.swash_cache,
+            &mut self.atlas,
+            device,
+            queue,
+            self.font_size,
+            self.line_height,
+        );
+        self.atlas_trim_frames = 0;
+    }
+
     /// Preload ASCII printable characters into the glyph atlas
     fn preload_ascii_glyphs(
         font_system: &mut FontSystem,
@@ -228,9 +642,12 @@ impl TextRenderer {
         let scale = scale_factor as f32;
         self.scale_factor = scale;
         self.font_size = font_size * scale;
-        self.line_height = (font_size * 1.22) * scale;
-        let metrics = Metrics::new(self.font_size, self.line_height);
-        for pb in self.pane_buffers.values_mut() {
+        self.line_height = (font_size * self.line_height_factor) * scale;
+        for (pane_id, pb) in self.pane_buffers.iter_mut() {
+            let metrics = match self.pane_font_sizes.get(pane_id) {
+                Some(&size) => Metrics::new(size * scale, size * self.line_height_factor * scale),
+                None => Metrics::new(self.font_size, self.line_height),
+            };
             for lb in &mut pb.lines {
                 lb.buffer.set_metrics(&mut self.font_system, metrics);
             }
@@ -238,7 +655,221 @@ impl TextRenderer {
         }
     }
 
+    /// Configure line height (multiple of font size) and extra letter spacing
+    /// (logical pixels). Pane buffers are dropped so the next content update
+    /// reshapes every line with the new metrics.
+    pub fn set_cell_metrics(&mut self, line_height: f32, letter_spacing: f32) {
+        let line_height = line_height.max(0.5);
+        let letter_spacing = letter_spacing.max(0.0);
+        if line_height == self.line_height_factor && letter_spacing == self.letter_spacing {
+            return;
+        }
+        let unscaled_font_size = self.font_size / self.scale_factor;
+        self.line_height_factor = line_height;
+        self.letter_spacing = letter_spacing;
+        self.line_height = unscaled_font_size * line_height * self.scale_factor;
+        self.pane_buffers.clear();
+    }
+
+    /// Underline a hovered link, or clear it with `None`. Returns whether
+    /// it changed (a redraw is needed).
+    pub fn set_link_underline(&mut self, underline: Option<LinkUnderline>) -> bool {
+        let changed = self.link_underline != underline;
+        self.link_underline = underline;
+        changed
+    }
+
+    /// Monospace families installed on the system, sorted and deduplicated
+    pub fn monospace_families(&self) -> Vec<String> {
+        monospace_families_in(self.font_system.db())
+    }
+
+    /// Render with the first of `families` that resolves (see
+    /// [`font::resolve`]). Pane buffers are dropped so every line is
+    /// reshaped with the new face.
+    pub fn set_font_family(&mut self, families: &[&str]) -> FontResolution {
+        let resolution = font::resolve(families, &self.monospace_families());
+        if let Some(family) = &resolution.family {
+            self.font_system
+                .db_mut()
+                .set_monospace_family(family.as_str());
+            // Whether bold/italic faces exist depends on the family
+            self.face_styles = resolve_face_styles(self.font_system.db(), &self.font_faces).0;
+            self.pane_buffers.clear();
+        }
+        resolution
+    }
+
+    /// Use `faces` for bold and italic cell text. Returns the configured
+    /// families that are not installed; those styles fall back to the
+    /// regular family.
+    pub fn set_font_faces(&mut self, faces: FontFaces) -> Vec<String> {
+        let (styles, missing) = resolve_face_styles(self.font_system.db(), &faces);
+        self.face_styles = styles;
+        self.font_faces = faces;
+        self.pane_buffers.clear();
+        missing
+    }
+
+    /// Show right-to-left text in visual order, with lines in `base`
+    /// direction, or every cell where it is stored with `None`.
+    pub fn set_bidi(&mut self, base: Option<BaseDirection>) {
+        if self.bidi != base {
+            self.bidi = base;
+            self.pane_buffers.clear();
+        }
+    }
+
+    /// Logical column shown at visual column `col` of a pane's `row`; the
+    /// same column unless bidi mode reordered the row.
+    pub fn logical_col(&self, pane_id: PaneId, row: u16, col: u16) -> u16 {
+        self.pane_buffers
+            .get(&pane_id)
+            .and_then(|pb| pb.visual.get(row as usize)?.as_ref())
+            .map_or(col, |visual| visual.logical(col))
+    }
+
+    /// Animate cursor movement, or move it instantly with `None`.
+    pub fn set_cursor_animation(&mut self, animation: Option<CursorAnimation>) {
+        self.cursor_animation = animation;
+        if animation.is_none() {
+            for pb in self.pane_buffers.values_mut() {
+                pb.cursor_motion = None;
+            }
+        }
+    }
+
+    /// Draw the bar cursor `scale` times as wide, up to a full cell.
+    pub fn set_cursor_scale(&mut self, scale: f32) {
+        self.cursor_scale = scale.max(1.0);
+    }
+
+    /// Animate scrolling, or scroll by whole lines with `None`.
+    pub fn set_scroll_animation(&mut self, animation: Option<ScrollAnimation>) {
+        self.scroll_animation = animation;
+        if animation.is_none() {
+            for pb in self.pane_buffers.values_mut() {
+                pb.scroll_motion = None;
+            }
+        }
+    }
+
+    /// Slide a pane's content into place after its view moved `rows` lines
+    /// (positive when the content moved down, i.e. scrolled into history).
+    pub fn animate_scroll(&mut self, pane_id: PaneId, rows: i32) {
+        let Some(animation) = self.scroll_animation else {
+            return;
+        };
+        let Some(pb) = self.pane_buffers.get_mut(&pane_id) else {
+            return;
+        };
+        if rows == 0 {
+            return;
+        }
+        // Continue from wherever the content is drawn now, capped to a screenful
+        let now = Instant::now();
+        let limit = pb.lines.len().max(1) as f32;
+        let from = scroll_rows(pb.scroll_motion.as_ref(), animation, now) - rows as f32;
+        pb.scroll_motion = Some(ScrollMotion {
+            from: from.clamp(-limit, limit),
+            started: now,
+        });
+    }
+
+    /// Whether a cursor or scroll is still sliding and more frames are needed
+    pub fn is_animating(&self) -> bool {
+        self.pane_buffers.values().any(|pb| {
+            let cursor = self.cursor_animation.is_some_and(|animation| {
+                pb.cursor_motion
+                    .as_ref()
+                    .is_some_and(|motion| motion.started.elapsed() < animation.duration)
+            });
+            let scroll = self.scroll_animation.is_some_and(|animation| {
+                pb.scroll_motion
+                    .as_ref()
+                    .is_some_and(|motion| motion.started.elapsed() < animation.duration)
+            });
+            cursor || scroll
+        })
+    }
+
+    /// Vertical offset (physical pixels) of a pane's content mid-scroll
+    fn scroll_offset(&self, pb: &PaneBuffer, line_h: f32) -> f32 {
+        match self.scroll_animation {
+            Some(animation) => {
+                scroll_rows(pb.scroll_motion.as_ref(), animation, Instant::now()) * line_h
+            }
+            None => 0.0,
+        }
+    }
+
+    /// Override the font size of a single pane (logical points), or restore
+    /// the global size with `None`. The pane's buffers are dropped so the
+    /// next content update reshapes every line with the new metrics.
+    pub fn set_pane_font_size(&mut self, pane_id: PaneId, font_size: Option<f32>) {
+        let changed = match font_size {
+            Some(size) => self.pane_font_sizes.insert(pane_id, size) != Some(size),
+            None => self.pane_font_sizes.remove(&pane_id).is_some(),
+        };
+        if changed {
+            self.pane_buffers.remove(&pane_id);
+        }
+    }
+
+    /// Grow or shrink a pane's font size by `delta` points, starting from
+    /// `base` when it has no override yet. A `delta` of 0 drops the override.
+    pub fn step_pane_font_size(&mut self, pane_id: PaneId, base: f32, delta: f32) {
+        let size = if delta == 0.0 {
+            None
+        } else {
+            let current = self.pane_font_size(pane_id).unwrap_or(base);
+            let next = (current + delta).clamp(PANE_FONT_SIZE_MIN, PANE_FONT_SIZE_MAX);
+            (next != base).then_some(next)
+        };
+        self.set_pane_font_size(pane_id, size);
+    }
+
+    /// Fade a pane's text and cell backgrounds to `opacity` (0..1) over the
+    /// window background. Returns whether it changed; the pane's content must
+    /// then be set again for its text to pick it up.
+    pub fn set_pane_opacity(&mut self, pane_id: PaneId, opacity: f32) -> bool {
+        let opacity = opacity.clamp(0.0, 1.0);
+        self.pane_opacity.insert(pane_id, opacity).unwrap_or(1.0) != opacity
+    }
+
+    fn pane_opacity(&self, pane_id: PaneId) -> f32 {
+        self.pane_opacity.get(&pane_id).copied().unwrap_or(1.0)
+    }
+
+    /// Font size override of a pane (logical points), if any
+    pub fn pane_font_size(&self, pane_id: PaneId) -> Option<f32> {
+        self.pane_font_sizes.get(&pane_id).copied()
+    }
+
+    /// Scaled (font size, line height) used to lay out a pane
+    fn pane_metrics(&self, pane_id: PaneId) -> (f32, f32) {
+        match self.pane_font_sizes.get(&pane_id) {
+            Some(&size) => (
+                size * self.scale_factor,
+                size * self.line_height_factor * self.scale_factor,
+            ),
+            None => (self.font_size, self.line_height),
+        }
+    }
+
+    /// Attributes for terminal cell text (monospace + configured letter spacing).
+    fn cell_attrs(&self, font_size: f32) -> Attrs<'static> {
+        let attrs = Attrs::new().family(Family::Monospace);
+        if self.letter_spacing > 0.0 {
+            // cosmic-text expresses letter spacing in em units
+            attrs.letter_spacing(self.letter_spacing * self.scale_factor / font_size)
+        } else {
+            attrs
+        }
+    }
+
     /// Update a pane's line buffers. Only reshapes lines whose content changed.
+    #[allow(clippy::too_many_arguments)]
     pub fn set_pane_content(
         &mut self,
         pane_id: PaneId,
@@ -251,7 +882,10 @@ impl TextRenderer {
         selection: Option<((u16, u16), (u16, u16))>, // normalized (start, end) or None
         selection_bg: RgbColor,
     ) {
-        let metrics = Metrics::new(self.font_size, self.line_height);
+        let (font_size, line_height) = self.pane_metrics(pane_id);
+        let metrics = Metrics::new(font_size, line_height);
+        let default_attrs = self.cell_attrs(font_size);
+        let opacity = self.pane_opacity(pane_id);
         let pb = self
             .pane_buffers
             .entry(pane_id)
@@ -260,10 +894,16 @@ impl TextRenderer {
                 content_bg_spans: Vec::new(),
                 selection_bg_spans: Vec::new(),
                 cursor: None,
+                cursor_motion: None,
+                scroll_motion: None,
                 last_selection: None,
                 last_selection_bg: RgbColor::new(0, 0, 0),
                 last_default_bg: RgbColor::new(0, 0, 0),
                 last_line_layout_key: None,
+                h_scroll: None,
+                grid_cols: 0,
+                text_opacity: 1.0,
+                visual: Vec::new(),
                 scratch_text: String::with_capacity(256),
                 scratch_spans: Vec::with_capacity(16),
                 generation: 0,
@@ -271,6 +911,9 @@ impl TextRenderer {
 
         // Ensure correct number of line buffers
         let line_count_changed = pb.lines.len() != grid.len();
+        // Text colors carry the opacity, so a change reshapes every line
+        let opacity_changed = pb.text_opacity != opacity;
+        pb.text_opacity = opacity;
         while pb.lines.len() < grid.len() {
             pb.lines.push(LineBuffer {
                 buffer: Buffer::new(&mut self.font_system, metrics),
@@ -279,9 +922,64 @@ impl TextRenderer {
             });
         }
         pb.lines.truncate(grid.len());
+        pb.grid_cols = grid.first().map_or(0, |line| line.cells.len());
+
+        // Bidi mode shows rows with right-to-left text reordered; the
+        // cursor and selection follow their cells
+        let display: Vec<GridLine>;
+        let grid = match self.bidi {
+            Some(base) => {
+                pb.visual.resize(grid.len(), None);
+                let rows = match dirty_rows {
+                    Some(rows) if !line_count_changed && !opacity_changed => rows.to_vec(),
+                    _ => (0..grid.len()).collect(),
+                };
+                for row in rows {
+                    if let Some(line) = grid.get(row) {
+                        pb.visual[row] = Visual::of(&line.cells, base);
+                    }
+                }
+                if pb.visual.iter().any(Option::is_some) {
+                    display = grid
+                        .iter()
+                        .zip(&pb.visual)
+                        .map(|(line, visual)| match visual {
+                            Some(visual) => GridLine {
+                                cells: visual.apply(&line.cells),
+                            },
+                            None => line.clone(),
+                        })
+                        .collect();
+                    &display
+                } else {
+                    grid
+                }
+            }
+            None => {
+                pb.visual.clear();
+                grid
+            }
+        };
+        let cursor_pos = match pb.visual.get(cursor_pos.1 as usize) {
+            Some(Some(visual)) => (visual.visual(cursor_pos.0), cursor_pos.1),
+            _ => cursor_pos,
+        };
 
         // Store cursor for vertical bar rendering in collect_bg_rects
         let (cursor_col, cursor_row) = cursor_pos;
+        let previous = pb.cursor.map(|(col, row, _)| (col, row));
+        pb.cursor_motion = match (self.cursor_animation, previous) {
+            // Start from wherever the cursor is drawn now, mid-slide or not
+            (Some(animation), Some(previous)) if cursor_visible && previous != cursor_pos => {
+                let now = Instant::now();
+                Some(CursorMotion {
+                    from: cursor_cell(pb.cursor_motion.as_ref(), previous, animation, 0.0, now),
+                    started: now,
+                })
+            }
+            (Some(_), _) if cursor_visible => pb.cursor_motion.take(),
+            _ => None,
+        };
         if cursor_visible {
             pb.cursor = Some((
                 cursor_col,
@@ -297,12 +995,11 @@ impl TextRenderer {
             pb.cursor = None;
         }
 
-        let default_attrs = Attrs::new().family(Family::Monospace);
         let bg_full_rebuild = line_count_changed || pb.last_default_bg != default_bg;
         let mut bg_dirty_rows: Vec<usize> = Vec::new();
 
         // Use native damage tracking from alacritty_terminal - no hash computation needed
-        if line_count_changed {
+        if line_count_changed || opacity_changed {
             for (row_idx, line) in grid.iter().enumerate() {
                 update_line_buffer_no_hash(
                     &mut self.font_system,
@@ -310,6 +1007,7 @@ impl TextRenderer {
                     row_idx,
                     line,
                     &default_attrs,
+                    &self.face_styles,
                 );
                 bg_dirty_rows.push(row_idx);
             }
@@ -323,6 +1021,7 @@ impl TextRenderer {
                         row_idx,
                         line,
                         &default_attrs,
+                        &self.face_styles,
                     );
                     bg_dirty_rows.push(row_idx);
                 }
@@ -336,6 +1035,7 @@ impl TextRenderer {
                     row_idx,
                     line,
                     &default_attrs,
+                    &self.face_styles,
                 );
                 bg_dirty_rows.push(row_idx);
             }
@@ -357,18 +1057,41 @@ impl TextRenderer {
             pb.last_default_bg = default_bg;
         }
 
-        let selection_dirty =
-            pb.last_selection != selection || pb.last_selection_bg != selection_bg;
+        let selection_dirty = pb.last_selection != selection
+            || pb.last_selection_bg != selection_bg
+            || (!pb.visual.is_empty() && any_bg_dirty);
         if selection_dirty {
-            rebuild_selection_bg_spans(&mut pb.selection_bg_spans, grid, selection, selection_bg);
+            rebuild_selection_bg_spans(
+                &mut pb.selection_bg_spans,
+                grid,
+                &pb.visual,
+                selection,
+                selection_bg,
+            );
             pb.last_selection = selection;
             pb.last_selection_bg = selection_bg;
         }
     }
 
+    /// Set the horizontal scroll offset (columns) for a pane in no-wrap mode.
+    /// `None` restores normal wrapping: no offset and no horizontal scrollbar.
+    pub fn set_pane_h_scroll(&mut self, pane_id: PaneId, offset: Option<u16>) {
+        if let Some(pb) = self.pane_buffers.get_mut(&pane_id) {
+            pb.h_scroll = offset;
+        }
+    }
+
     /// Remove a pane's buffers (when the pane is closed).
     pub fn remove_pane(&mut self, pane_id: PaneId) {
         self.pane_buffers.remove(&pane_id);
+        self.pane_font_sizes.remove(&pane_id);
+        self.pane_opacity.remove(&pane_id);
+        if self
+            .link_underline
+            .is_some_and(|link| link.pane_id == pane_id)
+        {
+            self.link_underline = None;
+        }
     }
 
     /// Prepare all visible panes for rendering.
@@ -384,20 +1107,27 @@ impl TextRenderer {
             height: self.height,
         };
         self.viewport.update(queue, resolution);
-        let no_wrap_slack = (self.font_size * 0.6 * 2.0).max(2.0);
 
         // Set width on each line buffer only when pane width / line height changed.
         for (pane_id, rect) in panes {
+            let (cell_w, line_h) = self.pane_cell_size(*pane_id);
+            let no_wrap_slack = (cell_w * 2.0).max(2.0);
             if let Some(pb) = self.pane_buffers.get_mut(pane_id) {
-                let layout_key = Some((rect.w.to_bits(), self.line_height.to_bits()));
+                // In no-wrap mode the grid is wider than the pane; lay out the full row.
+                let line_w = if pb.h_scroll.is_some() {
+                    rect.w.max(pb.grid_cols as f32 * cell_w)
+                } else {
+                    rect.w
+                };
+                let layout_key = Some((line_w.to_bits(), line_h.to_bits()));
                 if pb.last_line_layout_key != layout_key {
                     for lb in &mut pb.lines {
                         lb.buffer.set_size(
                             &mut self.font_system,
                             // Add a small slack so terminal rows don't soft-wrap due to
                             // glyph advance rounding differences vs our cell width estimate.
-                            Some(rect.w + no_wrap_slack),
-                            Some(self.line_height),
+                            Some(line_w + no_wrap_slack),
+                            Some(line_h),
                         );
                     }
                     pb.last_line_layout_key = layout_key;
@@ -406,7 +1136,6 @@ impl TextRenderer {
         }
 
         let default_glyphon_color = Color::rgb(default_color.r, default_color.g, default_color.b);
-        let line_h = self.line_height;
 
         let mut text_areas: Vec<TextArea<'_>> = Vec::new();
 
@@ -416,13 +1145,29 @@ impl TextRenderer {
                 text_areas.push(TextArea {
                     buffer,
                     left: *x_offset,
-                    top: 0.0,
+                    top: tb.y,
+                    scale: 1.0,
+                    bounds: TextBounds {
+                        left: x_offset.max(0.0) as i32,
+                        top: tb.y as i32,
+                        right: tb.layout.strip_width as i32,
+                        bottom: (tb.y + tb.height) as i32,
+                    },
+                    default_color: default_glyphon_color,
+                    custom_glyphs: &[],
+                });
+            }
+            if let Some((buffer, x_offset)) = &tb.overflow_buffer {
+                text_areas.push(TextArea {
+                    buffer,
+                    left: *x_offset,
+                    top: tb.y,
                     scale: 1.0,
                     bounds: TextBounds {
                         left: *x_offset as i32,
-                        top: 0,
+                        top: tb.y as i32,
                         right: self.width as i32,
-                        bottom: tb.height as i32,
+                        bottom: (tb.y + tb.height) as i32,
                     },
                     default_color: default_glyphon_color,
                     custom_glyphs: &[],
@@ -432,15 +1177,18 @@ impl TextRenderer {
 
         // Pane text
         for (pane_id, rect) in panes {
+            let (cell_w, line_h) = self.pane_cell_size(*pane_id);
             if let Some(pb) = self.pane_buffers.get(pane_id) {
+                let scroll_x = pb.h_scroll.unwrap_or(0) as f32 * cell_w;
+                let scroll_y = self.scroll_offset(pb, line_h);
                 for (idx, lb) in pb.lines.iter().enumerate() {
                     if lb.is_blank {
                         continue;
                     }
                     text_areas.push(TextArea {
                         buffer: &lb.buffer,
-                        left: rect.x,
-                        top: rect.y + idx as f32 * line_h,
+                        left: rect.x - scroll_x,
+                        top: rect.y + scroll_y + idx as f32 * line_h,
                         scale: 1.0,
                         bounds: TextBounds {
                             left: rect.x as i32,
@@ -466,21 +1214,45 @@ impl TextRenderer {
             &mut self.swash_cache,
         );
 
-        // Context menu text — separate prepare for overlay rendering
+        // Context menu, keystroke and cheat sheet text — separate prepare for
+        // overlay rendering
         let mut overlay_areas: Vec<TextArea<'_>> = Vec::new();
-        if let Some(ref cm) = self.context_menu {
+        if let Some(bar) = &self.status_bar {
+            for (buffer, x, w) in &bar.segments {
+                overlay_areas.push(TextArea {
+                    buffer,
+                    left: *x,
+                    top: bar.y,
+                    scale: 1.0,
+                    bounds: TextBounds {
+                        left: *x as i32,
+                        top: bar.y as i32,
+                        right: (x + w).min(self.width as f32) as i32,
+                        bottom: (bar.y + bar.h) as i32,
+                    },
+                    default_color: Color::rgb(default_color.r, default_color.g, default_color.b),
+                    custom_glyphs: &[],
+                });
+            }
+        }
+        let panels = self
+            .context_menu
+            .iter()
+            .chain(self.keystrokes.as_ref().map(|(panel, _)| panel))
+            .chain(self.cheat_sheet.as_ref().map(|(panel, _)| panel));
+        for panel in panels {
             let default_glyphon_color2 =
                 Color::rgb(default_color.r, default_color.g, default_color.b);
             overlay_areas.push(TextArea {
-                buffer: &cm.buffer,
-                left: cm.x,
-                top: cm.y,
+                buffer: &panel.buffer,
+                left: panel.x,
+                top: panel.y,
                 scale: 1.0,
                 bounds: TextBounds {
-                    left: cm.x as i32,
-                    top: cm.y as i32,
-                    right: (cm.x + cm.w) as i32,
-                    bottom: (cm.y + cm.h) as i32,
+                    left: panel.x as i32,
+                    top: panel.y as i32,
+                    right: (panel.x + panel.w) as i32,
+                    bottom: (panel.y + panel.h) as i32,
                 },
                 default_color: default_glyphon_color2,
                 custom_glyphs: &[],
@@ -522,15 +1294,15 @@ impl TextRenderer {
 
     /// Collect background rects for all visible panes (physical pixel coords)
     pub fn collect_bg_rects(&self, panes: &[(PaneId, PixelRect)]) -> Vec<crate::bg::BgRect> {
-        let cell_w = self.font_size * 0.6;
-        let cell_h = self.line_height;
-        let cursor_bar_w = 2.0 * self.scale_factor;
+        let cursor_bar_w = 2.0 * self.scale_factor * self.cursor_scale;
+        let scrollbar_h = 4.0 * self.scale_factor;
         let mut total_rects = self.tab_bar.as_ref().map_or(0, |tb| tb.bg_rects.len());
         for (pane_id, _) in panes {
             if let Some(pb) = self.pane_buffers.get(pane_id) {
                 total_rects += pb.content_bg_spans.len();
                 total_rects += pb.selection_bg_spans.len();
-                total_rects += usize::from(pb.cursor.is_some());
+                total_rects += usize::from(pb.cursor.is_some()) * (1 + CURSOR_TRAIL.len());
+                total_rects += if pb.h_scroll.is_some() { 2 } else { 0 };
             }
         }
         let mut rects = Vec::with_capacity(total_rects);
@@ -540,34 +1312,86 @@ impl TextRenderer {
             rects.extend_from_slice(&tb.bg_rects);
         }
         for (pane_id, rect) in panes {
+            let (cell_w, cell_h) = self.pane_cell_size(*pane_id);
+            let opacity = self.pane_opacity(*pane_id);
+            let fade = |c: [f32; 4]| [c[0], c[1], c[2], c[3] * opacity];
             if let Some(pb) = self.pane_buffers.get(pane_id) {
-                for bg in &pb.content_bg_spans {
-                    rects.push(crate::bg::BgRect {
-                        x: rect.x + bg.col as f32 * cell_w,
-                        y: rect.y + bg.row as f32 * cell_h,
-                        w: bg.width as f32 * cell_w,
-                        h: cell_h,
-                        color: bg.color,
-                    });
-                }
-                for bg in &pb.selection_bg_spans {
-                    rects.push(crate::bg::BgRect {
-                        x: rect.x + bg.col as f32 * cell_w,
-                        y: rect.y + bg.row as f32 * cell_h,
-                        w: bg.width as f32 * cell_w,
-                        h: cell_h,
-                        color: bg.color,
-                    });
+                let origin_x = rect.x - pb.h_scroll.unwrap_or(0) as f32 * cell_w;
+                let origin_y = rect.y + self.scroll_offset(pb, cell_h);
+                for bg in pb.content_bg_spans.iter().chain(&pb.selection_bg_spans) {
+                    push_clipped_rect(
+                        &mut rects,
+                        rect,
+                        origin_x + bg.col as f32 * cell_w,
+                        origin_y + bg.row as f32 * cell_h,
+                        bg.width as f32 * cell_w,
+                        cell_h,
+                        fade(bg.color),
+                    );
                 }
-                // Vertical bar cursor (iTerm2 style)
+                // Vertical bar cursor (iTerm2 style), possibly mid-slide
                 if let Some((col, row, color)) = pb.cursor {
-                    rects.push(crate::bg::BgRect {
-                        x: rect.x + col as f32 * cell_w,
-                        y: rect.y + row as f32 * cell_h,
-                        w: cursor_bar_w,
-                        h: cell_h,
-                        color,
-                    });
+                    let now = Instant::now();
+                    let motion = pb.cursor_motion.as_ref();
+                    let mut push_cursor = |(x, y): (f32, f32), alpha: f32| {
+                        push_clipped_rect(
+                            &mut rects,
+                            rect,
+                            origin_x + x * cell_w,
+                            origin_y + y * cell_h,
+                            cursor_bar_w.min(cell_w),
+                            cell_h,
+                            fade([color[0], color[1], color[2], color[3] * alpha]),
+                        );
+                    };
+                    match (self.cursor_animation, motion) {
+                        (Some(animation), Some(m)) => {
+                            if animation.trail && m.started.elapsed() < animation.duration {
+                                for (lag, alpha) in CURSOR_TRAIL {
+                                    let cell = cursor_cell(motion, (col, row), animation, lag, now);
+                                    push_cursor(cell, alpha);
+                                }
+                            }
+                            push_cursor(cursor_cell(motion, (col, row), animation, 0.0, now), 1.0);
+                        }
+                        _ => push_cursor((col as f32, row as f32), 1.0),
+                    }
+                }
+                if let Some(link) = self.link_underline.filter(|l| l.pane_id == *pane_id) {
+                    let thickness = self.scale_factor.max(1.0);
+                    push_clipped_rect(
+                        &mut rects,
+                        rect,
+                        origin_x + link.start as f32 * cell_w,
+                        origin_y + (link.row + 1) as f32 * cell_h - thickness,
+                        (link.end - link.start) as f32 * cell_w,
+                        thickness,
+                        fade(rgb_to_rgba(link.color)),
+                    );
+                }
+                // Horizontal scrollbar (no-wrap mode, only when the grid overflows)
+                if let Some(offset) = pb.h_scroll {
+                    let content_w = pb.grid_cols as f32 * cell_w;
+                    if content_w > rect.w {
+                        let track_y = rect.y + rect.h - scrollbar_h;
+                        rects.push(crate::bg::BgRect {
+                            x: rect.x,
+                            y: track_y,
+                            w: rect.w,
+                            h: scrollbar_h,
+                            color: fade([1.0, 1.0, 1.0, 0.08]),
+                        });
+                        let thumb_w = (rect.w * rect.w / content_w).max(scrollbar_h * 4.0);
+                        let thumb_x = rect.x
+                            + (offset as f32 * cell_w / content_w * rect.w).min(rect.w - thumb_w);
+                        rects.push(crate::bg::BgRect {
+                            x: thumb_x,
+                            y: track_y,
+                            w: thumb_w,
+                            h: scrollbar_h,
+                            color: fade([1.0, 1.0, 1.0, 0.35]),
+                        });
+                    }
                 }
             }
         }
@@ -575,17 +1399,36 @@ impl TextRenderer {
         rects
     }
 
-    /// Collect overlay bg rects (context menu) — drawn AFTER text
+    /// Collect overlay bg rects (status bar, context menu, keystrokes, cheat
+    /// sheet) — drawn AFTER text
     pub fn collect_overlay_bg_rects(&self) -> Vec<crate::bg::BgRect> {
-        if let Some(ref cm) = self.context_menu {
-            cm.bg_rects.clone()
-        } else {
-            Vec::new()
-        }
+        let bar = self
+            .status_bar
+            .iter()
+            .flat_map(|bar| bar.bg_rects.iter().copied());
+        let panels = self
+            .context_menu
+            .iter()
+            .chain(self.keystrokes.as_ref().map(|(panel, _)| panel))
+            .chain(self.cheat_sheet.as_ref().map(|(panel, _)| panel))
+            .flat_map(|panel| panel.bg_rects.iter().copied());
+        bar.chain(panels).collect()
     }
 
     pub fn cell_size(&self) -> (f32, f32) {
-        (self.font_size * 0.6, self.line_height)
+        (
+            self.font_size * 0.6 + self.letter_spacing * self.scale_factor,
+            self.line_height,
+        )
+    }
+
+    /// Cell size of a pane, honoring its font size override
+    pub fn pane_cell_size(&self, pane_id: PaneId) -> (f32, f32) {
+        let (font_size, line_height) = self.pane_metrics(pane_id);
+        (
+            font_size * 0.6 + self.letter_spacing * self.scale_factor,
+            line_height,
+        )
     }
 
     pub fn font_size(&self) -> f32 {
@@ -601,6 +1444,51 @@ impl TextRenderer {
         self.tab_bar.as_ref().map_or(0.0, |tb| tb.height)
     }
 
+    /// Space the tab bar takes from the (top, bottom) of the window
+    pub fn tab_bar_insets(&self) -> (f32, f32) {
+        let height = self.tab_bar_height();
+        if self.tab_bar_bottom {
+            (0.0, height)
+        } else {
+            (height, 0.0)
+        }
+    }
+
+    /// Whether a point `y` pixels from the top of the window is on the tab bar
+    pub fn tab_bar_contains(&self, y: f32) -> bool {
+        self.tab_bar
+            .as_ref()
+            .is_some_and(|tb| y >= tb.y && y < tb.y + tb.height)
+    }
+
+    /// Draw the tab bar along the bottom edge instead of the top. Takes
+    /// effect on the next `set_tab_bar`.
+    pub fn set_tab_bar_bottom(&mut self, bottom: bool) {
+        if self.tab_bar_bottom != bottom {
+            self.tab_bar_bottom = bottom;
+            if let Some(tb) = &mut self.tab_bar {
+                tb.content_hash = 0; // force rebuild
+            }
+        }
+    }
+
+    /// Current tab strip layout, for hit-testing clicks in the tab bar
+    pub fn tab_strip_layout(&self) -> Option<TabStripLayout> {
+        self.tab_bar.as_ref().map(|tb| tb.layout)
+    }
+
+    /// Scroll the tab strip horizontally. Takes effect on the next `set_tab_bar`.
+    pub fn scroll_tab_bar(&mut self, delta: f32) {
+        let Some(ref mut tb) = self.tab_bar else {
+            return;
+        };
+        let scroll_x = (self.tab_scroll_x + delta).clamp(0.0, tb.layout.max_scroll());
+        if scroll_x != self.tab_scroll_x {
+            self.tab_scroll_x = scroll_x;
+            tb.content_hash = 0; // force rebuild
+        }
+    }
+
     /// Update tab bar content. Pass empty slice to hide.
     pub fn set_tab_bar(
         &mut self,
@@ -610,11 +1498,40 @@ impl TextRenderer {
         fg: RgbColor,
         active_fg: RgbColor,
     ) {
-        if tabs.len() <= 1 {
+        if tabs.is_empty() {
             self.tab_bar = None;
+            self.tab_scroll_x = 0.0;
             return;
         }
 
+        let tab_font_size = self.font_size * 0.8; // slightly smaller than terminal
+        let tab_height = tab_font_size * 1.6;
+        let bar_y = if self.tab_bar_bottom {
+            (self.height as f32 - tab_height).max(0.0)
+        } else {
+            0.0
+        };
+        let close_btn_w = tab_font_size * 2.0; // width reserved for ✕
+        let min_tab_w = 120.0 * self.scale_factor;
+        let active = tabs.iter().position(|(_, active)| *active);
+
+        let mut layout = TabStripLayout::new(
+            self.width as f32,
+            tabs.len(),
+            min_tab_w,
+            tab_height * 1.5,
+            close_btn_w,
+            self.tab_scroll_x,
+        );
+        // Newly activated tabs are scrolled into view
+        let prev_active = self.tab_bar.as_ref().and_then(|tb| tb.active);
+        if let Some(idx) = active {
+            if prev_active != active {
+                layout.scroll_to_tab(idx);
+            }
+        }
+        self.tab_scroll_x = layout.scroll_x;
+
         // Hash to skip if unchanged
         use std::hash::{Hash, Hasher};
         let mut hasher = std::collections::hash_map::DefaultHasher::new();
@@ -622,60 +1539,74 @@ impl TextRenderer {
             label.hash(&mut hasher);
             active.hash(&mut hasher);
         }
+        self.width.hash(&mut hasher);
+        bar_y.to_bits().hash(&mut hasher);
+        layout.scroll_x.to_bits().hash(&mut hasher);
         let hash = hasher.finish();
 
-        let tab_font_size = self.font_size * 0.8; // slightly smaller than terminal
-        let tab_height = tab_font_size * 1.6;
-        let tab_width = self.width as f32 / tabs.len() as f32;
-
         if let Some(ref tb) = self.tab_bar {
             if tb.content_hash == hash {
                 return;
             }
         }
 
+        let tab_width = layout.tab_width;
+        let to_color = |c: RgbColor| {
+            [
+                c.r as f32 / 255.0,
+                c.g as f32 / 255.0,
+                c.b as f32 / 255.0,
+                1.0,
+            ]
+        };
+
         // Build bg rects for each tab
-        let mut bg_rects = Vec::with_capacity(tabs.len() + 1);
+        let mut bg_rects = Vec::with_capacity(3);
         // Full bar background
         bg_rects.push(crate::bg::BgRect {
             x: 0.0,
-            y: 0.0,
+            y: bar_y,
             w: self.width as f32,
             h: tab_height,
-            color: [
-                bar_bg.r as f32 / 255.0,
-                bar_bg.g as f32 / 255.0,
-                bar_bg.b as f32 / 255.0,
-                1.0,
-            ],
+            color: to_color(bar_bg),
         });
-        // Active tab highlight
-        for (i, (_label, active)) in tabs.iter().enumerate() {
-            if *active {
+        // Active tab highlight, clipped to the visible strip
+        if let Some(idx) = active {
+            let left = layout.tab_x(idx).max(0.0);
+            let right = (layout.tab_x(idx) + tab_width).min(layout.strip_width);
+            if right > left {
                 bg_rects.push(crate::bg::BgRect {
-                    x: i as f32 * tab_width,
-                    y: 0.0,
-                    w: tab_width,
+                    x: left,
+                    y: bar_y,
+                    w: right - left,
                     h: tab_height,
-                    color: [
-                        active_bg.r as f32 / 255.0,
-                        active_bg.g as f32 / 255.0,
-                        active_bg.b as f32 / 255.0,
-                        1.0,
-                    ],
+                    color: to_color(active_bg),
                 });
             }
         }
+        // Separator between the strip and the overflow button
+        if layout.overflows() {
+            bg_rects.push(crate::bg::BgRect {
+                x: layout.strip_width,
+                y: bar_y,
+                w: self.scale_factor,
+                h: tab_height,
+                color: to_color(fg),
+            });
+        }
 
         // Build per-tab text buffers, each positioned at its tab region
         // Each tab has a label buffer (left) and a close button buffer (right)
         let metrics = Metrics::new(tab_font_size, tab_height);
         let default_attrs = Attrs::new().family(Family::Monospace);
-        let close_btn_w = tab_font_size * 2.0; // width reserved for ✕
         let mut tab_buffers = Vec::with_capacity(tabs.len() * 2);
 
         for (i, (label, active)) in tabs.iter().enumerate() {
-            let x_offset = i as f32 * tab_width;
+            let x_offset = layout.tab_x(i);
+            // Skip tabs scrolled entirely out of view
+            if x_offset + tab_width <= 0.0 || x_offset >= layout.strip_width {
+                continue;
+            }
             let color = if *active { active_fg } else { fg };
             let attrs = default_attrs.clone().color(Color::rgb(color.r, color.g, color.b));
 
@@ -714,10 +1645,34 @@ impl TextRenderer {
             tab_buffers.push((close_buf, x_offset + tab_width - close_btn_w));
         }
 
+        // Overflow button (opens a menu listing every tab)
+        let overflow_buffer = layout.overflows().then(|| {
+            let mut buf = Buffer::new(&mut self.font_system, metrics);
+            buf.set_size(
+                &mut self.font_system,
+                Some(layout.overflow_button_w),
+                Some(tab_height),
+            );
+            let attrs = default_attrs.clone().color(Color::rgb(fg.r, fg.g, fg.b));
+            buf.set_rich_text(
+                &mut self.font_system,
+                [(" »", attrs)],
+                &default_attrs,
+                Shaping::Advanced,
+                None,
+            );
+            buf.shape_until_scroll(&mut self.font_system, false);
+            (buf, layout.strip_width)
+        });
+
         self.tab_bar = Some(TabBar {
             tab_buffers,
+            overflow_buffer,
             height: tab_height,
+            y: bar_y,
             bg_rects,
+            layout,
+            active,
             content_hash: hash,
         });
     }
@@ -809,7 +1764,7 @@ impl TextRenderer {
         );
         buffer.shape_until_scroll(&mut self.font_system, false);
 
-        self.context_menu = Some(ContextMenuOverlay {
+        self.context_menu = Some(OverlayPanel {
             buffer,
             x: mx,
             y: my + y_pad,
@@ -823,6 +1778,250 @@ impl TextRenderer {
     pub fn clear_context_menu(&mut self) {
         self.context_menu = None;
     }
+
+    /// Show recently pressed keys in a box at a window corner. `font_size` is
+    /// in logical points; the text is only reshaped when something changed.
+    pub fn set_keystroke_overlay(&mut self, text: &str, font_size: f32, corner: OverlayCorner) {
+        let scale = self.scale_factor;
+        let font_size = font_size * scale;
+        let bottom = (self.height as f32 - self.status_bar_height()).max(0.0);
+        let key = KeystrokeOverlayKey {
+            text: text.to_string(),
+            font_size,
+            corner,
+            window: (self.width, bottom as u32),
+        };
+        if self
+            .keystrokes
+            .as_ref()
+            .is_some_and(|(_, built_from)| *built_from == key)
+        {
+            return;
+        }
+
+        let line_h = font_size * 1.4;
+        let pad = 10.0 * scale;
+        let margin = 16.0 * scale;
+
+        let metrics = Metrics::new(font_size, line_h);
+        let mut buffer = Buffer::new(&mut self.font_system, metrics);
+        buffer.set_size(&mut self.font_system, None, Some(line_h));
+        let attrs = Attrs::new()
+            .family(Family::Monospace)
+            .color(Color::rgb(0xff, 0xff, 0xff));
+        buffer.set_text(&mut self.font_system, text, &attrs, Shaping::Advanced, None);
+        buffer.shape_until_scroll(&mut self.font_system, false);
+        let text_w = buffer
+            .layout_runs()
+            .map(|run| run.line_w)
+            .fold(0.0f32, f32::max);
+
+        let w = (text_w + pad * 2.0).min(self.width as f32 - margin * 2.0);
+        let h = line_h + pad * 2.0;
+        let x = match corner {
+            OverlayCorner::TopLeft | OverlayCorner::BottomLeft => margin,
+            OverlayCorner::TopRight | OverlayCorner::BottomRight => self.width as f32 - margin - w,
+        };
+        let y = match corner {
+            OverlayCorner::TopLeft | OverlayCorner::TopRight => margin,
+            OverlayCorner::BottomLeft | OverlayCorner::BottomRight => bottom - margin - h,
+        };
+        let bg_rects = vec![crate::bg::BgRect {
+            x,
+            y,
+            w,
+            h,
+            color: [0.0, 0.0, 0.0, 0.75],
+        }];
+
+        self.keystrokes = Some((
+            OverlayPanel {
+                buffer,
+                x: x + pad,
+                y: y + pad,
+                w: w - pad * 2.0,
+                h: line_h,
+                bg_rects,
+            },
+            key,
+        ));
+    }
+
+    /// Hide the keystroke overlay
+    pub fn clear_keystroke_overlay(&mut self) {
+        self.keystrokes = None;
+    }
+
+    /// Show the key binding cheat sheet in a box in the middle of the
+    /// window. `font_size` is in logical points; lines that don't fit are
+    /// cut off.
+    pub fn set_cheat_sheet_overlay(&mut self, text: &str, font_size: f32) {
+        let scale = self.scale_factor;
+        let font_size = font_size * scale;
+        let key = CheatSheetKey {
+            text: text.to_string(),
+            font_size,
+            window: (self.width, self.height),
+        };
+        if self
+            .cheat_sheet
+            .as_ref()
+            .is_some_and(|(_, built_from)| *built_from == key)
+        {
+            return;
+        }
+
+        let line_h = font_size * 1.4;
+        let pad = 16.0 * scale;
+        let margin = 32.0 * scale;
+        let max_w = (self.width as f32 - (margin + pad) * 2.0).max(0.0);
+        let max_h = (self.height as f32 - (margin + pad) * 2.0).max(0.0);
+
+        let metrics = Metrics::new(font_size, line_h);
+        let mut buffer = Buffer::new(&mut self.font_system, metrics);
+        buffer.set_size(&mut self.font_system, None, Some(max_h));
+        let attrs = Attrs::new()
+            .family(Family::Monospace)
+            .color(Color::rgb(0xff, 0xff, 0xff));
+        buffer.set_text(&mut self.font_system, text, &attrs, Shaping::Advanced, None);
+        buffer.shape_until_scroll(&mut self.font_system, false);
+        let (text_w, lines) = buffer
+            .layout_runs()
+            .fold((0.0f32, 0usize), |(w, lines), run| {
+                (w.max(run.line_w), lines + 1)
+            });
+
+        let w = text_w.min(max_w) + pad * 2.0;
+        let h = (lines as f32 * line_h).min(max_h) + pad * 2.0;
+        let x = ((self.width as f32 - w) / 2.0).max(0.0);
+        let y = ((self.height as f32 - h) / 2.0).max(0.0);
+        let bg_rects = vec![crate::bg::BgRect {
+            x,
+            y,
+            w,
+            h,
+            color: [0.0, 0.0, 0.0, 0.85],
+        }];
+
+        self.cheat_sheet = Some((
+            OverlayPanel {
+                buffer,
+                x: x + pad,
+                y: y + pad,
+                w: w - pad * 2.0,
+                h: h - pad * 2.0,
+                bg_rects,
+            },
+            key,
+        ));
+    }
+
+    /// Hide the cheat sheet overlay
+    pub fn clear_cheat_sheet_overlay(&mut self) {
+        self.cheat_sheet = None;
+    }
+
+    /// Show `segments` left to right in a bar along the bottom edge.
+    /// `font_size` is in logical points; returns whether the bar changed.
+    pub fn set_status_bar(
+        &mut self,
+        segments: &[String],
+        font_size: f32,
+        fg: RgbColor,
+        bg: RgbColor,
+    ) -> bool {
+        let scale = self.scale_factor;
+        let font_size = font_size * scale;
+        let key = StatusBarKey {
+            segments: segments.to_vec(),
+            font_size,
+            fg,
+            bg,
+            window: (self.width, self.height),
+        };
+        if self.status_bar.as_ref().is_some_and(|bar| bar.key == key) {
+            return false;
+        }
+
+        let h = font_size * 1.6;
+        let y = (self.height as f32 - h).max(0.0);
+        let pad = 8.0 * scale;
+        let gap = font_size * 1.5;
+        let metrics = Metrics::new(font_size, h);
+        let attrs = Attrs::new()
+            .family(Family::Monospace)
+            .color(Color::rgb(fg.r, fg.g, fg.b));
+        let to_color = |c: RgbColor, alpha: f32| {
+            [
+                c.r as f32 / 255.0,
+                c.g as f32 / 255.0,
+                c.b as f32 / 255.0,
+                alpha,
+            ]
+        };
+        let mut bg_rects = vec![crate::bg::BgRect {
+            x: 0.0,
+            y,
+            w: self.width as f32,
+            h,
+            color: to_color(bg, 1.0),
+        }];
+
+        let mut x = pad;
+        let mut laid_out = Vec::with_capacity(segments.len());
+        for text in segments {
+            let mut buffer = Buffer::new(&mut self.font_system, metrics);
+            buffer.set_size(&mut self.font_system, None, Some(h));
+            buffer.set_text(&mut self.font_system, text, &attrs, Shaping::Advanced, None);
+            buffer.shape_until_scroll(&mut self.font_system, false);
+            let w = buffer
+                .layout_runs()
+                .map(|run| run.line_w)
+                .fold(0.0f32, f32::max);
+            if !laid_out.is_empty() {
+                // Separator halfway into the gap before the segment
+                bg_rects.push(crate::bg::BgRect {
+                    x: x - gap / 2.0,
+                    y: y + h * 0.25,
+                    w: scale,
+                    h: h * 0.5,
+                    color: to_color(fg, 0.3),
+                });
+            }
+            laid_out.push((buffer, x, w));
+            x += w + gap;
+        }
+
+        self.status_bar = Some(StatusBar {
+            segments: laid_out,
+            y,
+            h,
+            bg_rects,
+            key,
+        });
+        true
+    }
+
+    /// Hide the status bar; returns whether it was shown
+    pub fn clear_status_bar(&mut self) -> bool {
+        self.status_bar.take().is_some()
+    }
+
+    /// Height of the status bar in physical pixels (0 if hidden)
+    pub fn status_bar_height(&self) -> f32 {
+        self.status_bar.as_ref().map_or(0.0, |bar| bar.h)
+    }
+
+    /// Index of the status bar segment at a point (physical pixels)
+    pub fn status_bar_segment_at(&self, x: f32, y: f32) -> Option<usize> {
+        let bar = self.status_bar.as_ref()?;
+        if y < bar.y || y >= bar.y + bar.h {
+            return None;
+        }
+        bar.segments
+            .iter()
+            .position(|(_, left, w)| x >= *left && x < left + w)
+    }
 }
 
 /// Update line buffer without hash computation - relies on native damage tracking
@@ -832,6 +2031,7 @@ fn update_line_buffer_no_hash(
     row_idx: usize,
     line: &GridLine,
     default_attrs: &Attrs<'static>,
+    styles: &FaceStyles,
 ) {
     // Increment generation to mark this line as updated
     pb.generation = pb.generation.wrapping_add(1);
@@ -841,6 +2041,19 @@ fn update_line_buffer_no_hash(
     let text = &mut pb.scratch_text;
     let spans = &mut pb.scratch_spans;
     let line_info = build_line_rich_text_into(line, text, spans);
+    // A row already in visual order must not be reordered again by the
+    // shaper: a left-to-right mark and override keep it as given
+    let reordered = pb.visual.get(row_idx).is_some_and(Option::is_some);
+    if reordered {
+        text.insert_str(0, LTR_OVERRIDE);
+        for span in spans.iter_mut() {
+            span.start += LTR_OVERRIDE.len();
+            span.end += LTR_OVERRIDE.len();
+        }
+        if let Some(first) = spans.first_mut() {
+            first.start = 0;
+        }
+    }
 
     let lb = &mut pb.lines[row_idx];
     lb.generation = current_gen;
@@ -850,22 +2063,19 @@ fn update_line_buffer_no_hash(
         return;
     }
 
-    let shaping = if line_info.all_ascii {
+    let shaping = if line_info.all_ascii && !reordered {
         Shaping::Basic
     } else {
         Shaping::Advanced
     };
 
     lb.is_blank = false;
+    let alpha = (pb.text_opacity * 255.0).round() as u8;
     if spans.len() == 1 {
         let span = &spans[0];
-        let mut attrs = default_attrs.clone().color(Color::rgb(span.fg.r, span.fg.g, span.fg.b));
-        if span.bold {
-            attrs = attrs.weight(Weight::BOLD);
-        }
-        if span.italic {
-            attrs = attrs.style(Style::Italic);
-        }
+        let color = Color::rgba(span.fg.r, span.fg.g, span.fg.b, alpha);
+        let attrs = default_attrs.clone().color(color);
+        let attrs = styled_attrs(styles, attrs, span.bold, span.italic);
         let slice = &text[span.start..span.end];
         lb.buffer
             .set_rich_text(font_system, [(slice, attrs)], default_attrs, shaping, None);
@@ -874,14 +2084,9 @@ fn update_line_buffer_no_hash(
             .iter()
             .map(|span| {
                 let slice = &text[span.start..span.end];
-                let mut attrs = default_attrs.clone().color(Color::rgb(span.fg.r, span.fg.g, span.fg.b));
-                if span.bold {
-                    attrs = attrs.weight(Weight::BOLD);
-                }
-                if span.italic {
-                    attrs = attrs.style(Style::Italic);
-                }
-                (slice, attrs)
+                let color = Color::rgba(span.fg.r, span.fg.g, span.fg.b, alpha);
+                let attrs = default_attrs.clone().color(color);
+                (slice, styled_attrs(styles, attrs, span.bold, span.italic))
             })
             .collect();
         lb.buffer
@@ -890,6 +2095,65 @@ fn update_line_buffer_no_hash(
     lb.buffer.shape_until_scroll(font_system, false);
 }
 
+/// Where the cursor is drawn (cell coordinates) `lag` of the animation
+/// behind `now`, easing out towards its cell `to`.
+fn cursor_cell(
+    motion: Option<&CursorMotion>,
+    to: (u16, u16),
+    animation: CursorAnimation,
+    lag: f32,
+    now: Instant,
+) -> (f32, f32) {
+    let to = (to.0 as f32, to.1 as f32);
+    let Some(motion) = motion else {
+        return to;
+    };
+    let elapsed = now.saturating_duration_since(motion.started).as_secs_f32();
+    let t = (elapsed / animation.duration.as_secs_f32().max(f32::EPSILON) - lag).clamp(0.0, 1.0);
+    let eased = 1.0 - (1.0 - t).powi(3);
+    (
+        motion.from.0 + (to.0 - motion.from.0) * eased,
+        motion.from.1 + (to.1 - motion.from.1) * eased,
+    )
+}
+
+/// Rows a pane's content is drawn away from its resting position at `now`
+fn scroll_rows(motion: Option<&ScrollMotion>, animation: ScrollAnimation, now: Instant) -> f32 {
+    let Some(motion) = motion else {
+        return 0.0;
+    };
+    let elapsed = now.saturating_duration_since(motion.started).as_secs_f32();
+    let t = (elapsed / animation.duration.as_secs_f32().max(f32::EPSILON)).clamp(0.0, 1.0);
+    motion.from * (1.0 - animation.easing.apply(t))
+}
+
+/// Push a bg rect clipped to the pane bounds (no-wrap panes can have spans
+/// that start or end outside the visible area, and scrolling panes rows).
+fn push_clipped_rect(
+    rects: &mut Vec<crate::bg::BgRect>,
+    pane: &PixelRect,
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+    color: [f32; 4],
+) {
+    let left = x.max(pane.x);
+    let right = (x + w).min(pane.x + pane.w);
+    let top = y.max(pane.y);
+    let bottom = (y + h).min(pane.y + pane.h);
+    if right <= left || bottom <= top {
+        return;
+    }
+    rects.push(crate::bg::BgRect {
+        x: left,
+        y: top,
+        w: right - left,
+        h: bottom - top,
+        color,
+    });
+}
+
 fn rgb_to_rgba(color: RgbColor) -> [f32; 4] {
     [
         color.r as f32 / 255.0,
@@ -950,6 +2214,7 @@ fn emit_bg_spans_for_row(out: &mut Vec<BgSpan>, line: &GridLine, row_idx: usize,
 fn rebuild_selection_bg_spans(
     out: &mut Vec<BgSpan>,
     grid: &[GridLine],
+    visual: &[Option<Visual>],
     selection: Option<((u16, u16), (u16, u16))>,
     selection_bg: RgbColor,
 ) {
@@ -979,6 +2244,19 @@ fn rebuild_selection_bg_spans(
             continue;
         }
 
+        // A reordered row can show the selected cells in several places
+        if let Some(Some(visual)) = visual.get(row as usize) {
+            for (col, width) in visual.runs(col_start, clamped_end - 1) {
+                out.push(BgSpan {
+                    col,
+                    row,
+                    width,
+                    color,
+                });
+            }
+            continue;
+        }
+
         out.push(BgSpan {
             col: col_start,
             row,
@@ -1,7 +1,7 @@
 use anyhow::{anyhow, Result};
 use pterminal_plugin_api::{
-    CommandContribution, Contributions, PaneContentSnapshot, PaneStateSnapshot, SidebarViewContribution,
-    TabTypeContribution, TerminalTopology,
+    CommandContribution, Contributions, PaneContentSnapshot, PaneStateSnapshot,
+    SidebarViewContribution, StatusSegmentContribution, TabTypeContribution, TerminalTopology,
 };
 use pterminal_plugin_host::{
     HostRequest, HostRequestPayload, HostResponse, HostResponsePayload, PluginHostRuntime,
@@ -64,6 +64,16 @@ impl PluginContext {
         });
     }
 
+    pub fn register_status_segment(&mut self, id: impl Into<String>, text: impl Into<String>) {
+        self.contributes
+            .status_segments
+            .push(StatusSegmentContribution {
+                id: id.into(),
+                text: text.into(),
+                run: None,
+            });
+    }
+
     pub fn contributions(&self) -> &Contributions {
         &self.contributes
     }
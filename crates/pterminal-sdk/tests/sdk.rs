@@ -6,11 +6,13 @@ fn plugin_context_collects_contributions() {
     ctx.register_command("acme.workspace.focus", "Focus Workspace");
     ctx.register_sidebar_view("acme.workspace.tree", "Workspaces", 100);
     ctx.register_tab_type("acme.browser", "Browser");
+    ctx.register_status_segment("acme.workspace.count", "1 ws");
 
     let contributes = ctx.contributions();
     assert_eq!(contributes.commands.len(), 1);
     assert_eq!(contributes.sidebar_views.len(), 1);
     assert_eq!(contributes.tab_types.len(), 1);
+    assert_eq!(contributes.status_segments.len(), 1);
 }
 
 #[test]
@@ -0,0 +1,153 @@
+//! The cases, grouped like vttest's menus. Expectations follow xterm and
+//! the DEC VT manuals; screens are 10x4 unless set otherwise.
+
+use super::Case;
+
+pub const CASES: &[Case] = &[
+    // Cursor movement
+    Case::new("CUP", "moves to row;column", "\x1b[2;3HX")
+        .screen(&["", "  X"])
+        .cursor(3, 1),
+    Case::new("CUP", "defaults to home", "\x1b[3;3H\x1b[H").cursor(0, 0),
+    Case::new(
+        "CUU/CUD/CUF/CUB",
+        "relative moves",
+        "\x1b[3;5H\x1b[2A\x1b[B\x1b[3C\x1b[2D",
+    )
+    .cursor(5, 1),
+    Case::new("CUU", "stops at the top", "\x1b[2;1H\x1b[9A").cursor(0, 0),
+    Case::new("CUF", "stops at the right margin", "\x1b[99C").cursor(9, 0),
+    Case::new("CHA", "moves to a column", "\x1b[2;2H\x1b[7G").cursor(6, 1),
+    Case::new("VPA", "moves to a row", "\x1b[1;4H\x1b[3d").cursor(3, 2),
+    Case::new("HVP", "moves like CUP", "\x1b[2;2f").cursor(1, 1),
+    Case::new("CNL", "next line, first column", "\x1b[2;5H\x1b[E").cursor(0, 2),
+    Case::new("CPL", "previous line, first column", "\x1b[3;5H\x1b[2F").cursor(0, 0),
+    Case::new("BS", "moves left, not past the margin", "ab\x08\x08\x08X").screen(&["Xb"]),
+    Case::new("CR/LF", "return and line feed", "ab\r\ncd\nX").screen(&["ab", "cd", "  X"]),
+    // Tabs
+    Case::new("HT", "default stops every 8 columns", "a\tX")
+        .screen(&["a       X"])
+        .cursor(9, 0),
+    Case::new(
+        "HTS/TBC",
+        "sets a stop after clearing all",
+        "\x1b[3g\x1b[1;4H\x1bH\r\tX",
+    )
+    .screen(&["   X"]),
+    Case::new("CBT", "back to the previous stop", "\x1b[1;10H\x1b[Z").cursor(8, 0),
+    // Erasing
+    Case::new("ED", "erases below", "aaaa\r\nbbbb\r\ncccc\x1b[2;3H\x1b[J").screen(&["aaaa", "bb"]),
+    Case::new("ED", "erases above", "aaaa\r\nbbbb\r\ncccc\x1b[2;3H\x1b[1J")
+        .screen(&["", "   b", "cccc"]),
+    Case::new("ED", "erases all", "aaaa\r\nbbbb\r\ncccc\x1b[2J").screen(&[]),
+    Case::new("EL", "erases to the right", "abcdef\x1b[1;3H\x1b[K").screen(&["ab"]),
+    Case::new("EL", "erases to the left", "abcdef\x1b[1;3H\x1b[1K").screen(&["   def"]),
+    Case::new("EL", "erases the line", "abcdef\x1b[1;3H\x1b[2K").screen(&[]),
+    Case::new(
+        "ECH",
+        "erases characters in place",
+        "abcdef\x1b[1;2H\x1b[3X",
+    )
+    .screen(&["a   ef"])
+    .cursor(1, 0),
+    // Editing
+    Case::new("ICH", "inserts blanks", "abcdef\x1b[1;2H\x1b[2@").screen(&["a  bcdef"]),
+    Case::new("DCH", "deletes characters", "abcdef\x1b[1;2H\x1b[2P").screen(&["adef"]),
+    Case::new("IL", "inserts lines", "aaaa\r\nbbbb\r\ncccc\x1b[2;1H\x1b[L")
+        .screen(&["aaaa", "", "bbbb", "cccc"]),
+    Case::new("DL", "deletes lines", "aaaa\r\nbbbb\r\ncccc\x1b[1;1H\x1b[M")
+        .screen(&["bbbb", "cccc"]),
+    Case::new("REP", "repeats the last character", "x\x1b[3b").screen(&["xxxx"]),
+    Case::new(
+        "IRM",
+        "insert mode shifts text right",
+        "abc\r\x1b[4hX\x1b[4l",
+    )
+    .screen(&["Xabc"]),
+    Case::new("DECALN", "fills the screen with E", "\x1b#8").screen(&[
+        "EEEEEEEEEE",
+        "EEEEEEEEEE",
+        "EEEEEEEEEE",
+        "EEEEEEEEEE",
+    ]),
+    // Scrolling
+    Case::new(
+        "DECSTBM",
+        "line feed scrolls the region",
+        "1\r\n2\r\n3\r\n4\x1b[2;3r\x1b[3;1H\n",
+    )
+    .screen(&["1", "3", "", "4"]),
+    Case::new("DECSTBM", "moves the cursor home", "\x1b[2;3H\x1b[2;3r").cursor(0, 0),
+    Case::new("SU", "scrolls up", "1\r\n2\r\n3\x1b[S").screen(&["2", "3"]),
+    Case::new("SD", "scrolls down", "1\r\n2\x1b[T").screen(&["", "1", "2"]),
+    Case::new("IND", "index keeps the column", "ab\x1bDc").screen(&["ab", "  c"]),
+    Case::new("NEL", "next line", "ab\x1bEc").screen(&["ab", "c"]),
+    Case::new("RI", "reverse index scrolls at the top", "1\x1b[H\x1bM").screen(&["", "1"]),
+    // Modes
+    Case::new("DECAWM", "wraps at the margin", "abcdefghijkl").screen(&["abcdefghij", "kl"]),
+    Case::new(
+        "DECAWM",
+        "reset overwrites the last column",
+        "\x1b[?7labcdefghijkl",
+    )
+    .screen(&["abcdefghil"])
+    .cursor(9, 0),
+    Case::new(
+        "DECOM",
+        "origin is the region's top",
+        "\x1b[2;3r\x1b[?6h\x1b[HX\x1b[?6l",
+    )
+    .screen(&["", "X"]),
+    Case::new(
+        "DECSC/DECRC",
+        "saves and restores the cursor",
+        "\x1b[2;3H\x1b7\x1b[H\x1b8X",
+    )
+    .screen(&["", "  X"]),
+    Case::new(
+        "SCOSC/SCORC",
+        "saves and restores the cursor",
+        "\x1b[2;3H\x1b[s\x1b[H\x1b[uX",
+    )
+    .screen(&["", "  X"]),
+    Case::new(
+        "DECSET 1049",
+        "alternate screen is restored",
+        "main\x1b[?1049halt\x1b[?1049l",
+    )
+    .screen(&["main"])
+    .cursor(4, 0),
+    Case::new(
+        "LNM",
+        "line feed returns the carriage",
+        "a\x1b[20h\nb\x1b[20l",
+    )
+    .screen(&["a", "b"]),
+    // Character sets
+    Case::new("SCS", "DEC special graphics", "\x1b(0qxl\x1b(Bq").screen(&["─│┌q"]),
+    Case::new("Wide characters", "take two columns", "あb")
+        .screen(&["あb"])
+        .cursor(3, 0),
+    Case::new(
+        "Combining characters",
+        "join the previous cell",
+        "e\u{301}x",
+    )
+    .cursor(2, 0),
+    // Reports
+    Case::new("DA1", "primary device attributes", "\x1b[c").reply("\x1b[?"),
+    Case::new("DA2", "secondary device attributes", "\x1b[>c").reply("\x1b[>"),
+    Case::new("DSR", "operating status", "\x1b[5n").reply("\x1b[0n"),
+    Case::new("CPR", "cursor position report", "\x1b[2;3H\x1b[6n").reply("\x1b[2;3R"),
+    Case::new(
+        "DECXCPR",
+        "extended cursor position report",
+        "\x1b[2;3H\x1b[?6n",
+    )
+    .reply("\x1b[?2;3"),
+    Case::new("DECRQM", "reports autowrap set", "\x1b[?7$p").reply("\x1b[?7;1$y"),
+    Case::new("XTVERSION", "reports the terminal's name", "\x1b[>0q").reply("\x1bP>|"),
+    Case::new("XTWINOPS 18", "reports the text area size", "\x1b[18t").reply("\x1b[8;4;10t"),
+    Case::new("OSC 10", "reports the foreground color", "\x1b]10;?\x07").reply("\x1b]10;rgb:"),
+    Case::new("OSC 4", "reports a palette color", "\x1b]4;1;?\x07").reply("\x1b]4;1;rgb:"),
+];
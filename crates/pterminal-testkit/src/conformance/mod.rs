@@ -0,0 +1,164 @@
+//! Conformance runner: vttest/esctest-style cases fed to a fresh emulator
+//! each, checked against what a DEC/xterm terminal shows or answers, and
+//! summed up in a pass/fail report per feature.
+//!
+//! The report is checked in as a golden file, so a feature that starts
+//! working shows up in its diff and one that breaks fails the tests;
+//! `pterminal-cli conformance` prints it.
+
+mod cases;
+
+use std::fmt;
+
+use crate::Harness;
+
+pub use cases::CASES;
+
+/// One sequence and what a conforming terminal does with it. Only the
+/// expectations given are checked.
+#[derive(Debug, Clone, Copy)]
+pub struct Case {
+    /// Control function tested, e.g. "DECSTBM"
+    pub feature: &'static str,
+    pub name: &'static str,
+    /// Columns and rows
+    pub size: (u16, u16),
+    pub input: &'static str,
+    /// Rows from the top, trailing blanks trimmed; rows left out are blank
+    pub screen: Option<&'static [&'static str]>,
+    /// (column, row), 0-based
+    pub cursor: Option<(u16, u16)>,
+    /// What the reply to the program starts with
+    pub reply: Option<&'static str>,
+}
+
+impl Case {
+    /// A case on a 10x4 screen, expecting nothing yet
+    pub const fn new(feature: &'static str, name: &'static str, input: &'static str) -> Self {
+        Self {
+            feature,
+            name,
+            size: (10, 4),
+            input,
+            screen: None,
+            cursor: None,
+            reply: None,
+        }
+    }
+
+    pub const fn size(mut self, cols: u16, rows: u16) -> Self {
+        self.size = (cols, rows);
+        self
+    }
+
+    pub const fn screen(mut self, rows: &'static [&'static str]) -> Self {
+        self.screen = Some(rows);
+        self
+    }
+
+    pub const fn cursor(mut self, col: u16, row: u16) -> Self {
+        self.cursor = Some((col, row));
+        self
+    }
+
+    pub const fn reply(mut self, reply: &'static str) -> Self {
+        self.reply = Some(reply);
+        self
+    }
+
+    /// Run the case on a fresh emulator; `Err` says what differed
+    pub fn run(&self) -> Result<(), String> {
+        let mut term = Harness::new(self.size.0, self.size.1);
+        term.feed(self.input);
+        let mut failures = Vec::new();
+        if let Some(expected) = self.reply {
+            let reply = String::from_utf8_lossy(&term.replies()).into_owned();
+            if !reply.starts_with(expected) {
+                failures.push(format!("reply {reply:?}, expected {expected:?}"));
+            }
+        }
+        let snapshot = term.snapshot();
+        if let Some(expected) = self.screen {
+            for (row, text) in snapshot.rows.iter().enumerate() {
+                let expected = expected.get(row).copied().unwrap_or("");
+                if text != expected {
+                    failures.push(format!("row {row} {text:?}, expected {expected:?}"));
+                }
+            }
+        }
+        if let Some(expected) = self.cursor {
+            if snapshot.cursor != expected {
+                failures.push(format!(
+                    "cursor at {:?}, expected {expected:?}",
+                    snapshot.cursor
+                ));
+            }
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures.join("; "))
+        }
+    }
+}
+
+/// Outcome of every case run
+pub struct Report {
+    pub results: Vec<(Case, Result<(), String>)>,
+}
+
+impl Report {
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|(_, r)| r.is_ok()).count()
+    }
+
+    /// Features with every case passing, and with at least one failing
+    pub fn features(&self) -> (Vec<&'static str>, Vec<&'static str>) {
+        let mut supported: Vec<&'static str> = Vec::new();
+        let mut unsupported: Vec<&'static str> = Vec::new();
+        for (case, result) in &self.results {
+            let feature = case.feature;
+            if result.is_err() {
+                supported.retain(|&f| f != feature);
+                if !unsupported.contains(&feature) {
+                    unsupported.push(feature);
+                }
+            } else if !supported.contains(&feature) && !unsupported.contains(&feature) {
+                supported.push(feature);
+            }
+        }
+        (supported, unsupported)
+    }
+}
+
+/// Run `cases`, each on its own emulator
+pub fn run(cases: &[Case]) -> Report {
+    let results = cases.iter().map(|case| (*case, case.run())).collect();
+    Report { results }
+}
+
+/// A Markdown table of the cases, then the features lacking support
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "# Conformance: {}/{} cases pass\n",
+            self.passed(),
+            self.results.len()
+        )?;
+        writeln!(f, "| Feature | Case | Result |")?;
+        writeln!(f, "|---|---|---|")?;
+        for (case, result) in &self.results {
+            let result = match result {
+                Ok(()) => "pass".to_string(),
+                Err(why) => format!("FAIL: {}", why.replace('|', "\\|")),
+            };
+            writeln!(f, "| {} | {} | {result} |", case.feature, case.name)?;
+        }
+        let (_, unsupported) = self.features();
+        if !unsupported.is_empty() {
+            writeln!(f, "\nNot fully supported: {}", unsupported.join(", "))?;
+        }
+        Ok(())
+    }
+}
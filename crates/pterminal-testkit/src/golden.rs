@@ -0,0 +1,51 @@
+//! Golden files: expected output checked in next to the tests. When a
+//! change to the output is intended, run the tests with
+//! `PTERMINAL_UPDATE_GOLDEN=1` to rewrite them, then review the diff.
+
+use std::path::Path;
+
+/// Set to rewrite golden files instead of comparing against them
+pub const UPDATE_ENV: &str = "PTERMINAL_UPDATE_GOLDEN";
+
+/// Compare `actual` with the golden file at `path`, or rewrite it when
+/// [`UPDATE_ENV`] is set
+#[track_caller]
+pub fn check(path: impl AsRef<Path>, actual: &str) {
+    let path = path.as_ref();
+    if std::env::var_os(UPDATE_ENV).is_some() {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        if let Err(e) = std::fs::write(path, actual) {
+            panic!("failed to write {}: {e}", path.display());
+        }
+        return;
+    }
+    let expected = match std::fs::read_to_string(path) {
+        Ok(expected) => expected,
+        Err(e) => panic!(
+            "failed to read {}: {e}\n(run with {UPDATE_ENV}=1 to create it)",
+            path.display()
+        ),
+    };
+    if expected != actual {
+        panic!(
+            "output differs from {}\n--- expected\n{expected}--- actual\n{actual}\
+             (run with {UPDATE_ENV}=1 if the change is intended)",
+            path.display()
+        );
+    }
+}
+
+/// Check against `tests/golden/<name>` in the calling crate
+#[macro_export]
+macro_rules! assert_golden {
+    ($name:expr, $actual:expr) => {
+        $crate::golden::check(
+            ::std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/golden")
+                .join($name),
+            &$actual,
+        )
+    };
+}
@@ -0,0 +1,110 @@
+use std::path::Path;
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+
+use pterminal_core::config::theme::Theme;
+use pterminal_core::terminal::{GridDelta, GridLine, TerminalEmulator, TerminalEmulatorHandle};
+
+use crate::snapshot::Snapshot;
+use crate::typescript;
+
+/// A terminal emulator wired to a fake PTY.
+///
+/// Output is fed through the same parser handle the PTY reader thread
+/// uses, and every query goes through the parser thread after the output
+/// fed before it, so assertions never race the parser.
+pub struct Harness {
+    emulator: TerminalEmulator,
+    pty: TerminalEmulatorHandle,
+    replies: Receiver<Vec<u8>>,
+    theme: Arc<Theme>,
+    /// Grid kept up to date by [`Self::delta`]
+    grid: Vec<GridLine>,
+}
+
+impl Harness {
+    pub fn new(cols: u16, rows: u16) -> Self {
+        let mut emulator = TerminalEmulator::new(cols, rows);
+        let mut pty = emulator
+            .take_parser_handle()
+            .expect("a new emulator has its parser handle");
+        let replies = pty
+            .take_reply_receiver()
+            .expect("a new parser handle has its reply receiver");
+        Self {
+            emulator,
+            pty,
+            replies,
+            theme: Arc::new(Theme::default()),
+            grid: Vec::new(),
+        }
+    }
+
+    /// Resolve colors with `theme` instead of the default one
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = Arc::new(theme);
+        self
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// The emulator, for anything the harness doesn't wrap
+    pub fn emulator(&self) -> &TerminalEmulator {
+        &self.emulator
+    }
+
+    /// Feed program output, as one PTY read
+    pub fn feed(&mut self, bytes: impl AsRef<[u8]>) -> &mut Self {
+        self.pty.process(bytes.as_ref());
+        self
+    }
+
+    /// Feed program output in reads of at most `chunk` bytes, to check that
+    /// state carries across reads split mid-sequence
+    pub fn feed_chunked(&mut self, bytes: impl AsRef<[u8]>, chunk: usize) -> &mut Self {
+        for part in bytes.as_ref().chunks(chunk.max(1)) {
+            self.pty.process(part);
+        }
+        self
+    }
+
+    /// Feed a recorded session; `script(1)` typescripts lose their header
+    /// and trailer
+    pub fn feed_file(&mut self, path: impl AsRef<Path>) -> std::io::Result<&mut Self> {
+        let recording = std::fs::read(path)?;
+        Ok(self.feed(typescript::output(&recording)))
+    }
+
+    pub fn resize(&mut self, cols: u16, rows: u16) -> &mut Self {
+        self.emulator.resize(cols, rows);
+        self
+    }
+
+    /// Bytes the terminal wrote back to the program since the last call,
+    /// such as answers to status and color queries
+    pub fn replies(&mut self) -> Vec<u8> {
+        // Once the parser answers, everything fed before has been parsed
+        let _ = self.emulator.size();
+        self.replies.try_iter().flatten().collect()
+    }
+
+    /// The screen as text, with the cursor
+    pub fn snapshot(&self) -> Snapshot {
+        let grid = self.emulator.extract_grid(&self.theme);
+        Snapshot::new(&grid, self.emulator.cursor_position())
+    }
+
+    /// The visible grid, colors resolved with the harness theme
+    pub fn grid(&self) -> Vec<GridLine> {
+        self.emulator.extract_grid(&self.theme)
+    }
+
+    /// Rows changed since the previous call (all of them the first time),
+    /// as the renderer sees them
+    pub fn delta(&mut self) -> GridDelta {
+        self.emulator
+            .extract_grid_delta_into(&self.theme, &mut self.grid)
+    }
+}
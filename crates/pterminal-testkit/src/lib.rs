@@ -0,0 +1,28 @@
+//! Headless harness for emulator and renderer regression tests.
+//!
+//! A [`Harness`] runs a [`TerminalEmulator`](pterminal_core::terminal::TerminalEmulator)
+//! behind a fake PTY: bytes fed to it play the part of a program's output
+//! (scripted, or recorded with `script(1)`, e.g. a vttest session), and the
+//! replies the terminal writes back are collected. Tests then assert on
+//! screen [`Snapshot`]s, on the rows a change dirtied, and, with the
+//! `render` feature, on the background and text spans the renderer would
+//! draw. Expected output lives in golden files next to the tests:
+//!
+//! ```no_run
+//! use pterminal_testkit::{assert_golden, Harness};
+//!
+//! let mut term = Harness::new(20, 4);
+//! term.feed("\x1b[1mbold\x1b[0m\r\nplain");
+//! assert_golden!("bold.txt", term.snapshot().to_string());
+//! ```
+
+pub mod conformance;
+pub mod golden;
+mod harness;
+#[cfg(feature = "render")]
+pub mod render;
+mod snapshot;
+pub mod typescript;
+
+pub use harness::Harness;
+pub use snapshot::Snapshot;
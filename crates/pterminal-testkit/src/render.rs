@@ -0,0 +1,56 @@
+//! The spans the renderer generates for a grid, as text for golden files:
+//! background runs and the style runs of each line's shaped text.
+
+use std::fmt::Write;
+
+use pterminal_core::config::theme::{RgbColor, Theme};
+use pterminal_core::terminal::GridLine;
+use pterminal_render::text::{bg_spans, text_spans};
+
+/// One block per row with something to draw; blank rows are left out.
+///
+/// ```text
+/// row 0 "ls -l"
+///   text 0..2 #50fa7b bold
+///   text 2..5 #f8f8f2
+///   bg 0..2 #44475a
+/// ```
+pub fn spans(grid: &[GridLine], theme: &Theme) -> String {
+    let default_bg = theme.colors.background;
+    let backgrounds = bg_spans(grid, default_bg);
+    let mut out = String::new();
+    for (row, line) in grid.iter().enumerate() {
+        let (text, runs) = text_spans(line);
+        let row_bgs: Vec<_> = backgrounds
+            .iter()
+            .filter(|span| span.row as usize == row)
+            .collect();
+        if text.trim().is_empty() && row_bgs.is_empty() {
+            continue;
+        }
+        let _ = writeln!(out, "row {row} {:?}", text.trim_end());
+        for run in runs
+            .iter()
+            .filter(|run| !text[run.start..run.end].trim().is_empty())
+        {
+            let _ = write!(out, "  text {}..{} {}", run.start, run.end, hex(run.fg));
+            if run.bold {
+                out.push_str(" bold");
+            }
+            if run.italic {
+                out.push_str(" italic");
+            }
+            out.push('\n');
+        }
+        for span in row_bgs {
+            let [r, g, b, _] = span.color.map(|c| (c * 255.0).round() as u8);
+            let (start, end) = (span.col, span.col + span.width);
+            let _ = writeln!(out, "  bg {start}..{end} {}", hex(RgbColor::new(r, g, b)));
+        }
+    }
+    out
+}
+
+fn hex(color: RgbColor) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
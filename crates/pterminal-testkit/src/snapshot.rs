@@ -0,0 +1,50 @@
+use std::fmt;
+
+use pterminal_core::terminal::GridLine;
+
+/// The text on screen and the cursor position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// One entry per row, trailing blanks trimmed
+    pub rows: Vec<String>,
+    /// (column, row)
+    pub cursor: (u16, u16),
+}
+
+impl Snapshot {
+    pub fn new(grid: &[GridLine], cursor: (u16, u16)) -> Self {
+        let rows = grid
+            .iter()
+            .map(|line| {
+                let text: String = line
+                    .cells
+                    .iter()
+                    .filter(|cell| !cell.wide_spacer)
+                    // Cells a tab moved over keep the tab, for copying
+                    .map(|cell| match cell.c {
+                        '\0' | '\t' => ' ',
+                        c => c,
+                    })
+                    .collect();
+                text.trim_end().to_string()
+            })
+            .collect();
+        Self { rows, cursor }
+    }
+
+    /// Text of `row`, empty past the last row
+    pub fn row(&self, row: usize) -> &str {
+        self.rows.get(row).map_or("", String::as_str)
+    }
+}
+
+/// Rows between bars, so indentation and empty rows stay visible in
+/// golden files, then the cursor
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in &self.rows {
+            writeln!(f, "|{row}|")?;
+        }
+        writeln!(f, "cursor: {},{}", self.cursor.0, self.cursor.1)
+    }
+}
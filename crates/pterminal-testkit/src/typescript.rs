@@ -0,0 +1,36 @@
+//! Sessions recorded with util-linux `script(1)`. The raw output sits
+//! between a `Script started on ...` header line and a `Script done on ...`
+//! trailer that `script` adds on its own line.
+
+const HEADER: &[u8] = b"Script started on ";
+const TRAILER: &[u8] = b"\nScript done on ";
+
+/// The program output in a recording. Anything without the header is
+/// taken to be raw output already.
+pub fn output(recording: &[u8]) -> &[u8] {
+    if !recording.starts_with(HEADER) {
+        return recording;
+    }
+    let Some(start) = recording.iter().position(|&b| b == b'\n') else {
+        return &[];
+    };
+    let output = &recording[start + 1..];
+    let end = output
+        .windows(TRAILER.len())
+        .rposition(|window| window == TRAILER)
+        .unwrap_or(output.len());
+    &output[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_header_and_trailer() {
+        let recording = b"Script started on 2026-01-05 10:00:00+00:00 [COMMAND=\"ls\"]\n\
+            a  b\r\n\nScript done on 2026-01-05 10:00:01+00:00 [COMMAND_EXIT_CODE=\"0\"]\n";
+        assert_eq!(output(recording), b"a  b\r\n");
+        assert_eq!(output(b"raw\r\n"), b"raw\r\n");
+    }
+}
@@ -0,0 +1,7 @@
+use pterminal_testkit::{assert_golden, conformance};
+
+#[test]
+fn conformance_report() {
+    let report = conformance::run(conformance::CASES);
+    assert_golden!("conformance.md", report.to_string());
+}
@@ -0,0 +1,62 @@
+use pterminal_testkit::{assert_golden, Harness};
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+#[test]
+fn wraps_and_scrolls() {
+    let mut term = Harness::new(10, 3);
+    term.feed("one\r\ntwo\r\nthree\r\nfour is long");
+    assert_golden!("wraps_and_scrolls.txt", term.snapshot().to_string());
+}
+
+#[test]
+fn answers_status_reports() {
+    let mut term = Harness::new(20, 4);
+    term.feed("\x1b[5n");
+    assert_eq!(term.replies(), b"\x1b[0n");
+    term.feed("ab\x1b[6n");
+    assert_eq!(term.replies(), b"\x1b[1;3R");
+    assert!(term.replies().is_empty());
+}
+
+#[test]
+fn deltas_mark_only_changed_rows() {
+    let mut term = Harness::new(10, 4);
+    assert!(term.delta().full);
+    term.feed("\x1b[3;2Hx");
+    let delta = term.delta();
+    assert!(!delta.full);
+    // The row the cursor left and the one written to
+    assert_eq!(delta.dirty_rows, vec![0, 2]);
+    // With nothing new, only the cursor's row, which is always redrawn
+    assert_eq!(term.delta().dirty_rows, vec![2]);
+}
+
+#[test]
+fn state_carries_across_split_reads() {
+    let output = "\x1b[1;31mred\x1b[0m \x1b]0;title\x07é\x1b[2;4Hmoved";
+    let mut whole = Harness::new(12, 3);
+    whole.feed(output);
+    let mut split = Harness::new(12, 3);
+    split.feed_chunked(output, 1);
+    assert_eq!(split.snapshot(), whole.snapshot());
+    assert_eq!(split.snapshot().row(1), "   moved");
+}
+
+#[test]
+fn replays_typescript() {
+    let mut term = Harness::new(40, 3);
+    term.feed_file(format!("{FIXTURES}/ls-color.typescript"))
+        .unwrap();
+    assert_golden!("ls_color.txt", term.snapshot().to_string());
+}
+
+#[cfg(feature = "render")]
+#[test]
+fn renderer_spans_for_typescript() {
+    let mut term = Harness::new(40, 3);
+    term.feed_file(format!("{FIXTURES}/ls-color.typescript"))
+        .unwrap();
+    let spans = pterminal_testkit::render::spans(&term.grid(), term.theme());
+    assert_golden!("ls_color_spans.txt", spans);
+}
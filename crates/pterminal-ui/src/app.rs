@@ -15,16 +15,28 @@ use winit::application::ApplicationHandler;
 use winit::event::{ElementState, MouseButton, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, EventLoop};
 use winit::keyboard::{Key, KeyCode, ModifiersState, NamedKey, PhysicalKey};
-use winit::window::{Window, WindowAttributes, WindowId};
+use winit::window::{CursorIcon, Window, WindowAttributes, WindowId};
 
 use pterminal_core::config::theme::{RgbColor, Theme};
+use pterminal_core::config::SelectionConfig;
+use pterminal_core::link::{self, Link};
+use pterminal_core::pane_log::{self, LogOptions, PaneLog};
+use pterminal_core::process_tree::{self, Signal};
+use pterminal_core::scale;
+use pterminal_core::shell_words::ShellKind;
 use pterminal_core::split::{PaneId, SplitDirection};
-use pterminal_core::terminal::{PtyHandle, TerminalEmulator};
+use pterminal_core::ssh::SshTarget;
+use pterminal_core::terminal::{
+    encode_key, InputModes, KeyMods, KeypadKey, PtyHandle, SpecialKey, TerminalEmulator,
+};
 use pterminal_core::workspace::WorkspaceManager;
 use pterminal_core::{Config, NotificationStore};
 use pterminal_ipc::{IpcServer, JsonRpcRequest, JsonRpcResponse};
-use pterminal_render::text::PixelRect;
-use pterminal_render::Renderer;
+use pterminal_render::text::{LinkUnderline, PixelRect};
+use pterminal_render::{Renderer, TabBarPosition, TabHit};
+
+use crate::context_menu::ContextAction;
+use crate::file_drop::{self, drop_text, paste_text, start_upload, DropChoice, UploadResult};
 
 /// Minimum frame interval for rate limiting (8ms ≈ 120fps max)
 const MIN_FRAME_INTERVAL_MS: u64 = 8;
@@ -58,6 +70,8 @@ struct PaneState {
     render_dirty_rows: Vec<usize>,
     /// Last cursor visible state used in rendering (for blink-only updates)
     last_cursor_visible: bool,
+    /// Whether the pane last reported itself focused (DECSET 1004)
+    focused: bool,
 }
 
 /// Main application state
@@ -73,12 +87,16 @@ struct RunningState {
     workspace_mgr: WorkspaceManager,
     pane_states: HashMap<PaneId, PaneState>,
     scale_factor: f64,
+    /// Inner pane padding from `window.padding` (logical pixels)
+    padding: f32,
     modifiers: ModifiersState,
     clipboard: Option<Clipboard>,
     // Mouse selection
     selection: Option<Selection>,
     mouse_pressed: bool,
     last_mouse_pos: (f64, f64), // as reported by CursorMoved (may need scaling)
+    /// Link under the pointer: its pane, viewport row and span
+    hovered_link: Option<(PaneId, u16, Link)>,
     // Click counting for double/triple click
     last_click_time: Instant,
     last_click_pos: (u16, u16),
@@ -102,6 +120,22 @@ struct RunningState {
     last_render_time: Instant,
     /// Pending input events to process before rendering (Strategy 3)
     pending_input_events: u32,
+    /// Whether the window has keyboard focus
+    window_focused: bool,
+    /// Files dropped onto the window, handled together once events settle
+    dropped_files: Vec<PathBuf>,
+    /// Dropped files waiting on the upload menu of an ssh pane
+    file_drop: Option<PendingDrop>,
+    upload_tx: Sender<UploadResult>,
+    upload_rx: Receiver<UploadResult>,
+    /// Labels of the entries in an open process menu
+    process_labels: HashMap<u32, String>,
+}
+
+struct PendingDrop {
+    pane_id: PaneId,
+    files: Vec<PathBuf>,
+    target: SshTarget,
 }
 
 /// Right-click context menu
@@ -111,10 +145,25 @@ struct ContextMenu {
     items: Vec<ContextMenuItem>,
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, PartialEq)]
 enum ContextMenuItem {
     Copy,
     Paste,
+    /// Entry in the tab overflow menu
+    SelectTab(usize),
+    /// Step the clicked pane's font size (0 resets it)
+    FontSize(i8),
+    /// Link (true) or unlink the clicked pane's scrolling with other linked panes
+    LinkScroll(bool),
+    /// Action for files dropped onto an ssh pane
+    FileDrop(DropChoice),
+    /// List the clicked pane's process tree
+    Processes,
+    /// Entry in the process list: pick a signal for this pid
+    Process(u32),
+    SendSignal(u32, Signal),
+    /// Entry the Slint pane menu has too
+    Pane(ContextAction),
 }
 
 struct IpcEnvelope {
@@ -155,11 +204,17 @@ impl AppHandler {
         (state.last_mouse_pos.0 as f32, state.last_mouse_pos.1 as f32)
     }
 
+    /// Inner pane padding in physical pixels
+    fn padding_px(state: &RunningState) -> f32 {
+        state.padding * state.scale_factor as f32
+    }
+
     fn pane_pixel_rect(state: &RunningState, pane_id: PaneId) -> Option<PixelRect> {
         let scale = state.scale_factor as f32;
+        let padding = Self::padding_px(state);
         let w = state.renderer.width();
         let h = state.renderer.height();
-        let tab_bar_h = state.renderer.text_renderer.tab_bar_height();
+        let tab_bar = state.renderer.text_renderer.tab_bar_insets();
         state
             .workspace_mgr
             .active_workspace()
@@ -167,12 +222,12 @@ impl AppHandler {
             .layout()
             .into_iter()
             .find(|(id, _)| *id == pane_id)
-            .map(|(_, rect)| Self::pane_to_pixel_rect(&rect, w, h, scale, tab_bar_h))
+            .map(|(_, rect)| Self::pane_to_pixel_rect(&rect, w, h, scale, tab_bar, padding))
     }
 
     fn pane_at_pixel(state: &RunningState, x: f32, y: f32) -> Option<PaneId> {
-        let tab_bar_h = state.renderer.text_renderer.tab_bar_height();
-        if y < tab_bar_h {
+        let tab_bar = state.renderer.text_renderer.tab_bar_insets();
+        if state.renderer.text_renderer.tab_bar_contains(y) {
             return None;
         }
         let scale = state.scale_factor as f32;
@@ -185,7 +240,8 @@ impl AppHandler {
             .layout()
             .into_iter()
             .find_map(|(pane_id, pane_rect)| {
-                let px = Self::pane_to_pixel_rect(&pane_rect, w, h, scale, tab_bar_h);
+                // Hit-test the full pane area, including its padding
+                let px = Self::pane_to_pixel_rect(&pane_rect, w, h, scale, tab_bar, 0.0);
                 let in_x = x >= px.x && x < px.x + px.w;
                 let in_y = y >= px.y && y < px.y + px.h;
                 if in_x && in_y {
@@ -198,7 +254,7 @@ impl AppHandler {
 
     /// Convert mouse position to grid cell (col, row) for a specific pane
     fn pixel_to_cell(state: &RunningState, pane_id: PaneId) -> (u16, u16) {
-        let (cell_w, cell_h) = state.renderer.text_renderer.cell_size();
+        let cell = state.renderer.text_renderer.pane_cell_size(pane_id);
         let (mx, my) = Self::mouse_physical(state);
         let pane_rect = Self::pane_pixel_rect(state, pane_id);
         let (px, py) = if let Some(rect) = pane_rect {
@@ -206,11 +262,11 @@ impl AppHandler {
         } else {
             (mx, my)
         };
-        let col = (px / cell_w).max(0.0) as u16;
-        let row = (py / cell_h).max(0.0) as u16;
+        let (col, row) = scale::cell_at(px, py, cell);
 
         if let Some(ps) = state.pane_states.get(&pane_id) {
-            let (grid_cols, grid_rows) = ps.emulator.size();
+            let metadata = ps.emulator.metadata();
+            let (grid_cols, grid_rows) = (metadata.cols, metadata.rows);
             (
                 col.min(grid_cols.saturating_sub(1)),
                 row.min(grid_rows.saturating_sub(1)),
@@ -220,6 +276,57 @@ impl AppHandler {
         }
     }
 
+    /// Link under the pointer, found in its pane's last rendered grid
+    fn link_at_pointer(state: &RunningState) -> Option<(PaneId, u16, Link)> {
+        if state.mouse_pressed || state.split_drag.is_some() {
+            return None;
+        }
+        let (x, y) = Self::mouse_physical(state);
+        let pane_id = Self::pane_at_pixel(state, x, y)?;
+        let (col, row) = Self::pixel_to_cell(state, pane_id);
+        let line = state
+            .pane_states
+            .get(&pane_id)?
+            .render_grid
+            .get(row as usize)?;
+        link::link_at(line, col).map(|link| (pane_id, row, link))
+    }
+
+    /// Underline `hovered` and show a pointer cursor over it, redrawing
+    /// only when the hovered link changes.
+    fn set_hovered_link(state: &mut RunningState, hovered: Option<(PaneId, u16, Link)>) {
+        if state.hovered_link == hovered {
+            return;
+        }
+        let underline = hovered.as_ref().and_then(|(pane_id, row, link)| {
+            let line = state
+                .pane_states
+                .get(pane_id)?
+                .render_grid
+                .get(*row as usize)?;
+            Some(LinkUnderline {
+                pane_id: *pane_id,
+                row: *row,
+                start: link.start,
+                end: link.end,
+                color: line.cells.get(link.start as usize)?.fg,
+            })
+        });
+        for (pane_id, _, _) in state.hovered_link.iter().chain(&hovered) {
+            if let Some(ps) = state.pane_states.get(pane_id) {
+                ps.dirty.store(true, Ordering::Relaxed);
+            }
+        }
+        state.window.set_cursor(if hovered.is_some() {
+            CursorIcon::Pointer
+        } else {
+            CursorIcon::Default
+        });
+        state.hovered_link = hovered;
+        state.renderer.text_renderer.set_link_underline(underline);
+        state.window.request_redraw();
+    }
+
     /// Extract selected text from the active pane's grid
     fn get_selected_text(state: &RunningState, theme: &Arc<Theme>) -> Option<String> {
         let sel = state.selection?;
@@ -281,11 +388,12 @@ impl AppHandler {
         let scale = state.scale_factor as f32;
         let w = state.renderer.width();
         let h = state.renderer.height();
-        let tab_bar_h = state.renderer.text_renderer.tab_bar_height();
+        let tab_bar = state.renderer.text_renderer.tab_bar_insets();
+        let padding = Self::padding_px(state);
         let layout = state.workspace_mgr.active_workspace().split_tree.layout();
         for (pane_id, pane_rect) in &layout {
-            let px_rect = Self::pane_to_pixel_rect(pane_rect, w, h, scale, tab_bar_h);
-            let (cols, rows) = Self::pixel_rect_to_cols_rows(&px_rect, &state.renderer);
+            let px_rect = Self::pane_to_pixel_rect(pane_rect, w, h, scale, tab_bar, padding);
+            let (cols, rows) = Self::pixel_rect_to_cols_rows(&px_rect, &state.renderer, *pane_id);
             if let Some(ps) = state.pane_states.get(pane_id) {
                 ps.emulator.resize(cols, rows);
                 let _ = ps.pty.resize(cols, rows);
@@ -297,15 +405,16 @@ impl AppHandler {
     fn split_divider_hit(state: &RunningState, x: f32, y: f32) -> Option<SplitDrag> {
         let scale = state.scale_factor as f32;
         let threshold = 4.0 * scale;
-        let tab_bar_h = state.renderer.text_renderer.tab_bar_height();
+        let tab_bar = state.renderer.text_renderer.tab_bar_insets();
         let w = state.renderer.width();
         let h = state.renderer.height();
         let layout = state.workspace_mgr.active_workspace().split_tree.layout();
 
         for (i, (a_id, a_rect_n)) in layout.iter().enumerate() {
-            let a = Self::pane_to_pixel_rect(a_rect_n, w, h, scale, tab_bar_h);
+            // Dividers sit on the outer pane edges, so ignore padding here
+            let a = Self::pane_to_pixel_rect(a_rect_n, w, h, scale, tab_bar, 0.0);
             for (b_id, b_rect_n) in layout.iter().skip(i + 1) {
-                let b = Self::pane_to_pixel_rect(b_rect_n, w, h, scale, tab_bar_h);
+                let b = Self::pane_to_pixel_rect(b_rect_n, w, h, scale, tab_bar, 0.0);
 
                 let v_boundary =
                     (a.x + a.w - b.x).abs() <= threshold || (b.x + b.w - a.x).abs() <= threshold;
@@ -352,7 +461,13 @@ impl AppHandler {
     }
 
     /// Find the word boundaries around a cell position
-    fn word_selection_at(state: &RunningState, theme: &Arc<Theme>, col: u16, row: u16) -> Selection {
+    fn word_selection_at(
+        state: &RunningState,
+        theme: &Arc<Theme>,
+        words: &SelectionConfig,
+        col: u16,
+        row: u16,
+    ) -> Selection {
         let active_pane = state.workspace_mgr.active_workspace().active_pane();
         if let Some(ps) = state.pane_states.get(&active_pane) {
             let grid = ps.emulator.extract_grid(theme);
@@ -361,7 +476,7 @@ impl AppHandler {
                 let cells = &line.cells;
                 let c = col as usize;
                 if c < cells.len() {
-                    let is_word_char = |ch: char| ch.is_alphanumeric() || ch == '_';
+                    let is_word_char = |ch: char| words.is_word_char(ch);
                     let ch = cells[c].c;
                     if is_word_char(ch) {
                         let mut start = c;
@@ -390,8 +505,7 @@ impl AppHandler {
     fn line_selection_at(state: &RunningState, row: u16) -> Selection {
         let active_pane = state.workspace_mgr.active_workspace().active_pane();
         let max_col = if let Some(ps) = state.pane_states.get(&active_pane) {
-            let (cols, _) = ps.emulator.size();
-            cols.saturating_sub(1)
+            ps.emulator.metadata().cols.saturating_sub(1)
         } else {
             79
         };
@@ -451,29 +565,43 @@ impl AppHandler {
             render_grid: Vec::new(),
             render_dirty_rows: Vec::new(),
             last_cursor_visible: true,
+            focused: false,
         }
     }
 
     /// Calculate cols/rows from a physical-pixel pane rect
-    fn rect_to_cols_rows(renderer: &Renderer, scale_factor: f64) -> (u16, u16) {
-        let (cell_w, cell_h) = renderer.text_renderer.cell_size();
-        let padding = (6.0 * scale_factor as f32) as u32;
-        let w = renderer.width().max(1);
-        let h =
-            (renderer.height() as f32 - renderer.text_renderer.tab_bar_height()).max(1.0) as u32;
-        let cols = ((w - padding * 2) as f32 / cell_w).max(1.0) as u16;
-        let rows = ((h - padding * 2) as f32 / cell_h).max(1.0) as u16;
-        (cols, rows)
+    /// Move to a new GPU device after the old one was lost (driver reset,
+    /// eGPU unplugged). Terminals and shaped text are kept; every pane is
+    /// redrawn. Returns false if no device could be had, to try again on
+    /// the next frame.
+    fn recover_renderer(state: &mut RunningState) -> bool {
+        let window = Arc::clone(&state.window);
+        if let Err(e) = pollster::block_on(state.renderer.recover(window)) {
+            warn!("failed to recreate the GPU device: {e:#}");
+            return false;
+        }
+        for ps in state.pane_states.values() {
+            ps.dirty.store(true, Ordering::Relaxed);
+        }
+        info!("Renderer rebuilt on a new GPU device");
+        true
     }
 
-    /// Calculate cols/rows for a specific pane pixel rect
-    fn pixel_rect_to_cols_rows(rect: &PixelRect, renderer: &Renderer) -> (u16, u16) {
-        let (cell_w, cell_h) = renderer.text_renderer.cell_size();
-        let scale = renderer.text_renderer.scale_factor();
-        let inner_padding = 6.0 * scale;
-        let cols = ((rect.w - inner_padding * 2.0) / cell_w).max(1.0) as u16;
-        let rows = ((rect.h - inner_padding * 2.0) / cell_h).max(1.0) as u16;
-        (cols, rows)
+    fn rect_to_cols_rows(renderer: &Renderer, padding: f32) -> (u16, u16) {
+        let w = renderer.width().max(1) as f32;
+        let h = (renderer.height() as f32 - renderer.text_renderer.tab_bar_height()).max(1.0);
+        let cell = renderer.text_renderer.cell_size();
+        scale::grid_size(w - padding * 2.0, h - padding * 2.0, cell)
+    }
+
+    /// Calculate cols/rows for a specific pane pixel rect (padding already applied)
+    fn pixel_rect_to_cols_rows(
+        rect: &PixelRect,
+        renderer: &Renderer,
+        pane_id: PaneId,
+    ) -> (u16, u16) {
+        let cell = renderer.text_renderer.pane_cell_size(pane_id);
+        scale::grid_size(rect.w, rect.h, cell)
     }
 
     /// Build PixelRect from normalized PaneRect
@@ -481,15 +609,15 @@ impl AppHandler {
         pane_rect: &pterminal_core::split::PaneRect,
         window_w: u32,
         window_h: u32,
-        scale: f32,
-        tab_bar_h: f32,
+        _scale: f32,
+        tab_bar: (f32, f32),
+        padding: f32,
     ) -> PixelRect {
         let content_w = (window_w as f32).max(1.0);
-        let content_h = window_h as f32 - tab_bar_h;
-        let padding = 6.0 * scale;
+        let content_h = window_h as f32 - tab_bar.0 - tab_bar.1;
         PixelRect {
             x: pane_rect.x * content_w + padding,
-            y: pane_rect.y * content_h + padding + tab_bar_h,
+            y: pane_rect.y * content_h + padding + tab_bar.0,
             w: pane_rect.width * content_w - padding * 2.0,
             h: pane_rect.height * content_h - padding * 2.0,
         }
@@ -514,10 +642,10 @@ impl AppHandler {
     fn update_ime_cursor_area(state: &RunningState) {
         let active = state.workspace_mgr.active_workspace().active_pane();
         let scale = state.scale_factor as f32;
-        let (cell_w, cell_h) = state.renderer.text_renderer.cell_size();
+        let (cell_w, cell_h) = state.renderer.text_renderer.pane_cell_size(active);
 
         if let Some(ps) = state.pane_states.get(&active) {
-            let (col, row) = ps.emulator.cursor_position();
+            let (col, row) = ps.emulator.metadata().cursor;
 
             let layout = state.workspace_mgr.active_workspace().split_tree.layout();
             let pane_rect = layout.iter().find(|(id, _)| *id == active);
@@ -529,7 +657,8 @@ impl AppHandler {
                     w,
                     h,
                     scale,
-                    state.renderer.text_renderer.tab_bar_height(),
+                    state.renderer.text_renderer.tab_bar_insets(),
+                    Self::padding_px(state),
                 );
 
                 // Cursor top-left position in physical pixels;
@@ -545,6 +674,199 @@ impl AppHandler {
         }
     }
 
+    /// Scroll a pane's history, or send arrow keys when a full-screen app on the
+    /// alternate screen asked for alternate scroll mode.
+    fn scroll_pane(ps: &PaneState, lines: i32, config: &Config) {
+        if config.scrollback.alternate_scroll {
+            let modes = ps.emulator.input_modes();
+            if modes.scroll_as_arrows() {
+                let key = modes.arrow_key(lines > 0);
+                let _ = ps.pty.write(&key.repeat(lines.unsigned_abs() as usize));
+                return;
+            }
+        }
+        ps.emulator.scroll(lines);
+        ps.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Grow or shrink one pane's font size independently of the other panes;
+    /// a `delta` of 0 restores the configured size.
+    fn step_pane_font_size(state: &mut RunningState, config: &Config, pane_id: PaneId, delta: f32) {
+        state
+            .renderer
+            .text_renderer
+            .step_pane_font_size(pane_id, config.font.size, delta);
+        Self::resize_active_workspace_panes(state);
+    }
+
+    /// Send focus-in/out to panes whose focus changed, for apps that enabled
+    /// focus reporting. Only the active pane is focused, and only while the
+    /// window itself has focus.
+    fn sync_pane_focus(state: &mut RunningState) {
+        let focused = state
+            .window_focused
+            .then(|| state.workspace_mgr.active_workspace().active_pane());
+        for (&pane_id, ps) in state.pane_states.iter_mut() {
+            let has_focus = focused == Some(pane_id);
+            if ps.focused != has_focus {
+                ps.focused = has_focus;
+                if let Some(seq) = ps.emulator.input_modes().focus_report(has_focus) {
+                    let _ = ps.pty.write(seq);
+                }
+            }
+        }
+    }
+
+    /// Replace the context menu with the active pane's process tree.
+    fn open_process_menu(state: &mut RunningState, x: f32, y: f32) {
+        let active = state.workspace_mgr.active_workspace().active_pane();
+        let Some(pid) = state
+            .pane_states
+            .get(&active)
+            .and_then(|ps| ps.pty.child_pid())
+        else {
+            return;
+        };
+        let processes = process_tree::tree(&process_tree::snapshot(), pid);
+        state.process_labels = processes
+            .iter()
+            .map(|(depth, p)| {
+                let label = format!(
+                    "{}{}  {:.1}% {:.0}M",
+                    "  ".repeat(*depth),
+                    p.command,
+                    p.cpu,
+                    p.rss_kb as f64 / 1024.0
+                );
+                (p.pid, label)
+            })
+            .collect();
+        state.context_menu = Some(ContextMenu {
+            x,
+            y,
+            items: processes
+                .iter()
+                .map(|(_, p)| ContextMenuItem::Process(p.pid))
+                .collect(),
+        });
+        state.skip_next_release = true;
+        state.window.request_redraw();
+    }
+
+    /// Paste dropped files into the pane under the pointer, or offer to upload
+    /// them when that pane is running ssh.
+    fn handle_dropped_files(state: &mut RunningState, config: &Config) {
+        if state.dropped_files.is_empty() {
+            return;
+        }
+        let files = std::mem::take(&mut state.dropped_files);
+        let (x, y) = Self::mouse_physical(state);
+        let pane_id = Self::pane_at_pixel(state, x, y)
+            .unwrap_or_else(|| state.workspace_mgr.active_workspace().active_pane());
+        let Some(ps) = state.pane_states.get(&pane_id) else {
+            return;
+        };
+        let Some(target) = file_drop::ssh_target(&ps.pty) else {
+            let _ = ps.pty.write(drop_text(&files, config).as_bytes());
+            return;
+        };
+        state.file_drop = Some(PendingDrop {
+            pane_id,
+            files,
+            target,
+        });
+        state.context_menu = Some(ContextMenu {
+            x,
+            y,
+            items: DropChoice::ALL.map(ContextMenuItem::FileDrop).to_vec(),
+        });
+        state.window.request_redraw();
+    }
+
+    /// Tee the active pane's output to a new file in the logs directory.
+    fn start_pane_log(state: &mut RunningState, config: &Config) {
+        let active = state.workspace_mgr.active_workspace().active_pane();
+        let Some(ps) = state.pane_states.get(&active) else {
+            return;
+        };
+        let logging = &config.logging;
+        let path = pane_log::default_path(&config.logs_dir(), active);
+        let options = LogOptions {
+            format: logging.format,
+            timestamps: logging.timestamps,
+            collapse_progress: logging.collapse_progress,
+        };
+        match PaneLog::open(
+            &path,
+            options,
+            logging.max_size_mb * 1024 * 1024,
+            logging.keep,
+        ) {
+            Ok(log) => {
+                ps.pty.start_log(log);
+                info!(pane_id = active, path = %path.display(), "Pane log started");
+                state
+                    .notifications
+                    .push("Logging pane output", path.display().to_string());
+            }
+            Err(e) => {
+                state
+                    .notifications
+                    .push("Pane log failed", format!("{e:#}"));
+            }
+        }
+    }
+
+    /// Close the active pane's log, if it has one.
+    fn stop_pane_log(state: &mut RunningState) {
+        let active = state.workspace_mgr.active_workspace().active_pane();
+        let path = state
+            .pane_states
+            .get(&active)
+            .and_then(|ps| ps.pty.stop_log());
+        if let Some(path) = path {
+            state
+                .notifications
+                .push("Pane log stopped", path.display().to_string());
+        }
+    }
+
+    fn run_drop_choice(state: &mut RunningState, choice: DropChoice) {
+        let Some(drop) = state.file_drop.take() else {
+            return;
+        };
+        match choice {
+            DropChoice::Upload(tool) => {
+                let done = state.upload_tx.clone();
+                start_upload(drop.pane_id, &drop.target, tool, drop.files, done);
+            }
+            DropChoice::PasteLocalPaths => {
+                if let Some(ps) = state.pane_states.get(&drop.pane_id) {
+                    // The remote shell is assumed to be POSIX
+                    let text = paste_text(&drop.files, ShellKind::Posix);
+                    let _ = ps.pty.write(text.as_bytes());
+                }
+            }
+        }
+    }
+
+    /// Report a finished upload; on success the remote paths are pasted into the pane.
+    fn finish_upload(state: &mut RunningState, upload: UploadResult) {
+        match upload.result {
+            Ok(paths) => {
+                if let Some(ps) = state.pane_states.get(&upload.pane_id) {
+                    let _ = ps.pty.write(format!("{} ", paths.join(" ")).as_bytes());
+                }
+                let body = format!("{} file(s) copied to {}:~", paths.len(), upload.destination);
+                state.notifications.push("Upload finished", body);
+            }
+            Err(error) => {
+                warn!(destination = %upload.destination, %error, "Upload failed");
+                state.notifications.push("Upload failed", error);
+            }
+        }
+    }
+
     /// Hit test context menu — returns the clicked item, or None if outside
     fn context_menu_hit_test(
         menu: &ContextMenu,
@@ -563,7 +885,7 @@ impl AppHandler {
         for (i, item) in menu.items.iter().enumerate() {
             let iy = menu_y + i as f32 * item_h;
             if phys_y >= iy && phys_y < iy + item_h {
-                return Some(*item);
+                return Some(item.clone());
             }
         }
         None
@@ -655,7 +977,8 @@ impl AppHandler {
             }
             "workspace.new" | "new-workspace" => {
                 let (ws_id, pane_id) = state.workspace_mgr.add_workspace();
-                let (cols, rows) = Self::rect_to_cols_rows(&state.renderer, state.scale_factor);
+                let (cols, rows) =
+                    Self::rect_to_cols_rows(&state.renderer, Self::padding_px(state));
                 let ps = Self::spawn_pane(config, pane_id, cols, rows, &state.window);
                 state.pane_states.insert(pane_id, ps);
                 Self::update_title(state);
@@ -807,7 +1130,7 @@ impl ApplicationHandler for AppHandler {
         let size = window.inner_size();
         let font_size = self.app.config.font.size;
 
-        let renderer = pollster::block_on(Renderer::new(
+        let mut renderer = pollster::block_on(Renderer::new(
             window.clone(),
             size.width.max(1),
             size.height.max(1),
@@ -815,8 +1138,13 @@ impl ApplicationHandler for AppHandler {
             font_size,
         ))
         .expect("create renderer");
+        renderer.text_renderer.set_cell_metrics(
+            self.app.config.font.line_height,
+            self.app.config.font.letter_spacing,
+        );
 
-        let (cols, rows) = Self::rect_to_cols_rows(&renderer, scale_factor);
+        let padding = self.app.config.window.padding.unwrap_or(6.0).max(0.0);
+        let (cols, rows) = Self::rect_to_cols_rows(&renderer, padding * scale_factor as f32);
 
         // WorkspaceManager starts with workspace 0, pane 0
         let workspace_mgr = WorkspaceManager::new();
@@ -829,6 +1157,7 @@ impl ApplicationHandler for AppHandler {
         let clipboard = Clipboard::new().ok();
         let debug_timing = std::env::var("PTERMINAL_DEBUG").is_ok();
         let (ipc_tx, ipc_rx) = mpsc::channel::<IpcEnvelope>();
+        let (upload_tx, upload_rx) = mpsc::channel::<UploadResult>();
         let ipc_socket_path = Config::config_dir().join("pterminal.sock");
         let ipc_server = match IpcServer::start(
             &ipc_socket_path,
@@ -849,6 +1178,7 @@ impl ApplicationHandler for AppHandler {
                     Err(_) => JsonRpcResponse::internal_error(req_id, "request timed out"),
                 }
             }),
+            &self.app.config.ipc,
         ) {
             Ok(server) => Some(server),
             Err(e) => {
@@ -864,11 +1194,13 @@ impl ApplicationHandler for AppHandler {
             workspace_mgr,
             pane_states,
             scale_factor,
+            padding,
             modifiers: ModifiersState::empty(),
             clipboard,
             selection: None,
             mouse_pressed: false,
             last_mouse_pos: (0.0, 0.0),
+            hovered_link: None,
             last_click_time: Instant::now() - Duration::from_secs(10),
             last_click_pos: (0, 0),
             click_count: 0,
@@ -886,6 +1218,12 @@ impl ApplicationHandler for AppHandler {
             // Frame rate limiting - start in the past to allow immediate first frame
             last_render_time: Instant::now() - Duration::from_millis(100),
             pending_input_events: 0,
+            window_focused: true,
+            dropped_files: Vec::new(),
+            file_drop: None,
+            upload_tx,
+            upload_rx,
+            process_labels: HashMap::new(),
         };
 
         Self::update_title(&running);
@@ -909,10 +1247,19 @@ impl ApplicationHandler for AppHandler {
                 event_loop.exit();
             }
 
+            WindowEvent::DroppedFile(path) => {
+                state.dropped_files.push(path);
+            }
+
             WindowEvent::ModifiersChanged(mods) => {
                 state.modifiers = mods.state();
             }
 
+            WindowEvent::Focused(focused) => {
+                state.window_focused = focused;
+                Self::sync_pane_focus(state);
+            }
+
             // IME composition (Chinese, Japanese, Korean input, dead keys)
             WindowEvent::Ime(ime) => {
                 match ime {
@@ -944,6 +1291,9 @@ impl ApplicationHandler for AppHandler {
                     .renderer
                     .text_renderer
                     .update_scale_factor(scale_factor, self.app.config.font.size);
+                // Cell metrics changed: re-derive grids even if the window
+                // keeps its physical size
+                Self::resize_active_workspace_panes(state);
                 // Mark all panes dirty
                 for ps in state.pane_states.values() {
                     ps.dirty.store(true, Ordering::Relaxed);
@@ -995,9 +1345,63 @@ impl ApplicationHandler for AppHandler {
                                         }
                                     }
                                 }
+                                ContextMenuItem::SelectTab(idx) => {
+                                    state.workspace_mgr.select_workspace(idx);
+                                    Self::update_title(state);
+                                    for ps in state.pane_states.values() {
+                                        ps.dirty.store(true, Ordering::Relaxed);
+                                    }
+                                }
+                                ContextMenuItem::FontSize(delta) => {
+                                    let active =
+                                        state.workspace_mgr.active_workspace().active_pane();
+                                    Self::step_pane_font_size(
+                                        state,
+                                        &self.app.config,
+                                        active,
+                                        delta as f32,
+                                    );
+                                }
+                                ContextMenuItem::LinkScroll(linked) => {
+                                    let ws = state.workspace_mgr.active_workspace_mut();
+                                    let active = ws.active_pane();
+                                    ws.set_scroll_linked(active, linked);
+                                }
+                                ContextMenuItem::FileDrop(choice) => {
+                                    Self::run_drop_choice(state, choice);
+                                }
+                                ContextMenuItem::Processes => {
+                                    Self::open_process_menu(state, phys_x, phys_y);
+                                    return;
+                                }
+                                ContextMenuItem::Process(pid) => {
+                                    state.context_menu = Some(ContextMenu {
+                                        x: phys_x,
+                                        y: phys_y,
+                                        items: Signal::ALL
+                                            .map(|sig| ContextMenuItem::SendSignal(pid, sig))
+                                            .to_vec(),
+                                    });
+                                    state.skip_next_release = true;
+                                    state.window.request_redraw();
+                                    return;
+                                }
+                                ContextMenuItem::SendSignal(pid, signal) => {
+                                    if let Err(e) = process_tree::send_signal(pid, signal) {
+                                        warn!(pid, "{e}");
+                                    }
+                                }
+                                ContextMenuItem::Pane(ContextAction::StartLog) => {
+                                    Self::start_pane_log(state, &self.app.config);
+                                }
+                                ContextMenuItem::Pane(ContextAction::StopLog) => {
+                                    Self::stop_pane_log(state);
+                                }
+                                ContextMenuItem::Pane(_) => {}
                             }
                         }
                         state.context_menu = None;
+                        state.file_drop = None;
                         state.skip_next_release = true;
                         state.window.request_redraw();
                         return;
@@ -1014,43 +1418,57 @@ impl ApplicationHandler for AppHandler {
                 }
 
                 // Check tab bar click
-                let tab_bar_h = state.renderer.text_renderer.tab_bar_height();
-                if tab_bar_h > 0.0 && phys_y < tab_bar_h && btn_state == ElementState::Pressed {
-                    let tab_count = state.workspace_mgr.workspace_count();
-                    if tab_count > 1 {
-                        let tab_width = state.renderer.width() as f32 / tab_count as f32;
-                        // Close button zone matches renderer: font_size * 0.8 * 2.0
-                        let tab_font_size = state.renderer.text_renderer.font_size() * 0.8;
-                        let close_btn_w = tab_font_size * 2.0;
-                        let clicked_tab = (phys_x / tab_width).min(tab_count as f32 - 1.0) as usize;
-                        let tab_right_edge = (clicked_tab + 1) as f32 * tab_width;
-
-                        if phys_x > tab_right_edge - close_btn_w {
-                            // Close button hit — close this tab
-                            if tab_count > 1 {
-                                // Switch to tab first, then close
-                                state.workspace_mgr.select_workspace(clicked_tab);
-                                let ws = state.workspace_mgr.active_workspace();
-                                let pane_ids = ws.pane_ids();
-                                let ws_id = ws.id;
-                                for pid in &pane_ids {
-                                    state.pane_states.remove(pid);
-                                }
-                                state.workspace_mgr.close_workspace(ws_id);
-                                Self::update_title(state);
+                if state.renderer.text_renderer.tab_bar_contains(phys_y)
+                    && btn_state == ElementState::Pressed
+                {
+                    let hit = state
+                        .renderer
+                        .text_renderer
+                        .tab_strip_layout()
+                        .and_then(|layout| layout.hit_test(phys_x));
+                    match hit {
+                        Some(TabHit::Close(clicked_tab)) => {
+                            // Switch to tab first, then close
+                            state.workspace_mgr.select_workspace(clicked_tab);
+                            let ws = state.workspace_mgr.active_workspace();
+                            let pane_ids = ws.pane_ids();
+                            let ws_id = ws.id;
+                            for pid in &pane_ids {
+                                state.pane_states.remove(pid);
                             }
-                        } else {
-                            // Switch to clicked tab
+                            state.workspace_mgr.close_workspace(ws_id);
+                            Self::update_title(state);
+                        }
+                        Some(TabHit::Tab(clicked_tab)) => {
                             state.workspace_mgr.select_workspace(clicked_tab);
                             Self::update_title(state);
                         }
-                        // Mark all panes dirty for redraw
-                        for ps in state.pane_states.values() {
-                            ps.dirty.store(true, Ordering::Relaxed);
+                        Some(TabHit::Overflow) => {
+                            // List every tab in a menu anchored under the button
+                            // (kept on screen above a bottom tab bar)
+                            let menu_w = 160.0 * scale;
+                            let pad = 6.0 * scale;
+                            let (tab_bar_top, _) = state.renderer.text_renderer.tab_bar_insets();
+                            state.context_menu = Some(ContextMenu {
+                                x: state.renderer.width() as f32 - menu_w - pad,
+                                y: if tab_bar_top > 0.0 {
+                                    tab_bar_top
+                                } else {
+                                    state.renderer.height() as f32
+                                },
+                                items: (0..state.workspace_mgr.workspace_count())
+                                    .map(ContextMenuItem::SelectTab)
+                                    .collect(),
+                            });
                         }
-                        state.window.request_redraw();
-                        return;
+                        None => {}
+                    }
+                    // Mark all panes dirty for redraw
+                    for ps in state.pane_states.values() {
+                        ps.dirty.store(true, Ordering::Relaxed);
                     }
+                    state.window.request_redraw();
+                    return;
                 }
 
                 match btn_state {
@@ -1091,6 +1509,7 @@ impl ApplicationHandler for AppHandler {
                                 state.selection = Some(Self::word_selection_at(
                                     state,
                                     &self.app.theme,
+                                    &self.app.config.selection,
                                     cell.0,
                                     cell.1,
                                 ));
@@ -1164,6 +1583,31 @@ impl ApplicationHandler for AppHandler {
                         items.push(ContextMenuItem::Copy);
                     }
                     items.push(ContextMenuItem::Paste);
+                    items.push(ContextMenuItem::FontSize(1));
+                    items.push(ContextMenuItem::FontSize(-1));
+                    let active = state.workspace_mgr.active_workspace().active_pane();
+                    if state
+                        .renderer
+                        .text_renderer
+                        .pane_font_size(active)
+                        .is_some()
+                    {
+                        items.push(ContextMenuItem::FontSize(0));
+                    }
+                    let ws = state.workspace_mgr.active_workspace();
+                    if ws.pane_ids().len() > 1 {
+                        items.push(ContextMenuItem::LinkScroll(!ws.is_scroll_linked(active)));
+                    }
+                    items.push(ContextMenuItem::Processes);
+                    let logging = state
+                        .pane_states
+                        .get(&active)
+                        .is_some_and(|ps| ps.pty.log_path().is_some());
+                    items.push(ContextMenuItem::Pane(if logging {
+                        ContextAction::StopLog
+                    } else {
+                        ContextAction::StartLog
+                    }));
                     state.context_menu = Some(ContextMenu {
                         x: phys_x,
                         y: phys_y,
@@ -1201,6 +1645,8 @@ impl ApplicationHandler for AppHandler {
                     }
                     return;
                 }
+                let hovered = Self::link_at_pointer(state);
+                Self::set_hovered_link(state, hovered);
                 // Only drag-extend for single-click selections (not word/line)
                 if state.mouse_pressed && state.click_count <= 1 {
                     let active = state.workspace_mgr.active_workspace().active_pane();
@@ -1216,21 +1662,76 @@ impl ApplicationHandler for AppHandler {
                 }
             }
 
+            WindowEvent::CursorLeft { .. } => {
+                Self::set_hovered_link(state, None);
+            }
+
             WindowEvent::MouseWheel { delta, .. } => {
-                let lines = match delta {
-                    winit::event::MouseScrollDelta::LineDelta(_, y) => y as i32 * 3,
+                // The content moves under the pointer; hover again to re-detect
+                Self::set_hovered_link(state, None);
+                // Wheel over an overflowing tab bar scrolls the tab strip
+                let (_, phys_y) = Self::mouse_physical(state);
+                let overflows = state
+                    .renderer
+                    .text_renderer
+                    .tab_strip_layout()
+                    .is_some_and(|layout| layout.overflows());
+                if overflows && state.renderer.text_renderer.tab_bar_contains(phys_y) {
+                    let step = 40.0 * state.scale_factor as f32;
+                    let dx = match delta {
+                        winit::event::MouseScrollDelta::LineDelta(x, y) => {
+                            -(if x != 0.0 { x } else { y }) * step
+                        }
+                        winit::event::MouseScrollDelta::PixelDelta(pos) => {
+                            -(if pos.x != 0.0 { pos.x } else { pos.y }) as f32
+                        }
+                    };
+                    state.renderer.text_renderer.scroll_tab_bar(dx);
+                    state.window.request_redraw();
+                    return;
+                }
+                let scrollback = &self.app.config.scrollback;
+                // Horizontal scrolling goes to applications that asked for
+                // mouse reports, as wheel buttons 6 and 7
+                let cols = match delta {
+                    winit::event::MouseScrollDelta::LineDelta(x, _) => x.round() as i32,
                     winit::event::MouseScrollDelta::PixelDelta(pos) => {
-                        let (_, cell_h) = state.renderer.text_renderer.cell_size();
-                        (pos.y as f32 / cell_h).round() as i32
+                        let active = state.workspace_mgr.active_workspace().active_pane();
+                        let (cell_w, _) = state.renderer.text_renderer.pane_cell_size(active);
+                        (pos.x as f32 / cell_w).round() as i32
                     }
                 };
-                if lines != 0 {
+                if cols != 0 {
                     let active = state.workspace_mgr.active_workspace().active_pane();
+                    let (col, row) = Self::pixel_to_cell(state, active);
                     if let Some(ps) = state.pane_states.get(&active) {
-                        ps.emulator.scroll(lines);
-                        ps.dirty.store(true, Ordering::Relaxed);
-                        state.window.request_redraw();
+                        let button = if cols > 0 { 6 } else { 7 };
+                        let report = ps.emulator.input_modes().wheel_report(button, col, row);
+                        if let Some(report) = report {
+                            let _ = ps.pty.write(&report.repeat(cols.unsigned_abs() as usize));
+                        }
+                    }
+                }
+                let lines = match delta {
+                    winit::event::MouseScrollDelta::LineDelta(_, y) => {
+                        (y * scrollback.multiplier as f32 * scrollback.wheel_multiplier).round()
+                            as i32
                     }
+                    winit::event::MouseScrollDelta::PixelDelta(pos) => {
+                        let active = state.workspace_mgr.active_workspace().active_pane();
+                        let (_, cell_h) = state.renderer.text_renderer.pane_cell_size(active);
+                        (pos.y as f32 / cell_h * scrollback.trackpad_multiplier).round() as i32
+                    }
+                };
+                if lines != 0 {
+                    let ws = state.workspace_mgr.active_workspace();
+                    let active = ws.active_pane();
+                    for pane_id in std::iter::once(active).chain(ws.scroll_peers(active)) {
+                        if let Some(ps) = state.pane_states.get(&pane_id) {
+                            Self::scroll_pane(ps, lines, &self.app.config);
+                        }
+                    }
+                    state.window.request_redraw();
                 }
             }
 
@@ -1247,6 +1748,19 @@ impl ApplicationHandler for AppHandler {
 
                 if super_key {
                     if let Key::Character(ref c) = event.logical_key {
+                        // Cmd+Shift+= / Cmd+Shift+- / Cmd+Shift+0: per-pane font size
+                        let font_step = match c.as_str() {
+                            "+" | "=" => Some(1.0),
+                            "_" | "-" => Some(-1.0),
+                            ")" | "0" => Some(0.0),
+                            _ => None,
+                        };
+                        if let (true, Some(delta)) = (shift, font_step) {
+                            let active = state.workspace_mgr.active_workspace().active_pane();
+                            Self::step_pane_font_size(state, &self.app.config, active, delta);
+                            state.window.request_redraw();
+                            return;
+                        }
                         match c.as_str() {
                             // Cmd+C: Copy selection
                             "c" => {
@@ -1274,8 +1788,10 @@ impl ApplicationHandler for AppHandler {
                             // Cmd+T: New workspace (tab)
                             "t" => {
                                 let (_ws_id, pane_id) = state.workspace_mgr.add_workspace();
-                                let (cols, rows) =
-                                    Self::rect_to_cols_rows(&state.renderer, state.scale_factor);
+                                let (cols, rows) = Self::rect_to_cols_rows(
+                                    &state.renderer,
+                                    Self::padding_px(state),
+                                );
                                 let ps = Self::spawn_pane(
                                     &self.app.config,
                                     pane_id,
@@ -1335,11 +1851,15 @@ impl ApplicationHandler for AppHandler {
                                         w,
                                         h,
                                         scale,
-                                        state.renderer.text_renderer.tab_bar_height(),
+                                        state.renderer.text_renderer.tab_bar_insets(),
+                                        Self::padding_px(state),
                                     );
-                                    Self::pixel_rect_to_cols_rows(&px, &state.renderer)
+                                    Self::pixel_rect_to_cols_rows(&px, &state.renderer, new_pane_id)
                                 } else {
-                                    Self::rect_to_cols_rows(&state.renderer, state.scale_factor)
+                                    Self::rect_to_cols_rows(
+                                        &state.renderer,
+                                        Self::padding_px(state),
+                                    )
                                 };
 
                                 let ps = Self::spawn_pane(
@@ -1360,10 +1880,14 @@ impl ApplicationHandler for AppHandler {
                                         w,
                                         h,
                                         scale,
-                                        state.renderer.text_renderer.tab_bar_height(),
+                                        state.renderer.text_renderer.tab_bar_insets(),
+                                        Self::padding_px(state),
+                                    );
+                                    let (c, r) = Self::pixel_rect_to_cols_rows(
+                                        &px,
+                                        &state.renderer,
+                                        active_pane,
                                     );
-                                    let (c, r) =
-                                        Self::pixel_rect_to_cols_rows(&px, &state.renderer);
                                     if let Some(ops) = state.pane_states.get(&active_pane) {
                                         ops.emulator.resize(c, r);
                                         let _ = ops.pty.resize(c, r);
@@ -1464,6 +1988,18 @@ impl ApplicationHandler for AppHandler {
                 }
 
                 // Send keystrokes to the active pane's PTY
+                let active = state.workspace_mgr.active_workspace().active_pane();
+                let modes = state
+                    .pane_states
+                    .get(&active)
+                    .map(|ps| ps.emulator.input_modes())
+                    .unwrap_or_default();
+                let mods = KeyMods {
+                    shift: state.modifiers.shift_key(),
+                    alt: state.modifiers.alt_key(),
+                    ctrl,
+                    meta: state.modifiers.super_key(),
+                };
                 // Handle Ctrl+letter → control character (0x01..0x1A)
                 let bytes = if ctrl {
                     if let Key::Character(ref c) = event.logical_key {
@@ -1471,13 +2007,13 @@ impl ApplicationHandler for AppHandler {
                         if ch.len() == 1 && ch[0].is_ascii_alphabetic() {
                             Some(vec![ch[0].to_ascii_lowercase() - b'a' + 1])
                         } else {
-                            key_to_bytes(&event, state.ime_active)
+                            key_to_bytes(&event, state.ime_active, mods, &modes)
                         }
                     } else {
-                        key_to_bytes(&event, state.ime_active)
+                        key_to_bytes(&event, state.ime_active, mods, &modes)
                     }
                 } else {
-                    key_to_bytes(&event, state.ime_active)
+                    key_to_bytes(&event, state.ime_active, mods, &modes)
                 };
                 if let Some(bytes) = bytes {
                     let active = state.workspace_mgr.active_workspace().active_pane();
@@ -1507,6 +2043,10 @@ impl ApplicationHandler for AppHandler {
                 // Reset pending input counter since we're rendering now
                 state.pending_input_events = 0;
 
+                if state.renderer.is_device_lost() && !Self::recover_renderer(state) {
+                    return;
+                }
+
                 let t_frame = Instant::now();
                 let theme = &self.app.theme;
                 let scale = state.scale_factor as f32;
@@ -1516,13 +2056,22 @@ impl ApplicationHandler for AppHandler {
                 // Update tab bar
                 let tab_count = state.workspace_mgr.workspace_count();
                 let active_idx = state.workspace_mgr.active_index();
-                let tabs: Vec<(String, bool)> = (0..tab_count)
-                    .map(|i| (format!("Tab {}", i + 1), i == active_idx))
-                    .collect();
+                let position = TabBarPosition::from_name(&self.app.config.window.tab_bar);
+                let tabs: Vec<(String, bool)> = if position.shown(tab_count) {
+                    (0..tab_count)
+                        .map(|i| (format!("Tab {}", i + 1), i == active_idx))
+                        .collect()
+                } else {
+                    Vec::new()
+                };
                 let tab_bar_bg = RgbColor::new(0x1e, 0x1f, 0x29);
                 let tab_active_bg = theme.colors.background;
                 let tab_fg = RgbColor::new(0x88, 0x88, 0x88);
                 let tab_active_fg = theme.colors.foreground;
+                state
+                    .renderer
+                    .text_renderer
+                    .set_tab_bar_bottom(position.is_bottom());
                 state.renderer.text_renderer.set_tab_bar(
                     &tabs,
                     tab_bar_bg,
@@ -1531,17 +2080,40 @@ impl ApplicationHandler for AppHandler {
                     tab_active_fg,
                 );
                 let tab_bar_h = state.renderer.text_renderer.tab_bar_height();
+                let tab_bar = state.renderer.text_renderer.tab_bar_insets();
 
                 // Update context menu overlay
                 if let Some(ref menu) = state.context_menu {
-                    let items: Vec<(&str, bool)> = menu
+                    let labels: Vec<String> = menu
                         .items
                         .iter()
                         .map(|item| match item {
-                            ContextMenuItem::Copy => ("Copy", true),
-                            ContextMenuItem::Paste => ("Paste", true),
+                            ContextMenuItem::Copy => "Copy".to_string(),
+                            ContextMenuItem::Paste => "Paste".to_string(),
+                            ContextMenuItem::SelectTab(i) if *i == active_idx => {
+                                format!("• Tab {}", i + 1)
+                            }
+                            ContextMenuItem::SelectTab(i) => format!("  Tab {}", i + 1),
+                            ContextMenuItem::FontSize(0) => "Reset Text Size".to_string(),
+                            ContextMenuItem::FontSize(d) if *d > 0 => "Larger Text".to_string(),
+                            ContextMenuItem::FontSize(_) => "Smaller Text".to_string(),
+                            ContextMenuItem::LinkScroll(true) => "Link Scrolling".to_string(),
+                            ContextMenuItem::LinkScroll(false) => "Unlink Scrolling".to_string(),
+                            ContextMenuItem::FileDrop(choice) => choice.label().to_string(),
+                            ContextMenuItem::Processes => "Processes…".to_string(),
+                            ContextMenuItem::Process(pid) => state
+                                .process_labels
+                                .get(pid)
+                                .cloned()
+                                .unwrap_or_else(|| format!("pid {pid}")),
+                            ContextMenuItem::SendSignal(pid, sig) => {
+                                format!("Send SIG{} to {pid}", sig.name())
+                            }
+                            ContextMenuItem::Pane(action) => action.label(),
                         })
                         .collect();
+                    let items: Vec<(&str, bool)> =
+                        labels.iter().map(|label| (label.as_str(), true)).collect();
                     state
                         .renderer
                         .text_renderer
@@ -1594,10 +2166,12 @@ impl ApplicationHandler for AppHandler {
                 let mut pane_rects: Vec<(PaneId, PixelRect)> = Vec::with_capacity(layout.len());
                 let cursor_color = theme.colors.cursor;
                 let mut any_updated = false;
+                let padding = Self::padding_px(state);
 
                 let t_grid = Instant::now();
                 for (pane_id, pane_rect) in &layout {
-                    let px_rect = Self::pane_to_pixel_rect(pane_rect, w, h, scale, tab_bar_h);
+                    let px_rect =
+                        Self::pane_to_pixel_rect(pane_rect, w, h, scale, tab_bar, padding);
 
                     if let Some(ps) = state.pane_states.get_mut(pane_id) {
                         let show_cursor = *pane_id == active_pane;
@@ -1624,7 +2198,7 @@ impl ApplicationHandler for AppHandler {
                                     ps.render_dirty_rows.extend(delta.dirty_rows);
                                 }
                             } else {
-                                cursor_pos = ps.emulator.cursor_position();
+                                cursor_pos = ps.emulator.metadata().cursor;
                             }
                             let sel = if *pane_id == active_pane {
                                 state.selection.map(|s| s.normalized())
@@ -1732,12 +2306,17 @@ impl ApplicationHandler for AppHandler {
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
         if let Some(state) = &mut self.app.state {
             Self::handle_ipc_requests(state, &self.app.config, &self.app.theme, event_loop);
+            Self::sync_pane_focus(state);
+            Self::handle_dropped_files(state, &self.app.config);
+            while let Ok(upload) = state.upload_rx.try_recv() {
+                Self::finish_upload(state, upload);
+            }
             let active_panes = state.workspace_mgr.active_workspace().pane_ids();
             let any_dirty = active_panes.iter().any(|pid| {
                 state
                     .pane_states
                     .get(pid)
-                    .map_or(false, |ps| ps.dirty.load(Ordering::Relaxed))
+                    .is_some_and(|ps| ps.dirty.load(Ordering::Relaxed))
             });
 
             // Strategy 1: Frame rate limiting with proper scheduling
@@ -1765,25 +2344,82 @@ impl ApplicationHandler for AppHandler {
     }
 }
 
+/// The key of a winit key event, if it sends an escape sequence
+fn special_key(event: &winit::event::KeyEvent) -> Option<SpecialKey> {
+    // Keypad keys type text or, with Num Lock off, are cursor keys
+    if let PhysicalKey::Code(code) = event.physical_key {
+        let keypad = KeypadKey::from_code(&format!("{code:?}"));
+        let types_text = matches!(
+            event.logical_key,
+            Key::Character(_) | Key::Named(NamedKey::Enter)
+        );
+        if let (Some(keypad), true) = (keypad, types_text) {
+            return Some(SpecialKey::Keypad(keypad));
+        }
+    }
+    let Key::Named(named) = &event.logical_key else {
+        return None;
+    };
+    Some(match named {
+        NamedKey::ArrowUp => SpecialKey::Up,
+        NamedKey::ArrowDown => SpecialKey::Down,
+        NamedKey::ArrowRight => SpecialKey::Right,
+        NamedKey::ArrowLeft => SpecialKey::Left,
+        NamedKey::Home => SpecialKey::Home,
+        NamedKey::End => SpecialKey::End,
+        NamedKey::PageUp => SpecialKey::PageUp,
+        NamedKey::PageDown => SpecialKey::PageDown,
+        NamedKey::Delete => SpecialKey::Delete,
+        NamedKey::Insert => SpecialKey::Insert,
+        NamedKey::ContextMenu => SpecialKey::Menu,
+        NamedKey::PrintScreen => SpecialKey::PrintScreen,
+        NamedKey::F1 => SpecialKey::F(1),
+        NamedKey::F2 => SpecialKey::F(2),
+        NamedKey::F3 => SpecialKey::F(3),
+        NamedKey::F4 => SpecialKey::F(4),
+        NamedKey::F5 => SpecialKey::F(5),
+        NamedKey::F6 => SpecialKey::F(6),
+        NamedKey::F7 => SpecialKey::F(7),
+        NamedKey::F8 => SpecialKey::F(8),
+        NamedKey::F9 => SpecialKey::F(9),
+        NamedKey::F10 => SpecialKey::F(10),
+        NamedKey::F11 => SpecialKey::F(11),
+        NamedKey::F12 => SpecialKey::F(12),
+        NamedKey::F13 => SpecialKey::F(13),
+        NamedKey::F14 => SpecialKey::F(14),
+        NamedKey::F15 => SpecialKey::F(15),
+        NamedKey::F16 => SpecialKey::F(16),
+        NamedKey::F17 => SpecialKey::F(17),
+        NamedKey::F18 => SpecialKey::F(18),
+        NamedKey::F19 => SpecialKey::F(19),
+        NamedKey::F20 => SpecialKey::F(20),
+        NamedKey::F21 => SpecialKey::F(21),
+        NamedKey::F22 => SpecialKey::F(22),
+        NamedKey::F23 => SpecialKey::F(23),
+        NamedKey::F24 => SpecialKey::F(24),
+        _ => return None,
+    })
+}
+
 /// Convert winit key events to bytes for PTY input
-fn key_to_bytes(event: &winit::event::KeyEvent, ime_active: bool) -> Option<Vec<u8>> {
-    // Named keys (arrows, enter, etc.) — always handled here regardless of IME state
+fn key_to_bytes(
+    event: &winit::event::KeyEvent,
+    ime_active: bool,
+    mods: KeyMods,
+    modes: &InputModes,
+) -> Option<Vec<u8>> {
+    // Function, cursor and keypad keys, with their modifiers
+    if let Some(key) = special_key(event) {
+        return Some(encode_key(key, mods, modes));
+    }
+
+    // Other named keys (enter, etc.) — always handled here regardless of IME state
     if let Key::Named(named) = &event.logical_key {
         let bytes: &[u8] = match named {
             NamedKey::Enter => b"\r",
             NamedKey::Backspace => b"\x7f",
             NamedKey::Tab => b"\t",
             NamedKey::Escape => b"\x1b",
-            NamedKey::ArrowUp => b"\x1b[A",
-            NamedKey::ArrowDown => b"\x1b[B",
-            NamedKey::ArrowRight => b"\x1b[C",
-            NamedKey::ArrowLeft => b"\x1b[D",
-            NamedKey::Home => b"\x1b[H",
-            NamedKey::End => b"\x1b[F",
-            NamedKey::PageUp => b"\x1b[5~",
-            NamedKey::PageDown => b"\x1b[6~",
-            NamedKey::Delete => b"\x1b[3~",
-            NamedKey::Insert => b"\x1b[2~",
             NamedKey::Space => b" ",
             _ => return None,
         };
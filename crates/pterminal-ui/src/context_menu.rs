@@ -0,0 +1,89 @@
+//! The menu opened by right-clicking a pane.
+
+use pterminal_core::terminal::CommandBlock;
+
+/// What an entry of the context menu does, to the pane it was opened on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ContextAction {
+    Copy,
+    /// Copy the selection with its colours, as HTML
+    CopyHtml,
+    Paste,
+    /// Switch whether copies join lines the terminal wrapped
+    JoinWrapped,
+    SplitRight,
+    SplitDown,
+    /// Split with a new shell in the same directory
+    Duplicate,
+    /// Duplicate and run the pane's foreground command again
+    DuplicateRunning(String),
+    /// Copy the command line of the block clicked on
+    CopyCommand(String),
+    /// Copy what the command of the block clicked on printed
+    CopyOutput(String),
+    /// Copy the block's command line followed by its output
+    CopyBlock(String),
+    /// Type the block's command at the prompt again and run it
+    Rerun(String),
+    /// Tee the pane's output to a new file in the logs directory
+    StartLog,
+    /// Close the pane's log
+    StopLog,
+}
+
+impl ContextAction {
+    pub(crate) fn label(&self) -> String {
+        match self {
+            Self::Copy => "Copy".into(),
+            Self::CopyHtml => "Copy as HTML".into(),
+            Self::Paste => "Paste".into(),
+            Self::JoinWrapped => "Copy Wrapped Lines as One".into(),
+            Self::SplitRight => "Split Right".into(),
+            Self::SplitDown => "Split Down".into(),
+            Self::Duplicate => "Duplicate Pane".into(),
+            Self::DuplicateRunning(command) => format!("Duplicate Running {command}"),
+            Self::CopyCommand(_) => "Copy Command".into(),
+            Self::CopyOutput(_) => "Copy Output".into(),
+            Self::CopyBlock(_) => "Copy Command and Output".into(),
+            Self::Rerun(_) => "Re-run Command".into(),
+            Self::StartLog => "Log Output to File".into(),
+            Self::StopLog => "Stop Logging Output".into(),
+        }
+    }
+}
+
+/// The entries for a pane, in menu order. `command` is its foreground
+/// command, if it isn't at the prompt; `block` the command clicked on;
+/// `logging` whether its output goes to a log.
+pub(crate) fn pane_actions(
+    command: Option<String>,
+    block: Option<CommandBlock>,
+    logging: bool,
+) -> Vec<ContextAction> {
+    let mut actions = Vec::new();
+    if let Some(block) = block {
+        let both = format!("{}\n{}", block.command, block.output);
+        actions.extend([
+            ContextAction::CopyCommand(block.command.clone()),
+            ContextAction::CopyOutput(block.output),
+            ContextAction::CopyBlock(both),
+            ContextAction::Rerun(block.command),
+        ]);
+    }
+    actions.extend([
+        ContextAction::Copy,
+        ContextAction::CopyHtml,
+        ContextAction::Paste,
+        ContextAction::JoinWrapped,
+        ContextAction::SplitRight,
+        ContextAction::SplitDown,
+        ContextAction::Duplicate,
+    ]);
+    actions.extend(command.map(ContextAction::DuplicateRunning));
+    actions.push(if logging {
+        ContextAction::StopLog
+    } else {
+        ContextAction::StartLog
+    });
+    actions
+}
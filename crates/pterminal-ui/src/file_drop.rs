@@ -0,0 +1,95 @@
+//! Files dropped onto a pane: pasted as shell-escaped paths, or uploaded
+//! through the ssh session running in the pane.
+
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+
+use pterminal_core::shell_words::ShellKind;
+use pterminal_core::split::PaneId;
+use pterminal_core::ssh::{SshTarget, TransferTool};
+use pterminal_core::terminal::PtyHandle;
+use pterminal_core::Config;
+
+/// What to do with files dropped onto an ssh pane
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DropChoice {
+    Upload(TransferTool),
+    PasteLocalPaths,
+}
+
+impl DropChoice {
+    pub(crate) const ALL: [DropChoice; 3] = [
+        DropChoice::Upload(TransferTool::Scp),
+        DropChoice::Upload(TransferTool::Rsync),
+        DropChoice::PasteLocalPaths,
+    ];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            DropChoice::Upload(TransferTool::Scp) => "Upload with scp",
+            DropChoice::Upload(TransferTool::Rsync) => "Upload with rsync",
+            DropChoice::PasteLocalPaths => "Paste local path",
+        }
+    }
+}
+
+/// Outcome of an upload started by [`start_upload`]
+pub(crate) struct UploadResult {
+    pub pane_id: PaneId,
+    pub destination: String,
+    /// Shell-escaped remote paths, or the tool's error output
+    pub result: Result<Vec<String>, String>,
+}
+
+/// The ssh session in the pane's foreground, if any.
+pub(crate) fn ssh_target(pty: &PtyHandle) -> Option<SshTarget> {
+    SshTarget::from_argv(&pty.foreground_command()?)
+}
+
+/// Shell-escaped paths separated by spaces, with a trailing space so the next
+/// word can be typed straight away.
+pub(crate) fn paste_text(paths: &[PathBuf], shell: ShellKind) -> String {
+    paths
+        .iter()
+        .map(|p| shell.quote(&p.to_string_lossy()) + " ")
+        .collect()
+}
+
+/// Input for files dropped onto a local pane: their paths, or a `cd` into a
+/// single dropped directory when configured.
+pub(crate) fn drop_text(paths: &[PathBuf], config: &Config) -> String {
+    let shell = ShellKind::from_shell(&config.shell());
+    match paths {
+        [dir] if config.drop.cd_into_directory && dir.is_dir() => {
+            format!("{}\r", shell.cd_command(dir))
+        }
+        _ => paste_text(paths, shell),
+    }
+}
+
+/// Upload `files` to the remote home directory on a worker thread; the result
+/// is sent to `done`.
+pub(crate) fn start_upload(
+    pane_id: PaneId,
+    target: &SshTarget,
+    tool: TransferTool,
+    files: Vec<PathBuf>,
+    done: Sender<UploadResult>,
+) {
+    let mut cmd = target.upload_command(tool, &files);
+    let destination = target.destination.clone();
+    std::thread::spawn(move || {
+        let result = match cmd.output() {
+            Ok(output) if output.status.success() => {
+                Ok(files.iter().map(|f| SshTarget::remote_path(f)).collect())
+            }
+            Ok(output) => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+            Err(e) => Err(format!("{}: {e}", tool.name())),
+        };
+        let _ = done.send(UploadResult {
+            pane_id,
+            destination,
+            result,
+        });
+    });
+}
@@ -0,0 +1,101 @@
+//! System-wide shortcuts (`[global_hotkeys]`) that reach us while another
+//! app has focus.
+//!
+//! Registration is the platform's own: Carbon hot keys on macOS,
+//! `RegisterHotKey` on Windows and a key grab on the X11 root window.
+//! Wayland gives clients no way to grab keys, so there the shortcuts are
+//! only seen while an XWayland window has focus.
+
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver};
+
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use pterminal_core::config::GlobalHotkeysConfig;
+
+use crate::wakeup;
+
+/// What a global hotkey does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HotkeyAction {
+    /// Show and focus the window, or hide it when it's focused
+    ToggleWindow,
+    /// Paste the clipboard into the active pane
+    PasteClipboard,
+}
+
+/// The registered hotkeys; they're released when this is dropped
+pub(crate) struct GlobalHotkeys {
+    _manager: GlobalHotKeyManager,
+    /// Hotkey ids and what they do
+    bindings: Vec<(u32, HotkeyAction)>,
+    events: Receiver<GlobalHotKeyEvent>,
+}
+
+impl GlobalHotkeys {
+    /// Register the configured hotkeys. Call from the UI thread. `None`
+    /// when none are configured or none could be registered.
+    pub(crate) fn register(config: &GlobalHotkeysConfig) -> Option<Self> {
+        let configured = [
+            (&config.toggle_window, HotkeyAction::ToggleWindow),
+            (&config.paste_clipboard, HotkeyAction::PasteClipboard),
+        ];
+        let configured: Vec<_> = configured
+            .into_iter()
+            .filter(|(keys, _)| !keys.trim().is_empty())
+            .collect();
+        if configured.is_empty() {
+            return None;
+        }
+        let manager = match GlobalHotKeyManager::new() {
+            Ok(manager) => manager,
+            Err(e) => {
+                tracing::warn!("global hotkeys are unavailable: {e}");
+                return None;
+            }
+        };
+        let mut bindings = Vec::new();
+        for (keys, action) in configured {
+            let hotkey = match HotKey::from_str(keys.trim()) {
+                Ok(hotkey) => hotkey,
+                Err(e) => {
+                    tracing::warn!("invalid global hotkey {keys:?}: {e}");
+                    continue;
+                }
+            };
+            match manager.register(hotkey) {
+                Ok(()) => bindings.push((hotkey.id(), action)),
+                Err(e) => tracing::warn!("failed to register global hotkey {keys:?}: {e}"),
+            }
+        }
+        if bindings.is_empty() {
+            return None;
+        }
+        // Events arrive on the platform's thread (or its event loop on
+        // macOS); hand them over and wake the UI thread to act on them
+        let (tx, events) = mpsc::channel();
+        GlobalHotKeyEvent::set_event_handler(Some(move |event| {
+            let _ = tx.send(event);
+            wakeup::wake();
+        }));
+        Some(Self {
+            _manager: manager,
+            bindings,
+            events,
+        })
+    }
+
+    /// Actions of the hotkeys pressed since the last call
+    pub(crate) fn take_actions(&self) -> Vec<HotkeyAction> {
+        self.events
+            .try_iter()
+            .filter(|event| event.state == HotKeyState::Pressed)
+            .filter_map(|event| {
+                self.bindings
+                    .iter()
+                    .find(|(id, _)| *id == event.id)
+                    .map(|(_, action)| *action)
+            })
+            .collect()
+    }
+}
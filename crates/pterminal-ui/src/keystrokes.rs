@@ -0,0 +1,167 @@
+//! Log of recently pressed keys for the on-screen keystroke overlay.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Most chords kept on screen at once
+const MAX_ENTRIES: usize = 8;
+/// Consecutive typed characters are merged into one entry up to this length
+const MAX_TYPED_RUN: usize = 24;
+
+struct Entry {
+    label: String,
+    /// Plain typed text (merged with the next typed character)
+    typed: bool,
+    at: Instant,
+}
+
+/// Modifier state of a key press, after the macOS Ctrl/Cmd swap
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub meta: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct KeystrokeLog {
+    entries: VecDeque<Entry>,
+    /// Set whenever the displayed text changes
+    changed: bool,
+}
+
+impl KeystrokeLog {
+    /// Record a key press as Slint reports it (`ch` is the first char of the event text).
+    pub(crate) fn record(&mut self, ch: char, mods: Modifiers) {
+        let now = Instant::now();
+        let chord = mods.ctrl || mods.alt || mods.meta;
+        let Some(key) = key_name(ch, mods.ctrl) else {
+            return;
+        };
+
+        if !chord && !key.named {
+            // Plain typing: extend the previous run instead of adding a chord
+            if let Some(last) = self.entries.back_mut() {
+                if last.typed && last.label.chars().count() < MAX_TYPED_RUN {
+                    last.label.push(ch);
+                    last.at = now;
+                    self.changed = true;
+                    return;
+                }
+            }
+            self.push(Entry {
+                label: ch.to_string(),
+                typed: true,
+                at: now,
+            });
+            return;
+        }
+
+        let mut label = modifier_prefix(mods);
+        label.push_str(&key.label);
+        self.push(Entry {
+            label,
+            typed: false,
+            at: now,
+        });
+    }
+
+    fn push(&mut self, entry: Entry) {
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+        self.changed = true;
+    }
+
+    /// Forget everything (e.g. when the overlay is turned off).
+    pub(crate) fn clear(&mut self) {
+        self.changed |= !self.entries.is_empty();
+        self.entries.clear();
+    }
+
+    /// Whether an entry has outlived `timeout` and the overlay needs a redraw.
+    pub(crate) fn has_expired(&self, timeout: Duration) -> bool {
+        self.entries
+            .front()
+            .is_some_and(|entry| entry.at.elapsed() >= timeout)
+    }
+
+    /// Drop entries older than `timeout`; returns whether the displayed text
+    /// changed since the last call.
+    pub(crate) fn expire(&mut self, timeout: Duration) -> bool {
+        while self.has_expired(timeout) {
+            self.entries.pop_front();
+            self.changed = true;
+        }
+        std::mem::take(&mut self.changed)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Overlay text, oldest key first.
+    pub(crate) fn text(&self) -> String {
+        let labels: Vec<&str> = self.entries.iter().map(|e| e.label.as_str()).collect();
+        labels.join("  ")
+    }
+}
+
+struct KeyName {
+    label: String,
+    /// A named key (arrow, Return, F-key …) rather than a printable character
+    named: bool,
+}
+
+fn key_name(ch: char, ctrl: bool) -> Option<KeyName> {
+    let named = |label: &str| {
+        Some(KeyName {
+            label: label.to_string(),
+            named: true,
+        })
+    };
+    // With Ctrl held Slint may report the control character instead of the letter
+    if ctrl && ('\u{1}'..='\u{1a}').contains(&ch) {
+        return named(&((b'A' + ch as u8 - 1) as char).to_string());
+    }
+    match ch {
+        '\n' | '\r' => named("⏎"),
+        '\t' => named("⇥"),
+        '\u{1b}' => named("⎋"),
+        '\u{8}' => named("⌫"),
+        '\u{7f}' => named("⌦"),
+        ' ' => named("␣"),
+        '\u{F700}' => named("↑"),
+        '\u{F701}' => named("↓"),
+        '\u{F702}' => named("←"),
+        '\u{F703}' => named("→"),
+        '\u{F729}' => named("Home"),
+        '\u{F72B}' => named("End"),
+        '\u{F72C}' => named("PgUp"),
+        '\u{F72D}' => named("PgDn"),
+        '\u{F727}' => named("Ins"),
+        '\u{F704}'..='\u{F71B}' => named(&format!("F{}", ch as u32 - 0xF704 + 1)),
+        // Other control characters and Slint's private-use key codes
+        c if c.is_control() || ('\u{F700}'..='\u{F8FF}').contains(&c) => None,
+        c => Some(KeyName {
+            label: c.to_uppercase().to_string(),
+            named: false,
+        }),
+    }
+}
+
+/// Modifier symbols in the platform's customary order.
+fn modifier_prefix(mods: Modifiers) -> String {
+    let names = if cfg!(target_os = "macos") {
+        ["⌃", "⌥", "⇧", "⌘"]
+    } else {
+        ["Ctrl+", "Alt+", "Shift+", "Super+"]
+    };
+    [mods.ctrl, mods.alt, mods.shift, mods.meta]
+        .into_iter()
+        .zip(names)
+        .filter_map(|(held, name)| held.then_some(name))
+        .collect()
+}
@@ -1,6 +1,14 @@
 pub mod app;
+mod context_menu;
+mod file_drop;
+mod global_hotkeys;
+mod keystrokes;
+#[cfg(target_os = "macos")]
+mod macos;
+mod platform;
 pub mod plugin;
 pub mod slint_app;
+mod wakeup;
 
 pub use app::App;
 pub use slint_app::SlintApp;
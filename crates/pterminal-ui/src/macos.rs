@@ -0,0 +1,797 @@
+//! Native macOS application menu and Dock integration: a menu bar mapping
+//! to terminal actions, a Dock badge with the unread notification count and
+//! New Tab / New Window entries in the Dock menu. AppleScript (and so
+//! Shortcuts' "Run AppleScript") reaches the IPC methods through the `call`
+//! command of `macos/pterminal.sdef`, with `ipc.applescript`.
+//!
+//! Menu items and Apple Events fire on the main thread inside AppKit's event
+//! handling, so they only queue an action (or suspend the event) and wake
+//! the UI thread, which runs it with the terminal state borrowed as usual.
+
+use std::cell::{Cell, RefCell};
+use std::ffi::{c_char, c_void, CStr, CString};
+
+use objc2::encode::{Encode, Encoding};
+use objc2::rc::{Allocated, Retained};
+use objc2::runtime::{AnyClass, AnyObject, ClassBuilder, Sel};
+use objc2::{class, msg_send, sel};
+use pterminal_core::terminal::Progress;
+
+use crate::wakeup;
+
+/// What a menu item does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MenuAction {
+    NewTab,
+    NewWindow,
+    CloseTab,
+    SplitRight,
+    SplitDown,
+    SplitWithEnv,
+    DuplicatePane,
+    RespawnPane,
+    Snippets,
+    History,
+    Processes,
+    SendToPane,
+    LogOutput,
+    ExportLayout,
+    ImportLayout,
+    Copy,
+    CopyWithStyles,
+    CopyAsHtml,
+    Paste,
+    FilterScrollback,
+    FindInPanes,
+    FindInAllWorkspaces,
+    TogglePresentation,
+    ToggleEco,
+    ToggleHighContrast,
+    SimulateColorVision,
+    ToggleWrap,
+    TogglePromptTimes,
+    Marks,
+    OpenFullLine,
+    PauseOutput,
+    PinScreen,
+    Overview,
+    SwitchTheme,
+    BiggerText,
+    SmallerText,
+    ResetText,
+    NextPane,
+    PrevPane,
+    NextTab,
+    PrevTab,
+    JumpToAlert,
+    DoNotDisturb,
+    MuteWorkspace,
+    ShowLogs,
+    DumpState,
+    Settings,
+    KeyBindings,
+}
+
+impl MenuAction {
+    /// Every action; an item's tag is its index here
+    const ALL: [MenuAction; 46] = [
+        Self::NewTab,
+        Self::NewWindow,
+        Self::CloseTab,
+        Self::SplitRight,
+        Self::SplitDown,
+        Self::SplitWithEnv,
+        Self::DuplicatePane,
+        Self::RespawnPane,
+        Self::Snippets,
+        Self::History,
+        Self::Processes,
+        Self::SendToPane,
+        Self::LogOutput,
+        Self::ExportLayout,
+        Self::ImportLayout,
+        Self::Copy,
+        Self::CopyWithStyles,
+        Self::CopyAsHtml,
+        Self::Paste,
+        Self::FilterScrollback,
+        Self::FindInPanes,
+        Self::FindInAllWorkspaces,
+        Self::TogglePresentation,
+        Self::ToggleEco,
+        Self::ToggleHighContrast,
+        Self::SimulateColorVision,
+        Self::ToggleWrap,
+        Self::TogglePromptTimes,
+        Self::Marks,
+        Self::OpenFullLine,
+        Self::PauseOutput,
+        Self::PinScreen,
+        Self::Overview,
+        Self::SwitchTheme,
+        Self::BiggerText,
+        Self::SmallerText,
+        Self::ResetText,
+        Self::NextPane,
+        Self::PrevPane,
+        Self::NextTab,
+        Self::PrevTab,
+        Self::JumpToAlert,
+        Self::DoNotDisturb,
+        Self::MuteWorkspace,
+        Self::ShowLogs,
+        Self::DumpState,
+        Self::Settings,
+        Self::KeyBindings,
+    ];
+
+    /// Menu item title
+    fn title(self) -> &'static str {
+        match self {
+            Self::NewTab => "New Tab",
+            Self::NewWindow => "New Window",
+            Self::CloseTab => "Close Tab",
+            Self::SplitRight => "Split Right",
+            Self::SplitDown => "Split Down",
+            Self::SplitWithEnv => "Split with Environment",
+            Self::DuplicatePane => "Duplicate Pane",
+            Self::RespawnPane => "Restart Shell",
+            Self::Snippets => "Snippets…",
+            Self::History => "History…",
+            Self::Processes => "Processes…",
+            Self::SendToPane => "Send to Pane…",
+            Self::LogOutput => "Log Output…",
+            Self::ExportLayout => "Export Layout…",
+            Self::ImportLayout => "Import Layout…",
+            Self::Copy => "Copy",
+            Self::CopyWithStyles => "Copy with Styles",
+            Self::CopyAsHtml => "Copy as HTML",
+            Self::Paste => "Paste",
+            Self::FilterScrollback => "Filter Scrollback…",
+            Self::FindInPanes => "Find in Panes…",
+            Self::FindInAllWorkspaces => "Find in All Workspaces…",
+            Self::TogglePresentation => "Presentation Mode",
+            Self::ToggleEco => "Eco Mode",
+            Self::ToggleHighContrast => "High Contrast",
+            Self::SimulateColorVision => "Simulate Color Blindness",
+            Self::ToggleWrap => "Toggle Line Wrap",
+            Self::TogglePromptTimes => "Prompt Timestamps",
+            Self::Marks => "Marks…",
+            Self::OpenFullLine => "Open Full Line",
+            Self::PauseOutput => "Pause Output",
+            Self::PinScreen => "Pin Screen for Comparison",
+            Self::Overview => "Workspace Overview",
+            Self::SwitchTheme => "Switch Theme…",
+            Self::BiggerText => "Bigger Text",
+            Self::SmallerText => "Smaller Text",
+            Self::ResetText => "Actual Size",
+            Self::NextPane => "Next Pane",
+            Self::PrevPane => "Previous Pane",
+            Self::NextTab => "Next Tab",
+            Self::PrevTab => "Previous Tab",
+            Self::JumpToAlert => "Jump to Latest Alert",
+            Self::DoNotDisturb => "Do Not Disturb",
+            Self::MuteWorkspace => "Mute Workspace Alerts",
+            Self::ShowLogs => "Logs",
+            Self::DumpState => "Save Debug State",
+            Self::Settings => "Settings…",
+            Self::KeyBindings => "Key Bindings",
+        }
+    }
+
+    /// Key equivalent and modifiers, matching the keyboard shortcuts
+    fn shortcut(self) -> (&'static str, usize) {
+        match self {
+            Self::NewTab => ("t", COMMAND),
+            Self::NewWindow => ("n", COMMAND),
+            Self::CloseTab => ("w", COMMAND),
+            Self::SplitRight => ("d", COMMAND),
+            Self::SplitDown => ("d", COMMAND | SHIFT),
+            Self::SplitWithEnv => ("o", COMMAND | SHIFT),
+            Self::DuplicatePane => ("\r", COMMAND | SHIFT),
+            Self::RespawnPane => ("\u{8}", COMMAND | SHIFT),
+            Self::Snippets => ("s", COMMAND | SHIFT),
+            Self::History => ("y", COMMAND | SHIFT),
+            Self::Processes => ("x", COMMAND | SHIFT),
+            Self::SendToPane => ("i", COMMAND | SHIFT),
+            Self::LogOutput => ("", 0),
+            Self::ExportLayout => ("e", COMMAND | SHIFT),
+            Self::ImportLayout => ("l", COMMAND | SHIFT),
+            Self::Copy => ("c", COMMAND),
+            Self::CopyWithStyles => ("c", COMMAND | SHIFT),
+            Self::CopyAsHtml => ("", 0),
+            Self::Paste => ("v", COMMAND),
+            Self::FilterScrollback => ("f", COMMAND),
+            Self::FindInPanes => ("f", COMMAND | SHIFT),
+            Self::FindInAllWorkspaces => ("", 0),
+            Self::TogglePresentation => ("m", COMMAND | SHIFT),
+            Self::ToggleEco => ("b", COMMAND | SHIFT),
+            Self::ToggleHighContrast => ("h", COMMAND | SHIFT),
+            Self::SimulateColorVision => ("", 0),
+            Self::ToggleWrap => ("r", COMMAND | SHIFT),
+            Self::TogglePromptTimes => ("z", COMMAND | SHIFT),
+            Self::Marks => ("n", COMMAND | SHIFT),
+            Self::OpenFullLine => ("j", COMMAND | SHIFT),
+            Self::PauseOutput => ("p", COMMAND | SHIFT),
+            Self::PinScreen => ("v", COMMAND | SHIFT),
+            Self::Overview => ("g", COMMAND | SHIFT),
+            Self::SwitchTheme => ("k", COMMAND | SHIFT),
+            Self::BiggerText => ("=", COMMAND | SHIFT),
+            Self::SmallerText => ("-", COMMAND | SHIFT),
+            Self::ResetText => ("0", COMMAND | SHIFT),
+            Self::NextPane => ("]", COMMAND),
+            Self::PrevPane => ("[", COMMAND),
+            Self::NextTab => ("]", COMMAND | SHIFT),
+            Self::PrevTab => ("[", COMMAND | SHIFT),
+            Self::JumpToAlert => ("\\", COMMAND | SHIFT),
+            Self::DoNotDisturb => ("", 0),
+            Self::MuteWorkspace => ("", 0),
+            Self::ShowLogs => ("a", COMMAND | SHIFT),
+            Self::DumpState => ("", 0),
+            Self::Settings => (",", COMMAND),
+            Self::KeyBindings => ("/", COMMAND),
+        }
+    }
+
+    fn tag(self) -> isize {
+        Self::ALL.iter().position(|a| *a == self).unwrap_or(0) as isize
+    }
+
+    fn from_tag(tag: isize) -> Option<Self> {
+        usize::try_from(tag)
+            .ok()
+            .and_then(|i| Self::ALL.get(i).copied())
+    }
+}
+
+// NSEventModifierFlags
+const COMMAND: usize = 1 << 20;
+const SHIFT: usize = 1 << 17;
+const OPTION: usize = 1 << 19;
+
+thread_local! {
+    /// Actions picked from a menu, waiting for the UI thread
+    static PENDING: RefCell<Vec<MenuAction>> = const { RefCell::new(Vec::new()) };
+    /// Menu returned to the Dock (retained for the life of the app)
+    static DOCK_MENU: Cell<*mut AnyObject> = const { Cell::new(std::ptr::null_mut()) };
+    /// Count currently shown on the Dock icon
+    static BADGE: Cell<usize> = const { Cell::new(0) };
+    /// Progress bar over the Dock icon, created when first shown
+    static DOCK_PROGRESS: Cell<*mut AnyObject> = const { Cell::new(std::ptr::null_mut()) };
+    /// Progress currently shown on the Dock icon
+    static PROGRESS: Cell<Option<Progress>> = const { Cell::new(None) };
+    /// Script `call`s waiting for the UI thread
+    static SCRIPT_CALLS: RefCell<Vec<ScriptCall>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Actions picked since the last call
+pub(crate) fn take_actions() -> Vec<MenuAction> {
+    PENDING.with(|p| std::mem::take(&mut *p.borrow_mut()))
+}
+
+/// Event class and ID of the sdef's `call` command
+const CALL_EVENT_CLASS: &[u8; 4] = b"PTrm";
+const CALL_EVENT_ID: &[u8; 4] = b"call";
+const KEY_DIRECT_OBJECT: &[u8; 4] = b"----";
+const KEY_PARAMS: &[u8; 4] = b"prms";
+const KEY_ERROR_NUMBER: &[u8; 4] = b"errn";
+const KEY_ERROR_STRING: &[u8; 4] = b"errs";
+
+fn four_cc(code: &[u8; 4]) -> u32 {
+    u32::from_be_bytes(*code)
+}
+
+/// `NSAppleEventManagerSuspensionID`
+#[repr(transparent)]
+#[derive(Clone, Copy)]
+struct SuspensionId(*const c_void);
+
+unsafe impl Encode for SuspensionId {
+    const ENCODING: Encoding =
+        Encoding::Pointer(&Encoding::Struct("__NSAppleEventManagerSuspension", &[]));
+}
+
+/// An AppleScript `call` of an IPC method, suspended until it is answered
+pub(crate) struct ScriptCall {
+    pub method: String,
+    /// JSON params, empty for none
+    pub params: String,
+    suspension: SuspensionId,
+}
+
+impl ScriptCall {
+    /// Answer the script with a JSON result, or fail it with an error
+    /// number and message, and let it continue.
+    pub(crate) fn reply(self, result: Result<String, (i32, String)>) {
+        unsafe {
+            let manager = apple_event_manager();
+            let reply: *mut AnyObject =
+                msg_send![manager, replyAppleEventForSuspensionID: self.suspension];
+            if !reply.is_null() {
+                let descriptor = |text: &str| -> *mut AnyObject {
+                    msg_send![class!(NSAppleEventDescriptor), descriptorWithString: ns_string(text)]
+                };
+                let params: Vec<(*mut AnyObject, &[u8; 4])> = match &result {
+                    Ok(json) => vec![(descriptor(json), KEY_DIRECT_OBJECT)],
+                    Err((code, message)) => {
+                        let number: *mut AnyObject =
+                            msg_send![class!(NSAppleEventDescriptor), descriptorWithInt32: *code];
+                        vec![
+                            (number, KEY_ERROR_NUMBER),
+                            (descriptor(message), KEY_ERROR_STRING),
+                        ]
+                    }
+                };
+                for (value, key) in params {
+                    let _: () =
+                        msg_send![reply, setParamDescriptor: value, forKeyword: four_cc(key)];
+                }
+            }
+            let _: () = msg_send![manager, resumeWithSuspensionID: self.suspension];
+        }
+    }
+}
+
+/// Script calls received since the last call
+pub(crate) fn take_script_calls() -> Vec<ScriptCall> {
+    SCRIPT_CALLS.with(|c| std::mem::take(&mut *c.borrow_mut()))
+}
+
+/// Handle the sdef's `call` Apple Event. Call on the main thread.
+pub(crate) unsafe fn install_script_handler() {
+    let name = c"PterminalScriptHandler";
+    let class = match ClassBuilder::new(name, class!(NSObject)) {
+        Some(mut builder) => {
+            builder.add_method(
+                sel!(handleCallEvent:withReplyEvent:),
+                call_event as extern "C-unwind" fn(&AnyObject, Sel, *mut AnyObject, *mut AnyObject),
+            );
+            builder.register()
+        }
+        None => AnyClass::get(name).expect("script handler class registered"),
+    };
+    let handler: Retained<AnyObject> = msg_send![class, new];
+    let _: () = msg_send![
+        apple_event_manager(),
+        setEventHandler: Retained::into_raw(handler),
+        andSelector: sel!(handleCallEvent:withReplyEvent:),
+        forEventClass: four_cc(CALL_EVENT_CLASS),
+        andEventID: four_cc(CALL_EVENT_ID)
+    ];
+}
+
+/// Suspends a `call` event and queues it for the UI thread
+extern "C-unwind" fn call_event(
+    _this: &AnyObject,
+    _cmd: Sel,
+    event: *mut AnyObject,
+    _reply: *mut AnyObject,
+) {
+    unsafe {
+        let param = |key: &[u8; 4]| -> String {
+            let descriptor: *mut AnyObject =
+                msg_send![event, paramDescriptorForKeyword: four_cc(key)];
+            if descriptor.is_null() {
+                return String::new();
+            }
+            rust_string(msg_send![descriptor, stringValue])
+        };
+        let method = param(KEY_DIRECT_OBJECT);
+        let params = param(KEY_PARAMS);
+        let suspension: SuspensionId = msg_send![apple_event_manager(), suspendCurrentAppleEvent];
+        SCRIPT_CALLS.with(|c| {
+            c.borrow_mut().push(ScriptCall {
+                method,
+                params,
+                suspension,
+            })
+        });
+    }
+    wakeup::wake();
+}
+
+unsafe fn apple_event_manager() -> *mut AnyObject {
+    msg_send![class!(NSAppleEventManager), sharedAppleEventManager]
+}
+
+/// Show `count` unread notifications on the Dock icon (none when 0).
+pub(crate) fn set_dock_badge(count: usize) {
+    if BADGE.with(|b| b.replace(count)) == count {
+        return;
+    }
+    unsafe {
+        let app: *mut AnyObject = msg_send![class!(NSApplication), sharedApplication];
+        let tile: *mut AnyObject = msg_send![app, dockTile];
+        let label = if count == 0 {
+            std::ptr::null_mut()
+        } else {
+            ns_string(&count.to_string())
+        };
+        let _: () = msg_send![tile, setBadgeLabel: label];
+    }
+}
+
+/// `NSRect`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Rect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+unsafe impl Encode for Rect {
+    const ENCODING: Encoding = Encoding::Struct(
+        "CGRect",
+        &[
+            Encoding::Struct("CGPoint", &[Encoding::Double, Encoding::Double]),
+            Encoding::Struct("CGSize", &[Encoding::Double, Encoding::Double]),
+        ],
+    );
+}
+
+/// `NSSize`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Size {
+    width: f64,
+    height: f64,
+}
+
+unsafe impl Encode for Size {
+    const ENCODING: Encoding = Encoding::Struct("CGSize", &[Encoding::Double, Encoding::Double]);
+}
+
+/// Show `progress` as a bar across the bottom of the Dock icon (none
+/// restores the plain icon).
+pub(crate) fn set_dock_progress(progress: Option<Progress>) {
+    if PROGRESS.with(|p| p.replace(progress)) == progress {
+        return;
+    }
+    unsafe {
+        let app: *mut AnyObject = msg_send![class!(NSApplication), sharedApplication];
+        let tile: *mut AnyObject = msg_send![app, dockTile];
+        let view = match progress {
+            Some(progress) => {
+                let bar = dock_progress_bar(app, tile);
+                let percent = progress.percent();
+                let _: () = msg_send![bar, setIndeterminate: percent.is_none()];
+                let _: () = msg_send![bar, setDoubleValue: f64::from(percent.unwrap_or(0))];
+                msg_send![bar, superview]
+            }
+            None => std::ptr::null_mut::<AnyObject>(),
+        };
+        let _: () = msg_send![tile, setContentView: view];
+        let _: () = msg_send![tile, display];
+    }
+}
+
+/// The Dock icon with a progress bar over it, made on first use and kept
+unsafe fn dock_progress_bar(app: *mut AnyObject, tile: *mut AnyObject) -> *mut AnyObject {
+    let existing = DOCK_PROGRESS.with(Cell::get);
+    if !existing.is_null() {
+        return existing;
+    }
+    let size: Size = msg_send![tile, size];
+    let frame = Rect {
+        x: 0.0,
+        y: 0.0,
+        width: size.width,
+        height: size.height,
+    };
+    let view: Allocated<AnyObject> = msg_send![class!(NSImageView), alloc];
+    let view: Retained<AnyObject> = msg_send![view, initWithFrame: frame];
+    let icon: *mut AnyObject = msg_send![app, applicationIconImage];
+    let _: () = msg_send![&*view, setImage: icon];
+
+    let frame = Rect {
+        x: size.width * 0.1,
+        y: size.height * 0.05,
+        width: size.width * 0.8,
+        height: size.height * 0.15,
+    };
+    let bar: Allocated<AnyObject> = msg_send![class!(NSProgressIndicator), alloc];
+    let bar: Retained<AnyObject> = msg_send![bar, initWithFrame: frame];
+    // NSProgressIndicatorStyleBar, 0-100
+    let _: () = msg_send![&*bar, setStyle: 0isize];
+    let _: () = msg_send![&*bar, setMinValue: 0.0f64];
+    let _: () = msg_send![&*bar, setMaxValue: 100.0f64];
+    let _: () = msg_send![&*view, addSubview: &*bar];
+
+    // The view holds the bar; both live as long as the app
+    let _ = Retained::into_raw(view);
+    let bar = Retained::into_raw(bar);
+    DOCK_PROGRESS.with(|p| p.set(bar));
+    bar
+}
+
+/// Put `text` on the general pasteboard with HTML and RTF flavors, so
+/// rich-text editors paste it styled.
+pub(crate) fn set_rich_clipboard(text: &str, html: &str, rtf: &str) {
+    unsafe {
+        let board: *mut AnyObject = msg_send![class!(NSPasteboard), generalPasteboard];
+        let _: isize = msg_send![board, clearContents];
+        for (value, kind) in [(text, "public.utf8-plain-text"), (html, "public.html")] {
+            let _: bool = msg_send![board, setString: ns_string(value), forType: ns_string(kind)];
+        }
+        let data: *mut AnyObject = msg_send![
+            class!(NSData),
+            dataWithBytes: rtf.as_ptr().cast::<std::ffi::c_void>(),
+            length: rtf.len()
+        ];
+        let _: bool = msg_send![board, setData: data, forType: ns_string("public.rtf")];
+    }
+}
+
+/// Start another pterminal process, which opens its own window.
+pub(crate) fn open_new_window() {
+    match std::env::current_exe() {
+        Ok(exe) => {
+            if let Err(e) = std::process::Command::new(exe).spawn() {
+                tracing::warn!("New window: {e}");
+            }
+        }
+        Err(e) => tracing::warn!("New window: {e}"),
+    }
+}
+
+/// Replace the default menu bar and add the Dock menu. Call on the main
+/// thread once the application has finished launching.
+pub(crate) unsafe fn install_menus() {
+    use MenuAction::*;
+
+    let target = menu_target();
+    let app: *mut AnyObject = msg_send![class!(NSApplication), sharedApplication];
+    let main_menu = new_menu("");
+
+    let app_menu = new_menu("pterminal");
+    let about = sel!(orderFrontStandardAboutPanel:);
+    add_system_item(app_menu, "About pterminal", about, "", 0);
+    add_separator(app_menu);
+    add_item(app_menu, target, Settings, true);
+    add_separator(app_menu);
+    add_system_item(app_menu, "Hide pterminal", sel!(hide:), "h", COMMAND);
+    let hide_others = sel!(hideOtherApplications:);
+    add_system_item(app_menu, "Hide Others", hide_others, "h", COMMAND | OPTION);
+    add_system_item(app_menu, "Show All", sel!(unhideAllApplications:), "", 0);
+    add_separator(app_menu);
+    add_system_item(app_menu, "Quit pterminal", sel!(terminate:), "q", COMMAND);
+    add_submenu(main_menu, app_menu);
+
+    let shell = [
+        Some(NewTab),
+        Some(NewWindow),
+        None,
+        Some(SplitRight),
+        Some(SplitDown),
+        Some(SplitWithEnv),
+        Some(DuplicatePane),
+        Some(RespawnPane),
+        None,
+        Some(Snippets),
+        Some(History),
+        Some(Processes),
+        Some(SendToPane),
+        Some(LogOutput),
+        None,
+        Some(ExportLayout),
+        Some(ImportLayout),
+        None,
+        Some(CloseTab),
+    ];
+    add_submenu(main_menu, action_menu(target, "Shell", &shell, true));
+    let edit = [
+        Some(Copy),
+        Some(CopyWithStyles),
+        Some(CopyAsHtml),
+        Some(Paste),
+        None,
+        Some(FilterScrollback),
+        Some(FindInPanes),
+        Some(FindInAllWorkspaces),
+    ];
+    add_submenu(main_menu, action_menu(target, "Edit", &edit, true));
+    let view = [
+        Some(TogglePresentation),
+        Some(ToggleEco),
+        Some(ToggleHighContrast),
+        Some(SimulateColorVision),
+        Some(ToggleWrap),
+        Some(TogglePromptTimes),
+        Some(Marks),
+        Some(OpenFullLine),
+        Some(PauseOutput),
+        Some(PinScreen),
+        Some(Overview),
+        Some(SwitchTheme),
+        None,
+        Some(BiggerText),
+        Some(SmallerText),
+        Some(ResetText),
+    ];
+    add_submenu(main_menu, action_menu(target, "View", &view, true));
+
+    let window = new_menu("Window");
+    add_system_item(window, "Minimize", sel!(performMiniaturize:), "m", COMMAND);
+    add_system_item(window, "Zoom", sel!(performZoom:), "", 0);
+    add_separator(window);
+    for action in [NextPane, PrevPane, NextTab, PrevTab, JumpToAlert] {
+        add_item(window, target, action, true);
+    }
+    add_separator(window);
+    for action in [DoNotDisturb, MuteWorkspace] {
+        add_item(window, target, action, true);
+    }
+    add_submenu(main_menu, window);
+    let help = [Some(KeyBindings), None, Some(ShowLogs), Some(DumpState)];
+    let help = action_menu(target, "Help", &help, true);
+    add_submenu(main_menu, help);
+
+    let _: () = msg_send![app, setMainMenu: main_menu];
+    let _: () = msg_send![app, setWindowsMenu: window];
+    let _: () = msg_send![app, setHelpMenu: help];
+
+    let dock = [
+        Some(NewTab),
+        Some(NewWindow),
+        None,
+        Some(JumpToAlert),
+        Some(DoNotDisturb),
+    ];
+    let dock = action_menu(target, "", &dock, false);
+    DOCK_MENU.with(|m| m.set(dock));
+    add_dock_menu_method(app);
+}
+
+/// Sends `menuAction:` on behalf of our items and queues the action
+extern "C-unwind" fn menu_action(_this: &AnyObject, _cmd: Sel, sender: *mut AnyObject) {
+    let tag: isize = unsafe { msg_send![sender, tag] };
+    if let Some(action) = MenuAction::from_tag(tag) {
+        PENDING.with(|p| p.borrow_mut().push(action));
+        wakeup::wake();
+    }
+}
+
+/// `applicationDockMenu:` for the application delegate
+extern "C-unwind" fn dock_menu(
+    _this: &AnyObject,
+    _cmd: Sel,
+    _app: *mut AnyObject,
+) -> *mut AnyObject {
+    DOCK_MENU.with(Cell::get)
+}
+
+/// Object receiving `menuAction:` from our menu items
+unsafe fn menu_target() -> *mut AnyObject {
+    let name = c"PterminalMenuTarget";
+    let class = match ClassBuilder::new(name, class!(NSObject)) {
+        Some(mut builder) => {
+            builder.add_method(
+                sel!(menuAction:),
+                menu_action as extern "C-unwind" fn(&AnyObject, Sel, *mut AnyObject),
+            );
+            builder.register()
+        }
+        None => AnyClass::get(name).expect("menu target class registered"),
+    };
+    let target: Retained<AnyObject> = msg_send![class, new];
+    Retained::into_raw(target)
+}
+
+/// The delegate belongs to the windowing library; teach its class to hand
+/// AppKit our Dock menu.
+unsafe fn add_dock_menu_method(app: *mut AnyObject) {
+    let delegate: *mut AnyObject = msg_send![app, delegate];
+    if delegate.is_null() {
+        return;
+    }
+    let class = (*delegate).class() as *const AnyClass as *mut AnyClass;
+    let imp = dock_menu as extern "C-unwind" fn(&AnyObject, Sel, *mut AnyObject) -> *mut AnyObject;
+    let types: *const c_char = c"@@:@".as_ptr();
+    let _ = objc2::ffi::class_addMethod(
+        class,
+        sel!(applicationDockMenu:),
+        std::mem::transmute::<_, objc2::ffi::IMP>(imp),
+        types,
+    );
+}
+
+unsafe fn ns_string(text: &str) -> *mut AnyObject {
+    let text = CString::new(text).unwrap_or_default();
+    msg_send![class!(NSString), stringWithUTF8String: text.as_ptr()]
+}
+
+unsafe fn rust_string(text: *mut AnyObject) -> String {
+    if text.is_null() {
+        return String::new();
+    }
+    let utf8: *const c_char = msg_send![text, UTF8String];
+    if utf8.is_null() {
+        return String::new();
+    }
+    CStr::from_ptr(utf8).to_string_lossy().into_owned()
+}
+
+unsafe fn new_menu(title: &str) -> *mut AnyObject {
+    // Menus live as long as the app
+    let menu: Allocated<AnyObject> = msg_send![class!(NSMenu), alloc];
+    let menu: Retained<AnyObject> = msg_send![menu, initWithTitle: ns_string(title)];
+    Retained::into_raw(menu)
+}
+
+unsafe fn new_item(
+    title: &str,
+    action: Option<Sel>,
+    key: &str,
+    modifiers: usize,
+) -> *mut AnyObject {
+    let item: Allocated<AnyObject> = msg_send![class!(NSMenuItem), alloc];
+    let item: Retained<AnyObject> = msg_send![
+        item,
+        initWithTitle: ns_string(title),
+        action: action,
+        keyEquivalent: ns_string(key)
+    ];
+    let item = Retained::into_raw(item);
+    if !key.is_empty() {
+        let _: () = msg_send![item, setKeyEquivalentModifierMask: modifiers];
+    }
+    item
+}
+
+/// A menu of pterminal actions, `None` marking separators
+unsafe fn action_menu(
+    target: *mut AnyObject,
+    title: &str,
+    actions: &[Option<MenuAction>],
+    shortcuts: bool,
+) -> *mut AnyObject {
+    let menu = new_menu(title);
+    for action in actions {
+        match action {
+            Some(action) => add_item(menu, target, *action, shortcuts),
+            None => add_separator(menu),
+        }
+    }
+    menu
+}
+
+/// Add an item running `action` in pterminal
+unsafe fn add_item(
+    menu: *mut AnyObject,
+    target: *mut AnyObject,
+    action: MenuAction,
+    shortcut: bool,
+) {
+    let (key, modifiers) = if shortcut { action.shortcut() } else { ("", 0) };
+    let item = new_item(action.title(), Some(sel!(menuAction:)), key, modifiers);
+    let _: () = msg_send![item, setTarget: target];
+    let _: () = msg_send![item, setTag: action.tag()];
+    let _: () = msg_send![menu, addItem: item];
+}
+
+/// Add an item sending a standard AppKit action up the responder chain
+unsafe fn add_system_item(
+    menu: *mut AnyObject,
+    title: &str,
+    action: Sel,
+    key: &str,
+    modifiers: usize,
+) {
+    let item = new_item(title, Some(action), key, modifiers);
+    let _: () = msg_send![menu, addItem: item];
+}
+
+unsafe fn add_separator(menu: *mut AnyObject) {
+    let item: *mut AnyObject = msg_send![class!(NSMenuItem), separatorItem];
+    let _: () = msg_send![menu, addItem: item];
+}
+
+unsafe fn add_submenu(main_menu: *mut AnyObject, submenu: *mut AnyObject) {
+    let title: *mut AnyObject = msg_send![submenu, title];
+    let item = new_item("", None, "", 0);
+    let _: () = msg_send![item, setTitle: title];
+    let _: () = msg_send![item, setSubmenu: submenu];
+    let _: () = msg_send![main_menu, addItem: item];
+}
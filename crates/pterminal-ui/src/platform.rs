@@ -0,0 +1,173 @@
+//! Window system differences the toolkit leaves to us.
+//!
+//! The display scale is read from the window system everywhere, with a
+//! CoreGraphics fallback on macOS. On Linux this module also handles the
+//! primary selection, the app id compositors match window rules and
+//! `.desktop` files on, decorations, and the startup notification token
+//! that lets a window opened from a launcher take focus. Desktop
+//! notifications go through the platform's own service. Global hotkeys are
+//! in `global_hotkeys`.
+//! Menus, the Dock and AppleScript are in `macos`.
+
+use arboard::Clipboard;
+use pterminal_core::config::WindowConfig;
+use slint::winit_030::WinitWindowAccessor;
+use winit::window::WindowAttributes;
+
+/// App id on Wayland and WM_CLASS on X11; matches `pterminal.desktop`
+#[cfg(target_os = "linux")]
+const APP_ID: &str = "pterminal";
+
+/// Set up the selected backend before the first window is shown.
+pub(crate) fn init() {
+    #[cfg(target_os = "linux")]
+    if let Err(e) = slint::set_xdg_app_id(APP_ID) {
+        tracing::warn!("failed to set the app id: {e}");
+    }
+}
+
+/// Hook adjusting the attributes of the window the backend creates.
+pub(crate) fn window_attributes(
+    config: &WindowConfig,
+) -> impl Fn(WindowAttributes) -> WindowAttributes + 'static {
+    // "full" asks for server-side decorations on Wayland, falling back to
+    // drawing our own when the compositor has none (winit's default)
+    let decorations = config.decorations != "none";
+    #[cfg(target_os = "linux")]
+    let token = std::cell::Cell::new(take_activation_token());
+    move |attributes| {
+        let attributes = attributes.with_decorations(decorations);
+        #[cfg(target_os = "linux")]
+        if let Some(token) = token.take() {
+            use winit::platform::startup_notify::WindowAttributesExtStartupNotify;
+            use winit::window::ActivationToken;
+            return attributes.with_activation_token(ActivationToken::from_raw(token));
+        }
+        attributes
+    }
+}
+
+/// The token a launcher passed to focus our first window. It's removed from
+/// the environment so shells started in panes don't hand it on to whatever
+/// they run.
+#[cfg(target_os = "linux")]
+fn take_activation_token() -> Option<String> {
+    let token = ["XDG_ACTIVATION_TOKEN", "DESKTOP_STARTUP_ID"]
+        .into_iter()
+        .find_map(|var| std::env::var(var).ok().filter(|token| !token.is_empty()));
+    std::env::remove_var("XDG_ACTIVATION_TOKEN");
+    std::env::remove_var("DESKTOP_STARTUP_ID");
+    token
+}
+
+/// Physical pixels per logical pixel of the display the window is on. Not
+/// rounded: Wayland's fractional scaling and X11's Xft.dpi give 1.25, 1.5
+/// and so on. Before the window exists this is the main display's scale on
+/// macOS and 1 elsewhere.
+pub(crate) fn display_scale(window: &slint::Window) -> f64 {
+    let window_scale = window.with_winit_window(|window| window.scale_factor());
+    #[cfg(target_os = "macos")]
+    {
+        // Slint's wgpu backend may report 1 on Retina displays, but the
+        // window's own backing scale is right, and follows it onto a 1x
+        // external display where the main display's would not
+        window_scale.unwrap_or_else(main_display_scale)
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        window_scale.unwrap_or(1.0)
+    }
+}
+
+/// Backing scale of the main display, from CoreGraphics.
+#[cfg(target_os = "macos")]
+fn main_display_scale() -> f64 {
+    #[repr(C)]
+    struct CGRect {
+        origin_x: f64,
+        origin_y: f64,
+        size_width: f64,
+        size_height: f64,
+    }
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGMainDisplayID() -> u32;
+        fn CGDisplayPixelsWide(display: u32) -> usize;
+        fn CGDisplayBounds(display: u32) -> CGRect;
+    }
+
+    unsafe {
+        let display = CGMainDisplayID();
+        let hw_pixels = CGDisplayPixelsWide(display) as f64;
+        let bounds = CGDisplayBounds(display);
+        if bounds.size_width > 0.0 {
+            (hw_pixels / bounds.size_width).round().max(1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Bring the window to the front, or hide it if it's already focused, for
+/// the dropdown-style global hotkey. Hidden through the window system, as
+/// hiding the Slint window would end the event loop with no window left.
+pub(crate) fn toggle_window_visibility(window: &slint::Window) {
+    window.with_winit_window(|window| {
+        if window.is_visible() != Some(false) && window.has_focus() {
+            window.set_visible(false);
+            return;
+        }
+        window.set_visible(true);
+        window.set_minimized(false);
+        window.focus_window();
+    });
+}
+
+/// Make `text` the primary selection; a no-op without one.
+pub(crate) fn set_primary_selection(clipboard: &mut Clipboard, text: &str) {
+    #[cfg(target_os = "linux")]
+    {
+        use arboard::{LinuxClipboardKind, SetExtLinux};
+        let result = clipboard
+            .set()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text(text);
+        if let Err(e) = result {
+            tracing::debug!("failed to set the primary selection: {e}");
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = (clipboard, text);
+}
+
+/// Text of the primary selection, if there is one.
+pub(crate) fn primary_selection(clipboard: &mut Clipboard) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        use arboard::{GetExtLinux, LinuxClipboardKind};
+        clipboard
+            .get()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text()
+            .ok()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = clipboard;
+        None
+    }
+}
+
+/// Show a desktop notification: Notification Center on macOS, the
+/// freedesktop notification service elsewhere. Sent from a thread of its
+/// own, as the D-Bus round trip can take a while.
+pub(crate) fn show_notification(title: &str, body: &str) {
+    let mut notification = notify_rust::Notification::new();
+    notification.appname("pterminal").summary(title).body(body);
+    std::thread::spawn(move || {
+        if let Err(e) = notification.show() {
+            tracing::debug!("failed to show a notification: {e}");
+        }
+    });
+}
@@ -1,4 +1,4 @@
-use pterminal_plugin_api::SidebarViewContribution;
+use pterminal_plugin_api::{SidebarViewContribution, StatusSegmentContribution};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RegistrySidebarItem {
@@ -11,6 +11,7 @@ pub struct RegistrySidebarItem {
 pub struct ContributionRegistry {
     sidebar_views: Vec<SidebarViewContribution>,
     active_sidebar_view: Option<String>,
+    status_segments: Vec<StatusSegmentContribution>,
 }
 
 impl ContributionRegistry {
@@ -55,6 +56,27 @@ impl ContributionRegistry {
         self.set_active_sidebar(format!("builtin.workspace.{active_idx}"));
     }
 
+    pub fn replace_status_segments(&mut self, segments: Vec<StatusSegmentContribution>) {
+        self.status_segments = segments;
+    }
+
+    pub fn status_segments(&self) -> &[StatusSegmentContribution] {
+        &self.status_segments
+    }
+
+    pub fn status_segment(&self, id: &str) -> Option<&StatusSegmentContribution> {
+        self.status_segments.iter().find(|segment| segment.id == id)
+    }
+
+    /// Replace a contributed segment's text; false if no plugin contributes it
+    pub fn set_status_text(&mut self, id: &str, text: impl Into<String>) -> bool {
+        let segment = self
+            .status_segments
+            .iter_mut()
+            .find(|segment| segment.id == id);
+        segment.map(|segment| segment.text = text.into()).is_some()
+    }
+
     pub fn builtin_workspace_index(view_id: &str) -> Option<usize> {
         view_id.strip_prefix("builtin.workspace.")?.parse().ok()
     }
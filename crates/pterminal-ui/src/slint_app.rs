@@ -1,29 +1,80 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     mpsc::{self, Receiver, Sender},
-    Arc,
+    Arc, Mutex,
 };
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use arboard::Clipboard;
-use serde_json::{json, Value};
 use tracing::{info, warn};
 
+use pterminal_core::accessibility::{self, Announcer};
+use pterminal_core::appearance::{self, Appearance};
+use pterminal_core::color_vision::ColorVision;
 use pterminal_core::config::theme::Theme;
-use pterminal_core::split::{PaneId, SplitDirection};
-use pterminal_core::terminal::{PtyHandle, TerminalEmulator};
-use pterminal_core::workspace::WorkspaceManager;
-use pterminal_core::{Config, NotificationStore};
-use pterminal_ipc::{IpcServer, JsonRpcRequest, JsonRpcResponse};
-use pterminal_render::text::PixelRect;
+use pterminal_core::config::{keys, CaptureConfig, ConfigProblem, ZoomMode};
+use pterminal_core::debug_dump::{self, StateDump};
+use pterminal_core::env_info::PaneEnv;
+use pterminal_core::event::TermEvent;
+use pterminal_core::fuzzy::fuzzy_filter;
+use pterminal_core::git_info;
+use pterminal_core::hooks::{self, HookContext, HookEvent};
+use pterminal_core::input_history::InputHistory;
+use pterminal_core::keyboard_layout::{self, KeyboardLayout};
+use pterminal_core::link::{self, Link};
+use pterminal_core::log_ring;
+use pterminal_core::pane_log::{self, LogFormat, LogOptions, PaneLog};
+use pterminal_core::power;
+use pterminal_core::process_tree::{self, ProcessInfo, Signal};
+use pterminal_core::prompt_time;
+use pterminal_core::reconnect::{self, ReconnectWatch, WatchEvent};
+use pterminal_core::rich_text;
+use pterminal_core::scale::{self, ScaleTracker};
+use pterminal_core::shell_history;
+use pterminal_core::shell_words::ShellKind;
+use pterminal_core::snippet::{Placeholder, Snippet};
+use pterminal_core::split::{DropSide, PaneId, SplitDirection};
+use pterminal_core::ssh::SshTarget;
+use pterminal_core::status_bar::{self, StatusSegment};
+use pterminal_core::terminal::{
+    encode_key, ClampedLine, GridCell, GridLine, KeyMods, KeypadKey, Mark, PinnedScreen, Progress,
+    PtyHandle, ScreenReader, SearchMatch, SpecialKey, TermMetadata, TerminalEmulator,
+};
+use pterminal_core::update::{self, Release};
+use pterminal_core::workspace::layout::{self, PaneLayout, WorkspaceLayout};
+use pterminal_core::workspace::{WorkspaceId, WorkspaceManager};
+use pterminal_core::{
+    Config, Notification, NotificationRules, NotificationSource, NotificationStore,
+};
+#[cfg(target_os = "macos")]
+use pterminal_ipc::auth::Access;
+use pterminal_ipc::methods::{self as rpc, dispatch};
+use pterminal_ipc::protocol::RpcResult;
+#[cfg(target_os = "macos")]
+use pterminal_ipc::RequestGate;
+use pterminal_ipc::{IpcServer, JsonRpcError, JsonRpcRequest, JsonRpcResponse, RpcMethod};
+use pterminal_plugin_api::{discover_plugin_catalog, StatusSegmentContribution};
+use pterminal_render::border::{self, BorderStyle, Divider};
+use pterminal_render::post;
+use pterminal_render::text::{
+    CursorAnimation, Easing, FontFaces, LinkUnderline, OverlayCorner, PixelRect, ScrollAnimation,
+};
 use pterminal_render::{BgRect, OffscreenRenderer};
 
+use crate::context_menu::{self, ContextAction};
+use crate::file_drop::{self, drop_text, paste_text, start_upload, DropChoice, UploadResult};
+use crate::global_hotkeys::{GlobalHotkeys, HotkeyAction};
+use crate::keystrokes::{self, KeystrokeLog};
+#[cfg(target_os = "macos")]
+use crate::macos;
+use crate::platform;
 use crate::plugin::ContributionRegistry;
+use crate::wakeup;
 
 slint::include_modules!();
 
@@ -32,46 +83,6 @@ use slint::private_unstable_api::re_exports::{
     EventResult, KeyEvent, PointerEventButton, PointerEventKind,
 };
 
-// ---------------------------------------------------------------------------
-// Display scale detection (Slint wgpu-28 reports sf=1 on macOS Retina)
-// ---------------------------------------------------------------------------
-
-/// On macOS, Slint's wgpu backend may report scale_factor=1 even on Retina
-/// displays. Detect the real backing scale via CoreGraphics.
-#[cfg(target_os = "macos")]
-fn detect_display_scale() -> f64 {
-    #[repr(C)]
-    struct CGRect {
-        origin_x: f64,
-        origin_y: f64,
-        size_width: f64,
-        size_height: f64,
-    }
-
-    #[link(name = "CoreGraphics", kind = "framework")]
-    extern "C" {
-        fn CGMainDisplayID() -> u32;
-        fn CGDisplayPixelsWide(display: u32) -> usize;
-        fn CGDisplayBounds(display: u32) -> CGRect;
-    }
-
-    unsafe {
-        let display = CGMainDisplayID();
-        let hw_pixels = CGDisplayPixelsWide(display) as f64;
-        let bounds = CGDisplayBounds(display);
-        if bounds.size_width > 0.0 {
-            (hw_pixels / bounds.size_width).round().max(1.0)
-        } else {
-            1.0
-        }
-    }
-}
-
-#[cfg(not(target_os = "macos"))]
-fn detect_display_scale() -> f64 {
-    1.0
-}
-
 // ---------------------------------------------------------------------------
 // macOS titlebar customization
 // ---------------------------------------------------------------------------
@@ -109,14 +120,48 @@ unsafe fn configure_macos_titlebar(ns_view: *mut std::ffi::c_void) {
 // Supporting types (mirrored from app.rs for the Slint backend)
 // ---------------------------------------------------------------------------
 
+/// What a selection grows by while dragging or Shift+clicking
+#[derive(Clone, Copy, PartialEq)]
+enum SelectionUnit {
+    Cell,
+    Word,
+    Line,
+}
+
 /// Text selection range in grid coordinates
 #[derive(Clone, Copy, PartialEq)]
 struct Selection {
     start: (u16, u16), // (col, row)
     end: (u16, u16),
+    /// The cell, word or line first clicked (normalized); it stays selected
+    /// whichever way the selection is extended
+    anchor: ((u16, u16), (u16, u16)),
+    unit: SelectionUnit,
 }
 
 impl Selection {
+    /// Select `span`, the unit under a click
+    fn new(span: ((u16, u16), (u16, u16)), unit: SelectionUnit) -> Self {
+        Self {
+            start: span.0,
+            end: span.1,
+            anchor: span,
+            unit,
+        }
+    }
+
+    /// Extend from the anchor to cover `span`, the unit under the pointer
+    fn extend(&mut self, span: ((u16, u16), (u16, u16))) {
+        let key = |(col, row): (u16, u16)| (row, col);
+        if key(span.0) < key(self.anchor.0) {
+            self.start = self.anchor.1;
+            self.end = span.0;
+        } else {
+            self.start = self.anchor.0;
+            self.end = span.1;
+        }
+    }
+
     /// Normalize so start <= end (row-major order)
     fn normalized(&self) -> ((u16, u16), (u16, u16)) {
         if self.start.1 < self.end.1
@@ -127,6 +172,36 @@ impl Selection {
             (self.end, self.start)
         }
     }
+
+    /// Move down `shift` rows and fit a resized `cols` x `rows` screen,
+    /// clipping what falls off it. Returns false when nothing is left.
+    fn remap(&mut self, shift: i64, cols: u16, rows: u16) -> bool {
+        let (start, end) = self.normalized();
+        if end.1 as i64 + shift < 0 || start.1 as i64 + shift >= rows as i64 {
+            return false;
+        }
+        let last_col = cols.saturating_sub(1);
+        let fit = |(col, row): (u16, u16)| {
+            let row = row as i64 + shift;
+            if row < 0 {
+                (0, 0)
+            } else if row >= rows as i64 {
+                (last_col, rows.saturating_sub(1))
+            } else {
+                (col.min(last_col), row as u16)
+            }
+        };
+        self.start = fit(self.start);
+        self.end = fit(self.end);
+        self.anchor = (fit(self.anchor.0), fit(self.anchor.1));
+        true
+    }
+}
+
+/// Pane being dragged onto another pane's edge (Ctrl+Shift+drag)
+struct PaneDrag {
+    pane_id: PaneId,
+    target: Option<(PaneId, DropSide)>,
 }
 
 /// Per-pane terminal state
@@ -138,6 +213,58 @@ struct PaneState {
     render_grid: Vec<pterminal_core::terminal::GridLine>,
     render_dirty_rows: Vec<usize>,
     last_cursor_visible: bool,
+    /// No-wrap mode: the grid is wider than the viewport and panned horizontally
+    no_wrap: bool,
+    /// Horizontal scroll offset in columns (no-wrap mode only)
+    h_offset: u16,
+    /// Columns that fit in the pane's viewport
+    visible_cols: u16,
+    /// Whether the pane last reported itself focused (DECSET 1004)
+    focused: bool,
+    /// Title set by the shell (OSC 0/2); empty until one arrives
+    title: String,
+    /// Shell working directory and git branch, refreshed for the sidebar
+    cwd: Option<PathBuf>,
+    git_branch: Option<String>,
+    /// Python env and direnv state, refreshed with the cwd
+    env: PaneEnv,
+    /// ssh/mosh session to re-run if it drops
+    reconnect: ReconnectWatch,
+    /// Lines sent from the keyboard and IPC
+    input: InputHistory,
+    /// The foreground command matched `logging.auto` at the last check
+    auto_log: bool,
+    /// Log started for that match, stopped when the command ends
+    auto_log_path: Option<PathBuf>,
+    /// Last line cut short on screen, for Open Full Line
+    clamped_line: Option<ClampedLine>,
+    /// Progress the running program reports
+    progress: Option<Progress>,
+    /// Output held back while the pane is paused; `None` when it isn't
+    held_bytes: Option<usize>,
+    /// Output skipped while the pane fast-forwards past a flood; `None`
+    /// when it isn't
+    fast_forward: Option<u64>,
+    /// Why one of the pane's threads panicked; the pane waits for a restart
+    failed: Option<String>,
+    /// How long the pane's parser thread has gone without answering
+    unresponsive: Option<Duration>,
+    /// Flashed until then, after jumping to it from an alert
+    highlight_until: Option<Instant>,
+    /// The prompt timestamps gutter is shown along the left edge
+    prompt_gutter: bool,
+    /// Marks were added; their ticks are drawn along the right edge
+    marked: bool,
+    /// Screen pinned for comparison, shown through the live one
+    pinned: Option<PinnedScreen>,
+    /// The live screen with the pinned one shown through, as drawn
+    pinned_grid: Vec<pterminal_core::terminal::GridLine>,
+    /// Whether copies join wrapped lines, when switched from
+    /// `selection.join_wrapped`
+    join_wrapped: Option<bool>,
+    /// Grid kept through a font size change in scale zoom mode; `None`
+    /// while the grid fits the pane
+    scaled_grid: Option<ScaledGrid>,
 }
 
 struct IpcEnvelope {
@@ -145,6 +272,52 @@ struct IpcEnvelope {
     response_tx: Sender<JsonRpcResponse>,
 }
 
+/// Frame rate limiting: minimum interval between frames (~120fps max)
+const MIN_FRAME_INTERVAL: Duration = Duration::from_millis(8);
+/// How often the event loop wakes when nothing else wakes it
+const WATCHDOG_INTERVAL: Duration = Duration::from_millis(250);
+/// Shortest time between refreshes of one workspace's tab preview
+const PREVIEW_INTERVAL: Duration = Duration::from_millis(1000);
+/// Tab preview thumbnail width (physical pixels)
+const PREVIEW_WIDTH: u32 = 320;
+/// Levels the log viewer filters by, in the order of its buttons
+const LOG_LEVELS: [tracing::Level; 5] = [
+    tracing::Level::ERROR,
+    tracing::Level::WARN,
+    tracing::Level::INFO,
+    tracing::Level::DEBUG,
+    tracing::Level::TRACE,
+];
+/// Newest lines the log viewer shows
+const LOG_VIEWER_LINES: usize = 500;
+/// How often pane titles, cwd, git branch and env in the sidebar are refreshed
+const SIDEBAR_REFRESH_INTERVAL: Duration = Duration::from_millis(2000);
+/// How often foreground commands are sampled for dropped ssh sessions
+const RECONNECT_CHECK_INTERVAL: Duration = Duration::from_millis(1000);
+/// How often foreground commands are matched against `logging.auto`
+const AUTO_LOG_CHECK_INTERVAL: Duration = Duration::from_millis(1000);
+/// How often the OS appearance is read while `theme.follow_system` is on
+const APPEARANCE_CHECK_INTERVAL: Duration = Duration::from_millis(5000);
+/// How often the power source is read while `power.eco` is "auto"
+const POWER_CHECK_INTERVAL: Duration = Duration::from_millis(30_000);
+/// How often the OS is asked about reduced motion while
+/// `accessibility.reduce_motion` is "auto"
+const MOTION_CHECK_INTERVAL: Duration = Duration::from_millis(5000);
+/// How often the screen shown to screen readers is refreshed
+const SCREEN_READER_INTERVAL: Duration = Duration::from_millis(500);
+/// How often prompt timestamps are refreshed, so ages ("3m ago") keep up
+const PROMPT_GUTTER_INTERVAL: Duration = Duration::from_millis(1000);
+/// How often panes suspected of hanging are asked again whether they are
+const UNRESPONSIVE_CHECK_INTERVAL: Duration = Duration::from_millis(1000);
+/// Width of the prompt timestamps gutter, in cells
+const PROMPT_GUTTER_CELLS: f32 = 9.0;
+/// Most rows the picker overlay lists at once (matches the cap in app.slint)
+const PICKER_MAX_ITEMS: usize = 12;
+/// Matching lines of each pane a search counts; the newest are listed
+const SEARCH_MAX_MATCHES: usize = 1000;
+/// Matching lines the grep view lists; the newest are kept
+const GREP_MAX_LINES: usize = 5000;
+
 // ---------------------------------------------------------------------------
 // Shared mutable state accessible from Slint callbacks
 // ---------------------------------------------------------------------------
@@ -157,24 +330,205 @@ struct TerminalState {
     config: Config,
     theme: Arc<Theme>,
     /// Effective display scale (real Retina factor, may differ from Slint sf).
-    /// Used for font sizing, mouse mapping, padding.
+    /// Used for font sizing, padding and borders.
     scale_factor: f64,
-    /// Slint-reported scale factor. Used only for viewport resize math
-    /// (converting Slint lengths → drawable pixels).
+    /// Slint-reported scale factor. Used for converting Slint lengths and
+    /// pointer positions to drawable pixels.
     slint_scale_factor: f64,
+    /// Holds back scale changes while the window straddles two displays
+    scale_tracker: ScaleTracker,
     clipboard: Option<Clipboard>,
     selection: Option<Selection>,
+    pane_drag: Option<PaneDrag>,
+    /// Divider under the pointer, drawn highlighted
+    hovered_divider: Option<Divider>,
+    /// Link under the pointer: its pane, viewport row and span
+    hovered_link: Option<(PaneId, u16, Link)>,
     mouse_pressed: bool,
     last_mouse_pos: (f64, f64),
     last_click_time: Instant,
     last_click_pos: (u16, u16),
     click_count: u8,
     notifications: NotificationStore,
+    /// `config.notification`'s do not disturb and mute rules
+    notification_rules: NotificationRules,
     ipc_rx: Receiver<IpcEnvelope>,
     _ipc_server: Option<IpcServer>,
-    ipc_socket_path: PathBuf,
+    /// Screen readers for the IPC thread, kept current by the poll
+    screens: Arc<Mutex<SharedScreens>>,
     /// Frame rate limiting - last render time
     last_render_time: Instant,
+    /// Wakes the loop when a frame held back by the rate limit is due
+    frame_timer: slint::Timer,
+    /// Thumbnails of each workspace's active pane, shown when hovering its tab
+    tab_previews: HashMap<WorkspaceId, TabPreview>,
+    last_sidebar_refresh: Instant,
+    last_reconnect_check: Instant,
+    last_auto_log_check: Instant,
+    /// OS appearance the theme was last picked for (`theme.follow_system`)
+    appearance: Option<Appearance>,
+    /// Appearance reported by the window system, applied on the next poll
+    appearance_changed: Option<Appearance>,
+    last_appearance_check: Instant,
+    /// Eco mode is on: lower frame rate, no animations or shader
+    eco: bool,
+    /// Eco mode set from the toggle, overriding `power.eco`
+    eco_override: Option<bool>,
+    last_power_check: Instant,
+    /// Animations are off for `accessibility.reduce_motion`
+    reduce_motion: bool,
+    last_motion_check: Instant,
+    /// New output in `announcer_pane`, read out by screen readers
+    announcer: Announcer,
+    announcer_pane: Option<PaneId>,
+    last_screen_reader_update: Instant,
+    /// Prompt timestamps shown in gutters, as last handed to the UI
+    prompt_gutter: Vec<PromptTimeLabel>,
+    last_prompt_gutter_update: Instant,
+    last_unresponsive_check: Instant,
+    /// Active pane whose title the tab strip last showed
+    tab_title_pane: Option<PaneId>,
+    /// Entries of the context menu last opened, by index
+    context_menu: Vec<ContextAction>,
+    /// Status bar segments shown, with their text; empty when hidden
+    status_bar: Vec<(StatusSegment, String)>,
+    /// System-wide shortcuts, while any are registered
+    global_hotkeys: Option<GlobalHotkeys>,
+    /// Size and rate limits of AppleScript `call`s, the socket's
+    #[cfg(target_os = "macos")]
+    script_gate: RequestGate,
+    /// Whether the terminal area holds keyboard focus
+    window_focused: bool,
+    /// Whether the window is the focused one on the desktop
+    app_focused: bool,
+    /// Set while presentation mode is on
+    presentation: Option<Presentation>,
+    /// Recent keys for the on-screen keystroke overlay
+    keystrokes: KeystrokeLog,
+    /// Open picker overlay (snippets, history)
+    picker: Option<Picker>,
+    /// Theme shown on one pane while it is highlighted in the theme picker
+    theme_preview: Option<(PaneId, Arc<Theme>)>,
+    /// Monospace family `font.family` resolved to
+    font_family: Option<String>,
+    /// Workspace overview is open; its thumbnails follow preview refreshes
+    overview: bool,
+    /// Log viewer is open: the ring's push count when its lines were filled
+    log_viewer: Option<u64>,
+    /// Grep view is open over this pane, with the lines it lists
+    grep: Option<(PaneId, Vec<SearchMatch>)>,
+    /// Settings overlay is open; it is refilled whenever the config changes
+    settings: bool,
+    /// Key binding cheat sheet shown over the panes, while it is open
+    cheat_sheet: Option<String>,
+    /// Modifiers held, from the window system: Command is Super here on
+    /// macOS, where Slint's key events report it as Control
+    key_modifiers: winit::keyboard::ModifiersState,
+    /// Physical key (W3C code) of the key press being handled
+    pressed_key: Option<String>,
+    /// What each key typed without modifiers when last pressed, by code
+    typed_keys: HashMap<String, char>,
+    /// Layout those keys fit, naming keys in the cheat sheet
+    keyboard_layout: &'static KeyboardLayout,
+    /// Files dropped onto the window, handled on the next poll tick so a
+    /// multi-file drop is treated as one
+    dropped_files: Vec<PathBuf>,
+    upload_tx: Sender<UploadResult>,
+    upload_rx: Receiver<UploadResult>,
+    /// Update check running in the background, when one was due
+    update_rx: Option<Receiver<Result<Release>>>,
+}
+
+/// The picker overlay while it is open
+struct Picker {
+    /// Pane that receives the result
+    pane_id: PaneId,
+    mode: PickerMode,
+}
+
+enum PickerMode {
+    /// Choosing a snippet: indices into `config.snippets`, best match first
+    Snippets(Vec<usize>),
+    /// Asking for a chosen snippet's placeholder values, first pending one shown
+    SnippetValues {
+        snippet: Snippet,
+        pending: Vec<Placeholder>,
+        values: HashMap<String, String>,
+    },
+    /// Choosing a command from history to replace what was typed at the prompt
+    History {
+        /// Most recent first
+        entries: Vec<String>,
+        matches: Vec<usize>,
+        /// Text already typed at the prompt, erased before inserting
+        typed: String,
+    },
+    /// Choosing what to do with files dropped onto an ssh session
+    FileDrop {
+        files: Vec<PathBuf>,
+        target: SshTarget,
+        matches: Vec<usize>,
+    },
+    /// Choosing a process from the pane's process tree
+    Processes {
+        /// Tree order, with depth below the shell
+        entries: Vec<(usize, ProcessInfo)>,
+        matches: Vec<usize>,
+    },
+    /// Choosing the signal to send to a process
+    Signal { pid: u32, matches: Vec<usize> },
+    /// Choosing a pane to type into without switching to it
+    SendTarget {
+        /// Pane, label and "cwd · command" detail
+        panes: Vec<(PaneId, String, String)>,
+        matches: Vec<usize>,
+    },
+    /// Typing into the picker's pane; each Return sends one line
+    SendText,
+    /// Choosing a theme, previewed on the picker's pane while highlighted
+    Themes {
+        names: Vec<String>,
+        matches: Vec<usize>,
+    },
+    /// Typing the file to export the active workspace's layout to
+    ExportLayout,
+    /// Choosing a saved layout to open, or typing the path of another
+    ImportLayout {
+        files: Vec<PathBuf>,
+        matches: Vec<usize>,
+    },
+    /// Starting a log of the pane's output in a format (`None` stops it)
+    PaneLog {
+        choices: Vec<Option<LogFormat>>,
+        matches: Vec<usize>,
+    },
+    /// Choosing a mark to jump to, or adding one named by the query
+    Marks {
+        marks: Vec<Mark>,
+        /// Index into `marks` of each row; `None` is the row adding a mark
+        rows: Vec<Option<usize>>,
+    },
+    /// Searching the panes of the active workspace, or of all of them
+    Search {
+        all_workspaces: bool,
+        /// Rows: matches grouped by pane
+        hits: Vec<(PaneId, SearchMatch)>,
+    },
+}
+
+/// Columns and rows a pane keeps while zoomed in scale mode, through window
+/// resizes too. It fits the pane again once the font is back at the size
+/// it was kept at, or the pane is split.
+struct ScaledGrid {
+    cols: u16,
+    rows: u16,
+    /// Logical points
+    font_size: f32,
+}
+
+/// Settings put aside while presentation mode is on, restored when it ends
+struct Presentation {
+    theme: Arc<Theme>,
 }
 
 // ---------------------------------------------------------------------------
@@ -183,39 +537,68 @@ struct TerminalState {
 
 pub struct SlintApp {
     config: Config,
+    /// What loading the config file found, shown once the window is up
+    config_problems: Vec<ConfigProblem>,
 }
 
 impl SlintApp {
     pub fn new(config: Config) -> Self {
-        Self { config }
+        Self {
+            config,
+            config_problems: Vec::new(),
+        }
+    }
+
+    pub fn with_config_problems(mut self, problems: Vec<ConfigProblem>) -> Self {
+        self.config_problems = problems;
+        self
     }
 
     pub fn run(self) -> Result<()> {
         // 1. Select Slint wgpu-28 backend
         slint::BackendSelector::new()
             .require_wgpu_28(slint::wgpu_28::WGPUConfiguration::default())
+            .with_winit_window_attributes_hook(platform::window_attributes(&self.config.window))
             .select()
             .map_err(|e| anyhow::anyhow!("Slint backend: {e}"))?;
+        platform::init();
 
         // 2. Create AppWindow
         let app = AppWindow::new()?;
         let app_weak = app.as_weak();
+        app.set_tab_bar_position(self.config.window.tab_bar.as_str().into());
 
         // 3. Shared state
-        let theme = Arc::new(Theme::default());
+        let system_appearance = if self.config.theme.follow_system {
+            appearance::detect()
+        } else {
+            None
+        };
+        let theme = Arc::new(resolve_theme(&self.config, system_appearance));
         let workspace_mgr = WorkspaceManager::new();
         let mut contributions = ContributionRegistry::new();
         contributions.set_builtin_workspace_sidebar(
             workspace_mgr.workspace_count(),
             workspace_mgr.active_index(),
         );
+        contributions.replace_status_segments(plugin_status_segments());
         let clipboard = Clipboard::new().ok();
 
         let (ipc_tx, ipc_rx) = mpsc::channel::<IpcEnvelope>();
+        let (upload_tx, upload_rx) = mpsc::channel::<UploadResult>();
+        let update_rx = start_update_check(&self.config);
         let ipc_socket_path = Config::config_dir().join("pterminal.sock");
+        let socket_for_ipc = ipc_socket_path.clone();
+        let screens = Arc::new(Mutex::new(SharedScreens::default()));
+        let screens_for_ipc = Arc::clone(&screens);
         let ipc_server = match IpcServer::start(
             &ipc_socket_path,
             Arc::new(move |request: JsonRpcRequest| {
+                if let Some(response) =
+                    handle_ipc_off_thread(&request, &socket_for_ipc, &screens_for_ipc)
+                {
+                    return response;
+                }
                 let req_id = request.id.clone();
                 let (resp_tx, resp_rx) = mpsc::channel();
                 if ipc_tx
@@ -227,11 +610,13 @@ impl SlintApp {
                 {
                     return JsonRpcResponse::internal_error(req_id, "application unavailable");
                 }
+                wakeup::wake();
                 match resp_rx.recv_timeout(Duration::from_secs(2)) {
                     Ok(resp) => resp,
                     Err(_) => JsonRpcResponse::internal_error(req_id, "request timed out"),
                 }
             }),
+            &self.config.ipc,
         ) {
             Ok(server) => Some(server),
             Err(e) => {
@@ -241,7 +626,7 @@ impl SlintApp {
         };
 
         let slint_sf = app.window().scale_factor() as f64;
-        let display_sf = detect_display_scale();
+        let display_sf = platform::display_scale(app.window());
         let effective_sf = display_sf.max(slint_sf);
         info!(slint_sf, display_sf, effective_sf, "Scale factors");
 
@@ -254,25 +639,86 @@ impl SlintApp {
             theme: theme.clone(),
             scale_factor: effective_sf,
             slint_scale_factor: slint_sf,
+            scale_tracker: ScaleTracker::default(),
             clipboard,
             selection: None,
+            pane_drag: None,
+            hovered_divider: None,
+            hovered_link: None,
             mouse_pressed: false,
             last_mouse_pos: (0.0, 0.0),
             last_click_time: Instant::now() - Duration::from_secs(10),
             last_click_pos: (0, 0),
             click_count: 0,
             notifications: NotificationStore::new(),
+            notification_rules: NotificationRules::new(&self.config.notification),
             ipc_rx,
             _ipc_server: ipc_server,
-            ipc_socket_path,
+            screens,
             last_render_time: Instant::now() - Duration::from_millis(100),
+            frame_timer: slint::Timer::default(),
+            tab_previews: HashMap::new(),
+            last_sidebar_refresh: Instant::now(),
+            last_reconnect_check: Instant::now(),
+            last_auto_log_check: Instant::now(),
+            appearance: system_appearance,
+            appearance_changed: None,
+            last_appearance_check: Instant::now(),
+            eco: false,
+            eco_override: None,
+            last_power_check: Instant::now() - POWER_CHECK_INTERVAL,
+            reduce_motion: false,
+            last_motion_check: Instant::now() - MOTION_CHECK_INTERVAL,
+            announcer: Announcer::default(),
+            announcer_pane: None,
+            last_screen_reader_update: Instant::now(),
+            prompt_gutter: Vec::new(),
+            last_prompt_gutter_update: Instant::now(),
+            last_unresponsive_check: Instant::now(),
+            tab_title_pane: None,
+            context_menu: Vec::new(),
+            status_bar: Vec::new(),
+            global_hotkeys: GlobalHotkeys::register(&self.config.global_hotkeys),
+            #[cfg(target_os = "macos")]
+            script_gate: RequestGate::new(&self.config.ipc),
+            window_focused: false,
+            app_focused: true,
+            presentation: None,
+            keystrokes: KeystrokeLog::default(),
+            picker: None,
+            theme_preview: None,
+            font_family: None,
+            overview: false,
+            log_viewer: None,
+            grep: None,
+            settings: false,
+            cheat_sheet: None,
+            key_modifiers: winit::keyboard::ModifiersState::empty(),
+            pressed_key: None,
+            typed_keys: HashMap::new(),
+            keyboard_layout: &KeyboardLayout::US,
+            dropped_files: Vec::new(),
+            upload_tx,
+            upload_rx,
+            update_rx,
         }));
+        if let Some((title, body)) = config_problems_notification(&self.config_problems) {
+            state.borrow_mut().notifications.push(title, body);
+        }
+
+        // No config file yet: the first pane gets a welcome, and a starter
+        // file is written so that happens once
+        let first_run = !Config::config_path().exists();
+        if first_run {
+            if let Err(e) = Config::create_starter_file() {
+                warn!("Can't write a starter config file: {e:#}");
+            }
+        }
 
         // 4. Rendering notifier ─ runs on RenderingSetup and BeforeRendering
         {
             let state = state.clone();
             let app_weak = app_weak.clone();
-            let theme = theme.clone();
             let config = self.config.clone();
             app.window().set_rendering_notifier(move |rendering_state, graphics_api| {
                 match rendering_state {
@@ -282,18 +728,24 @@ impl SlintApp {
                             return;
                         };
                         let mut s = state.borrow_mut();
+                        if s.renderer.is_some() {
+                            recover_renderer(&mut s, device, queue);
+                            return;
+                        }
                         // Initial texture uses the drawable size from Slint.
                         // get_terminal_width/Height may not be available yet,
                         // so use a reasonable default; BeforeRendering will resize.
-                        let slint_sf = s.slint_scale_factor as f32;
                         let (init_w, init_h) = if let Some(app) = app_weak.upgrade() {
-                            let tw = (app.get_terminal_width() * slint_sf) as u32;
-                            let th = (app.get_terminal_height() * slint_sf) as u32;
+                            let (tw, th) = scale::drawable_size(
+                                app.get_terminal_width(),
+                                app.get_terminal_height(),
+                                s.slint_scale_factor,
+                            );
                             if tw > 0 && th > 0 { (tw, th) } else { (1920, 1216) }
                         } else {
                             (1920, 1216)
                         };
-                        let renderer = OffscreenRenderer::new(
+                        let mut renderer = OffscreenRenderer::new(
                             device.clone(),
                             queue.clone(),
                             init_w,
@@ -301,43 +753,78 @@ impl SlintApp {
                             s.scale_factor, // effective display scale for font
                             config.font.size,
                         );
-                        let (cols, rows) = calc_cols_rows(&renderer, s.scale_factor);
-                        let ps = spawn_pane_slint(&config, 0, cols, rows);
-                        s.pane_states.insert(0, ps);
+                        renderer.text_renderer.set_cell_metrics(
+                            config.font.line_height,
+                            config.font.letter_spacing,
+                        );
+                        let (cols, rows) = calc_cols_rows(&renderer, pane_padding(&s));
                         s.renderer = Some(renderer);
-                        info!(cols, rows, "Slint: initial pane spawned");
+                        if !open_startup_workspaces(&mut s, &app_weak) {
+                            let ps = spawn_pane_slint(&s, 0, cols, rows);
+                            if first_run {
+                                welcome_banner(&ps);
+                            }
+                            s.pane_states.insert(0, ps);
+                            info!(cols, rows, "Slint: initial pane spawned");
+                        }
+                        apply_font_family(&mut s);
+                        apply_animations(&mut s);
+                        apply_bidi(&mut s);
+                        apply_post_effect(&mut s);
+                        let ws = s.workspace_mgr.active_workspace();
+                        let context = HookContext {
+                            pane_id: Some(ws.active_pane()),
+                            workspace_id: Some(ws.id),
+                            cwd: Some(config.working_directory()),
+                            ..Default::default()
+                        };
+                        hooks::run(&config.hooks, HookEvent::Startup, context);
                     }
                     slint::RenderingState::BeforeRendering => {
                         let mut s = state.borrow_mut();
+                        // Nothing can be drawn on a lost device; Slint sets
+                        // rendering up again with a new one
+                        if s.renderer.as_ref().is_some_and(OffscreenRenderer::is_device_lost) {
+                            return;
+                        }
                         if let Some(app) = app_weak.upgrade() {
                             let sf = app.window().scale_factor() as f64;
-                            // Update effective scale if Slint's sf changed
+                            s.slint_scale_factor = sf;
+                            // Update effective scale if the display's changed
                             // (e.g., window moved to a different display, or sf
                             // was 1 at init and is now 2 after layout).
-                            let new_effective = sf.max(detect_display_scale());
-                            if (new_effective - s.scale_factor).abs() > 0.01 {
-                                s.scale_factor = new_effective;
-                                s.slint_scale_factor = sf;
-                                if let Some(renderer) = &mut s.renderer {
-                                    renderer
-                                        .text_renderer
-                                        .update_scale_factor(new_effective, config.font.size);
-                                }
+                            let display_sf = platform::display_scale(app.window());
+                            let seen = sf.max(display_sf);
+                            let current = s.scale_factor;
+                            let changed = s.scale_tracker.observe(current, seen, Instant::now());
+                            if let Some(scale) = changed {
+                                set_scale_factor(&mut s, scale);
+                            } else if s.scale_tracker.is_waiting() {
+                                app.window().request_redraw();
                             }
                             // Viewport resize — use Slint's sf for length→drawable
-                            let slint_sf = sf as f32;
-                            let tw = (app.get_terminal_width() * slint_sf) as u32;
-                            let th = (app.get_terminal_height() * slint_sf) as u32;
+                            let (tw, th) = scale::drawable_size(
+                                app.get_terminal_width(),
+                                app.get_terminal_height(),
+                                sf,
+                            );
+                            let mut resized = changed.is_some();
                             if let Some(renderer) = &mut s.renderer {
                                 if tw > 0
                                     && th > 0
                                     && (tw != renderer.width() || th != renderer.height())
                                 {
                                     renderer.resize(tw, th);
-                                    resize_active_workspace_panes(&mut s);
+                                    resized = true;
                                 }
                             }
+                            // New cell metrics or a new size: re-derive every
+                            // pane's grid and resize its PTY
+                            if resized {
+                                resize_active_workspace_panes(&mut s);
+                            }
                         }
+                        let theme = Arc::clone(&s.theme);
                         render_frame(&mut s, &theme, &app_weak);
                     }
                     _ => {}
@@ -356,6 +843,177 @@ impl SlintApp {
                 EventResult::Accept
             });
         }
+        {
+            let state = state.clone();
+            app.on_terminal_focus_changed(move |focused| {
+                let mut s = state.borrow_mut();
+                s.window_focused = focused;
+                sync_pane_focus(&mut s);
+            });
+        }
+
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_picker_query_edited(move |query| {
+                let mut s = state.borrow_mut();
+                filter_picker(&mut s, &app_weak2, &query);
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_picker_accepted(move |idx, query| {
+                let mut s = state.borrow_mut();
+                picker_accepted(&mut s, &app_weak2, idx, &query);
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_picker_cancelled(move || {
+                let mut s = state.borrow_mut();
+                close_picker(&mut s, &app_weak2);
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_picker_highlighted(move |idx| {
+                let mut s = state.borrow_mut();
+                picker_highlighted(&mut s, &app_weak2, idx);
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_overview_accepted(move |idx| {
+                let mut s = state.borrow_mut();
+                close_overview(&mut s, &app_weak2);
+                let Ok(idx) = usize::try_from(idx) else {
+                    return;
+                };
+                if idx < s.workspace_mgr.workspace_count() {
+                    s.workspace_mgr.select_workspace(idx);
+                    for ps in s.pane_states.values() {
+                        ps.dirty.store(true, Ordering::Relaxed);
+                    }
+                    update_tabs(&mut s, &app_weak2);
+                    request_redraw(&app_weak2);
+                }
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_overview_cancelled(move || {
+                let mut s = state.borrow_mut();
+                close_overview(&mut s, &app_weak2);
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_log_level_selected(move |level| {
+                let mut s = state.borrow_mut();
+                if let Some(app) = app_weak2.upgrade() {
+                    app.set_log_level(level.clamp(0, LOG_LEVELS.len() as i32 - 1));
+                }
+                update_log_viewer(&mut s, &app_weak2);
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_logs_closed(move || {
+                let mut s = state.borrow_mut();
+                close_log_viewer(&mut s, &app_weak2);
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_grep_query_edited(move |query| {
+                let mut s = state.borrow_mut();
+                filter_grep_view(&mut s, &app_weak2, &query);
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_grep_accepted(move |idx| {
+                let mut s = state.borrow_mut();
+                grep_accepted(&mut s, &app_weak2, idx);
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_grep_closed(move || {
+                let mut s = state.borrow_mut();
+                close_grep_view(&mut s, &app_weak2);
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_settings_option_set(move |key, text| {
+                let mut s = state.borrow_mut();
+                let value = serde_json::from_str(&text)
+                    .unwrap_or_else(|_| serde_json::Value::String(text.to_string()));
+                settings_option_set(&mut s, &app_weak2, &key, value);
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_settings_option_stepped(move |key, direction| {
+                let mut s = state.borrow_mut();
+                settings_option_stepped(&mut s, &app_weak2, &key, direction);
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_settings_binding_edited(move |idx, keys| {
+                let mut s = state.borrow_mut();
+                let bindings = sorted_bindings(&s.config.keybindings);
+                let Some((old, action)) = usize::try_from(idx).ok().and_then(|i| bindings.get(i))
+                else {
+                    return;
+                };
+                let new = Some((keys.as_str(), action.as_str()));
+                settings_bindings_changed(&mut s, &app_weak2, Some(old.as_str()), new);
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_settings_binding_removed(move |idx| {
+                let mut s = state.borrow_mut();
+                let bindings = sorted_bindings(&s.config.keybindings);
+                if let Some((old, _)) = usize::try_from(idx).ok().and_then(|i| bindings.get(i)) {
+                    settings_bindings_changed(&mut s, &app_weak2, Some(old.as_str()), None);
+                }
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_settings_binding_added(move |keys, action| {
+                let mut s = state.borrow_mut();
+                let new = Some((keys.as_str(), action.as_str()));
+                settings_bindings_changed(&mut s, &app_weak2, None, new)
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_settings_closed(move || {
+                let mut s = state.borrow_mut();
+                close_settings(&mut s, &app_weak2);
+            });
+        }
 
         // 6. Tab callbacks
         {
@@ -370,6 +1028,27 @@ impl SlintApp {
                 update_tabs(&mut s, &app_weak2);
             });
         }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_tab_hovered(move |idx| {
+                let s = state.borrow();
+                let Some(app) = app_weak2.upgrade() else { return };
+                // The active workspace is already on screen; only preview others
+                let preview = usize::try_from(idx)
+                    .ok()
+                    .filter(|&i| i != s.workspace_mgr.active_index())
+                    .and_then(|i| s.workspace_mgr.workspaces().get(i))
+                    .and_then(|ws| s.tab_previews.get(&ws.id));
+                match preview {
+                    Some(preview) => {
+                        app.set_tab_preview(preview.image.clone());
+                        app.set_tab_preview_visible(true);
+                    }
+                    None => app.set_tab_preview_visible(false),
+                }
+            });
+        }
         {
             let state = state.clone();
             let app_weak2 = app_weak.clone();
@@ -399,16 +1078,44 @@ impl SlintApp {
             let state = state.clone();
             let app_weak2 = app_weak.clone();
             app.on_new_tab_clicked(move || {
+                new_workspace(&mut state.borrow_mut(), &app_weak2);
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_restart_failed_pane(move || {
                 let mut s = state.borrow_mut();
-                let (_ws_id, pane_id) = s.workspace_mgr.add_workspace();
-                let (cols, rows) = if let Some(renderer) = &s.renderer {
-                    calc_cols_rows(renderer, s.scale_factor)
-                } else {
-                    (80, 24)
-                };
-                let ps = spawn_pane_slint(&s.config, pane_id, cols, rows);
-                s.pane_states.insert(pane_id, ps);
-                update_tabs(&mut s, &app_weak2);
+                let active = s.workspace_mgr.active_workspace().active_pane();
+                restart_pane(&mut s, &app_weak2, active);
+            });
+        }
+        {
+            let state = state.clone();
+            app.on_dump_pane_diagnostics(move || {
+                save_state_dump(&mut state.borrow_mut());
+            });
+        }
+        {
+            let state = state.clone();
+            app.on_prompt_time_clicked(move |index| {
+                let mut s = state.borrow_mut();
+                if let Ok(index) = usize::try_from(index) {
+                    copy_prompt_time(&mut s, index);
+                }
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_context_menu_selected(move |index| {
+                let mut s = state.borrow_mut();
+                let action = usize::try_from(index)
+                    .ok()
+                    .and_then(|index| s.context_menu.get(index).cloned());
+                if let Some(action) = action {
+                    run_context_action(&mut s, &app_weak2, action);
+                }
             });
         }
 
@@ -438,42 +1145,156 @@ impl SlintApp {
                 update_tabs(&mut s, &app_weak2);
             });
         }
-
-        // 8. Mouse callbacks
         {
             let state = state.clone();
             let app_weak2 = app_weak.clone();
-            let theme = theme.clone();
-            app.on_terminal_pointer_event(move |event, x, y| {
+            app.on_sidebar_pane_clicked(move |pane_id| {
                 let mut s = state.borrow_mut();
-                let sf = s.scale_factor as f32;
-                let phys_x = x * sf;
-                let phys_y = y * sf;
-                s.last_mouse_pos = (phys_x as f64, phys_y as f64);
-
-                let is_left_button = event.button == PointerEventButton::Left;
-                if !is_left_button {
+                let pane_id = pane_id as PaneId;
+                let Some(ws_idx) = s.workspace_mgr.workspace_index_of(pane_id) else {
                     return;
+                };
+                s.workspace_mgr.select_workspace(ws_idx);
+                s.workspace_mgr
+                    .active_workspace_mut()
+                    .set_active_pane(pane_id);
+                for ps in s.pane_states.values() {
+                    ps.dirty.store(true, Ordering::Relaxed);
                 }
-
-                match event.kind {
-                    PointerEventKind::Down => {
-                        // Determine which pane was clicked
-                        if let Some(clicked_pane) = pane_at_pixel(&s, phys_x, phys_y) {
-                            let prev_active = s.workspace_mgr.active_workspace().active_pane();
-                            if prev_active != clicked_pane {
-                                s.workspace_mgr
-                                    .active_workspace_mut()
-                                    .set_active_pane(clicked_pane);
-                                for ps in s.pane_states.values() {
-                                    ps.dirty.store(true, Ordering::Relaxed);
-                                }
-                            }
-                        }
-
-                        s.mouse_pressed = true;
-                        let active = s.workspace_mgr.active_workspace().active_pane();
-                        let cell = pixel_to_cell(&s, active);
+                update_tabs(&mut s, &app_weak2);
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_sidebar_pane_dropped(move |pane_id, ws_idx| {
+                let mut s = state.borrow_mut();
+                let pane_id = pane_id as PaneId;
+                let Some(src_idx) = s.workspace_mgr.workspace_index_of(pane_id) else {
+                    return;
+                };
+                let src_id = s.workspace_mgr.workspaces()[src_idx].id;
+                if !s.workspace_mgr.move_pane(pane_id, ws_idx as usize) {
+                    return;
+                }
+                // The PTY stays with its PaneState; only the split trees change.
+                // Resize the source workspace (if it survived) and the target.
+                if let Some(src_idx) = s
+                    .workspace_mgr
+                    .workspaces()
+                    .iter()
+                    .position(|ws| ws.id == src_id)
+                {
+                    let target_idx = s.workspace_mgr.active_index();
+                    s.workspace_mgr.select_workspace(src_idx);
+                    resize_active_workspace_panes(&mut s);
+                    s.workspace_mgr.select_workspace(target_idx);
+                }
+                resize_active_workspace_panes(&mut s);
+                for ps in s.pane_states.values() {
+                    ps.dirty.store(true, Ordering::Relaxed);
+                }
+                update_tabs(&mut s, &app_weak2);
+            });
+        }
+
+        // 8. Mouse callbacks
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_terminal_pointer_event(move |event, x, y| {
+                let mut s = state.borrow_mut();
+                let (phys_x, phys_y) = scale::to_physical(x, y, s.slint_scale_factor);
+                s.last_mouse_pos = (phys_x as f64, phys_y as f64);
+
+                if event.kind == PointerEventKind::Move {
+                    let hovered = link_at_pointer(&s);
+                    set_hovered_link(&mut s, &app_weak2, hovered);
+                    return;
+                }
+
+                // Middle click pastes the primary selection, as on X11
+                if event.button == PointerEventButton::Middle
+                    && event.kind == PointerEventKind::Down
+                {
+                    if let Some(pane_id) = pane_at_pixel(&s, phys_x, phys_y) {
+                        paste_primary_selection(&mut s, &app_weak2, pane_id);
+                    }
+                    return;
+                }
+
+                // Right click opens the pane's context menu
+                if event.button == PointerEventButton::Right {
+                    if event.kind == PointerEventKind::Down {
+                        if let Some(pane_id) = pane_at_pixel(&s, phys_x, phys_y) {
+                            open_context_menu(&mut s, &app_weak2, pane_id, x, y);
+                        }
+                    }
+                    return;
+                }
+
+                let is_left_button = event.button == PointerEventButton::Left;
+                if !is_left_button {
+                    return;
+                }
+
+                if event.kind == PointerEventKind::Down {
+                    let segment = s
+                        .renderer
+                        .as_ref()
+                        .and_then(|r| r.text_renderer.status_bar_segment_at(phys_x, phys_y));
+                    if let Some(idx) = segment {
+                        click_status_segment(&mut s, &app_weak2, idx);
+                        return;
+                    }
+                }
+
+                match event.kind {
+                    PointerEventKind::Down => {
+                        // Ctrl+Shift+drag picks up a pane to rearrange the split
+                        if event.modifiers.control
+                            && event.modifiers.shift
+                            && s.workspace_mgr.active_workspace().pane_ids().len() > 1
+                        {
+                            if let Some(pane_id) = pane_at_pixel(&s, phys_x, phys_y) {
+                                s.pane_drag = Some(PaneDrag {
+                                    pane_id,
+                                    target: None,
+                                });
+                                return;
+                            }
+                        }
+
+                        // Determine which pane was clicked
+                        if let Some(clicked_pane) = pane_at_pixel(&s, phys_x, phys_y) {
+                            let prev_active = s.workspace_mgr.active_workspace().active_pane();
+                            if prev_active != clicked_pane {
+                                s.workspace_mgr
+                                    .active_workspace_mut()
+                                    .set_active_pane(clicked_pane);
+                                for ps in s.pane_states.values() {
+                                    ps.dirty.store(true, Ordering::Relaxed);
+                                }
+                            }
+                        }
+
+                        s.mouse_pressed = true;
+                        let active = s.workspace_mgr.active_workspace().active_pane();
+                        let cell = pixel_to_cell(&s, active);
+
+                        // Shift+click extends the selection by its unit
+                        if event.modifiers.shift {
+                            if let Some(unit) = s.selection.map(|sel| sel.unit) {
+                                let span = selection_span(&s, unit, cell);
+                                if let Some(sel) = &mut s.selection {
+                                    sel.extend(span);
+                                }
+                                mark_pane_dirty(&s, active);
+                                request_redraw(&app_weak2);
+                                return;
+                            }
+                        }
+
                         let now = Instant::now();
                         let double_click_threshold = Duration::from_millis(400);
 
@@ -487,27 +1308,32 @@ impl SlintApp {
                         s.last_click_time = now;
                         s.last_click_pos = cell;
 
-                        match s.click_count {
-                            2 => {
-                                s.selection =
-                                    Some(word_selection_at(&s, &theme, cell.0, cell.1));
-                            }
-                            3 => {
-                                s.selection = Some(line_selection_at(&s, cell.1));
-                            }
-                            _ => {
-                                s.selection = Some(Selection {
-                                    start: cell,
-                                    end: cell,
-                                });
-                            }
-                        }
+                        let unit = match s.click_count {
+                            2 => SelectionUnit::Word,
+                            3 => SelectionUnit::Line,
+                            _ => SelectionUnit::Cell,
+                        };
+                        s.selection = Some(Selection::new(selection_span(&s, unit, cell), unit));
                         if let Some(ps) = s.pane_states.get(&active) {
                             ps.dirty.store(true, Ordering::Relaxed);
                         }
                         request_redraw(&app_weak2);
                     }
                     PointerEventKind::Up => {
+                        if let Some(drag) = s.pane_drag.take() {
+                            if let Some((target, side)) = drag.target {
+                                let ws = s.workspace_mgr.active_workspace_mut();
+                                if ws.split_tree.move_pane(drag.pane_id, target, side) {
+                                    ws.set_active_pane(drag.pane_id);
+                                    resize_active_workspace_panes(&mut s);
+                                }
+                            }
+                            for ps in s.pane_states.values() {
+                                ps.dirty.store(true, Ordering::Relaxed);
+                            }
+                            request_redraw(&app_weak2);
+                            return;
+                        }
                         s.mouse_pressed = false;
                         // Clear zero-length selection on single-click release
                         if s.click_count <= 1 {
@@ -522,6 +1348,11 @@ impl SlintApp {
                                 }
                             }
                         }
+                        if let Some(text) = get_selected_text(&s) {
+                            if let Some(clip) = &mut s.clipboard {
+                                platform::set_primary_selection(clip, &text);
+                            }
+                        }
                         request_redraw(&app_weak2);
                     }
                     _ => {}
@@ -533,20 +1364,49 @@ impl SlintApp {
             let app_weak2 = app_weak.clone();
             app.on_terminal_pointer_move(move |x, y| {
                 let mut s = state.borrow_mut();
-                let sf = s.scale_factor as f32;
-                let phys_x = x * sf;
-                let phys_y = y * sf;
+                let (phys_x, phys_y) = scale::to_physical(x, y, s.slint_scale_factor);
                 s.last_mouse_pos = (phys_x as f64, phys_y as f64);
 
-                if s.mouse_pressed && s.click_count <= 1 {
+                if let Some(prev) = s.pane_drag.as_ref().map(|drag| drag.target) {
+                    let target = drop_target_at(&s, phys_x, phys_y);
+                    if prev != target {
+                        if let Some(drag) = &mut s.pane_drag {
+                            drag.target = target;
+                        }
+                        let active = s.workspace_mgr.active_workspace().active_pane();
+                        if let Some(ps) = s.pane_states.get(&active) {
+                            ps.dirty.store(true, Ordering::Relaxed);
+                        }
+                        request_redraw(&app_weak2);
+                    }
+                    return;
+                }
+
+                if s.config.borders.hover_highlight && !s.mouse_pressed {
+                    let hovered = divider_at(&s, phys_x, phys_y);
+                    if s.hovered_divider != hovered {
+                        s.hovered_divider = hovered;
+                        let active = s.workspace_mgr.active_workspace().active_pane();
+                        if let Some(ps) = s.pane_states.get(&active) {
+                            ps.dirty.store(true, Ordering::Relaxed);
+                        }
+                        request_redraw(&app_weak2);
+                    }
+                }
+
+                // Drag extends by the unit clicked: cells, words or lines
+                if s.mouse_pressed {
                     let active = s.workspace_mgr.active_workspace().active_pane();
                     let cell = pixel_to_cell(&s, active);
-                    if let Some(sel) = &mut s.selection {
-                        if sel.end != cell {
-                            sel.end = cell;
-                            if let Some(ps) = s.pane_states.get(&active) {
-                                ps.dirty.store(true, Ordering::Relaxed);
-                            }
+                    if let Some(unit) = s.selection.map(|sel| sel.unit) {
+                        let span = selection_span(&s, unit, cell);
+                        let Some(sel) = &mut s.selection else {
+                            return;
+                        };
+                        let before = *sel;
+                        sel.extend(span);
+                        if *sel != before {
+                            mark_pane_dirty(&s, active);
                             request_redraw(&app_weak2);
                         }
                     }
@@ -556,70 +1416,123 @@ impl SlintApp {
         {
             let state = state.clone();
             let app_weak2 = app_weak.clone();
-            app.on_terminal_scroll(move |_dx, dy| {
-                let s = state.borrow_mut();
-                let (_, cell_h) = if let Some(r) = &s.renderer {
-                    r.text_renderer.cell_size()
+            app.on_terminal_pointer_exit(move || {
+                set_hovered_link(&mut state.borrow_mut(), &app_weak2, None);
+            });
+        }
+        {
+            let state = state.clone();
+            let app_weak2 = app_weak.clone();
+            app.on_terminal_scroll(move |dx, dy, shift| {
+                let mut s = state.borrow_mut();
+                // The content moves under the pointer; hover again to re-detect
+                set_hovered_link(&mut s, &app_weak2, None);
+                let active = s.workspace_mgr.active_workspace().active_pane();
+                let (cell_w, cell_h) = if let Some(r) = &s.renderer {
+                    r.text_renderer.pane_cell_size(active)
                 } else {
                     return;
                 };
-                let sf = s.scale_factor as f32;
-                let lines = (dy * sf / cell_h).round() as i32;
-                if lines != 0 {
-                    let active = s.workspace_mgr.active_workspace().active_pane();
-                    if let Some(ps) = s.pane_states.get(&active) {
-                        ps.emulator.scroll(lines);
-                        ps.dirty.store(true, Ordering::Relaxed);
-                        request_redraw(&app_weak2);
+                let sf = s.slint_scale_factor as f32;
+
+                // Horizontal scrolling (trackpad dx, or shift+wheel on a
+                // no-wrap pane) goes to applications that asked for mouse
+                // reports, as wheel buttons 6 and 7; otherwise no-wrap panes
+                // pan
+                let no_wrap = s.pane_states.get(&active).is_some_and(|ps| ps.no_wrap);
+                let (dx, dy) = wheel_axes(dx, dy, shift, no_wrap);
+                let cols = (dx * sf / cell_w).round() as i32;
+                let (col, row) = pixel_to_cell(&s, active);
+                if let Some(ps) = s.pane_states.get_mut(&active) {
+                    let modes = if cols != 0 {
+                        ps.emulator.input_modes()
+                    } else {
+                        Default::default()
+                    };
+                    if modes.mouse_reporting {
+                        // Content following the fingers rightwards means
+                        // scrolling left
+                        let button = if cols > 0 { 6 } else { 7 };
+                        if let Some(report) = modes.wheel_report(button, col, row) {
+                            let _ = ps.pty.write(&report.repeat(cols.unsigned_abs() as usize));
+                        }
+                    } else if ps.no_wrap && cols != 0 {
+                        let grid_cols = ps.emulator.metadata().cols;
+                        let max_offset = grid_cols.saturating_sub(ps.visible_cols) as i32;
+                        let offset = (ps.h_offset as i32 - cols).clamp(0, max_offset) as u16;
+                        if offset != ps.h_offset {
+                            ps.h_offset = offset;
+                            ps.dirty.store(true, Ordering::Relaxed);
+                            request_redraw(&app_weak2);
+                        }
                     }
                 }
+
+                let multiplier = s.config.scrollback.trackpad_multiplier;
+                let lines = (dy * sf / cell_h * multiplier).round() as i32;
+                if lines != 0 {
+                    scroll_linked_panes(&mut s, active, lines);
+                    request_redraw(&app_weak2);
+                }
             });
         }
 
-        // 9. Timer for polling dirty flags & dead panes
-        // Frame rate limiting: 8ms minimum interval (~120fps max)
-        const MIN_FRAME_INTERVAL_MS: u64 = 8;
-        let poll_timer = slint::Timer::default();
+        // 9. Wake on PTY output, pane exits and IPC requests; a slow watchdog
+        // covers periodic work (sidebar refresh, reconnect checks, expiring
+        // keystrokes) and anything a wakeup missed
         {
             let state = state.clone();
             let app_weak2 = app_weak.clone();
-            poll_timer.start(
-                slint::TimerMode::Repeated,
-                Duration::from_millis(4),
-                move || {
-                    let s = state.borrow();
-                    let active_panes = s.workspace_mgr.active_workspace().pane_ids();
-                    let any_dirty = active_panes.iter().any(|pid| {
-                        s.pane_states
-                            .get(pid)
-                            .map_or(false, |ps| ps.dirty.load(Ordering::Relaxed))
-                    });
-                    let any_dead = s.pane_states.values().any(|ps| !ps.pty.is_alive());
-
-                    // Frame rate limiting: skip redraw if too recent
-                    let now = Instant::now();
-                    let elapsed = now.duration_since(s.last_render_time);
-                    let should_render = elapsed >= Duration::from_millis(MIN_FRAME_INTERVAL_MS);
-                    drop(s);
-
-                    if any_dead {
-                        handle_dead_panes(&state, &app_weak2);
-                    }
-
-                    // Only request redraw if dirty AND enough time has passed
-                    if (any_dirty || any_dead) && should_render {
-                        request_redraw(&app_weak2);
-                    }
-
-                    // Handle IPC requests
-                    handle_ipc_requests(&state, &app_weak2);
-                },
-            );
+            wakeup::set_handler(move || poll_state(&state, &app_weak2));
         }
+        let watchdog = slint::Timer::default();
+        watchdog.start(slint::TimerMode::Repeated, WATCHDOG_INTERVAL, wakeup::run);
 
         // 10. Initial tab bar state
         update_tabs(&mut state.borrow_mut(), &app_weak);
 
+        // Files dropped from Finder / Explorer; winit reports one event per file.
+        // Every other window event also wakes the poll.
+        {
+            use slint::winit_030::{EventResult, WinitWindowAccessor};
+            let state = state.clone();
+            app.window().on_winit_window_event(move |_, event| {
+                match event {
+                    winit::event::WindowEvent::DroppedFile(path) => {
+                        state.borrow_mut().dropped_files.push(path.clone());
+                    }
+                    winit::event::WindowEvent::Focused(focused) => {
+                        // Pane opacity may depend on window focus
+                        let mut s = state.borrow_mut();
+                        s.app_focused = *focused;
+                        for ps in s.pane_states.values() {
+                            ps.dirty.store(true, Ordering::Relaxed);
+                        }
+                    }
+                    winit::event::WindowEvent::ModifiersChanged(modifiers) => {
+                        state.borrow_mut().key_modifiers = modifiers.state();
+                    }
+                    winit::event::WindowEvent::KeyboardInput { event, .. }
+                        if event.state.is_pressed() =>
+                    {
+                        record_key_press(&mut state.borrow_mut(), event);
+                    }
+                    winit::event::WindowEvent::ThemeChanged(theme) => {
+                        state.borrow_mut().appearance_changed = Some(match theme {
+                            winit::window::Theme::Dark => Appearance::Dark,
+                            winit::window::Theme::Light => Appearance::Light,
+                        });
+                    }
+                    _ => {}
+                }
+                // Input may change focus or the active pane; sync after it
+                if !matches!(event, winit::event::WindowEvent::RedrawRequested) {
+                    wakeup::wake();
+                }
+                EventResult::Propagate
+            });
+        }
+
         // 11. Customize macOS titlebar to blend with terminal background
         #[cfg(target_os = "macos")]
         {
@@ -637,6 +1550,18 @@ impl SlintApp {
             });
         }
 
+        // Replace winit's default menu bar once AppKit has finished launching
+        #[cfg(target_os = "macos")]
+        {
+            let scripting = self.config.ipc.applescript;
+            slint::Timer::single_shot(Duration::ZERO, move || unsafe {
+                macos::install_menus();
+                if scripting {
+                    macos::install_script_handler();
+                }
+            });
+        }
+
         // 12. Focus terminal and run
         app.invoke_focus_terminal();
         app.run()?;
@@ -648,6 +1573,150 @@ impl SlintApp {
 // Helpers
 // ---------------------------------------------------------------------------
 
+/// Work done on each wakeup: redraw dirty panes, reap dead ones, answer IPC
+/// requests and run periodic refreshes that are due.
+fn poll_state(state: &Rc<RefCell<TerminalState>>, app_weak: &slint::Weak<AppWindow>) {
+    let s = state.borrow();
+    let active_panes = s.workspace_mgr.active_workspace().pane_ids();
+    let animating = s
+        .renderer
+        .as_ref()
+        .is_some_and(|r| r.text_renderer.is_animating());
+    let any_dirty = animating
+        || active_panes.iter().any(|pid| {
+            s.pane_states
+                .get(pid)
+                .is_some_and(|ps| ps.dirty.load(Ordering::Relaxed))
+        });
+    let any_dead = s.pane_states.values().any(|ps| !ps.pty.is_alive());
+
+    // Frame rate limiting: skip redraw if too recent
+    let elapsed = s.last_render_time.elapsed();
+    let keystroke_timeout = Duration::from_millis(s.config.keystrokes.timeout_ms);
+    let keystrokes_expired = s.keystrokes.has_expired(keystroke_timeout);
+    let frame_interval = frame_interval(&s);
+    if (any_dirty || keystrokes_expired) && elapsed < frame_interval {
+        // Come back when the next frame is due
+        if !s.frame_timer.running() {
+            s.frame_timer.start(
+                slint::TimerMode::SingleShot,
+                frame_interval - elapsed,
+                wakeup::run,
+            );
+        }
+    }
+    drop(s);
+
+    if any_dead {
+        handle_dead_panes(state, app_weak);
+    }
+
+    // Only request redraw if dirty AND enough time has passed
+    if (any_dirty || any_dead || keystrokes_expired) && elapsed >= frame_interval {
+        request_redraw(app_weak);
+    }
+
+    handle_ipc_requests(state, app_weak);
+
+    let mut s = state.borrow_mut();
+    publish_screens(&s);
+    #[cfg(target_os = "macos")]
+    {
+        for action in macos::take_actions() {
+            run_menu_action(&mut s, app_weak, action);
+        }
+        for call in macos::take_script_calls() {
+            let result = run_script_call(&mut s, app_weak, &call);
+            call.reply(result);
+        }
+        macos::set_dock_badge(s.notifications.unread_count());
+        let progress = s.pane_states.values().filter_map(|ps| ps.progress);
+        let progress = Progress::overall(progress).filter(|_| s.config.progress.dock);
+        macos::set_dock_progress(progress);
+    }
+    let hotkey_actions = s
+        .global_hotkeys
+        .as_ref()
+        .map(GlobalHotkeys::take_actions)
+        .unwrap_or_default();
+    for action in hotkey_actions {
+        match action {
+            HotkeyAction::ToggleWindow => {
+                if let Some(app) = app_weak.upgrade() {
+                    platform::toggle_window_visibility(app.window());
+                }
+            }
+            HotkeyAction::PasteClipboard => paste_clipboard(&mut s, app_weak),
+        }
+    }
+    sync_pane_focus(&mut s);
+    let active_pane = s.workspace_mgr.active_workspace().active_pane();
+    let refocused = s.tab_title_pane.replace(active_pane) != Some(active_pane);
+    if poll_term_events(&mut s) || refocused {
+        update_tabs(&mut s, app_weak);
+    }
+    for ps in s.pane_states.values_mut() {
+        if ps
+            .highlight_until
+            .is_some_and(|until| until <= Instant::now())
+        {
+            ps.highlight_until = None;
+            ps.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+    if s.last_unresponsive_check.elapsed() >= UNRESPONSIVE_CHECK_INTERVAL {
+        s.last_unresponsive_check = Instant::now();
+        check_unresponsive_panes(&mut s);
+    }
+    update_paused_badge(&s, app_weak);
+    update_failure_banner(&s, app_weak);
+    let logged = log_ring::global().pushed();
+    if s.log_viewer.is_some_and(|seen| seen != logged) {
+        update_log_viewer(&mut s, app_weak);
+    }
+    sync_system_theme(&mut s, app_weak);
+    sync_power_source(&mut s, app_weak);
+    sync_reduced_motion(&mut s);
+    if s.last_screen_reader_update.elapsed() >= SCREEN_READER_INTERVAL {
+        s.last_screen_reader_update = Instant::now();
+        update_screen_reader(&mut s, app_weak);
+    }
+    if s.last_prompt_gutter_update.elapsed() >= PROMPT_GUTTER_INTERVAL {
+        update_prompt_gutter(&mut s, app_weak);
+    }
+    let status_bar = status_bar_segments(&s);
+    if status_bar != s.status_bar {
+        s.status_bar = status_bar;
+        request_redraw(app_weak);
+    }
+    handle_dropped_files(&mut s, app_weak);
+    while let Ok(upload) = s.upload_rx.try_recv() {
+        finish_upload(&mut s, upload);
+    }
+    if let Some(Ok(result)) = s.update_rx.as_ref().map(Receiver::try_recv) {
+        s.update_rx = None;
+        finish_update_check(&mut s, app_weak, result);
+    }
+    if s.config.reconnect.enabled && s.last_reconnect_check.elapsed() >= RECONNECT_CHECK_INTERVAL {
+        s.last_reconnect_check = Instant::now();
+        check_reconnects(&mut s);
+    }
+    if !s.config.logging.auto.is_empty()
+        && s.last_auto_log_check.elapsed() >= AUTO_LOG_CHECK_INTERVAL
+    {
+        s.last_auto_log_check = Instant::now();
+        check_auto_logs(&mut s, app_weak);
+    }
+    refresh_background_preview(&mut s, app_weak);
+    if s.last_sidebar_refresh.elapsed() >= SIDEBAR_REFRESH_INTERVAL {
+        s.last_sidebar_refresh = Instant::now();
+        if s.workspace_mgr.workspace_count() > 1 {
+            refresh_pane_info(&mut s);
+            update_sidebar(&mut s, app_weak);
+        }
+    }
+}
+
 fn request_redraw(app_weak: &slint::Weak<AppWindow>) {
     if let Some(app) = app_weak.upgrade() {
         app.window().request_redraw();
@@ -658,1030 +1727,6382 @@ fn update_tabs(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
     let Some(app) = app_weak.upgrade() else { return };
     let active_idx = s.workspace_mgr.active_index();
     let tabs: Vec<TabInfo> = (0..s.workspace_mgr.workspace_count())
-        .map(|i| TabInfo {
-            title: format!("Tab {}", i + 1).into(),
-            active: i == active_idx,
+        .map(|i| {
+            // Mark workspaces with a pane being logged
+            let ws = &s.workspace_mgr.workspaces()[i];
+            let logging = ws.pane_ids().iter().any(|id| {
+                let ps = s.pane_states.get(id);
+                ps.is_some_and(|ps| ps.pty.log_path().is_some())
+            });
+            let marker = if logging { "● " } else { "" };
+            // Project workspaces go by the project's name, others by the
+            // title the active pane's program set, if any
+            let pane_title = s
+                .pane_states
+                .get(&ws.active_pane())
+                .map_or("", |ps| ps.title.as_str());
+            let title = match &ws.project {
+                Some(_) => format!("{marker}{}", ws.name),
+                None if !pane_title.is_empty() => format!("{marker}{pane_title}"),
+                None => format!("{marker}Tab {}", i + 1),
+            };
+            TabInfo {
+                title: title.into(),
+                active: i == active_idx,
+            }
         })
         .collect();
     let model = std::rc::Rc::new(slint::VecModel::from(tabs));
     app.set_tabs(slint::ModelRc::from(model));
+    app.set_active_tab(active_idx as i32);
+    app.set_tab_preview_visible(false);
+
+    // Drop previews of closed workspaces
+    let live: Vec<WorkspaceId> = s
+        .workspace_mgr
+        .workspaces()
+        .iter()
+        .map(|ws| ws.id)
+        .collect();
+    s.tab_previews.retain(|id, _| live.contains(id));
+
+    update_sidebar(s, app_weak);
+}
 
+/// Rebuild the sidebar tree: each workspace row is followed by its panes.
+fn update_sidebar(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let Some(app) = app_weak.upgrade() else { return };
+    let active_idx = s.workspace_mgr.active_index();
     s.contributions
         .set_builtin_workspace_sidebar(s.workspace_mgr.workspace_count(), active_idx);
-    let sidebar_items: Vec<SidebarItem> = s
-        .contributions
-        .sidebar_items()
-        .into_iter()
-        .enumerate()
-        .map(|(idx, item)| SidebarItem {
-            title: item.title.into(),
+
+    let mut sidebar_items: Vec<SidebarItem> = Vec::new();
+    for (idx, item) in s.contributions.sidebar_items().into_iter().enumerate() {
+        let ws_idx = ContributionRegistry::builtin_workspace_index(&item.view_id);
+        let ws = ws_idx.and_then(|i| s.workspace_mgr.workspaces().get(i));
+        let title = match ws {
+            Some(ws) if ws.project.is_some() => ws.name.clone(),
+            _ => item.title,
+        };
+        sidebar_items.push(SidebarItem {
+            title: title.into(),
             active: item.active,
             index: idx as i32,
-        })
-        .collect();
+            depth: 0,
+            subtitle: Default::default(),
+            pane_id: -1,
+            workspace: ws_idx.map_or(-1, |i| i as i32),
+            activity: false,
+        });
+        let Some(ws) = ws else {
+            continue;
+        };
+        let on_screen = ws_idx == Some(active_idx);
+        for pane_id in ws.pane_ids() {
+            let Some(ps) = s.pane_states.get(&pane_id) else {
+                continue;
+            };
+            sidebar_items.push(SidebarItem {
+                title: pane_label(pane_id, ps).into(),
+                active: on_screen && ws.active_pane() == pane_id,
+                index: idx as i32,
+                depth: 1,
+                subtitle: pane_subtitle(ps).into(),
+                pane_id: pane_id as i32,
+                workspace: ws_idx.map_or(-1, |i| i as i32),
+                activity: !on_screen && ps.dirty.load(Ordering::Relaxed),
+            });
+        }
+    }
     let sidebar_model = std::rc::Rc::new(slint::VecModel::from(sidebar_items));
     app.set_sidebar_items(slint::ModelRc::from(sidebar_model));
     app.set_sidebar_visible(s.workspace_mgr.workspace_count() > 1);
 }
 
-fn spawn_pane_slint(config: &Config, pane_id: PaneId, cols: u16, rows: u16) -> PaneState {
-    let shell = config.shell();
-    let cwd = config.working_directory();
-    let dirty = Arc::new(AtomicBool::new(true));
-    let redraw_queued = Arc::new(AtomicBool::new(false));
-
-    let mut emulator = TerminalEmulator::new(cols, rows);
-    let parser_handle = emulator
-        .take_parser_handle()
-        .expect("terminal parser handle already taken");
-    let dirty_for_pty = Arc::clone(&dirty);
-
-    let pty = PtyHandle::spawn(
-        &shell,
-        &cwd,
-        cols,
-        rows,
-        parser_handle,
-        move || {
-            dirty_for_pty.store(true, Ordering::Release);
-        },
-        || {},
-    )
-    .expect("spawn PTY");
+/// Pick up shell titles and re-resolve each pane's cwd, git branch and env.
+fn refresh_pane_info(s: &mut TerminalState) {
+    for ps in s.pane_states.values_mut() {
+        if let Some(cwd) = ps.pty.cwd() {
+            ps.git_branch = git_info::current_branch(&cwd);
+            ps.cwd = Some(cwd);
+        }
+        ps.env = pane_env(ps, ps.cwd.as_deref());
+    }
+}
 
-    info!(pane_id, cols, rows, %shell, "Pane spawned (Slint)");
+/// Apply terminal events: new titles, and the bell and command-finished hooks
+/// and alerts. Returns whether a pane's title changed.
+fn poll_term_events(s: &mut TerminalState) -> bool {
+    let (hooks, workspace_mgr) = (&s.config.hooks, &s.workspace_mgr);
+    let show_progress = s.config.progress.enabled;
+    let notification = &s.config.notification;
+    let shown = workspace_mgr.active_workspace();
+    let (focused, visible) = (shown.active_pane(), shown.pane_ids());
+    let mut retitled = false;
+    for (&pane_id, ps) in s.pane_states.iter_mut() {
+        if ps.failed.is_none() {
+            if let Some(reason) = ps.emulator.failure().or_else(|| ps.pty.failure()) {
+                warn!(pane_id, %reason, "Pane failed");
+                let body = format!("{reason}. Press Return in the pane to restart it.");
+                s.notifications.push("Pane stopped working", body);
+                ps.failed = Some(reason);
+                ps.dirty.store(true, Ordering::Relaxed);
+            }
+        }
+        if ps.held_bytes.is_some() {
+            ps.held_bytes = ps.emulator.held_bytes();
+        }
+        if let Some(line) = ps.pty.take_clamped_line() {
+            // Once per pane; later lines just replace the one kept
+            if ps.clamped_line.is_none() {
+                let body = format!(
+                    "{} bytes hidden. Cmd+Shift+J opens the full line in the pager.",
+                    line.hidden
+                );
+                s.notifications.push("Long line truncated", body);
+            }
+            ps.clamped_line = Some(line);
+        }
+        for event in ps.emulator.poll_events() {
+            let (event, context) = match event {
+                TermEvent::TitleChanged(title) => {
+                    retitled |= ps.title != title;
+                    ps.title = title;
+                    continue;
+                }
+                TermEvent::Progress(progress) => {
+                    ps.progress = progress.filter(|_| show_progress);
+                    continue;
+                }
+                TermEvent::FastForward(skipped) => {
+                    ps.fast_forward = skipped;
+                    continue;
+                }
+                TermEvent::Notify { title, body } => {
+                    if notification.enabled && notification.detect_osc {
+                        // Asked for by the program, so listed even when seen
+                        let title = title.unwrap_or_else(|| pane_label(pane_id, ps));
+                        let pushed = push_pane_notification(
+                            &mut s.notifications,
+                            &s.notification_rules,
+                            workspace_mgr,
+                            pane_id,
+                            NotificationSource::Osc,
+                            title,
+                            body,
+                        );
+                        if !s.app_focused && !pushed.read {
+                            platform::show_notification(&pushed.title, &pushed.body);
+                        }
+                    }
+                    continue;
+                }
+                TermEvent::Bell => (HookEvent::Bell, HookContext::default()),
+                TermEvent::CommandFinished { command, exit_code } => {
+                    // A program that exits mid-way leaves no progress behind
+                    ps.progress = None;
+                    let context = HookContext {
+                        exit_code,
+                        command,
+                        ..Default::default()
+                    };
+                    (HookEvent::CommandFinished, context)
+                }
+                _ => continue,
+            };
+            let workspace_id = workspace_mgr
+                .workspace_index_of(pane_id)
+                .map(|idx| workspace_mgr.workspaces()[idx].id);
+            let context = HookContext {
+                pane_id: Some(pane_id),
+                workspace_id,
+                cwd: ps.cwd.clone(),
+                ..context
+            };
+            // Alerts come from panes out of sight: a bell anywhere but the
+            // focused pane, a command finishing in another workspace
+            let seen = s.app_focused
+                && match event {
+                    HookEvent::Bell => pane_id == focused,
+                    _ => visible.contains(&pane_id),
+                };
+            let wanted =
+                notification.enabled && (event != HookEvent::Bell || notification.detect_bell);
+            if wanted && !seen {
+                if let Some((title, body)) = alert(event, &context, pane_id, ps) {
+                    let source = match event {
+                        HookEvent::Bell => NotificationSource::Bell,
+                        _ => NotificationSource::Command,
+                    };
+                    push_pane_notification(
+                        &mut s.notifications,
+                        &s.notification_rules,
+                        workspace_mgr,
+                        pane_id,
+                        source,
+                        title.to_string(),
+                        body,
+                    );
+                }
+            }
+            hooks::run(hooks, event, context);
+        }
+    }
+    retitled
+}
 
-    PaneState {
-        emulator,
-        pty,
-        dirty,
-        redraw_queued,
-        render_grid: Vec::new(),
-        render_dirty_rows: Vec::new(),
-        last_cursor_visible: true,
+/// Push a notification from a pane, silenced when the rules or its
+/// workspace's mute say so
+fn push_pane_notification(
+    notifications: &mut NotificationStore,
+    rules: &NotificationRules,
+    workspace_mgr: &WorkspaceManager,
+    pane_id: PaneId,
+    source: NotificationSource,
+    title: String,
+    body: String,
+) -> Notification {
+    let workspace = workspace_mgr
+        .workspace_index_of(pane_id)
+        .map(|idx| &workspace_mgr.workspaces()[idx]);
+    let silenced =
+        workspace.is_some_and(|ws| ws.muted) || rules.silences_now(source, &title, &body);
+    let origin = workspace.map(|ws| (ws.id, pane_id));
+    notifications.push_from(title, body, source, origin, silenced)
+}
+
+/// Notification for a bell or a finished command
+fn alert(
+    event: HookEvent,
+    context: &HookContext,
+    pane_id: PaneId,
+    ps: &PaneState,
+) -> Option<(&'static str, String)> {
+    let label = || pane_label(pane_id, ps);
+    match event {
+        HookEvent::Bell => Some(("Bell", label())),
+        HookEvent::CommandFinished => {
+            let command = context.command.clone().unwrap_or_else(label);
+            match context.exit_code {
+                Some(code) if code != 0 => {
+                    Some(("Command failed", format!("{command} (exit {code})")))
+                }
+                _ => Some(("Command finished", command)),
+            }
+        }
+        _ => None,
     }
 }
 
-fn calc_cols_rows(renderer: &OffscreenRenderer, _scale_factor: f64) -> (u16, u16) {
-    let (cell_w, cell_h) = renderer.text_renderer.cell_size();
-    let w = renderer.width().max(1);
-    let h = renderer.height().max(1);
-    let cols = (w as f32 / cell_w).max(1.0) as u16;
-    let rows = (h as f32 / cell_h).max(1.0) as u16;
-    (cols, rows)
+fn pane_env(ps: &PaneState, cwd: Option<&Path>) -> PaneEnv {
+    let prompt = ps.emulator.prompt_text();
+    PaneEnv::detect(prompt.as_deref(), cwd, |key| ps.pty.env_var(key))
 }
 
-fn pixel_rect_to_cols_rows(rect: &PixelRect, renderer: &OffscreenRenderer) -> (u16, u16) {
-    let (cell_w, cell_h) = renderer.text_renderer.cell_size();
-    let cols = (rect.w / cell_w).max(1.0) as u16;
-    let rows = (rect.h / cell_h).max(1.0) as u16;
-    (cols, rows)
+fn pane_label(pane_id: PaneId, ps: &PaneState) -> String {
+    if !ps.title.is_empty() {
+        return ps.title.clone();
+    }
+    ps.cwd
+        .as_ref()
+        .and_then(|cwd| cwd.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| format!("Pane {pane_id}"))
 }
 
-/// Half-width of the divider gap between panes (physical pixels).
-const DIVIDER_HALF: f32 = 1.0;
-/// Color for pane divider lines (light gray, semi-transparent).
-const DIVIDER_COLOR: [f32; 4] = [0.45, 0.45, 0.50, 1.0];
+/// "~/src/project · main · venv:project"
+/// A path with the home directory shown as `~`
+fn home_relative(path: &Path) -> String {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    match home.as_deref().and_then(|h| path.strip_prefix(h).ok()) {
+        Some(rel) if rel.as_os_str().is_empty() => "~".to_string(),
+        Some(rel) => format!("~/{}", rel.display()),
+        None => path.display().to_string(),
+    }
+}
 
-fn pane_to_pixel_rect(
-    pane_rect: &pterminal_core::split::PaneRect,
-    window_w: u32,
-    window_h: u32,
-    scale: f32,
-    tab_bar_h: f32,
-) -> PixelRect {
-    let content_w = (window_w as f32).max(1.0);
-    let content_h = window_h as f32 - tab_bar_h;
-    // Only add gap on sides that border another pane (not window edges).
-    let gap = DIVIDER_HALF * scale;
-    let left = if pane_rect.x > 0.001 { gap } else { 0.0 };
-    let top = if pane_rect.y > 0.001 { gap } else { 0.0 };
-    let right = if pane_rect.x + pane_rect.width < 0.999 { gap } else { 0.0 };
-    let bottom = if pane_rect.y + pane_rect.height < 0.999 { gap } else { 0.0 };
-    PixelRect {
-        x: pane_rect.x * content_w + left,
-        y: pane_rect.y * content_h + top + tab_bar_h,
-        w: pane_rect.width * content_w - left - right,
-        h: pane_rect.height * content_h - top - bottom,
+fn pane_subtitle(ps: &PaneState) -> String {
+    let mut subtitle = ps.cwd.as_deref().map(home_relative).unwrap_or_default();
+    if let Some(branch) = &ps.git_branch {
+        subtitle.push_str(" · ");
+        subtitle.push_str(branch);
     }
+    let env = ps.env.label();
+    if !env.is_empty() {
+        subtitle.push_str(" · ");
+        subtitle.push_str(&env);
+    }
+    if ps.pty.log_path().is_some() {
+        subtitle.push_str(" · ● logging");
+    }
+    if let Some(held) = ps.held_bytes {
+        subtitle.push_str(&format!(" · ⏸ {} held", format_bytes(held)));
+    }
+    if ps.fast_forward.is_some() {
+        subtitle.push_str(" · ⏩ fast-forwarding");
+    }
+    if ps.pty.input_stopped() {
+        subtitle.push_str(" · XOFF (Ctrl+Q resumes)");
+    }
+    if ps.failed.is_some() {
+        subtitle.push_str(" · ⚠ failed");
+    }
+    subtitle
 }
 
-fn pane_pixel_rect(s: &TerminalState, pane_id: PaneId) -> Option<PixelRect> {
-    let renderer = s.renderer.as_ref()?;
-    let scale = s.scale_factor as f32;
-    let w = renderer.width();
-    let h = renderer.height();
-    s.workspace_mgr
-        .active_workspace()
-        .split_tree
-        .layout()
-        .into_iter()
-        .find(|(id, _)| *id == pane_id)
-        .map(|(_, rect)| pane_to_pixel_rect(&rect, w, h, scale, 0.0))
+/// "512 B", "1.5 KB", "12.0 MB"
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
 }
 
-fn pane_at_pixel(s: &TerminalState, x: f32, y: f32) -> Option<PaneId> {
-    let renderer = s.renderer.as_ref()?;
-    let scale = s.scale_factor as f32;
-    let w = renderer.width();
-    let h = renderer.height();
-    s.workspace_mgr
-        .active_workspace()
-        .split_tree
-        .layout()
-        .into_iter()
-        .find_map(|(pane_id, pane_rect)| {
-            let px = pane_to_pixel_rect(&pane_rect, w, h, scale, 0.0);
-            let in_x = x >= px.x && x < px.x + px.w;
-            let in_y = y >= px.y && y < px.y + px.h;
-            if in_x && in_y {
-                Some(pane_id)
-            } else {
-                None
-            }
-        })
+fn spawn_pane_slint(s: &TerminalState, pane_id: PaneId, cols: u16, rows: u16) -> PaneState {
+    spawn_pane_in(s, pane_id, cols, rows, &s.config.working_directory())
 }
 
-fn pixel_to_cell(s: &TerminalState, pane_id: PaneId) -> (u16, u16) {
-    let renderer = match s.renderer.as_ref() {
-        Some(r) => r,
-        None => return (0, 0),
+fn spawn_pane_in(
+    s: &TerminalState,
+    pane_id: PaneId,
+    cols: u16,
+    rows: u16,
+    cwd: &Path,
+) -> PaneState {
+    spawn_pane_on(&s.config, &s.theme, pane_id, cols, rows, cwd, None)
+}
+
+/// Spawn a pane's shell, answering color queries from `theme`. With
+/// `kept`, the emulator of the pane it replaces, the new shell's output
+/// goes on below the old history after `kept`'s separator line.
+fn spawn_pane_on(
+    config: &Config,
+    theme: &Arc<Theme>,
+    pane_id: PaneId,
+    cols: u16,
+    rows: u16,
+    cwd: &Path,
+    kept: Option<(TerminalEmulator, &str)>,
+) -> PaneState {
+    let shell = config.shell();
+    let dirty = Arc::new(AtomicBool::new(true));
+    let redraw_queued = Arc::new(AtomicBool::new(false));
+
+    let no_wrap = config.nowrap.enabled;
+    let visible_cols = cols;
+    let cols = if no_wrap { cols.max(config.nowrap.columns) } else { cols };
+
+    let (emulator, parser_handle) = match kept {
+        Some((mut emulator, separator)) => {
+            emulator.resize(cols, rows);
+            let handle = emulator.reattach_parser_handle(separator);
+            (emulator, handle)
+        }
+        None => {
+            let mut emulator = TerminalEmulator::with_theme(cols, rows, Arc::clone(theme));
+            let handle = emulator
+                .take_parser_handle()
+                .expect("terminal parser handle already taken");
+            (emulator, handle)
+        }
     };
-    let (cell_w, cell_h) = renderer.text_renderer.cell_size();
-    let (mx, my) = (s.last_mouse_pos.0 as f32, s.last_mouse_pos.1 as f32);
-    let (px, py) = if let Some(rect) = pane_pixel_rect(s, pane_id) {
-        (mx - rect.x, my - rect.y)
-    } else {
-        (mx, my)
+    let dirty_for_pty = Arc::clone(&dirty);
+
+    let pty = PtyHandle::spawn(
+        &shell,
+        cwd,
+        cols,
+        rows,
+        parser_handle,
+        move || {
+            dirty_for_pty.store(true, Ordering::Release);
+            wakeup::wake();
+        },
+        wakeup::wake,
+    )
+    .expect("spawn PTY");
+    pty.set_max_line_bytes(config.long_lines.max_bytes);
+    emulator.set_output_limit(config.flow.max_output_mb_per_sec * 1024 * 1024);
+    pty.set_honor_xoff(config.flow.honor_xoff);
+    if config.progress.enabled && config.progress.detect_text {
+        emulator.set_text_progress(true);
+    }
+
+    info!(pane_id, cols, rows, %shell, "Pane spawned (Slint)");
+    let context = HookContext {
+        pane_id: Some(pane_id),
+        cwd: Some(cwd.to_path_buf()),
+        ..Default::default()
     };
-    let col = (px / cell_w).max(0.0) as u16;
-    let row = (py / cell_h).max(0.0) as u16;
+    hooks::run(&config.hooks, HookEvent::PaneSpawned, context);
 
-    if let Some(ps) = s.pane_states.get(&pane_id) {
-        let (grid_cols, grid_rows) = ps.emulator.size();
-        (
-            col.min(grid_cols.saturating_sub(1)),
-            row.min(grid_rows.saturating_sub(1)),
-        )
-    } else {
-        (col, row)
+    PaneState {
+        emulator,
+        pty,
+        dirty,
+        redraw_queued,
+        render_grid: Vec::new(),
+        render_dirty_rows: Vec::new(),
+        last_cursor_visible: true,
+        no_wrap,
+        h_offset: 0,
+        visible_cols,
+        focused: false,
+        title: String::new(),
+        git_branch: git_info::current_branch(cwd),
+        cwd: Some(cwd.to_path_buf()),
+        env: PaneEnv::default(),
+        reconnect: ReconnectWatch::default(),
+        auto_log: false,
+        auto_log_path: None,
+        clamped_line: None,
+        held_bytes: None,
+        fast_forward: None,
+        failed: None,
+        unresponsive: None,
+        highlight_until: None,
+        progress: None,
+        input: InputHistory::default(),
+        prompt_gutter: config.prompt_times.gutter,
+        marked: false,
+        pinned: None,
+        pinned_grid: Vec::new(),
+        join_wrapped: None,
+        scaled_grid: None,
     }
 }
 
-fn get_selected_text(s: &TerminalState) -> Option<String> {
-    let sel = s.selection?;
-    let (start, end) = sel.normalized();
+/// Split the active pane and focus the new one. Its shell starts in `cwd`, or
+/// the workspace's project directory, or the configured working directory.
+fn split_active_pane(
+    s: &mut TerminalState,
+    direction: SplitDirection,
+    cwd: Option<&Path>,
+) -> PaneId {
     let active_pane = s.workspace_mgr.active_workspace().active_pane();
-    let ps = s.pane_states.get(&active_pane)?;
-    let grid = ps.emulator.extract_grid(&s.theme);
+    let new_pane_id = s.workspace_mgr.next_pane_id();
+    s.workspace_mgr
+        .active_workspace_mut()
+        .split_tree
+        .split(active_pane, direction, new_pane_id);
 
-    let mut text = String::new();
-    for row in start.1..=end.1 {
-        if row as usize >= grid.len() {
-            break;
-        }
-        let line = &grid[row as usize];
-        let col_start = if row == start.1 { start.0 as usize } else { 0 };
-        let col_end = if row == end.1 {
-            (end.0 as usize + 1).min(line.cells.len())
+    let (cols, rows) = if let Some(renderer) = &s.renderer {
+        let gap = divider_gap(s);
+        let w = renderer.width();
+        let h = panes_height(renderer);
+        let layout = s.workspace_mgr.active_workspace().split_tree.layout();
+        if let Some((_, pr)) = layout.iter().find(|(id, _)| *id == new_pane_id) {
+            let px = pane_to_pixel_rect(pr, w, h, gap, 0.0, pane_padding(s));
+            let gutter = gutter_width(renderer, new_pane_id, s.config.prompt_times.gutter);
+            pixel_rect_to_cols_rows(&inset_gutter(px, gutter), renderer, new_pane_id)
         } else {
-            line.cells.len()
-        };
-        for col in col_start..col_end {
-            let c = line.cells[col].c;
-            text.push(if c == '\0' { ' ' } else { c });
-        }
-        let trimmed = text.trim_end_matches(' ').len();
-        text.truncate(trimmed);
-        if row < end.1 {
-            text.push('\n');
+            calc_cols_rows(renderer, pane_padding(s))
         }
-    }
-    if text.is_empty() {
-        None
     } else {
-        Some(text)
-    }
-}
+        (80, 24)
+    };
 
-fn word_selection_at(
-    s: &TerminalState,
-    theme: &Arc<Theme>,
-    col: u16,
-    row: u16,
-) -> Selection {
-    let active_pane = s.workspace_mgr.active_workspace().active_pane();
-    if let Some(ps) = s.pane_states.get(&active_pane) {
-        let grid = ps.emulator.extract_grid(theme);
-        if (row as usize) < grid.len() {
-            let line = &grid[row as usize];
-            let cells = &line.cells;
-            let c = col as usize;
-            if c < cells.len() {
-                let is_word_char = |ch: char| ch.is_alphanumeric() || ch == '_';
-                let ch = cells[c].c;
-                if is_word_char(ch) {
-                    let mut start = c;
-                    while start > 0 && is_word_char(cells[start - 1].c) {
-                        start -= 1;
-                    }
-                    let mut end = c;
-                    while end + 1 < cells.len() && is_word_char(cells[end + 1].c) {
-                        end += 1;
-                    }
-                    return Selection {
-                        start: (start as u16, row),
-                        end: (end as u16, row),
-                    };
-                }
+    let cwd = match cwd {
+        Some(cwd) => cwd.to_path_buf(),
+        None => s
+            .workspace_mgr
+            .active_workspace()
+            .project
+            .clone()
+            .unwrap_or_else(|| s.config.working_directory()),
+    };
+    let ps = spawn_pane_in(s, new_pane_id, cols, rows, &cwd);
+    s.pane_states.insert(new_pane_id, ps);
+
+    // Resize original pane
+    if let Some(renderer) = &s.renderer {
+        let gap = divider_gap(s);
+        let w = renderer.width();
+        let h = panes_height(renderer);
+        let layout = s.workspace_mgr.active_workspace().split_tree.layout();
+        if let Some((_, pr)) = layout.iter().find(|(id, _)| *id == active_pane) {
+            let px = pane_to_pixel_rect(pr, w, h, gap, 0.0, pane_padding(s));
+            let shown = s
+                .pane_states
+                .get(&active_pane)
+                .is_some_and(|ps| ps.prompt_gutter);
+            let px = inset_gutter(px, gutter_width(renderer, active_pane, shown));
+            let (c, r) = pixel_rect_to_cols_rows(&px, renderer, active_pane);
+            if let Some(ops) = s.pane_states.get_mut(&active_pane) {
+                ops.scaled_grid = None;
+                resize_pane(ops, c, r, &s.config);
             }
         }
     }
-    Selection {
-        start: (col, row),
-        end: (col, row),
+
+    s.workspace_mgr
+        .active_workspace_mut()
+        .set_active_pane(new_pane_id);
+    new_pane_id
+}
+
+/// Split the active pane with the new shell in the same directory and the same
+/// Python environment activated. direnv reloads on its own from the cwd.
+fn split_with_env(s: &mut TerminalState, direction: SplitDirection) -> Option<PaneId> {
+    let active = s.workspace_mgr.active_workspace().active_pane();
+    let ps = s.pane_states.get(&active)?;
+    let cwd = ps.pty.cwd();
+    let env = pane_env(ps, cwd.as_deref());
+    let new_pane_id = split_active_pane(s, direction, cwd.as_deref());
+    if let (Some(python), Some(ps)) = (env.python, s.pane_states.get(&new_pane_id)) {
+        let command = format!("{}\n", python.activation_command());
+        let _ = ps.pty.write(command.as_bytes());
     }
+    Some(new_pane_id)
 }
 
-fn line_selection_at(s: &TerminalState, row: u16) -> Selection {
-    let active_pane = s.workspace_mgr.active_workspace().active_pane();
-    let max_col = if let Some(ps) = s.pane_states.get(&active_pane) {
-        let (cols, _) = ps.emulator.size();
-        cols.saturating_sub(1)
-    } else {
-        79
-    };
-    Selection {
-        start: (0, row),
-        end: (max_col, row),
+/// Split the active pane as [`split_with_env`] does, and run `command` in
+/// the new shell too
+fn duplicate_pane(
+    s: &mut TerminalState,
+    direction: SplitDirection,
+    command: Option<&str>,
+) -> Option<PaneId> {
+    let new_pane_id = split_with_env(s, direction)?;
+    if let (Some(command), Some(ps)) = (command, s.pane_states.get(&new_pane_id)) {
+        let _ = ps.pty.write(format!("{command}\n").as_bytes());
     }
+    Some(new_pane_id)
 }
 
-fn resize_active_workspace_panes(s: &mut TerminalState) {
-    let Some(renderer) = &s.renderer else { return };
-    let scale = s.scale_factor as f32;
-    let w = renderer.width();
-    let h = renderer.height();
-    let layout = s.workspace_mgr.active_workspace().split_tree.layout();
-    for (pane_id, pane_rect) in &layout {
-        let px_rect = pane_to_pixel_rect(pane_rect, w, h, scale, 0.0);
-        let (cols, rows) = pixel_rect_to_cols_rows(&px_rect, renderer);
-        if let Some(ps) = s.pane_states.get(pane_id) {
-            ps.emulator.resize(cols, rows);
-            let _ = ps.pty.resize(cols, rows);
+/// Focus `pane_id` and open its context menu at `x`, `y` in the terminal area
+fn open_context_menu(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    pane_id: PaneId,
+    x: f32,
+    y: f32,
+) {
+    let Some(app) = app_weak.upgrade() else {
+        return;
+    };
+    if s.workspace_mgr.active_workspace().active_pane() != pane_id {
+        s.workspace_mgr
+            .active_workspace_mut()
+            .set_active_pane(pane_id);
+        for ps in s.pane_states.values() {
             ps.dirty.store(true, Ordering::Relaxed);
         }
     }
+    let shell = s.config.shell();
+    let command = s
+        .pane_states
+        .get(&pane_id)
+        .and_then(|ps| running_command(ps, &shell));
+    let joins = s
+        .pane_states
+        .get(&pane_id)
+        .is_some_and(|ps| joins_wrapped(&s.config, ps));
+    let (_, row) = pixel_to_cell(s, pane_id);
+    let block = s
+        .pane_states
+        .get(&pane_id)
+        .and_then(|ps| ps.emulator.command_block_at(row));
+    let logging = s
+        .pane_states
+        .get(&pane_id)
+        .is_some_and(|ps| ps.pty.log_path().is_some());
+    // Re-running only makes sense back at the prompt
+    let at_prompt = command.is_none();
+    s.context_menu = context_menu::pane_actions(command, block, logging);
+    let items: Vec<ContextMenuItem> = s
+        .context_menu
+        .iter()
+        .map(|action| ContextMenuItem {
+            label: action.label().into(),
+            enabled: match action {
+                ContextAction::Copy | ContextAction::CopyHtml => s.selection.is_some(),
+                ContextAction::Rerun(_) => at_prompt,
+                _ => true,
+            },
+            checked: *action == ContextAction::JoinWrapped && joins,
+        })
+        .collect();
+    let model = std::rc::Rc::new(slint::VecModel::from(items));
+    app.set_context_menu_items(slint::ModelRc::from(model));
+    app.set_context_menu_x(x);
+    app.set_context_menu_y(y);
+    app.invoke_show_context_menu();
+    request_redraw(app_weak);
 }
 
-// ---------------------------------------------------------------------------
-// Key handling
-// ---------------------------------------------------------------------------
-
-fn handle_key_event(
-    event: &KeyEvent,
+fn run_context_action(
     s: &mut TerminalState,
-    _theme: &Arc<Theme>,
     app_weak: &slint::Weak<AppWindow>,
+    action: ContextAction,
 ) {
-    let text = event.text.to_string();
-    if text.is_empty() {
-        return;
+    match action {
+        ContextAction::Copy => copy_selection(s),
+        ContextAction::CopyHtml => copy_selection_html(s),
+        ContextAction::Paste => paste_clipboard(s, app_weak),
+        ContextAction::JoinWrapped => {
+            let active = s.workspace_mgr.active_workspace().active_pane();
+            let joins = s
+                .pane_states
+                .get(&active)
+                .is_some_and(|ps| joins_wrapped(&s.config, ps));
+            if let Some(ps) = s.pane_states.get_mut(&active) {
+                ps.join_wrapped = Some(!joins);
+            }
+        }
+        ContextAction::SplitRight => {
+            split_active_pane(s, SplitDirection::Horizontal, None);
+        }
+        ContextAction::SplitDown => {
+            split_active_pane(s, SplitDirection::Vertical, None);
+        }
+        ContextAction::Duplicate => {
+            duplicate_pane(s, SplitDirection::Horizontal, None);
+        }
+        ContextAction::DuplicateRunning(command) => {
+            duplicate_pane(s, SplitDirection::Horizontal, Some(&command));
+        }
+        ContextAction::CopyCommand(text)
+        | ContextAction::CopyOutput(text)
+        | ContextAction::CopyBlock(text) => {
+            if let Some(clip) = &mut s.clipboard {
+                let _ = clip.set_text(text);
+            }
+        }
+        ContextAction::Rerun(command) => {
+            let active = s.workspace_mgr.active_workspace().active_pane();
+            if let Some(ps) = s.pane_states.get_mut(&active) {
+                let _ = send_input(ps, format!("{command}\r").as_bytes());
+            }
+        }
+        ContextAction::StartLog => {
+            let active = s.workspace_mgr.active_workspace().active_pane();
+            let options = log_options(&s.config.logging);
+            let (title, body) = match start_pane_log(s, active, None, options) {
+                Ok(path) => ("Logging pane output", path.display().to_string()),
+                Err(e) => ("Pane log failed", format!("{e:#}")),
+            };
+            s.notifications.push(title, body);
+            update_tabs(s, app_weak);
+        }
+        ContextAction::StopLog => {
+            let active = s.workspace_mgr.active_workspace().active_pane();
+            if let Some(path) = stop_pane_log(s, active) {
+                s.notifications
+                    .push("Pane log stopped", path.display().to_string());
+            }
+            update_tabs(s, app_weak);
+        }
     }
-    let ch = match text.chars().next() {
-        Some(c) => c,
-        None => return,
+    update_sidebar(s, app_weak);
+    request_redraw(app_weak);
+}
+
+/// Open the active pane's last truncated line in the pager, in a split.
+fn open_full_line(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let active = s.workspace_mgr.active_workspace().active_pane();
+    let Some(line) = s
+        .pane_states
+        .get(&active)
+        .and_then(|ps| ps.clamped_line.as_ref())
+    else {
+        s.notifications
+            .push("No truncated line", "No line in this pane was cut short.");
+        return;
     };
-    let raw_ctrl = event.modifiers.control;
-    let raw_meta = event.modifiers.meta;
-    let shift = event.modifiers.shift;
+    let name = format!("line-{}-{active}.txt", std::process::id());
+    let path = match Config::write_scratch(&name, &line.bytes) {
+        Ok(path) => path,
+        Err(e) => {
+            s.notifications.push("Full line not opened", e.to_string());
+            return;
+        }
+    };
+    let kind = ShellKind::from_shell(&s.config.shell());
+    let command = format!(
+        "{} {}\r",
+        s.config.long_lines.pager(),
+        kind.quote(&path.to_string_lossy())
+    );
+    let pane_id = split_active_pane(s, SplitDirection::Horizontal, None);
+    if let Some(ps) = s.pane_states.get(&pane_id) {
+        let _ = ps.pty.write(command.as_bytes());
+    }
+    request_redraw(app_weak);
+}
 
-    // On macOS, Slint swaps Command and Control:
-    //   Physical Command (⌘) → modifiers.control
-    //   Physical Control (⌃) → modifiers.meta
-    // Swap them so our logic matches physical keys.
-    #[cfg(target_os = "macos")]
-    let (ctrl, meta) = (raw_meta, raw_ctrl);
-    #[cfg(not(target_os = "macos"))]
-    let (ctrl, meta) = (raw_ctrl, raw_meta);
+/// Check for a newer release on another thread, if checks are on and one
+/// is due
+fn start_update_check(config: &Config) -> Option<Receiver<Result<Release>>> {
+    let stamp = Config::update_stamp_path();
+    let interval = Duration::from_secs(config.updates.interval_hours.saturating_mul(3600));
+    if !config.updates.check || !update::is_due(&stamp, interval) {
+        return None;
+    }
+    let (tx, rx) = mpsc::channel();
+    let feed = config.updates.feed.clone();
+    std::thread::spawn(move || {
+        let result = update::fetch(&feed);
+        if let Ok(release) = &result {
+            if let Err(e) = update::record_check(&stamp, &release.version) {
+                warn!("Can't record the update check: {e}");
+            }
+        }
+        let _ = tx.send(result);
+        wakeup::wake();
+    });
+    Some(rx)
+}
 
-    // Modifier-only keys — ignore ONLY when no Ctrl/Meta modifier is held.
-    // When Ctrl is pressed, chars like \u{0016} are Ctrl+V, not modifier-only.
-    if !ctrl && !meta {
-        match ch {
-            '\u{0010}' | '\u{0011}' | '\u{0012}' | '\u{0013}' | '\u{0014}' | '\u{0015}'
-            | '\u{0016}' | '\u{0017}' | '\u{0018}' => return,
-            _ => {}
+/// Tell of a newer release, with its notes in a new tab
+fn finish_update_check(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    result: Result<Release>,
+) {
+    let release = match result {
+        Ok(release) => release,
+        Err(e) => {
+            // Offline is not worth a notification
+            warn!("Update check failed: {e:#}");
+            return;
         }
+    };
+    if !update::is_newer(&release.version, update::CURRENT_VERSION) {
+        info!(latest = %release.version, "pterminal is up to date");
+        return;
     }
+    let title = format!("pterminal {} is available", release.version);
+    let origin = open_release_notes(s, app_weak, &release);
+    let body = match origin {
+        Some(_) => format!(
+            "You have {}; the release notes are in a new tab.",
+            update::CURRENT_VERSION
+        ),
+        None => format!("You have {}. {}", update::CURRENT_VERSION, release.url),
+    };
+    s.notifications
+        .push_from(title, body, NotificationSource::App, origin, false);
+    request_redraw(app_weak);
+}
 
-    // ── Cmd/Ctrl shortcuts ──
-    // On macOS, Cmd (meta) is the primary modifier for UI actions.
-    // Ctrl sends terminal control characters.
-    let action_mod = meta || ctrl;
+/// Show a release's notes in the pager, in a tab opened behind the active
+/// one. Returns the pane showing them.
+fn open_release_notes(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    release: &Release,
+) -> Option<(WorkspaceId, PaneId)> {
+    let name = format!("release-{}.md", release.version);
+    let text = format!(
+        "# pterminal {}\n\n{}\n\n{}\n",
+        release.version,
+        release.url,
+        release.notes.replace("\r\n", "\n")
+    );
+    let path = match Config::write_scratch(&name, text.as_bytes()) {
+        Ok(path) => path,
+        Err(e) => {
+            warn!("Can't write the release notes: {e}");
+            return None;
+        }
+    };
+    let kind = ShellKind::from_shell(&s.config.shell());
+    let command = format!(
+        "{} {}",
+        s.config.long_lines.pager(),
+        kind.quote(&path.to_string_lossy())
+    );
+    let saved = WorkspaceLayout {
+        name: format!("pterminal {}", release.version),
+        root: layout::LayoutNode::Pane(PaneLayout {
+            command: Some(command),
+            ..Default::default()
+        }),
+    };
+    let active = s.workspace_mgr.active_index();
+    let (workspace_id, panes) = open_layout(s, app_weak, &saved);
+    select_workspace(s, app_weak, active);
+    Some((workspace_id, *panes.first()?))
+}
 
-    if action_mod {
-        // Determine the letter for matching.
-        // Slint may send either the literal letter or a control character
-        // depending on which modifier is active.
-        let letter = if ch.is_ascii_alphabetic() {
-            Some(ch.to_ascii_lowercase())
-        } else if (ch as u32) >= 1 && (ch as u32) <= 26 {
-            Some((b'a' + ch as u8 - 1) as char)
-        } else {
-            None
-        };
+/// Pin the pane's screen as it is now, to compare later output with, or
+/// unpin it. Returns whether it is pinned, or `None` if there is no such
+/// pane.
+fn set_pane_pinned(s: &mut TerminalState, pane_id: PaneId, pinned: bool) -> Option<bool> {
+    let ps = s.pane_states.get_mut(&pane_id)?;
+    ps.pinned = pinned.then(|| PinnedScreen::new(ps.emulator.extract_grid(&s.theme)));
+    // Every row changes, so the next frame extracts them all
+    ps.render_grid.clear();
+    ps.dirty.store(true, Ordering::Relaxed);
+    Some(pinned)
+}
 
-        match letter {
-            Some('c') => {
-                // Copy if selection exists, otherwise send SIGINT (Ctrl+C)
-                if s.selection.is_some() {
-                    if let Some(txt) = get_selected_text(s) {
-                        if let Some(clip) = &mut s.clipboard {
-                            let _ = clip.set_text(txt);
-                        }
-                    }
-                } else {
-                    let active = s.workspace_mgr.active_workspace().active_pane();
-                    if let Some(ps) = s.pane_states.get(&active) {
-                        let _ = ps.pty.write(b"\x03");
-                    }
-                    request_redraw(app_weak);
-                }
-                return;
-            }
-            Some('v') => {
-                if let Some(clip) = &mut s.clipboard {
-                    if let Ok(txt) = clip.get_text() {
-                        let active = s.workspace_mgr.active_workspace().active_pane();
-                        if let Some(ps) = s.pane_states.get(&active) {
-                            let _ = ps.pty.write(txt.as_bytes());
-                        }
-                    }
-                }
-                request_redraw(app_weak);
-                return;
-            }
-            Some('t') if meta => {
-                let (_ws_id, pane_id) = s.workspace_mgr.add_workspace();
-                let (cols, rows) = if let Some(renderer) = &s.renderer {
-                    calc_cols_rows(renderer, s.scale_factor)
-                } else {
-                    (80, 24)
-                };
-                let ps = spawn_pane_slint(&s.config, pane_id, cols, rows);
-                s.pane_states.insert(pane_id, ps);
-                update_tabs(s, app_weak);
-                request_redraw(app_weak);
-                return;
-            }
-            Some('w') if meta => {
-                if s.workspace_mgr.workspace_count() > 1 {
-                    let ws = s.workspace_mgr.active_workspace();
-                    let pane_ids = ws.pane_ids();
-                    let ws_id = ws.id;
-                    for pid in &pane_ids {
-                        s.pane_states.remove(pid);
-                        if let Some(renderer) = &mut s.renderer {
-                            renderer.text_renderer.remove_pane(*pid);
-                        }
-                    }
-                    s.workspace_mgr.close_workspace(ws_id);
-                    update_tabs(s, app_weak);
-                    request_redraw(app_weak);
-                }
-                return;
-            }
-            Some('d') | Some('D') if meta => {
-                let direction = if shift {
-                    SplitDirection::Vertical
-                } else {
-                    SplitDirection::Horizontal
-                };
-                let active_pane = s.workspace_mgr.active_workspace().active_pane();
-                let new_pane_id = s.workspace_mgr.next_pane_id();
-                s.workspace_mgr
-                    .active_workspace_mut()
-                    .split_tree
-                    .split(active_pane, direction, new_pane_id);
-
-                let (cols, rows) = if let Some(renderer) = &s.renderer {
-                    let scale = s.scale_factor as f32;
-                    let w = renderer.width();
-                    let h = renderer.height();
-                    let layout = s.workspace_mgr.active_workspace().split_tree.layout();
-                    if let Some((_, pr)) = layout.iter().find(|(id, _)| *id == new_pane_id) {
-                        let px = pane_to_pixel_rect(pr, w, h, scale, 0.0);
-                        pixel_rect_to_cols_rows(&px, renderer)
-                    } else {
-                        calc_cols_rows(renderer, s.scale_factor)
-                    }
-                } else {
-                    (80, 24)
-                };
+/// Pin the active pane's screen, or unpin it.
+fn toggle_pin_screen(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let active = s.workspace_mgr.active_workspace().active_pane();
+    let pinned = s
+        .pane_states
+        .get(&active)
+        .is_some_and(|ps| ps.pinned.is_none());
+    set_pane_pinned(s, active, pinned);
+    info!(pane_id = active, pinned, "Screen pin");
+    request_redraw(app_weak);
+}
 
-                let ps = spawn_pane_slint(&s.config, new_pane_id, cols, rows);
-                s.pane_states.insert(new_pane_id, ps);
-
-                // Resize original pane
-                if let Some(renderer) = &s.renderer {
-                    let scale = s.scale_factor as f32;
-                    let w = renderer.width();
-                    let h = renderer.height();
-                    let layout = s.workspace_mgr.active_workspace().split_tree.layout();
-                    if let Some((_, pr)) = layout.iter().find(|(id, _)| *id == active_pane) {
-                        let px = pane_to_pixel_rect(pr, w, h, scale, 0.0);
-                        let (c, r) = pixel_rect_to_cols_rows(&px, renderer);
-                        if let Some(ops) = s.pane_states.get(&active_pane) {
-                            ops.emulator.resize(c, r);
-                            let _ = ops.pty.resize(c, r);
-                        }
-                    }
-                }
+/// Pause or resume the active pane's output. While paused the screen holds
+/// still so fast-scrolling output can be read and scrolled; resuming catches
+/// up on what was held back.
+fn toggle_pause_output(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let active = s.workspace_mgr.active_workspace().active_pane();
+    let Some(ps) = s.pane_states.get_mut(&active) else {
+        return;
+    };
+    let paused = ps.held_bytes.is_none();
+    ps.emulator.set_paused(paused);
+    ps.held_bytes = paused.then_some(0);
+    ps.dirty.store(true, Ordering::Relaxed);
+    update_paused_badge(s, app_weak);
+    update_sidebar(s, app_weak);
+    request_redraw(app_weak);
+}
 
-                s.workspace_mgr
-                    .active_workspace_mut()
-                    .set_active_pane(new_pane_id);
-                request_redraw(app_weak);
-                return;
-            }
-            Some(']') if meta => {
-                let ws = s.workspace_mgr.active_workspace();
-                let current = ws.active_pane();
-                if let Some(next) = ws.split_tree.next_pane(current) {
-                    s.workspace_mgr.active_workspace_mut().set_active_pane(next);
-                    for ps in s.pane_states.values() {
-                        ps.dirty.store(true, Ordering::Relaxed);
-                    }
-                    request_redraw(app_weak);
-                }
-                return;
-            }
-            Some('[') if meta => {
-                let ws = s.workspace_mgr.active_workspace();
-                let current = ws.active_pane();
-                if let Some(prev) = ws.split_tree.prev_pane(current) {
-                    s.workspace_mgr.active_workspace_mut().set_active_pane(prev);
-                    for ps in s.pane_states.values() {
-                        ps.dirty.store(true, Ordering::Relaxed);
-                    }
-                    request_redraw(app_weak);
-                }
-                return;
-            }
-            Some(c) if meta && c.is_ascii_digit() && c != '0' => {
-                let idx = (c as u8 - b'1') as usize;
-                if idx < s.workspace_mgr.workspace_count() {
-                    s.workspace_mgr.select_workspace(idx);
-                    for ps in s.pane_states.values() {
-                        ps.dirty.store(true, Ordering::Relaxed);
-                    }
-                    update_tabs(s, app_weak);
-                    request_redraw(app_weak);
-                }
-                return;
+/// Show how much output the active pane is holding back, if it is paused,
+/// or skipping, if it is fast-forwarding.
+fn update_paused_badge(s: &TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let Some(app) = app_weak.upgrade() else {
+        return;
+    };
+    let active = s.workspace_mgr.active_workspace().active_pane();
+    let ps = s.pane_states.get(&active);
+    let badge = match ps.map(|ps| (ps.held_bytes, ps.fast_forward)) {
+        Some((Some(held), _)) => format!("⏸ Output paused · {} buffered", format_bytes(held)),
+        Some((None, Some(skipped))) => format!(
+            "⏩ Output too fast to show · {} skipped",
+            format_bytes(usize::try_from(skipped).unwrap_or(usize::MAX))
+        ),
+        _ => String::new(),
+    };
+    if app.get_paused_badge() != badge.as_str() {
+        app.set_paused_badge(badge.into());
+    }
+}
+
+/// Note panes whose parser thread stopped answering queries, and those that
+/// came back. Only panes a query already timed out on are asked again.
+fn check_unresponsive_panes(s: &mut TerminalState) {
+    for (&pane_id, ps) in s.pane_states.iter_mut() {
+        if ps.failed.is_some() {
+            continue;
+        }
+        let unresponsive = ps.emulator.unresponsive();
+        match (ps.unresponsive, unresponsive) {
+            (None, Some(_)) => {
+                warn!(pane_id, "Pane not responding");
+                s.notifications.push(
+                    "Pane not responding",
+                    "Its screen stopped updating. Dump diagnostics or restart it from the banner.",
+                );
             }
-            // Any other Cmd/Ctrl+letter → send control character to PTY
-            // e.g. Cmd+L → \x0c (clear), Cmd+D → \x04 (EOF)
-            Some(letter) if letter.is_ascii_alphabetic() => {
-                let ctrl_byte = letter.to_ascii_lowercase() as u8 - b'a' + 1;
-                let active = s.workspace_mgr.active_workspace().active_pane();
-                if let Some(ps) = s.pane_states.get(&active) {
-                    let _ = ps.pty.write(&[ctrl_byte]);
-                }
-                request_redraw(app_weak);
-                return;
+            (Some(waited), None) => {
+                info!(pane_id, ?waited, "Pane responding again");
+                ps.dirty.store(true, Ordering::Relaxed);
             }
-            _ => return, // Unhandled Cmd/Ctrl combo — ignore
+            _ => {}
         }
+        ps.unresponsive = unresponsive;
     }
+}
 
-    // Clear selection on non-modifier key press
-    if s.selection.is_some() {
-        s.selection = None;
-        let active = s.workspace_mgr.active_workspace().active_pane();
-        if let Some(ps) = s.pane_states.get(&active) {
-            ps.dirty.store(true, Ordering::Relaxed);
+/// Show why the active pane failed or that it stopped responding, if it
+/// did, with a way to restart it.
+fn update_failure_banner(s: &TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let Some(app) = app_weak.upgrade() else {
+        return;
+    };
+    let active = s.workspace_mgr.active_workspace().active_pane();
+    let ps = s.pane_states.get(&active);
+    let unresponsive = ps.and_then(|ps| ps.unresponsive.filter(|_| ps.failed.is_none()));
+    let banner = match (ps.and_then(|ps| ps.failed.as_deref()), unresponsive) {
+        (Some(reason), _) => format!("⚠ This pane stopped working: {reason}"),
+        (None, Some(waited)) => format!("⚠ This pane hasn't responded for {}s", waited.as_secs()),
+        (None, None) => String::new(),
+    };
+    if app.get_failure_banner() != banner.as_str() {
+        app.set_failure_banner(banner.into());
+    }
+    if app.get_pane_unresponsive() != unresponsive.is_some() {
+        app.set_pane_unresponsive(unresponsive.is_some());
+    }
+}
+
+/// Replace a pane's shell with a new one, in the same directory and at the
+/// same size. Its history stays, above a separator line, unless the
+/// emulator itself failed or stopped responding: then that is replaced too,
+/// and the crash log keeps what went wrong. Returns whether the history
+/// was kept.
+fn restart_pane(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>, pane_id: PaneId) -> bool {
+    let Some(old) = s.pane_states.remove(&pane_id) else {
+        return false;
+    };
+    let cwd = old
+        .pty
+        .cwd()
+        .or_else(|| old.cwd.clone())
+        .unwrap_or_else(|| s.config.working_directory());
+    // A failed emulator can no longer tell its size; the last frame drawn can
+    let rows = u16::try_from(old.render_grid.len()).unwrap_or(24).max(1);
+    let cols = old.visible_cols;
+    let healthy = old.emulator.failure().is_none() && old.unresponsive.is_none();
+    // The old shell hangs up as its PTY closes
+    let PaneState { emulator, pty, .. } = old;
+    drop(pty);
+    let kept = healthy.then(|| {
+        // Output paused in the old shell would hold back the new one's
+        emulator.set_paused(false);
+        emulator
+    });
+    if kept.is_none() {
+        if let Some(renderer) = &mut s.renderer {
+            renderer.text_renderer.remove_pane(pane_id);
         }
     }
+    let now = SystemTime::now();
+    let separator = format!(
+        "── restarted at {} ──",
+        prompt_time::clock_time(now, prompt_time::local_offset(now))
+    );
+    let kept_history = kept.is_some();
+    let kept = kept.map(|emulator| (emulator, separator.as_str()));
+    let ps = spawn_pane_on(&s.config, &s.theme, pane_id, cols, rows, &cwd, kept);
+    s.pane_states.insert(pane_id, ps);
+    info!(pane_id, kept_history, "Pane restarted");
+    update_failure_banner(s, app_weak);
+    update_sidebar(s, app_weak);
+    request_redraw(app_weak);
+    kept_history
+}
 
-    // Convert key to bytes
-    let bytes = slint_key_to_bytes(ch, ctrl, &text);
-    if let Some(bytes) = bytes {
-        let active = s.workspace_mgr.active_workspace().active_pane();
-        if let Some(ps) = s.pane_states.get(&active) {
-            let _ = ps.pty.write(&bytes);
+/// Apply a viewport size to a pane. In no-wrap mode the grid keeps at least
+/// `nowrap.columns` columns so long lines extend past the viewport.
+fn resize_pane(ps: &mut PaneState, cols: u16, rows: u16, config: &Config) {
+    ps.visible_cols = cols;
+    let grid_cols = if ps.no_wrap {
+        cols.max(config.nowrap.columns)
+    } else {
+        cols
+    };
+    ps.h_offset = ps.h_offset.min(grid_cols.saturating_sub(cols));
+    ps.emulator.resize(grid_cols, rows);
+    let _ = ps.pty.resize(grid_cols, rows);
+    ps.dirty.store(true, Ordering::Relaxed);
+}
+
+/// Write a state snapshot to the default place and say where it went.
+fn save_state_dump(s: &mut TerminalState) {
+    let path = debug_dump::default_path();
+    match dump_state(s, &path) {
+        Ok(_) => {
+            s.notifications.push(
+                "Debug state saved",
+                format!("{}. Attach it to your issue.", path.display()),
+            );
+        }
+        Err(e) => {
+            s.notifications
+                .push("Debug state not saved", format!("{e:#}"));
         }
-        request_redraw(app_weak);
     }
 }
 
-fn slint_key_to_bytes(ch: char, ctrl: bool, text: &str) -> Option<Vec<u8>> {
-    // Special keys
-    match ch {
-        '\u{000a}' => return Some(b"\r".to_vec()),       // Return
-        '\u{0008}' => return Some(b"\x7f".to_vec()),     // Backspace
-        '\u{0009}' => return Some(b"\t".to_vec()),        // Tab
-        '\u{001b}' => return Some(b"\x1b".to_vec()),     // Escape
-        '\u{007f}' => return Some(b"\x1b[3~".to_vec()),  // Delete
-        '\u{F700}' => return Some(b"\x1b[A".to_vec()),   // Up
-        '\u{F701}' => return Some(b"\x1b[B".to_vec()),   // Down
-        '\u{F702}' => return Some(b"\x1b[D".to_vec()),   // Left
-        '\u{F703}' => return Some(b"\x1b[C".to_vec()),   // Right
-        '\u{F729}' => return Some(b"\x1b[H".to_vec()),   // Home
-        '\u{F72B}' => return Some(b"\x1b[F".to_vec()),   // End
-        '\u{F72C}' => return Some(b"\x1b[5~".to_vec()),  // PageUp
-        '\u{F72D}' => return Some(b"\x1b[6~".to_vec()),  // PageDown
-        '\u{F727}' => return Some(b"\x1b[2~".to_vec()),  // Insert
-        '\u{0020}' => return Some(b" ".to_vec()),         // Space
-        _ => {}
+/// Write a state snapshot for a bug report to `path`: config, each
+/// workspace's split tree, pane and renderer sizes, recent log lines,
+/// sidebar contributions and the redacted text of every screen. Returns the
+/// files it holds.
+fn dump_state(s: &TerminalState, path: &Path) -> Result<Vec<String>> {
+    let mut dump = StateDump::new();
+    dump.add_json("config.json", &debug_dump::redacted_config(&s.config));
+
+    let mut layout = String::new();
+    for (idx, ws) in s.workspace_mgr.workspaces().iter().enumerate() {
+        let active = if idx == s.workspace_mgr.active_index() {
+            " (active)"
+        } else {
+            ""
+        };
+        layout.push_str(&format!(
+            "workspace {} \"{}\"{active}, active pane {}\n{:#?}\n\n",
+            ws.id,
+            ws.name,
+            ws.active_pane(),
+            ws.split_tree
+        ));
     }
+    dump.add("layout.txt", &layout);
 
-    // Ctrl+letter → control character
-    if ctrl && ch.is_ascii_alphabetic() {
-        return Some(vec![ch.to_ascii_lowercase() as u8 - b'a' + 1]);
+    let mut pane_ids: Vec<PaneId> = s.pane_states.keys().copied().collect();
+    pane_ids.sort_unstable();
+    let panes: Vec<serde_json::Value> = pane_ids
+        .iter()
+        .map(|pane_id| {
+            let ps = &s.pane_states[pane_id];
+            let TermMetadata {
+                cols,
+                rows,
+                history_size,
+                ..
+            } = ps.emulator.metadata();
+            let stalls = ps.emulator.input_stalls();
+            serde_json::json!({
+                "id": pane_id,
+                "cols": cols,
+                "rows": rows,
+                "visible_cols": ps.visible_cols,
+                "no_wrap": ps.no_wrap,
+                "history": history_size,
+                "alive": ps.pty.is_alive(),
+                "held_bytes": ps.held_bytes,
+                "fast_forward": ps.fast_forward,
+                "input_stopped": ps.pty.input_stopped(),
+                "input_stalls": {
+                    "count": stalls.count,
+                    "total_ms": stalls.total.as_millis() as u64,
+                    "longest_ms": stalls.longest.as_millis() as u64,
+                },
+                "failed": ps.failed,
+                "unresponsive_ms": ps.unresponsive.map(|waited| waited.as_millis() as u64),
+                "queued_commands": ps.emulator.queued_commands(),
+            })
+        })
+        .collect();
+    dump.add_json("panes.json", &serde_json::json!(panes));
+
+    let renderer = s.renderer.as_ref().map(|renderer| {
+        let text = &renderer.text_renderer;
+        serde_json::json!({
+            "width": renderer.width(),
+            "height": renderer.height(),
+            "scale_factor": text.scale_factor(),
+            "font_size": text.font_size(),
+            "cell_size": text.cell_size(),
+        })
+    });
+    dump.add_json(
+        "renderer.json",
+        &serde_json::json!({
+            "renderer": renderer,
+            "scale_factor": s.scale_factor,
+            "slint_scale_factor": s.slint_scale_factor,
+            "font_family": s.font_family,
+        }),
+    );
+
+    let log: Vec<String> = log_ring::global()
+        .lines()
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    dump.add("log.txt", &log.join("\n"));
+
+    let plugins: Vec<serde_json::Value> = s
+        .contributions
+        .sidebar_items()
+        .into_iter()
+        .map(|item| {
+            serde_json::json!({
+                "view_id": item.view_id,
+                "title": item.title,
+                "active": item.active,
+            })
+        })
+        .collect();
+    dump.add_json("plugins.json", &serde_json::json!(plugins));
+
+    for pane_id in pane_ids {
+        let grid = s.pane_states[&pane_id].emulator.extract_grid(&s.theme);
+        let text = debug_dump::redact(&grid_to_text(&grid));
+        dump.add(format!("screens/pane-{pane_id}.txt"), &text);
     }
 
-    // Regular text
-    Some(text.as_bytes().to_vec())
+    dump.write_zip(path)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    info!(path = %path.display(), "State dump written");
+    Ok(dump.names())
 }
 
-// ---------------------------------------------------------------------------
-// Pane divider lines
-// ---------------------------------------------------------------------------
+/// Rendering was set up again, after the GPU device was lost (driver reset,
+/// eGPU unplugged) or the window's graphics torn down. Panes, their
+/// terminals and shaped text are kept; only GPU state is rebuilt, and every
+/// pane redrawn.
+fn recover_renderer(s: &mut TerminalState, device: &wgpu::Device, queue: &wgpu::Queue) {
+    let Some(renderer) = &mut s.renderer else {
+        return;
+    };
+    renderer.recover(device.clone(), queue.clone());
+    for ps in s.pane_states.values() {
+        ps.dirty.store(true, Ordering::Relaxed);
+    }
+    s.tab_previews.clear();
+    info!("Renderer rebuilt on a new GPU device");
+}
 
-/// Build thin BgRect divider lines at split boundaries between panes.
-fn build_divider_rects(
-    layout: &[(PaneId, pterminal_core::split::PaneRect)],
-    window_w: u32,
-    window_h: u32,
-    scale: f32,
-    tab_bar_h: f32,
-) -> Vec<BgRect> {
-    if layout.len() <= 1 {
-        return Vec::new();
+/// Apply a new display scale: fonts, cell metrics and padding follow it.
+/// Pane grids are re-derived by the caller once the viewport is resized too.
+fn set_scale_factor(s: &mut TerminalState, scale: f64) {
+    info!(from = s.scale_factor, to = scale, "Display scale changed");
+    s.scale_factor = scale;
+    let font_size = base_font_size(s);
+    if let Some(renderer) = &mut s.renderer {
+        renderer.text_renderer.update_scale_factor(scale, font_size);
     }
-    let cw = (window_w as f32).max(1.0);
-    let ch = window_h as f32 - tab_bar_h;
-    let thickness = (1.0 * scale).max(1.0);
-    let mut rects = Vec::new();
-    let mut edges_seen: Vec<(f32, f32, f32, f32, bool)> = Vec::new(); // (pos, start, end, _, is_vertical)
-
-    for (_, pr) in layout {
-        // Right edge → vertical divider
-        let right = pr.x + pr.width;
-        if right > 0.001 && right < 0.999 {
-            let px = right * cw;
-            let py = pr.y * ch + tab_bar_h;
-            let ph = pr.height * ch;
-            let key = (px as i32, py as i32, ph as i32);
-            if !edges_seen.iter().any(|(a, b, c, _, v)| {
-                *v && (*a as i32 == key.0) && (*b as i32 == key.1) && (*c as i32 == key.2)
-            }) {
-                edges_seen.push((px, py, ph, 0.0, true));
-                rects.push(BgRect {
-                    x: px - thickness * 0.5,
-                    y: py,
-                    w: thickness,
-                    h: ph,
-                    color: DIVIDER_COLOR,
-                });
-            }
-        }
-        // Bottom edge → horizontal divider
-        let bottom = pr.y + pr.height;
-        if bottom > 0.001 && bottom < 0.999 {
-            let py = bottom * ch + tab_bar_h;
-            let px = pr.x * cw;
-            let pw = pr.width * cw;
-            let key = (px as i32, py as i32, pw as i32);
-            if !edges_seen.iter().any(|(a, b, c, _, v)| {
-                !*v && (*a as i32 == key.0) && (*b as i32 == key.1) && (*c as i32 == key.2)
-            }) {
-                edges_seen.push((px, py, pw, 0.0, false));
-                rects.push(BgRect {
-                    x: px,
-                    y: py - thickness * 0.5,
-                    w: pw,
-                    h: thickness,
-                    color: DIVIDER_COLOR,
-                });
-            }
+    for ps in s.pane_states.values() {
+        ps.dirty.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Font size for panes without an override
+fn base_font_size(s: &TerminalState) -> f32 {
+    if s.presentation.is_some() {
+        s.config.presentation.font_size
+    } else {
+        s.config.font.size
+    }
+}
+
+/// The configured theme for the OS appearance; the default for unknown names.
+/// `accessibility.high_contrast` overrides both.
+fn resolve_theme(config: &Config, appearance: Option<Appearance>) -> Theme {
+    if config.accessibility.high_contrast {
+        return with_min_contrast(config, Theme::high_contrast());
+    }
+    let name = config.theme.active_name(appearance);
+    load_theme(config, name).unwrap_or_else(|| {
+        warn!("Unknown theme {name:?}, using the default");
+        with_min_contrast(config, Theme::default())
+    })
+}
+
+/// Theme called `name`, with at least `accessibility.min_contrast`
+fn load_theme(config: &Config, name: &str) -> Option<Theme> {
+    let theme = Theme::load(name, &Config::themes_dir())?;
+    Some(with_min_contrast(config, theme))
+}
+
+fn with_min_contrast(config: &Config, mut theme: Theme) -> Theme {
+    theme.min_contrast = theme.min_contrast.max(config.accessibility.min_contrast);
+    theme
+}
+
+/// Quick toggle for `accessibility.high_contrast`, until the next launch.
+fn toggle_high_contrast(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let on = !s.config.accessibility.high_contrast;
+    s.config.accessibility.high_contrast = on;
+    let theme = resolve_theme(&s.config, s.appearance);
+    set_theme(s, app_weak, theme);
+    info!(on, "high contrast");
+}
+
+/// Step `accessibility.color_vision` through the simulations and back to
+/// none, until the next launch.
+#[cfg(target_os = "macos")]
+fn cycle_color_vision(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let current = s.config.accessibility.color_vision();
+    let next = match current {
+        None => ColorVision::ALL.first(),
+        Some(vision) => ColorVision::ALL
+            .iter()
+            .skip_while(|&&other| other != vision)
+            .nth(1),
+    };
+    s.config.accessibility.color_vision = next.map_or("", |vision| vision.name()).to_string();
+    apply_post_effect(s);
+    let body = match next {
+        Some(vision) => format!("Showing the screen as seen with {}.", vision.name()),
+        None => "Showing the screen as it is.".to_string(),
+    };
+    s.notifications.push("Color blindness simulation", body);
+    request_redraw(app_weak);
+}
+
+/// With `accessibility.reduce_motion` "auto", follow the OS setting.
+fn sync_reduced_motion(s: &mut TerminalState) {
+    if s.last_motion_check.elapsed() < MOTION_CHECK_INTERVAL {
+        return;
+    }
+    s.last_motion_check = Instant::now();
+    let reduce = s
+        .config
+        .accessibility
+        .reduce_motion_enabled(appearance::reduced_motion);
+    if reduce != s.reduce_motion {
+        s.reduce_motion = reduce;
+        info!(reduce, "reduced motion");
+        apply_animations(s);
+    }
+}
+
+/// Switch every pane to `theme`. While presenting in high contrast it takes
+/// effect when presentation mode ends.
+fn set_theme(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>, theme: Theme) {
+    let theme = Arc::new(theme);
+    match &mut s.presentation {
+        Some(saved) if s.config.presentation.high_contrast => saved.theme = theme,
+        Some(saved) => {
+            saved.theme = Arc::clone(&theme);
+            s.theme = theme;
         }
+        None => s.theme = theme,
+    }
+    apply_post_effect(s);
+    for ps in s.pane_states.values() {
+        ps.dirty.store(true, Ordering::Relaxed);
     }
-    rects
+    request_redraw(app_weak);
 }
 
-// ---------------------------------------------------------------------------
-// Render pipeline
-// ---------------------------------------------------------------------------
+/// Resolve `font.family` to an installed monospace family and set up the
+/// bold/italic faces, warning about configured families that are missing.
+fn apply_font_family(s: &mut TerminalState) {
+    let Some(renderer) = &mut s.renderer else {
+        return;
+    };
+    let resolution = renderer
+        .text_renderer
+        .set_font_family(&s.config.font.families());
+    let missing_faces = renderer.text_renderer.set_font_faces(font_faces(&s.config));
+    if !resolution.missing.is_empty() || !missing_faces.is_empty() {
+        let missing = [resolution.missing, missing_faces].concat().join(", ");
+        let using = resolution.family.as_deref().unwrap_or("the system default");
+        warn!("Fonts not found: {missing}; using {using}");
+        s.notifications.push(
+            "Font not found",
+            format!("Not installed (or not monospace): {missing}. Using {using}."),
+        );
+    }
+    s.font_family = resolution.family;
+}
 
-fn render_frame(s: &mut TerminalState, theme: &Arc<Theme>, app_weak: &slint::Weak<AppWindow>) {
+/// Compile the current theme's post-processing shader (or `theme.shader`),
+/// reporting failures as notifications.
+fn apply_post_effect(s: &mut TerminalState) {
+    let name = match &s.theme.shader {
+        _ if s.eco => String::new(),
+        Some(name) => name.clone(),
+        None => s.config.theme.shader.clone(),
+    };
     let Some(renderer) = &mut s.renderer else {
         return;
     };
-    let w = renderer.width();
-    let h = renderer.height();
+    let source = if name.is_empty() {
+        Ok(None)
+    } else if let Some(source) = post::preset(&name) {
+        Ok(Some(source.to_string()))
+    } else {
+        std::fs::read_to_string(Config::config_dir().join(&name)).map(Some)
+    };
+    let result = source
+        .map_err(anyhow::Error::from)
+        .and_then(|source| renderer.set_post_effect(source.as_deref()));
+    if let Err(e) = result {
+        warn!("Shader {name}: {e}");
+        let title = format!("Shader {name} failed");
+        s.notifications.push(title, e.to_string());
+    }
 
-    let layout = s.workspace_mgr.active_workspace().split_tree.layout();
-    let active_pane = s.workspace_mgr.active_workspace().active_pane();
+    let vision = s.config.accessibility.color_vision();
+    if let Err(e) = renderer.set_color_vision(vision.map(ColorVision::matrix)) {
+        warn!("Color blindness simulation: {e}");
+    }
+}
 
-    let mut pane_rects: Vec<(PaneId, PixelRect)> = Vec::with_capacity(layout.len());
-    let cursor_color = theme.colors.cursor;
-    let mut any_updated = false;
+/// With `theme.follow_system`, re-theme when the OS switches between dark
+/// and light.
+fn sync_system_theme(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    if !s.config.theme.follow_system {
+        return;
+    }
+    let reported = s.appearance_changed.take();
+    let appearance = if reported.is_some() {
+        reported
+    } else if s.last_appearance_check.elapsed() >= APPEARANCE_CHECK_INTERVAL {
+        s.last_appearance_check = Instant::now();
+        appearance::detect()
+    } else {
+        return;
+    };
+    if appearance.is_none() || appearance == s.appearance {
+        return;
+    }
+    s.appearance = appearance;
+    let theme = resolve_theme(&s.config, appearance);
+    set_theme(s, app_weak, theme);
+}
 
-    for (pane_id, pane_rect) in &layout {
-        let scale = s.scale_factor as f32;
-        let px_rect = pane_to_pixel_rect(pane_rect, w, h, scale, 0.0);
+/// With `power.eco` "auto", follow the power source: eco mode on battery.
+fn sync_power_source(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    if s.last_power_check.elapsed() < POWER_CHECK_INTERVAL {
+        return;
+    }
+    s.last_power_check = Instant::now();
+    let eco = s
+        .eco_override
+        .unwrap_or_else(|| s.config.power.eco_enabled(power::on_battery));
+    set_eco(s, app_weak, eco);
+}
 
-        if let Some(ps) = s.pane_states.get_mut(pane_id) {
-            ps.redraw_queued.store(false, Ordering::Release);
-            let show_cursor = *pane_id == active_pane;
-            let content_dirty = ps.dirty.load(Ordering::Acquire);
-            let cursor_changed = ps.last_cursor_visible != show_cursor;
-            let selection_active = *pane_id == active_pane && s.selection.is_some();
+/// Show the active pane's screen and cursor to screen readers, and read out
+/// its new output. Switching panes doesn't read out the whole screen.
+fn update_screen_reader(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let Some(app) = app_weak.upgrade() else {
+        return;
+    };
+    if !s.config.accessibility.screen_reader {
+        if s.announcer_pane.take().is_some() {
+            app.set_screen_text("".into());
+            app.set_cursor_position("".into());
+            app.set_announcement("".into());
+        }
+        return;
+    }
+    let pane_id = s.workspace_mgr.active_workspace().active_pane();
+    let Some(ps) = s.pane_states.get(&pane_id) else {
+        return;
+    };
+    let lines = accessibility::screen_lines(&ps.emulator.extract_grid(&s.theme));
+    let (col, row) = ps.emulator.metadata().cursor;
+    app.set_screen_text(lines.join("\n").into());
+    app.set_cursor_position(accessibility::cursor_description(row, col).into());
+    if s.announcer_pane != Some(pane_id) {
+        s.announcer_pane = Some(pane_id);
+        s.announcer.reset(lines);
+        return;
+    }
+    let announcement = s.announcer.update(lines);
+    if let Some(text) = announcement.filter(|_| s.config.accessibility.announce_output) {
+        app.set_announcement(text.into());
+    }
+}
 
-            if content_dirty || cursor_changed || selection_active {
-                let cursor_pos;
-                if content_dirty || ps.render_grid.is_empty() {
-                    // Use timeout to avoid blocking main thread during high throughput
-                    let (delta, cursor) = ps
-                        .emulator
-                        .extract_grid_delta_with_cursor_into_timeout(
-                            theme,
-                            &mut ps.render_grid,
-                            Some(Duration::from_millis(2)),
-                        );
-                    cursor_pos = cursor;
-                    ps.render_dirty_rows.clear();
-                    if delta.full {
-                        ps.render_dirty_rows.extend(0..ps.render_grid.len());
-                    } else {
-                        ps.render_dirty_rows.extend(delta.dirty_rows);
-                    }
-                } else {
-                    cursor_pos = ps.emulator.cursor_position();
+/// Place the prompt timestamps of panes showing the gutter beside the lines
+/// their commands were typed on
+fn update_prompt_gutter(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    s.last_prompt_gutter_update = Instant::now();
+    let Some(renderer) = &s.renderer else {
+        return;
+    };
+    let sf = s.slint_scale_factor as f32;
+    let now = SystemTime::now();
+    let mut labels = Vec::new();
+    for (pane_id, _) in s.workspace_mgr.active_workspace().split_tree.layout() {
+        let Some(ps) = s.pane_states.get(&pane_id).filter(|ps| ps.prompt_gutter) else {
+            continue;
+        };
+        let Some(rect) = pane_pixel_rect(s, pane_id) else {
+            continue;
+        };
+        let gutter = gutter_width(renderer, pane_id, true).min(rect.w);
+        let (cell_w, cell_h) = renderer.text_renderer.pane_cell_size(pane_id);
+        for (row, time) in ps.emulator.prompt_times() {
+            let offset = prompt_time::local_offset(time.started);
+            labels.push(PromptTimeLabel {
+                x: (rect.x - gutter) / sf,
+                y: (rect.y + row as f32 * cell_h) / sf,
+                width: (gutter - cell_w).max(0.0) / sf,
+                height: cell_h / sf,
+                text: time.label(&s.config.prompt_times.format, now).into(),
+                time: prompt_time::full_time(time.started, offset).into(),
+                failed: time.exit_code.is_some_and(|code| code != 0),
+            });
+        }
+    }
+    if labels == s.prompt_gutter {
+        return;
+    }
+    if let Some(app) = app_weak.upgrade() {
+        let model = Rc::new(slint::VecModel::from(labels.clone()));
+        app.set_prompt_times(slint::ModelRc::from(model));
+    }
+    s.prompt_gutter = labels;
+}
+
+/// Status bar segments contributed by installed plugins that are enabled
+fn plugin_status_segments() -> Vec<StatusSegmentContribution> {
+    let catalog = match discover_plugin_catalog(Config::plugins_dir()) {
+        Ok(catalog) => catalog,
+        Err(e) => {
+            warn!("Can't read the plugins directory: {e}");
+            return Vec::new();
+        }
+    };
+    for diagnostic in &catalog.diagnostics {
+        let dir = diagnostic.plugin_dir.display();
+        warn!("Plugin in {dir} skipped: {}", diagnostic.message);
+    }
+    catalog
+        .plugins
+        .into_iter()
+        .filter(|plugin| plugin.enabled)
+        .flat_map(|plugin| plugin.manifest.contributes.status_segments)
+        .collect()
+}
+
+/// The status bar's segments with their text, leaving out those with
+/// nothing to show; none when the bar is off or while presenting
+fn status_bar_segments(s: &TerminalState) -> Vec<(StatusSegment, String)> {
+    let config = &s.config.status_bar;
+    if !config.enabled || s.presentation.is_some() {
+        return Vec::new();
+    }
+    let active = s.workspace_mgr.active_workspace().active_pane();
+    let ps = s.pane_states.get(&active);
+    let contributed: Vec<&str> = s
+        .contributions
+        .status_segments()
+        .iter()
+        .map(|segment| segment.id.as_str())
+        .collect();
+    status_bar::segments(&config.segments, &contributed)
+        .into_iter()
+        .filter_map(|segment| {
+            let text = match &segment {
+                StatusSegment::Cwd => ps.and_then(|ps| ps.cwd.as_deref()).map(home_relative),
+                StatusSegment::GitBranch => ps
+                    .and_then(|ps| ps.git_branch.as_ref())
+                    .map(|branch| format!("⎇ {branch}")),
+                StatusSegment::Time => {
+                    let now = SystemTime::now();
+                    let time = prompt_time::clock_time(now, prompt_time::local_offset(now));
+                    Some(time[..5].to_string())
                 }
-                let sel = if *pane_id == active_pane {
-                    s.selection.map(|sel| sel.normalized())
-                } else {
-                    None
-                };
+                StatusSegment::PaneSize => ps.map(|ps| {
+                    let grid = ps.emulator.metadata();
+                    format!("{}×{}", grid.cols, grid.rows)
+                }),
+                StatusSegment::Scroll => {
+                    ps.map(|ps| status_bar::scroll_text(ps.emulator.metadata().display_offset))
+                }
+                StatusSegment::Notifications => Some(status_bar::notifications_text(
+                    s.notifications.unread_count(),
+                )),
+                StatusSegment::Plugin(id) => s
+                    .contributions
+                    .status_segment(id)
+                    .map(|segment| segment.text.clone()),
+            };
+            text.filter(|text| !text.is_empty())
+                .map(|text| (segment, text))
+        })
+        .collect()
+}
 
-                renderer.text_renderer.set_pane_content(
-                    *pane_id,
-                    &ps.render_grid,
-                    if content_dirty {
-                        Some(&ps.render_dirty_rows)
-                    } else {
-                        Some(&[])
-                    },
-                    cursor_pos,
-                    show_cursor,
-                    cursor_color,
-                    theme.colors.background,
-                    sel,
-                    theme.colors.selection_bg,
-                );
-                ps.last_cursor_visible = show_cursor;
-                ps.dirty.store(false, Ordering::Relaxed);
-                any_updated = true;
+/// Hand the status bar to the renderer, relaying out the panes when it
+/// appears, goes or changes height; returns whether it changed
+fn update_status_bar(s: &mut TerminalState) -> bool {
+    let Some(renderer) = &mut s.renderer else {
+        return false;
+    };
+    let height = renderer.text_renderer.status_bar_height();
+    let changed = if s.status_bar.is_empty() {
+        renderer.text_renderer.clear_status_bar()
+    } else {
+        let texts: Vec<String> = s.status_bar.iter().map(|(_, text)| text.clone()).collect();
+        let colors = &s.theme.colors;
+        renderer.text_renderer.set_status_bar(
+            &texts,
+            s.config.status_bar.font_size,
+            colors.selection_fg,
+            colors.selection_bg,
+        )
+    };
+    if renderer.text_renderer.status_bar_height() != height {
+        resize_active_workspace_panes(s);
+    }
+    changed
+}
+
+/// Act on a click on a status bar segment: copy the directory or branch,
+/// scroll back down, jump to the latest alert, or run a plugin segment's
+/// command
+fn click_status_segment(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>, idx: usize) {
+    let Some((segment, _)) = s.status_bar.get(idx).cloned() else {
+        return;
+    };
+    let active = s.workspace_mgr.active_workspace().active_pane();
+    match segment {
+        StatusSegment::Cwd | StatusSegment::GitBranch => {
+            let text = s.pane_states.get(&active).and_then(|ps| match segment {
+                StatusSegment::Cwd => ps.cwd.as_ref().map(|cwd| cwd.display().to_string()),
+                _ => ps.git_branch.clone(),
+            });
+            if let (Some(text), Some(clip)) = (text, &mut s.clipboard) {
+                let _ = clip.set_text(text);
             }
         }
+        StatusSegment::Scroll => {
+            if let Some(ps) = s.pane_states.get(&active) {
+                let offset = ps.emulator.metadata().display_offset;
+                ps.emulator.scroll(-(offset as i32));
+                ps.dirty.store(true, Ordering::Relaxed);
+            }
+        }
+        StatusSegment::Notifications => jump_to_latest_alert(s, app_weak),
+        StatusSegment::Plugin(id) => {
+            let run = s
+                .contributions
+                .status_segment(&id)
+                .and_then(|segment| segment.run.clone());
+            if let (Some(run), Some(ps)) = (run, s.pane_states.get_mut(&active)) {
+                let _ = send_input(ps, format!("{run}\r").as_bytes());
+            }
+        }
+        StatusSegment::Time | StatusSegment::PaneSize => {}
+    }
+    request_redraw(app_weak);
+}
 
-        pane_rects.push((*pane_id, px_rect));
+/// Show or hide the active pane's prompt timestamps gutter
+fn toggle_prompt_gutter(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let active = s.workspace_mgr.active_workspace().active_pane();
+    let Some(ps) = s.pane_states.get_mut(&active) else {
+        return;
+    };
+    ps.prompt_gutter = !ps.prompt_gutter;
+    ps.dirty.store(true, Ordering::Relaxed);
+    resize_active_workspace_panes(s);
+    update_prompt_gutter(s, app_weak);
+    request_redraw(app_weak);
+}
+
+/// Copy the full date and time of a gutter's prompt timestamp
+fn copy_prompt_time(s: &mut TerminalState, index: usize) {
+    let Some(label) = s.prompt_gutter.get(index) else {
+        return;
+    };
+    let time = label.time.to_string();
+    if let Some(clip) = &mut s.clipboard {
+        let _ = clip.set_text(time.clone());
+    }
+    s.notifications.push("Timestamp copied", time);
+}
+
+/// Turn eco mode on or off: frame rate, animations, shader, tab previews
+/// and worker thread polling all follow it.
+fn set_eco(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>, on: bool) {
+    if on == s.eco {
+        return;
+    }
+    s.eco = on;
+    power::set_eco(on);
+    apply_animations(s);
+    apply_post_effect(s);
+    for ps in s.pane_states.values() {
+        ps.dirty.store(true, Ordering::Relaxed);
+    }
+    info!(on, "eco mode");
+    request_redraw(app_weak);
+}
+
+/// Quick toggle: flip eco mode until the next toggle, whatever `power.eco`
+/// says.
+fn toggle_eco(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let on = !s.eco;
+    s.eco_override = Some(on);
+    set_eco(s, app_weak, on);
+    if on {
+        s.notifications
+            .push("Eco mode on", "Lower frame rate, no animations or shader");
+    } else {
+        s.notifications
+            .push("Eco mode off", "Full frame rate and effects");
+    }
+}
+
+/// Target time between frames: `power.eco_max_fps` in eco mode
+fn frame_interval(s: &TerminalState) -> Duration {
+    if s.eco {
+        power::frame_interval(s.config.power.eco_max_fps).max(MIN_FRAME_INTERVAL)
+    } else {
+        MIN_FRAME_INTERVAL
+    }
+}
+
+/// Cursor and scroll animations from config; none in eco mode
+fn apply_animations(s: &mut TerminalState) {
+    let still = s.eco || s.reduce_motion;
+    let Some(renderer) = &mut s.renderer else {
+        return;
+    };
+    let cursor = cursor_animation(&s.config).filter(|_| !still);
+    let scroll = scroll_animation(&s.config).filter(|_| !still);
+    renderer.text_renderer.set_cursor_animation(cursor);
+    renderer.text_renderer.set_scroll_animation(scroll);
+    renderer
+        .text_renderer
+        .set_cursor_scale(s.config.accessibility.cursor_scale);
+}
+
+/// Apply an option just set over IPC; `keys::LIVE_KEYS` lists the ones
+/// handled here or read from the config as they are used.
+fn apply_config_change(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>, key: &str) {
+    match key.split('.').next().unwrap_or(key) {
+        "font" | "presentation" => {
+            keep_grids_for_zoom(s);
+            apply_font_family(s);
+            let (scale, font_size) = (s.scale_factor, base_font_size(s));
+            let font = &s.config.font;
+            if let Some(renderer) = &mut s.renderer {
+                renderer
+                    .text_renderer
+                    .set_cell_metrics(font.line_height, font.letter_spacing);
+                renderer.text_renderer.update_scale_factor(scale, font_size);
+            }
+            resize_active_workspace_panes(s);
+        }
+        "theme" | "accessibility" => {
+            let theme = resolve_theme(&s.config, s.appearance);
+            set_theme(s, app_weak, theme);
+            apply_animations(s);
+        }
+        "cursor" | "scrollback" => apply_animations(s),
+        "bidi" => apply_bidi(s),
+        "notification" => s.notification_rules = NotificationRules::new(&s.config.notification),
+        _ => {}
+    }
+    for ps in s.pane_states.values() {
+        ps.dirty.store(true, Ordering::Relaxed);
+    }
+    request_redraw(app_weak);
+}
+
+/// Show right-to-left text in visual order if bidi mode is on
+fn apply_bidi(s: &mut TerminalState) {
+    let direction = s.config.bidi.base_direction();
+    if let Some(renderer) = &mut s.renderer {
+        renderer.text_renderer.set_bidi(direction);
+    }
+}
+
+/// Enter or leave presentation mode: larger text, no tab bar or sidebar, and
+/// optionally the high-contrast theme.
+fn set_presentation(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>, enabled: bool) {
+    if enabled == s.presentation.is_some() {
+        return;
+    }
+    keep_grids_for_zoom(s);
+    if enabled {
+        let saved = Presentation {
+            theme: Arc::clone(&s.theme),
+        };
+        if s.config.presentation.high_contrast {
+            s.theme = Arc::new(Theme::high_contrast());
+        }
+        s.presentation = Some(saved);
+    } else if let Some(saved) = s.presentation.take() {
+        s.theme = saved.theme;
+    }
+    apply_post_effect(s);
+
+    let font_size = base_font_size(s);
+    let scale = s.scale_factor;
+    if let Some(renderer) = &mut s.renderer {
+        renderer.text_renderer.update_scale_factor(scale, font_size);
+    }
+    s.selection = None;
+    s.keystrokes.clear();
+    resize_active_workspace_panes(s);
+    if let Some(app) = app_weak.upgrade() {
+        app.set_presentation(enabled);
+    }
+    request_redraw(app_weak);
+}
+
+/// Open the snippet picker for the active pane.
+fn open_snippet_picker(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    if s.config.snippets.is_empty() {
+        info!("No snippets configured");
+        return;
+    }
+    s.picker = Some(Picker {
+        pane_id: s.workspace_mgr.active_workspace().active_pane(),
+        mode: PickerMode::Snippets(Vec::new()),
+    });
+    filter_picker(s, app_weak, "");
+    show_picker(app_weak, "Insert snippet", "", None);
+}
+
+/// Open the history picker below the active pane's cursor. Commands captured
+/// through shell integration come first, then the shell's history file; the
+/// text already typed at the prompt becomes the initial query.
+fn open_history_picker(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    if !s.config.history.enabled {
+        return;
+    }
+    let pane_id = s.workspace_mgr.active_workspace().active_pane();
+    let Some(ps) = s.pane_states.get(&pane_id) else {
+        return;
+    };
+    let typed = ps.emulator.command_line().unwrap_or_default();
+    let config = &s.config.history;
+    let path = if config.file.is_empty() {
+        shell_history::history_path(&s.config.general.shell)
+    } else {
+        Some(PathBuf::from(&config.file))
+    };
+    let mut entries: Vec<String> = ps.emulator.recent_commands().into_iter().rev().collect();
+    if let Some(path) = path {
+        entries.extend(shell_history::load(&path, config.max_entries));
+    }
+    let mut seen = std::collections::HashSet::new();
+    entries.retain(|cmd| seen.insert(cmd.clone()));
+    if entries.is_empty() {
+        info!("No command history found");
+        return;
+    }
+
+    let anchor = cursor_anchor(s, pane_id);
+    s.picker = Some(Picker {
+        pane_id,
+        mode: PickerMode::History {
+            entries,
+            matches: Vec::new(),
+            typed: typed.clone(),
+        },
+    });
+    filter_picker(s, app_weak, &typed);
+    show_picker(app_weak, "History", &typed, anchor);
+}
+
+/// Logical position just below a pane's cursor, relative to the terminal area
+fn cursor_anchor(s: &TerminalState, pane_id: PaneId) -> Option<(f32, f32)> {
+    let rect = pane_pixel_rect(s, pane_id)?;
+    let (cell_w, cell_h) = s.renderer.as_ref()?.text_renderer.pane_cell_size(pane_id);
+    let ps = s.pane_states.get(&pane_id)?;
+    let (col, row) = ps.emulator.metadata().cursor;
+    let col = col.saturating_sub(ps.h_offset);
+    let sf = s.slint_scale_factor as f32;
+    Some((
+        (rect.x + col as f32 * cell_w) / sf,
+        (rect.y + (row + 1) as f32 * cell_h) / sf,
+    ))
+}
+
+fn show_picker(
+    app_weak: &slint::Weak<AppWindow>,
+    prompt: &str,
+    query: &str,
+    anchor: Option<(f32, f32)>,
+) {
+    let Some(app) = app_weak.upgrade() else {
+        return;
+    };
+    let (x, y) = anchor.unwrap_or((-1.0, -1.0));
+    app.set_picker_anchor_x(x);
+    app.set_picker_anchor_y(y);
+    app.set_picker_prompt(prompt.into());
+    app.set_picker_query(query.into());
+    app.set_picker_selected(0);
+    app.set_picker_visible(true);
+    focus_later(app_weak, AppWindow::invoke_focus_picker);
+}
+
+fn close_picker(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    s.picker = None;
+    // Leaving the theme picker without a choice reverts the preview
+    if let Some((pane_id, _)) = s.theme_preview.take() {
+        mark_pane_dirty(s, pane_id);
+        request_redraw(app_weak);
+    }
+    if let Some(app) = app_weak.upgrade() {
+        app.set_picker_visible(false);
+        app.set_picker_items(slint::ModelRc::default());
+    }
+    focus_later(app_weak, AppWindow::invoke_focus_terminal);
+}
+
+/// Show every workspace as a thumbnail, with the active one selected.
+fn open_overview(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    if s.picker.is_some() {
+        close_picker(s, app_weak);
+    }
+    s.overview = true;
+    // The active workspace's thumbnail is taken on the next frame
+    let ws_id = s.workspace_mgr.active_workspace().id;
+    if let Some(preview) = s.tab_previews.get_mut(&ws_id) {
+        preview.stale = true;
+    }
+    update_overview(s, app_weak);
+    if let Some(app) = app_weak.upgrade() {
+        app.set_overview_selected(s.workspace_mgr.active_index() as i32);
+        app.set_overview_visible(true);
+    }
+    focus_later(app_weak, AppWindow::invoke_focus_overview);
+    request_redraw(app_weak);
+}
+
+fn update_overview(s: &TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let Some(app) = app_weak.upgrade() else {
+        return;
+    };
+    let active_idx = s.workspace_mgr.active_index();
+    let items: Vec<OverviewItem> = s
+        .workspace_mgr
+        .workspaces()
+        .iter()
+        .enumerate()
+        .map(|(idx, ws)| {
+            let pane_id = ws.active_pane();
+            let ps = s.pane_states.get(&pane_id);
+            let label = ps.map_or_else(|| ws.name.clone(), |ps| pane_label(pane_id, ps));
+            let panes = ws.pane_ids().len();
+            let title = if panes > 1 {
+                format!("{}  {label}  (+{} panes)", idx + 1, panes - 1)
+            } else {
+                format!("{}  {label}", idx + 1)
+            };
+            OverviewItem {
+                title: title.into(),
+                detail: ps.map(pane_subtitle).unwrap_or_default().into(),
+                preview: s
+                    .tab_previews
+                    .get(&ws.id)
+                    .map(|preview| preview.image.clone())
+                    .unwrap_or_default(),
+                active: idx == active_idx,
+            }
+        })
+        .collect();
+    app.set_overview_items(slint::ModelRc::from(Rc::new(slint::VecModel::from(items))));
+}
+
+fn close_overview(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    s.overview = false;
+    if let Some(app) = app_weak.upgrade() {
+        app.set_overview_visible(false);
+        app.set_overview_items(slint::ModelRc::default());
+    }
+    focus_later(app_weak, AppWindow::invoke_focus_terminal);
+}
+
+/// Show recent log lines, for users who never see stderr.
+fn open_log_viewer(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    if s.picker.is_some() {
+        close_picker(s, app_weak);
+    }
+    update_log_viewer(s, app_weak);
+    if let Some(app) = app_weak.upgrade() {
+        app.set_logs_visible(true);
+    }
+    focus_later(app_weak, AppWindow::invoke_focus_logs);
+}
+
+/// Refill the log viewer with the newest lines at or above its level.
+fn update_log_viewer(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let Some(app) = app_weak.upgrade() else {
+        return;
+    };
+    let ring = log_ring::global();
+    s.log_viewer = Some(ring.pushed());
+    let level = usize::try_from(app.get_log_level())
+        .ok()
+        .and_then(|idx| LOG_LEVELS.get(idx))
+        .copied()
+        .unwrap_or(tracing::Level::INFO);
+    let lines = ring.lines();
+    let shown: Vec<_> = lines.iter().filter(|line| line.level <= level).collect();
+    let skip = shown.len().saturating_sub(LOG_VIEWER_LINES);
+    let rows: Vec<LogRow> = shown[skip..]
+        .iter()
+        .map(|line| {
+            let time = pane_log::timestamp(line.time);
+            LogRow {
+                // Time of day; the viewer only holds recent lines
+                time: time.get(11..23).unwrap_or(&time).into(),
+                level: line.level.as_str().into(),
+                target: line.target.as_str().into(),
+                message: line.message.as_str().into(),
+            }
+        })
+        .collect();
+    app.set_log_lines(slint::ModelRc::from(Rc::new(slint::VecModel::from(rows))));
+}
+
+fn close_log_viewer(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    s.log_viewer = None;
+    if let Some(app) = app_weak.upgrade() {
+        app.set_logs_visible(false);
+        app.set_log_lines(slint::ModelRc::default());
+    }
+    focus_later(app_weak, AppWindow::invoke_focus_terminal);
+}
+
+/// Filter the active pane's buffer down to the lines matching a pattern,
+/// like `less`'s `&pattern`.
+fn open_grep_view(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    if s.picker.is_some() {
+        close_picker(s, app_weak);
+    }
+    s.grep = Some((s.workspace_mgr.active_workspace().active_pane(), Vec::new()));
+    filter_grep_view(s, app_weak, "");
+    if let Some(app) = app_weak.upgrade() {
+        app.set_grep_query("".into());
+        app.set_grep_visible(true);
+    }
+    focus_later(app_weak, AppWindow::invoke_focus_grep);
+}
+
+/// Refill the grep view for the edited pattern, newest line selected.
+fn filter_grep_view(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>, query: &str) {
+    let Some((pane_id, found)) = &mut s.grep else {
+        return;
+    };
+    *found = s
+        .pane_states
+        .get(pane_id)
+        .map(|ps| ps.emulator.search(query, GREP_MAX_LINES))
+        .unwrap_or_default();
+    let Some(app) = app_weak.upgrade() else {
+        return;
+    };
+    let lines = match found.len() {
+        1 => "1 line".to_string(),
+        GREP_MAX_LINES => format!("newest {GREP_MAX_LINES} lines"),
+        lines => format!("{lines} lines"),
+    };
+    let summary = match found.iter().map(|found| found.count).sum::<usize>() {
+        _ if query.trim().is_empty() => "Type to show matching lines".to_string(),
+        0 => "No matching lines".to_string(),
+        1 => "1 match".to_string(),
+        matches => format!("{matches} matches in {lines}"),
+    };
+    let rows: Vec<GrepRow> = found
+        .iter()
+        .map(|found| GrepRow {
+            line: (found.line + 1).to_string().into(),
+            text: found.text.as_str().into(),
+            count: if found.count > 1 {
+                format!("×{}", found.count).into()
+            } else {
+                Default::default()
+            },
+        })
+        .collect();
+    app.set_grep_summary(summary.into());
+    app.set_grep_rows(slint::ModelRc::from(Rc::new(slint::VecModel::from(rows))));
+    app.set_grep_selected(found.len().saturating_sub(1) as i32);
+}
+
+/// Close the grep view and jump to the chosen line in the real buffer.
+fn grep_accepted(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>, idx: i32) {
+    let chosen = s.grep.as_mut().and_then(|(pane_id, found)| {
+        let idx = usize::try_from(idx).ok().filter(|&idx| idx < found.len())?;
+        Some((*pane_id, found.swap_remove(idx)))
+    });
+    close_grep_view(s, app_weak);
+    if let Some((pane_id, found)) = chosen {
+        jump_to_search_match(s, app_weak, pane_id, &found);
+    }
+}
+
+fn close_grep_view(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    s.grep = None;
+    if let Some(app) = app_weak.upgrade() {
+        app.set_grep_visible(false);
+        app.set_grep_rows(slint::ModelRc::default());
+    }
+    focus_later(app_weak, AppWindow::invoke_focus_terminal);
+}
+
+/// Show the settings overlay, filled from the config.
+fn open_settings(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    if s.picker.is_some() {
+        close_picker(s, app_weak);
+    }
+    s.settings = true;
+    update_settings(s, app_weak);
+    if let Some(app) = app_weak.upgrade() {
+        app.set_settings_status("Changes are saved to config.toml".into());
+        app.set_settings_status_error(false);
+        app.set_settings_visible(true);
+    }
+    focus_later(app_weak, AppWindow::invoke_focus_settings);
+}
+
+/// Refill the settings overlay from the config.
+fn update_settings(s: &TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let Some(app) = app_weak.upgrade() else {
+        return;
+    };
+    let config = &s.config;
+    let theme = if config.theme.follow_system {
+        format!("{} / {} (system)", config.theme.dark, config.theme.light)
+    } else {
+        config.theme.name.clone()
+    };
+    app.set_settings_values(SettingsValues {
+        font_family: config.font.family.as_str().into(),
+        font_size: config.font.size.to_string().into(),
+        theme: theme.into(),
+        cursor_style: config.cursor.style.as_str().into(),
+        cursor_blink: config.cursor.blink,
+        opacity: format!("{:.0}%", config.window.opacity * 100.0).into(),
+        scrollback_lines: config.scrollback.lines.to_string().into(),
+    });
+    let bindings = sorted_bindings(&config.keybindings);
+    let rows: Vec<BindingRow> = bindings
+        .iter()
+        .map(|(keys, action)| {
+            let normalized = keys::normalize_binding(keys);
+            let others: Vec<&str> = bindings
+                .iter()
+                .filter(|(other, _)| other != keys && keys::normalize_binding(other) == normalized)
+                .map(|(_, action)| action.as_str())
+                .collect();
+            BindingRow {
+                keys: keys.as_str().into(),
+                action: action.as_str().into(),
+                conflict: if others.is_empty() {
+                    Default::default()
+                } else {
+                    format!("also {}", others.join(", ")).into()
+                },
+            }
+        })
+        .collect();
+    app.set_settings_bindings(slint::ModelRc::from(Rc::new(slint::VecModel::from(rows))));
+}
+
+/// Key bindings as (keys, action), by action, as the settings overlay
+/// lists them
+fn sorted_bindings(bindings: &HashMap<String, String>) -> Vec<(String, String)> {
+    let mut bindings: Vec<(String, String)> = bindings
+        .iter()
+        .map(|(keys, action)| (keys.clone(), action.clone()))
+        .collect();
+    bindings.sort_by(|a, b| (&a.1, &a.0).cmp(&(&b.1, &b.0)));
+    bindings
+}
+
+fn set_settings_status(app_weak: &slint::Weak<AppWindow>, status: &str, error: bool) {
+    if let Some(app) = app_weak.upgrade() {
+        app.set_settings_status(status.into());
+        app.set_settings_status_error(error);
+    }
+}
+
+/// Save an option changed in the settings overlay. Returns whether it was.
+fn settings_option_set(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    key: &str,
+    value: serde_json::Value,
+) -> bool {
+    match set_config_option(s, app_weak, key, value, true) {
+        Ok(_) if keys::is_live(key) => {
+            set_settings_status(app_weak, "Saved", false);
+            true
+        }
+        Ok(_) => {
+            set_settings_status(app_weak, "Saved; takes effect on the next launch", false);
+            true
+        }
+        Err(e) => {
+            set_settings_status(app_weak, &e.message, true);
+            // Show the value that is still in effect
+            update_settings(s, app_weak);
+            false
+        }
+    }
+}
+
+/// Move a numeric or listed option one step from the settings overlay.
+fn settings_option_stepped(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    key: &str,
+    direction: i32,
+) {
+    let step = f64::from(direction.signum());
+    let value = match key {
+        "font.size" => {
+            let size = (f64::from(s.config.font.size).round() + step).clamp(6.0, 72.0);
+            serde_json::json!(size)
+        }
+        // In steps of 5%
+        "window.opacity" => {
+            let steps = (f64::from(s.config.window.opacity) * 20.0).round() + step;
+            serde_json::json!(steps.clamp(2.0, 20.0) / 20.0)
+        }
+        "theme.name" => {
+            let names = Theme::installed(&Config::themes_dir());
+            if names.is_empty() {
+                return;
+            }
+            let current = names.iter().position(|name| *name == s.config.theme.name);
+            let next = match current {
+                Some(i) => (i as i32 + direction.signum()).rem_euclid(names.len() as i32),
+                None => 0,
+            };
+            // A theme picked by hand replaces the system's and the
+            // high-contrast one
+            for key in ["theme.follow_system", "accessibility.high_contrast"] {
+                if s.config.value(key) == Some(serde_json::Value::Bool(true))
+                    && !settings_option_set(s, app_weak, key, serde_json::Value::Bool(false))
+                {
+                    return;
+                }
+            }
+            serde_json::json!(names[next as usize])
+        }
+        _ => return,
+    };
+    settings_option_set(s, app_weak, key, value);
+}
+
+/// Replace the key bindings from the settings overlay; `keys` are
+/// refused if another binding already uses them, in any spelling.
+fn settings_bindings_changed(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    old_keys: Option<&str>,
+    new: Option<(&str, &str)>,
+) -> bool {
+    let mut bindings = s.config.keybindings.clone();
+    if let Some(old_keys) = old_keys {
+        bindings.remove(old_keys);
+    }
+    if let Some((keys, action)) = new {
+        let (keys, action) = (keys.trim(), action.trim());
+        if keys.is_empty() || action.is_empty() {
+            set_settings_status(app_weak, "A key binding needs keys and an action", true);
+            return false;
+        }
+        if !keys::is_action(action) {
+            set_settings_status(app_weak, &format!("There is no action {action}"), true);
+            return false;
+        }
+        let normalized = keys::normalize_binding(keys);
+        let taken = bindings
+            .iter()
+            .find(|(other, _)| keys::normalize_binding(other) == normalized);
+        if let Some((other, action)) = taken {
+            let status = if other == keys {
+                format!("{keys} is already bound to {action}")
+            } else {
+                format!("{keys} is already bound to {action}, as {other}")
+            };
+            set_settings_status(app_weak, &status, true);
+            return false;
+        }
+        bindings.insert(keys.to_string(), action.to_string());
+    }
+    let bindings = serde_json::json!(bindings);
+    settings_option_set(s, app_weak, "keybindings", bindings)
+}
+
+fn close_settings(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    s.settings = false;
+    if let Some(app) = app_weak.upgrade() {
+        app.set_settings_visible(false);
+        app.set_settings_bindings(slint::ModelRc::default());
+    }
+    focus_later(app_weak, AppWindow::invoke_focus_terminal);
+}
+
+/// Show or hide the key binding cheat sheet, drawn over the panes.
+fn toggle_cheat_sheet(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    s.cheat_sheet = match s.cheat_sheet {
+        Some(_) => None,
+        None => Some(cheat_sheet_text(&s.config, s.keyboard_layout)),
+    };
+    for ps in s.pane_states.values() {
+        ps.dirty.store(true, Ordering::Relaxed);
+    }
+    request_redraw(app_weak);
+}
+
+/// The key bindings shortcuts run, as the cheat sheet shows them, with
+/// keys named as the `keyboard` layout labels them
+fn cheat_sheet_text(config: &Config, keyboard: &KeyboardLayout) -> String {
+    let shown: HashMap<String, String> = config
+        .keybindings
+        .iter()
+        .map(|(keys, action)| (keyboard.display_binding(keys), action.clone()))
+        .collect();
+    let bindings = if shown.is_empty() {
+        "No key bindings configured".to_string()
+    } else {
+        keys::cheat_sheet(&shown)
+    };
+    let settings = shown
+        .iter()
+        .filter(|(_, action)| *action == "settings")
+        .map(|(keys, _)| keys.as_str())
+        .min();
+    let footer = match settings {
+        Some(keys) => format!("Escape closes this; {keys} changes them"),
+        None => "Escape closes this".to_string(),
+    };
+    format!("Key bindings\n\n{bindings}\n\n{footer}")
+}
+
+/// Quick-start text written at the top of the first pane on the first
+/// launch, before the shell's prompt
+fn welcome_banner(ps: &PaneState) {
+    let text = format!(
+        "\x1b[1mWelcome to pterminal\x1b[0m\n\n\
+         \x20 Cmd+/        key bindings\n\
+         \x20 Cmd+,        settings\n\
+         \x20 Cmd+T        new tab\n\
+         \x20 Cmd+D        split right (Cmd+Shift+D splits down)\n\
+         \x20 Cmd+F        filter the scrollback\n\n\
+         Settings are saved to {}\n\n",
+        Config::config_path().display()
+    );
+    ps.emulator.process(text.replace('\n', "\r\n").as_bytes());
+}
+
+/// Most config problems listed in the startup notification
+const CONFIG_PROBLEMS_SHOWN: usize = 5;
+
+/// Title and body of the startup notification about config problems
+fn config_problems_notification(problems: &[ConfigProblem]) -> Option<(&'static str, String)> {
+    let title = match problems {
+        [] => return None,
+        _ if problems.iter().any(|problem| problem.fatal) => "Config not loaded, using defaults",
+        _ => "Config problems",
+    };
+    let mut lines: Vec<String> = problems
+        .iter()
+        .take(CONFIG_PROBLEMS_SHOWN)
+        .map(ToString::to_string)
+        .collect();
+    if problems.len() > CONFIG_PROBLEMS_SHOWN {
+        let more = problems.len() - CONFIG_PROBLEMS_SHOWN;
+        lines.push(format!("and {more} more"));
+    }
+    lines.push("Run `pterminal --check-config` to check again.".to_string());
+    Some((title, lines.join("\n")))
+}
+
+/// Move keyboard focus on the next event loop turn. Focus changes fire
+/// `terminal-focus-changed` synchronously, which needs the state borrow our
+/// callers are still holding.
+fn focus_later(app_weak: &slint::Weak<AppWindow>, focus: fn(&AppWindow)) {
+    let app_weak = app_weak.clone();
+    slint::Timer::single_shot(Duration::ZERO, move || {
+        if let Some(app) = app_weak.upgrade() {
+            focus(&app);
+        }
+    });
+}
+
+/// Refill the picker list for the edited query.
+fn filter_picker(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>, query: &str) {
+    let Some(picker) = &mut s.picker else { return };
+    let mut prompt = None;
+    let items: Vec<PickerItem> = match &mut picker.mode {
+        PickerMode::Snippets(matches) => {
+            let snippets = &s.config.snippets;
+            *matches = fuzzy_filter(query, snippets.iter().map(|sn| sn.name.as_str()));
+            matches.truncate(PICKER_MAX_ITEMS);
+            matches
+                .iter()
+                .map(|&i| {
+                    let sn = &snippets[i];
+                    let detail = if sn.description.is_empty() {
+                        &sn.command
+                    } else {
+                        &sn.description
+                    };
+                    PickerItem {
+                        title: sn.name.as_str().into(),
+                        detail: detail.as_str().into(),
+                    }
+                })
+                .collect()
+        }
+        PickerMode::SnippetValues { .. } => Vec::new(),
+        PickerMode::History {
+            entries, matches, ..
+        } => {
+            *matches = fuzzy_filter(query, entries.iter().map(String::as_str));
+            matches.truncate(PICKER_MAX_ITEMS);
+            matches
+                .iter()
+                .map(|&i| PickerItem {
+                    // Rows are single-line; show multi-line commands joined
+                    title: entries[i].replace('\n', " ⏎ ").into(),
+                    detail: Default::default(),
+                })
+                .collect()
+        }
+        PickerMode::FileDrop {
+            target, matches, ..
+        } => {
+            let labels = DropChoice::ALL.map(DropChoice::label);
+            *matches = fuzzy_filter(query, labels);
+            matches
+                .iter()
+                .map(|&i| PickerItem {
+                    title: labels[i].into(),
+                    detail: match DropChoice::ALL[i] {
+                        DropChoice::Upload(_) => format!("to {}:~", target.destination).into(),
+                        DropChoice::PasteLocalPaths => Default::default(),
+                    },
+                })
+                .collect()
+        }
+        PickerMode::Processes { entries, matches } => {
+            *matches = fuzzy_filter(query, entries.iter().map(|(_, p)| p.command.as_str()));
+            matches.truncate(PICKER_MAX_ITEMS);
+            matches
+                .iter()
+                .map(|&i| {
+                    let (depth, p) = &entries[i];
+                    PickerItem {
+                        title: format!("{}{}", "  ".repeat(*depth), p.command).into(),
+                        detail: format!(
+                            "pid {} · {:.1}% CPU · {:.1} MB",
+                            p.pid,
+                            p.cpu,
+                            p.rss_kb as f64 / 1024.0
+                        )
+                        .into(),
+                    }
+                })
+                .collect()
+        }
+        PickerMode::Signal { pid, matches } => {
+            let names = Signal::ALL.map(Signal::name);
+            *matches = fuzzy_filter(query, names);
+            matches
+                .iter()
+                .map(|&i| PickerItem {
+                    title: format!("SIG{}", names[i]).into(),
+                    detail: format!("to pid {pid}").into(),
+                })
+                .collect()
+        }
+        PickerMode::SendTarget { panes, matches } => {
+            let haystacks: Vec<String> = panes
+                .iter()
+                .map(|(_, label, detail)| format!("{label} {detail}"))
+                .collect();
+            *matches = fuzzy_filter(query, haystacks.iter().map(String::as_str));
+            matches.truncate(PICKER_MAX_ITEMS);
+            matches
+                .iter()
+                .map(|&i| PickerItem {
+                    title: panes[i].1.as_str().into(),
+                    detail: panes[i].2.as_str().into(),
+                })
+                .collect()
+        }
+        PickerMode::SendText => Vec::new(),
+        PickerMode::Themes { names, matches } => {
+            *matches = fuzzy_filter(query, names.iter().map(String::as_str));
+            matches.truncate(PICKER_MAX_ITEMS);
+            let current = s.theme.name.as_str();
+            matches
+                .iter()
+                .map(|&i| PickerItem {
+                    title: names[i].as_str().into(),
+                    detail: if names[i] == current { "current" } else { "" }.into(),
+                })
+                .collect()
+        }
+        PickerMode::ExportLayout => Vec::new(),
+        PickerMode::PaneLog { choices, matches } => {
+            let labels: Vec<&str> = choices.iter().map(|c| log_choice(*c).0).collect();
+            *matches = fuzzy_filter(query, labels.iter().copied());
+            matches
+                .iter()
+                .map(|&i| PickerItem {
+                    title: labels[i].into(),
+                    detail: log_choice(choices[i]).1.into(),
+                })
+                .collect()
+        }
+        PickerMode::ImportLayout { files, matches } => {
+            let names: Vec<String> = files
+                .iter()
+                .map(|f| {
+                    f.file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect();
+            *matches = fuzzy_filter(query, names.iter().map(String::as_str));
+            matches.truncate(PICKER_MAX_ITEMS);
+            matches
+                .iter()
+                .map(|&i| PickerItem {
+                    title: names[i].as_str().into(),
+                    detail: Default::default(),
+                })
+                .collect()
+        }
+        PickerMode::Marks { marks, rows } => {
+            let mut matches = fuzzy_filter(query, marks.iter().map(|m| m.name.as_str()));
+            matches.truncate(PICKER_MAX_ITEMS - 1);
+            // Adding comes first unless the query picks out marks to jump to
+            let jumps = matches.iter().map(|&i| Some(i));
+            *rows = if query.trim().is_empty() || matches.is_empty() {
+                std::iter::once(None).chain(jumps).collect()
+            } else {
+                jumps.chain(std::iter::once(None)).collect()
+            };
+            rows.iter()
+                .map(|row| match row {
+                    Some(i) => PickerItem {
+                        title: marks[*i].name.as_str().into(),
+                        detail: format!("line {}", marks[*i].line + 1).into(),
+                    },
+                    None => PickerItem {
+                        title: format!("Add mark \"{}\"", mark_name(query, marks)).into(),
+                        detail: "at the top of the view".into(),
+                    },
+                })
+                .collect()
+        }
+        PickerMode::Search {
+            all_workspaces,
+            hits,
+        } => {
+            let found = search_panes(
+                &s.workspace_mgr,
+                &s.pane_states,
+                query,
+                *all_workspaces,
+                SEARCH_MAX_MATCHES,
+            );
+            let lines: usize = found.iter().map(|(_, matches)| matches.len()).sum();
+            prompt = Some(search_prompt(*all_workspaces, lines, found.len()));
+            // Each pane gets a share of the rows for its newest matches
+            let share = (PICKER_MAX_ITEMS / found.len().max(1)).max(1);
+            *hits = found
+                .into_iter()
+                .flat_map(|(pane_id, matches)| {
+                    let skip = matches.len().saturating_sub(share);
+                    matches.into_iter().skip(skip).map(move |m| (pane_id, m))
+                })
+                .take(PICKER_MAX_ITEMS)
+                .collect();
+            hits.iter()
+                .map(|(pane_id, m)| {
+                    let label = s
+                        .pane_states
+                        .get(pane_id)
+                        .map(|ps| pane_label(*pane_id, ps))
+                        .unwrap_or_default();
+                    PickerItem {
+                        title: m.text.trim().into(),
+                        detail: format!("{label} · line {}", m.line + 1).into(),
+                    }
+                })
+                .collect()
+        }
+    };
+    if let Some(app) = app_weak.upgrade() {
+        let model = std::rc::Rc::new(slint::VecModel::from(items));
+        app.set_picker_items(slint::ModelRc::from(model));
+        if let Some(prompt) = prompt {
+            app.set_picker_prompt(prompt.into());
+        }
+    }
+    // Editing the query highlights the first match
+    picker_highlighted(s, app_weak, 0);
+}
+
+/// A picker row was highlighted; the theme picker previews it.
+fn picker_highlighted(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>, idx: i32) {
+    let Some(Picker {
+        pane_id,
+        mode: PickerMode::Themes { names, matches },
+    }) = &s.picker
+    else {
+        return;
+    };
+    let Some(&i) = usize::try_from(idx).ok().and_then(|idx| matches.get(idx)) else {
+        return;
+    };
+    let pane_id = *pane_id;
+    let previewed = s.theme_preview.as_ref().map(|(_, t)| t.name.as_str());
+    if previewed == Some(names[i].as_str()) {
+        return;
+    }
+    let Some(theme) = load_theme(&s.config, &names[i]) else {
+        return;
+    };
+    s.theme_preview = Some((pane_id, Arc::new(theme)));
+    mark_pane_dirty(s, pane_id);
+    request_redraw(app_weak);
+}
+
+fn mark_pane_dirty(s: &TerminalState, pane_id: PaneId) {
+    if let Some(ps) = s.pane_states.get(&pane_id) {
+        ps.dirty.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Return pressed (or a row clicked) in the picker.
+fn picker_accepted(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    idx: i32,
+    query: &str,
+) {
+    let Some(picker) = s.picker.take() else {
+        return;
+    };
+    match picker.mode {
+        PickerMode::Snippets(matches) => {
+            let Some(&i) = usize::try_from(idx).ok().and_then(|idx| matches.get(idx)) else {
+                close_picker(s, app_weak);
+                return;
+            };
+            let snippet = s.config.snippets[i].clone();
+            let pending = snippet.placeholders();
+            next_snippet_value(
+                s,
+                app_weak,
+                picker.pane_id,
+                snippet,
+                pending,
+                HashMap::new(),
+            );
+        }
+        PickerMode::SnippetValues {
+            snippet,
+            mut pending,
+            mut values,
+        } => {
+            if !pending.is_empty() {
+                values.insert(pending.remove(0).name, query.to_string());
+            }
+            next_snippet_value(s, app_weak, picker.pane_id, snippet, pending, values);
+        }
+        PickerMode::History {
+            entries,
+            matches,
+            typed,
+        } => {
+            close_picker(s, app_weak);
+            let Some(&i) = usize::try_from(idx).ok().and_then(|idx| matches.get(idx)) else {
+                return;
+            };
+            if let Some(ps) = s.pane_states.get_mut(&picker.pane_id) {
+                // Erase what was typed, then insert the command (not executed)
+                let mut bytes = vec![0x7f; typed.chars().count()];
+                bytes.extend_from_slice(entries[i].as_bytes());
+                let _ = send_input(ps, &bytes);
+            }
+            request_redraw(app_weak);
+        }
+        PickerMode::FileDrop {
+            files,
+            target,
+            matches,
+        } => {
+            close_picker(s, app_weak);
+            let Some(&i) = usize::try_from(idx).ok().and_then(|idx| matches.get(idx)) else {
+                return;
+            };
+            match DropChoice::ALL[i] {
+                DropChoice::Upload(tool) => {
+                    let done = s.upload_tx.clone();
+                    start_upload(picker.pane_id, &target, tool, files, done);
+                }
+                DropChoice::PasteLocalPaths => {
+                    if let Some(ps) = s.pane_states.get(&picker.pane_id) {
+                        // The remote shell is assumed to be POSIX
+                        let text = paste_text(&files, ShellKind::Posix);
+                        let _ = ps.pty.write(text.as_bytes());
+                    }
+                }
+            }
+        }
+        PickerMode::Processes { entries, matches } => {
+            let Some(&i) = usize::try_from(idx).ok().and_then(|idx| matches.get(idx)) else {
+                close_picker(s, app_weak);
+                return;
+            };
+            let pid = entries[i].1.pid;
+            s.picker = Some(Picker {
+                pane_id: picker.pane_id,
+                mode: PickerMode::Signal {
+                    pid,
+                    matches: Vec::new(),
+                },
+            });
+            filter_picker(s, app_weak, "");
+            let prompt = format!("Signal {}", entries[i].1.command);
+            show_picker(app_weak, &prompt, "", None);
+        }
+        PickerMode::Signal { pid, matches } => {
+            close_picker(s, app_weak);
+            let Some(&i) = usize::try_from(idx).ok().and_then(|idx| matches.get(idx)) else {
+                return;
+            };
+            if let Err(e) = process_tree::send_signal(pid, Signal::ALL[i]) {
+                warn!(pid, "{e}");
+            }
+        }
+        PickerMode::SendTarget { panes, matches } => {
+            let Some(&i) = usize::try_from(idx).ok().and_then(|idx| matches.get(idx)) else {
+                close_picker(s, app_weak);
+                return;
+            };
+            let (pane_id, label, _) = &panes[i];
+            s.picker = Some(Picker {
+                pane_id: *pane_id,
+                mode: PickerMode::SendText,
+            });
+            filter_picker(s, app_weak, "");
+            let prompt = format!("Send to {label} (Return sends a line, Escape stops)");
+            show_picker(app_weak, &prompt, "", None);
+        }
+        PickerMode::Themes { names, matches } => {
+            close_picker(s, app_weak);
+            let Some(&i) = usize::try_from(idx).ok().and_then(|idx| matches.get(idx)) else {
+                return;
+            };
+            let Some(theme) = load_theme(&s.config, &names[i]) else {
+                return;
+            };
+            // A theme picked by hand replaces the high-contrast one
+            s.config.accessibility.high_contrast = false;
+            s.config.theme.name = names[i].clone();
+            set_theme(s, app_weak, theme);
+        }
+        PickerMode::ExportLayout => {
+            close_picker(s, app_weak);
+            let path = layout::expand_path(query.trim(), &home_dir());
+            let workspace = s.workspace_mgr.active_index();
+            let (title, body) = match export_layout(s, workspace, &path) {
+                Ok(_) => ("Layout exported", path.display().to_string()),
+                Err(e) => ("Layout export failed", format!("{e:#}")),
+            };
+            s.notifications.push(title, body);
+            request_redraw(app_weak);
+        }
+        PickerMode::ImportLayout { files, matches } => {
+            close_picker(s, app_weak);
+            let path = match usize::try_from(idx).ok().and_then(|idx| matches.get(idx)) {
+                Some(&i) => files[i].clone(),
+                None => layout::expand_path(query.trim(), &home_dir()),
+            };
+            if let Err(e) = import_layout(s, app_weak, &path) {
+                let body = format!("{e:#}");
+                s.notifications.push("Layout import failed", body);
+                request_redraw(app_weak);
+            }
+        }
+        PickerMode::PaneLog { choices, matches } => {
+            close_picker(s, app_weak);
+            let Some(&i) = usize::try_from(idx).ok().and_then(|idx| matches.get(idx)) else {
+                return;
+            };
+            let (title, body) = match choices[i] {
+                Some(format) => match start_pane_log(
+                    s,
+                    picker.pane_id,
+                    None,
+                    LogOptions {
+                        format,
+                        ..log_options(&s.config.logging)
+                    },
+                ) {
+                    Ok(path) => ("Logging pane output", path.display().to_string()),
+                    Err(e) => ("Pane log failed", format!("{e:#}")),
+                },
+                None => match stop_pane_log(s, picker.pane_id) {
+                    Some(path) => ("Pane log stopped", path.display().to_string()),
+                    None => return,
+                },
+            };
+            s.notifications.push(title, body);
+            update_tabs(s, app_weak);
+            request_redraw(app_weak);
+        }
+        PickerMode::Search { hits, .. } => {
+            close_picker(s, app_weak);
+            let Some((pane_id, found)) = usize::try_from(idx).ok().and_then(|idx| hits.get(idx))
+            else {
+                return;
+            };
+            jump_to_search_match(s, app_weak, *pane_id, found);
+        }
+        PickerMode::Marks { marks, rows } => {
+            close_picker(s, app_weak);
+            let Some(&row) = usize::try_from(idx).ok().and_then(|idx| rows.get(idx)) else {
+                return;
+            };
+            match row {
+                Some(i) => scroll_to_mark(s, app_weak, picker.pane_id, &marks[i]),
+                None => add_mark(s, app_weak, picker.pane_id, mark_name(query, &marks)),
+            }
+        }
+        PickerMode::SendText => {
+            let Some(ps) = s.pane_states.get_mut(&picker.pane_id) else {
+                close_picker(s, app_weak);
+                return;
+            };
+            let _ = send_input(ps, format!("{query}\r").as_bytes());
+            // Stay open for the next line
+            s.picker = Some(picker);
+            if let Some(app) = app_weak.upgrade() {
+                app.set_picker_query(Default::default());
+            }
+            request_redraw(app_weak);
+        }
+    }
+}
+
+/// List installed themes, previewing the highlighted one on the active pane.
+fn open_theme_picker(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let pane_id = s.workspace_mgr.active_workspace().active_pane();
+    s.picker = Some(Picker {
+        pane_id,
+        mode: PickerMode::Themes {
+            names: Theme::installed(&Config::themes_dir()),
+            matches: Vec::new(),
+        },
+    });
+    filter_picker(s, app_weak, "");
+    show_picker(app_weak, "Switch Theme", "", None);
+}
+
+/// Ask where to export the active workspace's layout, suggesting the
+/// layouts directory.
+fn open_export_layout_picker(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    s.picker = Some(Picker {
+        pane_id: s.workspace_mgr.active_workspace().active_pane(),
+        mode: PickerMode::ExportLayout,
+    });
+    filter_picker(s, app_weak, "");
+    let path = default_layout_path(&s.workspace_mgr.active_workspace().name);
+    let query = layout::portable_path(&path, &home_dir());
+    show_picker(app_weak, "Export layout to (.toml or .json)", &query, None);
+}
+
+/// List saved layouts; a typed path opens a layout from elsewhere.
+fn open_import_layout_picker(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(Config::layouts_dir())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            let ext = path.extension().and_then(|ext| ext.to_str());
+            matches!(ext, Some("toml" | "json"))
+        })
+        .collect();
+    files.sort();
+    s.picker = Some(Picker {
+        pane_id: s.workspace_mgr.active_workspace().active_pane(),
+        mode: PickerMode::ImportLayout {
+            files,
+            matches: Vec::new(),
+        },
+    });
+    filter_picker(s, app_weak, "");
+    show_picker(app_weak, "Import layout (name or path)", "", None);
+}
+
+/// Offer to start logging the active pane's output, or to stop its log.
+fn open_pane_log_picker(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let pane_id = s.workspace_mgr.active_workspace().active_pane();
+    let Some(ps) = s.pane_states.get(&pane_id) else {
+        return;
+    };
+    let choices = match ps.pty.log_path() {
+        Some(_) => vec![None],
+        None => {
+            // The configured format first
+            let default = s.config.logging.format;
+            let other = [LogFormat::Plain, LogFormat::Raw]
+                .into_iter()
+                .filter(|&f| f != default);
+            std::iter::once(default).chain(other).map(Some).collect()
+        }
+    };
+    s.picker = Some(Picker {
+        pane_id,
+        mode: PickerMode::PaneLog {
+            choices,
+            matches: Vec::new(),
+        },
+    });
+    filter_picker(s, app_weak, "");
+    show_picker(app_weak, "Log Output", "", None);
+}
+
+/// List the active pane's marks to jump to, and offer to add one.
+fn open_marks_picker(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let pane_id = s.workspace_mgr.active_workspace().active_pane();
+    let Some(ps) = s.pane_states.get(&pane_id) else {
+        return;
+    };
+    s.picker = Some(Picker {
+        pane_id,
+        mode: PickerMode::Marks {
+            marks: ps.emulator.marks(),
+            rows: Vec::new(),
+        },
+    });
+    filter_picker(s, app_weak, "");
+    show_picker(app_weak, "Marks (type a name to add one)", "", None);
+}
+
+/// Search the panes of the active workspace, or of every workspace.
+fn open_pane_search(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    all_workspaces: bool,
+) {
+    s.picker = Some(Picker {
+        pane_id: s.workspace_mgr.active_workspace().active_pane(),
+        mode: PickerMode::Search {
+            all_workspaces,
+            hits: Vec::new(),
+        },
+    });
+    filter_picker(s, app_weak, "");
+    show_picker(app_weak, &search_prompt(all_workspaces, 0, 0), "", None);
+}
+
+/// "Find in workspace", with the lines found so far
+fn search_prompt(all_workspaces: bool, lines: usize, panes: usize) -> String {
+    let scope = if all_workspaces {
+        "Find in all workspaces"
+    } else {
+        "Find in workspace"
+    };
+    match (lines, panes) {
+        (0, _) => scope.to_string(),
+        (1, _) => format!("{scope} · 1 line"),
+        (lines, 1) => format!("{scope} · {lines} lines in 1 pane"),
+        (lines, panes) => format!("{scope} · {lines} lines in {panes} panes"),
+    }
+}
+
+/// Panes to search: the active workspace's, active pane first, then the
+/// other workspaces' when `all_workspaces`
+fn search_scope(workspace_mgr: &WorkspaceManager, all_workspaces: bool) -> Vec<PaneId> {
+    let shown = workspace_mgr.active_workspace();
+    let active = shown.active_pane();
+    let mut panes = vec![active];
+    panes.extend(shown.pane_ids().into_iter().filter(|&id| id != active));
+    if all_workspaces {
+        for (idx, ws) in workspace_mgr.workspaces().iter().enumerate() {
+            if idx != workspace_mgr.active_index() {
+                panes.extend(ws.pane_ids());
+            }
+        }
+    }
+    panes
+}
+
+/// The last `limit` lines matching `query` in each pane in scope that has
+/// any, in scope order
+fn search_panes(
+    workspace_mgr: &WorkspaceManager,
+    pane_states: &HashMap<PaneId, PaneState>,
+    query: &str,
+    all_workspaces: bool,
+    limit: usize,
+) -> Vec<(PaneId, Vec<SearchMatch>)> {
+    search_scope(workspace_mgr, all_workspaces)
+        .into_iter()
+        .filter_map(|pane_id| {
+            let found = pane_states.get(&pane_id)?.emulator.search(query, limit);
+            (!found.is_empty()).then_some((pane_id, found))
+        })
+        .collect()
+}
+
+/// Focus the pane of a search match and scroll the match into view,
+/// selected
+fn jump_to_search_match(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    pane_id: PaneId,
+    found: &SearchMatch,
+) {
+    if !focus_pane(s, app_weak, pane_id) {
+        return;
+    }
+    let Some(ps) = s.pane_states.get(&pane_id) else {
+        return;
+    };
+    // A third of the way down, with what led up to it above
+    let rows = ps.emulator.metadata().rows as usize;
+    ps.emulator
+        .scroll_to_line(found.line.saturating_sub(rows / 3));
+    let row = found.line.saturating_sub(view_top(ps)) as u16;
+    let (start, end) = (found.columns.0 as u16, found.columns.1 as u16);
+    s.selection = Some(Selection::new(
+        ((start, row), (end, row)),
+        SelectionUnit::Cell,
+    ));
+    request_redraw(app_weak);
+}
+
+/// Name for a new mark: what was typed, or the next "Mark N"
+fn mark_name(query: &str, marks: &[Mark]) -> String {
+    match query.trim() {
+        "" => format!("Mark {}", marks.len() + 1),
+        name => name.to_string(),
+    }
+}
+
+/// Mark the line at the top of a pane's view
+fn add_mark(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    pane_id: PaneId,
+    name: String,
+) {
+    let Some(ps) = s.pane_states.get_mut(&pane_id) else {
+        return;
+    };
+    if ps.emulator.add_mark(name.as_str()).is_none() {
+        s.notifications.push(
+            "No mark added",
+            "Full-screen programs have no scrollback to mark.",
+        );
+        return;
+    }
+    ps.marked = true;
+    ps.dirty.store(true, Ordering::Relaxed);
+    s.notifications.push("Mark added", name);
+    request_redraw(app_weak);
+}
+
+fn scroll_to_mark(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    pane_id: PaneId,
+    mark: &Mark,
+) {
+    if let Some(ps) = s.pane_states.get(&pane_id) {
+        ps.emulator.scroll_to_line(mark.line);
+        ps.dirty.store(true, Ordering::Relaxed);
+        request_redraw(app_weak);
+    }
+}
+
+/// Scroll the active pane to its next mark below the top of the view, or
+/// the previous one above it
+fn jump_to_mark(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>, forward: bool) {
+    let pane_id = s.workspace_mgr.active_workspace().active_pane();
+    let Some(ps) = s.pane_states.get(&pane_id) else {
+        return;
+    };
+    let top = view_top(ps);
+    let marks = ps.emulator.marks();
+    let mark = if forward {
+        marks.iter().find(|mark| mark.line > top)
+    } else {
+        marks.iter().rev().find(|mark| mark.line < top)
+    };
+    match mark {
+        Some(mark) => scroll_to_mark(s, app_weak, pane_id, mark),
+        None if marks.is_empty() => {
+            s.notifications
+                .push("No marks", "Add one with Cmd+Shift+N.");
+        }
+        None => {}
+    }
+}
+
+/// Picker title and detail of a log choice
+fn log_choice(choice: Option<LogFormat>) -> (&'static str, &'static str) {
+    match choice {
+        Some(LogFormat::Plain) => ("Log plain text", "escape sequences removed"),
+        Some(LogFormat::Raw) => ("Log raw output", "escape sequences kept"),
+        None => ("Stop logging", ""),
+    }
+}
+
+/// Tee a pane's output to `path` (default: a new file in the logs
+/// directory), replacing any log it has open.
+fn start_pane_log(
+    s: &TerminalState,
+    pane_id: PaneId,
+    path: Option<PathBuf>,
+    options: LogOptions,
+) -> Result<PathBuf> {
+    let ps = s.pane_states.get(&pane_id).context("no such pane")?;
+    let config = &s.config.logging;
+    let path = path.unwrap_or_else(|| pane_log::default_path(&s.config.logs_dir(), pane_id));
+    let log = PaneLog::open(
+        &path,
+        options,
+        config.max_size_mb * 1024 * 1024,
+        config.keep,
+    )?;
+    ps.pty.start_log(log);
+    info!(pane_id, path = %path.display(), format = options.format.name(), "Pane log started");
+    Ok(path)
+}
+
+/// Log transforms configured under `[logging]`
+fn log_options(config: &pterminal_core::config::LoggingConfig) -> LogOptions {
+    LogOptions {
+        format: config.format,
+        timestamps: config.timestamps,
+        collapse_progress: config.collapse_progress,
+    }
+}
+
+/// Stop a pane's log, returning its path if it had one.
+fn stop_pane_log(s: &TerminalState, pane_id: PaneId) -> Option<PathBuf> {
+    s.pane_states.get(&pane_id)?.pty.stop_log()
+}
+
+/// Log panes whose foreground command line matches `logging.auto`, and
+/// stop those logs once the command ends.
+fn check_auto_logs(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let mut changed = false;
+    let pane_ids: Vec<PaneId> = s.pane_states.keys().copied().collect();
+    for pane_id in pane_ids {
+        let Some(ps) = s.pane_states.get(&pane_id) else {
+            continue;
+        };
+        let command = ps.pty.foreground_command().unwrap_or_default().join(" ");
+        let matched = s
+            .config
+            .logging
+            .auto
+            .iter()
+            .any(|pattern| !pattern.is_empty() && command.contains(pattern.as_str()));
+        if matched == ps.auto_log {
+            continue;
+        }
+        let mut auto_log_path = None;
+        if matched && ps.pty.log_path().is_none() {
+            match start_pane_log(s, pane_id, None, log_options(&s.config.logging)) {
+                Ok(path) => auto_log_path = Some(path),
+                Err(e) => warn!(pane_id, "Auto log failed: {e:#}"),
+            }
+        } else if !matched && ps.auto_log_path == ps.pty.log_path() {
+            // Only the log started here; one started by hand keeps going
+            stop_pane_log(s, pane_id);
+        }
+        if let Some(ps) = s.pane_states.get_mut(&pane_id) {
+            ps.auto_log = matched;
+            ps.auto_log_path = auto_log_path;
+        }
+        changed = true;
+    }
+    if changed {
+        update_tabs(s, app_weak);
+    }
+}
+
+/// Offer every other pane, matched by title, cwd or running command, as a
+/// target for typed lines; the active pane keeps focus.
+fn open_send_picker(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let active = s.workspace_mgr.active_workspace().active_pane();
+    let panes: Vec<(PaneId, String, String)> = s
+        .workspace_mgr
+        .workspaces()
+        .iter()
+        .flat_map(|ws| ws.pane_ids())
+        .filter(|&pane_id| pane_id != active)
+        .filter_map(|pane_id| {
+            let ps = s.pane_states.get(&pane_id)?;
+            let command = ps.pty.foreground_command().unwrap_or_default().join(" ");
+            let detail = match pane_subtitle(ps) {
+                subtitle if subtitle.is_empty() => command,
+                subtitle if command.is_empty() => subtitle,
+                subtitle => format!("{subtitle} · {command}"),
+            };
+            Some((pane_id, pane_label(pane_id, ps), detail))
+        })
+        .collect();
+    if panes.is_empty() {
+        info!("No other pane to send to");
+        return;
+    }
+    s.picker = Some(Picker {
+        pane_id: active,
+        mode: PickerMode::SendTarget {
+            panes,
+            matches: Vec::new(),
+        },
+    });
+    filter_picker(s, app_weak, "");
+    show_picker(app_weak, "Send to pane…", "", None);
+}
+
+/// Show the process tree of the active pane's shell.
+fn open_process_picker(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    let pane_id = s.workspace_mgr.active_workspace().active_pane();
+    let entries = pane_processes(s, pane_id);
+    if entries.is_empty() {
+        info!(pane_id, "No processes found for pane");
+        return;
+    }
+    s.picker = Some(Picker {
+        pane_id,
+        mode: PickerMode::Processes {
+            entries,
+            matches: Vec::new(),
+        },
+    });
+    filter_picker(s, app_weak, "");
+    show_picker(app_weak, "Processes", "", None);
+}
+
+/// The pane's shell and its descendants, with their depth below the shell
+fn pane_processes(s: &TerminalState, pane_id: PaneId) -> Vec<(usize, ProcessInfo)> {
+    let Some(ps) = s.pane_states.get(&pane_id) else {
+        return Vec::new();
+    };
+    let Some(pid) = ps.pty.child_pid() else {
+        return Vec::new();
+    };
+    process_tree::tree(&process_tree::snapshot(), pid)
+}
+
+/// Paste dropped files into the pane under the pointer, or offer to upload
+/// them when that pane is running ssh.
+fn handle_dropped_files(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    if s.dropped_files.is_empty() {
+        return;
+    }
+    let files = std::mem::take(&mut s.dropped_files);
+    let (x, y) = (s.last_mouse_pos.0 as f32, s.last_mouse_pos.1 as f32);
+    let pane_id =
+        pane_at_pixel(s, x, y).unwrap_or_else(|| s.workspace_mgr.active_workspace().active_pane());
+    let Some(ps) = s.pane_states.get(&pane_id) else {
+        return;
+    };
+    let Some(target) = file_drop::ssh_target(&ps.pty) else {
+        let _ = ps.pty.write(drop_text(&files, &s.config).as_bytes());
+        request_redraw(app_weak);
+        return;
+    };
+    let prompt = if files.len() == 1 {
+        "Dropped file".to_string()
+    } else {
+        format!("{} dropped files", files.len())
+    };
+    s.picker = Some(Picker {
+        pane_id,
+        mode: PickerMode::FileDrop {
+            files,
+            target,
+            matches: Vec::new(),
+        },
+    });
+    filter_picker(s, app_weak, "");
+    show_picker(app_weak, &prompt, "", None);
+}
+
+/// Report a finished upload; on success the remote paths are pasted into the pane.
+fn finish_upload(s: &mut TerminalState, upload: UploadResult) {
+    match upload.result {
+        Ok(paths) => {
+            if let Some(ps) = s.pane_states.get(&upload.pane_id) {
+                let _ = ps.pty.write(format!("{} ", paths.join(" ")).as_bytes());
+            }
+            let body = format!("{} file(s) copied to {}:~", paths.len(), upload.destination);
+            s.notifications.push("Upload finished", body);
+        }
+        Err(error) => {
+            warn!(destination = %upload.destination, %error, "Upload failed");
+            s.notifications.push("Upload failed", error);
+        }
+    }
+}
+
+/// Re-run ssh/mosh sessions that dropped with a network error, after a backoff.
+/// Progress is written into the pane as a banner.
+fn check_reconnects(s: &mut TerminalState) {
+    let now = Instant::now();
+    let config = &s.config.reconnect;
+    for ps in s.pane_states.values_mut() {
+        let foreground = ps.pty.foreground_command();
+        match ps.reconnect.observe(foreground.as_deref(), config, now) {
+            None => {}
+            Some(WatchEvent::Retry(command)) => {
+                let _ = ps.pty.write(format!("{command}\r").as_bytes());
+            }
+            Some(WatchEvent::Ended) => {
+                let screen = grid_to_text(&ps.emulator.extract_grid(&s.theme));
+                if !reconnect::is_network_disconnect(&screen) {
+                    ps.reconnect.cancel();
+                    continue;
+                }
+                let banner = match ps.reconnect.schedule(config, now) {
+                    Some((delay, attempt)) => format!(
+                        "Connection lost, reconnecting in {}s (attempt {attempt} of {}); \
+                         press any key to cancel",
+                        delay.as_secs(),
+                        config.max_attempts
+                    ),
+                    None => "Connection lost, giving up".to_string(),
+                };
+                reconnect_banner(ps, &banner);
+            }
+        }
+    }
+}
+
+/// Write a local status line into the pane; it never reaches the shell.
+fn reconnect_banner(ps: &PaneState, text: &str) {
+    ps.emulator
+        .process(format!("\r\n\x1b[7m [pterminal] {text} \x1b[0m\r\n").as_bytes());
+}
+
+/// Prompt for the next placeholder, or insert the snippet once all are filled.
+fn next_snippet_value(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    pane_id: PaneId,
+    snippet: Snippet,
+    pending: Vec<Placeholder>,
+    values: HashMap<String, String>,
+) {
+    if let Some(next) = pending.first() {
+        let prompt = format!("{}: {}", snippet.name, next.name);
+        let default = next.default.clone().unwrap_or_default();
+        s.picker = Some(Picker {
+            pane_id,
+            mode: PickerMode::SnippetValues {
+                snippet,
+                pending,
+                values,
+            },
+        });
+        filter_picker(s, app_weak, "");
+        show_picker(app_weak, &prompt, &default, None);
+        return;
+    }
+    close_picker(s, app_weak);
+    if let Some(ps) = s.pane_states.get_mut(&pane_id) {
+        let _ = send_input(ps, snippet.expand(&values).as_bytes());
+    }
+    request_redraw(app_weak);
+}
+
+/// Whether key presses are shown on screen: always when configured, or
+/// while presenting.
+fn keystrokes_visible(s: &TerminalState) -> bool {
+    s.config.keystrokes.enabled
+        || (s.presentation.is_some() && s.config.presentation.show_keystrokes)
+}
+
+/// Expire old keys and hand the overlay text to the renderer; returns whether
+/// the overlay changed and the frame must be redrawn.
+fn update_keystroke_overlay(s: &mut TerminalState) -> bool {
+    let config = &s.config.keystrokes;
+    let changed = s
+        .keystrokes
+        .expire(Duration::from_millis(config.timeout_ms));
+    let Some(renderer) = &mut s.renderer else {
+        return false;
+    };
+    if s.keystrokes.is_empty() {
+        renderer.text_renderer.clear_keystroke_overlay();
+    } else {
+        renderer.text_renderer.set_keystroke_overlay(
+            &s.keystrokes.text(),
+            config.font_size,
+            OverlayCorner::from_name(&config.position),
+        );
+    }
+    changed
+}
+
+/// Grow or shrink one pane's font size independently of the other panes;
+/// a `delta` of 0 restores the configured size. In reflow mode the grid is
+/// fitted to the pane again, in scale mode it keeps its columns and rows.
+fn step_pane_font_size(s: &mut TerminalState, pane_id: PaneId, delta: f32, mode: ZoomMode) {
+    match mode {
+        ZoomMode::Reflow => {
+            if let Some(ps) = s.pane_states.get_mut(&pane_id) {
+                ps.scaled_grid = None;
+            }
+        }
+        ZoomMode::Scale => keep_grid(s, pane_id),
+    }
+    let base = base_font_size(s);
+    let Some(renderer) = &mut s.renderer else {
+        return;
+    };
+    renderer
+        .text_renderer
+        .step_pane_font_size(pane_id, base, delta);
+    resize_active_workspace_panes(s);
+}
+
+/// Font size `pane_id` is drawn at (logical points)
+fn drawn_font_size(renderer: &OffscreenRenderer, pane_id: PaneId) -> f32 {
+    let text = &renderer.text_renderer;
+    text.pane_font_size(pane_id)
+        .unwrap_or(text.font_size() / text.scale_factor())
+}
+
+/// Keep `pane_id`'s columns and rows through the font size change about to
+/// be made, for scale zoom mode.
+fn keep_grid(s: &mut TerminalState, pane_id: PaneId) {
+    let Some(renderer) = &s.renderer else { return };
+    let font_size = drawn_font_size(renderer, pane_id);
+    let Some(ps) = s.pane_states.get_mut(&pane_id) else {
+        return;
+    };
+    if ps.scaled_grid.is_none() {
+        let rows = ps.emulator.metadata().rows;
+        ps.scaled_grid = Some(ScaledGrid {
+            cols: ps.visible_cols,
+            rows,
+            font_size,
+        });
+    }
+}
+
+/// Keep every pane's grid through a change of the base font size, when
+/// `font.zoom` is "scale".
+fn keep_grids_for_zoom(s: &mut TerminalState) {
+    if s.config.font.zoom_mode() != ZoomMode::Scale {
+        return;
+    }
+    let pane_ids: Vec<PaneId> = s.pane_states.keys().copied().collect();
+    for pane_id in pane_ids {
+        keep_grid(s, pane_id);
+    }
+}
+
+/// The grid a pane drawn at `font_size` keeps, if it still keeps one.
+fn kept_grid(ps: &mut PaneState, font_size: f32) -> Option<(u16, u16)> {
+    let grid = ps.scaled_grid.as_ref()?;
+    if (grid.font_size - font_size).abs() < 0.01 {
+        ps.scaled_grid = None;
+        return None;
+    }
+    Some((grid.cols, grid.rows))
+}
+
+/// Switch a pane between normal wrapping and no-wrap (horizontal scrolling) mode.
+/// The (horizontal, vertical) scroll of a wheel event. Shift turns the
+/// wheel sideways on no-wrap panes; elsewhere it still scrolls history.
+fn wheel_axes(dx: f32, dy: f32, shift: bool, no_wrap: bool) -> (f32, f32) {
+    if shift && no_wrap {
+        (dy, 0.0)
+    } else {
+        (dx, dy)
+    }
+}
+
+fn set_pane_no_wrap(s: &mut TerminalState, pane_id: PaneId, no_wrap: bool) -> bool {
+    let Some(ps) = s.pane_states.get_mut(&pane_id) else {
+        return false;
+    };
+    ps.no_wrap = no_wrap;
+    ps.h_offset = 0;
+    let rows = ps.emulator.metadata().rows;
+    let cols = ps.visible_cols;
+    resize_pane(ps, cols, rows, &s.config);
+    true
+}
+
+/// Scroll a pane's history, or send arrow keys when a full-screen app on the
+/// alternate screen asked for alternate scroll mode. Returns how many lines
+/// the view moved when smooth scrolling needs to know.
+fn scroll_pane(ps: &PaneState, lines: i32, config: &Config) -> i32 {
+    if config.scrollback.alternate_scroll {
+        let modes = ps.emulator.input_modes();
+        if modes.scroll_as_arrows() {
+            let key = modes.arrow_key(lines > 0);
+            let _ = ps.pty.write(&key.repeat(lines.unsigned_abs() as usize));
+            return 0;
+        }
+    }
+    if !config.scrollback.smooth {
+        ps.emulator.scroll(lines);
+        ps.dirty.store(true, Ordering::Relaxed);
+        return 0;
+    }
+    // Scrolling stops at either end of the history
+    let metadata = ps.emulator.metadata();
+    let before = metadata.display_offset as i32;
+    let landed = (before + lines).clamp(0, metadata.history_size as i32);
+    ps.emulator.scroll(lines);
+    ps.dirty.store(true, Ordering::Relaxed);
+    landed - before
+}
+
+/// Scroll a pane together with every pane whose scrolling is linked to it.
+fn scroll_linked_panes(s: &mut TerminalState, pane_id: PaneId, lines: i32) {
+    let peers = s.workspace_mgr.active_workspace().scroll_peers(pane_id);
+    for id in std::iter::once(pane_id).chain(peers) {
+        let Some(ps) = s.pane_states.get(&id) else {
+            continue;
+        };
+        let moved = scroll_pane(ps, lines, &s.config);
+        if let Some(renderer) = s.renderer.as_mut() {
+            renderer.text_renderer.animate_scroll(id, moved);
+        }
+    }
+}
+
+/// Send focus-in/out to panes whose focus changed, for apps that enabled
+/// focus reporting. Only the active pane is focused, and only while the
+/// terminal itself has focus.
+fn sync_pane_focus(s: &mut TerminalState) {
+    let focused = s
+        .window_focused
+        .then(|| s.workspace_mgr.active_workspace().active_pane());
+    for (&pane_id, ps) in s.pane_states.iter_mut() {
+        let has_focus = focused == Some(pane_id);
+        if ps.focused != has_focus {
+            ps.focused = has_focus;
+            if let Some(seq) = ps.emulator.input_modes().focus_report(has_focus) {
+                let _ = ps.pty.write(seq);
+            }
+        }
+    }
+}
+
+fn calc_cols_rows(renderer: &OffscreenRenderer, padding: f32) -> (u16, u16) {
+    let w = renderer.width().max(1) as f32 - padding * 2.0;
+    let h = panes_height(renderer) as f32 - padding * 2.0;
+    scale::grid_size(w, h, renderer.text_renderer.cell_size())
+}
+
+/// Cursor slide settings from config, `None` when turned off
+fn cursor_animation(config: &Config) -> Option<CursorAnimation> {
+    let cursor = &config.cursor;
+    (cursor.animate && cursor.animation_ms > 0).then(|| CursorAnimation {
+        duration: Duration::from_millis(cursor.animation_ms),
+        trail: cursor.trail,
+    })
+}
+
+/// Bold and italic face settings from config
+fn font_faces(config: &Config) -> FontFaces {
+    let family = |name: &str| (!name.trim().is_empty()).then(|| name.trim().to_string());
+    FontFaces {
+        bold: family(&config.font.bold_family),
+        italic: family(&config.font.italic_family),
+        bold_italic: family(&config.font.bold_italic_family),
+        synthetic: config.font.synthetic_styles,
+    }
+}
+
+/// Smooth scrolling settings from config, `None` when turned off
+fn scroll_animation(config: &Config) -> Option<ScrollAnimation> {
+    let scrollback = &config.scrollback;
+    (scrollback.smooth && scrollback.smooth_duration_ms > 0).then(|| ScrollAnimation {
+        duration: Duration::from_millis(scrollback.smooth_duration_ms),
+        easing: Easing::from_name(&scrollback.smooth_easing),
+    })
+}
+
+/// Inner pane padding in physical pixels (`window.padding` × display scale).
+fn pane_padding(s: &TerminalState) -> f32 {
+    s.config.window.padding.unwrap_or(0.0).max(0.0) * s.scale_factor as f32
+}
+
+fn pixel_rect_to_cols_rows(
+    rect: &PixelRect,
+    renderer: &OffscreenRenderer,
+    pane_id: PaneId,
+) -> (u16, u16) {
+    let cell = renderer.text_renderer.pane_cell_size(pane_id);
+    scale::grid_size(rect.w, rect.h, cell)
+}
+
+/// Minimum half-width of the divider gap between panes (logical pixels).
+const DIVIDER_HALF: f32 = 1.0;
+/// How close (logical pixels) the pointer must be to a divider to hover it
+const DIVIDER_HOVER_SLOP: f32 = 4.0;
+/// Highlight for the half of a pane a dragged pane would occupy
+const DROP_ZONE_COLOR: [f32; 4] = [0.32, 0.58, 0.89, 0.25];
+
+/// How long a pane jumped to from an alert stays highlighted, and the tint
+/// of its accent color over it
+const PANE_HIGHLIGHT: Duration = Duration::from_millis(800);
+const PANE_HIGHLIGHT_ALPHA: f32 = 0.2;
+
+/// Height of a pane's progress strip (logical pixels)
+const PROGRESS_STRIP_HEIGHT: f32 = 2.0;
+
+/// Size of the ticks marking where a pane's marks are (logical pixels)
+const MARK_TICK_WIDTH: f32 = 6.0;
+const MARK_TICK_HEIGHT: f32 = 2.0;
+
+/// Ticks along the right edge of `area`, placed like a scrollbar's: where
+/// each mark is in the whole buffer of `rows` screen lines plus history
+fn mark_ticks(
+    emulator: &TerminalEmulator,
+    rows: usize,
+    area: &PixelRect,
+    theme: &Theme,
+    scale: f32,
+) -> Vec<BgRect> {
+    let marks = emulator.marks();
+    if marks.is_empty() {
+        return Vec::new();
+    }
+    let total = (emulator.metadata().history_size + rows).max(1) as f32;
+    let (w, h) = (MARK_TICK_WIDTH * scale, MARK_TICK_HEIGHT * scale);
+    let color = theme.colors.ansi[4].to_wgpu_color();
+    marks
+        .iter()
+        .map(|mark| BgRect {
+            x: area.x + area.w - w,
+            y: area.y + (area.h - h) * mark.line as f32 / total,
+            w,
+            h,
+            color,
+        })
+        .collect()
+}
+
+/// Strip along the top of `area`, as wide as the progress made: the accent
+/// color, red on error and yellow when paused. Indeterminate progress and
+/// errors without a percentage fill it, faded.
+fn progress_strip(progress: Progress, area: &PixelRect, theme: &Theme, scale: f32) -> BgRect {
+    let colors = &theme.colors;
+    let color = match progress {
+        Progress::Error(_) => colors.ansi[1],
+        Progress::Paused(_) => colors.ansi[3],
+        Progress::Normal(_) | Progress::Indeterminate => colors.border_accent_color(),
+    };
+    let mut color = color.to_wgpu_color();
+    let fraction = match progress.percent() {
+        Some(percent) if percent > 0 => percent as f32 / 100.0,
+        Some(_) if progress == Progress::Normal(0) => 0.0,
+        _ => {
+            color[3] = 0.5;
+            1.0
+        }
+    };
+    BgRect {
+        x: area.x,
+        y: area.y,
+        w: area.w * fraction,
+        h: PROGRESS_STRIP_HEIGHT * scale,
+        color,
+    }
+}
+
+/// Gap left on each side of a divider (physical pixels)
+fn divider_gap(s: &TerminalState) -> f32 {
+    (s.config.borders.width * 0.5).max(DIVIDER_HALF) * s.scale_factor as f32
+}
+
+/// Height panes are laid out in: the renderer's, less the status bar
+fn panes_height(renderer: &OffscreenRenderer) -> u32 {
+    let bar = renderer.text_renderer.status_bar_height() as u32;
+    renderer.height().saturating_sub(bar).max(1)
+}
+
+fn pane_to_pixel_rect(
+    pane_rect: &pterminal_core::split::PaneRect,
+    window_w: u32,
+    window_h: u32,
+    gap: f32,
+    tab_bar_h: f32,
+    padding: f32,
+) -> PixelRect {
+    let content_w = (window_w as f32).max(1.0);
+    let content_h = window_h as f32 - tab_bar_h;
+    // Only add gap on sides that border another pane (not window edges).
+    let left = if pane_rect.x > 0.001 { gap } else { 0.0 } + padding;
+    let top = if pane_rect.y > 0.001 { gap } else { 0.0 } + padding;
+    let right = if pane_rect.x + pane_rect.width < 0.999 { gap } else { 0.0 } + padding;
+    let bottom = if pane_rect.y + pane_rect.height < 0.999 { gap } else { 0.0 } + padding;
+    PixelRect {
+        x: pane_rect.x * content_w + left,
+        y: pane_rect.y * content_h + top + tab_bar_h,
+        w: pane_rect.width * content_w - left - right,
+        h: pane_rect.height * content_h - top - bottom,
+    }
+}
+
+/// Where a pane's cells are drawn, inside its padding and gutter
+fn pane_pixel_rect(s: &TerminalState, pane_id: PaneId) -> Option<PixelRect> {
+    let renderer = s.renderer.as_ref()?;
+    let gap = divider_gap(s);
+    let padding = pane_padding(s);
+    let w = renderer.width();
+    let h = panes_height(renderer);
+    let shown = s
+        .pane_states
+        .get(&pane_id)
+        .is_some_and(|ps| ps.prompt_gutter);
+    s.workspace_mgr
+        .active_workspace()
+        .split_tree
+        .layout()
+        .into_iter()
+        .find(|(id, _)| *id == pane_id)
+        .map(|(_, rect)| pane_to_pixel_rect(&rect, w, h, gap, 0.0, padding))
+        .map(|rect| inset_gutter(rect, gutter_width(renderer, pane_id, shown)))
+}
+
+/// Width of a pane's prompt timestamps gutter (physical pixels); 0 when it
+/// isn't `shown`
+fn gutter_width(renderer: &OffscreenRenderer, pane_id: PaneId, shown: bool) -> f32 {
+    if !shown {
+        return 0.0;
+    }
+    PROMPT_GUTTER_CELLS * renderer.text_renderer.pane_cell_size(pane_id).0
+}
+
+/// `rect` less a gutter of `width` along its left edge
+fn inset_gutter(rect: PixelRect, width: f32) -> PixelRect {
+    let width = width.min(rect.w * 0.5);
+    PixelRect {
+        x: rect.x + width,
+        y: rect.y,
+        w: rect.w - width,
+        h: rect.h,
+    }
+}
+
+fn pane_at_pixel(s: &TerminalState, x: f32, y: f32) -> Option<PaneId> {
+    let renderer = s.renderer.as_ref()?;
+    let gap = divider_gap(s);
+    let w = renderer.width();
+    let h = panes_height(renderer);
+    s.workspace_mgr
+        .active_workspace()
+        .split_tree
+        .layout()
+        .into_iter()
+        .find_map(|(pane_id, pane_rect)| {
+            // Hit-test the full pane area, including its padding
+            let px = pane_to_pixel_rect(&pane_rect, w, h, gap, 0.0, 0.0);
+            let in_x = x >= px.x && x < px.x + px.w;
+            let in_y = y >= px.y && y < px.y + px.h;
+            if in_x && in_y {
+                Some(pane_id)
+            } else {
+                None
+            }
+        })
+}
+
+/// Pane under the pointer (other than the one being dragged) and the edge
+/// of it nearest to the pointer.
+fn drop_target_at(s: &TerminalState, x: f32, y: f32) -> Option<(PaneId, DropSide)> {
+    let renderer = s.renderer.as_ref()?;
+    let dragged = s.pane_drag.as_ref()?.pane_id;
+    let nx = x / (renderer.width() as f32).max(1.0);
+    let ny = y / panes_height(renderer) as f32;
+    s.workspace_mgr
+        .active_workspace()
+        .split_tree
+        .layout()
+        .into_iter()
+        .find(|(_, r)| nx >= r.x && nx < r.x + r.width && ny >= r.y && ny < r.y + r.height)
+        .filter(|(pane_id, _)| *pane_id != dragged)
+        .map(|(pane_id, rect)| (pane_id, DropSide::nearest(&rect, nx, ny)))
+}
+
+fn pixel_to_cell(s: &TerminalState, pane_id: PaneId) -> (u16, u16) {
+    let renderer = match s.renderer.as_ref() {
+        Some(r) => r,
+        None => return (0, 0),
+    };
+    let cell = renderer.text_renderer.pane_cell_size(pane_id);
+    let (mx, my) = (s.last_mouse_pos.0 as f32, s.last_mouse_pos.1 as f32);
+    let (px, py) = if let Some(rect) = pane_pixel_rect(s, pane_id) {
+        (mx - rect.x, my - rect.y)
+    } else {
+        (mx, my)
+    };
+    let (col, row) = scale::cell_at(px, py, cell);
+
+    if let Some(ps) = s.pane_states.get(&pane_id) {
+        let col = col.saturating_add(ps.h_offset);
+        let grid = ps.emulator.metadata();
+        let (col, row) = (
+            col.min(grid.cols.saturating_sub(1)),
+            row.min(grid.rows.saturating_sub(1)),
+        );
+        // The cell shown there, which bidi mode may have moved
+        (renderer.text_renderer.logical_col(pane_id, row, col), row)
+    } else {
+        (col, row)
+    }
+}
+
+/// Cells of the active pane's selection, one row per line
+fn selected_rows(s: &TerminalState) -> Option<Vec<Vec<GridCell>>> {
+    let sel = s.selection?;
+    let (start, end) = sel.normalized();
+    let active_pane = s.workspace_mgr.active_workspace().active_pane();
+    let ps = s.pane_states.get(&active_pane)?;
+    let grid = ps.emulator.extract_grid(&s.theme);
+
+    let rows = (start.1..=end.1)
+        .map_while(|row| {
+            let line = grid.get(row as usize)?;
+            let col_start = if row == start.1 { start.0 as usize } else { 0 };
+            let col_end = if row == end.1 {
+                (end.0 as usize + 1).min(line.cells.len())
+            } else {
+                line.cells.len()
+            };
+            Some(line.cells[col_start.min(col_end)..col_end].to_vec())
+        })
+        .collect();
+    Some(rows)
+}
+
+fn get_selected_text(s: &TerminalState) -> Option<String> {
+    let rows = selected_rows(s)?;
+    let active_pane = s.workspace_mgr.active_workspace().active_pane();
+    let ps = s.pane_states.get(&active_pane)?;
+    let joined: Vec<bool> = if joins_wrapped(&s.config, ps) {
+        let (start, _) = s.selection?.normalized();
+        let wrapped = ps.emulator.wrapped_rows();
+        wrapped.into_iter().skip(start.1 as usize).collect()
+    } else {
+        Vec::new()
+    };
+    let text = rich_text::to_text(&rows, &joined);
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// Whether copies from the pane make one line of a line the terminal wrapped
+fn joins_wrapped(config: &Config, ps: &PaneState) -> bool {
+    ps.join_wrapped.unwrap_or(config.selection.join_wrapped)
+}
+
+/// The cell, word or line at `cell` of the active pane, as a normalized
+/// (start, end) pair.
+fn selection_span(
+    s: &TerminalState,
+    unit: SelectionUnit,
+    (col, row): (u16, u16),
+) -> ((u16, u16), (u16, u16)) {
+    let active_pane = s.workspace_mgr.active_workspace().active_pane();
+    let Some(ps) = s.pane_states.get(&active_pane) else {
+        return ((col, row), (col, row));
+    };
+    match unit {
+        SelectionUnit::Cell => ((col, row), (col, row)),
+        SelectionUnit::Word => {
+            let Some(cells) = ps.render_grid.get(row as usize).map(|line| &line.cells) else {
+                return ((col, row), (col, row));
+            };
+            let is_word_char = |ch: char| s.config.selection.is_word_char(ch);
+            let c = col as usize;
+            if !cells.get(c).is_some_and(|cell| is_word_char(cell.c)) {
+                return ((col, row), (col, row));
+            }
+            let mut start = c;
+            while start > 0 && is_word_char(cells[start - 1].c) {
+                start -= 1;
+            }
+            let mut end = c;
+            while end + 1 < cells.len() && is_word_char(cells[end + 1].c) {
+                end += 1;
+            }
+            ((start as u16, row), (end as u16, row))
+        }
+        SelectionUnit::Line => {
+            let cols = ps.emulator.metadata().cols;
+            ((0, row), (cols.saturating_sub(1), row))
+        }
+    }
+}
+
+fn resize_active_workspace_panes(s: &mut TerminalState) {
+    let Some(renderer) = &s.renderer else { return };
+    let gap = divider_gap(s);
+    let w = renderer.width();
+    let h = panes_height(renderer);
+    let padding = pane_padding(s);
+    let layout = s.workspace_mgr.active_workspace().split_tree.layout();
+    let active_pane = s.workspace_mgr.active_workspace().active_pane();
+    for (pane_id, pane_rect) in &layout {
+        let px_rect = pane_to_pixel_rect(pane_rect, w, h, gap, 0.0, padding);
+        let shown = s
+            .pane_states
+            .get(pane_id)
+            .is_some_and(|ps| ps.prompt_gutter);
+        let px_rect = inset_gutter(px_rect, gutter_width(renderer, *pane_id, shown));
+        let (cols, rows) = pixel_rect_to_cols_rows(&px_rect, renderer, *pane_id);
+        let font_size = drawn_font_size(renderer, *pane_id);
+        if let Some(ps) = s.pane_states.get_mut(pane_id) {
+            let (cols, rows) = kept_grid(ps, font_size).unwrap_or((cols, rows));
+            let top = (*pane_id == active_pane && s.selection.is_some()).then(|| view_top(ps));
+            resize_pane(ps, cols, rows, &s.config);
+            // Keep the selection on the same text, wherever the resize
+            // left it on screen
+            if let Some(top) = top {
+                // Asked of the parser, which applies the resize first; the
+                // published metadata may not have it yet
+                let after = ps
+                    .emulator
+                    .history_size()
+                    .saturating_sub(ps.emulator.display_offset());
+                let shift = top as i64 - after as i64;
+                let (grid_cols, _) = ps.emulator.size();
+                let kept = s
+                    .selection
+                    .as_mut()
+                    .is_some_and(|sel| sel.remap(shift, grid_cols, rows));
+                if !kept {
+                    s.selection = None;
+                }
+            }
+        }
+    }
+}
+
+/// Absolute buffer line (counted from the oldest line of history) at the
+/// top of a pane's view
+fn view_top(ps: &PaneState) -> usize {
+    let metadata = ps.emulator.metadata();
+    metadata.history_size.saturating_sub(metadata.display_offset)
+}
+
+// ---------------------------------------------------------------------------
+// Key handling
+// ---------------------------------------------------------------------------
+
+/// Note the physical key of a key press for shortcut matching, and what
+/// it types without modifiers to tell the keyboard layout.
+fn record_key_press(s: &mut TerminalState, event: &winit::event::KeyEvent) {
+    use winit::keyboard::{Key, PhysicalKey};
+    use winit::platform::modifier_supplement::KeyEventExtModifierSupplement;
+
+    let PhysicalKey::Code(code) = event.physical_key else {
+        s.pressed_key = None;
+        return;
+    };
+    // winit names keys as the W3C codes do
+    let code = format!("{code:?}");
+    if let Key::Character(typed) = event.key_without_modifiers() {
+        if let Some(typed) = typed.chars().next() {
+            if s.typed_keys.insert(code.clone(), typed) != Some(typed) {
+                let seen = s.typed_keys.iter().map(|(code, ch)| (code.as_str(), *ch));
+                s.keyboard_layout = KeyboardLayout::detect(seen);
+            }
+        }
+    }
+    s.pressed_key = Some(code);
+}
+
+fn handle_key_event(
+    event: &KeyEvent,
+    s: &mut TerminalState,
+    _theme: &Arc<Theme>,
+    app_weak: &slint::Weak<AppWindow>,
+) {
+    let text = event.text.to_string();
+    if text.is_empty() {
+        return;
+    }
+    let ch = match text.chars().next() {
+        Some(c) => c,
+        None => return,
+    };
+    // Ctrl and Command (Super) as the keys pressed, the same on every
+    // platform; shortcuts match by physical key where layouts differ
+    let ctrl = s.key_modifiers.control_key();
+    let meta = s.key_modifiers.super_key();
+    let shift = event.modifiers.shift;
+    let code = s.pressed_key.take();
+    let key = keyboard_layout::shortcut_char(code.as_deref(), ch);
+
+    // Modifier-only keys — ignore ONLY when no Ctrl/Meta modifier is held.
+    // When Ctrl is pressed, chars like \u{0016} are Ctrl+V, not modifier-only.
+    if !ctrl && !meta {
+        match ch {
+            '\u{0010}' | '\u{0011}' | '\u{0012}' | '\u{0013}' | '\u{0014}' | '\u{0015}'
+            | '\u{0016}' | '\u{0017}' | '\u{0018}' => return,
+            _ => {}
+        }
+    }
+
+    if keystrokes_visible(s) {
+        let mods = keystrokes::Modifiers {
+            ctrl,
+            alt: event.modifiers.alt,
+            shift,
+            meta,
+        };
+        s.keystrokes.record(ch, mods);
+        request_redraw(app_weak);
+    }
+
+    if s.cheat_sheet.is_some() && !ctrl && !meta && ch == '\u{001b}' {
+        toggle_cheat_sheet(s, app_weak);
+        return;
+    }
+
+    // Any key cancels a pending reconnect and is swallowed
+    let active = s.workspace_mgr.active_workspace().active_pane();
+    if let Some(ps) = s.pane_states.get_mut(&active) {
+        if ps.reconnect.is_pending() {
+            ps.reconnect.cancel();
+            reconnect_banner(ps, "Reconnect cancelled");
+            request_redraw(app_weak);
+            return;
+        }
+        // Nothing reaches a failed pane; Return restarts it
+        if ps.failed.is_some() && !ctrl && !meta && ch == '\u{000a}' {
+            restart_pane(s, app_weak, active);
+            return;
+        }
+    }
+
+    // ── Cmd/Ctrl shortcuts ──
+    // On macOS, Cmd (meta) is the primary modifier for UI actions.
+    // Ctrl sends terminal control characters.
+    let action_mod = meta || ctrl;
+
+    // Shortcuts, as the key bindings have them
+    if action_mod {
+        let pressed = pressed_binding(key, ctrl, event.modifiers.alt, shift, meta);
+        let action = pressed
+            .as_deref()
+            .and_then(|pressed| keys::bound_action(&s.config.keybindings, pressed))
+            .map(str::to_string);
+        if let Some(action) = action {
+            if run_action(s, app_weak, &action) {
+                return;
+            }
+            warn!(
+                "{} is bound to unknown action {action}",
+                pressed.unwrap_or_default()
+            );
+        }
+    }
+
+    // Function, cursor and keypad keys go to the pane with their modifiers
+    let special = special_key(ch, code.as_deref(), action_mod);
+
+    if action_mod && special.is_none() {
+        // Any other Cmd/Ctrl+letter → send control character to PTY
+        // e.g. Cmd+L → \x0c (clear), Cmd+D → \x04 (EOF). Slint may send
+        // either the literal letter or a control character depending on
+        // which modifier is active.
+        let letter = if key.is_ascii_alphabetic() {
+            Some(key.to_ascii_lowercase())
+        } else if (key as u32) >= 1 && (key as u32) <= 26 {
+            Some((b'a' + key as u8 - 1) as char)
+        } else {
+            None
+        };
+        if let Some(letter) = letter {
+            let ctrl_byte = letter as u8 - b'a' + 1;
+            let active = s.workspace_mgr.active_workspace().active_pane();
+            if let Some(ps) = s.pane_states.get_mut(&active) {
+                let _ = send_input(ps, &[ctrl_byte]);
+            }
+            request_redraw(app_weak);
+        }
+        // Unhandled Cmd/Ctrl combo — ignore
+        return;
+    }
+
+    // Clear selection on non-modifier key press
+    if s.selection.is_some() {
+        s.selection = None;
+        let active = s.workspace_mgr.active_workspace().active_pane();
+        if let Some(ps) = s.pane_states.get(&active) {
+            ps.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+
+    // Convert key to bytes
+    let active = s.workspace_mgr.active_workspace().active_pane();
+    let modes = s
+        .pane_states
+        .get(&active)
+        .map(|ps| ps.emulator.input_modes())
+        .unwrap_or_default();
+    let mods = KeyMods {
+        shift,
+        alt: event.modifiers.alt,
+        ctrl,
+        meta,
+    };
+    let encode = || match special {
+        Some(special) => Some(encode_key(special, mods, &modes)),
+        None => slint_key_to_bytes(ch, ctrl, &text),
+    };
+    let bytes = if event.modifiers.alt {
+        word_jump_bytes(ch, modes.alt_screen).or_else(encode)
+    } else {
+        encode()
+    };
+    if let Some(bytes) = bytes {
+        let active = s.workspace_mgr.active_workspace().active_pane();
+        if let Some(ps) = s.pane_states.get_mut(&active) {
+            let _ = send_input(ps, &bytes);
+        }
+        request_redraw(app_weak);
+    }
+}
+
+/// A key press as key bindings spell it, e.g. "ctrl+shift+t", from the
+/// character shortcuts match by. Shifted punctuation is named by its
+/// unshifted key, as "super+shift+=" for Cmd+Shift+=.
+fn pressed_binding(key: char, ctrl: bool, alt: bool, shift: bool, meta: bool) -> Option<String> {
+    const SHIFTED: &str = "!@#$%^&*()_+{}|:\"<>?~";
+    const UNSHIFTED: &str = "1234567890-=[]\\;',./`";
+    let name = match key {
+        '\u{F700}' => "up".to_string(),
+        '\u{F701}' => "down".to_string(),
+        '\u{F702}' => "left".to_string(),
+        '\u{F703}' => "right".to_string(),
+        '\u{F704}'..='\u{F71B}' => format!("f{}", key as u32 - 0xF704 + 1),
+        '\u{000a}' => "enter".to_string(),
+        '\u{0008}' => "backspace".to_string(),
+        '\u{0009}' => "tab".to_string(),
+        '\u{001b}' => "escape".to_string(),
+        '\u{007f}' => "delete".to_string(),
+        ' ' => "space".to_string(),
+        // Ctrl+letter
+        '\u{0001}'..='\u{001a}' => ((b'a' + key as u8 - 1) as char).to_string(),
+        _ if key.is_ascii_alphabetic() => key.to_ascii_lowercase().to_string(),
+        _ => match SHIFTED.chars().position(|c| c == key) {
+            Some(i) => UNSHIFTED.chars().nth(i)?.to_string(),
+            None if key.is_ascii_graphic() => key.to_string(),
+            None => return None,
+        },
+    };
+    let modifiers = [
+        (ctrl, "ctrl"),
+        (alt, "alt"),
+        (shift, "shift"),
+        (meta, "super"),
+    ];
+    let mut parts: Vec<&str> = modifiers
+        .iter()
+        .filter(|(held, _)| *held)
+        .map(|(_, name)| *name)
+        .collect();
+    parts.push(&name);
+    Some(parts.join("+"))
+}
+
+/// Run a key binding's action on the active pane. Returns false for an
+/// action there is none of.
+fn run_action(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>, action: &str) -> bool {
+    let active = s.workspace_mgr.active_workspace().active_pane();
+    match action {
+        "copy-styled" if s.selection.is_some() => copy_selection_styled(s),
+        "copy" | "copy-styled" => {
+            // Copy if selection exists, otherwise send SIGINT (Ctrl+C)
+            if s.selection.is_some() {
+                copy_selection(s);
+            } else {
+                if let Some(ps) = s.pane_states.get_mut(&active) {
+                    let _ = send_input(ps, b"\x03");
+                }
+                request_redraw(app_weak);
+            }
+        }
+        "copy-html" => copy_selection_html(s),
+        "paste" => paste_clipboard(s, app_weak),
+        "new-workspace" => {
+            new_workspace(s, app_weak);
+        }
+        "close-workspace" => close_active_workspace(s, app_weak),
+        "split-right" | "split-down" => {
+            let direction = if action == "split-down" {
+                SplitDirection::Vertical
+            } else {
+                SplitDirection::Horizontal
+            };
+            split_active_pane(s, direction, None);
+            request_redraw(app_weak);
+        }
+        "split-with-env" => {
+            split_with_env(s, SplitDirection::Horizontal);
+            request_redraw(app_weak);
+        }
+        "duplicate-pane" => {
+            duplicate_pane(s, SplitDirection::Horizontal, None);
+            update_sidebar(s, app_weak);
+            request_redraw(app_weak);
+        }
+        "respawn-pane" => {
+            restart_pane(s, app_weak, active);
+        }
+        "focus-next" => focus_adjacent_pane(s, app_weak, true),
+        "focus-prev" => focus_adjacent_pane(s, app_weak, false),
+        "search" => open_grep_view(s, app_weak),
+        "pane-search" => open_pane_search(s, app_weak, false),
+        "snippets" => open_snippet_picker(s, app_weak),
+        "history" => open_history_picker(s, app_weak),
+        "processes" => open_process_picker(s, app_weak),
+        "theme-picker" => open_theme_picker(s, app_weak),
+        "overview" => open_overview(s, app_weak),
+        "log-viewer" => open_log_viewer(s, app_weak),
+        "send-to-pane" => open_send_picker(s, app_weak),
+        "export-layout" => open_export_layout_picker(s, app_weak),
+        "import-layout" => open_import_layout_picker(s, app_weak),
+        "rerun-last-input" => {
+            // Re-send the previous line, for build/test loops in REPLs
+            if let Some(ps) = s.pane_states.get_mut(&active) {
+                if let Some(line) = ps.input.last().map(|l| format!("{l}\r")) {
+                    let _ = send_input(ps, line.as_bytes());
+                }
+            }
+            request_redraw(app_weak);
+        }
+        "full-line" => open_full_line(s, app_weak),
+        "marks" => open_marks_picker(s, app_weak),
+        "prev-mark" => jump_to_mark(s, app_weak, false),
+        "next-mark" => jump_to_mark(s, app_weak, true),
+        "latest-alert" => jump_to_latest_alert(s, app_weak),
+        "pin-screen" => toggle_pin_screen(s, app_weak),
+        "toggle-presentation" => {
+            let enabled = s.presentation.is_none();
+            set_presentation(s, app_weak, enabled);
+        }
+        "toggle-eco" => toggle_eco(s, app_weak),
+        "toggle-high-contrast" => toggle_high_contrast(s, app_weak),
+        "toggle-prompt-gutter" => toggle_prompt_gutter(s, app_weak),
+        "toggle-wrap" => {
+            let no_wrap = s.pane_states.get(&active).is_some_and(|ps| !ps.no_wrap);
+            set_pane_no_wrap(s, active, no_wrap);
+            request_redraw(app_weak);
+        }
+        "pause-output" => toggle_pause_output(s, app_weak),
+        "log-output" => open_pane_log_picker(s, app_weak),
+        "toggle-do-not-disturb" => {
+            let enabled = !s.config.notification.do_not_disturb;
+            set_do_not_disturb(s, enabled);
+        }
+        "font-bigger" | "font-smaller" | "font-reset" => {
+            let delta = match action {
+                "font-bigger" => 1.0,
+                "font-smaller" => -1.0,
+                _ => 0.0,
+            };
+            let mode = s.config.font.zoom_mode();
+            step_pane_font_size(s, active, delta, mode);
+            request_redraw(app_weak);
+        }
+        "cheat-sheet" => toggle_cheat_sheet(s, app_weak),
+        "settings" => open_settings(s, app_weak),
+        _ => match action
+            .strip_prefix("workspace-")
+            .and_then(|n| n.parse::<usize>().ok())
+        {
+            Some(n @ 1..=9) => select_workspace(s, app_weak, n - 1),
+            _ => return false,
+        },
+    }
+    true
+}
+
+/// Open a workspace with one shell pane and switch to it.
+fn new_workspace(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+) -> (WorkspaceId, PaneId) {
+    let (workspace_id, pane_id) = s.workspace_mgr.add_workspace();
+    let (cols, rows) = if let Some(renderer) = &s.renderer {
+        calc_cols_rows(renderer, pane_padding(s))
+    } else {
+        (80, 24)
+    };
+    let ps = spawn_pane_slint(s, pane_id, cols, rows);
+    workspace_created(s, workspace_id, pane_id, ps.cwd.clone());
+    s.pane_states.insert(pane_id, ps);
+    update_tabs(s, app_weak);
+    request_redraw(app_weak);
+    (workspace_id, pane_id)
+}
+
+fn workspace_created(
+    s: &TerminalState,
+    workspace_id: WorkspaceId,
+    pane_id: PaneId,
+    cwd: Option<PathBuf>,
+) {
+    let context = HookContext {
+        pane_id: Some(pane_id),
+        workspace_id: Some(workspace_id),
+        cwd,
+        ..Default::default()
+    };
+    hooks::run(&s.config.hooks, HookEvent::WorkspaceCreated, context);
+}
+
+/// ID of the workspace holding `pane_id`
+fn workspace_id_of(s: &TerminalState, pane_id: PaneId) -> Option<WorkspaceId> {
+    let idx = s.workspace_mgr.workspace_index_of(pane_id)?;
+    Some(s.workspace_mgr.workspaces()[idx].id)
+}
+
+/// Close the active workspace and its panes, unless it is the last one.
+fn close_active_workspace(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    if s.workspace_mgr.workspace_count() <= 1 {
+        return;
+    }
+    let ws = s.workspace_mgr.active_workspace();
+    let pane_ids = ws.pane_ids();
+    let ws_id = ws.id;
+    for pid in &pane_ids {
+        s.pane_states.remove(pid);
+        if let Some(renderer) = &mut s.renderer {
+            renderer.text_renderer.remove_pane(*pid);
+        }
+    }
+    s.workspace_mgr.close_workspace(ws_id);
+    update_tabs(s, app_weak);
+    request_redraw(app_weak);
+}
+
+fn home_dir() -> PathBuf {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+}
+
+/// `layouts/<name>.toml` in the config directory
+fn default_layout_path(name: &str) -> PathBuf {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '-' => c,
+            c if c.is_alphanumeric() => c,
+            _ => '_',
+        })
+        .collect();
+    Config::layouts_dir().join(format!("{name}.toml"))
+}
+
+/// The workspace at `idx` as a shareable layout: each pane's cwd, Python
+/// env activation and running program.
+fn workspace_layout(s: &TerminalState, idx: usize) -> Option<WorkspaceLayout> {
+    let ws = s.workspace_mgr.workspaces().get(idx)?;
+    let home = home_dir();
+    let shell = s.config.shell();
+    let root = ws.split_tree.to_layout(|pane_id| {
+        let Some(ps) = s.pane_states.get(&pane_id) else {
+            return PaneLayout::default();
+        };
+        let cwd = ps.pty.cwd().or_else(|| ps.cwd.clone());
+        PaneLayout {
+            cwd: cwd.as_deref().map(|cwd| layout::portable_path(cwd, &home)),
+            profile: pane_env(ps, cwd.as_deref())
+                .python
+                .map(|python| python.activation_command()),
+            command: running_command(ps, &shell),
+        }
+    });
+    Some(WorkspaceLayout {
+        name: ws.name.clone(),
+        root,
+    })
+}
+
+/// The pane's foreground program as a command line, `None` at the prompt
+fn running_command(ps: &PaneState, shell: &str) -> Option<String> {
+    let argv = ps.pty.foreground_command()?;
+    let program = Path::new(argv.first()?.trim_start_matches('-')).file_name()?;
+    if Path::new(shell).file_name() == Some(program) {
+        return None;
+    }
+    let kind = ShellKind::from_shell(shell);
+    let words: Vec<String> = argv.iter().map(|word| kind.quote(word)).collect();
+    Some(words.join(" "))
+}
+
+/// Write the layout of the workspace at `idx` to `path`. Returns its pane count.
+fn export_layout(s: &TerminalState, idx: usize, path: &Path) -> Result<usize> {
+    let layout = workspace_layout(s, idx).context("workspace not found")?;
+    layout.save(path)?;
+    info!(path = %path.display(), "Layout exported");
+    Ok(s.workspace_mgr.workspaces()[idx].pane_ids().len())
+}
+
+/// Open a workspace from a layout file.
+fn import_layout(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    path: &Path,
+) -> Result<(WorkspaceId, Vec<PaneId>)> {
+    let saved = WorkspaceLayout::load(path)?;
+    let opened = open_layout(s, app_weak, &saved);
+    info!(path = %path.display(), "Layout imported");
+    Ok(opened)
+}
+
+/// Open a workspace shaped like `saved` and switch to it: each pane starts
+/// its shell in the saved cwd (when it exists here) and types the saved
+/// startup lines.
+fn open_layout(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    saved: &WorkspaceLayout,
+) -> (WorkspaceId, Vec<PaneId>) {
+    let (workspace_id, panes) = s.workspace_mgr.add_workspace_from_layout(saved);
+    let home = home_dir();
+    for (pane_id, pane) in &panes {
+        let cwd = pane.cwd.as_deref();
+        let cwd = match cwd.map(|cwd| layout::expand_path(cwd, &home)) {
+            Some(cwd) if cwd.is_dir() => cwd,
+            Some(cwd) => {
+                warn!(cwd = %cwd.display(), "Layout directory not found");
+                s.config.working_directory()
+            }
+            None => s.config.working_directory(),
+        };
+        let ps = spawn_pane_in(s, *pane_id, 80, 24, &cwd);
+        for line in pane.startup_lines() {
+            let _ = ps.pty.write(format!("{line}\r").as_bytes());
+        }
+        s.pane_states.insert(*pane_id, ps);
+    }
+    resize_active_workspace_panes(s);
+    update_tabs(s, app_weak);
+    request_redraw(app_weak);
+    let (first, _) = &panes[0];
+    let cwd = s.pane_states.get(first).and_then(|ps| ps.cwd.clone());
+    workspace_created(s, workspace_id, *first, cwd);
+    (workspace_id, panes.into_iter().map(|(id, _)| id).collect())
+}
+
+/// Open the `[[startup.workspaces]]` in place of the initial workspace,
+/// focusing the first. Returns false if none opened, leaving the initial
+/// workspace (which has no shell yet) in place.
+fn open_startup_workspaces(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) -> bool {
+    let workspaces = s.config.startup.workspaces.clone();
+    let (layouts_dir, home) = (Config::layouts_dir(), home_dir());
+    let mut opened = false;
+    for workspace in &workspaces {
+        match workspace.to_layout(&layouts_dir, &home) {
+            Ok(saved) => {
+                open_layout(s, app_weak, &saved);
+                opened = true;
+            }
+            Err(e) => {
+                warn!("Startup workspace: {e:#}");
+                let body = format!("{e:#}");
+                s.notifications.push("Startup workspace not opened", body);
+            }
+        }
+    }
+    if opened {
+        let initial = s.workspace_mgr.workspaces()[0].id;
+        s.workspace_mgr.close_workspace(initial);
+        select_workspace(s, app_weak, 0);
+    }
+    opened
+}
+
+fn select_workspace(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>, idx: usize) {
+    if idx < s.workspace_mgr.workspace_count() {
+        s.workspace_mgr.select_workspace(idx);
+        for ps in s.pane_states.values() {
+            ps.dirty.store(true, Ordering::Relaxed);
+        }
+        update_tabs(s, app_weak);
+        request_redraw(app_weak);
+    }
+}
+
+/// Switch to the workspace and pane a notification came from, wherever the
+/// pane is now, and flash it. Returns false when it didn't come from a pane
+/// or the pane is gone.
+fn focus_notification(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>, id: u64) -> bool {
+    let Some(pane_id) = s.notifications.get(id).and_then(|n| n.pane_id) else {
+        return false;
+    };
+    if !focus_pane(s, app_weak, pane_id) {
+        return false;
+    }
+    s.notifications.mark_read(id);
+    true
+}
+
+/// Switch to a pane's workspace and focus it, flashing it so the eye finds
+/// it. Returns false if the pane is gone.
+fn focus_pane(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>, pane_id: PaneId) -> bool {
+    let Some(ws_idx) = s.workspace_mgr.workspace_index_of(pane_id) else {
+        return false;
+    };
+    s.workspace_mgr.select_workspace(ws_idx);
+    s.workspace_mgr
+        .active_workspace_mut()
+        .set_active_pane(pane_id);
+    if let Some(ps) = s.pane_states.get_mut(&pane_id) {
+        ps.highlight_until = Some(Instant::now() + PANE_HIGHLIGHT);
+    }
+    for ps in s.pane_states.values() {
+        ps.dirty.store(true, Ordering::Relaxed);
+    }
+    update_tabs(s, app_weak);
+    request_redraw(app_weak);
+    true
+}
+
+/// Focus the pane the newest unread alert came from. Alerts from panes
+/// closed since are marked read on the way.
+fn jump_to_latest_alert(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    while let Some(id) = s.notifications.latest_alert().map(|n| n.id) {
+        if focus_notification(s, app_weak, id) {
+            return;
+        }
+        s.notifications.mark_read(id);
+    }
+    s.notifications.push(
+        "No alerts",
+        "Bells and commands finishing in panes out of sight show up here.",
+    );
+}
+
+/// Turn do not disturb on or off until it is switched again; quiet hours
+/// still apply while it is off.
+fn set_do_not_disturb(s: &mut TerminalState, enabled: bool) {
+    s.config.notification.do_not_disturb = enabled;
+    s.notification_rules = NotificationRules::new(&s.config.notification);
+    let title = if enabled {
+        "Do not disturb on"
+    } else {
+        "Do not disturb off"
+    };
+    s.notifications.push(title, "");
+}
+
+/// Silence (or not) alerts from the panes of the workspace at `idx`.
+fn set_workspace_muted(s: &mut TerminalState, idx: usize, muted: bool) {
+    let Some(ws) = s.workspace_mgr.workspace_mut(idx) else {
+        return;
+    };
+    ws.muted = muted;
+    let name = ws.name.clone();
+    let title = if muted {
+        "Workspace alerts muted"
+    } else {
+        "Workspace alerts unmuted"
+    };
+    s.notifications.push(title, name);
+}
+
+/// Focus the next (or previous) pane of the active workspace.
+fn focus_adjacent_pane(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>, forward: bool) {
+    let ws = s.workspace_mgr.active_workspace();
+    let current = ws.active_pane();
+    let target = if forward {
+        ws.split_tree.next_pane(current)
+    } else {
+        ws.split_tree.prev_pane(current)
+    };
+    if let Some(target) = target {
+        s.workspace_mgr
+            .active_workspace_mut()
+            .set_active_pane(target);
+        for ps in s.pane_states.values() {
+            ps.dirty.store(true, Ordering::Relaxed);
+        }
+        request_redraw(app_weak);
+    }
+}
+
+fn copy_selection(s: &mut TerminalState) {
+    if let Some(txt) = get_selected_text(s) {
+        if let Some(clip) = &mut s.clipboard {
+            let _ = clip.set_text(txt);
+        }
+    }
+}
+
+/// Copy the selection as HTML source, to paste into a document's markup.
+fn copy_selection_html(s: &mut TerminalState) {
+    let Some(rows) = selected_rows(s) else {
+        return;
+    };
+    let html = rich_text::to_html(&rows, &selection_font(s), s.theme.colors.background);
+    if let Some(clip) = &mut s.clipboard {
+        let _ = clip.set_text(html);
+    }
+}
+
+/// Copy the selection with its colors and bold/italic (HTML, plus RTF on
+/// macOS) alongside the plain text, so rich editors keep the styling.
+fn copy_selection_styled(s: &mut TerminalState) {
+    let (Some(rows), Some(text)) = (selected_rows(s), get_selected_text(s)) else {
+        return;
+    };
+    let font = selection_font(s);
+    let background = s.theme.colors.background;
+    let html = rich_text::to_html(&rows, &font, background);
+    #[cfg(target_os = "macos")]
+    {
+        let rtf = rich_text::to_rtf(&rows, &font, background);
+        macos::set_rich_clipboard(&text, &html, &rtf);
+    }
+    #[cfg(not(target_os = "macos"))]
+    if let Some(clip) = &mut s.clipboard {
+        let _ = clip.set_html(html, Some(text));
+    }
+}
+
+/// Font family named in copied HTML/RTF
+fn selection_font(s: &TerminalState) -> String {
+    if let Some(family) = &s.font_family {
+        return family.clone();
+    }
+    let families = s.config.font.families();
+    families.first().unwrap_or(&"monospace").to_string()
+}
+
+/// Type the clipboard text into the active pane.
+fn paste_clipboard(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    if let Some(clip) = &mut s.clipboard {
+        if let Ok(txt) = clip.get_text() {
+            let active = s.workspace_mgr.active_workspace().active_pane();
+            if let Some(ps) = s.pane_states.get_mut(&active) {
+                let _ = send_input(ps, txt.as_bytes());
+            }
+        }
+    }
+    request_redraw(app_weak);
+}
+
+/// Paste the primary selection into `pane_id`, making it the active pane.
+fn paste_primary_selection(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    pane_id: PaneId,
+) {
+    let Some(text) = s.clipboard.as_mut().and_then(platform::primary_selection) else {
+        return;
+    };
+    s.workspace_mgr
+        .active_workspace_mut()
+        .set_active_pane(pane_id);
+    for ps in s.pane_states.values() {
+        ps.dirty.store(true, Ordering::Relaxed);
+    }
+    if let Some(ps) = s.pane_states.get_mut(&pane_id) {
+        let _ = send_input(ps, text.as_bytes());
+    }
+    request_redraw(app_weak);
+}
+
+/// Run an action picked from the macOS menu bar or Dock menu.
+#[cfg(target_os = "macos")]
+fn run_menu_action(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    action: macos::MenuAction,
+) {
+    use macos::MenuAction;
+
+    let active = s.workspace_mgr.active_workspace().active_pane();
+    match action {
+        MenuAction::NewTab => {
+            new_workspace(s, app_weak);
+        }
+        MenuAction::NewWindow => macos::open_new_window(),
+        MenuAction::CloseTab => close_active_workspace(s, app_weak),
+        MenuAction::SplitRight | MenuAction::SplitDown => {
+            let direction = if action == MenuAction::SplitRight {
+                SplitDirection::Horizontal
+            } else {
+                SplitDirection::Vertical
+            };
+            split_active_pane(s, direction, None);
+            request_redraw(app_weak);
+        }
+        MenuAction::SplitWithEnv => {
+            split_with_env(s, SplitDirection::Horizontal);
+            request_redraw(app_weak);
+        }
+        MenuAction::RespawnPane => {
+            let active = s.workspace_mgr.active_workspace().active_pane();
+            restart_pane(s, app_weak, active);
+        }
+        MenuAction::DuplicatePane => {
+            duplicate_pane(s, SplitDirection::Horizontal, None);
+            update_sidebar(s, app_weak);
+            request_redraw(app_weak);
+        }
+        MenuAction::Snippets => open_snippet_picker(s, app_weak),
+        MenuAction::History => open_history_picker(s, app_weak),
+        MenuAction::Processes => open_process_picker(s, app_weak),
+        MenuAction::SendToPane => open_send_picker(s, app_weak),
+        MenuAction::LogOutput => open_pane_log_picker(s, app_weak),
+        MenuAction::ExportLayout => open_export_layout_picker(s, app_weak),
+        MenuAction::ImportLayout => open_import_layout_picker(s, app_weak),
+        MenuAction::Copy => copy_selection(s),
+        MenuAction::CopyWithStyles => copy_selection_styled(s),
+        MenuAction::CopyAsHtml => copy_selection_html(s),
+        MenuAction::Paste => paste_clipboard(s, app_weak),
+        MenuAction::TogglePresentation => {
+            let enabled = s.presentation.is_none();
+            set_presentation(s, app_weak, enabled);
+        }
+        MenuAction::ToggleEco => toggle_eco(s, app_weak),
+        MenuAction::ToggleHighContrast => toggle_high_contrast(s, app_weak),
+        MenuAction::SimulateColorVision => cycle_color_vision(s, app_weak),
+        MenuAction::TogglePromptTimes => toggle_prompt_gutter(s, app_weak),
+        MenuAction::Marks => open_marks_picker(s, app_weak),
+        MenuAction::FilterScrollback => open_grep_view(s, app_weak),
+        MenuAction::FindInPanes => open_pane_search(s, app_weak, false),
+        MenuAction::FindInAllWorkspaces => open_pane_search(s, app_weak, true),
+        MenuAction::OpenFullLine => open_full_line(s, app_weak),
+        MenuAction::PauseOutput => toggle_pause_output(s, app_weak),
+        MenuAction::PinScreen => toggle_pin_screen(s, app_weak),
+        MenuAction::ToggleWrap => {
+            let no_wrap = s.pane_states.get(&active).is_some_and(|ps| !ps.no_wrap);
+            set_pane_no_wrap(s, active, no_wrap);
+            request_redraw(app_weak);
+        }
+        MenuAction::Overview => open_overview(s, app_weak),
+        MenuAction::SwitchTheme => open_theme_picker(s, app_weak),
+        MenuAction::BiggerText | MenuAction::SmallerText | MenuAction::ResetText => {
+            let delta = match action {
+                MenuAction::BiggerText => 1.0,
+                MenuAction::SmallerText => -1.0,
+                _ => 0.0,
+            };
+            let mode = s.config.font.zoom_mode();
+            step_pane_font_size(s, active, delta, mode);
+            request_redraw(app_weak);
+        }
+        MenuAction::NextPane => focus_adjacent_pane(s, app_weak, true),
+        MenuAction::PrevPane => focus_adjacent_pane(s, app_weak, false),
+        MenuAction::NextTab | MenuAction::PrevTab => {
+            let count = s.workspace_mgr.workspace_count();
+            let step = if action == MenuAction::NextTab {
+                1
+            } else {
+                count - 1
+            };
+            let idx = (s.workspace_mgr.active_index() + step) % count;
+            select_workspace(s, app_weak, idx);
+        }
+        MenuAction::JumpToAlert => jump_to_latest_alert(s, app_weak),
+        MenuAction::DoNotDisturb => {
+            let enabled = !s.config.notification.do_not_disturb;
+            set_do_not_disturb(s, enabled);
+        }
+        MenuAction::MuteWorkspace => {
+            let idx = s.workspace_mgr.active_index();
+            let muted = !s.workspace_mgr.workspaces()[idx].muted;
+            set_workspace_muted(s, idx, muted);
+        }
+        MenuAction::ShowLogs => open_log_viewer(s, app_weak),
+        MenuAction::DumpState => save_state_dump(s),
+        MenuAction::Settings => open_settings(s, app_weak),
+        MenuAction::KeyBindings => toggle_cheat_sheet(s, app_weak),
+    }
+}
+
+/// Write keyboard or IPC input to the pane, recording it in its input history.
+fn send_input(ps: &mut PaneState, bytes: &[u8]) -> Result<()> {
+    ps.input.record(bytes);
+    ps.pty.write(bytes)
+}
+
+/// Alt+Left/Right jump a word as in iTerm2: readline's ESC b / ESC f at the
+/// shell, xterm's Alt-modified arrows in full-screen programs.
+fn word_jump_bytes(ch: char, alt_screen: bool) -> Option<Vec<u8>> {
+    let bytes: &[u8] = match (ch, alt_screen) {
+        ('\u{F702}', false) => b"\x1bb",
+        ('\u{F703}', false) => b"\x1bf",
+        ('\u{F702}', true) => b"\x1b[1;3D",
+        ('\u{F703}', true) => b"\x1b[1;3C",
+        _ => return None,
+    };
+    Some(bytes.to_vec())
+}
+
+/// The key of a Slint key event typing `ch` on the physical key `code`, if
+/// it sends an escape sequence. Keypad keys count only without Ctrl or
+/// Command held, leaving those combinations to shortcuts.
+fn special_key(ch: char, code: Option<&str>, action_mod: bool) -> Option<SpecialKey> {
+    // Slint reports keypad keys as what they type; with Num Lock off they
+    // type cursor keys instead
+    let keypad = code.and_then(KeypadKey::from_code);
+    if let Some(keypad) = keypad.filter(|_| ch.is_ascii_graphic() || ch == '\n') {
+        return (!action_mod).then_some(SpecialKey::Keypad(keypad));
+    }
+    Some(match ch {
+        '\u{F700}' => SpecialKey::Up,
+        '\u{F701}' => SpecialKey::Down,
+        '\u{F702}' => SpecialKey::Left,
+        '\u{F703}' => SpecialKey::Right,
+        '\u{F704}'..='\u{F71B}' => SpecialKey::F((ch as u32 - 0xF704 + 1) as u8),
+        '\u{F727}' => SpecialKey::Insert,
+        '\u{007f}' => SpecialKey::Delete,
+        '\u{F729}' => SpecialKey::Home,
+        '\u{F72B}' => SpecialKey::End,
+        '\u{F72C}' => SpecialKey::PageUp,
+        '\u{F72D}' => SpecialKey::PageDown,
+        // Slint's SysReq is the Print Screen key
+        '\u{F731}' => SpecialKey::PrintScreen,
+        '\u{F735}' => SpecialKey::Menu,
+        _ => return None,
+    })
+}
+
+fn slint_key_to_bytes(ch: char, ctrl: bool, text: &str) -> Option<Vec<u8>> {
+    // Special keys
+    match ch {
+        '\u{000a}' => return Some(b"\r".to_vec()),   // Return
+        '\u{0008}' => return Some(b"\x7f".to_vec()), // Backspace
+        '\u{0009}' => return Some(b"\t".to_vec()),   // Tab
+        '\u{001b}' => return Some(b"\x1b".to_vec()), // Escape
+        '\u{0020}' => return Some(b" ".to_vec()),    // Space
+        _ => {}
+    }
+
+    // Ctrl+letter → control character
+    if ctrl && ch.is_ascii_alphabetic() {
+        return Some(vec![ch.to_ascii_lowercase() as u8 - b'a' + 1]);
+    }
+
+    // Regular text
+    Some(text.as_bytes().to_vec())
+}
+
+// ---------------------------------------------------------------------------
+// Pane borders
+// ---------------------------------------------------------------------------
+
+/// Divider widths from config and colors from the theme
+fn border_style(config: &Config, theme: &Theme, scale: f32) -> BorderStyle {
+    let borders = &config.borders;
+    let accent = theme.colors.border_accent_color().to_wgpu_color();
+    BorderStyle {
+        width: (borders.width * scale).max(1.0),
+        color: theme.colors.divider_color().to_wgpu_color(),
+        hover: borders
+            .hover_highlight
+            .then(|| ((borders.hover_width * scale).max(1.0), accent)),
+        accent: borders
+            .active_accent
+            .then(|| ((borders.accent_width * scale).max(1.0), accent)),
+    }
+}
+
+/// Divider under the pointer in the active workspace, if any
+/// Link under the pointer, found in its pane's last rendered grid
+fn link_at_pointer(s: &TerminalState) -> Option<(PaneId, u16, Link)> {
+    if s.mouse_pressed || s.pane_drag.is_some() {
+        return None;
+    }
+    let (x, y) = (s.last_mouse_pos.0 as f32, s.last_mouse_pos.1 as f32);
+    let pane_id = pane_at_pixel(s, x, y)?;
+    let (col, row) = pixel_to_cell(s, pane_id);
+    let line = s.pane_states.get(&pane_id)?.render_grid.get(row as usize)?;
+    link::link_at(line, col).map(|link| (pane_id, row, link))
+}
+
+/// Underline `hovered` and show a pointer cursor over it, redrawing only
+/// when the hovered link changes.
+fn set_hovered_link(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    hovered: Option<(PaneId, u16, Link)>,
+) {
+    if s.hovered_link == hovered {
+        return;
+    }
+    let underline = hovered.as_ref().and_then(|(pane_id, row, link)| {
+        let line = s.pane_states.get(pane_id)?.render_grid.get(*row as usize)?;
+        Some(LinkUnderline {
+            pane_id: *pane_id,
+            row: *row,
+            start: link.start,
+            end: link.end,
+            color: line.cells.get(link.start as usize)?.fg,
+        })
+    });
+    for (pane_id, _, _) in s.hovered_link.iter().chain(&hovered) {
+        mark_pane_dirty(s, *pane_id);
+    }
+    s.hovered_link = hovered;
+    if let Some(renderer) = &mut s.renderer {
+        renderer.text_renderer.set_link_underline(underline);
+    }
+    if let Some(app) = app_weak.upgrade() {
+        app.set_link_hovered(s.hovered_link.is_some());
+    }
+    request_redraw(app_weak);
+}
+
+fn divider_at(s: &TerminalState, x: f32, y: f32) -> Option<Divider> {
+    let renderer = s.renderer.as_ref()?;
+    let layout = s.workspace_mgr.active_workspace().split_tree.layout();
+    let slop = DIVIDER_HOVER_SLOP * s.scale_factor as f32;
+    border::dividers(&layout, renderer.width(), panes_height(renderer), 0.0)
+        .into_iter()
+        .find(|divider| divider.hit(x, y, slop))
+}
+
+// ---------------------------------------------------------------------------
+// Render pipeline
+// ---------------------------------------------------------------------------
+
+fn render_frame(s: &mut TerminalState, theme: &Arc<Theme>, app_weak: &slint::Weak<AppWindow>) {
+    let status_bar_changed = update_status_bar(s);
+    let keystrokes_changed = update_keystroke_overlay(s);
+    let gap = divider_gap(s);
+    let Some(renderer) = &mut s.renderer else {
+        return;
+    };
+    match &s.cheat_sheet {
+        Some(text) => renderer
+            .text_renderer
+            .set_cheat_sheet_overlay(text, s.config.font.size),
+        None => renderer.text_renderer.clear_cheat_sheet_overlay(),
+    }
+    let w = renderer.width();
+    let h = renderer.height();
+    let panes_h = panes_height(renderer);
+
+    let layout = s.workspace_mgr.active_workspace().split_tree.layout();
+    let active_pane = s.workspace_mgr.active_workspace().active_pane();
+
+    let mut pane_rects: Vec<(PaneId, PixelRect)> = Vec::with_capacity(layout.len());
+    let mut any_updated = keystrokes_changed || status_bar_changed;
+    let mut panes_updated = false;
+    let padding = s.config.window.padding.unwrap_or(0.0).max(0.0) * s.scale_factor as f32;
+
+    for (pane_id, pane_rect) in &layout {
+        let px_rect = pane_to_pixel_rect(pane_rect, w, panes_h, gap, 0.0, padding);
+        let shown = s
+            .pane_states
+            .get(pane_id)
+            .is_some_and(|ps| ps.prompt_gutter);
+        let px_rect = inset_gutter(px_rect, gutter_width(renderer, *pane_id, shown));
+
+        if let Some(ps) = s.pane_states.get_mut(pane_id) {
+            let theme = match &s.theme_preview {
+                Some((preview_pane, preview)) if preview_pane == pane_id => preview,
+                _ => theme,
+            };
+            ps.redraw_queued.store(false, Ordering::Release);
+            let show_cursor = *pane_id == active_pane;
+            let opacity = s.config.window.pane_opacity(s.app_focused, show_cursor);
+            let opacity_changed = renderer.text_renderer.set_pane_opacity(*pane_id, opacity);
+            let content_dirty = ps.dirty.load(Ordering::Acquire) || opacity_changed;
+            let cursor_changed = ps.last_cursor_visible != show_cursor;
+            let selection_active = *pane_id == active_pane && s.selection.is_some();
+
+            if content_dirty || cursor_changed || selection_active {
+                let cursor_pos;
+                if content_dirty || ps.render_grid.is_empty() {
+                    // Use timeout to avoid blocking main thread during high throughput
+                    let (delta, cursor) = ps
+                        .emulator
+                        .extract_grid_delta_with_cursor_into_timeout(
+                            theme,
+                            &mut ps.render_grid,
+                            Some(Duration::from_millis(2)),
+                        );
+                    cursor_pos = cursor;
+                    ps.render_dirty_rows.clear();
+                    if delta.full {
+                        ps.render_dirty_rows.extend(0..ps.render_grid.len());
+                    } else {
+                        ps.render_dirty_rows.extend(delta.dirty_rows);
+                    }
+                } else {
+                    cursor_pos = ps.emulator.metadata().cursor;
+                }
+                let sel = if *pane_id == active_pane {
+                    s.selection.map(|sel| sel.normalized())
+                } else {
+                    None
+                };
+
+                let grid = match &ps.pinned {
+                    Some(pinned) => {
+                        let tint = theme.colors.ansi[3];
+                        pinned.overlay_into(&ps.render_grid, tint, &mut ps.pinned_grid);
+                        &ps.pinned_grid
+                    }
+                    None => &ps.render_grid,
+                };
+                renderer.text_renderer.set_pane_content(
+                    *pane_id,
+                    grid,
+                    if content_dirty {
+                        Some(&ps.render_dirty_rows)
+                    } else {
+                        Some(&[])
+                    },
+                    cursor_pos,
+                    show_cursor,
+                    theme.colors.cursor,
+                    theme.colors.background,
+                    sel,
+                    theme.colors.selection_bg,
+                );
+                renderer
+                    .text_renderer
+                    .set_pane_h_scroll(*pane_id, ps.no_wrap.then_some(ps.h_offset));
+                ps.last_cursor_visible = show_cursor;
+                ps.dirty.store(false, Ordering::Relaxed);
+                any_updated = true;
+                panes_updated |= content_dirty;
+            }
+        }
+
+        pane_rects.push((*pane_id, px_rect));
+    }
+
+    // A sliding cursor needs frames even when no content changed
+    let animating = renderer.text_renderer.is_animating();
+    if !any_updated && !animating {
+        return;
+    }
+
+    let bg_rects = renderer.text_renderer.collect_bg_rects(&pane_rects);
+    renderer
+        .bg_renderer
+        .prepare(&renderer.device, &renderer.queue, &bg_rects, w, h);
+
+    // Divider lines between adjacent panes, the hovered one and the active
+    // pane's accent
+    let style = border_style(&s.config, theme, s.scale_factor as f32);
+    let dividers = border::dividers(&layout, w, panes_h, 0.0);
+    let active_area = layout
+        .iter()
+        .find(|(id, _)| *id == active_pane)
+        .map(|(_, rect)| pane_to_pixel_rect(rect, w, panes_h, gap, 0.0, 0.0));
+    let mut divider_rects =
+        border::border_rects(&dividers, s.hovered_divider, active_area.as_ref(), &style);
+
+    // Drop-zone highlight while dragging a pane
+    if let Some((target, side)) = s.pane_drag.as_ref().and_then(|drag| drag.target) {
+        if let Some((_, rect)) = layout.iter().find(|(id, _)| *id == target) {
+            let zone = rect.drop_zone(side);
+            let px = pane_to_pixel_rect(&zone, w, panes_h, gap, 0.0, 0.0);
+            divider_rects.push(BgRect {
+                x: px.x,
+                y: px.y,
+                w: px.w,
+                h: px.h,
+                color: DROP_ZONE_COLOR,
+            });
+        }
+    }
+    // Progress strips along the top of panes, and along the bottom of
+    // paused ones
+    for (pane_id, rect) in &layout {
+        let Some(ps) = s.pane_states.get(pane_id) else {
+            continue;
+        };
+        let area = pane_to_pixel_rect(rect, w, panes_h, gap, 0.0, 0.0);
+        let scale = s.scale_factor as f32;
+        if let Some(progress) = ps.progress {
+            divider_rects.push(progress_strip(progress, &area, theme, scale));
+        }
+        if ps.marked {
+            let rows = ps.render_grid.len();
+            divider_rects.extend(mark_ticks(&ps.emulator, rows, &area, theme, scale));
+        }
+        if ps.highlight_until.is_some() {
+            let mut color = theme.colors.border_accent_color().to_wgpu_color();
+            color[3] = PANE_HIGHLIGHT_ALPHA;
+            divider_rects.push(BgRect {
+                x: area.x,
+                y: area.y,
+                w: area.w,
+                h: area.h,
+                color,
+            });
+        }
+        if ps.held_bytes.is_some() {
+            let strip_h = PROGRESS_STRIP_HEIGHT * scale;
+            divider_rects.push(BgRect {
+                x: area.x,
+                y: area.y + area.h - strip_h,
+                w: area.w,
+                h: strip_h,
+                color: theme.colors.ansi[3].to_wgpu_color(),
+            });
+        }
+    }
+    divider_rects.extend(renderer.text_renderer.collect_overlay_bg_rects());
+    renderer.overlay_bg_renderer.prepare(
+        &renderer.device,
+        &renderer.queue,
+        &divider_rects,
+        w,
+        h,
+    );
+
+    renderer.text_renderer.prepare_panes(
+        &renderer.device,
+        &renderer.queue,
+        &pane_rects,
+        theme.colors.foreground,
+    );
+
+    let texture = renderer.render_to_texture(theme.colors.background);
+
+    // Refresh the active workspace's tab preview once its panes changed
+    let ws_id = s.workspace_mgr.active_workspace().id;
+    if panes_updated {
+        if let Some(preview) = s.tab_previews.get_mut(&ws_id) {
+            preview.stale = true;
+        }
+    }
+    let previews_shown = !s.eco && (s.workspace_mgr.workspace_count() > 1 || s.overview);
+    if previews_shown && preview_due(s.tab_previews.get(&ws_id), false) {
+        if let Some((_, rect)) = pane_rects.iter().find(|(id, _)| *id == active_pane) {
+            let reuse = s.tab_previews.get(&ws_id).map(|preview| &preview.texture);
+            let preview = renderer.render_preview(&texture, rect, PREVIEW_WIDTH, reuse);
+            store_preview(&mut s.tab_previews, ws_id, preview);
+        }
+        if s.overview {
+            update_overview(s, app_weak);
+        }
+    }
+
+    if let Some(app) = app_weak.upgrade() {
+        if let Ok(img) = slint::Image::try_from(texture) {
+            app.set_terminal_texture(img);
+        }
+    }
+
+    // Record render time for frame rate limiting
+    s.last_render_time = Instant::now();
+    // Output and scrolling move the lines prompt timestamps sit beside
+    if !s.prompt_gutter.is_empty() || s.pane_states.values().any(|ps| ps.prompt_gutter) {
+        update_prompt_gutter(s, app_weak);
+    }
+    if animating {
+        s.frame_timer
+            .start(slint::TimerMode::SingleShot, frame_interval(s), wakeup::run);
+    }
+}
+
+/// A workspace's tab thumbnail; refreshes draw over its texture
+struct TabPreview {
+    image: slint::Image,
+    texture: wgpu::Texture,
+    refreshed: Instant,
+    /// The workspace's panes changed since
+    stale: bool,
+}
+
+/// Whether a workspace's preview should be taken again: the first one
+/// right away, later ones once it changed (or `dirty` panes say so) and
+/// [`PREVIEW_INTERVAL`] passed
+fn preview_due(preview: Option<&TabPreview>, dirty: bool) -> bool {
+    preview.map_or(true, |preview| {
+        (preview.stale || dirty) && preview.refreshed.elapsed() >= PREVIEW_INTERVAL
+    })
+}
+
+/// Keep `texture` as the workspace's preview, wrapping it in an image
+/// only when it isn't the texture already shown
+fn store_preview(
+    previews: &mut HashMap<WorkspaceId, TabPreview>,
+    ws_id: WorkspaceId,
+    texture: wgpu::Texture,
+) {
+    if let Some(preview) = previews.get_mut(&ws_id).filter(|p| p.texture == texture) {
+        preview.refreshed = Instant::now();
+        preview.stale = false;
+        return;
+    }
+    if let Ok(image) = slint::Image::try_from(texture.clone()) {
+        let preview = TabPreview {
+            image,
+            texture,
+            refreshed: Instant::now(),
+            stale: false,
+        };
+        previews.insert(ws_id, preview);
+    }
+}
+
+/// Refresh the tab preview of one workspace off screen whose panes
+/// changed, the one refreshed longest ago, by drawing it into the preview
+/// frame. The window's own frame is left as it is.
+fn refresh_background_preview(s: &mut TerminalState, app_weak: &slint::Weak<AppWindow>) {
+    if s.eco || (s.workspace_mgr.workspace_count() < 2 && !s.overview) {
+        return;
+    }
+    let active_idx = s.workspace_mgr.active_index();
+    let due = s
+        .workspace_mgr
+        .workspaces()
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| *idx != active_idx)
+        .map(|(_, ws)| ws)
+        .filter(|ws| {
+            let dirty = ws.pane_ids().iter().any(|id| {
+                s.pane_states
+                    .get(id)
+                    .is_some_and(|ps| ps.dirty.load(Ordering::Relaxed))
+            });
+            preview_due(s.tab_previews.get(&ws.id), dirty)
+        })
+        .min_by_key(|ws| s.tab_previews.get(&ws.id).map(|preview| preview.refreshed));
+    let Some(ws) = due else {
+        return;
+    };
+    let (ws_id, layout, focused) = (ws.id, ws.split_tree.layout(), ws.active_pane());
+    let gap = divider_gap(s);
+    let padding = pane_padding(s);
+    let theme = Arc::clone(&s.theme);
+    let Some(renderer) = &mut s.renderer else {
+        return;
+    };
+    let (w, panes_h) = (renderer.width(), panes_height(renderer));
+    let mut pane_rects = Vec::with_capacity(layout.len());
+    for (pane_id, pane_rect) in &layout {
+        let Some(ps) = s.pane_states.get_mut(pane_id) else {
+            continue;
+        };
+        let px_rect = pane_to_pixel_rect(pane_rect, w, panes_h, gap, 0.0, padding);
+        let gutter = gutter_width(renderer, *pane_id, ps.prompt_gutter);
+        draw_pane_off_screen(renderer, *pane_id, ps, &theme);
+        pane_rects.push((*pane_id, inset_gutter(px_rect, gutter)));
+    }
+    let Some((_, rect)) = pane_rects
+        .iter()
+        .find(|(id, _)| *id == focused)
+        .or(pane_rects.first())
+    else {
+        return;
+    };
+    let (width, height) = (renderer.width(), renderer.height());
+    let bg_rects = renderer.text_renderer.collect_bg_rects(&pane_rects);
+    renderer
+        .bg_renderer
+        .prepare(&renderer.device, &renderer.queue, &bg_rects, width, height);
+    renderer
+        .overlay_bg_renderer
+        .prepare(&renderer.device, &renderer.queue, &[], width, height);
+    renderer.text_renderer.prepare_panes(
+        &renderer.device,
+        &renderer.queue,
+        &pane_rects,
+        theme.colors.foreground,
+    );
+    let frame = renderer.render_preview_frame(theme.colors.background);
+    let reuse = s.tab_previews.get(&ws_id).map(|preview| &preview.texture);
+    let preview = renderer.render_preview(&frame, rect, PREVIEW_WIDTH, reuse);
+    store_preview(&mut s.tab_previews, ws_id, preview);
+    if s.overview {
+        update_overview(s, app_weak);
+    }
+    // A hovered tab shows the texture just drawn over on Slint's next frame
+    request_redraw(app_weak);
+}
+
+/// Bring a pane's drawn content up to date, without its cursor or the
+/// selection, for the preview of a workspace off screen
+fn draw_pane_off_screen(
+    renderer: &mut OffscreenRenderer,
+    pane_id: PaneId,
+    ps: &mut PaneState,
+    theme: &Arc<Theme>,
+) {
+    let (delta, cursor) = ps.emulator.extract_grid_delta_with_cursor_into_timeout(
+        theme,
+        &mut ps.render_grid,
+        Some(Duration::from_millis(2)),
+    );
+    ps.render_dirty_rows.clear();
+    if delta.full {
+        ps.render_dirty_rows.extend(0..ps.render_grid.len());
+    } else {
+        ps.render_dirty_rows.extend(delta.dirty_rows);
+    }
+    let grid = match &ps.pinned {
+        Some(pinned) => {
+            pinned.overlay_into(&ps.render_grid, theme.colors.ansi[3], &mut ps.pinned_grid);
+            &ps.pinned_grid
+        }
+        None => &ps.render_grid,
+    };
+    renderer.text_renderer.set_pane_content(
+        pane_id,
+        grid,
+        Some(&ps.render_dirty_rows),
+        cursor,
+        false,
+        theme.colors.cursor,
+        theme.colors.background,
+        None,
+        theme.colors.selection_bg,
+    );
+    renderer
+        .text_renderer
+        .set_pane_h_scroll(pane_id, ps.no_wrap.then_some(ps.h_offset));
+    ps.last_cursor_visible = false;
+    ps.dirty.store(false, Ordering::Relaxed);
+}
+
+// ---------------------------------------------------------------------------
+// Dead pane cleanup
+// ---------------------------------------------------------------------------
+
+fn handle_dead_panes(state: &Rc<RefCell<TerminalState>>, app_weak: &slint::Weak<AppWindow>) {
+    let mut s = state.borrow_mut();
+    let dead_panes: Vec<PaneId> = s
+        .pane_states
+        .iter()
+        .filter(|(_, ps)| !ps.pty.is_alive())
+        .map(|(id, _)| *id)
+        .collect();
+
+    if dead_panes.is_empty() {
+        return;
+    }
+
+    for pid in &dead_panes {
+        if let Some(mut ps) = s.pane_states.remove(pid) {
+            let context = HookContext {
+                pane_id: Some(*pid),
+                workspace_id: workspace_id_of(&s, *pid),
+                cwd: ps.cwd.clone(),
+                exit_code: ps.pty.exit_code(),
+                ..Default::default()
+            };
+            hooks::run(&s.config.hooks, HookEvent::PaneExited, context);
+        }
+        if let Some(renderer) = &mut s.renderer {
+            renderer.text_renderer.remove_pane(*pid);
+        }
+    }
+
+    // Remove dead panes from split trees and fix active pane focus
+    let ws_count = s.workspace_mgr.workspace_count();
+    for i in 0..ws_count {
+        s.workspace_mgr.select_workspace(i);
+        {
+            let ws = s.workspace_mgr.active_workspace_mut();
+            for pid in &dead_panes {
+                ws.split_tree.remove(*pid);
+            }
+        }
+        let ws = s.workspace_mgr.active_workspace();
+        let active = ws.active_pane();
+        if dead_panes.contains(&active) {
+            let live_ids: Vec<PaneId> = ws.pane_ids();
+            if let Some(first_live) = live_ids.into_iter().find(|p| s.pane_states.contains_key(p)) {
+                s.workspace_mgr.active_workspace_mut().set_active_pane(first_live);
+            }
+        }
+    }
+
+    // If all panes are gone, quit
+    if s.pane_states.is_empty() {
+        drop(s);
+        let _ = slint::quit_event_loop();
+        return;
+    }
+
+    // Clean up empty workspaces (a workspace is "empty" if none of its panes
+    // still exist in pane_states — this handles the case where split_tree.remove()
+    // can't remove the only leaf)
+    let empty_ws_ids: Vec<_> = s
+        .workspace_mgr
+        .workspaces()
+        .iter()
+        .filter(|ws| ws.pane_ids().iter().all(|pid| !s.pane_states.contains_key(pid)))
+        .map(|ws| ws.id)
+        .collect();
+
+    for ws_id in empty_ws_ids {
+        if s.workspace_mgr.workspace_count() > 1 {
+            s.workspace_mgr.close_workspace(ws_id);
+        }
+    }
+
+    // Ensure active workspace index is valid after cleanup
+    let max_idx = s.workspace_mgr.workspace_count().saturating_sub(1);
+    if s.workspace_mgr.active_index() > max_idx {
+        s.workspace_mgr.select_workspace(max_idx);
+    }
+
+    for ps in s.pane_states.values() {
+        ps.dirty.store(true, Ordering::Relaxed);
+    }
+    // Re-layout surviving panes to fill the freed space
+    resize_active_workspace_panes(&mut s);
+    update_tabs(&mut s, app_weak);
+}
+
+// ---------------------------------------------------------------------------
+// IPC handling
+// ---------------------------------------------------------------------------
+
+/// Time per poll tick spent on IPC requests; the rest wait for the next tick
+/// so a flood of requests cannot freeze the UI.
+const IPC_TICK_BUDGET: Duration = Duration::from_millis(4);
+
+fn handle_ipc_requests(
+    state: &Rc<RefCell<TerminalState>>,
+    app_weak: &slint::Weak<AppWindow>,
+) {
+    let mut s = state.borrow_mut();
+    let started = Instant::now();
+    while started.elapsed() < IPC_TICK_BUDGET {
+        let Ok(msg) = s.ipc_rx.try_recv() else {
+            return;
+        };
+        let response = handle_ipc_request(&mut s, msg.request, app_weak);
+        let _ = msg.response_tx.send(response);
+    }
+    // Out of time; pick up the rest on the next turn of the loop
+    wakeup::wake();
+}
+
+/// What the IPC thread reads screens with: a reader per pane, the pane
+/// requests default to and how to render the text
+#[derive(Default)]
+struct SharedScreens {
+    readers: HashMap<PaneId, ScreenReader>,
+    active_pane: Option<PaneId>,
+    theme: Arc<Theme>,
+    capture: CaptureConfig,
+}
+
+/// Bring the IPC thread's [`SharedScreens`] up to date with the panes
+fn publish_screens(s: &TerminalState) {
+    let mut screens = s.screens.lock().unwrap_or_else(|e| e.into_inner());
+    screens.readers.clear();
+    let readers = s.pane_states.iter();
+    screens
+        .readers
+        .extend(readers.map(|(id, ps)| (*id, ps.emulator.screen_reader())));
+    screens.active_pane = Some(s.workspace_mgr.active_workspace().active_pane());
+    if !Arc::ptr_eq(&screens.theme, &s.theme) {
+        screens.theme = Arc::clone(&s.theme);
+    }
+    screens.capture = s.config.capture.clone();
+}
+
+/// Answer methods that need no UI state on the IPC thread, so they never
+/// queue behind the UI: the static ones, and screen reads of panes the IPC
+/// thread knows.
+fn handle_ipc_off_thread(
+    request: &JsonRpcRequest,
+    socket: &Path,
+    screens: &Mutex<SharedScreens>,
+) -> Option<JsonRpcResponse> {
+    let r = request;
+    let response = match rpc::find(&request.method)?.name {
+        rpc::PaneReadScreen::NAME => {
+            return read_screen_off_thread::<rpc::PaneReadScreen>(r, screens)
+        }
+        rpc::PaneCapture::NAME => return read_screen_off_thread::<rpc::PaneCapture>(r, screens),
+        rpc::Ping::NAME => dispatch::<rpc::Ping>(r, |_| Ok(rpc::PingResult { pong: true })),
+        rpc::Capabilities::NAME => dispatch::<rpc::Capabilities>(r, |_| {
+            Ok(rpc::CapabilitiesResult {
+                methods: rpc::METHODS.iter().map(|m| m.name.to_string()).collect(),
+                schema: rpc::schema(),
+            })
+        }),
+        rpc::Identify::NAME => dispatch::<rpc::Identify>(r, |_| {
+            Ok(rpc::IdentifyResult {
+                app: "pterminal".into(),
+                version: env!("CARGO_PKG_VERSION").into(),
+                pid: std::process::id(),
+                platform: std::env::consts::OS.into(),
+                socket: socket.to_string_lossy().into_owned(),
+            })
+        }),
+        rpc::ConfigSchema::NAME => dispatch::<rpc::ConfigSchema>(r, |_| {
+            Ok(rpc::ConfigSchemaResult {
+                schema: Config::json_schema(),
+                live: keys::LIVE_KEYS.iter().map(|key| key.to_string()).collect(),
+            })
+        }),
+        _ => return None,
+    };
+    Some(response)
+}
+
+/// `pane.read_screen` or `pane.capture` through the pane's [`ScreenReader`];
+/// `None` leaves panes the IPC thread doesn't know yet, such as one split
+/// off a moment ago, to the UI thread.
+fn read_screen_off_thread<M>(
+    request: &JsonRpcRequest,
+    screens: &Mutex<SharedScreens>,
+) -> Option<JsonRpcResponse>
+where
+    M: RpcMethod<Params = rpc::CaptureParams, Result = rpc::ScreenResult>,
+{
+    let mut unknown = false;
+    let response = dispatch::<M>(request, |p| {
+        let screens = screens.lock().unwrap_or_else(|e| e.into_inner());
+        let pane_id = p.pane_id.or(screens.active_pane);
+        let reader = pane_id.and_then(|id| screens.readers.get(&id)).cloned();
+        let theme = Arc::clone(&screens.theme);
+        let capture = screens.capture.clone();
+        // Not held while the parser answers, which the poll would wait on
+        drop(screens);
+        let (Some(pane_id), Some(reader)) = (pane_id, reader) else {
+            unknown = true;
+            return Err(pane_not_found());
+        };
+        let grid = reader
+            .extract_grid(&theme)
+            .ok_or_else(|| JsonRpcError::internal("pane not responding"))?;
+        let text = screen_text(grid, &theme, &capture, &p);
+        Ok(rpc::ScreenResult { pane_id, text })
+    });
+    (!unknown).then_some(response)
+}
+
+/// Answer an AppleScript `call` (with `ipc.applescript`) like a request
+/// from an IPC client holding the control token, within the socket's size
+/// and rate limits; macOS asks the user before a script may send it.
+#[cfg(target_os = "macos")]
+fn run_script_call(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    call: &macos::ScriptCall,
+) -> Result<String, (i32, String)> {
+    let len = call.method.len() + call.params.len();
+    if let Err(JsonRpcResponse {
+        error: Some(error), ..
+    }) = s.script_gate.admit(serde_json::Value::Null, len)
+    {
+        return Err((error.code as i32, error.message));
+    }
+    let params = match call.params.trim() {
+        "" => serde_json::Value::Null,
+        params => serde_json::from_str(params).map_err(|e| (-32602, format!("params: {e}")))?,
+    };
+    let request = JsonRpcRequest::new(0, call.method.clone(), params);
+    let id = request.id.clone();
+    let reads_content = rpc::find(&request.method).is_some_and(|m| m.access == Access::ReadContent);
+    let response = if reads_content && !s.config.ipc.allow_content_read {
+        JsonRpcResponse::unauthorized(id, "reading terminal content over IPC is disabled")
+    } else {
+        let socket = Config::config_dir().join("pterminal.sock");
+        handle_ipc_off_thread(&request, &socket, &s.screens)
+            .unwrap_or_else(|| handle_ipc_request(s, request, app_weak))
+    };
+    match response.error {
+        Some(error) => Err((error.code as i32, error.message)),
+        None => Ok(response.result.unwrap_or_default().to_string()),
+    }
+}
+
+fn handle_ipc_request(
+    s: &mut TerminalState,
+    request: JsonRpcRequest,
+    app_weak: &slint::Weak<AppWindow>,
+) -> JsonRpcResponse {
+    if request.jsonrpc != "2.0" {
+        return JsonRpcResponse::invalid_request(request.id);
+    }
+    let Some(method) = rpc::find(&request.method) else {
+        return JsonRpcResponse::method_not_found(request.id, &request.method);
+    };
+
+    let r = &request;
+    match method.name {
+        rpc::WorkspaceList::NAME => {
+            dispatch::<rpc::WorkspaceList>(r, |_| Ok(rpc_workspace_list(s)))
+        }
+        rpc::WorkspaceNew::NAME => {
+            dispatch::<rpc::WorkspaceNew>(r, |_| Ok(rpc_workspace_new(s, app_weak)))
+        }
+        rpc::WorkspaceClose::NAME => {
+            dispatch::<rpc::WorkspaceClose>(r, |p| rpc_workspace_close(s, app_weak, p))
+        }
+        rpc::WorkspaceSelect::NAME => {
+            dispatch::<rpc::WorkspaceSelect>(r, |p| rpc_workspace_select(s, app_weak, p))
+        }
+        rpc::WorkspaceExport::NAME => {
+            dispatch::<rpc::WorkspaceExport>(r, |p| rpc_workspace_export(s, p))
+        }
+        rpc::WorkspaceImport::NAME => {
+            dispatch::<rpc::WorkspaceImport>(r, |p| rpc_workspace_import(s, app_weak, p))
+        }
+        rpc::WorkspaceOpenProject::NAME => {
+            dispatch::<rpc::WorkspaceOpenProject>(r, |p| rpc_workspace_open_project(s, app_weak, p))
+        }
+        rpc::PaneList::NAME => dispatch::<rpc::PaneList>(r, |_| Ok(rpc_pane_list(s))),
+        rpc::PaneLogStart::NAME => {
+            dispatch::<rpc::PaneLogStart>(r, |p| rpc_pane_log_start(s, app_weak, p))
+        }
+        rpc::PaneLogStop::NAME => {
+            dispatch::<rpc::PaneLogStop>(r, |p| rpc_pane_log_stop(s, app_weak, p))
+        }
+        rpc::DebugDumpState::NAME => {
+            dispatch::<rpc::DebugDumpState>(r, |p| rpc_debug_dump_state(s, p))
+        }
+        rpc::TerminalSend::NAME => {
+            dispatch::<rpc::TerminalSend>(r, |p| rpc_terminal_send(s, app_weak, p))
+        }
+        rpc::PaneInputHistory::NAME => {
+            dispatch::<rpc::PaneInputHistory>(r, |p| rpc_input_history(s, p))
+        }
+        rpc::PaneReadScreen::NAME => dispatch::<rpc::PaneReadScreen>(r, |p| rpc_read_screen(s, p)),
+        rpc::PaneCapture::NAME => dispatch::<rpc::PaneCapture>(r, |p| rpc_read_screen(s, p)),
+        rpc::PaneSetWrap::NAME => dispatch::<rpc::PaneSetWrap>(r, |p| rpc_set_wrap(s, app_weak, p)),
+        rpc::PaneLinkScroll::NAME => dispatch::<rpc::PaneLinkScroll>(r, |p| rpc_link_scroll(s, p)),
+        rpc::PanePin::NAME => dispatch::<rpc::PanePin>(r, |p| rpc_pin(s, app_weak, p)),
+        rpc::PaneRespawn::NAME => dispatch::<rpc::PaneRespawn>(r, |p| rpc_respawn(s, app_weak, p)),
+        rpc::PaneProcesses::NAME => dispatch::<rpc::PaneProcesses>(r, |p| rpc_processes(s, p)),
+        rpc::PaneSignal::NAME => dispatch::<rpc::PaneSignal>(r, |p| rpc_signal(s, p)),
+        rpc::PaneMarks::NAME => dispatch::<rpc::PaneMarks>(r, |p| rpc_marks(s, p)),
+        rpc::PaneSearch::NAME => dispatch::<rpc::PaneSearch>(r, |p| rpc_search_panes(s, p)),
+        rpc::PaneSplitWithEnv::NAME => {
+            dispatch::<rpc::PaneSplitWithEnv>(r, |p| rpc_split_with_env(s, app_weak, p))
+        }
+        rpc::PaneDuplicate::NAME => {
+            dispatch::<rpc::PaneDuplicate>(r, |p| rpc_duplicate(s, app_weak, p))
+        }
+        rpc::PaneZoom::NAME => dispatch::<rpc::PaneZoom>(r, |p| rpc_zoom(s, app_weak, p)),
+        rpc::SnippetsRun::NAME => dispatch::<rpc::SnippetsRun>(r, |p| rpc_run_snippet(s, p)),
+        rpc::AppPresentation::NAME => dispatch::<rpc::AppPresentation>(r, |p| {
+            let enabled = p.enabled.unwrap_or(s.presentation.is_none());
+            set_presentation(s, app_weak, enabled);
+            Ok(rpc::PresentationResult { enabled })
+        }),
+        rpc::NotificationSend::NAME => dispatch::<rpc::NotificationSend>(r, |p| {
+            let title = p.title.as_deref().unwrap_or("Notification");
+            let body = p.body.or(p.message).unwrap_or_default();
+            let source = NotificationSource::Ipc;
+            let silenced = s.notification_rules.silences_now(source, title, &body);
+            let notification = s
+                .notifications
+                .push_from(title, body, source, None, silenced);
+            request_redraw(app_weak);
+            Ok(rpc::NotifyResult { notification })
+        }),
+        rpc::NotificationFocus::NAME => {
+            dispatch::<rpc::NotificationFocus>(r, |p| rpc_notification_focus(s, app_weak, p))
+        }
+        rpc::NotificationRules::NAME => {
+            dispatch::<rpc::NotificationRules>(r, |p| rpc_notification_rules(s, p))
+        }
+        rpc::NotificationMute::NAME => {
+            dispatch::<rpc::NotificationMute>(r, |p| rpc_notification_mute(s, p))
+        }
+        rpc::NotificationList::NAME => dispatch::<rpc::NotificationList>(r, |_| {
+            Ok(rpc::NotificationListResult {
+                notifications: s.notifications.list().to_vec(),
+            })
+        }),
+        rpc::StatusSet::NAME => dispatch::<rpc::StatusSet>(r, |p| {
+            if !s.contributions.set_status_text(&p.segment, p.text) {
+                return Err(JsonRpcError::invalid_params("status segment not found"));
+            }
+            Ok(rpc::StatusSetResult { segment: p.segment })
+        }),
+        rpc::NotificationClear::NAME => dispatch::<rpc::NotificationClear>(r, |_| {
+            s.notifications.clear();
+            request_redraw(app_weak);
+            Ok(rpc::NotificationClearResult { cleared: true })
+        }),
+        rpc::ConfigList::NAME => dispatch::<rpc::ConfigList>(r, |p| Ok(rpc_config_list(s, p))),
+        rpc::ConfigGet::NAME => dispatch::<rpc::ConfigGet>(r, |p| rpc_config_get(s, p)),
+        rpc::ConfigSet::NAME => dispatch::<rpc::ConfigSet>(r, |p| rpc_config_set(s, app_weak, p)),
+        rpc::FontsList::NAME => dispatch::<rpc::FontsList>(r, |_| {
+            Ok(rpc::FontsListResult {
+                families: s
+                    .renderer
+                    .as_ref()
+                    .map(|renderer| renderer.text_renderer.monospace_families())
+                    .unwrap_or_default(),
+                active: s.font_family.clone(),
+            })
+        }),
+        _ => JsonRpcResponse::method_not_found(request.id.clone(), &request.method),
+    }
+}
+
+/// The pane a request targets: `pane_id` if given, else the active pane.
+fn target_pane(s: &TerminalState, pane_id: Option<PaneId>) -> PaneId {
+    pane_id.unwrap_or_else(|| s.workspace_mgr.active_workspace().active_pane())
+}
+
+fn pane_not_found() -> JsonRpcError {
+    JsonRpcError::invalid_params("pane not found")
+}
+
+fn rpc_workspace_list(s: &TerminalState) -> rpc::WorkspaceListResult {
+    let active_idx = s.workspace_mgr.active_index();
+    let workspaces = s
+        .workspace_mgr
+        .workspaces()
+        .iter()
+        .enumerate()
+        .map(|(idx, ws)| rpc::WorkspaceInfo {
+            id: ws.id,
+            index: idx,
+            name: ws.name.clone(),
+            active: idx == active_idx,
+            pane_count: ws.pane_ids().len(),
+            project: ws.project.as_ref().map(|root| root.display().to_string()),
+        })
+        .collect();
+    rpc::WorkspaceListResult { workspaces }
+}
+
+fn rpc_workspace_new(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+) -> rpc::WorkspaceNewResult {
+    let (workspace_id, pane_id) = new_workspace(s, app_weak);
+    rpc::WorkspaceNewResult {
+        workspace_id,
+        pane_id,
+    }
+}
+
+fn rpc_workspace_close(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    p: rpc::WorkspaceCloseParams,
+) -> RpcResult<rpc::WorkspaceCloseResult> {
+    let active_ws = s.workspace_mgr.active_workspace().id;
+    let target_ws = p.id.unwrap_or(active_ws);
+    if s.workspace_mgr.workspace_count() <= 1 {
+        return Err(JsonRpcError::invalid_params("cannot close last workspace"));
+    }
+    let Some((ws_id, pane_ids)) = s
+        .workspace_mgr
+        .workspaces()
+        .iter()
+        .find(|ws| ws.id == target_ws)
+        .map(|ws| (ws.id, ws.pane_ids()))
+    else {
+        return Err(JsonRpcError::invalid_params("workspace not found"));
+    };
+    for pid in &pane_ids {
+        s.pane_states.remove(pid);
+        if let Some(renderer) = &mut s.renderer {
+            renderer.text_renderer.remove_pane(*pid);
+        }
+    }
+    s.workspace_mgr.close_workspace(ws_id);
+    update_tabs(s, app_weak);
+    request_redraw(app_weak);
+    Ok(rpc::WorkspaceCloseResult {
+        closed_workspace_id: ws_id,
+    })
+}
+
+fn rpc_notification_focus(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    p: rpc::NotificationFocusParams,
+) -> RpcResult<rpc::NotifyResult> {
+    let latest = s.notifications.latest_alert().map(|n| n.id);
+    let Some(id) = p.id.or(latest) else {
+        return Err(JsonRpcError::invalid_params("no unread alerts"));
+    };
+    let Some(mut notification) = s.notifications.get(id).cloned() else {
+        return Err(JsonRpcError::invalid_params(format!(
+            "notification {id} not found"
+        )));
+    };
+    if !focus_notification(s, app_weak, id) {
+        return Err(JsonRpcError::invalid_params(format!(
+            "notification {id} did not come from a pane that is still open"
+        )));
+    }
+    // Focusing it marked it read
+    notification.read = true;
+    Ok(rpc::NotifyResult { notification })
+}
+
+fn rpc_notification_rules(
+    s: &mut TerminalState,
+    p: rpc::NotificationRulesParams,
+) -> RpcResult<rpc::NotificationRulesResult> {
+    let mut config = s.config.notification.clone();
+    if let Some(enabled) = p.do_not_disturb {
+        config.do_not_disturb = enabled;
+    }
+    if let Some(quiet_hours) = p.quiet_hours {
+        config.quiet_hours = quiet_hours;
+    }
+    if let Some(sources) = p.muted_sources {
+        config.muted_sources = sources;
+    }
+    if let Some(patterns) = p.mute_patterns {
+        config.mute_patterns = patterns;
+    }
+    s.notification_rules =
+        NotificationRules::strict(&config).map_err(JsonRpcError::invalid_params)?;
+    s.config.notification = config;
+    let config = &s.config.notification;
+    Ok(rpc::NotificationRulesResult {
+        do_not_disturb: config.do_not_disturb,
+        quiet_hours: config.quiet_hours.clone(),
+        quiet: s.notification_rules.quiet_now(),
+        muted_sources: config.muted_sources.clone(),
+        mute_patterns: config.mute_patterns.clone(),
+    })
+}
+
+fn rpc_config_list(s: &TerminalState, p: rpc::ConfigListParams) -> rpc::ConfigListResult {
+    let prefix = p.prefix.unwrap_or_default();
+    let values = s
+        .config
+        .values()
+        .into_iter()
+        .filter(|(key, _)| {
+            prefix.is_empty()
+                || key == &prefix
+                || key
+                    .strip_prefix(&prefix)
+                    .is_some_and(|rest| rest.starts_with('.'))
+        })
+        .map(|(key, value)| rpc::ConfigValue {
+            live: keys::is_live(&key),
+            key,
+            value,
+        })
+        .collect();
+    rpc::ConfigListResult { values }
+}
+
+fn rpc_config_get(s: &TerminalState, p: rpc::ConfigGetParams) -> RpcResult<rpc::ConfigValue> {
+    let value = s
+        .config
+        .value(&p.key)
+        .ok_or_else(|| JsonRpcError::invalid_params(format!("unknown config key `{}`", p.key)))?;
+    Ok(rpc::ConfigValue {
+        live: keys::is_live(&p.key),
+        key: p.key,
+        value,
+    })
+}
+
+fn rpc_config_set(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    p: rpc::ConfigSetParams,
+) -> RpcResult<rpc::ConfigSetResult> {
+    let saved = p.save.unwrap_or(false);
+    let value = set_config_option(s, app_weak, &p.key, p.value, saved)?;
+    Ok(rpc::ConfigSetResult {
+        live: keys::is_live(&p.key),
+        key: p.key,
+        value,
+        saved,
+    })
+}
+
+/// Change an option, checked like the config file is, saving it to
+/// config.toml if asked and applying it at once if it can be. Returns the
+/// value as set.
+fn set_config_option(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    key: &str,
+    value: serde_json::Value,
+    save: bool,
+) -> RpcResult<serde_json::Value> {
+    let mut config = s.config.clone();
+    config
+        .set_value(key, value)
+        .map_err(JsonRpcError::invalid_params)?;
+    if key.starts_with("notification") {
+        NotificationRules::strict(&config.notification).map_err(JsonRpcError::invalid_params)?;
+    }
+    let value = config.value(key).unwrap_or_default();
+    if save {
+        Config::save_value(key, &value)
+            .map_err(|e| JsonRpcError::internal(format!("config not saved: {e:#}")))?;
+    }
+    s.config = config;
+    if keys::is_live(key) {
+        apply_config_change(s, app_weak, key);
     }
+    if s.settings {
+        update_settings(s, app_weak);
+    }
+    if s.cheat_sheet.is_some() && key.starts_with("keybindings") {
+        s.cheat_sheet = Some(cheat_sheet_text(&s.config, s.keyboard_layout));
+        request_redraw(app_weak);
+    }
+    Ok(value)
+}
 
-    if !any_updated {
-        return;
+fn rpc_notification_mute(
+    s: &mut TerminalState,
+    p: rpc::NotificationMuteParams,
+) -> RpcResult<rpc::NotificationMuteResult> {
+    let idx = match p.workspace_id {
+        Some(id) => s
+            .workspace_mgr
+            .workspaces()
+            .iter()
+            .position(|ws| ws.id == id)
+            .ok_or_else(|| JsonRpcError::invalid_params(format!("workspace {id} not found")))?,
+        None => s.workspace_mgr.active_index(),
+    };
+    let ws = &s.workspace_mgr.workspaces()[idx];
+    let (workspace_id, muted) = (ws.id, p.muted.unwrap_or(!ws.muted));
+    set_workspace_muted(s, idx, muted);
+    Ok(rpc::NotificationMuteResult {
+        workspace_id,
+        muted,
+    })
+}
+
+fn rpc_workspace_select(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    p: rpc::WorkspaceSelectParams,
+) -> RpcResult<rpc::WorkspaceSelectResult> {
+    let index = if let Some(ws_id) = p.id {
+        s.workspace_mgr
+            .workspaces()
+            .iter()
+            .position(|ws| ws.id == ws_id)
+    } else {
+        p.index
+    };
+    let Some(index) = index else {
+        return Err(JsonRpcError::invalid_params(
+            "workspace id or index required",
+        ));
+    };
+    if index >= s.workspace_mgr.workspace_count() {
+        return Err(JsonRpcError::invalid_params("workspace index out of range"));
     }
+    s.workspace_mgr.select_workspace(index);
+    update_tabs(s, app_weak);
+    request_redraw(app_weak);
+    Ok(rpc::WorkspaceSelectResult {
+        selected_index: index,
+        workspace_id: s.workspace_mgr.active_workspace().id,
+    })
+}
 
-    let bg_rects = renderer.text_renderer.collect_bg_rects(&pane_rects);
-    renderer
-        .bg_renderer
-        .prepare(&renderer.device, &renderer.queue, &bg_rects, w, h);
+fn rpc_workspace_export(
+    s: &TerminalState,
+    p: rpc::WorkspaceExportParams,
+) -> RpcResult<rpc::WorkspaceExportResult> {
+    let workspaces = s.workspace_mgr.workspaces();
+    let idx = match p.id {
+        Some(id) => workspaces.iter().position(|ws| ws.id == id),
+        None => Some(s.workspace_mgr.active_index()),
+    };
+    let Some(idx) = idx else {
+        return Err(JsonRpcError::invalid_params("workspace not found"));
+    };
+    let path = match p.path {
+        Some(path) => layout::expand_path(&path, &home_dir()),
+        None => default_layout_path(&workspaces[idx].name),
+    };
+    let pane_count =
+        export_layout(s, idx, &path).map_err(|e| JsonRpcError::internal(format!("{e:#}")))?;
+    Ok(rpc::WorkspaceExportResult {
+        path: path.display().to_string(),
+        pane_count,
+    })
+}
 
-    // Draw divider lines between adjacent panes
-    let divider_rects = build_divider_rects(&layout, w, h, s.scale_factor as f32, 0.0);
-    renderer.overlay_bg_renderer.prepare(
-        &renderer.device,
-        &renderer.queue,
-        &divider_rects,
-        w,
-        h,
-    );
+fn rpc_workspace_import(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    p: rpc::WorkspaceImportParams,
+) -> RpcResult<rpc::WorkspaceImportResult> {
+    let path = layout::expand_path(&p.path, &home_dir());
+    let (workspace_id, pane_ids) = import_layout(s, app_weak, &path)
+        .map_err(|e| JsonRpcError::invalid_params(format!("{e:#}")))?;
+    Ok(rpc::WorkspaceImportResult {
+        workspace_id,
+        pane_ids,
+    })
+}
 
-    renderer.text_renderer.prepare_panes(
-        &renderer.device,
-        &renderer.queue,
-        &pane_rects,
-        theme.colors.foreground,
-    );
+fn rpc_workspace_open_project(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    p: rpc::OpenProjectParams,
+) -> RpcResult<rpc::OpenProjectResult> {
+    let home = home_dir();
+    let root = layout::expand_path(&p.path, &home);
+    let root = root
+        .canonicalize()
+        .ok()
+        .filter(|root| root.is_dir())
+        .ok_or_else(|| JsonRpcError::invalid_params(format!("{} is not a directory", p.path)))?;
+    if let Some(idx) = s.workspace_mgr.project_index(&root) {
+        select_workspace(s, app_weak, idx);
+        return Ok(rpc::OpenProjectResult {
+            workspace_id: s.workspace_mgr.workspaces()[idx].id,
+            pane_ids: Vec::new(),
+            created: false,
+        });
+    }
+    let saved = s
+        .config
+        .startup
+        .project
+        .to_project_layout(&root, &Config::layouts_dir(), &home)
+        .map_err(|e| JsonRpcError::internal(format!("{e:#}")))?;
+    let (workspace_id, pane_ids) = open_layout(s, app_weak, &saved);
+    s.workspace_mgr.active_workspace_mut().project = Some(root);
+    update_tabs(s, app_weak);
+    Ok(rpc::OpenProjectResult {
+        workspace_id,
+        pane_ids,
+        created: true,
+    })
+}
 
-    let texture = renderer.render_to_texture(theme.colors.background);
-    if let Some(app) = app_weak.upgrade() {
-        if let Ok(img) = slint::Image::try_from(texture) {
-            app.set_terminal_texture(img);
-        }
+fn rpc_pane_list(s: &TerminalState) -> rpc::PaneListResult {
+    let ws = s.workspace_mgr.active_workspace();
+    let panes = ws
+        .pane_ids()
+        .into_iter()
+        .map(|pane_id| {
+            let ps = s.pane_states.get(&pane_id);
+            let integration = ps
+                .map(|ps| ps.emulator.shell_integration())
+                .unwrap_or_default();
+            rpc::PaneInfo {
+                id: pane_id,
+                active: pane_id == ws.active_pane(),
+                alive: ps.is_some_and(|ps| ps.pty.is_alive()),
+                wrap: ps.map_or(true, |ps| !ps.no_wrap),
+                scroll_linked: ws.is_scroll_linked(pane_id),
+                env: ps.map(|ps| ps.env.label()),
+                log: ps
+                    .and_then(|ps| ps.pty.log_path())
+                    .map(|path| path.display().to_string()),
+                stall_ms: ps.map_or(0, |ps| ps.emulator.input_stalls().total.as_millis() as u64),
+                prompt_marks: integration.prompt_marks,
+                reported_cwd: integration
+                    .reported_cwd
+                    .map(|cwd| cwd.display().to_string()),
+            }
+        })
+        .collect();
+    rpc::PaneListResult { panes }
+}
+
+fn rpc_pane_log_start(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    p: rpc::PaneLogStartParams,
+) -> RpcResult<rpc::PaneLogResult> {
+    let pane_id = target_pane(s, p.pane_id);
+    if !s.pane_states.contains_key(&pane_id) {
+        return Err(pane_not_found());
     }
+    let format = match p.format.as_deref() {
+        Some(name) => LogFormat::from_name(name).ok_or_else(|| {
+            JsonRpcError::invalid_params(format!(
+                "unknown log format '{name}' (expected plain or raw)"
+            ))
+        })?,
+        None => s.config.logging.format,
+    };
+    let defaults = log_options(&s.config.logging);
+    let options = LogOptions {
+        format,
+        timestamps: p.timestamps.unwrap_or(defaults.timestamps),
+        collapse_progress: p.collapse_progress.unwrap_or(defaults.collapse_progress),
+    };
+    let path = p.path.map(|path| layout::expand_path(&path, &home_dir()));
+    let path = start_pane_log(s, pane_id, path, options)
+        .map_err(|e| JsonRpcError::internal(format!("{e:#}")))?;
+    update_tabs(s, app_weak);
+    Ok(rpc::PaneLogResult {
+        pane_id,
+        path: Some(path.display().to_string()),
+    })
+}
 
-    // Record render time for frame rate limiting
-    s.last_render_time = Instant::now();
+fn rpc_pane_log_stop(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    p: rpc::PaneParams,
+) -> RpcResult<rpc::PaneLogResult> {
+    let pane_id = target_pane(s, p.pane_id);
+    if !s.pane_states.contains_key(&pane_id) {
+        return Err(pane_not_found());
+    }
+    let path = stop_pane_log(s, pane_id);
+    update_tabs(s, app_weak);
+    Ok(rpc::PaneLogResult {
+        pane_id,
+        path: path.map(|path| path.display().to_string()),
+    })
 }
 
-// ---------------------------------------------------------------------------
-// Dead pane cleanup
-// ---------------------------------------------------------------------------
+fn rpc_debug_dump_state(
+    s: &TerminalState,
+    p: rpc::DumpStateParams,
+) -> RpcResult<rpc::DumpStateResult> {
+    let path = p.path.map_or_else(debug_dump::default_path, |path| {
+        layout::expand_path(&path, &home_dir())
+    });
+    let files = dump_state(s, &path).map_err(|e| JsonRpcError::internal(format!("{e:#}")))?;
+    Ok(rpc::DumpStateResult {
+        path: path.display().to_string(),
+        files,
+    })
+}
 
-fn handle_dead_panes(state: &Rc<RefCell<TerminalState>>, app_weak: &slint::Weak<AppWindow>) {
-    let mut s = state.borrow_mut();
-    let dead_panes: Vec<PaneId> = s
-        .pane_states
-        .iter()
-        .filter(|(_, ps)| !ps.pty.is_alive())
-        .map(|(id, _)| *id)
-        .collect();
+fn rpc_terminal_send(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    p: rpc::SendParams,
+) -> RpcResult<rpc::SendResult> {
+    let limit = s.config.ipc.max_send_bytes;
+    if p.text.len() > limit {
+        return Err(JsonRpcError::invalid_params(format!(
+            "text exceeds {limit} bytes, send it in parts"
+        )));
+    }
+    let pane_id = target_pane(s, p.pane_id);
+    let ps = s.pane_states.get_mut(&pane_id).ok_or_else(pane_not_found)?;
+    send_input(ps, p.text.as_bytes())
+        .map_err(|e| JsonRpcError::internal(format!("pty write failed: {e}")))?;
+    request_redraw(app_weak);
+    Ok(rpc::SendResult {
+        pane_id,
+        bytes: p.text.len(),
+    })
+}
 
-    if dead_panes.is_empty() {
-        return;
+fn rpc_input_history(
+    s: &TerminalState,
+    p: rpc::InputHistoryParams,
+) -> RpcResult<rpc::InputHistoryResult> {
+    let pane_id = target_pane(s, p.pane_id);
+    let ps = s.pane_states.get(&pane_id).ok_or_else(pane_not_found)?;
+    let lines: Vec<String> = ps.input.lines().map(str::to_string).collect();
+    let skip = lines.len().saturating_sub(p.limit.unwrap_or(usize::MAX));
+    Ok(rpc::InputHistoryResult {
+        pane_id,
+        lines: lines[skip..].to_vec(),
+    })
+}
+
+fn rpc_read_screen(s: &TerminalState, p: rpc::CaptureParams) -> RpcResult<rpc::ScreenResult> {
+    let pane_id = target_pane(s, p.pane_id);
+    let ps = s.pane_states.get(&pane_id).ok_or_else(pane_not_found)?;
+    let grid = ps.emulator.extract_grid(&s.theme);
+    let text = screen_text(grid, &s.theme, &s.config.capture, &p);
+    Ok(rpc::ScreenResult { pane_id, text })
+}
+
+/// A screen as `pane.read_screen` returns it: plain or with SGR escapes,
+/// timestamped or not, as `p` or else `capture` says
+fn screen_text(
+    grid: Vec<GridLine>,
+    theme: &Theme,
+    capture: &CaptureConfig,
+    p: &rpc::CaptureParams,
+) -> String {
+    let text = if p.strip_ansi.unwrap_or(capture.strip_ansi) {
+        grid_to_text(&grid)
+    } else {
+        let rows: Vec<Vec<_>> = grid.into_iter().map(|line| line.cells).collect();
+        rich_text::to_ansi(&rows, theme.colors.background)
+    };
+    if p.timestamps.unwrap_or(capture.timestamps) {
+        pane_log::timestamp_lines(&text, std::time::SystemTime::now())
+    } else {
+        text
     }
+}
 
-    for pid in &dead_panes {
-        s.pane_states.remove(pid);
-        if let Some(renderer) = &mut s.renderer {
-            renderer.text_renderer.remove_pane(*pid);
-        }
+fn rpc_set_wrap(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    p: rpc::SetWrapParams,
+) -> RpcResult<rpc::SetWrapResult> {
+    let pane_id = target_pane(s, p.pane_id);
+    if !set_pane_no_wrap(s, pane_id, !p.wrap) {
+        return Err(pane_not_found());
     }
+    request_redraw(app_weak);
+    Ok(rpc::SetWrapResult {
+        pane_id,
+        wrap: p.wrap,
+    })
+}
 
-    // Remove dead panes from split trees and fix active pane focus
-    let ws_count = s.workspace_mgr.workspace_count();
-    for i in 0..ws_count {
-        s.workspace_mgr.select_workspace(i);
-        {
-            let ws = s.workspace_mgr.active_workspace_mut();
-            for pid in &dead_panes {
-                ws.split_tree.remove(*pid);
-            }
-        }
-        let ws = s.workspace_mgr.active_workspace();
-        let active = ws.active_pane();
-        if dead_panes.contains(&active) {
-            let live_ids: Vec<PaneId> = ws.pane_ids();
-            if let Some(first_live) = live_ids.into_iter().find(|p| s.pane_states.contains_key(p)) {
-                s.workspace_mgr.active_workspace_mut().set_active_pane(first_live);
-            }
-        }
+fn rpc_link_scroll(
+    s: &mut TerminalState,
+    p: rpc::LinkScrollParams,
+) -> RpcResult<rpc::LinkScrollResult> {
+    let pane_id = target_pane(s, p.pane_id);
+    let ws_idx = s
+        .workspace_mgr
+        .workspace_index_of(pane_id)
+        .ok_or_else(pane_not_found)?;
+    let linked = p
+        .linked
+        .unwrap_or(!s.workspace_mgr.workspaces()[ws_idx].is_scroll_linked(pane_id));
+    s.workspace_mgr.set_scroll_linked(pane_id, linked);
+    Ok(rpc::LinkScrollResult {
+        pane_id,
+        linked,
+        linked_with: s.workspace_mgr.workspaces()[ws_idx].scroll_peers(pane_id),
+    })
+}
+
+fn rpc_pin(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    p: rpc::PinParams,
+) -> RpcResult<rpc::PinResult> {
+    let pane_id = target_pane(s, p.pane_id);
+    let current = s.pane_states.get(&pane_id).ok_or_else(pane_not_found)?;
+    let pinned = p.pinned.unwrap_or(current.pinned.is_none());
+    set_pane_pinned(s, pane_id, pinned);
+    request_redraw(app_weak);
+    Ok(rpc::PinResult { pane_id, pinned })
+}
+
+fn rpc_respawn(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    p: rpc::PaneParams,
+) -> RpcResult<rpc::RespawnResult> {
+    let pane_id = target_pane(s, p.pane_id);
+    if !s.pane_states.contains_key(&pane_id) {
+        return Err(pane_not_found());
     }
+    let kept_history = restart_pane(s, app_weak, pane_id);
+    Ok(rpc::RespawnResult {
+        pane_id,
+        kept_history,
+    })
+}
 
-    // If all panes are gone, quit
-    if s.pane_states.is_empty() {
-        drop(s);
-        let _ = slint::quit_event_loop();
-        return;
+fn rpc_processes(s: &TerminalState, p: rpc::PaneParams) -> RpcResult<rpc::ProcessesResult> {
+    let pane_id = target_pane(s, p.pane_id);
+    if !s.pane_states.contains_key(&pane_id) {
+        return Err(pane_not_found());
     }
+    let processes = pane_processes(s, pane_id)
+        .into_iter()
+        .map(|(depth, p)| rpc::ProcessEntry {
+            pid: p.pid,
+            ppid: p.ppid,
+            depth,
+            cpu: p.cpu,
+            rss_kb: p.rss_kb,
+            command: p.command,
+        })
+        .collect();
+    Ok(rpc::ProcessesResult { pane_id, processes })
+}
 
-    // Clean up empty workspaces (a workspace is "empty" if none of its panes
-    // still exist in pane_states — this handles the case where split_tree.remove()
-    // can't remove the only leaf)
-    let empty_ws_ids: Vec<_> = s
-        .workspace_mgr
-        .workspaces()
-        .iter()
-        .filter(|ws| ws.pane_ids().iter().all(|pid| !s.pane_states.contains_key(pid)))
-        .map(|ws| ws.id)
+fn rpc_marks(s: &TerminalState, p: rpc::PaneParams) -> RpcResult<rpc::PaneMarksResult> {
+    let pane_id = target_pane(s, p.pane_id);
+    let ps = s.pane_states.get(&pane_id).ok_or_else(pane_not_found)?;
+    let marks = ps
+        .emulator
+        .marks()
+        .into_iter()
+        .map(|mark| rpc::MarkInfo {
+            name: mark.name,
+            line: mark.line,
+        })
         .collect();
+    Ok(rpc::PaneMarksResult { pane_id, marks })
+}
 
-    for ws_id in empty_ws_ids {
-        if s.workspace_mgr.workspace_count() > 1 {
-            s.workspace_mgr.close_workspace(ws_id);
-        }
+fn rpc_search_panes(
+    s: &TerminalState,
+    p: rpc::PaneSearchParams,
+) -> RpcResult<rpc::PaneSearchResult> {
+    let all_workspaces = p.all_workspaces.unwrap_or(false);
+    let limit = p.limit.unwrap_or(100);
+    let found = search_panes(
+        &s.workspace_mgr,
+        &s.pane_states,
+        &p.query,
+        all_workspaces,
+        limit,
+    );
+    let mut matches = Vec::new();
+    for (pane_id, found) in found {
+        let Some(ws_idx) = s.workspace_mgr.workspace_index_of(pane_id) else {
+            continue;
+        };
+        let workspace_id = s.workspace_mgr.workspaces()[ws_idx].id;
+        matches.extend(found.into_iter().map(|m| rpc::PaneSearchMatch {
+            pane_id,
+            workspace_id,
+            line: m.line,
+            column: m.columns.0,
+            count: m.count,
+            text: m.text,
+        }));
     }
+    Ok(rpc::PaneSearchResult { matches })
+}
 
-    // Ensure active workspace index is valid after cleanup
-    let max_idx = s.workspace_mgr.workspace_count().saturating_sub(1);
-    if s.workspace_mgr.active_index() > max_idx {
-        s.workspace_mgr.select_workspace(max_idx);
+fn rpc_signal(s: &TerminalState, p: rpc::SignalParams) -> RpcResult<rpc::SignalResult> {
+    let pane_id = target_pane(s, p.pane_id);
+    let name = p.signal.as_deref().unwrap_or("INT");
+    let signal = Signal::from_name(name).ok_or_else(|| {
+        JsonRpcError::invalid_params(format!(
+            "unknown signal '{name}' (expected INT, TERM or KILL)"
+        ))
+    })?;
+    // Only processes belonging to the pane may be signalled
+    let processes = pane_processes(s, pane_id);
+    if !processes.iter().any(|(_, process)| process.pid == p.pid) {
+        return Err(JsonRpcError::invalid_params(
+            "pid is not a process of the pane",
+        ));
     }
+    process_tree::send_signal(p.pid, signal).map_err(|e| JsonRpcError::internal(e.to_string()))?;
+    Ok(rpc::SignalResult {
+        pane_id,
+        pid: p.pid,
+        signal: signal.name().into(),
+    })
+}
 
-    for ps in s.pane_states.values() {
-        ps.dirty.store(true, Ordering::Relaxed);
+/// A split direction given over IPC, right by default
+fn split_direction(direction: Option<&str>) -> RpcResult<SplitDirection> {
+    match direction {
+        None | Some("right") => Ok(SplitDirection::Horizontal),
+        Some("down") => Ok(SplitDirection::Vertical),
+        Some(other) => Err(JsonRpcError::invalid_params(format!(
+            "unknown direction '{other}' (expected right or down)"
+        ))),
     }
-    // Re-layout surviving panes to fill the freed space
-    resize_active_workspace_panes(&mut s);
-    update_tabs(&mut s, app_weak);
 }
 
-// ---------------------------------------------------------------------------
-// IPC handling
-// ---------------------------------------------------------------------------
+fn rpc_split_with_env(
+    s: &mut TerminalState,
+    app_weak: &slint::Weak<AppWindow>,
+    p: rpc::SplitWithEnvParams,
+) -> RpcResult<rpc::SplitWithEnvResult> {
+    let direction = split_direction(p.direction.as_deref())?;
+    let pane_id = split_with_env(s, direction)
+        .ok_or_else(|| JsonRpcError::invalid_params("no active pane"))?;
+    update_sidebar(s, app_weak);
+    request_redraw(app_weak);
+    Ok(rpc::SplitWithEnvResult { pane_id })
+}
 
-fn handle_ipc_requests(
-    state: &Rc<RefCell<TerminalState>>,
+fn rpc_duplicate(
+    s: &mut TerminalState,
     app_weak: &slint::Weak<AppWindow>,
-) {
-    let mut s = state.borrow_mut();
-    while let Ok(msg) = s.ipc_rx.try_recv() {
-        let response = handle_ipc_request(&mut s, msg.request, app_weak);
-        let _ = msg.response_tx.send(response);
+    p: rpc::DuplicateParams,
+) -> RpcResult<rpc::DuplicateResult> {
+    let direction = split_direction(p.direction.as_deref())?;
+    let source = target_pane(s, p.pane_id);
+    if !focus_pane(s, app_weak, source) {
+        return Err(pane_not_found());
     }
+    let command = match p.rerun {
+        Some(true) => {
+            let shell = s.config.shell();
+            s.pane_states
+                .get(&source)
+                .and_then(|ps| running_command(ps, &shell))
+        }
+        _ => None,
+    };
+    let pane_id = duplicate_pane(s, direction, command.as_deref()).ok_or_else(pane_not_found)?;
+    update_sidebar(s, app_weak);
+    request_redraw(app_weak);
+    Ok(rpc::DuplicateResult { pane_id, command })
 }
 
-fn handle_ipc_request(
+fn rpc_zoom(
     s: &mut TerminalState,
-    request: JsonRpcRequest,
     app_weak: &slint::Weak<AppWindow>,
-) -> JsonRpcResponse {
-    if request.jsonrpc != "2.0" {
-        return JsonRpcResponse::invalid_request(request.id);
+    p: rpc::ZoomParams,
+) -> RpcResult<rpc::ZoomResult> {
+    let mode = match p.mode.as_deref() {
+        None => s.config.font.zoom_mode(),
+        Some("reflow") => ZoomMode::Reflow,
+        Some("scale") => ZoomMode::Scale,
+        Some(other) => {
+            return Err(JsonRpcError::invalid_params(format!(
+                "unknown mode '{other}' (expected reflow or scale)"
+            )))
+        }
+    };
+    let pane_id = target_pane(s, p.pane_id);
+    if !s.pane_states.contains_key(&pane_id) {
+        return Err(pane_not_found());
     }
+    step_pane_font_size(s, pane_id, p.step, mode);
+    request_redraw(app_weak);
+    let font_size = match &s.renderer {
+        Some(renderer) => drawn_font_size(renderer, pane_id),
+        None => base_font_size(s),
+    };
+    let ps = s.pane_states.get(&pane_id).ok_or_else(pane_not_found)?;
+    // Asked of the parser, so the answer includes the resize just made
+    let (_, rows) = ps.emulator.size();
+    Ok(rpc::ZoomResult {
+        pane_id,
+        font_size,
+        cols: u32::from(ps.visible_cols),
+        rows: u32::from(rows),
+    })
+}
 
-    let id = request.id.clone();
-    let params = &request.params;
-
-    match request.method.as_str() {
-        "ping" | "system.ping" => JsonRpcResponse::success(id, json!({ "pong": true })),
-        "capabilities" | "system.capabilities" => JsonRpcResponse::success(
-            id,
-            json!({
-                "methods": [
-                    "ping", "capabilities", "identify",
-                    "workspace.list", "workspace.new", "workspace.close", "workspace.select",
-                    "pane.list", "terminal.send", "pane.read_screen", "pane.capture",
-                    "notification.send", "notification.list", "notification.clear"
-                ]
-            }),
-        ),
-        "identify" | "system.identify" => JsonRpcResponse::success(
-            id,
-            json!({
-                "app": "pterminal",
-                "version": env!("CARGO_PKG_VERSION"),
-                "pid": std::process::id(),
-                "platform": std::env::consts::OS,
-                "socket": s.ipc_socket_path.to_string_lossy(),
-            }),
-        ),
-        "workspace.list" | "list-workspaces" => {
-            let active_idx = s.workspace_mgr.active_index();
-            let workspaces: Vec<Value> = s
-                .workspace_mgr
-                .workspaces()
-                .iter()
-                .enumerate()
-                .map(|(idx, ws)| {
-                    json!({
-                        "id": ws.id,
-                        "index": idx,
-                        "name": ws.name,
-                        "active": idx == active_idx,
-                        "pane_count": ws.pane_ids().len()
-                    })
-                })
-                .collect();
-            JsonRpcResponse::success(id, json!({ "workspaces": workspaces }))
-        }
-        "workspace.new" | "new-workspace" => {
-            let (_ws_id, pane_id) = s.workspace_mgr.add_workspace();
-            let (cols, rows) = if let Some(renderer) = &s.renderer {
-                calc_cols_rows(renderer, s.scale_factor)
-            } else {
-                (80, 24)
-            };
-            let ps = spawn_pane_slint(&s.config, pane_id, cols, rows);
-            s.pane_states.insert(pane_id, ps);
-            update_tabs(s, app_weak);
-            request_redraw(app_weak);
-            JsonRpcResponse::success(id, json!({ "workspace_id": _ws_id, "pane_id": pane_id }))
-        }
-        "workspace.close" | "close-workspace" => {
-            let target_ws = params
-                .get("id")
-                .and_then(Value::as_u64)
-                .unwrap_or_else(|| s.workspace_mgr.active_workspace().id);
-            if s.workspace_mgr.workspace_count() <= 1 {
-                return JsonRpcResponse::invalid_params(id, "cannot close last workspace");
-            }
-            let Some((ws_id, pane_ids)) = s
-                .workspace_mgr
-                .workspaces()
-                .iter()
-                .find(|ws| ws.id == target_ws)
-                .map(|ws| (ws.id, ws.pane_ids()))
-            else {
-                return JsonRpcResponse::invalid_params(id, "workspace not found");
-            };
-            for pid in &pane_ids {
-                s.pane_states.remove(pid);
-                if let Some(renderer) = &mut s.renderer {
-                    renderer.text_renderer.remove_pane(*pid);
-                }
-            }
-            s.workspace_mgr.close_workspace(ws_id);
-            update_tabs(s, app_weak);
-            request_redraw(app_weak);
-            JsonRpcResponse::success(id, json!({ "closed_workspace_id": ws_id }))
-        }
-        "workspace.select" | "select-workspace" => {
-            let index = if let Some(ws_id) = params.get("id").and_then(Value::as_u64) {
-                s.workspace_mgr
-                    .workspaces()
-                    .iter()
-                    .position(|ws| ws.id == ws_id)
-            } else {
-                params
-                    .get("index")
-                    .and_then(Value::as_u64)
-                    .map(|v| v as usize)
-            };
-            let Some(index) = index else {
-                return JsonRpcResponse::invalid_params(id, "workspace id or index required");
-            };
-            if index >= s.workspace_mgr.workspace_count() {
-                return JsonRpcResponse::invalid_params(id, "workspace index out of range");
-            }
-            s.workspace_mgr.select_workspace(index);
-            update_tabs(s, app_weak);
-            request_redraw(app_weak);
-            JsonRpcResponse::success(
-                id,
-                json!({
-                    "selected_index": index,
-                    "workspace_id": s.workspace_mgr.active_workspace().id
-                }),
-            )
-        }
-        "pane.list" | "list-panes" => {
-            let panes: Vec<Value> = s
-                .workspace_mgr
-                .active_workspace()
-                .pane_ids()
-                .into_iter()
-                .map(|pane_id| {
-                    json!({
-                        "id": pane_id,
-                        "active": pane_id == s.workspace_mgr.active_workspace().active_pane(),
-                        "alive": s.pane_states.get(&pane_id).is_some_and(|ps| ps.pty.is_alive())
-                    })
-                })
-                .collect();
-            JsonRpcResponse::success(id, json!({ "panes": panes }))
-        }
-        "terminal.send" | "send" => {
-            let Some(text) = params.get("text").and_then(Value::as_str) else {
-                return JsonRpcResponse::invalid_params(id, "missing params.text");
-            };
-            let pane_id = params
-                .get("pane_id")
-                .and_then(Value::as_u64)
-                .unwrap_or_else(|| s.workspace_mgr.active_workspace().active_pane());
-            let Some(ps) = s.pane_states.get(&pane_id) else {
-                return JsonRpcResponse::invalid_params(id, "pane not found");
-            };
-            if let Err(e) = ps.pty.write(text.as_bytes()) {
-                return JsonRpcResponse::internal_error(id, format!("pty write failed: {e}"));
-            }
-            request_redraw(app_weak);
-            JsonRpcResponse::success(id, json!({ "pane_id": pane_id, "bytes": text.len() }))
-        }
-        "pane.read_screen" | "read-screen" | "pane.capture" | "capture-pane" => {
-            let pane_id = params
-                .get("pane_id")
-                .and_then(Value::as_u64)
-                .unwrap_or_else(|| s.workspace_mgr.active_workspace().active_pane());
-            let Some(ps) = s.pane_states.get(&pane_id) else {
-                return JsonRpcResponse::invalid_params(id, "pane not found");
-            };
-            let grid = ps.emulator.extract_grid(&s.theme);
-            let text = grid_to_text(&grid);
-            JsonRpcResponse::success(id, json!({ "pane_id": pane_id, "text": text }))
-        }
-        "notification.send" | "notify" => {
-            let title = params
-                .get("title")
-                .and_then(Value::as_str)
-                .unwrap_or("Notification");
-            let body = params
-                .get("body")
-                .and_then(Value::as_str)
-                .or_else(|| params.get("message").and_then(Value::as_str))
-                .unwrap_or("");
-            let item = s.notifications.push(title, body);
-            request_redraw(app_weak);
-            JsonRpcResponse::success(id, json!({ "notification": item }))
-        }
-        "notification.list" | "list-notifications" => {
-            JsonRpcResponse::success(id, json!({ "notifications": s.notifications.list() }))
-        }
-        "notification.clear" | "clear-notifications" => {
-            s.notifications.clear();
-            request_redraw(app_weak);
-            JsonRpcResponse::success(id, json!({ "cleared": true }))
-        }
-        _ => JsonRpcResponse::method_not_found(id, &request.method),
+fn rpc_run_snippet(
+    s: &mut TerminalState,
+    p: rpc::RunSnippetParams,
+) -> RpcResult<rpc::RunSnippetResult> {
+    let Some(snippet) = s.config.snippets.iter().find(|sn| sn.name == p.name) else {
+        return Err(JsonRpcError::invalid_params(format!(
+            "unknown snippet: {}",
+            p.name
+        )));
+    };
+    let values = p.values.unwrap_or_default();
+    let missing = snippet
+        .placeholders()
+        .into_iter()
+        .find(|ph| ph.default.is_none() && values.get(&ph.name).map_or(true, String::is_empty));
+    if let Some(ph) = missing {
+        return Err(JsonRpcError::invalid_params(format!(
+            "missing value for placeholder: {}",
+            ph.name
+        )));
     }
+    let text = snippet.expand(&values);
+    let pane_id = target_pane(s, p.pane_id);
+    let ps = s.pane_states.get_mut(&pane_id).ok_or_else(pane_not_found)?;
+    send_input(ps, text.as_bytes())
+        .map_err(|e| JsonRpcError::internal(format!("pty write failed: {e}")))?;
+    Ok(rpc::RunSnippetResult { pane_id, text })
 }
 
-fn grid_to_text(grid: &[pterminal_core::terminal::GridLine]) -> String {
+fn grid_to_text(grid: &[GridLine]) -> String {
     let mut out = String::new();
     for (row_idx, line) in grid.iter().enumerate() {
         let mut row = String::with_capacity(line.cells.len());
@@ -1699,3 +8120,15 @@ fn grid_to_text(grid: &[pterminal_core::terminal::GridLine]) -> String {
     }
     out
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shift_wheel_pans_only_no_wrap_panes() {
+        assert_eq!(wheel_axes(0.0, 3.0, true, true), (3.0, 0.0));
+        assert_eq!(wheel_axes(0.0, 3.0, true, false), (0.0, 3.0));
+        assert_eq!(wheel_axes(2.0, 3.0, false, true), (2.0, 3.0));
+    }
+}
@@ -0,0 +1,39 @@
+//! Wakes the Slint event loop when there is work for the UI thread (PTY
+//! output, a pane exiting, an IPC request) instead of polling on a timer.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set while a wakeup is queued, so a burst of output queues only one
+static PENDING: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// Work run on the UI thread for each wakeup
+    static HANDLER: RefCell<Option<Box<dyn Fn()>>> = const { RefCell::new(None) };
+}
+
+/// Install the function run on wakeups. Call from the UI thread.
+pub(crate) fn set_handler(handler: impl Fn() + 'static) {
+    HANDLER.with(|h| *h.borrow_mut() = Some(Box::new(handler)));
+}
+
+/// Queue a run of the handler on the UI thread. Callable from any thread.
+pub(crate) fn wake() {
+    if PENDING.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    if slint::invoke_from_event_loop(run).is_err() {
+        // No event loop (yet, or any more); the next wake tries again
+        PENDING.store(false, Ordering::Release);
+    }
+}
+
+/// Run the handler now. Call from the UI thread.
+pub(crate) fn run() {
+    PENDING.store(false, Ordering::Release);
+    HANDLER.with(|h| {
+        if let Some(handler) = &*h.borrow() {
+            handler();
+        }
+    });
+}
@@ -0,0 +1,43 @@
+//! Arbitrary program output through the emulator: no input may panic the
+//! parser thread or grow the screen and scrollback past their bounds.
+//!
+//! The first two bytes pick the size, the third where to resize in the
+//! middle of the output, so reflow runs against half-parsed sequences too.
+
+#![no_main]
+
+use std::sync::Arc;
+
+use libfuzzer_sys::fuzz_target;
+use pterminal_core::config::theme::Theme;
+use pterminal_core::terminal::TerminalEmulator;
+
+/// alacritty's default scrollback, which the emulator keeps
+const MAX_HISTORY: usize = 10_000;
+
+fuzz_target!(|data: &[u8]| {
+    let [cols, rows, split, output @ ..] = data else {
+        return;
+    };
+    let (cols, rows) = (u16::from(cols % 120) + 1, u16::from(rows % 40) + 1);
+    let split = output.len() * usize::from(*split) / 255;
+    let (before, after) = output.split_at(split);
+
+    let emulator = TerminalEmulator::new(cols, rows);
+    emulator.process(before);
+    let (new_cols, new_rows) = (rows.max(2), cols.max(2));
+    emulator.resize(new_cols, new_rows);
+    emulator.process(after);
+
+    // Queries run on the parser thread after everything fed, so a panic
+    // there has already aborted by the time they return
+    let theme = Arc::new(Theme::default());
+    let grid = emulator.extract_grid(&theme);
+    assert_eq!(grid.len(), usize::from(new_rows));
+    assert!(grid
+        .iter()
+        .all(|line| line.cells.len() == usize::from(new_cols)));
+    assert!(emulator.history_size() <= MAX_HISTORY);
+    let (col, row) = emulator.cursor_position();
+    assert!(col < new_cols && row < new_rows);
+});
@@ -0,0 +1,82 @@
+//! Malformed JSON-RPC from a hostile client: the input is written to a live
+//! IPC server socket, as one or more lines, and handed line by line to the
+//! plugin host. Neither may panic, and the server must answer with JSON and
+//! keep serving.
+
+#![no_main]
+
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use libfuzzer_sys::fuzz_target;
+use pterminal_core::config::IpcConfig;
+use pterminal_ipc::methods::{dispatch, NoParams, Ping, PingResult};
+use pterminal_ipc::{IpcServer, JsonRpcRequest, JsonRpcResponse, RpcHandler, RpcMethod};
+use pterminal_plugin_host::PluginHostRuntime;
+
+/// Small enough that libFuzzer's inputs reach the size limit
+const MAX_MESSAGE_BYTES: usize = 1024;
+
+fn server_socket() -> &'static PathBuf {
+    static SERVER: OnceLock<(IpcServer, PathBuf)> = OnceLock::new();
+    let (_, socket) = SERVER.get_or_init(|| {
+        let socket =
+            std::env::temp_dir().join(format!("pterminal-fuzz-{}.sock", std::process::id()));
+        let handler: RpcHandler =
+            Arc::new(|request: JsonRpcRequest| match request.method.as_str() {
+                Ping::NAME => {
+                    dispatch::<Ping>(&request, |NoParams {}| Ok(PingResult { pong: true }))
+                }
+                _ => JsonRpcResponse::method_not_found(request.id, &request.method),
+            });
+        let config = IpcConfig {
+            require_auth: false,
+            max_message_bytes: MAX_MESSAGE_BYTES,
+            requests_per_second: 0,
+            ..IpcConfig::default()
+        };
+        let server = IpcServer::start(&socket, handler, &config).expect("start IPC server");
+        while !socket.exists() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        (server, socket)
+    });
+    socket
+}
+
+fn send_to_server(data: &[u8]) {
+    let mut stream = UnixStream::connect(server_socket()).expect("server keeps accepting");
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    // The server may hang up on an oversized line before reading the rest
+    if stream.write_all(data).is_err() || stream.write_all(b"\n").is_err() {
+        return;
+    }
+    let _ = stream.shutdown(std::net::Shutdown::Write);
+    let mut responses = Vec::new();
+    stream
+        .read_to_end(&mut responses)
+        .expect("server answers and closes the connection");
+    for line in responses.split(|&b| b == b'\n').filter(|l| !l.is_empty()) {
+        assert!(
+            serde_json::from_slice::<serde_json::Value>(line).is_ok(),
+            "response is not JSON: {}",
+            String::from_utf8_lossy(line)
+        );
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    send_to_server(data);
+
+    let mut host = PluginHostRuntime::new(vec!["fuzz".to_string()]);
+    for line in String::from_utf8_lossy(data).lines() {
+        if let Ok(response) = host.handle_json_line(line) {
+            assert!(serde_json::from_str::<serde_json::Value>(&response).is_ok());
+        }
+    }
+});
@@ -1,9 +1,12 @@
 use anyhow::Result;
 use clap::Parser;
 use tracing::info;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::EnvFilter;
 
-use pterminal_core::Config;
+use pterminal_core::config::dirs;
+use pterminal_core::{crash, log_ring, Config};
 use pterminal_ui::{App, SlintApp};
 
 #[derive(Parser, Debug)]
@@ -13,25 +16,49 @@ struct Args {
     /// Use raw winit backend instead of Slint
     #[arg(long)]
     raw: bool,
+    /// Check the config file, print what is wrong with it and exit
+    #[arg(long)]
+    check_config: bool,
+    /// Keep config, data and state next to the binary instead of the
+    /// user's directories (or set PTERMINAL_PORTABLE to 1 or a directory)
+    #[arg(long)]
+    portable: bool,
 }
 
 fn main() -> Result<()> {
-    // Initialize logging
+    let args = Args::parse();
+    // The flag wins over PTERMINAL_PORTABLE turning portable mode off, but
+    // keeps a directory it names
+    if args.portable && dirs::portable_root().is_none() {
+        std::env::set_var(dirs::PORTABLE_ENV, "1");
+    }
+    // Shells in the panes, and pterminal-cli run from them, get the
+    // directory itself
+    if let Some(root) = dirs::portable_root() {
+        std::env::set_var(dirs::PORTABLE_ENV, root);
+    }
+    if args.check_config {
+        std::process::exit(check_config());
+    }
+
+    // Initialize logging, to stderr and the in-app ring
     tracing_subscriber::fmt()
         .with_env_filter(
             EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
         )
+        .finish()
+        .with(log_ring::RingLayer)
         .init();
 
     info!("pterminal v{}", env!("CARGO_PKG_VERSION"));
+    dirs::migrate_state();
+    crash::install_hook(Config::crashes_dir());
 
-    // Load config
-    let config = Config::load().unwrap_or_else(|e| {
-        tracing::warn!("Failed to load config: {}, using defaults", e);
-        Config::default()
-    });
-
-    let args = Args::parse();
+    // Load config; problems are logged and shown once the window is up
+    let (config, problems) = Config::load_checked();
+    for problem in &problems {
+        tracing::warn!("{}: {problem}", Config::config_path().display());
+    }
 
     if args.raw {
         // Use raw winit backend
@@ -39,7 +66,29 @@ fn main() -> Result<()> {
         app.run()
     } else {
         // Use Slint backend (default)
-        let app = SlintApp::new(config);
+        let app = SlintApp::new(config).with_config_problems(problems);
         app.run()
     }
 }
+
+/// `--check-config`: print the config file's problems, compiler style.
+/// Exits with 1 if the file can't be used.
+fn check_config() -> i32 {
+    let path = Config::config_path();
+    if !path.exists() {
+        println!("{}: not found, the defaults are used", path.display());
+        return 0;
+    }
+    let (_, problems) = Config::load_checked();
+    for problem in &problems {
+        let kind = if problem.fatal { "error" } else { "warning" };
+        match problem.line {
+            Some(line) => eprintln!("{}:{line}: {kind}: {}", path.display(), problem.message),
+            None => eprintln!("{}: {kind}: {}", path.display(), problem.message),
+        }
+    }
+    if problems.is_empty() {
+        println!("{}: ok", path.display());
+    }
+    i32::from(problems.iter().any(|problem| problem.fatal))
+}